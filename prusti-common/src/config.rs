@@ -10,6 +10,7 @@ use config_crate::{Config, Environment, File, FileFormat};
 use self::commandline::CommandLine;
 use std::collections::HashSet;
 use std::env;
+use std::path::PathBuf;
 use std::sync::RwLock;
 use serde::Deserialize;
 
@@ -19,12 +20,6 @@ pub struct Optimizations {
     pub inline_constant_functions: bool,
     pub delete_unused_predicates: bool,
     pub optimize_folding: bool,
-    pub remove_empty_if: bool,
-    pub purify_vars: bool,
-    pub fix_quantifiers: bool,
-    pub remove_unused_vars: bool,
-    pub remove_trivial_assertions: bool,
-    pub clean_cfg: bool,
 }
 
 impl Optimizations {
@@ -33,12 +28,6 @@ impl Optimizations {
             inline_constant_functions: false,
             delete_unused_predicates: false,
             optimize_folding: false,
-            remove_empty_if: false,
-            purify_vars: false,
-            fix_quantifiers: false,
-            remove_unused_vars: false,
-            remove_trivial_assertions: false,
-            clean_cfg: false,
         }
     }
 
@@ -47,12 +36,6 @@ impl Optimizations {
             inline_constant_functions: true,
             delete_unused_predicates: true,
             optimize_folding: true,
-            remove_empty_if: true,
-            purify_vars: true,
-            fix_quantifiers: true,
-            remove_unused_vars: true,
-            remove_trivial_assertions: true,
-            clean_cfg: true,
         }
     }
 }
@@ -68,7 +51,14 @@ lazy_static! {
         settings.set_default("check_foldunfold_state", false).unwrap();
         settings.set_default("check_overflows", true).unwrap();
         settings.set_default("check_panics", true).unwrap();
+        settings.set_default("check_debug_assertions", true).unwrap();
+        settings.set_default("check_unreachable", true).unwrap();
+        settings.set_default("check_refcell_borrows", true).unwrap();
+        settings.set_default("require_decreases", false).unwrap();
+        settings.set_default("warn_missing_decreases", true).unwrap();
+        settings.set_default("strict_union_reads", false).unwrap();
         settings.set_default("encode_unsigned_num_constraint", false).unwrap();
+        settings.set_default("pointer_width", 64).unwrap();
         settings.set_default("simplify_encoding", true).unwrap();
         settings.set_default("log_dir", "./log/").unwrap();
         settings.set_default("dump_debug_info", false).unwrap();
@@ -77,30 +67,35 @@ lazy_static! {
         settings.set_default("dump_path_ctxt_in_debug_info", false).unwrap();
         settings.set_default("dump_reborrowing_dag_in_debug_info", false).unwrap();
         settings.set_default("dump_borrowck_info", false).unwrap();
-        settings.set_default("dump_viper_program", false).unwrap();
+        settings.set_default("dump_viper_program", "false").unwrap();
         settings.set_default("foldunfold_state_filter", "").unwrap();
         settings.set_default("contracts_lib", "").unwrap();
         settings.set_default::<Vec<String>>("extra_jvm_args", vec![]).unwrap();
         settings.set_default::<Vec<String>>("extra_verifier_args", vec![]).unwrap();
-        settings.set_default("quiet", false).unwrap();
         settings.set_default("assert_timeout", 10_000).unwrap();
         settings.set_default("use_more_complete_exhale", true).unwrap();
         settings.set_default("skip_unsupported_features", false).unwrap();
         settings.set_default("allow_unreachable_unsupported_code", false).unwrap();
         settings.set_default("no_verify", false).unwrap();
+        settings.set_default("verify_only", "").unwrap();
+        settings.set_default("verify_packages", "").unwrap();
         settings.set_default("full_compilation", false).unwrap();
         settings.set_default("json_communication", false).unwrap();
         settings.set_default("json_communication", false).unwrap();
+        settings.set_default("server_allow_insecure_token", false).unwrap();
         settings.set_default("optimizations","all").unwrap();
+        settings.set_default("vir_optimizations", "all").unwrap();
         settings.set_default("intern_names", true).unwrap();
         settings.set_default("enable_purification_optimization", false).unwrap();
         settings.set_default("enable_manual_axiomatization", false).unwrap();
+        settings.set_default("std_specs", true).unwrap();
 
         settings.set_default("print_desugared_specs", false).unwrap();
         settings.set_default("print_typeckd_specs", false).unwrap();
         settings.set_default("print_collected_verification_items", false).unwrap();
         settings.set_default("hide_uuids", false).unwrap();
         settings.set_default("counterexample", false).unwrap();
+        settings.set_default("force_reverify", false).unwrap();
 
         // Flags for debugging Prusti that can change verification results.
         settings.set_default("disable_name_mangling", false).unwrap();
@@ -114,6 +109,19 @@ lazy_static! {
         allowed_keys.insert("server_max_stored_verifiers".to_string());
         allowed_keys.insert("server_max_concurrency".to_string());
         allowed_keys.insert("server_address".to_string());
+        allowed_keys.insert("server_bind_address".to_string());
+        allowed_keys.insert("server_request_timeout".to_string());
+        allowed_keys.insert("procedure_timeout_ms".to_string());
+        allowed_keys.insert("smt_rlimit".to_string());
+        allowed_keys.insert("quiet".to_string());
+        allowed_keys.insert("server_token".to_string());
+        allowed_keys.insert("server_tls_cert".to_string());
+        allowed_keys.insert("server_tls_key".to_string());
+        allowed_keys.insert("server_ca_cert".to_string());
+        allowed_keys.insert("dump_specs".to_string());
+        allowed_keys.insert("report".to_string());
+        allowed_keys.insert("cache_path".to_string());
+        allowed_keys.insert("num_parallel_verifiers".to_string());
         allowed_keys.insert("config".to_string());
         allowed_keys.insert("log".to_string());
         allowed_keys.insert("log_style".to_string());
@@ -176,7 +184,12 @@ pub fn get_filtered_args() -> Vec<String> {
 
 /// Generate a dump of the settings
 pub fn dump() -> String {
-    format!("{:#?}", SETTINGS.read().unwrap())
+    let mut settings = SETTINGS.read().unwrap().clone();
+    // The dump ends up in logs and reports, which must not leak the token.
+    if settings.get_str("server_token").is_ok() {
+        settings.set("server_token", "<redacted>").unwrap();
+    }
+    format!("{:#?}", settings)
 }
 
 fn read_optional_setting<T>(name: &'static str) -> Option<T>
@@ -211,11 +224,49 @@ pub fn viper_backend() -> String {
         .to_string()
 }
 
-/// Should we check absence of panics?
+/// Should we check absence of panics? The check covers also the functions
+/// without a specification, whose arguments are then only constrained by
+/// their types.
 pub fn check_panics() -> bool {
     read_setting("check_panics")
 }
 
+/// Should we check the `debug_assert!`, `debug_assert_eq!` and
+/// `debug_assert_ne!` macros, or ignore them?
+pub fn check_debug_assertions() -> bool {
+    read_setting("check_debug_assertions")
+}
+
+/// Should we check that the `unreachable!()` macro cannot be reached, or
+/// assume it? Reaching `unreachable_unchecked()` is undefined behavior, so it
+/// is checked regardless of this flag.
+pub fn check_unreachable() -> bool {
+    read_setting("check_unreachable")
+}
+
+/// Should we check that the borrows of a `RefCell` do not conflict, or treat
+/// a conflicting borrow as a panic that is not reported?
+pub fn check_refcell_borrows() -> bool {
+    read_setting("check_refcell_borrows")
+}
+
+/// Should recursive pure functions without a `decreases` clause be rejected?
+pub fn require_decreases() -> bool {
+    read_setting("require_decreases")
+}
+
+/// Should recursive pure functions without a `decreases` clause be reported
+/// with a warning? Has no effect if `require_decreases` is set.
+pub fn warn_missing_decreases() -> bool {
+    read_setting("warn_missing_decreases")
+}
+
+/// Should reading a field of a union other than the one that was last written
+/// be rejected instead of only reported with a warning?
+pub fn strict_union_reads() -> bool {
+    read_setting("strict_union_reads")
+}
+
 /// Should we simplify the encoding before passing it to Viper?
 pub fn simplify_encoding() -> bool {
     read_setting("simplify_encoding")
@@ -252,9 +303,16 @@ pub fn dump_borrowck_info() -> bool {
     read_setting("dump_borrowck_info")
 }
 
-/// Should we dump the Viper program?
-pub fn dump_viper_program() -> bool {
-    read_setting("dump_viper_program")
+/// The folder in which the Viper program of each procedure should be dumped,
+/// if any. The setting is either `true`, to dump the programs in the
+/// `viper_program` folder of the log directory, or the path of a folder.
+pub fn dump_viper_program() -> Option<PathBuf> {
+    let value = read_setting::<String>("dump_viper_program");
+    match value.trim() {
+        "" | "false" => None,
+        "true" => Some(PathBuf::from(log_dir()).join("viper_program")),
+        path => Some(PathBuf::from(path)),
+    }
 }
 
 /// The Viper backend that should be used for the verification
@@ -272,6 +330,12 @@ pub fn check_overflows() -> bool {
     read_setting("check_overflows")
 }
 
+/// The number of bits of `usize` and `isize`, used for their bounds and for
+/// the truncation of integer casts.
+pub fn pointer_width() -> u64 {
+    read_setting("pointer_width")
+}
+
 /// Encode (and check) that unsigned integers are non-negative.
 pub fn encode_unsigned_num_constraint() -> bool {
     read_setting("encode_unsigned_num_constraint")
@@ -294,7 +358,14 @@ pub fn extra_verifier_args() -> Vec<String> {
 
 /// Should we hide user messages?
 pub fn quiet() -> bool {
-    read_setting("quiet")
+    read_optional_setting("quiet").unwrap_or(false)
+}
+
+/// Should we report the progress of the encoding and of the verification?
+/// `None` if `quiet` is not set, in which case the progress is reported only
+/// when stderr is a terminal.
+pub fn show_progress() -> Option<bool> {
+    read_optional_setting::<bool>("quiet").map(|quiet| !quiet)
 }
 
 /// The assert timeout (in milliseconds) passed to Silicon.
@@ -317,11 +388,30 @@ pub fn print_desugared_specs() -> bool {
     read_setting("print_desugared_specs")
 }
 
+/// Should Prusti add its built-in specifications of the standard library
+/// (e.g. of `Option` and `Result`) to the verified crate? Specifications given
+/// in the crate for the same functions take precedence over the built-in ones.
+pub fn std_specs() -> bool {
+    read_setting("std_specs")
+}
+
 /// Should Prusti print the type-checked specifications.
 pub fn print_typeckd_specs() -> bool {
     read_setting("print_typeckd_specs")
 }
 
+/// When set, Prusti will dump all collected specifications as JSON to the
+/// file with this path.
+pub fn dump_specs() -> Option<String> {
+    read_optional_setting("dump_specs")
+}
+
+/// When set, Prusti will write a JSON report of the verification, with the
+/// result and the timing of each procedure, to the file with this path.
+pub fn report_path() -> Option<String> {
+    read_optional_setting("report")
+}
+
 /// Should Prusti hide the UUIDs of expressions and specifications.
 pub fn hide_uuids() -> bool {
     read_setting("hide_uuids")
@@ -332,6 +422,29 @@ pub fn produce_counterexample() -> bool {
     read_setting("counterexample")
 }
 
+/// When set, Prusti will store the verification results in this directory and
+/// reuse them for the procedures whose encoding did not change.
+pub fn cache_path() -> Option<String> {
+    read_optional_setting("cache_path")
+}
+
+/// Should Prusti verify all procedures again, ignoring the results stored in
+/// the verification cache?
+pub fn force_reverify() -> bool {
+    read_setting("force_reverify")
+}
+
+/// The number of procedures that Prusti verifies concurrently, each with its
+/// own verifier. If not set, this defaults to the number of (logical) cores on
+/// the system.
+///
+/// **Note:** When verifying on a server (see `server_address`), the
+/// concurrency is further limited by the `server_max_concurrency` setting of
+/// the server.
+pub fn num_parallel_verifiers() -> Option<usize> {
+    read_optional_setting("num_parallel_verifiers")
+}
+
 /**
 The maximum amount of instantiated viper verifiers the server will keep around for reuse.
 If not set, this defaults to `SERVER_MAX_CONCURRENT_VERIFICATION_OPERATIONS`.
@@ -358,6 +471,72 @@ pub fn server_address() -> Option<String> {
     read_optional_setting("server_address")
 }
 
+/// The IP address on which the server listens, e.g. "0.0.0.0" to accept
+/// connections from other machines. If not set, the server only accepts
+/// connections from the local machine ("127.0.0.1").
+pub fn server_bind_address() -> Option<String> {
+    read_optional_setting("server_bind_address")
+}
+
+/// The time, in milliseconds, that the backend may spend verifying a single
+/// procedure. The verification of a procedure that takes longer is abandoned
+/// and reported as timed out, while the other procedures are still verified.
+/// If not set, there is no limit.
+pub fn procedure_timeout_ms() -> Option<u64> {
+    read_optional_setting("procedure_timeout_ms")
+}
+
+/// The resource limit (`rlimit`) of the SMT solver for the verification of
+/// each procedure. Unlike a timeout, it does not depend on the speed of the
+/// machine, so verification results are reproducible. A procedure can
+/// override it with a `#[prusti::smt_rlimit(N)]` attribute. If not set, there
+/// is no limit.
+///
+/// **Note:** This is only supported by the Silicon backend.
+pub fn smt_rlimit() -> Option<u64> {
+    read_optional_setting("smt_rlimit")
+}
+
+/// The time, in seconds, after which the server gives up waiting for the
+/// verification of a single procedure and reports it as timed out. If not set,
+/// the server waits indefinitely.
+pub fn server_request_timeout() -> Option<u64> {
+    read_optional_setting("server_request_timeout")
+}
+
+/// The bearer token that authenticates the clients of the server. When set on
+/// the server, it rejects the requests that do not carry this token; when set
+/// on the client, it is sent with every request. Usually given through the
+/// `PRUSTI_SERVER_TOKEN` environment variable.
+pub fn server_token() -> Option<String> {
+    read_optional_setting("server_token")
+}
+
+/// Should the client send `server_token` to a server whose address starts with
+/// `http://`? The token is then sent in plain text, so by default the client
+/// refuses to connect to such a server.
+pub fn server_allow_insecure_token() -> bool {
+    read_setting("server_allow_insecure_token")
+}
+
+/// The path of the PEM certificate chain with which the server serves its
+/// requests over TLS. Requires `server_tls_key` to be set as well.
+pub fn server_tls_cert() -> Option<PathBuf> {
+    read_optional_setting::<String>("server_tls_cert").map(PathBuf::from)
+}
+
+/// The path of the PEM private key of the certificate of `server_tls_cert`.
+pub fn server_tls_key() -> Option<PathBuf> {
+    read_optional_setting::<String>("server_tls_key").map(PathBuf::from)
+}
+
+/// The path of an additional PEM root certificate with which the client
+/// validates the certificate of the server, e.g. a self-signed one. To use
+/// TLS, `server_address` must start with `https://`.
+pub fn server_ca_cert() -> Option<PathBuf> {
+    read_optional_setting::<String>("server_ca_cert").map(PathBuf::from)
+}
+
 /// If true, communication with the server will be encoded as json and not the default of bincode.
 pub fn json_communication() -> bool {
     read_setting("json_communication")
@@ -395,7 +574,8 @@ pub fn verify_only_basic_block_path() -> Vec<String> {
     read_setting("verify_only_basic_block_path")
 }
 
-/// Which optimizations should be enabled
+/// Which optimizations of the whole program should be enabled. The
+/// optimizations of the methods are selected with `vir_optimizations`.
 pub fn optimizations() -> Optimizations {
     let optimizations_string = read_setting::<String>("optimizations");
 
@@ -408,12 +588,6 @@ pub fn optimizations() -> Optimizations {
             "inline_constant_functions" => opt.inline_constant_functions = true,
             "delete_unused_predicates" => opt.delete_unused_predicates = true,
             "optimize_folding" => opt.optimize_folding = true,
-            "remove_empty_if" => opt.remove_empty_if = true,
-            "purify_vars" => opt.purify_vars = true,
-            "fix_quantifiers" => opt.fix_quantifiers = true,
-            "remove_unused_vars" => opt.remove_unused_vars = true,
-            "remove_trivial_assertions" => opt.remove_trivial_assertions = true,
-            "clean_cfg" => opt.clean_cfg = true,
             _ => warn!("Ignoring Unkown optimization '{}'", trimmed)
         }
     }
@@ -421,6 +595,17 @@ pub fn optimizations() -> Optimizations {
     return opt;
 }
 
+/// The optimizations of the methods to run, in order: either the names of
+/// the optimizations, `all` for all of them in their default order, or
+/// `none`.
+pub fn vir_optimizations() -> Vec<String> {
+    read_setting::<String>("vir_optimizations")
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
 /// Enable purification optimization for impure functions.
 pub fn enable_purification_optimization() -> bool {
     read_setting("enable_purification_optimization")
@@ -454,6 +639,29 @@ pub fn no_verify() -> bool {
     read_setting("no_verify")
 }
 
+/// Verify only the procedures whose path matches one of the given
+/// comma-separated patterns, e.g. `parser::*,lexer::Lexer::next`. In a
+/// pattern, `*` matches any part of a path segment and `**` any sequence of
+/// path segments. The specifications of all procedures are still collected.
+pub fn verify_only() -> Vec<String> {
+    read_setting::<String>("verify_only")
+        .split(',')
+        .map(|pattern| pattern.trim().to_string())
+        .filter(|pattern| !pattern.is_empty())
+        .collect()
+}
+
+/// Verify only the crates of the given comma-separated cargo packages. The
+/// specifications of the other crates are still collected, so that the
+/// verified crates can use them. If empty, all crates are verified.
+pub fn verify_packages() -> Vec<String> {
+    read_setting::<String>("verify_packages")
+        .split(',')
+        .map(|package| package.trim().to_string())
+        .filter(|package| !package.is_empty())
+        .collect()
+}
+
 /// Continue the compilation and generate the binary after Prusti terminates
 pub fn full_compilation() -> bool {
     read_setting("full_compilation")
@@ -0,0 +1,223 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! This module reports the progress of the encoding and of the verification of
+//! the procedures to the user, with one line on *stderr* for each procedure
+//! that starts or finishes. When stderr is a terminal, the procedures that are
+//! in progress are shown in a progress bar below these lines instead.
+//!
+//! The reporter is disabled until `init` enables it. All the output goes
+//! through a single lock, so that the lines of parallel verifications do not
+//! interleave; diagnostics must be emitted within `suspend`, so that they do not
+//! interleave with the progress bar.
+
+use crate::config;
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The width of the bar, in characters.
+const BAR_WIDTH: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Encoding,
+    Verification,
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Encoding => "encoding",
+            Phase::Verification => "verifying",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    /// A progress bar, redrawn in place below the finished items.
+    Bar,
+    /// Plain lines, for logs and pipes.
+    Lines,
+}
+
+struct Reporter {
+    style: Style,
+    phase: Phase,
+    total: usize,
+    started: usize,
+    finished: usize,
+    /// The items in progress, with the time at which they started.
+    in_progress: BTreeMap<String, Instant>,
+    bar_visible: bool,
+}
+
+lazy_static! {
+    static ref REPORTER: Mutex<Option<Reporter>> = Mutex::new(None);
+}
+
+/// Enable the reporter, if the configuration asks for it. The reporter stays
+/// disabled when the diagnostics are emitted as JSON, because the tools that
+/// read them also read stderr, except under cargo, which passes the lines that
+/// are not JSON through.
+pub fn init(json_diagnostics: bool) {
+    let is_terminal = atty::is(atty::Stream::Stderr);
+    let under_cargo = std::env::var_os("CARGO").is_some();
+    let enabled = (!json_diagnostics || under_cargo)
+        && config::show_progress().unwrap_or(is_terminal);
+    let mut reporter = REPORTER.lock().unwrap();
+    *reporter = if enabled {
+        Some(Reporter {
+            style: if is_terminal { Style::Bar } else { Style::Lines },
+            phase: Phase::Encoding,
+            total: 0,
+            started: 0,
+            finished: 0,
+            in_progress: BTreeMap::new(),
+            bar_visible: false,
+        })
+    } else {
+        None
+    };
+}
+
+/// Start a phase with `total` items.
+pub fn begin(phase: Phase, total: usize) {
+    with_reporter(|reporter| {
+        reporter.clear_bar();
+        reporter.phase = phase;
+        reporter.total = total;
+        reporter.started = 0;
+        reporter.finished = 0;
+        reporter.in_progress.clear();
+    });
+}
+
+/// Report that `item` started.
+pub fn start(phase: Phase, item: &str) {
+    with_reporter(|reporter| {
+        if reporter.phase != phase {
+            return;
+        }
+        reporter.started += 1;
+        reporter.in_progress.insert(item.to_string(), Instant::now());
+        if reporter.style == Style::Lines {
+            let line = format!("[{}/{}] {} {}", reporter.started, reporter.total, phase.name(), item);
+            reporter.print_line(&line);
+        } else {
+            reporter.draw_bar();
+        }
+    });
+}
+
+/// Report that `item` finished, with `outcome` describing how, e.g.
+/// `verified`. An item that was not reported to start, such as a cached one,
+/// finishes immediately.
+pub fn finish(phase: Phase, item: &str, outcome: &str) {
+    with_reporter(|reporter| {
+        if reporter.phase != phase {
+            return;
+        }
+        reporter.finished += 1;
+        let elapsed = reporter.in_progress
+            .remove(item)
+            .map(|start| start.elapsed())
+            .unwrap_or_default();
+        let line = format!(
+            "[{}/{}] {} {} ({})",
+            reporter.finished,
+            reporter.total,
+            outcome,
+            item,
+            format_duration(elapsed),
+        );
+        reporter.print_line(&line);
+    });
+}
+
+/// End the current phase, removing the progress bar.
+pub fn end(phase: Phase) {
+    with_reporter(|reporter| {
+        if reporter.phase == phase {
+            reporter.clear_bar();
+            reporter.in_progress.clear();
+        }
+    });
+}
+
+/// Run `f`, which writes to stderr, without the progress bar in the way.
+pub fn suspend<T>(f: impl FnOnce() -> T) -> T {
+    let mut reporter = REPORTER.lock().unwrap();
+    if let Some(reporter) = reporter.as_mut() {
+        reporter.clear_bar();
+    }
+    let result = f();
+    if let Some(reporter) = reporter.as_mut() {
+        if !reporter.in_progress.is_empty() {
+            reporter.draw_bar();
+        }
+    }
+    result
+}
+
+fn with_reporter(f: impl FnOnce(&mut Reporter)) {
+    if let Some(reporter) = REPORTER.lock().unwrap().as_mut() {
+        f(reporter);
+    }
+}
+
+impl Reporter {
+    fn print_line(&mut self, line: &str) {
+        self.clear_bar();
+        eprintln!("{}", line);
+        if self.style == Style::Bar && !self.in_progress.is_empty() {
+            self.draw_bar();
+        }
+    }
+
+    fn draw_bar(&mut self) {
+        let filled = if self.total == 0 {
+            BAR_WIDTH
+        } else {
+            BAR_WIDTH * self.finished / self.total
+        };
+        let current = self.in_progress.keys().next().map(String::as_str).unwrap_or("");
+        let others = self.in_progress.len().saturating_sub(1);
+        let mut bar = format!(
+            "\r\x1b[2K[{}{}] {}/{} {} {}",
+            "=".repeat(filled),
+            " ".repeat(BAR_WIDTH - filled),
+            self.finished,
+            self.total,
+            self.phase.name(),
+            current,
+        );
+        if others > 0 {
+            bar.push_str(&format!(" and {} more", others));
+        }
+        let mut stderr = std::io::stderr();
+        let _ = stderr.write_all(bar.as_bytes());
+        let _ = stderr.flush();
+        self.bar_visible = true;
+    }
+
+    fn clear_bar(&mut self) {
+        if self.bar_visible {
+            let mut stderr = std::io::stderr();
+            let _ = stderr.write_all(b"\r\x1b[2K");
+            let _ = stderr.flush();
+            self.bar_visible = false;
+        }
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}s", duration.as_secs_f64())
+}
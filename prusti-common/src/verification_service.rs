@@ -1,6 +1,7 @@
 use crate::config;
 use viper::{self, VerificationBackend};
 use crate::vir::Program;
+use std::time::Duration;
 
 pub trait VerificationService {
     fn verify(&self, request: VerificationRequest) -> viper::ProgramVerificationResult;
@@ -13,6 +14,28 @@ pub struct VerificationRequest {
     pub backend_config: ViperBackendConfig,
 }
 
+/// Several verification requests that are sent to the server at once. The
+/// server verifies them concurrently and streams back the result of each
+/// request as soon as it is available.
+///
+/// Unknown fields are rejected, so that a client and a server that disagree on
+/// the format (e.g. on the unit of the timeout) fail loudly instead of
+/// silently ignoring a setting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchVerificationRequest {
+    pub requests: Vec<VerificationRequest>,
+    /// The maximum number of requests of the batch that are verified
+    /// concurrently. The server further limits it to its own
+    /// `server_max_concurrency`.
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
+    /// The time, in milliseconds, after which the result of a single request
+    /// is reported as timed out.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
 /**
 The configuration for the viper backend, (i.e. verifier).
 Expresses which backend (silicon or carbon) should be used, and provides command-line arguments to the viper verifier.
@@ -25,6 +48,14 @@ pub struct ViperBackendConfig {
 
 impl Default for ViperBackendConfig {
     fn default() -> Self {
+        Self::new(config::smt_rlimit())
+    }
+}
+
+impl ViperBackendConfig {
+    /// The configuration of the settings, with the resource limit `smt_rlimit`
+    /// for the SMT solver instead of the one of the `smt_rlimit` setting.
+    pub fn new(smt_rlimit: Option<u64>) -> Self {
         let backend = VerificationBackend::from_str(&config::viper_backend());
         let mut verifier_args = config::extra_verifier_args();
         match backend {
@@ -43,6 +74,15 @@ impl Default for ViperBackendConfig {
                     "--logLevel".to_string(),
                     "ERROR".to_string(),
                 ]);
+                if let Some(rlimit) = smt_rlimit {
+                    // The reason reported by Z3 tells a check that ran out of
+                    // resources apart from one that failed.
+                    verifier_args.extend(vec![
+                        "--z3Args".to_string(),
+                        format!("rlimit={}", rlimit),
+                        "--reportReasonUnknown".to_string(),
+                    ]);
+                }
             }
             VerificationBackend::Carbon => {
                 verifier_args.extend(vec![
@@ -55,4 +95,61 @@ impl Default for ViperBackendConfig {
             verifier_args,
         }
     }
+
+    /// Makes the backend stop on its own shortly after `timeout`, so that the
+    /// thread of a verification that we stopped waiting for is freed. Only
+    /// Silicon supports this, with a timeout in whole seconds.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        if self.backend != VerificationBackend::Silicon
+            || self.verifier_args.iter().any(|arg| arg == "--timeout")
+        {
+            return;
+        }
+        // Rounded up, with one more second, so that the backend only stops
+        // once the verification has already been reported as timed out.
+        let seconds = (timeout.as_millis() as u64 + 999) / 1000 + 1;
+        self.verifier_args.push("--timeout".to_string());
+        self.verifier_args.push(seconds.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn z3_args(config: &ViperBackendConfig) -> Vec<&str> {
+        config.verifier_args
+            .iter()
+            .zip(config.verifier_args.iter().skip(1))
+            .filter(|(flag, _)| flag.as_str() == "--z3Args")
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn smt_rlimit() {
+        let config = ViperBackendConfig::new(Some(42));
+        if config.backend == VerificationBackend::Silicon {
+            assert_eq!(z3_args(&config), vec!["rlimit=42"]);
+        }
+        assert!(z3_args(&ViperBackendConfig::new(None)).is_empty());
+    }
+
+    #[test]
+    fn timeout() {
+        let mut config = ViperBackendConfig::new(None);
+        config.set_timeout(Duration::from_millis(1500));
+        config.set_timeout(Duration::from_millis(500));
+        let timeouts: Vec<_> = config.verifier_args
+            .iter()
+            .zip(config.verifier_args.iter().skip(1))
+            .filter(|(flag, _)| flag.as_str() == "--timeout")
+            .map(|(_, value)| value.as_str())
+            .collect();
+        if config.backend == VerificationBackend::Silicon {
+            assert_eq!(timeouts, vec!["3"]);
+        } else {
+            assert!(timeouts.is_empty());
+        }
+    }
 }
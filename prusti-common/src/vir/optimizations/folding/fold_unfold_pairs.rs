@@ -0,0 +1,235 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A peephole optimization that removes the `fold acc(P(x), p)` statements
+//! that are followed, in the same basic block, by `unfold acc(P(x), p)`.
+//!
+//! The fold/unfold algorithm conservatively folds predicates that are then
+//! unfolded again before anything requires them to be folded. If the
+//! statements in between do not consume or require the predicate instance,
+//! the unfold gives back exactly the permissions and the assumptions taken by
+//! the fold, so the pair can be removed.
+
+use crate::vir::{cfg, default_walk_expr, AssignKind, Expr, ExprWalker, Stmt};
+
+/// Remove the pairs of `fold` and `unfold` statements of the same predicate
+/// instance with the same permission amount that are separated only by
+/// statements that provably do not use the predicate instance.
+///
+/// The statements that can consume or require a predicate through an alias,
+/// such as `exhale`, labels (which can be used by `old[l](unfolding ...)`),
+/// other folds and unfolds and magic wands, are never crossed. The pairs of
+/// a fold and an unfold with different permission amounts are kept, because
+/// they do not cancel each other when the permission is fractional.
+pub fn remove_fold_unfold_pairs(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    let mut removed_count = 0;
+    for block in &mut method.basic_blocks {
+        // Removing a pair can make an enclosing pair adjacent.
+        while let Some((fold_index, unfold_index)) = find_pair(&block.stmts) {
+            block.stmts.remove(unfold_index);
+            block.stmts.remove(fold_index);
+            removed_count += 1;
+        }
+    }
+    info!(
+        "Removed {} fold-unfold pairs from {}",
+        removed_count,
+        method.name(),
+    );
+    method
+}
+
+fn find_pair(stmts: &[Stmt]) -> Option<(usize, usize)> {
+    for (fold_index, stmt) in stmts.iter().enumerate() {
+        if let Stmt::Fold(name, args, perm, variant, _) = stmt {
+            for (offset, other) in stmts[fold_index + 1..].iter().enumerate() {
+                match other {
+                    Stmt::Unfold(other_name, other_args, other_perm, other_variant)
+                        if other_name == name
+                            && other_args == args
+                            && other_perm == perm
+                            && other_variant == variant =>
+                    {
+                        return Some((fold_index, fold_index + 1 + offset));
+                    }
+                    _ if is_independent(other, args) => {}
+                    _ => break,
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Check whether the statement cannot consume or require a predicate
+/// instance with the given arguments, nor change the places of the
+/// arguments.
+fn is_independent(stmt: &Stmt, args: &[Expr]) -> bool {
+    match stmt {
+        Stmt::Comment(_) => true,
+        Stmt::Assign(target, source, AssignKind::Copy)
+        | Stmt::Assign(target, source, AssignKind::Ghost) => {
+            is_independent_expr(target, args) && is_independent_expr(source, args)
+        }
+        Stmt::Inhale(expr) | Stmt::Assert(expr, _) => is_independent_expr(expr, args),
+        // Methods without arguments, such as the havoc methods, cannot
+        // require permissions.
+        Stmt::MethodCall(_, method_args, targets) => {
+            method_args.is_empty()
+                && targets
+                    .iter()
+                    .all(|target| is_independent_expr(&Expr::local(target.clone()), args))
+        }
+        _ => false,
+    }
+}
+
+fn is_independent_expr(expr: &Expr, args: &[Expr]) -> bool {
+    let mut finder = DependencyFinder {
+        args,
+        dependent: false,
+    };
+    finder.walk(expr);
+    !finder.dependent
+}
+
+/// Finds the places related to the arguments and the subexpressions that
+/// require permissions.
+struct DependencyFinder<'a> {
+    args: &'a [Expr],
+    dependent: bool,
+}
+
+impl<'a> ExprWalker for DependencyFinder<'a> {
+    fn walk(&mut self, expr: &Expr) {
+        match expr {
+            _ if expr.is_simple_place() => {
+                if self.args.iter().any(|arg| {
+                    arg.is_place() && (expr.has_prefix(arg) || arg.has_prefix(expr))
+                }) {
+                    self.dependent = true;
+                }
+            }
+            Expr::PredicateAccessPredicate(..)
+            | Expr::FieldAccessPredicate(..)
+            | Expr::MagicWand(..)
+            | Expr::Unfolding(..)
+            | Expr::FuncApp(..)
+            | Expr::SnapApp(..)
+            | Expr::InhaleExhale(..)
+            | Expr::Downcast(..) => {
+                self.dependent = true;
+            }
+            _ => default_walk_expr(self, expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{Field, LocalVar, PermAmount, Position, Type};
+
+    fn new_method(stmts: Vec<Stmt>) -> cfg::CfgMethod {
+        let mut method = cfg::CfgMethod::new("test".to_string(), 0, vec![], vec![], vec![]);
+        let block = method.add_block("start", stmts);
+        method.set_successor(block, cfg::Successor::Return);
+        method
+    }
+
+    fn point(name: &str) -> Expr {
+        Expr::local(LocalVar::new(name, Type::TypedRef("Point".to_string())))
+    }
+
+    fn fold(arg: Expr, perm: PermAmount) -> Stmt {
+        Stmt::Fold("Point".to_string(), vec![arg], perm, None, Position::default())
+    }
+
+    fn unfold(arg: Expr, perm: PermAmount) -> Stmt {
+        Stmt::Unfold("Point".to_string(), vec![arg], perm, None)
+    }
+
+    fn int_assign(name: &str, value: i64) -> Stmt {
+        let var = Expr::local(LocalVar::new(name, Type::Int));
+        Stmt::Assign(var, Expr::from(value), AssignKind::Copy)
+    }
+
+    #[test]
+    fn remove_a_pair_around_unrelated_statements() {
+        let stmts = vec![
+            fold(point("x"), PermAmount::Write),
+            int_assign("i", 1),
+            Stmt::comment("nothing"),
+            unfold(point("x"), PermAmount::Write),
+        ];
+
+        let method = remove_fold_unfold_pairs(new_method(stmts));
+
+        assert_eq!(
+            method.basic_blocks[0].stmts,
+            vec![int_assign("i", 1), Stmt::comment("nothing")]
+        );
+    }
+
+    #[test]
+    fn remove_nested_pairs() {
+        let stmts = vec![
+            fold(point("x"), PermAmount::Read),
+            fold(point("y"), PermAmount::Read),
+            unfold(point("y"), PermAmount::Read),
+            unfold(point("x"), PermAmount::Read),
+        ];
+
+        let method = remove_fold_unfold_pairs(new_method(stmts));
+
+        assert!(method.basic_blocks[0].stmts.is_empty());
+    }
+
+    #[test]
+    fn keep_a_pair_around_an_exhale() {
+        // The exhaled predicate instance could be the folded one, if `y` and
+        // `x` are aliases.
+        let exhale = Stmt::Exhale(
+            Expr::predicate_access_predicate("Point", point("y"), PermAmount::Write),
+            Position::default(),
+        );
+        let stmts = vec![
+            fold(point("x"), PermAmount::Write),
+            exhale,
+            unfold(point("x"), PermAmount::Write),
+        ];
+
+        let method = remove_fold_unfold_pairs(new_method(stmts.clone()));
+
+        assert_eq!(method.basic_blocks[0].stmts, stmts);
+    }
+
+    #[test]
+    fn keep_a_pair_with_different_permissions() {
+        let stmts = vec![
+            fold(point("x"), PermAmount::Write),
+            unfold(point("x"), PermAmount::Read),
+        ];
+
+        let method = remove_fold_unfold_pairs(new_method(stmts.clone()));
+
+        assert_eq!(method.basic_blocks[0].stmts, stmts);
+    }
+
+    #[test]
+    fn keep_a_pair_around_a_use_of_the_argument() {
+        let field = point("x").field(Field::new("f$x", Type::Int));
+        let stmts = vec![
+            fold(point("x"), PermAmount::Write),
+            Stmt::Assert(Expr::eq_cmp(field, Expr::from(0)), Position::default()),
+            unfold(point("x"), PermAmount::Write),
+        ];
+
+        let method = remove_fold_unfold_pairs(new_method(stmts.clone()));
+
+        assert_eq!(method.basic_blocks[0].stmts, stmts);
+    }
+}
@@ -7,5 +7,7 @@
 //! A module that contains optimizations related to fold/unfold.
 
 mod expressions;
+mod fold_unfold_pairs;
 
 pub use self::expressions::FoldingOptimizer;
+pub use self::fold_unfold_pairs::remove_fold_unfold_pairs;
@@ -4,9 +4,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-//! Optimization that removes unused temporary variables.
+//! Optimizations that remove trivial and duplicate assertions.
 
-use crate::vir::{cfg, Const, Expr, Stmt};
+use crate::vir::{cfg, default_walk_expr, Const, Expr, ExprWalker, Stmt};
 
 /// Remove trivial assertions:
 /// * `assert true`
@@ -24,3 +24,174 @@ pub fn remove_trivial_assertions(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
     });
     method
 }
+
+/// Remove the `assert e` statements that follow a syntactically identical
+/// `assert e` in the same basic block, when no statement in between may
+/// modify the local variables or the heap locations that occur in `e`.
+///
+/// The first occurrence is kept, so a failing assertion is still reported at
+/// the earliest position.
+pub fn remove_duplicate_assertions(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    for block in &mut method.basic_blocks {
+        // The expressions asserted earlier in the block that still hold.
+        let mut asserted: Vec<Expr> = vec![];
+        block.stmts.retain(|stmt| {
+            if let Stmt::Assert(expr, _) = stmt {
+                if asserted.contains(expr) {
+                    return false;
+                }
+                asserted.push(expr.clone());
+            } else {
+                asserted.retain(|expr| preserves(stmt, expr));
+            }
+            true
+        });
+    }
+    method
+}
+
+/// Check whether the statement certainly does not modify the value of the
+/// expression.
+fn preserves(stmt: &Stmt, expr: &Expr) -> bool {
+    match stmt {
+        Stmt::Comment(_) | Stmt::Label(_) | Stmt::Assert(..) => true,
+        Stmt::Inhale(inhaled) => inhaled.is_pure() || !reads_heap(expr),
+        Stmt::Assign(Expr::Local(var, _), _, _) => !expr.find(&Expr::local(var.clone())),
+        Stmt::Assign(..) => !reads_heap(expr),
+        Stmt::MethodCall(_, _, targets) => {
+            !reads_heap(expr) && targets.iter().all(|var| !expr.find(&Expr::local(var.clone())))
+        }
+        // These statements change only permissions and heap locations.
+        Stmt::Exhale(..)
+        | Stmt::Fold(..)
+        | Stmt::Unfold(..)
+        | Stmt::Obtain(..)
+        | Stmt::BeginFrame
+        | Stmt::EndFrame
+        | Stmt::TransferPerm(..)
+        | Stmt::ApplyMagicWand(..)
+        | Stmt::Downcast(..) => !reads_heap(expr),
+        _ => false,
+    }
+}
+
+/// Check whether the value of the expression depends on the heap, also
+/// through a snapshot.
+fn reads_heap(expr: &Expr) -> bool {
+    struct SnapshotFinder {
+        found: bool,
+    }
+    impl ExprWalker for SnapshotFinder {
+        fn walk(&mut self, expr: &Expr) {
+            if let Expr::SnapApp(..) = expr {
+                self.found = true;
+            } else {
+                default_walk_expr(self, expr);
+            }
+        }
+    }
+    let mut finder = SnapshotFinder { found: false };
+    finder.walk(expr);
+    finder.found || expr.is_heap_dependent()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{AssignKind, Field, LocalVar, Position, Type};
+
+    fn new_method(stmts: Vec<Stmt>) -> cfg::CfgMethod {
+        let mut method = cfg::CfgMethod::new("test".to_string(), 0, vec![], vec![], vec![]);
+        let block = method.add_block("start", stmts);
+        method.set_successor(block, cfg::Successor::Return);
+        method
+    }
+
+    fn local(name: &str) -> Expr {
+        Expr::local(LocalVar::new(name, Type::Int))
+    }
+
+    fn field(name: &str) -> Expr {
+        Expr::local(LocalVar::new(name, Type::TypedRef("i32".to_string())))
+            .field(Field::new("val_int", Type::Int))
+    }
+
+    fn bound_check(value: Expr) -> Expr {
+        Expr::gt_cmp(value, Expr::from(0))
+    }
+
+    fn assert(expr: Expr, line: i32) -> Stmt {
+        Stmt::Assert(expr, Position::new(line, 0, line as u64))
+    }
+
+    #[test]
+    fn remove_a_repeated_assertion() {
+        let stmts = vec![
+            assert(bound_check(local("x")), 1),
+            Stmt::Assign(local("y"), Expr::from(1), AssignKind::Copy),
+            assert(bound_check(field("a")), 2),
+            assert(bound_check(local("x")), 3),
+            assert(bound_check(field("a")), 4),
+        ];
+
+        let method = remove_duplicate_assertions(new_method(stmts));
+
+        // The positions of the first occurrences are kept.
+        let lines: Vec<_> = method.basic_blocks[0].stmts
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Assert(_, pos) => Some(pos.line()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lines, vec![1, 2]);
+        assert_eq!(method.basic_blocks[0].stmts.len(), 3);
+    }
+
+    #[test]
+    fn keep_an_assertion_after_a_write_to_a_variable() {
+        let stmts = vec![
+            assert(bound_check(local("x")), 1),
+            Stmt::Assign(local("x"), Expr::from(0), AssignKind::Copy),
+            assert(bound_check(local("x")), 2),
+        ];
+
+        let method = remove_duplicate_assertions(new_method(stmts.clone()));
+
+        assert_eq!(method.basic_blocks[0].stmts, stmts);
+    }
+
+    #[test]
+    fn keep_an_assertion_after_a_write_to_the_heap() {
+        // `b` and `a` could be aliases.
+        let stmts = vec![
+            assert(bound_check(field("a")), 1),
+            Stmt::Assign(field("b"), Expr::from(0), AssignKind::Copy),
+            assert(bound_check(field("a")), 2),
+            Stmt::Exhale(Expr::from(true), Position::default()),
+            assert(bound_check(field("a")), 3),
+        ];
+
+        let method = remove_duplicate_assertions(new_method(stmts.clone()));
+
+        assert_eq!(method.basic_blocks[0].stmts, stmts);
+    }
+
+    #[test]
+    fn keep_an_assertion_after_a_havoc() {
+        let havoc = Stmt::MethodCall(
+            "builtin$havoc_int".to_string(),
+            vec![],
+            vec![LocalVar::new("x", Type::Int)],
+        );
+        let stmts = vec![
+            assert(bound_check(local("x")), 1),
+            havoc,
+            assert(bound_check(local("x")), 2),
+        ];
+
+        let method = remove_duplicate_assertions(new_method(stmts.clone()));
+
+        assert_eq!(method.basic_blocks[0].stmts, stmts);
+    }
+}
@@ -8,6 +8,7 @@ pub fn clean_cfg(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
     let traversal_order = method.get_topological_sort();
     assert_eq!(traversal_order[0].block_index, 0, "The start block should be first.");
     let mut new_basic_blocks = Vec::new();
+    let mut new_basic_blocks_labels = Vec::new();
     let mut basic_blocks: HashMap<_, _> = method.basic_blocks.into_iter().enumerate().collect();
     let mut new_indices = HashMap::new();
 
@@ -30,6 +31,7 @@ pub fn clean_cfg(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
                 }
             }
             new_basic_blocks.push(basic_block);
+            new_basic_blocks_labels.push(method.basic_blocks_labels[block_index].clone());
         }
     }
     for basic_block in &mut new_basic_blocks {
@@ -47,5 +49,60 @@ pub fn clean_cfg(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
         }
     }
     method.basic_blocks = new_basic_blocks;
+    method.basic_blocks_labels = new_basic_blocks_labels;
     method
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::empty_block_remover::remove_empty_blocks;
+    use crate::vir::{Expr, LocalVar, Stmt, Type};
+
+    fn new_method() -> cfg::CfgMethod {
+        cfg::CfgMethod::new("test".to_string(), 0, vec![], vec![], vec![])
+    }
+
+    #[test]
+    fn merge_a_chain() {
+        let mut method = new_method();
+        let start = method.add_block("start", vec![Stmt::label("l0")]);
+        let middle = method.add_block("middle", vec![Stmt::label("l1")]);
+        let end = method.add_block("end", vec![Stmt::label("l2")]);
+        method.set_successor(start, cfg::Successor::Goto(middle));
+        method.set_successor(middle, cfg::Successor::Goto(end));
+        method.set_successor(end, cfg::Successor::Return);
+
+        let method = clean_cfg(method);
+
+        assert_eq!(method.basic_blocks.len(), 1);
+        assert_eq!(method.basic_blocks_labels, vec!["start"]);
+        assert_eq!(
+            method.basic_blocks[0].stmts,
+            vec![Stmt::label("l0"), Stmt::label("l1"), Stmt::label("l2")],
+        );
+        assert_eq!(method.basic_blocks[0].successor, cfg::Successor::Return);
+    }
+
+    #[test]
+    fn merge_after_removing_the_empty_branches() {
+        let mut method = new_method();
+        let start = method.add_block("start", vec![Stmt::label("l0")]);
+        let then_block = method.add_block("then_block", vec![]);
+        let else_block = method.add_block("else_block", vec![Stmt::comment("nothing")]);
+        let join = method.add_block("join", vec![Stmt::label("l1")]);
+        let end = method.add_block("end", vec![Stmt::label("l2")]);
+        let guard = Expr::local(LocalVar::new("b", Type::Bool));
+        method.set_successor(start, cfg::Successor::GotoSwitch(vec![(guard, then_block)], else_block));
+        method.set_successor(then_block, cfg::Successor::Goto(join));
+        method.set_successor(else_block, cfg::Successor::Goto(join));
+        method.set_successor(join, cfg::Successor::Goto(end));
+        method.set_successor(end, cfg::Successor::Return);
+
+        let method = clean_cfg(remove_empty_blocks(method));
+
+        assert_eq!(method.basic_blocks.len(), 2);
+        assert_eq!(method.basic_blocks_labels, vec!["start", "join"]);
+        assert_eq!(method.basic_blocks[1].stmts, vec![Stmt::label("l1"), Stmt::label("l2")]);
+    }
+}
@@ -0,0 +1,392 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Optimization that propagates the constants assigned to pure local
+//! variables and simplifies the resulting expressions.
+
+use crate::vir::{
+    cfg, BinOpKind, Const, Expr, ExprFolder, ExprWalker, LocalVar, Position, Stmt, StmtWalker,
+    Trigger, Type, UnaryOpKind,
+};
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+/// Fold constants within each basic block:
+/// * the uses of a pure local variable (of type `Int` or `Bool`) that was
+///   assigned a constant are replaced by the constant;
+/// * operations on constants and identities such as `x + 0`, `b && true` and
+///   `!!b` are simplified;
+/// * the assignments of constants to variables that are no longer used are
+///   removed.
+///
+/// The propagation stops at the statements that can fail or that modify more
+/// than a pure local variable, such as `assert`, `exhale`, method calls and
+/// folds. Old expressions and quantifiers are left unchanged.
+pub fn fold_constants(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    for block in &mut method.basic_blocks {
+        let mut folder = ConstantFolder {
+            constants: HashMap::new(),
+        };
+        let stmts = mem::take(&mut block.stmts);
+        block.stmts = stmts.into_iter().map(|stmt| folder.fold_stmt(stmt)).collect();
+        block.successor = match mem::replace(&mut block.successor, cfg::Successor::Undefined) {
+            cfg::Successor::GotoSwitch(conditional_targets, default_target) => {
+                cfg::Successor::GotoSwitch(
+                    conditional_targets
+                        .into_iter()
+                        .map(|(guard, target)| (folder.fold(guard), target))
+                        .collect(),
+                    default_target,
+                )
+            }
+            successor => successor,
+        };
+    }
+    remove_dead_assignments(&mut method);
+    method
+}
+
+fn is_pure(var: &LocalVar) -> bool {
+    matches!(var.typ, Type::Int | Type::Bool)
+}
+
+/// Remove the assignments of constants to pure local variables that are
+/// never read.
+fn remove_dead_assignments(method: &mut cfg::CfgMethod) {
+    let mut collector = ReadVarCollector {
+        read_vars: HashSet::new(),
+    };
+    for block in &method.basic_blocks {
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Assign(Expr::Local(..), rhs, _) => ExprWalker::walk(&mut collector, rhs),
+                _ => StmtWalker::walk(&mut collector, stmt),
+            }
+        }
+        if let cfg::Successor::GotoSwitch(conditional_targets, _) = &block.successor {
+            for (guard, _) in conditional_targets {
+                ExprWalker::walk(&mut collector, guard);
+            }
+        }
+    }
+    let read_vars = collector.read_vars;
+    let formal_returns = method.get_formal_returns().clone();
+    method.retain_stmts(|stmt| match stmt {
+        Stmt::Assign(Expr::Local(var, _), Expr::Const(..), _) => {
+            !is_pure(var) || read_vars.contains(var) || formal_returns.contains(var)
+        }
+        _ => true,
+    });
+}
+
+struct ConstantFolder {
+    /// The constant value of the pure local variables at the current
+    /// statement of the basic block.
+    constants: HashMap<LocalVar, Const>,
+}
+
+impl ConstantFolder {
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        match stmt {
+            Stmt::Comment(_) | Stmt::Label(_) => stmt,
+            Stmt::Inhale(expr) => Stmt::Inhale(self.fold(expr)),
+            Stmt::Assign(Expr::Local(var, pos), rhs, kind) if is_pure(&var) => {
+                let rhs = self.fold(rhs);
+                if let Expr::Const(value, _) = &rhs {
+                    self.constants.insert(var.clone(), value.clone());
+                } else {
+                    self.constants.remove(&var);
+                }
+                Stmt::Assign(Expr::Local(var, pos), rhs, kind)
+            }
+            Stmt::Assign(lhs, rhs, kind) => Stmt::Assign(lhs, self.fold(rhs), kind),
+            Stmt::Assert(expr, pos) => {
+                let stmt = Stmt::Assert(self.fold(expr), pos);
+                self.constants.clear();
+                stmt
+            }
+            Stmt::Exhale(expr, pos) => {
+                let stmt = Stmt::Exhale(self.fold(expr), pos);
+                self.constants.clear();
+                stmt
+            }
+            Stmt::MethodCall(name, args, targets) => {
+                let args = args.into_iter().map(|arg| self.fold(arg)).collect();
+                self.constants.clear();
+                Stmt::MethodCall(name, args, targets)
+            }
+            stmt => {
+                self.constants.clear();
+                stmt
+            }
+        }
+    }
+}
+
+impl ExprFolder for ConstantFolder {
+    fn fold_local(&mut self, var: LocalVar, pos: Position) -> Expr {
+        match self.constants.get(&var) {
+            Some(value) => Expr::Const(value.clone(), pos),
+            None => Expr::Local(var, pos),
+        }
+    }
+
+    fn fold_labelled_old(&mut self, label: String, body: Box<Expr>, pos: Position) -> Expr {
+        // The variables may have had a different value at the label.
+        Expr::LabelledOld(label, body, pos)
+    }
+
+    fn fold_forall(
+        &mut self,
+        vars: Vec<LocalVar>,
+        triggers: Vec<Trigger>,
+        body: Box<Expr>,
+        pos: Position,
+    ) -> Expr {
+        // Keep the body consistent with the triggers.
+        Expr::ForAll(vars, triggers, body, pos)
+    }
+
+    fn fold_exists(
+        &mut self,
+        vars: Vec<LocalVar>,
+        triggers: Vec<Trigger>,
+        body: Box<Expr>,
+        pos: Position,
+    ) -> Expr {
+        Expr::Exists(vars, triggers, body, pos)
+    }
+
+    fn fold_unary_op(&mut self, kind: UnaryOpKind, arg: Box<Expr>, pos: Position) -> Expr {
+        match (kind, self.fold(*arg)) {
+            (UnaryOpKind::Not, Expr::Const(Const::Bool(value), _)) => {
+                Expr::Const(Const::Bool(!value), pos)
+            }
+            (UnaryOpKind::Not, Expr::UnaryOp(UnaryOpKind::Not, box inner, _)) => inner,
+            (UnaryOpKind::Minus, Expr::Const(Const::Int(value), _)) if value != i64::MIN => {
+                Expr::Const(Const::Int(-value), pos)
+            }
+            (kind, arg) => Expr::UnaryOp(kind, box arg, pos),
+        }
+    }
+
+    fn fold_bin_op(
+        &mut self,
+        kind: BinOpKind,
+        left: Box<Expr>,
+        right: Box<Expr>,
+        pos: Position,
+    ) -> Expr {
+        let left = self.fold(*left);
+        let right = self.fold(*right);
+        if let (Expr::Const(left_value, _), Expr::Const(right_value, _)) = (&left, &right) {
+            if let Some(value) = evaluate_bin_op(kind, left_value, right_value) {
+                return Expr::Const(value, pos);
+            }
+        }
+        match (kind, left, right) {
+            (BinOpKind::Add, Expr::Const(Const::Int(0), _), expr)
+            | (BinOpKind::Add, expr, Expr::Const(Const::Int(0), _))
+            | (BinOpKind::Sub, expr, Expr::Const(Const::Int(0), _))
+            | (BinOpKind::Mul, Expr::Const(Const::Int(1), _), expr)
+            | (BinOpKind::Mul, expr, Expr::Const(Const::Int(1), _))
+            | (BinOpKind::And, Expr::Const(Const::Bool(true), _), expr)
+            | (BinOpKind::And, expr, Expr::Const(Const::Bool(true), _))
+            | (BinOpKind::Or, Expr::Const(Const::Bool(false), _), expr)
+            | (BinOpKind::Or, expr, Expr::Const(Const::Bool(false), _))
+            | (BinOpKind::Implies, Expr::Const(Const::Bool(true), _), expr) => expr,
+            // The right operand is not evaluated.
+            (BinOpKind::And, Expr::Const(Const::Bool(false), _), _) => {
+                Expr::Const(Const::Bool(false), pos)
+            }
+            (BinOpKind::Or, Expr::Const(Const::Bool(true), _), _)
+            | (BinOpKind::Implies, Expr::Const(Const::Bool(false), _), _) => {
+                Expr::Const(Const::Bool(true), pos)
+            }
+            (kind, left, right) => Expr::BinOp(kind, box left, box right, pos),
+        }
+    }
+
+    fn fold_cond(
+        &mut self,
+        guard: Box<Expr>,
+        then_expr: Box<Expr>,
+        else_expr: Box<Expr>,
+        pos: Position,
+    ) -> Expr {
+        match self.fold(*guard) {
+            Expr::Const(Const::Bool(true), _) => self.fold(*then_expr),
+            Expr::Const(Const::Bool(false), _) => self.fold(*else_expr),
+            guard => Expr::Cond(
+                box guard,
+                self.fold_boxed(then_expr),
+                self.fold_boxed(else_expr),
+                pos,
+            ),
+        }
+    }
+}
+
+/// Evaluate an operation on two constants. Overflowing integer operations,
+/// and divisions, whose semantics in Viper differ from Rust, are not
+/// evaluated.
+fn evaluate_bin_op(kind: BinOpKind, left: &Const, right: &Const) -> Option<Const> {
+    match (left, right) {
+        (&Const::Int(left), &Const::Int(right)) => match kind {
+            BinOpKind::Add => left.checked_add(right).map(Const::Int),
+            BinOpKind::Sub => left.checked_sub(right).map(Const::Int),
+            BinOpKind::Mul => left.checked_mul(right).map(Const::Int),
+            BinOpKind::EqCmp => Some(Const::Bool(left == right)),
+            BinOpKind::NeCmp => Some(Const::Bool(left != right)),
+            BinOpKind::GtCmp => Some(Const::Bool(left > right)),
+            BinOpKind::GeCmp => Some(Const::Bool(left >= right)),
+            BinOpKind::LtCmp => Some(Const::Bool(left < right)),
+            BinOpKind::LeCmp => Some(Const::Bool(left <= right)),
+            _ => None,
+        },
+        (&Const::Bool(left), &Const::Bool(right)) => match kind {
+            BinOpKind::EqCmp => Some(Const::Bool(left == right)),
+            BinOpKind::NeCmp => Some(Const::Bool(left != right)),
+            BinOpKind::And => Some(Const::Bool(left && right)),
+            BinOpKind::Or => Some(Const::Bool(left || right)),
+            BinOpKind::Implies => Some(Const::Bool(!left || right)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Collects the local variables that are read, i.e. all the variables except
+/// those that are only the target of an assignment.
+struct ReadVarCollector {
+    read_vars: HashSet<LocalVar>,
+}
+
+impl ExprWalker for ReadVarCollector {
+    fn walk_local_var(&mut self, local_var: &LocalVar) {
+        self.read_vars.insert(local_var.clone());
+    }
+}
+
+impl StmtWalker for ReadVarCollector {
+    fn walk_expr(&mut self, expr: &Expr) {
+        ExprWalker::walk(self, expr);
+    }
+    fn walk_local_var(&mut self, local_var: &LocalVar) {
+        self.read_vars.insert(local_var.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir;
+    use crate::vir::{AssignKind, Field};
+
+    fn method_with_block(local_vars: Vec<LocalVar>, stmts: Vec<Stmt>) -> cfg::CfgMethod {
+        let mut method = cfg::CfgMethod::new("test".to_string(), 0, vec![], local_vars, vec![]);
+        let block = method.add_block("start", stmts);
+        method.set_successor(block, cfg::Successor::Return);
+        method
+    }
+
+    fn assign(var: &LocalVar, rhs: Expr) -> Stmt {
+        Stmt::Assign(Expr::local(var.clone()), rhs, AssignKind::Copy)
+    }
+
+    #[test]
+    fn propagate_constants_and_remove_dead_assignments() {
+        let t1 = LocalVar::new("__t1", Type::Int);
+        let t2 = LocalVar::new("__t2", Type::Int);
+        let method = method_with_block(vec![t1.clone(), t2.clone()], vec![
+            assign(&t1, 5.into()),
+            assign(&t2, Expr::add(Expr::local(t1.clone()), 0.into())),
+            vir!{ assert ([Expr::local(t2.clone())] == [Expr::from(5)]) },
+        ]);
+
+        let method = fold_constants(method);
+
+        assert_eq!(method.basic_blocks[0].stmts, vec![vir!{ assert true }]);
+    }
+
+    #[test]
+    fn do_not_propagate_past_assertions() {
+        let x = LocalVar::new("x", Type::Int);
+        let y = LocalVar::new("y", Type::Int);
+        let stmts = vec![
+            assign(&x, 1.into()),
+            vir!{ assert ([Expr::local(x.clone())] > [Expr::from(0)]) },
+            assign(&y, Expr::add(Expr::local(x.clone()), 1.into())),
+        ];
+        let method = method_with_block(vec![x.clone(), y.clone()], stmts);
+
+        let method = fold_constants(method);
+
+        assert_eq!(method.basic_blocks[0].stmts, vec![
+            assign(&x, 1.into()),
+            vir!{ assert true },
+            assign(&y, Expr::add(Expr::local(x.clone()), 1.into())),
+        ]);
+    }
+
+    #[test]
+    fn simplify_identities() {
+        let b = Expr::local(LocalVar::new("b", Type::Bool));
+        let c = Expr::local(LocalVar::new("c", Type::Bool));
+        let n = Expr::local(LocalVar::new("n", Type::Int));
+        let method = method_with_block(vec![], vec![
+            vir!{ inhale (([b] && true) && [Expr::not(Expr::not(c.clone()))]) },
+            vir!{ inhale ([Expr::mul(Expr::sub(n.clone(), 0.into()), 1.into())] == [n]) },
+        ]);
+
+        let method = fold_constants(method);
+
+        assert_eq!(method.basic_blocks[0].stmts, vec![
+            vir!{ inhale ([b] && [c]) },
+            vir!{ inhale ([n] == [n]) },
+        ]);
+    }
+
+    #[test]
+    fn do_not_fold_heap_dependent_and_old_expressions() {
+        let x = LocalVar::new("x", Type::Int);
+        let y = LocalVar::new("y", Type::Int);
+        let r = Expr::local(LocalVar::new_typed_ref("r", "T".to_string()));
+        let stmts = vec![
+            assign(&x, r.field(Field::new("val_int", Type::Int))),
+            vir!{ inhale ([Expr::local(x.clone())] == [Expr::from(0)]) },
+            vir!{ label "l" },
+            assign(&y, 1.into()),
+            vir!{ inhale ([Expr::labelled_old("l", Expr::local(y.clone()))] == [Expr::from(1)]) },
+        ];
+        let method = method_with_block(vec![x, y], stmts.clone());
+
+        let method = fold_constants(method);
+
+        assert_eq!(method.basic_blocks[0].stmts, stmts);
+    }
+
+    #[test]
+    fn fold_the_guards_of_the_successor() {
+        let x = LocalVar::new("x", Type::Bool);
+        let mut method = method_with_block(vec![x.clone()], vec![assign(&x, true.into())]);
+        let start = method.get_indices()[0];
+        let end = method.add_block("end", vec![]);
+        method.set_successor(end, cfg::Successor::Return);
+        method.set_successor(start, cfg::Successor::GotoSwitch(
+            vec![(Expr::not(Expr::local(x)), end)],
+            end,
+        ));
+
+        let method = fold_constants(method);
+
+        assert!(method.basic_blocks[0].stmts.is_empty());
+        assert_eq!(
+            method.basic_blocks[0].successor,
+            cfg::Successor::GotoSwitch(vec![(false.into(), end)], end),
+        );
+    }
+}
@@ -0,0 +1,161 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Optimization that removes the basic blocks that only jump to another
+//! block.
+
+use crate::vir::{cfg, Stmt};
+use std::collections::HashSet;
+use std::mem;
+
+/// Remove the basic blocks that contain only comments and that jump
+/// unconditionally to another block, by redirecting the edges that lead to
+/// them to the block to which they jump. The start block is never removed.
+///
+/// Only comments are removed together with the blocks, so no position used
+/// for the error reporting is lost. The remaining blocks keep their labels.
+pub fn remove_empty_blocks(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    // The block to which each removable block jumps.
+    let mut forward: Vec<Option<usize>> = method.basic_blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| match &block.successor {
+            cfg::Successor::Goto(target) if index != 0 && is_empty(block) => {
+                Some(target.block_index)
+            }
+            _ => None,
+        })
+        .collect();
+    // Keep one block of each cycle of empty blocks, e.g. of `loop {}`.
+    for start in 0..forward.len() {
+        let mut visited = HashSet::new();
+        let mut index = start;
+        while let Some(target) = forward[index] {
+            if !visited.insert(index) {
+                forward[index] = None;
+                break;
+            }
+            index = target;
+        }
+    }
+    let resolve = |mut index: usize| {
+        while let Some(target) = forward[index] {
+            index = target;
+        }
+        index
+    };
+
+    let mut new_indices = vec![None; forward.len()];
+    let mut new_basic_blocks = Vec::new();
+    let mut new_basic_blocks_labels = Vec::new();
+    let basic_blocks = mem::take(&mut method.basic_blocks);
+    let basic_blocks_labels = mem::take(&mut method.basic_blocks_labels);
+    for (index, (block, label)) in basic_blocks.into_iter().zip(basic_blocks_labels).enumerate() {
+        if forward[index].is_none() {
+            new_indices[index] = Some(new_basic_blocks.len());
+            new_basic_blocks.push(block);
+            new_basic_blocks_labels.push(label);
+        }
+    }
+    let redirect = |target: &mut cfg::CfgBlockIndex| {
+        target.block_index = new_indices[resolve(target.block_index)].unwrap();
+    };
+    for block in &mut new_basic_blocks {
+        match &mut block.successor {
+            cfg::Successor::Undefined | cfg::Successor::Return => {}
+            cfg::Successor::Goto(target) => redirect(target),
+            cfg::Successor::GotoSwitch(conditional_targets, default_target) => {
+                redirect(default_target);
+                for (_, target) in conditional_targets {
+                    redirect(target);
+                }
+            }
+        }
+    }
+    method.basic_blocks = new_basic_blocks;
+    method.basic_blocks_labels = new_basic_blocks_labels;
+    method
+}
+
+fn is_empty(block: &cfg::CfgBlock) -> bool {
+    block.stmts.iter().all(|stmt| matches!(stmt, Stmt::Comment(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{Expr, LocalVar, Type};
+
+    fn new_method() -> cfg::CfgMethod {
+        cfg::CfgMethod::new("test".to_string(), 0, vec![], vec![], vec![])
+    }
+
+    fn guard() -> Expr {
+        Expr::local(LocalVar::new("b", Type::Bool))
+    }
+
+    #[test]
+    fn remove_the_blocks_of_a_chain() {
+        let mut method = new_method();
+        let start = method.add_block("start", vec![Stmt::label("l0")]);
+        let empty1 = method.add_block("empty1", vec![Stmt::comment("nothing")]);
+        let empty2 = method.add_block("empty2", vec![]);
+        let end = method.add_block("end", vec![Stmt::label("l1")]);
+        method.set_successor(start, cfg::Successor::Goto(empty1));
+        method.set_successor(empty1, cfg::Successor::Goto(empty2));
+        method.set_successor(empty2, cfg::Successor::Goto(end));
+        method.set_successor(end, cfg::Successor::Return);
+
+        let method = remove_empty_blocks(method);
+
+        assert_eq!(method.basic_blocks.len(), 2);
+        assert_eq!(method.basic_blocks_labels, vec!["start", "end"]);
+        assert_eq!(method.basic_blocks[0].successor.get_following()[0].block_index, 1);
+        assert_eq!(method.basic_blocks[1].stmts, vec![Stmt::label("l1")]);
+    }
+
+    #[test]
+    fn redirect_the_branches_of_a_switch() {
+        let mut method = new_method();
+        let start = method.add_block("start", vec![]);
+        let then_block = method.add_block("then_block", vec![]);
+        let else_block = method.add_block("else_block", vec![Stmt::label("l0")]);
+        let join = method.add_block("join", vec![Stmt::label("l1")]);
+        method.set_successor(start, cfg::Successor::GotoSwitch(vec![(guard(), then_block)], else_block));
+        method.set_successor(then_block, cfg::Successor::Goto(join));
+        method.set_successor(else_block, cfg::Successor::Goto(join));
+        method.set_successor(join, cfg::Successor::Return);
+
+        let method = remove_empty_blocks(method);
+
+        // The start block is empty, but it is kept.
+        assert_eq!(method.basic_blocks.len(), 3);
+        assert_eq!(method.basic_blocks_labels, vec!["start", "else_block", "join"]);
+        let targets: Vec<_> = method.basic_blocks[0].successor
+            .get_following()
+            .iter()
+            .map(|target| target.block_index)
+            .collect();
+        assert_eq!(targets, vec![2, 1]);
+    }
+
+    #[test]
+    fn keep_a_block_of_an_empty_loop() {
+        let mut method = new_method();
+        let start = method.add_block("start", vec![Stmt::label("l0")]);
+        let loop_head = method.add_block("loop_head", vec![]);
+        let loop_body = method.add_block("loop_body", vec![]);
+        method.set_successor(start, cfg::Successor::Goto(loop_head));
+        method.set_successor(loop_head, cfg::Successor::Goto(loop_body));
+        method.set_successor(loop_body, cfg::Successor::Goto(loop_head));
+
+        let method = remove_empty_blocks(method);
+
+        assert_eq!(method.basic_blocks.len(), 2);
+        assert_eq!(method.basic_blocks[0].successor.get_following()[0].block_index, 1);
+        assert_eq!(method.basic_blocks[1].successor.get_following()[0].block_index, 1);
+    }
+}
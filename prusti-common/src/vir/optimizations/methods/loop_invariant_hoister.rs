@@ -0,0 +1,413 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Optimization that moves the loop-invariant assignments of pure local
+//! variables out of the loops of the CFG.
+
+use crate::vir::{
+    cfg, AssignKind, BinOpKind, ContainerOpKind, Expr, ExprWalker, LocalVar, Position, Stmt,
+    StmtWalker, Type,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Move to the preheader of a loop the assignments `x := e` of the loop such
+/// that:
+/// * `x` is a pure local variable (of type `Int` or `Bool`) that is assigned
+///   only once in the loop and that is not read outside of the loop;
+/// * every read of `x` in the loop comes after the assignment, i.e. the block
+///   of the assignment dominates the block of the read;
+/// * `e` does not depend on the heap, cannot fail, and only reads variables
+///   that are not assigned in the loop.
+///
+/// Heap-dependent expressions, such as field accesses and function
+/// applications, are never moved, so the statements of the loop that modify
+/// the heap do not need to be considered. The inner loops are handled first,
+/// so that an assignment can be moved out of several nested loops. Only the
+/// loops with a preheader, i.e. a unique predecessor outside of the loop that
+/// jumps unconditionally to the loop head, are optimized.
+pub fn hoist_loop_invariants(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    if !method.has_loops() {
+        return method;
+    }
+    let dominators = compute_dominators(&method);
+    let mut loops = find_loops(&method, &dominators);
+    loops.sort_by_key(|loop_blocks| loop_blocks.1.len());
+
+    let count_stmts_in_loops = |method: &cfg::CfgMethod| -> usize {
+        let blocks: HashSet<_> = loops.iter().flat_map(|(_, blocks)| blocks).collect();
+        blocks.into_iter().map(|&index| method.basic_blocks[index].stmts.len()).sum()
+    };
+    let stmts_before = count_stmts_in_loops(&method);
+    let mut hoisted_count = 0;
+    for (head, loop_blocks) in &loops {
+        if let Some(preheader) = find_preheader(&method, *head, loop_blocks) {
+            while let Some((block_index, stmt_index)) =
+                find_hoistable_assignment(&method, loop_blocks, &dominators)
+            {
+                let stmt = method.basic_blocks[block_index].stmts.remove(stmt_index);
+                method.basic_blocks[preheader].stmts.push(stmt);
+                hoisted_count += 1;
+            }
+        }
+    }
+    info!(
+        "Hoisted {} loop-invariant assignments out of the {} loops of {}: {} statements in loops before, {} after",
+        hoisted_count,
+        loops.len(),
+        method.name(),
+        stmts_before,
+        count_stmts_in_loops(&method),
+    );
+    method
+}
+
+fn is_pure(var: &LocalVar) -> bool {
+    matches!(var.typ, Type::Int | Type::Bool)
+}
+
+fn predecessors(method: &cfg::CfgMethod) -> Vec<Vec<usize>> {
+    let mut predecessors = vec![vec![]; method.basic_blocks.len()];
+    for (index, block) in method.basic_blocks.iter().enumerate() {
+        for target in block.successor.get_following() {
+            predecessors[target.block_index].push(index);
+        }
+    }
+    predecessors
+}
+
+/// The blocks that dominate each block, including the block itself.
+fn compute_dominators(method: &cfg::CfgMethod) -> Vec<HashSet<usize>> {
+    let predecessors = predecessors(method);
+    let block_count = method.basic_blocks.len();
+    let mut dominators: Vec<HashSet<usize>> = vec![(0..block_count).collect(); block_count];
+    dominators[0] = [0].iter().cloned().collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for index in 1..block_count {
+            let mut new_dominators = predecessors[index]
+                .iter()
+                .map(|predecessor| dominators[*predecessor].clone())
+                .reduce(|left, right| left.intersection(&right).cloned().collect())
+                .unwrap_or_default();
+            new_dominators.insert(index);
+            if new_dominators != dominators[index] {
+                dominators[index] = new_dominators;
+                changed = true;
+            }
+        }
+    }
+    dominators
+}
+
+/// The natural loops of the CFG, given by their head and their blocks. The
+/// loops that share the same head are merged.
+fn find_loops(method: &cfg::CfgMethod, dominators: &[HashSet<usize>]) -> Vec<(usize, HashSet<usize>)> {
+    let predecessors = predecessors(method);
+    let mut loops: HashMap<usize, HashSet<usize>> = HashMap::new();
+    for (index, block) in method.basic_blocks.iter().enumerate() {
+        for target in block.successor.get_following() {
+            let head = target.block_index;
+            if !dominators[index].contains(&head) {
+                continue;
+            }
+            // `index -> head` is a back edge.
+            let loop_blocks = loops.entry(head).or_insert_with(|| [head].iter().cloned().collect());
+            let mut to_visit = vec![index];
+            while let Some(current) = to_visit.pop() {
+                if loop_blocks.insert(current) {
+                    to_visit.extend(&predecessors[current]);
+                }
+            }
+        }
+    }
+    let mut loops: Vec<_> = loops.into_iter().collect();
+    loops.sort_by_key(|(head, _)| *head);
+    loops
+}
+
+fn find_preheader(method: &cfg::CfgMethod, head: usize, loop_blocks: &HashSet<usize>) -> Option<usize> {
+    let entries: Vec<_> = predecessors(method)[head]
+        .iter()
+        .cloned()
+        .filter(|predecessor| !loop_blocks.contains(predecessor))
+        .collect();
+    match entries.as_slice() {
+        [preheader] => match &method.basic_blocks[*preheader].successor {
+            cfg::Successor::Goto(_) => Some(*preheader),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Find the first assignment of the loop that can be moved to its preheader.
+fn find_hoistable_assignment(
+    method: &cfg::CfgMethod,
+    loop_blocks: &HashSet<usize>,
+    dominators: &[HashSet<usize>],
+) -> Option<(usize, usize)> {
+    let mut assignment_counts: HashMap<LocalVar, usize> = HashMap::new();
+    for &block_index in loop_blocks {
+        for stmt in &method.basic_blocks[block_index].stmts {
+            for var in assigned_vars(stmt) {
+                *assignment_counts.entry(var).or_default() += 1;
+            }
+        }
+    }
+    let mut loop_blocks_in_order: Vec<_> = loop_blocks.iter().cloned().collect();
+    loop_blocks_in_order.sort_unstable();
+    for block_index in loop_blocks_in_order {
+        for (stmt_index, stmt) in method.basic_blocks[block_index].stmts.iter().enumerate() {
+            if let Stmt::Assign(Expr::Local(var, _), rhs, AssignKind::Copy) = stmt {
+                let is_hoistable = is_pure(var)
+                    && assignment_counts[var] == 1
+                    && is_invariant_expr(rhs)
+                    && read_vars(rhs).read_vars.iter().all(|read_var| !assignment_counts.contains_key(read_var))
+                    && are_reads_after_assignment(method, loop_blocks, dominators, var, block_index, stmt_index);
+                if is_hoistable {
+                    return Some((block_index, stmt_index));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Check that all the reads of `var` are in the loop, after its assignment
+/// at `stmt_index` of the block `assignment_block`, and not in old
+/// expressions.
+fn are_reads_after_assignment(
+    method: &cfg::CfgMethod,
+    loop_blocks: &HashSet<usize>,
+    dominators: &[HashSet<usize>],
+    var: &LocalVar,
+    assignment_block: usize,
+    stmt_index: usize,
+) -> bool {
+    for (block_index, block) in method.basic_blocks.iter().enumerate() {
+        let mut collectors: Vec<_> = block.stmts.iter().map(stmt_read_vars).collect();
+        if let cfg::Successor::GotoSwitch(conditional_targets, _) = &block.successor {
+            for (guard, _) in conditional_targets {
+                collectors.push(read_vars(guard));
+            }
+        }
+        for (index, collector) in collectors.iter().enumerate() {
+            if !collector.read_vars.contains(var) {
+                continue;
+            }
+            let is_after_assignment = if block_index == assignment_block {
+                index > stmt_index
+            } else {
+                dominators[block_index].contains(&assignment_block)
+            };
+            if !loop_blocks.contains(&block_index) || !is_after_assignment || collector.old_vars.contains(var) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Check that an expression does not depend on the heap and cannot fail.
+fn is_invariant_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Local(..) | Expr::Const(..) => true,
+        Expr::UnaryOp(_, arg, _) => is_invariant_expr(arg),
+        Expr::BinOp(BinOpKind::Div, ..) | Expr::BinOp(BinOpKind::Mod, ..) => false,
+        Expr::BinOp(_, left, right, _) => is_invariant_expr(left) && is_invariant_expr(right),
+        Expr::Cond(guard, then_expr, else_expr, _) => {
+            is_invariant_expr(guard) && is_invariant_expr(then_expr) && is_invariant_expr(else_expr)
+        }
+        Expr::DomainFuncApp(_, args, _) => args.iter().all(is_invariant_expr),
+        Expr::ContainerOp(ContainerOpKind::SeqIndex, ..) => false,
+        Expr::ContainerOp(_, left, right, _) => is_invariant_expr(left) && is_invariant_expr(right),
+        Expr::Seq(_, elems, _) => elems.iter().all(is_invariant_expr),
+        // Field and predicate accesses, function applications, old
+        // expressions, quantifiers, ...
+        _ => false,
+    }
+}
+
+fn assigned_vars(stmt: &Stmt) -> Vec<LocalVar> {
+    match stmt {
+        Stmt::Assign(target, _, _) => vec![target.get_base()],
+        Stmt::MethodCall(_, _, targets) => targets.clone(),
+        Stmt::If(_, then_stmts, else_stmts) => {
+            then_stmts.iter().chain(else_stmts).flat_map(assigned_vars).collect()
+        }
+        Stmt::PackageMagicWand(_, body, ..) => body.iter().flat_map(assigned_vars).collect(),
+        _ => vec![],
+    }
+}
+
+fn read_vars(expr: &Expr) -> ReadVarCollector {
+    let mut collector = ReadVarCollector::default();
+    ExprWalker::walk(&mut collector, expr);
+    collector
+}
+
+fn stmt_read_vars(stmt: &Stmt) -> ReadVarCollector {
+    match stmt {
+        Stmt::Assign(Expr::Local(..), rhs, _) => read_vars(rhs),
+        _ => {
+            let mut collector = ReadVarCollector::default();
+            StmtWalker::walk(&mut collector, stmt);
+            collector
+        }
+    }
+}
+
+/// Collects the local variables that are read, and those that are read in
+/// an old expression.
+#[derive(Default)]
+struct ReadVarCollector {
+    read_vars: HashSet<LocalVar>,
+    old_vars: HashSet<LocalVar>,
+    is_in_old: bool,
+}
+
+impl ExprWalker for ReadVarCollector {
+    fn walk_local_var(&mut self, local_var: &LocalVar) {
+        if self.is_in_old {
+            self.old_vars.insert(local_var.clone());
+        }
+        self.read_vars.insert(local_var.clone());
+    }
+    fn walk_labelled_old(&mut self, _label: &str, body: &Expr, _pos: &Position) {
+        let was_in_old = self.is_in_old;
+        self.is_in_old = true;
+        ExprWalker::walk(self, body);
+        self.is_in_old = was_in_old;
+    }
+}
+
+impl StmtWalker for ReadVarCollector {
+    fn walk_expr(&mut self, expr: &Expr) {
+        ExprWalker::walk(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int_var(name: &str) -> LocalVar {
+        LocalVar::new(name, Type::Int)
+    }
+
+    fn assign(var: &LocalVar, rhs: Expr) -> Stmt {
+        Stmt::Assign(Expr::local(var.clone()), rhs, AssignKind::Copy)
+    }
+
+    fn guard() -> Expr {
+        Expr::local(LocalVar::new("b", Type::Bool))
+    }
+
+    /// `start -> head <-> body`, `head -> end`
+    fn single_loop(body_stmts: Vec<Stmt>) -> cfg::CfgMethod {
+        let mut method = cfg::CfgMethod::new("test".to_string(), 0, vec![], vec![], vec![]);
+        let start = method.add_block("start", vec![]);
+        let head = method.add_block("head", vec![]);
+        let body = method.add_block("body", body_stmts);
+        let end = method.add_block("end", vec![]);
+        method.set_successor(start, cfg::Successor::Goto(head));
+        method.set_successor(head, cfg::Successor::GotoSwitch(vec![(guard(), body)], end));
+        method.set_successor(body, cfg::Successor::Goto(head));
+        method.set_successor(end, cfg::Successor::Return);
+        method
+    }
+
+    #[test]
+    fn hoist_an_invariant_assignment() {
+        let (n, i, len) = (int_var("n"), int_var("i"), int_var("len"));
+        let method = single_loop(vec![
+            assign(&len, Expr::add(Expr::local(n.clone()), 1.into())),
+            assign(&i, Expr::add(Expr::local(i.clone()), Expr::local(len.clone()))),
+        ]);
+
+        let method = hoist_loop_invariants(method);
+
+        assert_eq!(method.basic_blocks[0].stmts, vec![
+            assign(&len, Expr::add(Expr::local(n), 1.into())),
+        ]);
+        assert_eq!(method.basic_blocks[2].stmts, vec![
+            assign(&i, Expr::add(Expr::local(i.clone()), Expr::local(len))),
+        ]);
+    }
+
+    #[test]
+    fn keep_variant_and_heap_dependent_assignments() {
+        let (i, x, y) = (int_var("i"), int_var("x"), int_var("y"));
+        let r = Expr::local(LocalVar::new_typed_ref("r", "T".to_string()));
+        let body_stmts = vec![
+            // Reads a variable assigned in the loop.
+            assign(&x, Expr::add(Expr::local(i.clone()), 1.into())),
+            assign(&i, Expr::local(x.clone())),
+            // Reads the heap.
+            assign(&y, r.field(crate::vir::Field::new("val_int", Type::Int))),
+        ];
+        let method = single_loop(body_stmts.clone());
+
+        let method = hoist_loop_invariants(method);
+
+        assert!(method.basic_blocks[0].stmts.is_empty());
+        assert_eq!(method.basic_blocks[2].stmts, body_stmts);
+    }
+
+    #[test]
+    fn keep_assignments_read_before_or_after_the_loop() {
+        let (n, x, y, z) = (int_var("n"), int_var("x"), int_var("y"), int_var("z"));
+        let mut method = single_loop(vec![
+            assign(&y, Expr::local(x.clone())),
+            // Read before the assignment, by the previous statement.
+            assign(&x, Expr::local(n.clone())),
+            // Read after the loop.
+            assign(&z, Expr::local(n)),
+        ]);
+        method.basic_blocks[3].stmts.push(Stmt::Inhale(Expr::eq_cmp(Expr::local(z), 0.into())));
+
+        let method = hoist_loop_invariants(method);
+
+        assert!(method.basic_blocks[0].stmts.is_empty());
+        assert_eq!(method.basic_blocks[2].stmts.len(), 3);
+    }
+
+    #[test]
+    fn hoist_out_of_nested_loops() {
+        // start -> outer_head <-> outer_body -> inner_head <-> inner_body,
+        // inner_head -> outer_latch -> outer_head, outer_head -> end
+        let (n, k, i, j) = (int_var("n"), int_var("k"), int_var("i"), int_var("j"));
+        let mut method = cfg::CfgMethod::new("test".to_string(), 0, vec![], vec![], vec![]);
+        let start = method.add_block("start", vec![]);
+        let outer_head = method.add_block("outer_head", vec![]);
+        let outer_body = method.add_block("outer_body", vec![assign(&i, Expr::add(Expr::local(i.clone()), 1.into()))]);
+        let inner_head = method.add_block("inner_head", vec![]);
+        let inner_body = method.add_block("inner_body", vec![
+            assign(&k, Expr::mul(Expr::local(n.clone()), 2.into())),
+            assign(&j, Expr::add(Expr::local(j.clone()), Expr::local(k.clone()))),
+        ]);
+        let outer_latch = method.add_block("outer_latch", vec![]);
+        let end = method.add_block("end", vec![]);
+        method.set_successor(start, cfg::Successor::Goto(outer_head));
+        method.set_successor(outer_head, cfg::Successor::GotoSwitch(vec![(guard(), outer_body)], end));
+        method.set_successor(outer_body, cfg::Successor::Goto(inner_head));
+        method.set_successor(inner_head, cfg::Successor::GotoSwitch(vec![(guard(), inner_body)], outer_latch));
+        method.set_successor(inner_body, cfg::Successor::Goto(inner_head));
+        method.set_successor(outer_latch, cfg::Successor::Goto(outer_head));
+        method.set_successor(end, cfg::Successor::Return);
+
+        let method = hoist_loop_invariants(method);
+
+        // `k := n * 2` is moved to the preheader of the inner loop, and then
+        // to the preheader of the outer loop.
+        assert_eq!(method.basic_blocks[start.index()].stmts, vec![
+            assign(&k, Expr::mul(Expr::local(n), 2.into())),
+        ]);
+        assert_eq!(method.basic_blocks[outer_body.index()].stmts.len(), 1);
+        assert_eq!(method.basic_blocks[inner_body.index()].stmts.len(), 1);
+    }
+}
@@ -7,42 +7,111 @@
 //! A module that contains optimizations for methods.
 
 mod cfg_cleaner;
+mod empty_block_remover;
 mod empty_if_remover;
 mod assert_remover;
+mod constant_folder;
 mod var_remover;
+mod loop_invariant_hoister;
 mod purifier;
 mod quantifier_fixer;
 
-use crate::config::Optimizations;
+use crate::config;
 use crate::vir::cfg::CfgMethod;
+use super::folding::remove_fold_unfold_pairs;
 use super::log_method;
 
 use self::cfg_cleaner::clean_cfg;
+use self::empty_block_remover::remove_empty_blocks;
 use self::empty_if_remover::remove_empty_if;
-use self::assert_remover::remove_trivial_assertions;
+use self::assert_remover::{remove_duplicate_assertions, remove_trivial_assertions};
+use self::constant_folder::fold_constants;
 use self::var_remover::remove_unused_vars;
+use self::loop_invariant_hoister::hoist_loop_invariants;
 use self::purifier::purify_vars;
 use self::quantifier_fixer::fix_quantifiers;
 
-pub fn optimize_method_encoding(cfg: CfgMethod, source_file_name: &str, optimizations: &Optimizations) -> CfgMethod {
-    macro_rules! apply {
-        ($optimization: ident, $cfg: ident) => {
-            if optimizations.$optimization {
-                log_method(source_file_name, &$cfg, stringify!($optimization), false);
-                let optimized_cfg = $optimization($cfg);
-                log_method(source_file_name, &optimized_cfg, stringify!($optimization), true);
-                optimized_cfg
-            } else {
-                $cfg
-            }
-        };
+pub use self::purifier::{snapshot_havoc_methods, SnapshotDomains};
+
+/// An optimization of a method, which can use the snapshot domains of the
+/// program.
+pub type MethodOptimization = fn(CfgMethod, &SnapshotDomains) -> CfgMethod;
+
+/// The optimizations of the methods, in the order in which they run by
+/// default.
+const METHOD_OPTIMIZATIONS: &[(&str, MethodOptimization)] = &[
+    ("purify_vars", purify_vars),
+    ("fix_quantifiers", |cfg, _| fix_quantifiers(cfg)),
+    ("remove_fold_unfold_pairs", |cfg, _| remove_fold_unfold_pairs(cfg)),
+    ("remove_empty_if", |cfg, _| remove_empty_if(cfg)),
+    // Followed by `remove_unused_vars` and `remove_trivial_assertions`, which
+    // remove what the folding made unnecessary.
+    ("fold_constants", |cfg, _| fold_constants(cfg)),
+    ("hoist_loop_invariants", |cfg, _| hoist_loop_invariants(cfg)),
+    ("remove_unused_vars", |cfg, _| remove_unused_vars(cfg)),
+    ("remove_trivial_assertions", |cfg, _| remove_trivial_assertions(cfg)),
+    ("remove_duplicate_assertions", |cfg, _| remove_duplicate_assertions(cfg)),
+    ("remove_empty_blocks", |cfg, _| remove_empty_blocks(cfg)),
+    ("clean_cfg", |cfg, _| clean_cfg(cfg)),
+];
+
+/// The optimizations of the methods selected with the `vir_optimizations`
+/// flag, in order.
+pub fn selected_method_optimizations() -> Vec<(&'static str, MethodOptimization)> {
+    let mut selected = vec![];
+    for name in config::vir_optimizations() {
+        match name.as_str() {
+            "all" => selected.extend_from_slice(METHOD_OPTIMIZATIONS),
+            "none" => {}
+            _ => match METHOD_OPTIMIZATIONS.iter().find(|(known_name, _)| *known_name == name) {
+                Some(optimization) => selected.push(*optimization),
+                None => warn!("Ignoring unknown VIR optimization '{}'", name),
+            },
+        }
+    }
+    selected
+}
+
+/// The size of a method, to report the effect of each optimization.
+struct MethodSize {
+    basic_blocks: usize,
+    stmts: usize,
+    local_vars: usize,
+}
+
+impl MethodSize {
+    fn of(cfg: &CfgMethod) -> Self {
+        MethodSize {
+            basic_blocks: cfg.basic_blocks.len(),
+            stmts: cfg.basic_blocks.iter().map(|block| block.stmts.len()).sum(),
+            local_vars: cfg.local_vars.len(),
+        }
     }
-    let cfg = apply!(purify_vars, cfg);
-    let cfg = apply!(fix_quantifiers, cfg);
-    let cfg = apply!(remove_empty_if, cfg);
-    let cfg = apply!(remove_unused_vars, cfg);
-    let cfg = apply!(remove_trivial_assertions, cfg);
-    let cfg = apply!(clean_cfg, cfg);
+}
 
+pub fn optimize_method_encoding(
+    mut cfg: CfgMethod,
+    source_file_name: &str,
+    optimizations: &[(&str, MethodOptimization)],
+    snapshots: &SnapshotDomains,
+) -> CfgMethod {
+    for (name, optimization) in optimizations {
+        log_method(source_file_name, &cfg, name, false);
+        let size_before = MethodSize::of(&cfg);
+        cfg = optimization(cfg, snapshots);
+        let size_after = MethodSize::of(&cfg);
+        info!(
+            "Optimization {} of {}: {} -> {} basic blocks, {} -> {} statements, {} -> {} local variables",
+            name,
+            cfg.name(),
+            size_before.basic_blocks,
+            size_after.basic_blocks,
+            size_before.stmts,
+            size_after.stmts,
+            size_before.local_vars,
+            size_after.local_vars,
+        );
+        log_method(source_file_name, &cfg, name, true);
+    }
     cfg
 }
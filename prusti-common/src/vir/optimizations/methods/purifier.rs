@@ -8,6 +8,11 @@
 //! local variables.
 //!
 //! For example, `_1.val_int` will become `_1i` where `_1i` is of type Int.
+//!
+//! Variables of a struct, tuple or enum type that has a snapshot domain are
+//! purified into variables of the snapshot type. For example,
+//! `_1.f$x.val_int` will become `Snap$Point$0$field$f$x(_1)` where `_1` is of
+//! type `Snap$Point`.
 
 use super::super::super::ast;
 use super::super::super::cfg;
@@ -16,8 +21,17 @@ use std::collections::{HashMap, HashSet};
 use std::{self, mem};
 use prusti_utils::force_matches;
 
+/// The name of the function that converts a reference to its snapshot. It
+/// must match the one used by the snapshot encoder.
+const SNAP_FUNC_NAME: &str = "snap$";
+
 /// Purify vars.
-pub fn purify_vars(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+pub fn purify_vars(method: cfg::CfgMethod, snapshots: &SnapshotDomains) -> cfg::CfgMethod {
+    let method = purify_int_vars(method);
+    purify_snapshot_vars(method, snapshots)
+}
+
+fn purify_int_vars(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
     let mut collector = VarCollector {
         all_vars: HashSet::new(),
         impure_vars: HashSet::new(),
@@ -66,6 +80,7 @@ pub fn purify_vars(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
         }
     };
     method.local_vars = method.local_vars.into_iter().map(fix_var).collect();
+    info!("Purified {} local variables of {}", purifier.pure_vars.len(), method.name());
     method
 }
 
@@ -443,3 +458,798 @@ impl ast::StmtFolder for VarPurifier {
         )
     }
 }
+
+/// The snapshot domains of the structs, tuples and enums of a program, by
+/// the name of the predicate of their type.
+#[derive(Default)]
+pub struct SnapshotDomains {
+    domains: HashMap<String, SnapshotDomain>,
+}
+
+struct SnapshotDomain {
+    /// The constructor and the field functions of each variant. The field
+    /// functions are in the order of the arguments of the constructor.
+    variants: Vec<(ast::DomainFunc, Vec<(String, ast::DomainFunc)>)>,
+    /// The variant index of each `enum_*` field of an enum.
+    variant_indices: HashMap<String, usize>,
+    /// The discriminant function of an enum.
+    discriminant: Option<ast::DomainFunc>,
+}
+
+impl SnapshotDomain {
+    /// Recognizes the domain of the snapshot of a struct, tuple or enum by
+    /// the names of its functions.
+    fn new(domain: &ast::Domain) -> Option<Self> {
+        let field_prefix = format!("{}$", domain.name);
+        let mut constructors = vec![];
+        let mut fields = vec![];
+        let mut discriminant = None;
+        for function in &domain.functions {
+            if function.name == "discriminant$" {
+                discriminant = Some(function.clone());
+            } else if let Some(index) = function.name
+                .strip_prefix("cons$")
+                .and_then(|rest| rest.strip_suffix('$'))
+            {
+                constructors.push((index.parse::<usize>().ok()?, function.clone()));
+            } else if let Some((index, field_name)) = function.name
+                .strip_prefix(&field_prefix)
+                .and_then(|rest| rest.split_once("$field$"))
+            {
+                fields.push((index.parse::<usize>().ok()?, field_name.to_string(), function.clone()));
+            } else {
+                return None;
+            }
+        }
+        constructors.sort_by_key(|(index, _)| *index);
+        let mut variants = vec![];
+        for (expected_index, (index, constructor)) in constructors.into_iter().enumerate() {
+            if index != expected_index {
+                return None;
+            }
+            let variant_fields: Vec<_> = fields
+                .iter()
+                .filter(|(field_index, _, _)| *field_index == index)
+                .map(|(_, name, function)| (name.clone(), function.clone()))
+                .collect();
+            let matches_constructor = variant_fields.len() == constructor.formal_args.len()
+                && variant_fields
+                    .iter()
+                    .zip(&constructor.formal_args)
+                    .all(|((_, function), arg)| function.return_type == arg.typ);
+            if !matches_constructor {
+                return None;
+            }
+            variants.push((constructor, variant_fields));
+        }
+        if variants.is_empty() || (variants.len() > 1) != discriminant.is_some() {
+            return None;
+        }
+        Some(SnapshotDomain {
+            variants,
+            variant_indices: HashMap::new(),
+            discriminant,
+        })
+    }
+
+    fn field_function(&self, variant_index: usize, field_name: &str) -> Option<&ast::DomainFunc> {
+        self.variants
+            .get(variant_index)?
+            .1
+            .iter()
+            .find(|(name, _)| name == field_name)
+            .map(|(_, function)| function)
+    }
+}
+
+impl SnapshotDomains {
+    pub fn new(domains: &[ast::Domain], predicates: &[ast::Predicate]) -> Self {
+        let mut snapshot_domains = HashMap::new();
+        for domain in domains {
+            if let Some(predicate_name) = domain.name.strip_prefix("Snap$") {
+                if let Some(snapshot_domain) = SnapshotDomain::new(domain) {
+                    snapshot_domains.insert(predicate_name.to_string(), snapshot_domain);
+                }
+            }
+        }
+        // The snapshot encoder numbers the variants of an enum in the order
+        // of its predicate.
+        for predicate in predicates {
+            if let ast::Predicate::Enum(enum_predicate) = predicate {
+                if let Some(domain) = snapshot_domains.get_mut(&enum_predicate.name) {
+                    if domain.variants.len() == enum_predicate.variants.len() {
+                        for (index, (_, variant_name, _)) in enum_predicate.variants.iter().enumerate() {
+                            domain.variant_indices.insert(format!("enum_{}", variant_name), index);
+                        }
+                    }
+                }
+            }
+        }
+        SnapshotDomains { domains: snapshot_domains }
+    }
+
+    fn get(&self, typ: &ast::Type) -> Option<&SnapshotDomain> {
+        match typ {
+            ast::Type::Snapshot(predicate_name) => self.domains.get(predicate_name),
+            _ => None,
+        }
+    }
+
+    /// The variable of snapshot type that replaces `var`.
+    fn snapshot_var(&self, var: &ast::LocalVar) -> Option<ast::LocalVar> {
+        match &var.typ {
+            ast::Type::TypedRef(predicate_name) if self.domains.contains_key(predicate_name) => {
+                Some(ast::LocalVar::new(var.name.clone(), ast::Type::Snapshot(predicate_name.clone())))
+            }
+            _ => None,
+        }
+    }
+
+    /// The snapshot of `place` once its base variable is purified, if it
+    /// can be expressed with the domain functions.
+    fn snapshot_of_place(&self, place: &ast::Expr) -> Option<ast::Expr> {
+        match place {
+            ast::Expr::Local(var, pos) => Some(ast::Expr::Local(self.snapshot_var(var)?, *pos)),
+            ast::Expr::Field(base, field, pos) => {
+                match field.name.as_str() {
+                    // Snapshots do not distinguish references and boxes
+                    // from their target.
+                    "val_ref" => return self.snapshot_of_place(base),
+                    "val_int" | "val_bool" => {
+                        return self.snapshot_of_place(base).filter(|snapshot| {
+                            matches!(snapshot.get_type(), ast::Type::Int | ast::Type::Bool)
+                        });
+                    }
+                    _ => {}
+                }
+                let (base, variant) = match &**base {
+                    ast::Expr::Variant(enum_base, variant, _) => (enum_base, Some(variant)),
+                    _ => (base, None),
+                };
+                let snapshot = self.snapshot_of_place(base)?;
+                let domain = self.get(snapshot.get_type())?;
+                let function = match variant {
+                    Some(variant) => {
+                        domain.field_function(*domain.variant_indices.get(&variant.name)?, &field.name)?
+                    }
+                    None if field.name == "discriminant" => domain.discriminant.as_ref()?,
+                    None if domain.variants.len() == 1 => domain.field_function(0, &field.name)?,
+                    None => return None,
+                };
+                Some(ast::Expr::DomainFuncApp(function.clone(), vec![snapshot], *pos))
+            }
+            _ => None,
+        }
+    }
+
+    /// The snapshot of the base variable of `target` after assigning `value`
+    /// to it, if `target` is an integer or boolean reached only through
+    /// fields of structs and tuples.
+    fn snapshot_after_assign(&self, target: &ast::Expr, value: ast::Expr) -> Option<ast::Expr> {
+        match target {
+            ast::Expr::Field(place, field, _)
+                if field.name == "val_int" || field.name == "val_bool" =>
+            {
+                self.snapshot_of_place(target)?;
+                self.rebuild_place(place, value)
+            }
+            _ => None,
+        }
+    }
+
+    fn rebuild_place(&self, place: &ast::Expr, value: ast::Expr) -> Option<ast::Expr> {
+        match place {
+            ast::Expr::Local(..) => Some(value),
+            ast::Expr::Field(base, field, _) => {
+                let snapshot = self.snapshot_of_place(base)?;
+                let domain = self.get(snapshot.get_type())?;
+                if domain.variants.len() != 1 {
+                    return None;
+                }
+                let (constructor, fields) = &domain.variants[0];
+                if !fields.iter().any(|(name, _)| name == &field.name) {
+                    return None;
+                }
+                let args = fields
+                    .iter()
+                    .map(|(name, function)| {
+                        if name == &field.name {
+                            value.clone()
+                        } else {
+                            function.apply(vec![snapshot.clone()])
+                        }
+                    })
+                    .collect();
+                self.rebuild_place(base, constructor.apply(args))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The name of the builtin method that havocs a variable of the snapshot
+/// type `typ`.
+fn snapshot_havoc_method_name(typ: &ast::Type) -> String {
+    format!("builtin$havoc_snap${}", typ.name())
+}
+
+/// The declarations of the builtin methods that havoc the variables that the
+/// purifier replaced with their snapshots.
+pub fn snapshot_havoc_methods(methods: &[cfg::CfgMethod]) -> Vec<ast::BodylessMethod> {
+    let mut havoc_methods = HashMap::new();
+    for method in methods {
+        method.walk_statements(|stmt| {
+            if let ast::Stmt::MethodCall(name, _, targets) = stmt {
+                if let [target] = &targets[..] {
+                    if target.typ.is_snapshot() && *name == snapshot_havoc_method_name(&target.typ) {
+                        havoc_methods.entry(name.clone()).or_insert_with(|| ast::BodylessMethod {
+                            name: name.clone(),
+                            formal_args: vec![],
+                            formal_returns: vec![ast::LocalVar::new("ret", target.typ.clone())],
+                        });
+                    }
+                }
+            }
+        });
+    }
+    let mut havoc_methods: Vec<_> = havoc_methods.into_iter().map(|(_, method)| method).collect();
+    havoc_methods.sort_by(|left, right| left.name.cmp(&right.name));
+    havoc_methods
+}
+
+/// The variable on which `expr` is a chain of field and variant accesses.
+fn place_root(expr: &ast::Expr) -> Option<&ast::LocalVar> {
+    match expr {
+        ast::Expr::Local(var, _) => Some(var),
+        ast::Expr::Field(base, _, _) | ast::Expr::Variant(base, _, _) => place_root(base),
+        _ => None,
+    }
+}
+
+/// Purify the variables of a type with a snapshot domain that are only
+/// havocked, read through fields, assigned through fields of structs and
+/// tuples, or converted to a snapshot.
+fn purify_snapshot_vars(mut method: cfg::CfgMethod, snapshots: &SnapshotDomains) -> cfg::CfgMethod {
+    let candidates: HashSet<_> = method.local_vars
+        .iter()
+        .filter(|var| var.name != "_0" && snapshots.snapshot_var(var).is_some())
+        .cloned()
+        .collect();
+    if candidates.is_empty() {
+        return method;
+    }
+    let mut collector = SnapshotVarCollector {
+        snapshots,
+        candidates,
+        impure_vars: HashSet::new(),
+        assigned_vars: HashSet::new(),
+        old_vars: HashSet::new(),
+        is_impure_context: false,
+        is_old_context: false,
+    };
+    method.walk_statements(|stmt| {
+        ast::StmtWalker::walk(&mut collector, stmt);
+    });
+    method.walk_successors(|successor| {
+        if let cfg::Successor::GotoSwitch(conditional_targets, _) = successor {
+            for (expr, _) in conditional_targets {
+                ast::ExprWalker::walk(&mut collector, expr);
+            }
+        }
+    });
+    // The value of a variable in an old expression is the current one only
+    // if the variable is never assigned.
+    let SnapshotVarCollector { candidates, impure_vars, assigned_vars, old_vars, .. } = collector;
+    let pure_vars: HashSet<_> = candidates
+        .into_iter()
+        .filter(|var| {
+            !impure_vars.contains(var) && !(old_vars.contains(var) && assigned_vars.contains(var))
+        })
+        .collect();
+    if pure_vars.is_empty() {
+        return method;
+    }
+
+    let mut purifier = SnapshotVarPurifier {
+        snapshots,
+        pure_vars,
+    };
+    for block in &mut method.basic_blocks {
+        let stmts = mem::take(&mut block.stmts);
+        block.stmts = stmts
+            .into_iter()
+            .map(|stmt| ast::StmtFolder::fold(&mut purifier, stmt))
+            .collect();
+        if let cfg::Successor::GotoSwitch(conditional_targets, _) = &mut block.successor {
+            for (expr, _) in conditional_targets {
+                let guard = mem::replace(expr, true.into());
+                *expr = ast::ExprFolder::fold(&mut purifier, guard);
+            }
+        }
+    }
+    let pure_vars = &purifier.pure_vars;
+    method.local_vars = method.local_vars
+        .into_iter()
+        .map(|var| {
+            if pure_vars.contains(&var) {
+                snapshots.snapshot_var(&var).unwrap()
+            } else {
+                var
+            }
+        })
+        .collect();
+    info!("Purified {} local variables of {} into snapshots", pure_vars.len(), method.name());
+    method
+}
+
+/// Collects the variables of a type with a snapshot domain that cannot be
+/// purified.
+struct SnapshotVarCollector<'a> {
+    snapshots: &'a SnapshotDomains,
+    /// The variables of a type with a snapshot domain.
+    candidates: HashSet<ast::LocalVar>,
+    /// Candidates that cannot be purified.
+    impure_vars: HashSet<ast::LocalVar>,
+    /// Candidates that are havocked or assigned through their fields.
+    assigned_vars: HashSet<ast::LocalVar>,
+    /// Candidates that are read in old expressions.
+    old_vars: HashSet<ast::LocalVar>,
+    /// Are we in an expression in which no candidate can be purified, e.g.
+    /// a place that is borrowed?
+    is_impure_context: bool,
+    /// Are we in an old expression?
+    is_old_context: bool,
+}
+
+impl<'a> SnapshotVarCollector<'a> {
+    fn candidate_root(&self, expr: &ast::Expr) -> Option<ast::LocalVar> {
+        place_root(expr)
+            .filter(|var| self.candidates.contains(*var))
+            .cloned()
+    }
+    fn mark_read(&mut self, var: ast::LocalVar) {
+        if self.is_old_context {
+            self.old_vars.insert(var);
+        }
+    }
+    fn walk_impure(&mut self, expr: &ast::Expr) {
+        let old_impure_context = self.is_impure_context;
+        self.is_impure_context = true;
+        ast::ExprWalker::walk(self, expr);
+        self.is_impure_context = old_impure_context;
+    }
+    fn is_candidate_access(&self, args: &[ast::Expr]) -> bool {
+        args.len() == 1 && self.candidate_root(&args[0]).is_some()
+    }
+}
+
+impl<'a> ast::ExprWalker for SnapshotVarCollector<'a> {
+    fn walk(&mut self, expr: &ast::Expr) {
+        if !self.is_impure_context {
+            if let Some(var) = self.candidate_root(expr) {
+                if matches!(expr, ast::Expr::Field(..))
+                    && self.snapshots.snapshot_of_place(expr).is_some()
+                {
+                    self.mark_read(var);
+                } else {
+                    self.impure_vars.insert(var);
+                }
+                return;
+            }
+            match expr {
+                ast::Expr::PredicateAccessPredicate(_, arg, _, _)
+                | ast::Expr::FieldAccessPredicate(arg, _, _)
+                    if self.candidate_root(arg).is_some() => return,
+                ast::Expr::Unfolding(_, args, body, _, _, _)
+                    if self.is_candidate_access(args) =>
+                {
+                    self.walk(body);
+                    return;
+                }
+                ast::Expr::FuncApp(name, args, _, return_type, _)
+                    if name == SNAP_FUNC_NAME && self.is_candidate_access(args) =>
+                {
+                    let snapshot = self.snapshots.snapshot_of_place(&args[0]);
+                    let var = self.candidate_root(&args[0]).unwrap();
+                    if snapshot.map_or(false, |snapshot| snapshot.get_type() == return_type) {
+                        self.mark_read(var);
+                    } else {
+                        self.impure_vars.insert(var);
+                    }
+                    return;
+                }
+                // Downcasts only guide the fold-unfold algorithm.
+                ast::Expr::Downcast(base, enum_place, _)
+                    if self.candidate_root(enum_place).is_some() =>
+                {
+                    self.walk(base);
+                    return;
+                }
+                ast::Expr::AddrOf(..) => {
+                    self.walk_impure(expr);
+                    return;
+                }
+                ast::Expr::LabelledOld(_, base, _) => {
+                    let old_old_context = self.is_old_context;
+                    self.is_old_context = true;
+                    self.walk(base);
+                    self.is_old_context = old_old_context;
+                    return;
+                }
+                _ => {}
+            }
+        }
+        ast::default_walk_expr(self, expr);
+    }
+    fn walk_local_var(&mut self, local_var: &ast::LocalVar) {
+        if self.candidates.contains(local_var) {
+            self.impure_vars.insert(local_var.clone());
+        }
+    }
+}
+
+impl<'a> ast::StmtWalker for SnapshotVarCollector<'a> {
+    fn walk_expr(&mut self, expr: &ast::Expr) {
+        ast::ExprWalker::walk(self, expr);
+    }
+    fn walk_local_var(&mut self, local_var: &ast::LocalVar) {
+        ast::ExprWalker::walk_local_var(self, local_var);
+    }
+    fn walk_method_call(
+        &mut self,
+        method_name: &str,
+        args: &Vec<ast::Expr>,
+        targets: &Vec<ast::LocalVar>,
+    ) {
+        for arg in args {
+            self.walk_expr(arg);
+        }
+        for target in targets {
+            if is_purifiable_method(method_name) && self.candidates.contains(target) {
+                self.assigned_vars.insert(target.clone());
+            } else {
+                ast::StmtWalker::walk_local_var(self, target);
+            }
+        }
+    }
+    fn walk_assign(&mut self, target: &ast::Expr, expr: &ast::Expr, kind: &ast::AssignKind) {
+        if let Some(var) = self.candidate_root(target) {
+            let is_field_assign = *kind == ast::AssignKind::Copy
+                && self.snapshots.snapshot_after_assign(target, true.into()).is_some();
+            if is_field_assign {
+                self.assigned_vars.insert(var);
+            } else {
+                self.impure_vars.insert(var);
+            }
+        } else {
+            self.walk_expr(target);
+        }
+        match kind {
+            ast::AssignKind::Copy | ast::AssignKind::Ghost => self.walk_expr(expr),
+            // The permissions of the place are moved or borrowed.
+            _ => self.walk_impure(expr),
+        }
+    }
+    fn walk_fold(
+        &mut self,
+        _predicate_name: &str,
+        args: &Vec<ast::Expr>,
+        _perm: &ast::PermAmount,
+        _variant: &ast::MaybeEnumVariantIndex,
+        _pos: &ast::Position,
+    ) {
+        if !self.is_candidate_access(args) {
+            for arg in args {
+                self.walk_expr(arg);
+            }
+        }
+    }
+    fn walk_unfold(
+        &mut self,
+        _predicate_name: &str,
+        args: &Vec<ast::Expr>,
+        _perm: &ast::PermAmount,
+        _variant: &ast::MaybeEnumVariantIndex,
+    ) {
+        if !self.is_candidate_access(args) {
+            for arg in args {
+                self.walk_expr(arg);
+            }
+        }
+    }
+    fn walk_transfer_perm(&mut self, from: &ast::Expr, to: &ast::Expr, _unchecked: &bool) {
+        self.walk_impure(from);
+        self.walk_impure(to);
+    }
+    fn walk_package_magic_wand(
+        &mut self,
+        wand: &ast::Expr,
+        body: &Vec<ast::Stmt>,
+        _label: &str,
+        vars: &[ast::LocalVar],
+        _pos: &ast::Position,
+    ) {
+        let old_impure_context = self.is_impure_context;
+        self.is_impure_context = true;
+        self.walk_expr(wand);
+        for var in vars {
+            ast::StmtWalker::walk_local_var(self, var);
+        }
+        for stmt in body {
+            ast::StmtWalker::walk(self, stmt);
+        }
+        self.is_impure_context = old_impure_context;
+    }
+    fn walk_apply_magic_wand(&mut self, wand: &ast::Expr, _pos: &ast::Position) {
+        self.walk_impure(wand);
+    }
+    fn walk_downcast(&mut self, expr: &ast::Expr, _field: &ast::Field) {
+        if self.candidate_root(expr).is_none() {
+            self.walk_expr(expr);
+        }
+    }
+}
+
+/// Replaces the purifiable variables of a type with a snapshot domain with
+/// their snapshots.
+struct SnapshotVarPurifier<'a> {
+    snapshots: &'a SnapshotDomains,
+    pure_vars: HashSet<ast::LocalVar>,
+}
+
+impl<'a> SnapshotVarPurifier<'a> {
+    fn is_pure_place(&self, expr: &ast::Expr) -> bool {
+        place_root(expr).map_or(false, |var| self.pure_vars.contains(var))
+    }
+    fn is_pure_access(&self, args: &[ast::Expr]) -> bool {
+        args.len() == 1 && self.is_pure_place(&args[0])
+    }
+    fn get_snapshot(&self, place: &ast::Expr) -> ast::Expr {
+        self.snapshots
+            .snapshot_of_place(place)
+            .unwrap_or_else(|| panic!("place: {}", place))
+    }
+}
+
+impl<'a> ast::ExprFolder for SnapshotVarPurifier<'a> {
+    fn fold(&mut self, expr: ast::Expr) -> ast::Expr {
+        if self.is_pure_place(&expr) {
+            return self.get_snapshot(&expr);
+        }
+        match expr {
+            ast::Expr::PredicateAccessPredicate(_, box ref arg, _, _)
+            | ast::Expr::FieldAccessPredicate(box ref arg, _, _)
+                if self.is_pure_place(arg) => true.into(),
+            ast::Expr::Unfolding(_, ref args, _, _, _, _) if self.is_pure_access(args) => {
+                force_matches!(expr, ast::Expr::Unfolding(_, _, box body, _, _, _) => {
+                    self.fold(body)
+                })
+            }
+            ast::Expr::FuncApp(ref name, ref args, _, _, _)
+                if name == SNAP_FUNC_NAME && self.is_pure_access(args) =>
+            {
+                self.get_snapshot(&args[0])
+            }
+            ast::Expr::Downcast(_, box ref enum_place, _) if self.is_pure_place(enum_place) => {
+                force_matches!(expr, ast::Expr::Downcast(box base, _, _) => {
+                    self.fold(base)
+                })
+            }
+            _ => ast::default_fold_expr(self, expr),
+        }
+    }
+}
+
+impl<'a> ast::StmtFolder for SnapshotVarPurifier<'a> {
+    fn fold_expr(&mut self, e: ast::Expr) -> ast::Expr {
+        ast::ExprFolder::fold(self, e)
+    }
+
+    fn fold_method_call(
+        &mut self,
+        name: String,
+        args: Vec<ast::Expr>,
+        targets: Vec<ast::LocalVar>,
+    ) -> ast::Stmt {
+        if targets.len() == 1 && self.pure_vars.contains(&targets[0]) {
+            let target = self.snapshots.snapshot_var(&targets[0]).unwrap();
+            return ast::Stmt::MethodCall(
+                snapshot_havoc_method_name(&target.typ),
+                vec![],
+                vec![target],
+            );
+        }
+        ast::Stmt::MethodCall(
+            name,
+            args.into_iter().map(|e| self.fold_expr(e)).collect(),
+            targets,
+        )
+    }
+
+    fn fold_assign(&mut self, target: ast::Expr, expr: ast::Expr, kind: ast::AssignKind) -> ast::Stmt {
+        if self.is_pure_place(&target) {
+            let var = self.snapshots.snapshot_var(place_root(&target).unwrap()).unwrap();
+            let value = self.fold_expr(expr);
+            let snapshot = self.snapshots
+                .snapshot_after_assign(&target, value)
+                .unwrap_or_else(|| panic!("target: {}", target));
+            ast::Stmt::Assign(ast::Expr::local(var), snapshot, kind)
+        } else {
+            ast::Stmt::Assign(self.fold_expr(target), self.fold_expr(expr), kind)
+        }
+    }
+
+    fn fold_fold(
+        &mut self,
+        predicate_name: String,
+        args: Vec<ast::Expr>,
+        perm_amount: ast::PermAmount,
+        variant: ast::MaybeEnumVariantIndex,
+        pos: ast::Position,
+    ) -> ast::Stmt {
+        if self.is_pure_access(&args) {
+            ast::Stmt::comment(format!("purified: fold {}({})", predicate_name, args[0]))
+        } else {
+            ast::Stmt::Fold(
+                predicate_name,
+                args.into_iter().map(|e| self.fold_expr(e)).collect(),
+                perm_amount,
+                variant,
+                pos,
+            )
+        }
+    }
+
+    fn fold_unfold(
+        &mut self,
+        predicate_name: String,
+        args: Vec<ast::Expr>,
+        perm_amount: ast::PermAmount,
+        variant: ast::MaybeEnumVariantIndex,
+    ) -> ast::Stmt {
+        if self.is_pure_access(&args) {
+            ast::Stmt::comment(format!("purified: unfold {}({})", predicate_name, args[0]))
+        } else {
+            ast::Stmt::Unfold(
+                predicate_name,
+                args.into_iter().map(|e| self.fold_expr(e)).collect(),
+                perm_amount,
+                variant,
+            )
+        }
+    }
+
+    fn fold_downcast(&mut self, expr: ast::Expr, field: ast::Field) -> ast::Stmt {
+        if self.is_pure_place(&expr) {
+            ast::Stmt::comment(format!("purified: downcast {} to {}", expr, field))
+        } else {
+            ast::Stmt::Downcast(self.fold_expr(expr), field)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point_domains() -> SnapshotDomains {
+        let snapshot_type = ast::Type::Snapshot("Point".to_string());
+        let field_function = |name: &str| ast::DomainFunc {
+            name: format!("Snap$Point$0$field${}", name),
+            formal_args: vec![ast::LocalVar::new("self", snapshot_type.clone())],
+            return_type: ast::Type::Int,
+            unique: false,
+            domain_name: "Snap$Point".to_string(),
+        };
+        let domain = ast::Domain {
+            name: "Snap$Point".to_string(),
+            functions: vec![
+                ast::DomainFunc {
+                    name: "cons$0$".to_string(),
+                    formal_args: vec![
+                        ast::LocalVar::new("_0", ast::Type::Int),
+                        ast::LocalVar::new("_1", ast::Type::Int),
+                    ],
+                    return_type: snapshot_type.clone(),
+                    unique: false,
+                    domain_name: "Snap$Point".to_string(),
+                },
+                field_function("f$x"),
+                field_function("f$y"),
+            ],
+            axioms: vec![],
+            type_vars: vec![],
+        };
+        SnapshotDomains::new(&[domain], &[])
+    }
+
+    fn method_with_block(local_vars: Vec<ast::LocalVar>, stmts: Vec<ast::Stmt>) -> cfg::CfgMethod {
+        let mut method = cfg::CfgMethod::new("test".to_string(), 0, vec![], local_vars, vec![]);
+        let block = method.add_block("start", stmts);
+        method.set_successor(block, cfg::Successor::Return);
+        method
+    }
+
+    fn field_value(var: &ast::LocalVar, name: &str) -> ast::Expr {
+        ast::Expr::local(var.clone())
+            .field(ast::Field::new(name, ast::Type::TypedRef("i32".to_string())))
+            .field(ast::Field::new("val_int", ast::Type::Int))
+    }
+
+    fn initialize_point(point: &ast::LocalVar) -> Vec<ast::Stmt> {
+        vec![
+            ast::Stmt::MethodCall("builtin$havoc_ref".to_string(), vec![], vec![point.clone()]),
+            ast::Stmt::Inhale(ast::Expr::predicate_access_predicate(
+                "Point",
+                ast::Expr::local(point.clone()),
+                ast::PermAmount::Write,
+            )),
+            ast::Stmt::Unfold(
+                "Point".to_string(),
+                vec![ast::Expr::local(point.clone())],
+                ast::PermAmount::Write,
+                None,
+            ),
+            ast::Stmt::Assign(field_value(point, "f$x"), ast::Expr::from(5), ast::AssignKind::Copy),
+        ]
+    }
+
+    #[test]
+    fn purify_a_struct_variable() {
+        let snapshots = point_domains();
+        let point = ast::LocalVar::new("_1", ast::Type::TypedRef("Point".to_string()));
+        let mut stmts = initialize_point(&point);
+        stmts.push(ast::Stmt::Assert(
+            ast::Expr::eq_cmp(field_value(&point, "f$y"), ast::Expr::from(2)),
+            ast::Position::default(),
+        ));
+
+        let method = purify_snapshot_vars(method_with_block(vec![point], stmts), &snapshots);
+
+        let snapshot = ast::LocalVar::new("_1", ast::Type::Snapshot("Point".to_string()));
+        assert_eq!(method.local_vars, vec![snapshot.clone()]);
+        let domain = &snapshots.domains["Point"];
+        let (constructor, fields) = &domain.variants[0];
+        let field_y = fields[1].1.apply(vec![ast::Expr::local(snapshot.clone())]);
+        let stmts = &method.basic_blocks[0].stmts;
+        assert_eq!(stmts[0], ast::Stmt::MethodCall(
+            "builtin$havoc_snap$Point".to_string(),
+            vec![],
+            vec![snapshot.clone()],
+        ));
+        assert_eq!(stmts[1], ast::Stmt::Inhale(true.into()));
+        assert!(matches!(stmts[2], ast::Stmt::Comment(_)));
+        assert_eq!(stmts[3], ast::Stmt::Assign(
+            ast::Expr::local(snapshot),
+            constructor.apply(vec![ast::Expr::from(5), field_y.clone()]),
+            ast::AssignKind::Copy,
+        ));
+        assert_eq!(stmts[4], ast::Stmt::Assert(
+            ast::Expr::eq_cmp(field_y, ast::Expr::from(2)),
+            ast::Position::default(),
+        ));
+        let havoc_methods = snapshot_havoc_methods(&[method]);
+        assert_eq!(havoc_methods.len(), 1);
+        assert_eq!(havoc_methods[0].name, "builtin$havoc_snap$Point");
+    }
+
+    #[test]
+    fn keep_a_moved_struct_variable() {
+        let snapshots = point_domains();
+        let point = ast::LocalVar::new("_1", ast::Type::TypedRef("Point".to_string()));
+        let target = ast::LocalVar::new("_2", ast::Type::TypedRef("i32".to_string()));
+        let mut stmts = initialize_point(&point);
+        stmts.push(ast::Stmt::Assign(
+            ast::Expr::local(target.clone()),
+            ast::Expr::local(point.clone())
+                .field(ast::Field::new("f$x", ast::Type::TypedRef("i32".to_string()))),
+            ast::AssignKind::Move,
+        ));
+        let method = method_with_block(vec![point, target], stmts);
+
+        let purified_method = purify_snapshot_vars(method.clone(), &snapshots);
+
+        assert_eq!(purified_method.local_vars, method.local_vars);
+        assert_eq!(purified_method.basic_blocks[0].stmts, method.basic_blocks[0].stmts);
+    }
+}
@@ -94,9 +94,18 @@ pub fn optimize_program(p: Program, source_file_name: &str) -> Program {
             true
         );
     }
+    let method_optimizations = methods::selected_method_optimizations();
+    info!(
+        "Enabled method optimisations: {:?}",
+        method_optimizations.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+    );
+    let snapshots = methods::SnapshotDomains::new(&program.domains, &program.viper_predicates);
     program.methods = program.methods.into_iter().map(|method| {
-        methods::optimize_method_encoding(method, source_file_name, &optimizations)
+        methods::optimize_method_encoding(method, source_file_name, &method_optimizations, &snapshots)
     }).collect();
+    // The variables purified into snapshots are havocked with methods that
+    // the encoder did not declare.
+    program.builtin_methods.extend(methods::snapshot_havoc_methods(&program.methods));
     if optimizations.delete_unused_predicates {
         program.viper_predicates = predicates::delete_unused_predicates(
             &program.methods,
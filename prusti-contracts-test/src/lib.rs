@@ -19,3 +19,8 @@ pub fn test4() {
         body_invariant!(true)
     }
 }
+
+pub fn test5() {
+    prusti_assert!(true);
+    prusti_assume!(true);
+}
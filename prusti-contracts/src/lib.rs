@@ -20,9 +20,40 @@ mod private {
     /// A macro for marking a function as trusted.
     pub use prusti_contracts_impl::trusted;
 
+    /// A macro for marking a function as pure without checking that its body
+    /// is pure, e.g. because it memoizes its results.
+    pub use prusti_contracts_impl::pure_memoized;
+
+    /// A macro for marking a function as terminating.
+    pub use prusti_contracts_impl::terminates;
+
+    /// A macro for writing a termination measure of a pure function.
+    pub use prusti_contracts_impl::decreases;
+
+    /// A macro for setting the resource limit of the SMT solver for the
+    /// verification of a function.
+    pub use prusti_contracts_impl::smt_rlimit;
+
     /// A macro for writing a loop body invariant.
     pub use prusti_contracts_impl::body_invariant;
 
+    /// A macro for writing a termination measure of a loop.
+    pub use prusti_contracts_impl::body_variant;
+
+    /// A macro for asserting a specification in the body of a function.
+    pub use prusti_contracts_impl::prusti_assert;
+
+    /// A macro for assuming a specification in the body of a function,
+    /// without checking it.
+    pub use prusti_contracts_impl::prusti_assume;
+
+    /// A macro for writing ghost code, which is erased when compiling without
+    /// Prusti.
+    pub use prusti_contracts_impl::ghost;
+
+    /// A macro for writing an invariant on a static, a constant or a type.
+    pub use prusti_contracts_impl::invariant;
+
     /// A macro for defining a closure with a specification.
     pub use prusti_contracts_impl::closure;
 
@@ -32,6 +63,14 @@ mod private {
     /// A macro for specifying external functions.
     pub use prusti_contracts_impl::extern_spec;
 
+    /// A macro for refining the specification of an instantiation of a
+    /// generic function.
+    pub use prusti_contracts_impl::refine_spec;
+
+    /// A macro for excluding an item, and the items that it contains, from
+    /// the verification.
+    pub use prusti_contracts_impl::skip_verification;
+
     /// A macro for defining a predicate using prusti expression syntax instead
     /// of just Rust expressions.
     pub use prusti_contracts_impl::predicate;
@@ -57,9 +96,40 @@ mod private {
     /// A macro for marking a function as trusted.
     pub use prusti_contracts_internal::trusted;
 
+    /// A macro for marking a function as pure without checking that its body
+    /// is pure, e.g. because it memoizes its results.
+    pub use prusti_contracts_internal::pure_memoized;
+
+    /// A macro for marking a function as terminating.
+    pub use prusti_contracts_internal::terminates;
+
+    /// A macro for writing a termination measure of a pure function.
+    pub use prusti_contracts_internal::decreases;
+
+    /// A macro for setting the resource limit of the SMT solver for the
+    /// verification of a function.
+    pub use prusti_contracts_internal::smt_rlimit;
+
     /// A macro for writing a loop body invariant.
     pub use prusti_contracts_internal::body_invariant;
 
+    /// A macro for writing a termination measure of a loop.
+    pub use prusti_contracts_internal::body_variant;
+
+    /// A macro for asserting a specification in the body of a function.
+    pub use prusti_contracts_internal::prusti_assert;
+
+    /// A macro for assuming a specification in the body of a function,
+    /// without checking it.
+    pub use prusti_contracts_internal::prusti_assume;
+
+    /// A macro for writing ghost code, which is erased when compiling without
+    /// Prusti.
+    pub use prusti_contracts_internal::ghost;
+
+    /// A macro for writing an invariant on a static, a constant or a type.
+    pub use prusti_contracts_internal::invariant;
+
     /// A macro for defining a closure with a specification.
     pub use prusti_contracts_internal::closure;
 
@@ -69,6 +139,14 @@ mod private {
     /// A macro for specifying external functions.
     pub use prusti_contracts_internal::extern_spec;
 
+    /// A macro for refining the specification of an instantiation of a
+    /// generic function.
+    pub use prusti_contracts_internal::refine_spec;
+
+    /// A macro for excluding an item, and the items that it contains, from
+    /// the verification.
+    pub use prusti_contracts_internal::skip_verification;
+
     /// A macro for defining a predicate using prusti expression syntax instead
     /// of just Rust expressions.
     pub use prusti_contracts_internal::predicate;
@@ -87,4 +165,11 @@ pub fn old<T>(arg: T) -> T {
     arg
 }
 
+/// This function is used to compare two values structurally, by comparing
+/// their snapshots. It is what the `===` and `!==` operators of
+/// specifications are translated to.
+pub fn snapshot_equality<T: ?Sized>(_l: &T, _r: &T) -> bool {
+    true
+}
+
 pub use private::*;
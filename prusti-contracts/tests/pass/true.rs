@@ -25,6 +25,15 @@ fn test4() {
     }
 }
 
+fn test5() {
+    let mut i = 2;
+    while i > 0 {
+        body_invariant!(i > 0);
+        body_variant!(i);
+        i -= 1;
+    }
+}
+
 predicate! {
     fn pred_ok() -> bool {
         true
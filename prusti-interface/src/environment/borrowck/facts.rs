@@ -10,7 +10,6 @@ use polonius_engine::FactTypes;
 use rustc_mir::consumers::{RustcFacts, LocationTable, RichLocation};
 use rustc_middle::mir;
 use std::rc::Rc;
-use std::cell::RefCell;
 
 pub type Region = <RustcFacts as FactTypes>::Origin;
 pub type Loan = <RustcFacts as FactTypes>::Loan;
@@ -33,13 +32,16 @@ impl LocationTableExt for LocationTable {
     }
 }
 
+/// The borrow checker facts of a procedure. They are computed once by the
+/// compiler and can be read any number of times, for example to build the
+/// Polonius information both for the encoding and for the debug dumps.
 pub struct BorrowckFacts {
     /// Polonius input facts.
-    pub input_facts: RefCell<Option<AllInputFacts>>,
+    pub input_facts: AllInputFacts,
     /// Polonius output facts.
     pub output_facts: Rc<AllOutputFacts>,
     /// The table that maps Polonius points to locations in the table.
-    pub location_table: RefCell<Option<LocationTable>>,
+    pub location_table: Rc<LocationTable>,
 }
 
 /// The type of the point. Either the start of a statement or in the
@@ -88,11 +90,11 @@ impl std::fmt::Display for Point {
 }
 
 pub struct Interner {
-    location_table: LocationTable,
+    location_table: Rc<LocationTable>,
 }
 
 impl Interner {
-    pub fn new(location_table: LocationTable) -> Self {
+    pub fn new(location_table: Rc<LocationTable>) -> Self {
         Self { location_table }
     }
 
@@ -0,0 +1,81 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The call graph of the local procedures of the crate.
+
+use crate::data::ProcedureDefId;
+use crate::environment::Environment;
+use log::trace;
+use rustc_middle::{mir, ty};
+use std::collections::{HashMap, HashSet};
+
+/// The direct calls between the local procedures of the crate. Calls of
+/// trait methods are recorded with the `DefId` of the method of the
+/// implementation they resolve to or, if it is not known, e.g. because the
+/// receiver is a type parameter, of the called trait method.
+#[derive(Debug, Default)]
+pub struct CallGraph {
+    callees: HashMap<ProcedureDefId, HashSet<ProcedureDefId>>,
+}
+
+impl CallGraph {
+    /// Collects the calls in the MIR bodies of the given procedures. The
+    /// procedures without a local MIR body are ignored.
+    pub fn new(env: &Environment<'_>, procedures: &[ProcedureDefId]) -> Self {
+        let mut callees = HashMap::new();
+        for &caller in procedures {
+            let local_id = match caller.as_local() {
+                Some(local_id) if env.has_local_mir(local_id) => local_id,
+                _ => continue,
+            };
+            let mir = env.local_mir(local_id);
+            let param_env = env.tcx().param_env(caller);
+            let mut caller_callees = HashSet::new();
+            for basic_block in mir.basic_blocks() {
+                if let Some(mir::Terminator {
+                    kind: mir::TerminatorKind::Call { ref func, .. },
+                    ..
+                }) = basic_block.terminator {
+                    if let ty::TyKind::FnDef(callee, substs) = func.ty(&*mir, env.tcx()).kind() {
+                        let callee = match ty::Instance::resolve(env.tcx(), param_env, *callee, *substs) {
+                            Ok(Some(instance)) => instance.def_id(),
+                            _ => *callee,
+                        };
+                        if callee.is_local() {
+                            caller_callees.insert(callee);
+                        }
+                    }
+                }
+            }
+            trace!("Calls of {:?}: {:?}", caller, caller_callees);
+            callees.insert(caller, caller_callees);
+        }
+        CallGraph { callees }
+    }
+
+    /// Whether `to` is reachable from `from` through one or more calls.
+    pub fn reaches(&self, from: ProcedureDefId, to: ProcedureDefId) -> bool {
+        let mut visited = HashSet::new();
+        let mut to_visit = vec![from];
+        while let Some(current) = to_visit.pop() {
+            for &callee in self.callees.get(&current).into_iter().flatten() {
+                if callee == to {
+                    return true;
+                }
+                if visited.insert(callee) {
+                    to_visit.push(callee);
+                }
+            }
+        }
+        false
+    }
+
+    /// Whether the call from `caller` to `callee` belongs to a cycle of the
+    /// call graph, i.e. whether the callee might call back the caller.
+    pub fn is_recursive_call(&self, caller: ProcedureDefId, callee: ProcedureDefId) -> bool {
+        caller == callee || self.reaches(callee, caller)
+    }
+}
@@ -34,6 +34,12 @@ pub unsafe fn store_mir_body<'tcx>(
     });
 }
 
+/// Whether the MIR body of the procedure has been stored, which is not the
+/// case for the bodies that are tainted by type errors.
+pub(super) fn has_mir_body(def_id: LocalDefId) -> bool {
+    SHARED_STATE.with(|state| state.borrow().contains_key(&def_id))
+}
+
 /// # Safety
 ///
 /// See the module level comment.
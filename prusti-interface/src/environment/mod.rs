@@ -12,10 +12,13 @@ use rustc_middle::mir;
 use rustc_hir::hir_id::HirId;
 use rustc_hir::def_id::{DefId, LocalDefId};
 use rustc_middle::ty::{self, TyCtxt, ParamEnv, WithOptConstParam};
+use rustc_middle::ty::fold::{TypeFolder, TypeFoldable};
 use rustc_trait_selection::infer::{TyCtxtInferExt, InferCtxtExt};
 use std::path::PathBuf;
 use std::cell::Ref;
-use rustc_span::{Span, MultiSpan, symbol::Symbol};
+use rustc_span::{Span, MultiSpan, symbol::{sym, Symbol}};
+use rustc_errors::{Applicability, DiagnosticId};
+use rustc_session::config::ErrorOutputType;
 use std::collections::HashSet;
 use log::debug;
 use std::rc::Rc;
@@ -23,6 +26,7 @@ use std::collections::HashMap;
 use std::cell::RefCell;
 
 pub mod borrowck;
+mod call_graph;
 mod collect_prusti_spec_visitor;
 mod collect_closure_defs_visitor;
 mod dump_borrowck_info;
@@ -34,13 +38,19 @@ pub mod mir_utils;
 pub mod place_set;
 pub mod polonius_info;
 mod procedure;
+mod raw_pointers;
+mod union_reads;
+mod unsupported_constructs;
 
 use self::collect_prusti_spec_visitor::CollectPrustiSpecVisitor;
 use self::collect_closure_defs_visitor::CollectClosureDefsVisitor;
 use rustc_hir::intravisit::Visitor;
+pub use self::call_graph::CallGraph;
 pub use self::loops::{PlaceAccess, PlaceAccessKind, ProcedureLoops};
 pub use self::loops_utils::*;
 pub use self::procedure::{BasicBlockIndex, Procedure};
+pub use self::union_reads::InactiveUnionRead;
+pub use self::unsupported_constructs::UnsupportedConstruct;
 use self::borrowck::facts::BorrowckFacts;
 // use config;
 use crate::data::ProcedureDefId;
@@ -128,14 +138,27 @@ impl<'tcx> Environment<'tcx> {
         &self,
         sp: S,
         msg: &str,
+        code: Option<&str>,
         help: &Option<String>,
         notes: &[(String, Option<S>)],
+        suggestion: &Option<(String, Span, String)>,
     ) {
         let mut diagnostic = self.tcx.sess.struct_err(msg);
         diagnostic.set_span(sp);
+        if let Some(code) = code {
+            diagnostic.code(DiagnosticId::Error(code.to_string()));
+        }
         if let Some(help_msg) = help {
             diagnostic.help(help_msg);
         }
+        if let Some((suggestion_msg, suggestion_sp, replacement)) = suggestion {
+            diagnostic.span_suggestion(
+                *suggestion_sp,
+                suggestion_msg,
+                replacement.clone(),
+                Applicability::MachineApplicable,
+            );
+        }
         for (note_msg, opt_note_sp) in notes {
             if let Some(note_sp) = opt_note_sp {
                 diagnostic.span_note(note_sp.clone(), note_msg);
@@ -151,14 +174,27 @@ impl<'tcx> Environment<'tcx> {
         &self,
         sp: S,
         msg: &str,
+        code: Option<&str>,
         help: &Option<String>,
         notes: &[(String, Option<S>)],
+        suggestion: &Option<(String, Span, String)>,
     ) {
         let mut diagnostic = self.tcx.sess.struct_warn(msg);
         diagnostic.set_span(sp);
+        if let Some(code) = code {
+            diagnostic.code(DiagnosticId::Error(code.to_string()));
+        }
         if let Some(help_msg) = help {
             diagnostic.help(help_msg);
         }
+        if let Some((suggestion_msg, suggestion_sp, replacement)) = suggestion {
+            diagnostic.span_suggestion(
+                *suggestion_sp,
+                suggestion_msg,
+                replacement.clone(),
+                Applicability::MachineApplicable,
+            );
+        }
         for (note_msg, opt_note_sp) in notes {
             if let Some(note_sp) = opt_note_sp {
                 diagnostic.span_note(note_sp.clone(), note_msg);
@@ -169,6 +205,21 @@ impl<'tcx> Environment<'tcx> {
         diagnostic.emit();
     }
 
+    /// Emits a note that is not attached to an error or a warning.
+    pub fn span_note_with_notes<S: Into<MultiSpan>>(&self, sp: S, msg: &str, notes: &[&str]) {
+        let mut diagnostic = self.tcx.sess.struct_note_without_error(msg);
+        diagnostic.set_span(sp);
+        for note_msg in notes {
+            diagnostic.note(note_msg);
+        }
+        diagnostic.emit();
+    }
+
+    /// Whether the diagnostics are emitted as JSON, with `--error-format=json`.
+    pub fn has_json_diagnostics(&self) -> bool {
+        matches!(self.tcx.sess.opts.error_format, ErrorOutputType::Json { .. })
+    }
+
     /// Returns true if an error has been emitted
     pub fn has_errors(&self) -> bool {
         self.tcx.sess.has_errors()
@@ -185,9 +236,51 @@ impl<'tcx> Environment<'tcx> {
 
         let mut result: Vec<_> = visitor.get_annotated_procedures();
         result.extend(cl_visitor.get_closure_defs());
+        result.retain(|&def_id| !self.is_test_harness_item(def_id));
         result
     }
 
+    /// Whether the procedure was generated by the test harness of `--test`
+    /// builds: the entry point that runs the tests, or a closure in the
+    /// descriptor of a test. The `#[test]` functions themselves are verified.
+    fn is_test_harness_item(&self, def_id: ProcedureDefId) -> bool {
+        let base_def_id = self.tcx.closure_base_def_id(def_id);
+        self.tcx.has_attr(base_def_id, sym::rustc_main)
+            || self.tcx.has_attr(base_def_id, sym::rustc_test_marker)
+    }
+
+    /// Build the call graph of the given procedures.
+    pub fn build_call_graph(&self, procedures: &[ProcedureDefId]) -> CallGraph {
+        CallGraph::new(self, procedures)
+    }
+
+    /// Find whether the procedure should be verified. It is not the case if
+    /// the procedure, or an item that contains it, is marked with
+    /// `#[prusti::skip_verification]`, or if its path does not match any of
+    /// the patterns of the `VERIFY_ONLY` configuration flag. A closure is
+    /// selected by the path of the function that defines it.
+    pub fn is_selected_for_verification(&self, def_id: ProcedureDefId) -> bool {
+        let mut current_def_id = Some(def_id);
+        while let Some(item_def_id) = current_def_id {
+            if crate::utils::has_skip_verification_attr(self.tcx.get_attrs(item_def_id)) {
+                return false;
+            }
+            current_def_id = self.tcx.parent(item_def_id);
+        }
+
+        let patterns = prusti_common::config::verify_only();
+        if patterns.is_empty() {
+            return true;
+        }
+        let base_def_id = self.tcx.closure_base_def_id(def_id);
+        let path = self.get_absolute_item_name(base_def_id);
+        let crate_path = format!("{}::{}", self.tcx.crate_name(base_def_id.krate), path);
+        patterns.iter().any(|pattern| {
+            crate::utils::path_matches_pattern(&path, pattern)
+                || crate::utils::path_matches_pattern(&crate_path, pattern)
+        })
+    }
+
     /// Find whether the procedure has a particular `prusti::<name>` attribute.
     pub fn has_prusti_attribute(&self, def_id: ProcedureDefId, name: &str) -> bool {
         let tcx = self.tcx();
@@ -231,7 +324,8 @@ impl<'tcx> Environment<'tcx> {
         Procedure::new(self, proc_def_id)
     }
 
-    /// Get the MIR body of a local procedure.
+    /// Get the MIR body of a local procedure. The dereferences of the raw
+    /// pointers whose target is known are replaced by the target.
     pub fn local_mir(&self, def_id: LocalDefId) -> Rc<mir::Body<'tcx>> {
         let mut bodies = self.bodies.borrow_mut();
         if let Some(body) = bodies.get(&def_id) {
@@ -242,11 +336,12 @@ impl<'tcx> Environment<'tcx> {
             let body_with_facts = unsafe {
                 self::mir_storage::retrieve_mir_body(self.tcx, def_id)
             };
-            let body = body_with_facts.body;
+            let mut body = self.reveal_opaque_types(body_with_facts.body);
+            raw_pointers::replace_raw_pointer_derefs(self.tcx, &mut body);
             let facts = BorrowckFacts {
-                input_facts: RefCell::new(Some(body_with_facts.input_facts)),
+                input_facts: body_with_facts.input_facts,
                 output_facts: body_with_facts.output_facts,
-                location_table: RefCell::new(Some(body_with_facts.location_table)),
+                location_table: Rc::new(body_with_facts.location_table),
             };
 
             let mut borrowck_facts = self.borrowck_facts.borrow_mut();
@@ -258,6 +353,12 @@ impl<'tcx> Environment<'tcx> {
         }
     }
 
+    /// Whether the MIR body of a local procedure, with its borrow checker
+    /// facts, is available. It is not when the body has type errors.
+    pub fn has_local_mir(&self, def_id: LocalDefId) -> bool {
+        self.bodies.borrow().contains_key(&def_id) || self::mir_storage::has_mir_body(def_id)
+    }
+
     /// Get Polonius facts of a local procedure.
     pub fn local_mir_borrowck_facts(&self, def_id: LocalDefId) -> Rc<BorrowckFacts> {
         let borrowck_facts = self.borrowck_facts.borrow();
@@ -269,6 +370,44 @@ impl<'tcx> Environment<'tcx> {
         self.tcx().optimized_mir(def_id)
     }
 
+    /// Get the MIR body of a procedure of any crate. Bodies of other crates
+    /// are only available for the specifications imported from them, and for
+    /// crates compiled with `-Zalways-encode-mir`.
+    pub fn mir(&self, def_id: DefId) -> Rc<mir::Body<'tcx>> {
+        if let Some(local_id) = def_id.as_local() {
+            self.local_mir(local_id)
+        } else {
+            Rc::new(self.external_mir(def_id).clone())
+        }
+    }
+
+    /// Replace the opaque types of return-position `impl Trait` by the types
+    /// that they hide, so that their values are encoded like the values of
+    /// any other type. Code outside of the defining function can still only
+    /// use the API of the trait, which the type checker already ensures.
+    pub fn reveal_opaque_types<T: TypeFoldable<'tcx>>(&self, value: T) -> T {
+        struct OpaqueTypeRevealer<'tcx> {
+            tcx: TyCtxt<'tcx>,
+        }
+        impl<'tcx> TypeFolder<'tcx> for OpaqueTypeRevealer<'tcx> {
+            fn tcx(&self) -> TyCtxt<'tcx> {
+                self.tcx
+            }
+            fn fold_ty(&mut self, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+                match *ty.kind() {
+                    // The hidden type of a recursive opaque type is rejected
+                    // by the compiler, so it is left unchanged.
+                    ty::TyKind::Opaque(def_id, substs) => {
+                        self.tcx.try_expand_impl_trait_type(def_id, substs).unwrap_or(ty)
+                    }
+                    _ if ty.has_opaque_types() => ty.super_fold_with(self),
+                    _ => ty,
+                }
+            }
+        }
+        value.fold_with(&mut OpaqueTypeRevealer { tcx: self.tcx })
+    }
+
     /// Get all relevant trait declarations for some type.
     pub fn get_traits_decls_for_type(&self, ty: &ty::Ty<'tcx>) -> HashSet<DefId> {
         let mut res = HashSet::new();
@@ -302,6 +441,18 @@ impl<'tcx> Environment<'tcx> {
         result
     }
 
+    /// Whether the implementation of the trait for the type was derived by
+    /// the compiler, for example with `#[derive(PartialEq)]`.
+    pub fn has_derived_trait_impl(&self, ty: ty::Ty<'tcx>, trait_id: DefId) -> bool {
+        let mut is_derived = false;
+        self.tcx().for_each_relevant_impl(trait_id, ty, |impl_id| {
+            if self.tcx().has_attr(impl_id, sym::automatically_derived) {
+                is_derived = true;
+            }
+        });
+        is_derived
+    }
+
     pub fn type_is_copy(&self, ty: ty::Ty<'tcx>) -> bool {
         let copy_trait = self.tcx.lang_items().copy_trait();
         if let Some(copy_trait_def_id) = copy_trait {
@@ -298,9 +298,9 @@ pub fn graphviz<'tcx>(
     }
 
     let facts = env.local_mir_borrowck_facts(def_id.expect_local());
-    let interner = facts::Interner::new(facts.location_table.take().unwrap());
+    let interner = facts::Interner::new(facts.location_table.clone());
 
-    let borrowck_in_facts = facts.input_facts.take().unwrap();
+    let borrowck_in_facts = facts.input_facts.clone();
     let borrowck_out_facts = Output::compute(&borrowck_in_facts, Algorithm::Naive, true);
 
     use std::io::Write;
@@ -400,6 +400,39 @@ pub struct PoloniusInfo<'a, 'tcx: 'a> {
     pub(crate) loan_conflict_sets: HashMap<facts::Loan, HashSet<facts::Loan>>
 }
 
+/// The regions that are live on entry to `point`.
+fn live_regions_on_entry(
+    output: &facts::AllOutputFacts,
+    point: facts::PointIndex,
+) -> BTreeSet<facts::Region> {
+    output.origin_live_on_entry
+        .get(&point)
+        .map(|regions| regions.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// The loans that are live on entry to `point`.
+fn loans_live_on_entry(
+    output: &facts::AllOutputFacts,
+    point: facts::PointIndex,
+) -> BTreeSet<facts::Loan> {
+    output.loan_live_at
+        .get(&point)
+        .map(|loans| loans.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// The subset relation between regions that holds on entry to `point`.
+fn outlives_on_entry(
+    output: &facts::AllOutputFacts,
+    point: facts::PointIndex,
+) -> BTreeMap<facts::Region, BTreeSet<facts::Region>> {
+    output.subset
+        .get(&point)
+        .cloned()
+        .unwrap_or_default()
+}
+
 /// This creates a new loan for each move of a borrow. Moves occur either due to assignments or
 /// due to function calls. It returns three values, in this order:
 /// - The list loans that were created due to borrows moved by an assignment.
@@ -663,6 +696,57 @@ fn compute_loan_conflict_sets(
     Ok(loan_conflict_sets)
 }
 
+/// Computes the reborrows that go through a nested reference. A loan that
+/// borrows a place whose type contains references, such as `&mut x` with
+/// `x: &mut T`, blocks the loans held by the regions of those references:
+/// the permissions of `*x` can only be given back to their owner after the
+/// borrow of `x` expires.
+///
+/// ```datalog
+/// nested_reborrows(L1, L2) :-
+///     loan_issued_at(_, L1, P),
+///     borrowed_place_region(L1, R),
+///     origin_contains_loan_at(R, P, L2).
+/// ```
+fn compute_nested_reborrows<'tcx>(
+    tcx: ty::TyCtxt<'tcx>,
+    mir: &mir::Body<'tcx>,
+    loan_position: &HashMap<facts::Loan, mir::Location>,
+    borrowck_in_facts: &facts::AllInputFacts,
+    borrowck_out_facts: &facts::AllOutputFacts,
+) -> Vec<(facts::Loan, facts::Loan)> {
+    let mut nested_reborrows = Vec::new();
+    for &(_, loan, point) in &borrowck_in_facts.loan_issued_at {
+        let location = loan_position[&loan];
+        let statement = if let Some(statement) = mir.statement_at(location) {
+            statement
+        } else {
+            continue;
+        };
+        let borrowed_place = match statement.kind {
+            mir::StatementKind::Assign(box (_, mir::Rvalue::Ref(_, _, place))) => place,
+            _ => continue,
+        };
+        let region_map = match borrowck_out_facts.origin_contains_loan_at.get(&point) {
+            Some(region_map) => region_map,
+            None => continue,
+        };
+        let borrowed_ty = borrowed_place.ty(mir, tcx).ty;
+        tcx.for_each_free_region(&borrowed_ty, |region| {
+            if let ty::RegionKind::ReVar(region_vid) = region {
+                if let Some(loans) = region_map.get(region_vid) {
+                    for &blocked_loan in loans {
+                        if blocked_loan != loan {
+                            nested_reborrows.push((loan, blocked_loan));
+                        }
+                    }
+                }
+            }
+        });
+    }
+    nested_reborrows
+}
+
 impl<'a, 'tcx: 'a> PoloniusInfo<'a, 'tcx> {
     pub fn new(
         env: &'a Environment<'tcx>,
@@ -689,8 +773,8 @@ impl<'a, 'tcx: 'a> PoloniusInfo<'a, 'tcx> {
 
         let mut call_magic_wands = HashMap::new();
 
-        let mut all_facts = facts.input_facts.take().unwrap();
-        let interner = facts::Interner::new(facts.location_table.take().unwrap());
+        let mut all_facts = facts.input_facts.clone();
+        let interner = facts::Interner::new(facts.location_table.clone());
 
         let real_edges = RealEdges::new(&mir);
         let loop_info = loops::ProcedureLoops::new(&mir, &real_edges);
@@ -743,12 +827,23 @@ impl<'a, 'tcx: 'a> PoloniusInfo<'a, 'tcx> {
             })
             .collect();
 
-        let additional_facts = AdditionalFacts::new(&all_facts, &output, &incompatible_loans);
+        let nested_reborrows = compute_nested_reborrows(
+            tcx, mir, &loan_position, &all_facts, &output
+        );
+        let nested_reborrows_without_back_edges = compute_nested_reborrows(
+            tcx, mir, &loan_position, &all_facts_without_back_edges, &output_without_back_edges
+        );
+        let additional_facts = AdditionalFacts::new(
+            &all_facts,
+            &output,
+            &incompatible_loans,
+            &nested_reborrows);
         let additional_facts_without_back_edges =
             AdditionalFacts::new(
                 &all_facts_without_back_edges,
                 &output_without_back_edges,
-                &incompatible_loans);
+                &incompatible_loans,
+                &nested_reborrows_without_back_edges);
         // FIXME: Check whether the new info in Polonius could be used for computing initialization.
         let loan_conflict_sets =
             compute_loan_conflict_sets(procedure, &loan_position, &all_facts, &output)?;
@@ -869,6 +964,30 @@ impl<'a, 'tcx: 'a> PoloniusInfo<'a, 'tcx> {
         self.interner.get_point_index(&point)
     }
 
+    /// Get the regions that are live on entry to the given location.
+    pub fn live_regions_at(&self, location: mir::Location) -> BTreeSet<facts::Region> {
+        let point = self.get_point(location, facts::PointType::Start);
+        live_regions_on_entry(&self.borrowck_out_facts, point)
+    }
+
+    /// Get the loans that are live on entry to the given location. Unlike
+    /// `get_active_loans`, this does not include the loans created by the
+    /// statement at the location.
+    pub fn loans_live_at(&self, location: mir::Location) -> BTreeSet<facts::Loan> {
+        let point = self.get_point(location, facts::PointType::Start);
+        loans_live_on_entry(&self.borrowck_out_facts, point)
+    }
+
+    /// Get the subset relation between regions that holds on entry to the
+    /// given location: each region is mapped to the regions that contain it.
+    pub fn outlives_at(
+        &self,
+        location: mir::Location,
+    ) -> BTreeMap<facts::Region, BTreeSet<facts::Region>> {
+        let point = self.get_point(location, facts::PointType::Start);
+        outlives_on_entry(&self.borrowck_out_facts, point)
+    }
+
     pub fn get_all_loans_kept_alive_by(
         &self,
         point: facts::PointIndex,
@@ -2217,14 +2336,18 @@ impl AdditionalFacts {
     pub fn new(
         all_facts: &facts::AllInputFacts,
         output: &facts::AllOutputFacts,
-        incompatible_loans: &[Vec<facts::Loan>]
+        incompatible_loans: &[Vec<facts::Loan>],
+        nested_reborrows: &[(facts::Loan, facts::Loan)],
     ) -> AdditionalFacts {
         let (zombie_requires, zombie_borrow_live_at, borrow_become_zombie_at) =
             Self::derive_zombie_requires(all_facts, output);
 
         let origin_contains_loan_at = output.origin_contains_loan_at.iter().chain(zombie_requires.iter());
         let loan_issued_ats = all_facts.loan_issued_at.iter();
-        let reborrows = Self::load_reborrows(origin_contains_loan_at, loan_issued_ats, incompatible_loans);
+        let mut reborrows = Self::load_reborrows(origin_contains_loan_at, loan_issued_ats, incompatible_loans);
+        reborrows.extend(nested_reborrows.iter().cloned());
+        reborrows.sort();
+        reborrows.dedup();
 
         let mut reborrows = Self::transitive_closure(reborrows);
 
@@ -2334,3 +2457,80 @@ impl AdditionalFacts {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(index: usize) -> facts::PointIndex {
+        facts::PointIndex::new(index)
+    }
+
+    fn region(index: usize) -> facts::Region {
+        facts::Region::new(index)
+    }
+
+    fn loan(index: usize) -> facts::Loan {
+        facts::Loan::new(index)
+    }
+
+    /// The facts of a straight-line function whose points are numbered in
+    /// the order in which they are executed:
+    ///
+    /// ```ignore
+    /// let r = &mut x;  // 1: the loan 0 is created in the region 1 of `r`
+    /// let s = &mut *r; // 3: the region 1 flows into the region 2 of `s`
+    /// use(s);          // 5: the last use of `s`
+    /// ```
+    fn fixture() -> facts::AllOutputFacts {
+        let r = mir::Local::new(1);
+        let s = mir::Local::new(2);
+        let mut input = facts::AllInputFacts::default();
+        input.cfg_edge = (0..6).map(|index| (point(index), point(index + 1))).collect();
+        input.loan_issued_at = vec![(region(1), loan(0), point(1))];
+        input.var_defined_at = vec![(r, point(1)), (s, point(3))];
+        input.var_used_at = vec![(r, point(3)), (s, point(5))];
+        input.use_of_var_derefs_origin = vec![(r, region(1)), (s, region(2))];
+        input.subset_base = vec![(region(1), region(2), point(3))];
+        Output::compute(&input, Algorithm::Naive, true)
+    }
+
+    #[test]
+    fn test_live_regions_on_entry() {
+        let output = fixture();
+        assert!(live_regions_on_entry(&output, point(1)).is_empty());
+        let expected: BTreeSet<_> = vec![region(1)].into_iter().collect();
+        assert_eq!(live_regions_on_entry(&output, point(2)), expected);
+        assert_eq!(live_regions_on_entry(&output, point(3)), expected);
+        let expected: BTreeSet<_> = vec![region(2)].into_iter().collect();
+        assert_eq!(live_regions_on_entry(&output, point(4)), expected);
+        assert_eq!(live_regions_on_entry(&output, point(5)), expected);
+        assert!(live_regions_on_entry(&output, point(6)).is_empty());
+    }
+
+    #[test]
+    fn test_loans_live_on_entry() {
+        let output = fixture();
+        let expected: BTreeSet<_> = vec![loan(0)].into_iter().collect();
+        // The loan is created at point 1, but its region is only live after.
+        assert!(loans_live_on_entry(&output, point(1)).is_empty());
+        // The loan is kept alive first by `r`, then by its reborrow `s`.
+        for index in 2..=5 {
+            assert_eq!(loans_live_on_entry(&output, point(index)), expected);
+        }
+        assert!(loans_live_on_entry(&output, point(6)).is_empty());
+    }
+
+    #[test]
+    fn test_outlives_on_entry() {
+        let output = fixture();
+        assert!(outlives_on_entry(&output, point(2)).is_empty());
+        let expected: BTreeMap<_, BTreeSet<_>> = vec![
+            (region(1), vec![region(2)].into_iter().collect()),
+        ].into_iter().collect();
+        assert_eq!(outlives_on_entry(&output, point(3)), expected);
+        // The region of `r` is dead after its last use, so the relation is
+        // not propagated.
+        assert!(outlives_on_entry(&output, point(4)).is_empty());
+    }
+}
@@ -16,9 +16,14 @@ use rustc_span::Span;
 use log::{trace, debug};
 use rustc_middle::mir::StatementKind;
 use rustc_hir::def_id;
+use rustc_hir as hir;
+use rustc_hir::intravisit::{Visitor, NestedVisitorMap, walk_expr};
+use rustc_middle::hir::map::Map;
 use std::iter::FromIterator;
 use crate::environment::mir_utils::RealEdges;
 use crate::environment::Environment;
+use crate::environment::union_reads::{find_inactive_union_reads, InactiveUnionRead};
+use crate::environment::unsupported_constructs::{find_unsupported_constructs, UnsupportedConstruct};
 
 /// Index of a Basic Block
 pub type BasicBlockIndex = mir::BasicBlock;
@@ -32,6 +37,8 @@ pub struct Procedure<'tcx> {
     loop_info: loops::ProcedureLoops,
     reachable_basic_blocks: HashSet<BasicBlock>,
     nonspec_basic_blocks: HashSet<BasicBlock>,
+    /// Spec blocks that type-check a `prusti_assert!` or a `prusti_assume!`.
+    body_assertion_blocks: HashSet<BasicBlock>,
 }
 
 impl<'tcx> Procedure<'tcx> {
@@ -40,10 +47,13 @@ impl<'tcx> Procedure<'tcx> {
     pub fn new(env: &Environment<'tcx>, proc_def_id: ProcedureDefId) -> Self {
         trace!("Encoding procedure {:?}", proc_def_id);
         let tcx = env.tcx();
-        let mir = env.local_mir(proc_def_id.expect_local());
+        let mir = env.mir(proc_def_id);
         let real_edges = RealEdges::new(&mir);
         let reachable_basic_blocks = build_reachable_basic_blocks(&mir, &real_edges);
         let nonspec_basic_blocks = build_nonspec_basic_blocks(&mir, &real_edges, &tcx);
+        let body_assertion_blocks = build_body_assertion_blocks(
+            &mir, &real_edges, &nonspec_basic_blocks, &tcx
+        );
         let loop_info = loops::ProcedureLoops::new(&mir, &real_edges);
 
         Self {
@@ -54,6 +64,7 @@ impl<'tcx> Procedure<'tcx> {
             loop_info,
             reachable_basic_blocks,
             nonspec_basic_blocks,
+            body_assertion_blocks,
         }
     }
 
@@ -141,11 +152,33 @@ impl<'tcx> Procedure<'tcx> {
             .collect()
     }
 
+    /// Find the constructs of the procedure body that cannot be encoded
+    pub fn get_unsupported_constructs(&self) -> Vec<UnsupportedConstruct> {
+        find_unsupported_constructs(self)
+    }
+
+    /// Find the reads of union fields that are not the field last written
+    pub fn get_inactive_union_reads(&self) -> Vec<InactiveUnionRead> {
+        find_inactive_union_reads(self)
+    }
+
     /// Check whether the block is used for typechecking the specification
     pub fn is_spec_block(&self, bbi: BasicBlockIndex) -> bool {
         !self.nonspec_basic_blocks.contains(&bbi)
     }
 
+    /// Check whether the block is used for typechecking a `prusti_assert!` or a
+    /// `prusti_assume!`, rather than a loop invariant or a loop variant
+    pub fn is_body_assertion_block(&self, bbi: BasicBlockIndex) -> bool {
+        self.body_assertion_blocks.contains(&bbi)
+    }
+
+    /// Check whether the block is used for typechecking the specification of
+    /// a loop
+    pub fn is_loop_spec_block(&self, bbi: BasicBlockIndex) -> bool {
+        self.is_spec_block(bbi) && !self.is_body_assertion_block(bbi)
+    }
+
     /// Check whether the block is reachable
     pub fn is_reachable_block(&self, bbi: BasicBlockIndex) -> bool {
         self.reachable_basic_blocks.contains(&bbi)
@@ -172,7 +205,9 @@ impl<'tcx> Procedure<'tcx> {
                 let func_proc_name = self.tcx.def_path_str(*def_id);
                 &func_proc_name == "std::rt::begin_panic"
                     || &func_proc_name == "core::panicking::panic"
+                    || &func_proc_name == "core::panicking::panic_str"
                     || &func_proc_name == "core::panicking::panic_fmt"
+                    || &func_proc_name == "core::panicking::assert_failed"
             } else {
                 false
             }
@@ -184,6 +219,52 @@ impl<'tcx> Procedure<'tcx> {
     pub fn successors(&self, bbi: BasicBlockIndex) -> &[BasicBlockIndex] {
         self.real_edges.successors(bbi)
     }
+
+    /// Get the reachable blocks that leave the procedure through an explicit
+    /// `return` expression (including the desugaring of `?`), together with
+    /// the span of the expression.
+    pub fn get_return_site_blocks(&self) -> Vec<(BasicBlockIndex, Span)> {
+        let local_def_id = match self.proc_def_id.as_local() {
+            Some(local_def_id) => local_def_id,
+            None => return vec![],
+        };
+        let hir = self.tcx.hir();
+        let hir_id = hir.local_def_id_to_hir_id(local_def_id);
+        let mut visitor = CollectReturnSpansVisitor { spans: vec![] };
+        visitor.visit_body(hir.body(hir.body_owned_by(hir_id)));
+        self.get_reachable_nonspec_cfg_blocks()
+            .into_iter()
+            .filter_map(|bbi| {
+                let span = self.mir[bbi].terminator().source_info.span;
+                if visitor.spans.contains(&span) {
+                    Some((bbi, span))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Collects the spans of the `return` expressions of a body, without
+/// visiting the bodies of nested closures.
+struct CollectReturnSpansVisitor {
+    spans: Vec<Span>,
+}
+
+impl<'tcx> Visitor<'tcx> for CollectReturnSpansVisitor {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::None
+    }
+
+    fn visit_expr(&mut self, expr: &'tcx hir::Expr<'tcx>) {
+        if let hir::ExprKind::Ret(_) = expr.kind {
+            self.spans.push(expr.span);
+        }
+        walk_expr(self, expr)
+    }
 }
 
 /// Returns the set of basic blocks that are not used as part of the typechecking of Prusti specifications
@@ -232,6 +313,45 @@ fn is_spec_basic_block(bb_data: &BasicBlockData, tcx: &TyCtxt) -> bool {
     return false;
 }
 
+fn is_body_assertion_basic_block(bb_data: &BasicBlockData, tcx: &TyCtxt) -> bool {
+    bb_data.statements.iter().any(|stmt| {
+        if let StatementKind::Assign(box (_, Rvalue::Aggregate(box AggregateKind::Closure(def_id, _), _))) = &stmt.kind {
+            let attrs = tcx.get_attrs(*def_id);
+            crate::utils::has_prusti_attr(attrs, "body_assertion_spec")
+                || crate::utils::has_prusti_attr(attrs, "body_assumption_spec")
+        } else {
+            false
+        }
+    })
+}
+
+/// Returns the spec blocks that type-check a `prusti_assert!` or a
+/// `prusti_assume!`: the blocks creating their closures, and the spec blocks
+/// that lead only to them.
+fn build_body_assertion_blocks(
+    mir: &Mir,
+    real_edges: &RealEdges,
+    nonspec_basic_blocks: &HashSet<BasicBlock>,
+    tcx: &TyCtxt,
+) -> HashSet<BasicBlock> {
+    let mut body_assertion_blocks = HashSet::new();
+    let mut to_visit: Vec<BasicBlock> = mir.basic_blocks()
+        .indices()
+        .filter(|bb| is_body_assertion_basic_block(&mir[*bb], tcx))
+        .collect();
+    while let Some(bb) = to_visit.pop() {
+        if nonspec_basic_blocks.contains(&bb) || !body_assertion_blocks.insert(bb) {
+            continue;
+        }
+        for &pred in real_edges.predecessors(bb) {
+            if real_edges.successors(pred).len() == 1 {
+                to_visit.push(pred);
+            }
+        }
+    }
+    body_assertion_blocks
+}
+
 #[derive(Debug)]
 struct BasicBlockNode {
     successors: HashSet<BasicBlock>,
@@ -0,0 +1,270 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Prusti does not model raw pointers. However, a raw pointer that is assigned
+//! once, to the address of a place, and that is only dereferenced, points to
+//! that place during its whole life. This module replaces the dereferences of
+//! such pointers by the places they point to, and their assignments by `Nop`s,
+//! so that the round trip of a reference through a raw pointer is encoded as
+//! an access to the original place.
+//!
+//! A pointer is only tracked within the body that creates it. The places that
+//! it may point to do not contain indices and do not dereference references
+//! that might be reassigned, so that the address of the place does not change.
+//! The remaining raw pointers are reported as unsupported.
+
+use rustc_middle::mir::{
+    self,
+    visit::{MutVisitor, MutatingUseContext, PlaceContext, Visitor},
+};
+use rustc_middle::ty::{adjustment::PointerCast, TyCtxt};
+use std::collections::HashMap;
+use log::debug;
+
+/// Replace the dereferences of the raw pointers of the body whose target is
+/// known by their target.
+pub fn replace_raw_pointer_derefs<'tcx>(tcx: TyCtxt<'tcx>, body: &mut mir::Body<'tcx>) {
+    if !body.local_decls.iter().any(|decl| decl.ty.is_unsafe_ptr()) {
+        return;
+    }
+    let mut collector = LocalUsesCollector {
+        body: &*body,
+        uses: HashMap::new(),
+    };
+    collector.visit_body(body);
+    let uses = collector.uses;
+
+    // A pointer can be a copy of another pointer that is assigned later in
+    // the order of the map.
+    let mut resolver = TargetResolver { tcx, body, uses: &uses, targets: HashMap::new() };
+    loop {
+        let mut changed = false;
+        for &local in uses.keys() {
+            if resolver.targets.contains_key(&local) || !resolver.is_trackable_pointer(local) {
+                continue;
+            }
+            if let Some(target) = resolver.resolve_definition(uses[&local].definition.unwrap()) {
+                resolver.targets.insert(local, target);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    let targets = resolver.targets;
+
+    for (local, target) in &targets {
+        debug!("The raw pointer {:?} points to {:?}", local, target);
+        let location = uses[local].definition.unwrap();
+        body.basic_blocks_mut()[location.block].statements[location.statement_index].make_nop();
+    }
+    if !targets.is_empty() {
+        DerefReplacer { tcx, targets }.visit_body(body);
+    }
+}
+
+#[derive(Default)]
+struct LocalUses {
+    /// The number of assignments to the local or to a part of it, and of its
+    /// mutable borrows.
+    writes: usize,
+    /// The number of uses of the local, including the writes.
+    uses: usize,
+    /// The last statement that assigns the whole local.
+    definition: Option<mir::Location>,
+    /// Whether a raw pointer is used other than by dereferencing it or by
+    /// copying it to another pointer.
+    escapes: bool,
+}
+
+struct LocalUsesCollector<'a, 'tcx> {
+    body: &'a mir::Body<'tcx>,
+    uses: HashMap<mir::Local, LocalUses>,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for LocalUsesCollector<'a, 'tcx> {
+    fn visit_statement(&mut self, statement: &mir::Statement<'tcx>, location: mir::Location) {
+        match &statement.kind {
+            // Only the borrow checker needs these.
+            mir::StatementKind::FakeRead(..) => {}
+            mir::StatementKind::Assign(box (lhs, rhs)) => {
+                if let Some(lhs_local) = lhs.as_local() {
+                    self.uses.entry(lhs_local).or_default().definition = Some(location);
+                    if let Some(pointer) = copied_pointer(rhs) {
+                        if self.body.local_decls[lhs_local].ty.is_unsafe_ptr() {
+                            let lhs_uses = self.uses.entry(lhs_local).or_default();
+                            lhs_uses.writes += 1;
+                            lhs_uses.uses += 1;
+                            self.uses.entry(pointer).or_default().uses += 1;
+                            return;
+                        }
+                    }
+                }
+                self.super_statement(statement, location);
+            }
+            _ => self.super_statement(statement, location),
+        }
+    }
+
+    fn visit_place(&mut self, place: &mir::Place<'tcx>, context: PlaceContext, _location: mir::Location) {
+        if let PlaceContext::NonUse(_) = context {
+            return;
+        }
+        let is_pointer = self.body.local_decls[place.local].ty.is_unsafe_ptr();
+        let local_uses = self.uses.entry(place.local).or_default();
+        local_uses.uses += 1;
+        if let PlaceContext::MutatingUse(_) = context {
+            if !place.is_indirect() {
+                local_uses.writes += 1;
+            }
+        }
+        if is_pointer
+            && place.projection.first() != Some(&mir::ProjectionElem::Deref)
+            && context != PlaceContext::MutatingUse(MutatingUseContext::Store)
+        {
+            local_uses.escapes = true;
+        }
+    }
+}
+
+/// If the rvalue copies a raw pointer, return the local of the pointer.
+fn copied_pointer(rvalue: &mir::Rvalue<'_>) -> Option<mir::Local> {
+    match rvalue {
+        mir::Rvalue::Use(mir::Operand::Copy(place) | mir::Operand::Move(place))
+        | mir::Rvalue::Cast(
+            mir::CastKind::Pointer(PointerCast::MutToConstPointer),
+            mir::Operand::Copy(place) | mir::Operand::Move(place),
+            _,
+        ) => place.as_local(),
+        _ => None,
+    }
+}
+
+struct TargetResolver<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    body: &'a mir::Body<'tcx>,
+    uses: &'a HashMap<mir::Local, LocalUses>,
+    targets: HashMap<mir::Local, mir::Place<'tcx>>,
+}
+
+impl<'a, 'tcx> TargetResolver<'a, 'tcx> {
+    fn is_argument(&self, local: mir::Local) -> bool {
+        local.index() >= 1 && local.index() <= self.body.arg_count
+    }
+
+    fn is_trackable_pointer(&self, local: mir::Local) -> bool {
+        let local_uses = &self.uses[&local];
+        self.body.local_decls[local].ty.is_unsafe_ptr()
+            && local != mir::RETURN_PLACE
+            && !self.is_argument(local)
+            && local_uses.writes == 1
+            && local_uses.definition.is_some()
+            && !local_uses.escapes
+    }
+
+    /// Whether the local keeps its initial value.
+    fn is_stable(&self, local: mir::Local) -> bool {
+        let writes = self.uses.get(&local).map_or(0, |local_uses| local_uses.writes);
+        if self.is_argument(local) { writes == 0 } else { writes == 1 }
+    }
+
+    fn definition_rvalue(&self, location: mir::Location) -> Option<&'a mir::Rvalue<'tcx>> {
+        let statement = self.body[location.block].statements.get(location.statement_index)?;
+        if let mir::StatementKind::Assign(box (_, rvalue)) = &statement.kind {
+            Some(rvalue)
+        } else {
+            None
+        }
+    }
+
+    /// The place that the pointer assigned at `location` points to.
+    fn resolve_definition(&self, location: mir::Location) -> Option<mir::Place<'tcx>> {
+        let rvalue = self.definition_rvalue(location)?;
+        if let mir::Rvalue::AddressOf(_, place) = rvalue {
+            return self.resolve(place.local, place.projection);
+        }
+        let pointer = copied_pointer(rvalue)?;
+        self.targets.get(&pointer).cloned()
+    }
+
+    /// A place with the same address as `local.projection` that keeps that
+    /// address during the whole body, if there is one.
+    fn resolve(
+        &self,
+        local: mir::Local,
+        projection: &[mir::PlaceElem<'tcx>],
+    ) -> Option<mir::Place<'tcx>> {
+        let rest = match projection.split_first() {
+            Some((mir::ProjectionElem::Deref, rest)) => rest,
+            _ => return self.extend(mir::Place::from(local), projection),
+        };
+        let ty = self.body.local_decls[local].ty;
+        if ty.is_unsafe_ptr() {
+            return self.extend(*self.targets.get(&local)?, rest);
+        }
+        if !(ty.is_region_ptr() || ty.is_box()) || !self.is_stable(local) {
+            return None;
+        }
+        // A reference that is only created to be cast to a pointer, as in
+        // `&mut x as *mut _`, is resolved to the borrowed place, because the
+        // borrow ends before the pointer is used.
+        let local_uses = &self.uses[&local];
+        if !self.is_argument(local) && local_uses.uses == 2 {
+            let definition = local_uses.definition.and_then(|location| self.definition_rvalue(location));
+            if let Some(mir::Rvalue::Ref(_, _, borrowed)) = definition {
+                let borrowed = self.resolve(borrowed.local, borrowed.projection)?;
+                return self.extend(borrowed, rest);
+            }
+        }
+        self.extend(self.tcx.mk_place_deref(mir::Place::from(local)), rest)
+    }
+
+    /// Project `base` with `projection`, if the projection does not move the
+    /// place depending on the values of locals or of references.
+    fn extend(
+        &self,
+        base: mir::Place<'tcx>,
+        projection: &[mir::PlaceElem<'tcx>],
+    ) -> Option<mir::Place<'tcx>> {
+        let is_stable = projection.iter().all(|elem| matches!(
+            elem,
+            mir::ProjectionElem::Field(..) | mir::ProjectionElem::Downcast(..)
+        ));
+        if !is_stable {
+            return None;
+        }
+        let mut elems: Vec<_> = base.projection.iter().collect();
+        elems.extend_from_slice(projection);
+        Some(mir::Place { local: base.local, projection: self.tcx.intern_place_elems(&elems) })
+    }
+}
+
+/// Replaces the dereferences of the tracked pointers by their targets.
+struct DerefReplacer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    targets: HashMap<mir::Local, mir::Place<'tcx>>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for DerefReplacer<'tcx> {
+    fn tcx<'a>(&'a self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_place(&mut self, place: &mut mir::Place<'tcx>, _context: PlaceContext, _location: mir::Location) {
+        if place.projection.first() != Some(&mir::ProjectionElem::Deref) {
+            return;
+        }
+        if let Some(target) = self.targets.get(&place.local) {
+            let mut elems: Vec<_> = target.projection.iter().collect();
+            elems.extend(place.projection.iter().skip(1));
+            *place = mir::Place {
+                local: target.local,
+                projection: self.tcx.intern_place_elems(&elems),
+            };
+        }
+    }
+}
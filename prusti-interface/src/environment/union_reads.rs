@@ -0,0 +1,230 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! An analysis that finds the reads of a field of a union that is not the
+//! field that was last written. The encoder gives the fields of a union that
+//! were not last written arbitrary values, so such a read cannot be verified to
+//! yield anything in particular.
+//!
+//! The analysis only tracks the unions that are not behind a reference, and
+//! only knows the field that was last written when it is the same on all the
+//! paths that reach a read.
+
+use rustc_middle::mir::{self, visit::{MutatingUseContext, PlaceContext, Visitor}};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+use std::collections::HashMap;
+use super::Procedure;
+
+/// A read of a union field that is not the field that was last written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InactiveUnionRead {
+    pub span: Span,
+    /// The name of the field that is read.
+    pub read_field: String,
+    /// The name of the field that was last written.
+    pub written_field: String,
+}
+
+/// The field that was last written of each union.
+type State<'tcx> = HashMap<mir::PlaceRef<'tcx>, mir::Field>;
+
+/// Find the reads of inactive union fields in the reachable basic blocks of
+/// the procedure that do not belong to its specification, in the order of the
+/// source code.
+pub fn find_inactive_union_reads<'tcx>(procedure: &Procedure<'tcx>) -> Vec<InactiveUnionRead> {
+    let mir = procedure.get_mir();
+    let tcx = procedure.get_tcx();
+
+    // The states only shrink once a block has been reached, so this
+    // terminates.
+    let mut entry_states: HashMap<mir::BasicBlock, State<'tcx>> = HashMap::new();
+    entry_states.insert(mir::START_BLOCK, State::new());
+    let mut worklist = vec![mir::START_BLOCK];
+    while let Some(bb) = worklist.pop() {
+        let mut state = entry_states[&bb].clone();
+        apply_block(tcx, mir, bb, &mut state, None);
+        for &successor in mir[bb].terminator().successors() {
+            let changed = if let Some(successor_state) = entry_states.get_mut(&successor) {
+                let len = successor_state.len();
+                successor_state.retain(|place, field| state.get(place) == Some(field));
+                successor_state.len() != len
+            } else {
+                entry_states.insert(successor, state.clone());
+                true
+            };
+            if changed {
+                worklist.push(successor);
+            }
+        }
+    }
+
+    let mut found = vec![];
+    for bb in procedure.get_reachable_nonspec_cfg_blocks() {
+        if let Some(entry_state) = entry_states.get(&bb) {
+            let mut state = entry_state.clone();
+            apply_block(tcx, mir, bb, &mut state, Some(&mut found));
+        }
+    }
+    found.sort_by_key(|read| read.span);
+    found.dedup();
+    found
+}
+
+fn apply_block<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mir: &mir::Body<'tcx>,
+    bb: mir::BasicBlock,
+    state: &mut State<'tcx>,
+    mut found: Option<&mut Vec<InactiveUnionRead>>,
+) {
+    let data = &mir[bb];
+    for (statement_index, statement) in data.statements.iter().enumerate() {
+        let location = mir::Location { block: bb, statement_index };
+        let mut visitor = UnionAccessVisitor::new(tcx, mir, state, found.as_deref_mut());
+        visitor.visit_statement(statement, location);
+        let writes = visitor.writes;
+        for place in writes {
+            kill(state, place);
+        }
+        if let mir::StatementKind::Assign(box (lhs, rhs)) = &statement.kind {
+            gen(tcx, mir, state, lhs.as_ref(), rhs);
+        }
+    }
+    let location = mir::Location { block: bb, statement_index: data.statements.len() };
+    let mut visitor = UnionAccessVisitor::new(tcx, mir, state, found);
+    visitor.visit_terminator(data.terminator(), location);
+    let writes = visitor.writes;
+    for place in writes {
+        kill(state, place);
+    }
+}
+
+/// Forget the fields that were last written of the unions that overlap with
+/// the written place.
+fn kill<'tcx>(state: &mut State<'tcx>, written: mir::PlaceRef<'tcx>) {
+    state.retain(|place, _| {
+        place.local != written.local || !(
+            place.projection.starts_with(written.projection)
+            || written.projection.starts_with(place.projection)
+        )
+    });
+}
+
+/// Record the field written by the assignment, if it writes a union field or a
+/// whole union.
+fn gen<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mir: &mir::Body<'tcx>,
+    state: &mut State<'tcx>,
+    lhs: mir::PlaceRef<'tcx>,
+    rhs: &mir::Rvalue<'tcx>,
+) {
+    if lhs.projection.contains(&mir::ProjectionElem::Deref) {
+        return;
+    }
+    if let Some((base, field)) = union_field(tcx, mir, lhs) {
+        state.insert(base, field);
+        return;
+    }
+    match rhs {
+        mir::Rvalue::Aggregate(box mir::AggregateKind::Adt(adt_def, _, _, _, Some(active_field)), _)
+            if adt_def.is_union() =>
+        {
+            state.insert(lhs, mir::Field::from_usize(*active_field));
+        }
+        mir::Rvalue::Use(mir::Operand::Copy(src) | mir::Operand::Move(src)) => {
+            if let Some(&field) = state.get(&src.as_ref()) {
+                state.insert(lhs, field);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// If the place is a field of a union that is not behind a reference, return
+/// the union and the field.
+fn union_field<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mir: &mir::Body<'tcx>,
+    place: mir::PlaceRef<'tcx>,
+) -> Option<(mir::PlaceRef<'tcx>, mir::Field)> {
+    match place.last_projection() {
+        Some((base, mir::ProjectionElem::Field(field, _)))
+            if !base.projection.contains(&mir::ProjectionElem::Deref)
+                && base.ty(mir, tcx).ty.ty_adt_def().map_or(false, |adt_def| adt_def.is_union()) =>
+        {
+            Some((base, field))
+        }
+        _ => None,
+    }
+}
+
+/// Collects the places written by a statement or terminator, and checks the
+/// places that it reads against the state.
+struct UnionAccessVisitor<'a, 'b, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    mir: &'a mir::Body<'tcx>,
+    state: &'a State<'tcx>,
+    found: Option<&'b mut Vec<InactiveUnionRead>>,
+    writes: Vec<mir::PlaceRef<'tcx>>,
+}
+
+impl<'a, 'b, 'tcx> UnionAccessVisitor<'a, 'b, 'tcx> {
+    fn new(
+        tcx: TyCtxt<'tcx>,
+        mir: &'a mir::Body<'tcx>,
+        state: &'a State<'tcx>,
+        found: Option<&'b mut Vec<InactiveUnionRead>>,
+    ) -> Self {
+        UnionAccessVisitor { tcx, mir, state, found, writes: vec![] }
+    }
+
+    fn check_read(&mut self, place: &mir::Place<'tcx>, location: mir::Location) {
+        let found = if let Some(found) = self.found.as_mut() {
+            found
+        } else {
+            return;
+        };
+        for (base, elem) in place.iter_projections() {
+            let read_field = if let mir::ProjectionElem::Field(field, _) = elem {
+                field
+            } else {
+                continue;
+            };
+            let written_field = match self.state.get(&base) {
+                Some(&written_field) if written_field != read_field => written_field,
+                _ => continue,
+            };
+            let adt_def = base.ty(self.mir, self.tcx).ty.ty_adt_def().unwrap();
+            let fields = &adt_def.non_enum_variant().fields;
+            found.push(InactiveUnionRead {
+                span: self.mir.source_info(location).span,
+                read_field: fields[read_field.index()].ident.to_string(),
+                written_field: fields[written_field.index()].ident.to_string(),
+            });
+        }
+    }
+}
+
+impl<'a, 'b, 'tcx> Visitor<'tcx> for UnionAccessVisitor<'a, 'b, 'tcx> {
+    fn visit_place(&mut self, place: &mir::Place<'tcx>, context: PlaceContext, location: mir::Location) {
+        match context {
+            PlaceContext::NonMutatingUse(_) => self.check_read(place, location),
+            PlaceContext::MutatingUse(MutatingUseContext::Projection) => {}
+            PlaceContext::MutatingUse(_) => self.writes.push(place.as_ref()),
+            PlaceContext::NonUse(_) => {}
+        }
+    }
+
+    fn visit_local(&mut self, local: &mir::Local, context: PlaceContext, _location: mir::Location) {
+        if let PlaceContext::NonUse(_) = context {
+            // `StorageLive` and `StorageDead` end the life of the previous
+            // value of the local.
+            self.writes.push(mir::PlaceRef { local: *local, projection: &[] });
+        }
+    }
+}
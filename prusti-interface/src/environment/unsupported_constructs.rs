@@ -0,0 +1,223 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! An analysis that finds the constructs of a procedure body that the encoder
+//! does not support, so that they can all be reported before the encoding of
+//! the procedure starts, instead of failing in the middle of it.
+
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::{self, visit::{MutatingUseContext, PlaceContext, Visitor}};
+use rustc_middle::ty::{self, adjustment::PointerCast, TyCtxt};
+use rustc_span::Span;
+use super::Procedure;
+
+/// The reason of the uses of raw pointers that have no more precise reason.
+const RAW_POINTERS: &str = "raw pointers are not supported";
+
+/// The methods of raw pointers that compute another pointer.
+const POINTER_ARITHMETIC_METHODS: &[&str] = &[
+    "offset", "add", "sub", "wrapping_offset", "wrapping_add", "wrapping_sub",
+    "offset_from", "align_offset",
+];
+
+/// A construct of a procedure body that cannot be encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedConstruct {
+    pub span: Span,
+    /// Why the construct cannot be encoded, e.g. "raw pointers are not
+    /// supported".
+    pub reason: &'static str,
+}
+
+/// Find the unsupported constructs in the reachable basic blocks of the
+/// procedure that do not belong to its specification, in the order of the
+/// source code.
+pub fn find_unsupported_constructs<'tcx>(procedure: &Procedure<'tcx>) -> Vec<UnsupportedConstruct> {
+    let mir = procedure.get_mir();
+    let mut visitor = UnsupportedConstructsVisitor {
+        tcx: procedure.get_tcx(),
+        mir,
+        found: vec![],
+    };
+    for bbi in procedure.get_reachable_nonspec_cfg_blocks() {
+        visitor.visit_basic_block_data(bbi, &mir[bbi]);
+    }
+    let mut found = visitor.found;
+    found.sort_by_key(|construct| construct.span);
+    found.dedup();
+    let precise_spans: Vec<Span> = found.iter()
+        .filter(|construct| construct.reason != RAW_POINTERS)
+        .map(|construct| construct.span)
+        .collect();
+    found.retain(|construct| {
+        construct.reason != RAW_POINTERS || !precise_spans.contains(&construct.span)
+    });
+    found
+}
+
+struct UnsupportedConstructsVisitor<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    mir: &'a mir::Body<'tcx>,
+    found: Vec<UnsupportedConstruct>,
+}
+
+impl<'a, 'tcx> UnsupportedConstructsVisitor<'a, 'tcx> {
+    fn report(&mut self, location: mir::Location, reason: &'static str) {
+        let span = self.mir.source_info(location).span;
+        self.found.push(UnsupportedConstruct { span, reason });
+    }
+
+    fn is_argument(&self, local: mir::Local) -> bool {
+        local.index() >= 1 && local.index() <= self.mir.arg_count
+    }
+
+    fn is_pointer_arithmetic(&self, def_id: DefId) -> bool {
+        let is_pointer_method = self.tcx.impl_of_method(def_id)
+            .map_or(false, |impl_id| self.tcx.type_of(impl_id).is_unsafe_ptr());
+        is_pointer_method
+            && POINTER_ARITHMETIC_METHODS.contains(&&*self.tcx.item_name(def_id).as_str())
+    }
+
+    /// Classify the unsizing of a pointer. Only the unsizing of a reference
+    /// to an array into a slice is supported. The unsizing of closures is left
+    /// to the encoder, which checks that their contract is used.
+    fn check_unsize(&mut self, operand: &mir::Operand<'tcx>, target_ty: ty::Ty<'tcx>, location: mir::Location) {
+        let target = target_ty.builtin_deref(true).map(|type_and_mut| type_and_mut.ty);
+        if let (ty::TyKind::Ref(..), Some(target)) = (target_ty.kind(), target) {
+            if let ty::TyKind::Slice(..) = target.kind() {
+                return;
+            }
+        }
+        let operand_ty = operand.ty(self.mir, self.tcx);
+        if operand_ty.walk().any(|arg| matches!(
+            arg.unpack(),
+            ty::subst::GenericArgKind::Type(ty) if ty.is_closure()
+        )) {
+            return;
+        }
+        if let Some(ty::TyKind::Dynamic(..)) = target.map(|target| target.kind()) {
+            self.report(location, "creating trait objects is not supported");
+        } else {
+            self.report(location, "unsizing a pointer or reference value is not supported");
+        }
+    }
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for UnsupportedConstructsVisitor<'a, 'tcx> {
+    fn visit_statement(&mut self, statement: &mir::Statement<'tcx>, location: mir::Location) {
+        match statement.kind {
+            mir::StatementKind::LlvmInlineAsm(..) => {
+                self.report(location, "inline assembly is not supported");
+            }
+            // Only the borrow checker uses these, the encoder ignores them.
+            mir::StatementKind::FakeRead(..) => return,
+            _ => {}
+        }
+        self.super_statement(statement, location);
+    }
+
+    fn visit_terminator(&mut self, terminator: &mir::Terminator<'tcx>, location: mir::Location) {
+        match terminator.kind {
+            mir::TerminatorKind::InlineAsm { .. } => {
+                self.report(location, "inline assembly is not supported");
+            }
+            mir::TerminatorKind::Yield { .. } | mir::TerminatorKind::GeneratorDrop => {
+                self.report(location, "generators are not supported");
+            }
+            mir::TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                let func_ty = func.ty(self.mir, self.tcx);
+                let is_pointer_arithmetic = matches!(
+                    func_ty.kind(),
+                    ty::TyKind::FnDef(def_id, _) if self.is_pointer_arithmetic(*def_id)
+                );
+                if is_pointer_arithmetic {
+                    self.report(location, "pointer arithmetic is not supported");
+                } else if args.iter().any(|arg| arg.ty(self.mir, self.tcx).is_unsafe_ptr()) {
+                    self.report(location, "raw pointers are not supported as arguments of calls");
+                } else if destination.map_or(false, |(place, _)| place.ty(self.mir, self.tcx).ty.is_unsafe_ptr()) {
+                    self.report(location, "raw pointers are not supported as results of calls");
+                }
+            }
+            _ => {}
+        }
+        self.super_terminator(terminator, location);
+    }
+
+    fn visit_rvalue(&mut self, rvalue: &mir::Rvalue<'tcx>, location: mir::Location) {
+        match rvalue {
+            // The raw pointers that are assigned once, to the address of a
+            // place, and that are only dereferenced are replaced by that place.
+            mir::Rvalue::AddressOf(..) => {
+                self.report(
+                    location,
+                    "raw pointers are not supported unless they are assigned once, \
+                    to the address of a place, and only dereferenced",
+                );
+            }
+            mir::Rvalue::Cast(mir::CastKind::Misc, operand, ty)
+                if operand.ty(self.mir, self.tcx).is_unsafe_ptr() || ty.is_unsafe_ptr() =>
+            {
+                let reason = if ty.is_integral() {
+                    "casting raw pointers to integers is not supported"
+                } else if operand.ty(self.mir, self.tcx).is_integral() {
+                    "casting integers to raw pointers is not supported"
+                } else {
+                    "casts between raw pointer types are not supported"
+                };
+                self.report(location, reason);
+            }
+            mir::Rvalue::ThreadLocalRef(..) => {
+                self.report(location, "references to thread-local storage are not supported");
+            }
+            mir::Rvalue::Cast(mir::CastKind::Pointer(PointerCast::Unsize), operand, ty) => {
+                self.check_unsize(operand, *ty, location);
+            }
+            mir::Rvalue::Cast(mir::CastKind::Pointer(
+                PointerCast::ReifyFnPointer
+                | PointerCast::ClosureFnPointer(..)
+                | PointerCast::UnsafeFnPointer
+            ), ..) => {
+                self.report(location, "function pointers are not supported");
+            }
+            mir::Rvalue::Cast(mir::CastKind::Pointer(..), ..) => {
+                self.report(location, RAW_POINTERS);
+            }
+            // A write through the reference would not make the other fields
+            // of the union arbitrary.
+            mir::Rvalue::Ref(_, mir::BorrowKind::Mut { .. }, place)
+                if place.iter_projections().any(|(base, elem)| {
+                    matches!(elem, mir::ProjectionElem::Field(..))
+                        && base.ty(self.mir, self.tcx).ty.ty_adt_def().map_or(false, |adt_def| adt_def.is_union())
+                }) =>
+            {
+                self.report(location, "mutable references to fields of unions are not supported");
+            }
+            _ => {}
+        }
+        self.super_rvalue(rvalue, location);
+    }
+
+    fn visit_place(&mut self, place: &mir::Place<'tcx>, context: PlaceContext, location: mir::Location) {
+        if let PlaceContext::NonUse(_) = context {
+            return;
+        }
+        let local_ty = self.mir.local_decls[place.local].ty;
+        if local_ty.is_unsafe_ptr() && self.is_argument(place.local) {
+            self.report(location, "raw pointers received from the caller are not supported");
+        } else if local_ty.is_unsafe_ptr()
+            && place.local == mir::RETURN_PLACE
+            && context == PlaceContext::MutatingUse(MutatingUseContext::Store)
+        {
+            self.report(location, "returning raw pointers is not supported");
+        }
+        let derefs_raw_pointer = place.iter_projections().any(|(base, elem)| {
+            elem == mir::ProjectionElem::Deref && base.ty(self.mir, self.tcx).ty.is_unsafe_ptr()
+        });
+        if derefs_raw_pointer || place.ty(self.mir, self.tcx).ty.is_unsafe_ptr() {
+            self.report(location, RAW_POINTERS);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The codes of the verification errors reported by Prusti. They live in the
+//! `P` namespace, so that they do not clash with the error codes of rustc,
+//! and can be explained with `prusti-rustc --explain <code>`.
+
+/// An error code, with the explanation shown by `--explain`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    pub code: &'static str,
+    /// A short description of the error, like the message of the
+    /// diagnostics that carry the code.
+    pub title: &'static str,
+    pub explanation: &'static str,
+}
+
+pub const PANIC: &str = "P0001";
+pub const POSTCONDITION: &str = "P0002";
+pub const PRECONDITION: &str = "P0003";
+pub const LOOP_INVARIANT: &str = "P0004";
+pub const TERMINATION: &str = "P0005";
+pub const PRUSTI_ASSERTION: &str = "P0006";
+pub const TYPE_INVARIANT: &str = "P0007";
+pub const PLEDGE: &str = "P0008";
+pub const TRAIT_REFINEMENT: &str = "P0009";
+pub const INDEX_OUT_OF_BOUNDS: &str = "P0010";
+pub const CAST_OVERFLOW: &str = "P0011";
+pub const TIMEOUT: &str = "P0012";
+pub const RESOURCE_OUT: &str = "P0013";
+
+pub const ERROR_CODES: &[ErrorCode] = &[
+    ErrorCode {
+        code: PANIC,
+        title: "statement might panic",
+        explanation: "\
+A statement might panic, or an assertion might fail.
+
+Prusti checks that no `panic!`, `assert!`, `unreachable!` or `unimplemented!`
+is reachable, and that the built-in checks of Rust, such as the arithmetic
+overflow checks, always succeed. The note of the error points to the
+condition that might not hold.
+
+Add a precondition that rules out the failing inputs, or a loop invariant
+that carries the facts that the assertion needs:
+
+```
+#[requires(b != 0)]
+fn divide(a: u32, b: u32) -> u32 {
+    a / b
+}
+```
+",
+    },
+    ErrorCode {
+        code: POSTCONDITION,
+        title: "postcondition might not hold",
+        explanation: "\
+The postcondition of a function might not hold when the function returns.
+
+Prusti checks every `#[ensures(..)]` clause at every return of the function,
+assuming only the preconditions. The error points to the clause that might
+fail; a note points to the return, if there are several.
+
+```
+#[ensures(result > x)]
+#[requires(x < u32::MAX)]
+fn increment(x: u32) -> u32 {
+    x + 1
+}
+```
+
+Strengthen the precondition, weaken the postcondition, or fix the body of the
+function.
+",
+    },
+    ErrorCode {
+        code: PRECONDITION,
+        title: "precondition might not hold",
+        explanation: "\
+The precondition of a called function might not hold at the call.
+
+The caller is responsible for establishing every `#[requires(..)]` clause of
+the callee. The note of the error points to the clause that might fail.
+
+```
+#[requires(x > 0)]
+fn positive(x: i32) {}
+
+fn main() {
+    positive(0); // error: the precondition `x > 0` does not hold
+}
+```
+",
+    },
+    ErrorCode {
+        code: LOOP_INVARIANT,
+        title: "loop invariant might not hold",
+        explanation: "\
+A loop invariant might not hold before the first iteration of the loop, or
+might not be preserved by an iteration of the loop.
+
+A `body_invariant!(..)` must hold every time the execution reaches it. Check
+that it holds on entry to the loop, and that every iteration that does not
+exit the loop re-establishes it.
+",
+    },
+    ErrorCode {
+        code: TERMINATION,
+        title: "termination measure might not decrease",
+        explanation: "\
+A termination measure of a pure function, or the variant of a loop, might be
+negative or might not decrease.
+
+The measure given with `#[decreases(..)]` or `body_variant!(..)` must be
+non-negative and strictly decrease at every recursive call or loop iteration.
+",
+    },
+    ErrorCode {
+        code: PRUSTI_ASSERTION,
+        title: "prusti_assert!(..) might not hold",
+        explanation: "\
+The expression asserted with `prusti_assert!(..)` might not hold.
+
+Unlike `assert!`, the assertion is only checked by Prusti and is erased when
+compiling the program.
+",
+    },
+    ErrorCode {
+        code: TYPE_INVARIANT,
+        title: "type invariant might not hold",
+        explanation: "\
+A type invariant, declared with `#[invariant(..)]`, might not hold.
+
+The invariants of the arguments of a function must hold when it is called,
+and the invariants of the arguments and of the result must hold when it
+returns.
+",
+    },
+    ErrorCode {
+        code: PLEDGE,
+        title: "pledge might not hold",
+        explanation: "\
+A pledge of a function that returns a reference, declared with
+`#[after_expiry(..)]`, might not hold when the returned reference expires.
+",
+    },
+    ErrorCode {
+        code: TRAIT_REFINEMENT,
+        title: "method does not refine the trait specification",
+        explanation: "\
+The specification of a method is not a valid refinement of the specification
+of the trait method that it implements.
+
+The precondition of the implementation must be implied by the precondition of
+the trait, and the postcondition of the implementation must imply the
+postcondition of the trait.
+",
+    },
+    ErrorCode {
+        code: INDEX_OUT_OF_BOUNDS,
+        title: "index might be out of bounds",
+        explanation: "\
+The index of an array or slice access might be out of bounds.
+
+Add a precondition or a loop invariant stating that the index is smaller than
+the length of the array or slice. The help of the error gives the missing
+fact, when it can be found.
+",
+    },
+    ErrorCode {
+        code: CAST_OVERFLOW,
+        title: "value might not fit into the target type",
+        explanation: "\
+The value converted with an `as` cast might not fit into the target type.
+
+Prusti rejects casts that would truncate the value or change its sign. Bound
+the value before the cast.
+",
+    },
+    ErrorCode {
+        code: TIMEOUT,
+        title: "verification timed out",
+        explanation: "\
+The verification of a procedure did not finish within the time given by the
+`procedure_timeout_ms` setting, and was abandoned.
+
+Split the procedure, add intermediate assertions, or increase the timeout.
+",
+    },
+    ErrorCode {
+        code: RESOURCE_OUT,
+        title: "verification ran out of resources",
+        explanation: "\
+The SMT solver reached the resource limit given by the `smt_rlimit` setting, or
+by the `#[prusti::smt_rlimit(N)]` attribute of the procedure, before it could
+prove a check. The check might hold, but it has not been proven.
+
+Add triggers to the quantifiers of the specification, split the procedure, or
+increase the resource limit.
+",
+    },
+];
+
+/// The error code with the given code, such as `P0002`.
+pub fn find(code: &str) -> Option<&'static ErrorCode> {
+    ERROR_CODES.iter().find(|error_code| error_code.code.eq_ignore_ascii_case(code))
+}
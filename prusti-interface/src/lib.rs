@@ -32,6 +32,8 @@ extern crate rustc_span;
 extern crate rustc_ast;
 extern crate rustc_attr;
 extern crate rustc_data_structures;
+extern crate rustc_errors;
+extern crate rustc_session;
 extern crate rustc_index;
 extern crate rustc_trait_selection;
 extern crate polonius_engine;
@@ -40,6 +42,8 @@ extern crate lazy_static;
 
 pub mod data;
 pub mod environment;
+pub mod error_codes;
+pub mod report;
 pub mod specs;
 pub mod utils;
 
@@ -6,7 +6,11 @@
 
 use rustc_span::{Span, MultiSpan};
 use crate::environment::Environment;
+use crate::error_codes;
+use crate::report::ErrorReport;
+use crate::specs::dump::SpanDump;
 use prusti_common::config;
+use prusti_common::report::progress;
 use ::log::warn;
 
 /// The Prusti message that will be reported to the user.
@@ -32,6 +36,13 @@ pub struct PrustiError {
     span: MultiSpan,
     help: Option<String>,
     notes: Vec<(String, Option<MultiSpan>)>,
+    /// A machine-applicable replacement of a span, with its message.
+    suggestion: Option<(String, Span, String)>,
+    /// The code of the error, from the `error_codes` module.
+    code: Option<&'static str>,
+    /// The error of the backend that caused this error, as shown to the
+    /// tools that consume the JSON diagnostics.
+    backend_reason: Option<String>,
 }
 
 impl PartialOrd for PrustiError {
@@ -56,6 +67,9 @@ impl PrustiError {
             span,
             help: None,
             notes: vec![],
+            suggestion: None,
+            code: None,
+            backend_reason: None,
         }
     }
 
@@ -78,6 +92,16 @@ impl PrustiError {
         error
     }
 
+    /// Report a procedure whose verification did not finish within the time
+    /// limit of `procedure_timeout_ms`
+    pub fn verification_timeout<S: ToString>(message: S, span: MultiSpan) -> Self {
+        check_message(message.to_string());
+        PrustiError::new(
+            format!("[Prusti: verification timed out] {}", message.to_string()),
+            span
+        ).set_code(error_codes::TIMEOUT)
+    }
+
     /// Report an unsupported feature of the verified Rust code (e.g. dereferencing raw pointers)
     pub fn unsupported<S: ToString>(message: S, span: MultiSpan) -> Self {
         check_message(message.to_string());
@@ -135,23 +159,77 @@ impl PrustiError {
         self
     }
 
+    /// Suggest to replace `span` with `replacement`, e.g. to insert a missing
+    /// attribute. The suggestion can be applied automatically.
+    pub fn set_suggestion<S: ToString>(mut self, message: S, span: Span, replacement: String) -> Self {
+        self.suggestion = Some((message.to_string(), span, replacement));
+        self
+    }
+
+    /// Set the code of the error, one of the constants of the `error_codes`
+    /// module.
+    pub fn set_code(mut self, code: &'static str) -> Self {
+        debug_assert!(error_codes::find(code).is_some(), "unknown error code {:?}", code);
+        self.code = Some(code);
+        self
+    }
+
+    pub fn code(&self) -> Option<&'static str> {
+        self.code
+    }
+
+    /// Record the error of the backend that caused this error.
+    pub fn set_backend_reason<S: ToString>(mut self, reason: S) -> Self {
+        self.backend_reason = Some(reason.to_string());
+        self
+    }
+
     /// Report the encoding error using the compiler's interface
-    pub fn emit(self, env: &Environment) {
+    pub fn emit(mut self, env: &Environment) {
         assert!(!self.is_disabled);
-        if self.is_error {
-            env.span_err_with_help_and_notes(
-                self.span,
-                &self.message,
-                &self.help,
-                &self.notes,
-            );
-        } else {
-            env.span_warn_with_help_and_notes(
-                self.span,
-                &self.message,
-                &self.help,
-                &self.notes,
-            );
+        // The reason given by the backend is meant for tools, not for the
+        // humans reading the rendered diagnostics. It is left out of UI tests,
+        // where it would tie the expected output to the version of Viper.
+        if env.has_json_diagnostics() && !env.tcx().sess.opts.debugging_opts.ui_testing {
+            if let Some(reason) = self.backend_reason.take() {
+                self.notes.push((format!("the Viper verification error is: {}", reason), None));
+            }
+        }
+        // The diagnostic must not be drawn over by the progress bar.
+        progress::suspend(|| {
+            if self.is_error {
+                env.span_err_with_help_and_notes(
+                    self.span,
+                    &self.message,
+                    self.code,
+                    &self.help,
+                    &self.notes,
+                    &self.suggestion,
+                );
+            } else {
+                env.span_warn_with_help_and_notes(
+                    self.span,
+                    &self.message,
+                    self.code,
+                    &self.help,
+                    &self.notes,
+                    &self.suggestion,
+                );
+            }
+        });
+    }
+
+    /// Describe the error in the report of the verification (see the
+    /// `report` module).
+    pub fn to_report(&self, env: &Environment) -> ErrorReport {
+        ErrorReport {
+            message: self.message.clone(),
+            span: self.span
+                .primary_span()
+                .filter(|span| !span.is_dummy())
+                .map(|span| SpanDump::new(env.codemap(), span)),
+            is_error: self.is_error,
+            code: self.code.map(str::to_string),
         }
     }
 
@@ -160,6 +238,25 @@ impl PrustiError {
         assert!(self.is_disabled);
     }
 
+    /// Report this verification error as a check that the SMT solver could
+    /// neither prove nor disprove within its resource limit `rlimit`.
+    pub fn into_resource_out(self, rlimit: u64) -> Self {
+        let message = self.message
+            .strip_prefix("[Prusti: verification error] ")
+            .unwrap_or(&self.message)
+            .to_string();
+        PrustiError {
+            message: format!("[Prusti: verification ran out of resources] {}", message),
+            ..self
+        }.set_code(error_codes::RESOURCE_OUT).add_note(
+            format!(
+                "the SMT solver reached its resource limit of {} (`smt_rlimit`) before it could decide this check",
+                rlimit,
+            ),
+            None,
+        )
+    }
+
     /// Set the span of the failing assertion expression.
     ///
     /// Note: this is a noop if `opt_span` is None
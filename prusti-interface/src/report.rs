@@ -0,0 +1,130 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A machine-readable report of the verification of a crate, with one entry
+//! per procedure, intended to be used by continuous integration. See the
+//! `report` flag.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::time::Duration;
+use crate::data::ProcedureDefId;
+use crate::environment::Environment;
+use crate::specs::dump::SpanDump;
+
+/// The outcome of the verification of a procedure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemResult {
+    /// The procedure verified.
+    Verified,
+    /// The encoding or the verification of the procedure reported errors.
+    Failed,
+    /// The procedure uses features that are not supported, so it has not
+    /// been verified.
+    Unsupported,
+    /// The procedure is trusted, is assumed to be pure (see `assumes_purity`),
+    /// or has not been selected for verification.
+    Skipped,
+    /// The procedure verified according to the verification cache, without
+    /// running the backend.
+    Cached,
+    /// The verification of the procedure did not finish within the time
+    /// limit of `procedure_timeout_ms`.
+    TimedOut,
+    /// The SMT solver reached its resource limit of `smt_rlimit` before it
+    /// could verify the procedure.
+    ResourceOut,
+}
+
+/// An error or a warning reported for a procedure.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub message: String,
+    /// The primary span of the error, if any.
+    pub span: Option<SpanDump>,
+    pub is_error: bool,
+    /// The code of the error, such as `P0002`, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// The verification of a single procedure.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemReport {
+    pub def_path: String,
+    pub span: SpanDump,
+    pub result: ItemResult,
+    pub errors: Vec<ErrorReport>,
+    /// The `prusti_assume!` statements of the procedure, which are assumed
+    /// without being checked.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assumptions: Vec<SpanDump>,
+    /// Whether the procedure is marked with `#[pure_memoized]`, so that it is
+    /// assumed to be pure without checking its body.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub assumes_purity: bool,
+    /// The time spent encoding the procedure, in milliseconds.
+    pub encoding_time_ms: u64,
+    /// The time spent by the backend verifying the procedure, in milliseconds.
+    pub backend_time_ms: u64,
+    /// The resources consumed by the SMT solver while verifying the procedure,
+    /// in the unit of `smt_rlimit`, if the backend reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smt_rlimit_count: Option<u64>,
+}
+
+impl ItemReport {
+    pub fn new(env: &Environment, def_id: ProcedureDefId, result: ItemResult) -> Self {
+        ItemReport {
+            def_path: env.get_absolute_item_name(def_id),
+            span: SpanDump::new(env.codemap(), env.get_item_span(def_id)),
+            result,
+            errors: vec![],
+            assumptions: vec![],
+            assumes_purity: false,
+            encoding_time_ms: 0,
+            backend_time_ms: 0,
+            smt_rlimit_count: None,
+        }
+    }
+
+    pub fn set_encoding_time(&mut self, time: Duration) {
+        self.encoding_time_ms = time.as_millis() as u64;
+    }
+
+    pub fn set_backend_time(&mut self, time: Duration) {
+        self.backend_time_ms = time.as_millis() as u64;
+    }
+}
+
+/// The verification of all the procedures of a crate.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub items: Vec<ItemReport>,
+}
+
+impl VerificationReport {
+    /// Write the report as JSON to the file at `path`. The items are sorted
+    /// by their position, so that the report is deterministic.
+    pub fn write(mut self, path: &str) -> io::Result<()> {
+        self.items.sort_by(|a, b| {
+            (&a.span.file, a.span.line_start, a.span.column_start, &a.def_path)
+                .cmp(&(&b.span.file, b.span.line_start, b.span.column_start, &b.def_path))
+        });
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, &self)?;
+        writer.write_all(b"\n")?;
+        writer.flush()
+    }
+
+    /// Read a report written by `write`.
+    pub fn read(path: &str) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
@@ -8,7 +8,7 @@ use rustc_hir::{
 use rustc_middle::{hir::map::Map, ty::TyCtxt};
 use rustc_span::{MultiSpan, Span};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     environment::Environment,
@@ -17,13 +17,21 @@ use crate::{
 };
 
 /// Checker visitor for the specifications. Currently checks that `predicate!`
-/// functions are never used from non-specification code, but more checks may follow.
+/// functions are never used from non-specification code, that loop
+/// invariants are placed at the beginning of loop bodies and that ghost state
+/// is never assigned to non-ghost variables, but more checks may follow.
 pub struct SpecChecker {
     /// Map of the `DefID`s to the `Span`s of `predicate!` functions found in the first pass.
     predicates: HashMap<DefId, Span>,
 
     /// Span of use and definition of predicates used outside of specifications, collected in the second pass.
     pred_usages: Vec<(Span, Span)>,
+
+    /// Span of loop invariants and of the first statement preceding them in the loop body.
+    misplaced_invariants: Vec<(Span, Span)>,
+
+    /// Span of assignments of ghost state to non-ghost variables.
+    ghost_assignments: Vec<Span>,
 }
 
 /// First predicate checks visitor: collect all function items that originate
@@ -125,11 +133,263 @@ impl<'v, 'tcx> Visitor<'tcx> for CheckPredicatesVisitor<'v, 'tcx> {
     }
 }
 
+/// Loop invariant checks visitor: check that in the body of each loop the
+/// `body_invariant!` and `body_variant!` statements are not preceded by other
+/// statements
+struct CheckLoopInvariantsVisitor<'v, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+
+    misplaced_invariants: &'v mut Vec<(Span, Span)>,
+}
+
+impl<'v, 'tcx> CheckLoopInvariantsVisitor<'v, 'tcx> {
+    /// The block written by the user as the body of the loop, recovered from
+    /// the desugaring of `while` and `for` loops.
+    fn loop_body(block: &'tcx hir::Block<'tcx>, source: hir::LoopSource) -> Option<&'tcx hir::Block<'tcx>> {
+        match source {
+            hir::LoopSource::Loop => Some(block),
+            hir::LoopSource::While => {
+                // `loop { if cond { body } else { break } }`, or a `match`
+                // for `while let`
+                let then = match block.expr?.kind {
+                    hir::ExprKind::If(_, then, _) => then,
+                    hir::ExprKind::Match(_, arms, _) => arms.first()?.body,
+                    _ => return None,
+                };
+                match then.kind {
+                    hir::ExprKind::Block(body, _) => Some(body),
+                    _ => None,
+                }
+            }
+            hir::LoopSource::ForLoop => {
+                // `loop { let next; match ..; let pat = next; body }`
+                match block.stmts.last()?.kind {
+                    hir::StmtKind::Expr(hir::Expr { kind: hir::ExprKind::Block(body, _), .. }) => Some(body),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn is_invariant(&self, stmt: &'tcx hir::Stmt<'tcx>) -> bool {
+        let mut finder = FindInvariantVisitor {
+            tcx: self.tcx,
+            found: false,
+        };
+        finder.visit_stmt(stmt);
+        finder.found
+    }
+
+    fn check_loop_body(&mut self, body: &'tcx hir::Block<'tcx>) {
+        let mut first_statement = None;
+        for stmt in body.stmts {
+            if self.is_invariant(stmt) {
+                if let Some(preceding_span) = first_statement {
+                    let invariant_span = match stmt.kind {
+                        hir::StmtKind::Expr(expr) | hir::StmtKind::Semi(expr) => expr.span,
+                        _ => stmt.span,
+                    };
+                    self.misplaced_invariants.push((invariant_span, preceding_span));
+                }
+            } else if first_statement.is_none() {
+                first_statement = Some(stmt.span);
+            }
+        }
+    }
+}
+
+impl<'v, 'tcx> Visitor<'tcx> for CheckLoopInvariantsVisitor<'v, 'tcx> {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        intravisit::NestedVisitorMap::All(self.tcx.hir())
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        if let hir::ExprKind::Loop(block, _, source, _) = ex.kind {
+            if let Some(body) = Self::loop_body(block, source) {
+                self.check_loop_body(body);
+            }
+        }
+
+        intravisit::walk_expr(self, ex);
+    }
+}
+
+/// Searches a statement for the closure generated by `body_invariant!` or
+/// `body_variant!`
+struct FindInvariantVisitor<'tcx> {
+    tcx: TyCtxt<'tcx>,
+
+    found: bool,
+}
+
+impl<'tcx> Visitor<'tcx> for FindInvariantVisitor<'tcx> {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        intravisit::NestedVisitorMap::None
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        if let hir::ExprKind::Closure(..) = ex.kind {
+            let attrs = self.tcx.hir().attrs(ex.hir_id);
+            if has_prusti_attr(attrs, "loop_body_invariant_spec")
+                || has_prusti_attr(attrs, "loop_body_variant_spec")
+            {
+                self.found = true;
+                return;
+            }
+        }
+
+        intravisit::walk_expr(self, ex);
+    }
+}
+
+/// Ghost code checks visitor: check that neither ghost code nor non-ghost code
+/// assigns ghost state to non-ghost variables. Ghost code consists of the
+/// statements marked with `prusti::ghost` by `ghost!`, and ghost state of the
+/// variables declared in ghost code.
+struct CheckGhostCodeVisitor<'v, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+
+    /// Variables declared in ghost code.
+    ghost_locals: HashSet<hir::HirId>,
+    /// Whether the visited code is ghost code.
+    in_ghost_code: bool,
+    ghost_assignments: &'v mut Vec<Span>,
+}
+
+impl<'v, 'tcx> CheckGhostCodeVisitor<'v, 'tcx> {
+    fn is_ghost(&self, hir_id: hir::HirId) -> bool {
+        has_prusti_attr(self.tcx.hir().attrs(hir_id), "ghost")
+    }
+
+    /// Whether the place is (a part of) a ghost variable.
+    fn is_ghost_place(&self, place: &'tcx hir::Expr<'tcx>) -> bool {
+        match place.kind {
+            hir::ExprKind::Field(base, _)
+            | hir::ExprKind::Index(base, _)
+            | hir::ExprKind::Unary(hir::UnOp::Deref, base) => self.is_ghost_place(base),
+            hir::ExprKind::Path(hir::QPath::Resolved(None, path)) => match path.res {
+                hir::def::Res::Local(hir_id) => self.ghost_locals.contains(&hir_id),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn uses_ghost_state(&self, ex: &'tcx hir::Expr<'tcx>) -> bool {
+        let mut finder = FindGhostStateVisitor {
+            ghost_locals: &self.ghost_locals,
+            found: false,
+        };
+        finder.visit_expr(ex);
+        finder.found
+    }
+
+    fn walk_as_ghost_code(&mut self, is_ghost: bool, walk: impl FnOnce(&mut Self)) {
+        let was_ghost = self.in_ghost_code;
+        self.in_ghost_code = was_ghost || is_ghost;
+        walk(self);
+        self.in_ghost_code = was_ghost;
+    }
+}
+
+impl<'v, 'tcx> Visitor<'tcx> for CheckGhostCodeVisitor<'v, 'tcx> {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        intravisit::NestedVisitorMap::All(self.tcx.hir())
+    }
+
+    fn visit_local(&mut self, local: &'tcx hir::Local<'tcx>) {
+        let is_ghost = self.in_ghost_code || self.is_ghost(local.hir_id);
+        if is_ghost {
+            let ghost_locals = &mut self.ghost_locals;
+            local.pat.each_binding(|_, hir_id, _, _| {
+                ghost_locals.insert(hir_id);
+            });
+        } else if local.init.map_or(false, |init| self.uses_ghost_state(init)) {
+            self.ghost_assignments.push(local.span);
+        }
+
+        self.walk_as_ghost_code(is_ghost, |this| intravisit::walk_local(this, local));
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        let attrs = self.tcx.hir().attrs(ex.hir_id);
+        if has_spec_only_attr(attrs) {
+            // Specifications may freely refer to ghost state
+            return;
+        }
+
+        match ex.kind {
+            hir::ExprKind::Assign(place, value, _)
+            | hir::ExprKind::AssignOp(_, place, value) => {
+                if !self.is_ghost_place(place) && (self.in_ghost_code || self.uses_ghost_state(value)) {
+                    self.ghost_assignments.push(ex.span);
+                }
+            }
+            _ => {}
+        }
+
+        let is_ghost = self.is_ghost(ex.hir_id);
+        self.walk_as_ghost_code(is_ghost, |this| intravisit::walk_expr(this, ex));
+    }
+
+    fn visit_fn(
+        &mut self,
+        fk: intravisit::FnKind<'tcx>,
+        fd: &'tcx hir::FnDecl<'tcx>,
+        b: hir::BodyId,
+        s: Span,
+        id: hir::HirId,
+    ) {
+        let attrs = self.tcx.hir().attrs(id);
+        if has_spec_only_attr(attrs) {
+            return;
+        }
+
+        intravisit::walk_fn(self, fk, fd, b, s, id);
+    }
+}
+
+/// Searches an expression for uses of ghost variables
+struct FindGhostStateVisitor<'v> {
+    ghost_locals: &'v HashSet<hir::HirId>,
+
+    found: bool,
+}
+
+impl<'v, 'tcx> Visitor<'tcx> for FindGhostStateVisitor<'v> {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        intravisit::NestedVisitorMap::None
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        if let hir::ExprKind::Path(hir::QPath::Resolved(None, path)) = ex.kind {
+            if let hir::def::Res::Local(hir_id) = path.res {
+                if self.ghost_locals.contains(&hir_id) {
+                    self.found = true;
+                    return;
+                }
+            }
+        }
+
+        intravisit::walk_expr(self, ex);
+    }
+}
+
 impl<'tcx> SpecChecker {
     pub fn new() -> Self {
         Self {
             predicates: HashMap::new(),
             pred_usages: Vec::new(),
+            misplaced_invariants: Vec::new(),
+            ghost_assignments: Vec::new(),
         }
     }
 
@@ -151,6 +411,28 @@ impl<'tcx> SpecChecker {
         debug!("Predicate usages: {:?}", self.pred_usages);
     }
 
+    pub fn check_loop_invariants(&mut self, tcx: TyCtxt<'tcx>, krate: &'tcx hir::Crate<'tcx>) {
+        let mut visit = CheckLoopInvariantsVisitor {
+            tcx,
+            misplaced_invariants: &mut self.misplaced_invariants,
+        };
+        intravisit::walk_crate(&mut visit, krate);
+
+        debug!("Misplaced loop invariants: {:?}", self.misplaced_invariants);
+    }
+
+    pub fn check_ghost_code(&mut self, tcx: TyCtxt<'tcx>, krate: &'tcx hir::Crate<'tcx>) {
+        let mut visit = CheckGhostCodeVisitor {
+            tcx,
+            ghost_locals: HashSet::new(),
+            in_ghost_code: false,
+            ghost_assignments: &mut self.ghost_assignments,
+        };
+        intravisit::walk_crate(&mut visit, krate);
+
+        debug!("Ghost state assigned to non-ghost variables: {:?}", self.ghost_assignments);
+    }
+
     pub fn report_errors(&self, env: &Environment<'tcx>) {
         for &(usage_span, def_span) in &self.pred_usages {
             PrustiError::incorrect(
@@ -160,5 +442,26 @@ impl<'tcx> SpecChecker {
             .add_note("this is a specification-only predicate function", Some(def_span))
             .emit(env);
         }
+
+        for &(invariant_span, preceding_span) in &self.misplaced_invariants {
+            let mut span = MultiSpan::from_span(invariant_span);
+            span.push_span_label(
+                preceding_span,
+                "this statement precedes the loop invariant".to_string(),
+            );
+            PrustiError::incorrect(
+                "loop invariant must be at the beginning of the loop body".to_string(),
+                span,
+            )
+            .emit(env);
+        }
+
+        for &assignment_span in &self.ghost_assignments {
+            PrustiError::incorrect(
+                "ghost state cannot be assigned to non-ghost variables".to_string(),
+                MultiSpan::from_span(assignment_span),
+            )
+            .emit(env);
+        }
     }
 }
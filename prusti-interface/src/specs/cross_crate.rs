@@ -0,0 +1,267 @@
+//! Export and import of specifications across crates. The specifications of
+//! the procedures of a library are written, in the JSON format of the
+//! assertions, to a `.prusti` file next to the metadata of the library. The
+//! crates that depend on the library load this file and merge the
+//! specifications into their own.
+//!
+//! Items are referred to by their `DefPathHash`, which is stable across
+//! compilation sessions.
+
+use prusti_specs::specifications::{common::SpecIdRef, json::Assertion as JsonAssertion};
+use rustc_data_structures::fingerprint::Fingerprint;
+use rustc_hir::def_id::{CrateNum, DefId, DefPathHash, LocalDefId, LOCAL_CRATE};
+use rustc_middle::ty::TyCtxt;
+use rustc_session::config::CrateType;
+use rustc_span::MultiSpan;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use super::{typed, ProcedureSpecRef, SpecItem};
+use super::typed::StructuralToTyped;
+use crate::environment::Environment;
+use crate::PrustiError;
+use log::{debug, info};
+
+/// The extension of the files that contain exported specifications.
+const SPECS_EXTENSION: &str = "prusti";
+
+/// An item of the crate that exported the specifications.
+#[derive(Serialize, Deserialize)]
+struct ItemRef {
+    /// Only used to speed up the lookup of the item.
+    def_index: u32,
+    def_path_hash: (u64, u64),
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProcedureSpecExport {
+    item: ItemRef,
+    pres: Vec<typed::SpecificationId>,
+    posts: Vec<typed::SpecificationId>,
+    pledges: Vec<(Option<typed::SpecificationId>, typed::SpecificationId)>,
+    predicate_body: Option<typed::SpecificationId>,
+    decreases: Option<typed::SpecificationId>,
+    pure: bool,
+    trusted: bool,
+    #[serde(default)]
+    pure_memoized: bool,
+    terminates: bool,
+    #[serde(default)]
+    smt_rlimit: Option<u64>,
+}
+
+/// The content of a `.prusti` file.
+#[derive(Serialize, Deserialize)]
+struct CrateSpecsExport {
+    /// The closures of the expressions, keyed by `{spec_id}_{expr_id}`.
+    expressions: Vec<(String, ItemRef)>,
+    assertions: Vec<(typed::SpecificationId, JsonAssertion)>,
+    procedures: Vec<ProcedureSpecExport>,
+}
+
+/// Only libraries can be used by other crates.
+pub(super) fn is_library(tcx: TyCtxt) -> bool {
+    tcx.sess.crate_types().iter().any(|crate_type| *crate_type != CrateType::Executable)
+}
+
+fn export_path(tcx: TyCtxt) -> PathBuf {
+    let crate_name = tcx.crate_name(LOCAL_CRATE).to_string();
+    let outputs = tcx.output_filenames(());
+    rustc_session::output::filename_for_metadata(tcx.sess, &crate_name, &outputs)
+        .with_extension(SPECS_EXTENSION)
+}
+
+/// The `.prusti` file of a dependency, which is next to its metadata.
+fn import_path(tcx: TyCtxt, cnum: CrateNum) -> Option<PathBuf> {
+    let source = tcx.used_crate_source(cnum);
+    let (path, _) = source.rlib.as_ref().or_else(|| source.rmeta.as_ref())?;
+    Some(path.with_extension(SPECS_EXTENSION))
+}
+
+fn item_ref(tcx: TyCtxt, def_id: DefId) -> ItemRef {
+    ItemRef {
+        def_index: def_id.index.as_u32(),
+        def_path_hash: tcx.def_path_hash(def_id).0.as_value(),
+    }
+}
+
+fn resolve_item_ref(tcx: TyCtxt, cnum: CrateNum, item: &ItemRef) -> Option<DefId> {
+    let (hash_0, hash_1) = item.def_path_hash;
+    let def_path_hash = DefPathHash(Fingerprint::new(hash_0, hash_1));
+    tcx.cstore_untracked().def_path_hash_to_def_id(cnum, item.def_index, def_path_hash)
+}
+
+/// Write the specifications of the procedures of this crate to its `.prusti`
+/// file. The wrappers of external specifications are not exported.
+pub(super) fn export_specs<'a>(
+    env: &Environment,
+    spec_items: &[SpecItem],
+    typed_expressions: &HashMap<String, DefId>,
+    procedure_specs: impl Iterator<Item = (&'a LocalDefId, &'a ProcedureSpecRef)>,
+) {
+    let tcx = env.tcx();
+    let export = CrateSpecsExport {
+        expressions: typed_expressions
+            .iter()
+            .map(|(key, def_id)| (key.clone(), item_ref(tcx, *def_id)))
+            .collect(),
+        assertions: spec_items
+            .iter()
+            .map(|spec_item| (spec_item.spec_id, spec_item.specification.clone()))
+            .collect(),
+        procedures: procedure_specs
+            .map(|(local_id, refs)| export_procedure_spec(tcx, local_id.to_def_id(), refs))
+            .collect(),
+    };
+    let path = export_path(tcx);
+    debug!("Exporting {} procedure specs to {:?}", export.procedures.len(), path);
+    if let Err(error) = write_specs(&export, &path) {
+        PrustiError::internal(
+            format!("failed to export the specifications to {:?}: {}", path, error),
+            MultiSpan::new(),
+        ).emit(env);
+    }
+}
+
+fn write_specs(export: &CrateSpecsExport, path: &PathBuf) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut writer, export)?;
+    writer.flush()
+}
+
+fn export_procedure_spec(tcx: TyCtxt, def_id: DefId, refs: &ProcedureSpecRef) -> ProcedureSpecExport {
+    let mut export = ProcedureSpecExport {
+        item: item_ref(tcx, def_id),
+        pres: vec![],
+        posts: vec![],
+        pledges: vec![],
+        predicate_body: None,
+        decreases: None,
+        pure: refs.pure,
+        trusted: refs.trusted,
+        pure_memoized: refs.pure_memoized,
+        terminates: refs.terminates,
+        smt_rlimit: refs.smt_rlimit,
+    };
+    for spec_id_ref in &refs.spec_id_refs {
+        match *spec_id_ref {
+            SpecIdRef::Precondition(spec_id) => export.pres.push(spec_id),
+            SpecIdRef::Postcondition(spec_id) => export.posts.push(spec_id),
+            SpecIdRef::Pledge { lhs, rhs } => export.pledges.push((lhs, rhs)),
+            SpecIdRef::Predicate(spec_id) => export.predicate_body = Some(spec_id),
+            SpecIdRef::Decreases(spec_id) => export.decreases = Some(spec_id),
+        }
+    }
+    export
+}
+
+/// Load the specifications exported by the dependencies of this crate. The
+/// external specifications of this crate take precedence over the imported
+/// specifications, which is reported with a warning.
+pub(super) fn import_specs<'tcx>(
+    env: &Environment<'tcx>,
+    def_spec: &mut typed::DefSpecificationMap<'tcx>,
+) {
+    let tcx = env.tcx();
+    for &cnum in tcx.crates(()).iter() {
+        let path = match import_path(tcx, cnum) {
+            Some(path) if path.exists() => path,
+            _ => continue,
+        };
+        let export: CrateSpecsExport = match File::open(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|file| {
+                serde_json::from_reader(BufReader::new(file)).map_err(|error| error.to_string())
+            })
+        {
+            Ok(export) => export,
+            Err(error) => {
+                PrustiError::internal(
+                    format!("failed to import the specifications from {:?}: {}", path, error),
+                    MultiSpan::new(),
+                ).set_help(
+                    "this usually happens when the crate was verified with another version of Prusti"
+                ).emit(env);
+                continue;
+            }
+        };
+        let imported = import_crate_specs(env, cnum, export, def_spec);
+        info!("imported {} procedure specs from crate {}", imported, tcx.crate_name(cnum));
+    }
+}
+
+/// Returns the number of imported procedure specifications.
+fn import_crate_specs<'tcx>(
+    env: &Environment<'tcx>,
+    cnum: CrateNum,
+    export: CrateSpecsExport,
+    def_spec: &mut typed::DefSpecificationMap<'tcx>,
+) -> usize {
+    let tcx = env.tcx();
+    let typed_expressions: HashMap<String, DefId> = export.expressions
+        .iter()
+        .filter_map(|(key, item)| Some((key.clone(), resolve_item_ref(tcx, cnum, item)?)))
+        .collect();
+    let mut assertions: HashMap<typed::SpecificationId, JsonAssertion> =
+        export.assertions.into_iter().collect();
+    let mut typed_assertions = HashMap::new();
+    let mut get_assertion = |spec_id: typed::SpecificationId| -> typed::Assertion<'tcx> {
+        typed_assertions
+            .entry(spec_id)
+            .or_insert_with(|| {
+                let assertion = assertions.remove(&spec_id)
+                    .expect("an exported specification refers to a missing assertion");
+                assertion.to_typed(&typed_expressions, env)
+            })
+            .clone()
+    };
+    let mut imported = 0;
+    for procedure in export.procedures {
+        let def_id = match resolve_item_ref(tcx, cnum, &procedure.item) {
+            Some(def_id) => def_id,
+            None => {
+                debug!("Cannot resolve the exported item {:?}", procedure.item.def_path_hash);
+                continue;
+            }
+        };
+        if let Some(local_id) = def_spec.extern_specs.get(&def_id) {
+            let mut span = MultiSpan::from_span(env.get_item_span(local_id.to_def_id()));
+            span.push_span_label(
+                env.get_item_span(def_id),
+                "the imported specification is attached here".to_string(),
+            );
+            let mut warning = PrustiError::incorrect(
+                format!("the external specification of {} replaces the specification exported by \
+                    crate `{}`", env.get_item_name(def_id), tcx.crate_name(cnum)),
+                span,
+            );
+            warning.set_warning();
+            warning.emit(env);
+            continue;
+        }
+        let spec = typed::ProcedureSpecification {
+            pres: procedure.pres.into_iter().map(&mut get_assertion).collect(),
+            posts: procedure.posts.into_iter().map(&mut get_assertion).collect(),
+            pledges: procedure.pledges
+                .into_iter()
+                .map(|(lhs, rhs)| typed::Pledge {
+                    reference: None,
+                    lhs: lhs.map(&mut get_assertion),
+                    rhs: get_assertion(rhs),
+                })
+                .collect(),
+            predicate_body: procedure.predicate_body.map(&mut get_assertion),
+            decreases: procedure.decreases.map(&mut get_assertion),
+            pure: procedure.pure,
+            trusted: procedure.trusted,
+            pure_memoized: procedure.pure_memoized,
+            terminates: procedure.terminates,
+            smt_rlimit: procedure.smt_rlimit,
+        };
+        def_spec.imported_specs.insert(def_id, typed::SpecificationSet::Procedure(spec));
+        imported += 1;
+    }
+    imported
+}
@@ -0,0 +1,330 @@
+//! Serialization of the collected specifications to JSON, intended to be used
+//! by external tools such as editor plugins. See the `dump_specs` flag.
+
+use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::{Span, source_map::SourceMap};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use super::typed;
+use crate::environment::Environment;
+
+/// The position of an item or an expression in the source code. Lines and
+/// columns start from 1.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpanDump {
+    pub file: String,
+    pub line_start: usize,
+    pub column_start: usize,
+    pub line_end: usize,
+    pub column_end: usize,
+}
+
+impl SpanDump {
+    pub fn new(source_map: &SourceMap, span: Span) -> Self {
+        let lo = source_map.lookup_char_pos(span.lo());
+        let hi = source_map.lookup_char_pos(span.hi());
+        SpanDump {
+            file: lo.file.name.prefer_local().to_string(),
+            line_start: lo.line,
+            column_start: lo.col.0 + 1,
+            line_end: hi.line,
+            column_end: hi.col.0 + 1,
+        }
+    }
+}
+
+/// A Rust expression used in a specification.
+#[derive(Serialize)]
+pub struct ExpressionDump {
+    pub source: Option<String>,
+    pub span: SpanDump,
+}
+
+/// A variable bound by a quantifier or by a specification entailment.
+#[derive(Serialize)]
+pub struct VarDump {
+    pub local: String,
+    pub ty: String,
+}
+
+/// The structure of a typed assertion.
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+pub enum AssertionStructureDump {
+    Expr {
+        expr: ExpressionDump,
+    },
+    And {
+        conjuncts: Vec<AssertionStructureDump>,
+    },
+    Implies {
+        lhs: Box<AssertionStructureDump>,
+        rhs: Box<AssertionStructureDump>,
+    },
+    TypeCond {
+        vars: Vec<VarDump>,
+        body: Box<AssertionStructureDump>,
+    },
+    ForAll {
+        vars: Vec<VarDump>,
+        triggers: Vec<Vec<ExpressionDump>>,
+        body: Box<AssertionStructureDump>,
+    },
+    Exists {
+        vars: Vec<VarDump>,
+        triggers: Vec<Vec<ExpressionDump>>,
+        body: Box<AssertionStructureDump>,
+    },
+    SpecEntailment {
+        closure: ExpressionDump,
+        args: Vec<VarDump>,
+        result: VarDump,
+        pres: Vec<AssertionStructureDump>,
+        posts: Vec<AssertionStructureDump>,
+    },
+}
+
+/// A whole assertion, together with the source code it was written as.
+#[derive(Serialize)]
+pub struct AssertionDump {
+    pub source: Option<String>,
+    pub span: Option<SpanDump>,
+    pub structure: AssertionStructureDump,
+}
+
+#[derive(Serialize)]
+pub struct PledgeDump {
+    pub lhs: Option<AssertionDump>,
+    pub rhs: AssertionDump,
+}
+
+/// The specifications attached to a single item.
+#[derive(Serialize)]
+pub struct ItemSpecDump {
+    pub def_path: String,
+    pub span: SpanDump,
+    pub kind: &'static str,
+    pub extern_spec: bool,
+    pub pure: bool,
+    pub trusted: bool,
+    pub preconditions: Vec<AssertionDump>,
+    pub postconditions: Vec<AssertionDump>,
+    pub pledges: Vec<PledgeDump>,
+    pub invariants: Vec<AssertionDump>,
+}
+
+struct SpecDumper<'tcx> {
+    tcx: TyCtxt<'tcx>,
+}
+
+impl<'tcx> SpecDumper<'tcx> {
+    fn dump_span(&self, span: Span) -> SpanDump {
+        SpanDump::new(self.tcx.sess.source_map(), span)
+    }
+
+    fn snippet(&self, span: Span) -> Option<String> {
+        self.tcx.sess.source_map().span_to_snippet(span).ok()
+    }
+
+    fn expression_span(&self, expr: &typed::Expression) -> Span {
+        self.tcx.def_span(expr.expr)
+    }
+
+    fn dump_expression(&self, expr: &typed::Expression) -> ExpressionDump {
+        let span = self.expression_span(expr);
+        ExpressionDump {
+            source: self.snippet(span),
+            span: self.dump_span(span),
+        }
+    }
+
+    fn dump_vars(&self, vars: &[(rustc_middle::mir::Local, rustc_middle::ty::Ty<'tcx>)]) -> Vec<VarDump> {
+        vars.iter()
+            .map(|(local, ty)| VarDump {
+                local: format!("{:?}", local),
+                ty: ty.to_string(),
+            })
+            .collect()
+    }
+
+    fn dump_triggers(&self, trigger_set: &typed::TriggerSet) -> Vec<Vec<ExpressionDump>> {
+        trigger_set
+            .triggers()
+            .iter()
+            .map(|trigger| trigger.terms().iter().map(|term| self.dump_expression(term)).collect())
+            .collect()
+    }
+
+    fn dump_structure(&self, assertion: &typed::Assertion<'tcx>) -> AssertionStructureDump {
+        match *assertion.kind {
+            typed::AssertionKind::Expr(ref expr) => AssertionStructureDump::Expr {
+                expr: self.dump_expression(expr),
+            },
+            typed::AssertionKind::And(ref assertions) => AssertionStructureDump::And {
+                conjuncts: assertions.iter().map(|a| self.dump_structure(a)).collect(),
+            },
+            typed::AssertionKind::Implies(ref lhs, ref rhs) => AssertionStructureDump::Implies {
+                lhs: box self.dump_structure(lhs),
+                rhs: box self.dump_structure(rhs),
+            },
+            typed::AssertionKind::TypeCond(ref vars, ref body) => AssertionStructureDump::TypeCond {
+                vars: self.dump_vars(&vars.vars),
+                body: box self.dump_structure(body),
+            },
+            typed::AssertionKind::ForAll(ref vars, ref triggers, ref body) => AssertionStructureDump::ForAll {
+                vars: self.dump_vars(&vars.vars),
+                triggers: self.dump_triggers(triggers),
+                body: box self.dump_structure(body),
+            },
+            typed::AssertionKind::Exists(ref vars, ref triggers, ref body) => AssertionStructureDump::Exists {
+                vars: self.dump_vars(&vars.vars),
+                triggers: self.dump_triggers(triggers),
+                body: box self.dump_structure(body),
+            },
+            typed::AssertionKind::SpecEntailment {
+                ref closure,
+                ref arg_binders,
+                ref pres,
+                ref posts,
+            } => AssertionStructureDump::SpecEntailment {
+                closure: self.dump_expression(closure),
+                args: self.dump_vars(&arg_binders.args),
+                result: self.dump_vars(&[arg_binders.result]).pop().unwrap(),
+                pres: pres.iter().map(|a| self.dump_structure(a)).collect(),
+                posts: posts.iter().map(|a| self.dump_structure(a)).collect(),
+            },
+        }
+    }
+
+    /// Collect the spans of all expressions in the assertion.
+    fn collect_spans(&self, assertion: &typed::Assertion<'tcx>, spans: &mut Vec<Span>) {
+        match *assertion.kind {
+            typed::AssertionKind::Expr(ref expr) => spans.push(self.expression_span(expr)),
+            typed::AssertionKind::And(ref assertions) => {
+                for assertion in assertions {
+                    self.collect_spans(assertion, spans);
+                }
+            }
+            typed::AssertionKind::Implies(ref lhs, ref rhs) => {
+                self.collect_spans(lhs, spans);
+                self.collect_spans(rhs, spans);
+            }
+            typed::AssertionKind::TypeCond(_, ref body)
+            | typed::AssertionKind::ForAll(_, _, ref body)
+            | typed::AssertionKind::Exists(_, _, ref body) => self.collect_spans(body, spans),
+            typed::AssertionKind::SpecEntailment { ref closure, ref pres, ref posts, .. } => {
+                spans.push(self.expression_span(closure));
+                for assertion in pres.iter().chain(posts.iter()) {
+                    self.collect_spans(assertion, spans);
+                }
+            }
+        }
+    }
+
+    fn dump_assertion(&self, assertion: &typed::Assertion<'tcx>) -> AssertionDump {
+        let mut spans = vec![];
+        self.collect_spans(assertion, &mut spans);
+        let span = spans.into_iter().reduce(|a, b| a.to(b));
+        AssertionDump {
+            source: span.and_then(|span| self.snippet(span)),
+            span: span.map(|span| self.dump_span(span)),
+            structure: self.dump_structure(assertion),
+        }
+    }
+
+    fn dump_item(
+        &self,
+        def_id: DefId,
+        spec: &typed::SpecificationSet<'tcx>,
+        extern_spec: bool,
+    ) -> ItemSpecDump {
+        let mut item = ItemSpecDump {
+            def_path: self.tcx.def_path_str(def_id),
+            span: self.dump_span(self.tcx.def_span(def_id)),
+            kind: "",
+            extern_spec,
+            pure: false,
+            trusted: false,
+            preconditions: vec![],
+            postconditions: vec![],
+            pledges: vec![],
+            invariants: vec![],
+        };
+        match spec {
+            typed::SpecificationSet::Procedure(spec) => {
+                item.kind = "procedure";
+                item.pure = spec.pure;
+                item.trusted = spec.trusted;
+                item.preconditions = spec.pres.iter().map(|a| self.dump_assertion(a)).collect();
+                item.postconditions = spec.posts.iter().map(|a| self.dump_assertion(a)).collect();
+                item.pledges = spec.pledges
+                    .iter()
+                    .map(|pledge| PledgeDump {
+                        lhs: pledge.lhs.as_ref().map(|a| self.dump_assertion(a)),
+                        rhs: self.dump_assertion(&pledge.rhs),
+                    })
+                    .collect();
+            }
+            typed::SpecificationSet::Loop(spec) => {
+                item.kind = "loop";
+                item.invariants = spec.invariant.iter().map(|a| self.dump_assertion(a)).collect();
+            }
+            typed::SpecificationSet::Struct(specs) => {
+                item.kind = "struct";
+                item.invariants = specs.iter().map(|s| self.dump_assertion(&s.assertion)).collect();
+            }
+            typed::SpecificationSet::Static(spec) => {
+                item.kind = "static";
+                item.invariants = spec.invariant.iter().map(|a| self.dump_assertion(a)).collect();
+            }
+            typed::SpecificationSet::BodyAssertion(spec) => {
+                item.kind = if spec.is_assumption { "assumption" } else { "assertion" };
+                item.invariants = vec![self.dump_assertion(&spec.assertion)];
+            }
+        }
+        item
+    }
+}
+
+/// Convert all collected specifications to a serializable form. The items
+/// are sorted by their definition path, so that the output is deterministic.
+pub fn collect_spec_dumps<'tcx>(
+    env: &Environment<'tcx>,
+    def_spec: &typed::DefSpecificationMap<'tcx>,
+) -> Vec<ItemSpecDump> {
+    let dumper = SpecDumper { tcx: env.tcx() };
+    // Wrappers of external specifications are reported under the name of the
+    // function they specify.
+    let wrappers: HashSet<LocalDefId> = def_spec.extern_specs.values().cloned().collect();
+    let mut items: Vec<ItemSpecDump> = def_spec.specs
+        .iter()
+        .filter(|(local_id, _)| !wrappers.contains(local_id))
+        .map(|(local_id, spec)| dumper.dump_item(local_id.to_def_id(), spec, false))
+        .collect();
+    items.extend(def_spec.extern_specs
+        .iter()
+        .filter_map(|(def_id, local_id)| {
+            def_spec.specs.get(local_id).map(|spec| dumper.dump_item(*def_id, spec, true))
+        }));
+    items.sort_by(|a, b| {
+        (&a.def_path, a.span.line_start, a.span.column_start)
+            .cmp(&(&b.def_path, b.span.line_start, b.span.column_start))
+    });
+    items
+}
+
+/// Write all collected specifications as JSON to the file at `path`.
+pub fn dump_specs<'tcx>(
+    env: &Environment<'tcx>,
+    def_spec: &typed::DefSpecificationMap<'tcx>,
+    path: &str,
+) -> io::Result<()> {
+    let items = collect_spec_dumps(env, def_spec);
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, &items)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
@@ -1,13 +1,19 @@
 use rustc_hir::intravisit::{self, Visitor};
 use rustc_hir::def_id::DefId;
 use rustc_middle::hir::map::Map;
-use rustc_middle::ty::TyCtxt;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_middle::ty::subst::SubstsRef;
 use rustc_span::{Span, MultiSpan};
+use rustc_span::symbol::Symbol;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use crate::environment::Environment;
 use crate::PrustiError;
 
+/// The name of the module to which the driver adds the built-in
+/// specifications of the standard library (see the `std_specs` flag).
+pub const STD_SPECS_MODULE: &str = "prusti_std_specs";
+
 /// This struct is used to build a mapping of external functions to their
 /// Prusti specifications (see `extern_fn_map`).
 pub struct ExternSpecResolver<'tcx> {
@@ -16,12 +22,21 @@ pub struct ExternSpecResolver<'tcx> {
     /// Maps real functions (keyed by their `DefId`) to Prusti-generated fake
     /// functions with specifications. The mapping may also optionally contain
     /// the `DefId` of the implementing type to account for trait
-    /// implementations.
+    /// implementations. Trait methods are keyed by the `DefId` of the method
+    /// in the implementation of the specified type, if there is one.
     pub extern_fn_map: HashMap<DefId, (Option<DefId>, DefId)>,
 
     /// Duplicate specifications detected, keyed by the `DefId` of the function
     /// to be specified.
     spec_duplicates: HashMap<DefId, Vec<(DefId, Span)>>,
+
+    /// Specifications of the same function that were given for a different
+    /// implementing type than the one in `extern_fn_map`, keyed by the `DefId`
+    /// of the function to be specified.
+    spec_conflicts: HashMap<DefId, Vec<(DefId, Span)>>,
+
+    /// Functions in `extern_fn_map` whose specification is a built-in one.
+    std_specs: HashSet<DefId>,
 }
 
 impl<'tcx> ExternSpecResolver<'tcx> {
@@ -30,6 +45,8 @@ impl<'tcx> ExternSpecResolver<'tcx> {
             tcx: tcx,
             extern_fn_map: HashMap::new(),
             spec_duplicates: HashMap::new(),
+            spec_conflicts: HashMap::new(),
+            std_specs: HashSet::new(),
         }
     }
 
@@ -38,8 +55,14 @@ impl<'tcx> ExternSpecResolver<'tcx> {
     /// intravisit visitor.
     ///
     /// In case of duplicates, the function is added to `spec_duplicates`, and
-    /// will later (in `check_duplicates`) be reported as an error. Otherwise,
-    /// the function is added to `extern_fn_map`.
+    /// will later (in `check_duplicates`) be reported as an error. If the
+    /// function is already specified for a different implementing type, it is
+    /// added to `spec_conflicts` and also reported in `check_duplicates`.
+    /// Otherwise, the function is added to `extern_fn_map`.
+    ///
+    /// A built-in specification of the standard library is replaced by a
+    /// specification of the same function given in the crate, and is ignored
+    /// if it comes after one.
     pub fn add_extern_fn(
         &mut self,
         fn_kind: intravisit::FnKind<'tcx>,
@@ -54,7 +77,19 @@ impl<'tcx> ExternSpecResolver<'tcx> {
         };
         visitor.visit_fn(fn_kind, fn_decl, body_id, span, id);
         let current_def_id = self.tcx.hir().local_def_id(id).to_def_id();
-        if let Some((def_id, impl_ty, span)) = visitor.spec_found {
+        if let Some((def_id, impl_ty, _, span)) = visitor.spec_found {
+            let is_std_spec = self.is_std_spec(current_def_id);
+            if self.extern_fn_map.contains_key(&def_id) {
+                if is_std_spec {
+                    return;
+                }
+                if self.std_specs.remove(&def_id) {
+                    self.extern_fn_map.remove(&def_id);
+                }
+            }
+            if is_std_spec {
+                self.std_specs.insert(def_id);
+            }
             match self.extern_fn_map.get(&def_id) {
                 Some((existing_impl_ty, _)) if existing_impl_ty == &impl_ty => {
                     match self.spec_duplicates.get_mut(&def_id) {
@@ -66,16 +101,28 @@ impl<'tcx> ExternSpecResolver<'tcx> {
                         }
                     }
                 }
-                _ => {
-                    // TODO: what if def_id was present, but impl_ty was different?
+                Some(_) => {
+                    self.spec_conflicts
+                        .entry(def_id)
+                        .or_insert_with(Vec::new)
+                        .push((current_def_id, span));
+                }
+                None => {
                     self.extern_fn_map.insert(def_id, (impl_ty, current_def_id));
                 }
             }
         }
     }
 
-    /// Report errors for duplicate specifications found during specification
-    /// collection.
+    /// Is `def_id` part of the built-in specifications of the standard library?
+    fn is_std_spec(&self, def_id: DefId) -> bool {
+        let def_path = self.tcx.def_path(def_id);
+        def_path.data.first().and_then(|data| data.data.get_opt_name())
+            == Some(Symbol::intern(STD_SPECS_MODULE))
+    }
+
+    /// Report errors for duplicate and conflicting specifications found during
+    /// specification collection.
     pub fn check_duplicates(&self, env: &Environment<'tcx>) {
         for (&def_id, specs) in self.spec_duplicates.iter() {
             let function_name = env.get_item_name(def_id);
@@ -86,34 +133,167 @@ impl<'tcx> ExternSpecResolver<'tcx> {
                     .collect())
             ).emit(env);
         }
+        for (&def_id, specs) in self.spec_conflicts.iter() {
+            let function_name = env.get_item_name(def_id);
+            let (_, existing_spec_id) = self.extern_fn_map[&def_id];
+            PrustiError::incorrect(
+                format!(
+                    "conflicting specifications for {} given for different implementing types",
+                    function_name
+                ),
+                MultiSpan::from_spans(specs.iter()
+                    .map(|s| s.1)
+                    .collect())
+            ).add_note(
+                "this specification would be shadowed by the one given here",
+                Some(env.get_item_span(existing_spec_id)),
+            ).set_help(
+                "the specified function is shared by all implementing types, so only a single \
+                specification can be given for it"
+            ).emit(env);
+        }
+    }
+}
+
+/// This struct is used to build a mapping of instantiations of generic
+/// functions to the refinements of their specifications given with
+/// `#[refine_spec]` (see `refined_fn_map`).
+pub struct RefinedSpecResolver<'tcx> {
+    tcx: TyCtxt<'tcx>,
+
+    /// Maps instantiations of functions, keyed by the `DefId` of the function
+    /// and the generic arguments of the instantiation, to Prusti-generated
+    /// fake functions with the refined specifications. The generic arguments
+    /// may still contain type parameters of the fake function, in which case
+    /// the refinement applies to all the instantiations they match.
+    pub refined_fn_map: HashMap<(DefId, SubstsRef<'tcx>), DefId>,
+
+    /// Duplicate refinements detected, keyed like `refined_fn_map`.
+    spec_duplicates: HashMap<(DefId, SubstsRef<'tcx>), Vec<(DefId, Span)>>,
+}
+
+impl<'tcx> RefinedSpecResolver<'tcx> {
+    pub fn new(tcx: TyCtxt<'tcx>) -> Self {
+        Self {
+            tcx,
+            refined_fn_map: HashMap::new(),
+            spec_duplicates: HashMap::new(),
+        }
+    }
+
+    /// Registers a refinement of the specification of an instantiation. The
+    /// arguments for this function are the same as arguments given to a
+    /// function visit in an intravisit visitor.
+    ///
+    /// A second refinement of the same instantiation is added to
+    /// `spec_duplicates`, and will later (in `check_duplicates`) be reported
+    /// as an error.
+    pub fn add_refined_fn(
+        &mut self,
+        fn_kind: intravisit::FnKind<'tcx>,
+        fn_decl: &'tcx rustc_hir::FnDecl,
+        body_id: rustc_hir::BodyId,
+        span: Span,
+        id: rustc_hir::hir_id::HirId
+    ) {
+        let mut visitor = ExternSpecVisitor {
+            tcx: self.tcx,
+            spec_found: None,
+        };
+        visitor.visit_fn(fn_kind, fn_decl, body_id, span, id);
+        let current_def_id = self.tcx.hir().local_def_id(id).to_def_id();
+        if let Some((def_id, _, substs, span)) = visitor.spec_found {
+            let key = (def_id, self.tcx.erase_regions(substs));
+            if self.refined_fn_map.contains_key(&key) {
+                self.spec_duplicates
+                    .entry(key)
+                    .or_insert_with(Vec::new)
+                    .push((current_def_id, span));
+            } else {
+                self.refined_fn_map.insert(key, current_def_id);
+            }
+        }
+    }
+
+    /// Report errors for duplicate refinements found during specification
+    /// collection.
+    pub fn check_duplicates(&self, env: &Environment<'tcx>) {
+        for (key, specs) in self.spec_duplicates.iter() {
+            let (def_id, substs) = *key;
+            let mut span = MultiSpan::from_spans(specs.iter()
+                .map(|s| s.1)
+                .collect());
+            span.push_span_label(
+                env.get_item_span(self.refined_fn_map[key]),
+                "the instantiation is already refined here".to_string(),
+            );
+            PrustiError::incorrect(
+                format!(
+                    "duplicate refinement of the specification of {} for the generic arguments {:?}",
+                    env.get_item_name(def_id),
+                    substs,
+                ),
+                span,
+            ).emit(env);
+        }
     }
 }
 
 /// A visitor that is called on external specification methods, as generated by
 /// the external spec rewriter, looking specifically for the call to the
-/// external function.
+/// external function. The generic arguments of the call are recorded too,
+/// which is how refinements (see `RefinedSpecResolver`) determine the
+/// instantiation they refine.
 ///
 /// TODO: is the HIR representation stable enought that this could be
 /// accomplished by a nested match rather than a full visitor?
 struct ExternSpecVisitor<'tcx> {
     tcx: TyCtxt<'tcx>,
-    spec_found: Option<(DefId, Option<DefId>, Span)>,
+    spec_found: Option<(DefId, Option<DefId>, SubstsRef<'tcx>, Span)>,
 }
 
-/// Gets the `DefId` from the given path.
+/// Gets the `DefId` of the implementing type from the given path. Both
+/// `Type::method` and `<Type as Trait>::method` paths are supported.
 fn get_impl_type<'tcx>(qself: &rustc_hir::QPath<'tcx>) -> Option<DefId> {
-    if let rustc_hir::QPath::TypeRelative(ty, _) = qself {
-        if let rustc_hir::TyKind::Path(qpath) = &ty.kind {
-            if let rustc_hir::QPath::Resolved(_, path) = qpath {
-                if let rustc_hir::def::Res::Def(_, id) = path.res {
-                    return Some(id);
-                }
+    let self_ty = match qself {
+        rustc_hir::QPath::TypeRelative(ty, _) => ty,
+        rustc_hir::QPath::Resolved(Some(ty), _) => ty,
+        _ => return None,
+    };
+    if let rustc_hir::TyKind::Path(qpath) = &self_ty.kind {
+        if let rustc_hir::QPath::Resolved(_, path) = qpath {
+            if let rustc_hir::def::Res::Def(_, id) = path.res {
+                return Some(id);
             }
         }
     }
     return None;
 }
 
+/// If `def_id` is a trait method, resolves it to the method of the
+/// implementation selected by `substs`. This way, a specification written for
+/// a trait method of a specific implementor (e.g. `Iterator::next` of
+/// `std::vec::IntoIter`) is not applied to all the other implementors.
+///
+/// Returns `def_id` and `substs` unchanged if it is not a trait method or if
+/// the implementation cannot be determined (e.g. the implementor does not
+/// override a default method).
+fn resolve_trait_method<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    caller_def_id: DefId,
+    def_id: DefId,
+    substs: SubstsRef<'tcx>,
+) -> (DefId, SubstsRef<'tcx>) {
+    if tcx.trait_of_item(def_id).is_none() {
+        return (def_id, substs);
+    }
+    let param_env = tcx.param_env(caller_def_id);
+    match ty::Instance::resolve(tcx, param_env, def_id, substs) {
+        Ok(Some(instance)) => (instance.def_id(), instance.substs),
+        _ => (def_id, substs),
+    }
+}
+
 impl<'tcx> Visitor<'tcx> for ExternSpecVisitor<'tcx> {
     type Map = Map<'tcx>;
 
@@ -128,9 +308,18 @@ impl<'tcx> Visitor<'tcx> for ExternSpecVisitor<'tcx> {
         }
         if let rustc_hir::ExprKind::Call(ref callee_expr, ref _arguments) = ex.kind {
             if let rustc_hir::ExprKind::Path(ref qself) = callee_expr.kind {
-                let res = self.tcx.typeck(callee_expr.hir_id.owner).qpath_res(qself, callee_expr.hir_id);
+                let owner = callee_expr.hir_id.owner;
+                let typeck_results = self.tcx.typeck(owner);
+                let res = typeck_results.qpath_res(qself, callee_expr.hir_id);
                 if let rustc_hir::def::Res::Def(_, def_id) = res {
-                    self.spec_found = Some((def_id, get_impl_type(qself), ex.span));
+                    let impl_ty = get_impl_type(qself);
+                    let substs = typeck_results.node_substs(callee_expr.hir_id);
+                    let (def_id, substs) = if impl_ty.is_some() {
+                        resolve_trait_method(self.tcx, owner.to_def_id(), def_id, substs)
+                    } else {
+                        (def_id, substs)
+                    };
+                    self.spec_found = Some((def_id, impl_ty, substs, ex.span));
                     return;
                 }
             }
@@ -1,28 +1,33 @@
 use prusti_specs::specifications::{json::Assertion as JsonAssertion, SpecType};
 use rustc_ast::ast;
 use rustc_hir::{intravisit, ItemKind};
+use rustc_hir::def::DefKind;
 use rustc_middle::hir::map::Map;
-use rustc_middle::ty::TyCtxt;
+use rustc_middle::ty::{self, DefIdTree, TyCtxt, TypeckResults};
 use rustc_span::{Span, MultiSpan};
 use rustc_span::symbol::Symbol;
 use rustc_hir::def_id::{DefId, LocalDefId};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use crate::environment::Environment;
 use crate::PrustiError;
 use crate::utils::{
-    has_spec_only_attr, has_extern_spec_attr, read_prusti_attr, read_prusti_attrs, has_prusti_attr
+    has_spec_only_attr, has_extern_spec_attr, has_refine_spec_attr, read_prusti_attr, read_prusti_attrs, has_prusti_attr,
+    read_prusti_attr_int_arg,
+    has_any_prusti_attr,
 };
-use log::debug;
+use log::{debug, info, trace};
 
 pub mod external;
 pub mod typed;
 pub mod checker;
+pub mod dump;
+pub mod cross_crate;
 
 use typed::StructuralToTyped;
 use typed::SpecIdRef;
 use std::fmt;
-use crate::specs::external::ExternSpecResolver;
+use crate::specs::external::{ExternSpecResolver, RefinedSpecResolver};
 use prusti_specs::specifications::common::SpecificationId;
 
 struct SpecItem {
@@ -44,6 +49,9 @@ struct ProcedureSpecRef {
     spec_id_refs: Vec<prusti_specs::specifications::common::SpecIdRef>,
     pure: bool,
     trusted: bool,
+    pure_memoized: bool,
+    terminates: bool,
+    smt_rlimit: Option<u64>,
 }
 
 /// Specification collector, intended to be applied as a visitor over the crate
@@ -54,11 +62,18 @@ pub struct SpecCollector<'a, 'tcx: 'a> {
     tcx: TyCtxt<'tcx>,
     env: &'a Environment<'tcx>,
     extern_resolver: ExternSpecResolver<'tcx>,
+    refined_resolver: RefinedSpecResolver<'tcx>,
+
+    /// Items that contain a Prusti attribute in their own HIR (including the
+    /// bodies of their functions and closures) or in a nested item. The
+    /// bodies of the other functions contain no specifications, so they are
+    /// not visited.
+    items_with_prusti_attrs: HashSet<LocalDefId>,
 
     /// Collected assertions before deserialisation.
     spec_items: Vec<SpecItem>,
 
-    typed_expressions: HashMap<String, LocalDefId>,
+    typed_expressions: HashMap<String, DefId>,
 
     /// Collected, deserialised assertions, keyed by their specification id.
     typed_specs: typed::SpecificationMap<'tcx>,
@@ -66,6 +81,12 @@ pub struct SpecCollector<'a, 'tcx: 'a> {
     /// Resolved specifications.
     procedure_specs: HashMap<LocalDefId, ProcedureSpecRef>,
     loop_specs: HashMap<LocalDefId, Vec<SpecificationId>>,
+    loop_variants: HashMap<LocalDefId, Vec<SpecificationId>>,
+    /// `prusti_assert!` and `prusti_assume!` closures, with whether they are
+    /// assumptions.
+    body_assertions: HashMap<LocalDefId, (SpecificationId, bool)>,
+    static_specs: HashMap<LocalDefId, Vec<SpecificationId>>,
+    struct_specs: HashMap<LocalDefId, Vec<SpecificationId>>,
 }
 
 impl<'a, 'tcx> SpecCollector<'a, 'tcx> {
@@ -73,12 +94,18 @@ impl<'a, 'tcx> SpecCollector<'a, 'tcx> {
         Self {
             tcx: env.tcx(),
             env,
+            items_with_prusti_attrs: find_items_with_prusti_attrs(env.tcx()),
             spec_items: Vec::new(),
             typed_specs: HashMap::new(),
             procedure_specs: HashMap::new(),
             loop_specs: HashMap::new(),
+            loop_variants: HashMap::new(),
+            body_assertions: HashMap::new(),
+            static_specs: HashMap::new(),
+            struct_specs: HashMap::new(),
             typed_expressions: HashMap::new(),
             extern_resolver: ExternSpecResolver::new(env.tcx()),
+            refined_resolver: RefinedSpecResolver::new(env.tcx()),
         }
     }
 
@@ -87,52 +114,112 @@ impl<'a, 'tcx> SpecCollector<'a, 'tcx> {
         self.typed_specs = spec_items
             .into_iter()
             .map(|spec_item| {
-                let assertion = reconstruct_typed_assertion(
+                let mut assertion = reconstruct_typed_assertion(
                     spec_item.specification,
                     &self.typed_expressions,
                     self.env
                 );
+                // Triggers are only used by the verifier.
+                if !prusti_common::config::no_verify() {
+                    check_triggers(&mut assertion, &self.typed_expressions, self.env);
+                }
                 (spec_item.spec_id, assertion)
             })
             .collect()
     }
 
     pub fn build_def_specs(mut self, env: &Environment<'tcx>) -> typed::DefSpecificationMap<'tcx> {
+        if cross_crate::is_library(self.tcx) {
+            // Wrappers of external specifications and of refinements are not
+            // part of the API of the library.
+            let wrappers: HashSet<DefId> = self.extern_resolver.extern_fn_map
+                .values()
+                .map(|(_, spec_id)| *spec_id)
+                .chain(self.refined_resolver.refined_fn_map.values().copied())
+                .collect();
+            cross_crate::export_specs(
+                env,
+                &self.spec_items,
+                &self.typed_expressions,
+                self.procedure_specs
+                    .iter()
+                    .filter(|(local_id, _)| !wrappers.contains(&local_id.to_def_id())),
+            );
+        }
         self.prepare_typed_procedure_specs();
 
         let mut def_spec = typed::DefSpecificationMap::new();
         self.determine_procedure_specs(&mut def_spec);
         self.determine_extern_specs(&mut def_spec, env);
+        self.determine_refined_specs(&mut def_spec, env);
+        // Only the verifier uses the specifications of other crates.
+        if !prusti_common::config::no_verify() {
+            cross_crate::import_specs(env, &mut def_spec);
+        }
         self.determine_loop_specs(&mut def_spec);
+        self.determine_body_assertion_specs(&mut def_spec);
+        self.determine_static_specs(&mut def_spec);
         self.determine_struct_specs(&mut def_spec);
+        // Only the verifier requires specifications to be pure.
+        if !prusti_common::config::no_verify() {
+            let mut impure_calls = vec![];
+            for assertion in self.typed_specs.values() {
+                find_impure_calls(assertion, &def_spec, self.tcx, &mut impure_calls);
+            }
+            // Report the errors in the order of the source code.
+            impure_calls.sort();
+            for (call_span, def_id) in impure_calls {
+                report_impure_call(call_span, def_id, env);
+            }
+        }
+        log_spec_counts(&def_spec);
         def_spec
     }
 
     fn determine_extern_specs(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>, env: &Environment<'tcx>) {
         self.extern_resolver.check_duplicates(env);
-        // TODO: do something with the traits
         for (real_id, (_, spec_id)) in self.extern_resolver.extern_fn_map.iter() {
             if let Some(local_id) = real_id.as_local() {
                 if def_spec.specs.contains_key(&local_id) {
+                    let mut span = MultiSpan::from_span(env.get_item_span(*spec_id));
+                    span.push_span_label(
+                        env.get_item_span(*real_id),
+                        "the existing specification is attached here".to_string(),
+                    );
                     PrustiError::incorrect(
                         format!("external specification provided for {}, which already has a specification",
                             env.get_item_name(*real_id)),
-                        MultiSpan::from_span(env.get_item_span(*spec_id)),
+                        span,
                     ).emit(env);
+                    // Keep the local specification and continue, so that
+                    // other errors are reported in the same run.
+                    continue;
                 }
             }
             if let Some(_spec) = def_spec.specs.get(&spec_id.expect_local()) {
+                debug!("Extern spec {:?} is attached to {:?}", spec_id, real_id);
                 def_spec.extern_specs.insert(*real_id, spec_id.expect_local());
             }
         }
     }
 
+    fn determine_refined_specs(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>, env: &Environment<'tcx>) {
+        self.refined_resolver.check_duplicates(env);
+        for (&(real_id, substs), spec_id) in self.refined_resolver.refined_fn_map.iter() {
+            if let Some(_spec) = def_spec.specs.get(&spec_id.expect_local()) {
+                debug!("Refinement {:?} is attached to {:?} with {:?}", spec_id, real_id, substs);
+                def_spec.refined_specs.insert((real_id, substs), spec_id.expect_local());
+            }
+        }
+    }
+
     fn determine_procedure_specs(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>) {
         for (local_id, refs) in self.procedure_specs.iter() {
             let mut pres = Vec::new();
             let mut posts = Vec::new();
             let mut pledges = Vec::new();
             let mut predicate_body = None;
+            let mut decreases = None;
             for spec_id_ref in &refs.spec_id_refs {
                 match spec_id_ref {
                     SpecIdRef::Precondition(spec_id) => {
@@ -151,6 +238,9 @@ impl<'a, 'tcx> SpecCollector<'a, 'tcx> {
                     SpecIdRef::Predicate(spec_id) => {
                         predicate_body = Some(self.typed_specs.get(&spec_id).unwrap().clone());
                     }
+                    SpecIdRef::Decreases(spec_id) => {
+                        decreases = Some(self.typed_specs.get(&spec_id).unwrap().clone());
+                    }
                 }
             }
             def_spec.specs.insert(
@@ -160,8 +250,12 @@ impl<'a, 'tcx> SpecCollector<'a, 'tcx> {
                     posts,
                     pledges,
                     predicate_body,
+                    decreases,
                     pure: refs.pure,
                     trusted: refs.trusted,
+                    pure_memoized: refs.pure_memoized,
+                    terminates: refs.terminates,
+                    smt_rlimit: refs.smt_rlimit,
                 })
             );
         }
@@ -173,79 +267,504 @@ impl<'a, 'tcx> SpecCollector<'a, 'tcx> {
                 .map(|spec_id| self.typed_specs.get(&spec_id).unwrap().clone())
                 .collect();
             def_spec.specs.insert(*local_id, typed::SpecificationSet::Loop(typed::LoopSpecification {
+                invariant: specs,
+                variant: vec![],
+            }));
+        }
+        for (local_id, spec_ids) in self.loop_variants.iter() {
+            let specs = spec_ids.iter()
+                .map(|spec_id| self.typed_specs.get(&spec_id).unwrap().clone())
+                .collect();
+            def_spec.specs.insert(*local_id, typed::SpecificationSet::Loop(typed::LoopSpecification {
+                invariant: vec![],
+                variant: specs,
+            }));
+        }
+    }
+
+    fn determine_body_assertion_specs(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>) {
+        for (local_id, (spec_id, is_assumption)) in self.body_assertions.iter() {
+            let assertion = self.typed_specs.get(spec_id).unwrap().clone();
+            def_spec.specs.insert(
+                *local_id,
+                typed::SpecificationSet::BodyAssertion(typed::BodyAssertionSpecification {
+                    assertion,
+                    is_assumption: *is_assumption,
+                }),
+            );
+        }
+    }
+
+    fn determine_static_specs(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>) {
+        for (local_id, spec_ids) in self.static_specs.iter() {
+            let specs = spec_ids.iter()
+                .map(|spec_id| self.typed_specs.get(&spec_id).unwrap().clone())
+                .collect();
+            def_spec.specs.insert(*local_id, typed::SpecificationSet::Static(typed::StaticSpecification {
                 invariant: specs
             }));
         }
     }
 
-    // TODO: struct specs
-    fn determine_struct_specs(&self, _def_spec: &mut typed::DefSpecificationMap<'tcx>) {}
-}
+    /// Collect the invariants attached to a static or a (possibly associated)
+    /// constant. They are only recorded in the specification map.
+    fn collect_static_specs(&mut self, local_id: LocalDefId, attrs: &[ast::Attribute]) {
+        let spec_ids = self.read_invariant_spec_ids(local_id, attrs);
+        if !spec_ids.is_empty() {
+            self.static_specs.entry(local_id).or_insert(vec![]).extend(spec_ids);
+        }
+    }
 
-fn get_procedure_spec_ids(def_id: DefId, attrs: &[ast::Attribute]) -> Option<ProcedureSpecRef> {
-    let mut spec_id_refs = vec![];
+    /// Collect the invariants attached to a struct or an enum.
+    fn collect_struct_specs(&mut self, local_id: LocalDefId, attrs: &[ast::Attribute]) {
+        let spec_ids = self.read_invariant_spec_ids(local_id, attrs);
+        if !spec_ids.is_empty() {
+            self.struct_specs.entry(local_id).or_insert(vec![]).extend(spec_ids);
+        }
+    }
 
-    let parse_spec_id = |spec_id: String| -> SpecificationId {
-        spec_id.try_into().expect(
-            &format!("cannot parse the spec_id attached to {:?}", def_id)
-        )
-    };
+    fn read_invariant_spec_ids(&self, local_id: LocalDefId, attrs: &[ast::Attribute]) -> Vec<SpecificationId> {
+        let def_id = local_id.to_def_id();
+        read_prusti_attrs("inv_spec_id_ref", attrs)
+            .into_iter()
+            .filter_map(|raw_spec_id| parse_spec_id(self.env, def_id, raw_spec_id))
+            .collect()
+    }
 
-    spec_id_refs.extend(
-        read_prusti_attrs("pre_spec_id_ref", attrs).into_iter().map(
-            |raw_spec_id| SpecIdRef::Precondition(parse_spec_id(raw_spec_id))
-        )
-    );
-    spec_id_refs.extend(
-        read_prusti_attrs("post_spec_id_ref", attrs).into_iter().map(
-            |raw_spec_id| SpecIdRef::Postcondition(parse_spec_id(raw_spec_id))
-        )
-    );
-    spec_id_refs.extend(
-        read_prusti_attrs("pledge_spec_id_ref", attrs).into_iter().map(
-            |value| {
-                let mut value = value.splitn(2, ":");
-                let raw_lhs_spec_id = value.next().unwrap();
-                let raw_rhs_spec_id = value.next().unwrap();
-                let lhs_spec_id = if !raw_lhs_spec_id.is_empty() {
-                    Some(parse_spec_id(raw_lhs_spec_id.to_string()))
-                } else {
-                    None
-                };
-                let rhs_spec_id = parse_spec_id(raw_rhs_spec_id.to_string());
-                SpecIdRef::Pledge{ lhs: lhs_spec_id, rhs: rhs_spec_id }
+    fn determine_struct_specs(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>) {
+        for (local_id, spec_ids) in self.struct_specs.iter() {
+            let specs = spec_ids.iter()
+                .map(|spec_id| typed::Specification {
+                    typ: SpecType::Invariant,
+                    assertion: self.typed_specs.get(&spec_id).unwrap().clone(),
+                })
+                .collect();
+            def_spec.specs.insert(*local_id, typed::SpecificationSet::Struct(specs));
+        }
+    }
+}
+
+/// Log a summary of the collected specifications. Useful to diagnose why a
+/// specification was not picked up.
+fn log_spec_counts(def_spec: &typed::DefSpecificationMap) {
+    let mut procedure_specs = 0;
+    let mut loop_invariants = 0;
+    for (local_id, spec) in def_spec.specs.iter() {
+        match spec {
+            typed::SpecificationSet::Procedure(_) => {
+                // Wrappers of external specifications and of refinements are
+                // counted separately.
+                if !def_spec.extern_specs.values().any(|id| id == local_id)
+                    && !def_spec.refined_specs.values().any(|id| id == local_id)
+                {
+                    procedure_specs += 1;
+                }
             }
-        )
-    );
-    spec_id_refs.extend(
-        read_prusti_attr("pred_spec_id_ref", attrs).map(
-            |raw_spec_id| SpecIdRef::Predicate(parse_spec_id(raw_spec_id))
-        )
+            typed::SpecificationSet::Loop(spec) => loop_invariants += spec.invariant.len(),
+            typed::SpecificationSet::Struct(_)
+            | typed::SpecificationSet::Static(_)
+            | typed::SpecificationSet::BodyAssertion(_) => {}
+        }
+    }
+    info!(
+        "collected {} procedure specs, {} extern specs, {} loop invariants",
+        procedure_specs,
+        def_spec.extern_specs.len(),
+        loop_invariants,
     );
+}
+
+/// Parse a specification id attached to `def_id`. On failure, an error is
+/// reported and `None` is returned, so that the caller can skip the item.
+fn parse_spec_id(env: &Environment, def_id: DefId, raw_spec_id: String) -> Option<SpecificationId> {
+    match raw_spec_id.clone().try_into() {
+        Ok(spec_id) => Some(spec_id),
+        Err(_) => {
+            PrustiError::internal(
+                format!("cannot parse the specification id {:?} attached to {}",
+                    raw_spec_id, env.get_item_name(def_id)),
+                MultiSpan::from_span(env.get_item_span(def_id)),
+            ).set_help(
+                "this usually happens when a dependency was compiled with a version of \
+                prusti-contracts that does not match the version of Prusti"
+            ).emit(env);
+            None
+        }
+    }
+}
+
+fn get_procedure_spec_ids(
+    env: &Environment,
+    def_id: DefId,
+    attrs: &[ast::Attribute],
+) -> Option<ProcedureSpecRef> {
+    let mut spec_id_refs = vec![];
+
+    let parse = |raw_spec_id: String| parse_spec_id(env, def_id, raw_spec_id);
+
+    for raw_spec_id in read_prusti_attrs("pre_spec_id_ref", attrs) {
+        spec_id_refs.push(SpecIdRef::Precondition(parse(raw_spec_id)?));
+    }
+    for raw_spec_id in read_prusti_attrs("post_spec_id_ref", attrs) {
+        spec_id_refs.push(SpecIdRef::Postcondition(parse(raw_spec_id)?));
+    }
+    for value in read_prusti_attrs("pledge_spec_id_ref", attrs) {
+        let mut value = value.splitn(2, ":");
+        let raw_lhs_spec_id = value.next().unwrap();
+        let raw_rhs_spec_id = value.next().unwrap();
+        let lhs_spec_id = if !raw_lhs_spec_id.is_empty() {
+            Some(parse(raw_lhs_spec_id.to_string())?)
+        } else {
+            None
+        };
+        let rhs_spec_id = parse(raw_rhs_spec_id.to_string())?;
+        spec_id_refs.push(SpecIdRef::Pledge{ lhs: lhs_spec_id, rhs: rhs_spec_id });
+    }
+    if let Some(raw_spec_id) = read_prusti_attr("pred_spec_id_ref", attrs) {
+        spec_id_refs.push(SpecIdRef::Predicate(parse(raw_spec_id)?));
+    }
+    if let Some(raw_spec_id) = read_prusti_attr("decreases_spec_id_ref", attrs) {
+        spec_id_refs.push(SpecIdRef::Decreases(parse(raw_spec_id)?));
+    }
     debug!("Function {:?} has specification ids {:?}", def_id, spec_id_refs);
 
     let pure = has_prusti_attr(attrs, "pure");
     let trusted = has_prusti_attr(attrs, "trusted");
+    let pure_memoized = has_prusti_attr(attrs, "pure_memoized");
+    let terminates = has_prusti_attr(attrs, "terminates");
+    let smt_rlimit = match read_prusti_attr_int_arg("smt_rlimit", attrs) {
+        Some(Ok(rlimit)) => Some(rlimit),
+        Some(Err(span)) => {
+            PrustiError::incorrect(
+                "the argument of `prusti::smt_rlimit` must be an integer literal",
+                MultiSpan::from_span(span),
+            ).emit(env);
+            None
+        }
+        None => None,
+    };
 
-    if pure || trusted || spec_id_refs.len() > 0 {
+    if pure || trusted || terminates || smt_rlimit.is_some() || spec_id_refs.len() > 0 {
         Some(ProcedureSpecRef {
             spec_id_refs,
             pure,
             trusted,
+            pure_memoized,
+            terminates,
+            smt_rlimit,
         })
     } else {
         None
     }
 }
 
+/// Find the items that contain a Prusti attribute, by looking up the owners of
+/// all attributes of the crate.
+fn find_items_with_prusti_attrs(tcx: TyCtxt) -> HashSet<LocalDefId> {
+    let mut items = HashSet::new();
+    for (hir_id, attrs) in &tcx.hir().krate().attrs {
+        if !has_any_prusti_attr(attrs) {
+            continue;
+        }
+        // Specifications of nested items are only found by visiting the body
+        // of the enclosing item.
+        let mut def_id = hir_id.owner.to_def_id();
+        loop {
+            if !items.insert(def_id.expect_local()) {
+                break;
+            }
+            match tcx.parent(def_id) {
+                Some(parent) => def_id = parent,
+                None => break,
+            }
+        }
+    }
+    items
+}
+
 fn reconstruct_typed_assertion<'tcx>(
     assertion: JsonAssertion,
-    typed_expressions: &HashMap<String, LocalDefId>,
+    typed_expressions: &HashMap<String, DefId>,
     env: &Environment<'tcx>
 ) -> typed::Assertion<'tcx> {
     assertion.to_typed(typed_expressions, env)
 }
 
+/// Report user-provided triggers that cannot be used by the verifier (see
+/// `is_valid_trigger`). Invalid triggers are dropped, so that the encoder only
+/// sees valid triggers.
+fn check_triggers<'tcx>(
+    assertion: &mut typed::Assertion<'tcx>,
+    typed_expressions: &HashMap<String, DefId>,
+    env: &Environment<'tcx>
+) {
+    match &mut *assertion.kind {
+        typed::AssertionKind::Expr(_) => {}
+        typed::AssertionKind::And(assertions) => {
+            for assertion in assertions {
+                check_triggers(assertion, typed_expressions, env);
+            }
+        }
+        typed::AssertionKind::Implies(lhs, rhs) => {
+            check_triggers(lhs, typed_expressions, env);
+            check_triggers(rhs, typed_expressions, env);
+        }
+        typed::AssertionKind::TypeCond(_, body) => {
+            check_triggers(body, typed_expressions, env);
+        }
+        typed::AssertionKind::ForAll(vars, trigger_set, body)
+        | typed::AssertionKind::Exists(vars, trigger_set, body) => {
+            check_triggers(body, typed_expressions, env);
+            let quantifier = typed_expressions[&format!("{}_{}", vars.spec_id, vars.id)];
+            let bound_vars = get_closure_params(env.tcx(), quantifier.expect_local());
+            trigger_set.0.retain(|trigger| is_valid_trigger(trigger, &bound_vars, env));
+        }
+        typed::AssertionKind::SpecEntailment { pres, posts, .. } => {
+            for assertion in pres.iter_mut().chain(posts.iter_mut()) {
+                check_triggers(assertion, typed_expressions, env);
+            }
+        }
+    }
+}
+
+/// Collect the calls of impure functions of this crate in a specification,
+/// which are reported at the span of the call. Calls of functions of other
+/// crates are reported by the encoder, which knows the functions that have a
+/// built-in encoding.
+fn find_impure_calls<'tcx>(
+    assertion: &typed::Assertion<'tcx>,
+    def_spec: &typed::DefSpecificationMap<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    impure_calls: &mut Vec<(Span, DefId)>,
+) {
+    match &*assertion.kind {
+        typed::AssertionKind::Expr(expr) => {
+            impure_calls.extend(find_impure_local_calls(expr, def_spec, tcx));
+        }
+        typed::AssertionKind::And(assertions) => {
+            for assertion in assertions {
+                find_impure_calls(assertion, def_spec, tcx, impure_calls);
+            }
+        }
+        typed::AssertionKind::Implies(lhs, rhs) => {
+            find_impure_calls(lhs, def_spec, tcx, impure_calls);
+            find_impure_calls(rhs, def_spec, tcx, impure_calls);
+        }
+        typed::AssertionKind::TypeCond(_, body)
+        | typed::AssertionKind::ForAll(_, _, body)
+        | typed::AssertionKind::Exists(_, _, body) => {
+            find_impure_calls(body, def_spec, tcx, impure_calls);
+        }
+        typed::AssertionKind::SpecEntailment { pres, posts, .. } => {
+            for assertion in pres.iter().chain(posts.iter()) {
+                find_impure_calls(assertion, def_spec, tcx, impure_calls);
+            }
+        }
+    }
+}
+
+/// The calls in the expression of functions of this crate that are not pure.
+/// Methods of traits are ignored, because the called implementation is only
+/// known to the encoder.
+fn find_impure_local_calls<'tcx>(
+    expr: &typed::Expression,
+    def_spec: &typed::DefSpecificationMap<'tcx>,
+    tcx: TyCtxt<'tcx>,
+) -> Vec<(Span, DefId)> {
+    let local_id = expr.expr.expect_local();
+    let hir_id = tcx.hir().local_def_id_to_hir_id(local_id);
+    let body = tcx.hir().body(tcx.hir().body_owned_by(hir_id));
+    let mut visitor = CalleeVisitor {
+        typeck_results: tcx.typeck(local_id),
+        callees: vec![],
+    };
+    intravisit::Visitor::visit_expr(&mut visitor, &body.value);
+    visitor.callees
+        .into_iter()
+        .filter(|(_, def_id)| {
+            def_id.is_local()
+                && matches!(tcx.def_kind(*def_id), DefKind::Fn | DefKind::AssocFn)
+                && tcx.trait_of_item(*def_id).is_none()
+                && !matches!(
+                    def_spec.get(def_id),
+                    Some(typed::SpecificationSet::Procedure(spec)) if spec.pure
+                )
+        })
+        .collect()
+}
+
+/// Report a call of an impure function in a specification, with a suggestion
+/// to mark the function as `#[pure]`.
+fn report_impure_call(call_span: Span, def_id: DefId, env: &Environment) {
+    let def_span = env.tcx().def_span(def_id);
+    let mut span = MultiSpan::from_span(call_span);
+    span.push_span_label(def_span, "the function is defined here".to_string());
+    let indentation = env.tcx().sess.source_map().span_to_margin(def_span).unwrap_or(0);
+    PrustiError::incorrect(
+        format!("use of impure function {:?} in a specification", env.get_item_name(def_id)),
+        span,
+    ).set_help(
+        "only functions marked as `#[pure]` can be used in specifications"
+    ).set_suggestion(
+        "mark the function as pure",
+        def_span.shrink_to_lo(),
+        format!("#[pure]\n{}", " ".repeat(indentation)),
+    ).emit(env);
+}
+
+/// Collects the functions called in an expression, together with the spans
+/// of the calls.
+struct CalleeVisitor<'tcx> {
+    typeck_results: &'tcx TypeckResults<'tcx>,
+    callees: Vec<(Span, DefId)>,
+}
+
+impl<'tcx> intravisit::Visitor<'tcx> for CalleeVisitor<'tcx> {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        intravisit::NestedVisitorMap::None
+    }
+
+    fn visit_expr(&mut self, expr: &'tcx rustc_hir::Expr<'tcx>) {
+        match expr.kind {
+            rustc_hir::ExprKind::Call(func, _) => {
+                if let ty::TyKind::FnDef(def_id, _) = self.typeck_results.node_type(func.hir_id).kind() {
+                    self.callees.push((expr.span, *def_id));
+                }
+            }
+            rustc_hir::ExprKind::MethodCall(..) => {
+                if let Some(def_id) = self.typeck_results.type_dependent_def_id(expr.hir_id) {
+                    self.callees.push((expr.span, def_id));
+                }
+            }
+            _ => {}
+        }
+        intravisit::walk_expr(self, expr);
+    }
+}
+
+/// The bindings introduced by the parameters of a closure.
+fn get_closure_params(tcx: TyCtxt, closure: LocalDefId) -> Vec<(rustc_hir::HirId, Symbol)> {
+    let hir_id = tcx.hir().local_def_id_to_hir_id(closure);
+    let body = tcx.hir().body(tcx.hir().body_owned_by(hir_id));
+    body.params
+        .iter()
+        .filter_map(|param| match param.pat.kind {
+            rustc_hir::PatKind::Binding(_, _, ident, _) => Some((param.pat.hir_id, ident.name)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Check a trigger, reporting the reasons why it cannot be used by the
+/// verifier: every term has to be a function call whose arguments only
+/// contain function calls, variables and constants, and the terms have to
+/// mention all bound variables of the quantifier. This is the only check of
+/// the triggers; the encoder relies on it.
+fn is_valid_trigger(
+    trigger: &typed::Trigger,
+    bound_vars: &[(rustc_hir::HirId, Symbol)],
+    env: &Environment,
+) -> bool {
+    let tcx = env.tcx();
+    let mut valid = true;
+    let mut mentioned_locals = HashSet::new();
+    let mut term_spans = vec![];
+    for term in trigger.terms() {
+        let span = tcx.def_span(term.expr);
+        let hir_id = tcx.hir().local_def_id_to_hir_id(term.expr.expect_local());
+        let body = tcx.hir().body(tcx.hir().body_owned_by(hir_id));
+        let term_expr = peel_term_block(&body.value);
+        let mut visitor = TriggerTermVisitor {
+            invalid_subterms: vec![],
+            locals: HashSet::new(),
+        };
+        intravisit::Visitor::visit_expr(&mut visitor, term_expr);
+        if !matches!(
+            term_expr.kind,
+            rustc_hir::ExprKind::Call(..) | rustc_hir::ExprKind::MethodCall(..)
+        ) {
+            PrustiError::incorrect(
+                "trigger terms must be function calls",
+                MultiSpan::from_span(span),
+            ).set_help(
+                "arithmetic expressions, comparisons, conditionals and variables cannot be used \
+                as trigger terms"
+            ).emit(env);
+            valid = false;
+        } else if !visitor.invalid_subterms.is_empty() {
+            PrustiError::incorrect(
+                "the arguments of trigger terms can only contain function calls, variables and constants",
+                MultiSpan::from_spans(visitor.invalid_subterms),
+            ).emit(env);
+            valid = false;
+        }
+        mentioned_locals.extend(visitor.locals);
+        term_spans.push(span);
+    }
+    for (hir_id, name) in bound_vars {
+        if !mentioned_locals.contains(hir_id) {
+            PrustiError::incorrect(
+                format!("the trigger does not mention the bound variable `{}`", name),
+                MultiSpan::from_spans(term_spans.clone()),
+            ).emit(env);
+            valid = false;
+        }
+    }
+    valid
+}
+
+/// The expression of a trigger term, without the block that wraps it in the
+/// body of its type-checking closure.
+fn peel_term_block<'tcx>(expr: &'tcx rustc_hir::Expr<'tcx>) -> &'tcx rustc_hir::Expr<'tcx> {
+    match expr.kind {
+        rustc_hir::ExprKind::Block(block, _) if block.stmts.is_empty() && block.expr.is_some() => {
+            peel_term_block(block.expr.unwrap())
+        }
+        rustc_hir::ExprKind::DropTemps(inner) => peel_term_block(inner),
+        _ => expr,
+    }
+}
+
+/// Collects the local variables used in a trigger term and the subterms that
+/// are neither function calls, nor places, nor constants.
+struct TriggerTermVisitor {
+    invalid_subterms: Vec<Span>,
+    locals: HashSet<rustc_hir::HirId>,
+}
+
+impl<'tcx> intravisit::Visitor<'tcx> for TriggerTermVisitor {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        intravisit::NestedVisitorMap::None
+    }
+
+    fn visit_expr(&mut self, expr: &'tcx rustc_hir::Expr<'tcx>) {
+        match expr.kind {
+            rustc_hir::ExprKind::Path(rustc_hir::QPath::Resolved(None, path)) => {
+                if let rustc_hir::def::Res::Local(hir_id) = path.res {
+                    self.locals.insert(hir_id);
+                }
+            }
+            rustc_hir::ExprKind::Call(..)
+            | rustc_hir::ExprKind::MethodCall(..)
+            | rustc_hir::ExprKind::Path(..)
+            | rustc_hir::ExprKind::Lit(..)
+            | rustc_hir::ExprKind::Field(..)
+            | rustc_hir::ExprKind::AddrOf(..)
+            | rustc_hir::ExprKind::Unary(rustc_hir::UnOp::Deref, ..) => {}
+            _ => {
+                // The subterms of an invalid subterm are not reported.
+                self.invalid_subterms.push(expr.span);
+                return;
+            }
+        }
+        intravisit::walk_expr(self, expr);
+    }
+}
+
 fn deserialize_spec_from_attrs(attrs: &[ast::Attribute]) -> JsonAssertion {
     let json_string = read_prusti_attr("assertion", attrs)
         .expect("could not find prusti::assertion");
@@ -260,6 +779,32 @@ impl<'a, 'tcx> intravisit::Visitor<'tcx> for SpecCollector<'a, 'tcx> {
         intravisit::NestedVisitorMap::All(map)
     }
 
+    fn visit_item(&mut self, item: &'tcx rustc_hir::Item) {
+        intravisit::walk_item(self, item);
+
+        // Collect invariants of statics and constants
+        if let ItemKind::Static(..) | ItemKind::Const(..) = item.kind {
+            let attrs = self.tcx.hir().attrs(item.hir_id());
+            self.collect_static_specs(item.def_id, attrs);
+        }
+
+        // Collect type invariants
+        if let ItemKind::Struct(..) | ItemKind::Enum(..) = item.kind {
+            let attrs = self.tcx.hir().attrs(item.hir_id());
+            self.collect_struct_specs(item.def_id, attrs);
+        }
+    }
+
+    fn visit_impl_item(&mut self, ii: &'tcx rustc_hir::ImplItem) {
+        intravisit::walk_impl_item(self, ii);
+
+        // Collect invariants of associated constants
+        if let rustc_hir::ImplItemKind::Const(..) = ii.kind {
+            let attrs = self.tcx.hir().attrs(ii.hir_id());
+            self.collect_static_specs(ii.def_id, attrs);
+        }
+    }
+
     fn visit_trait_item(
         &mut self,
         ti: &'tcx rustc_hir::TraitItem,
@@ -272,7 +817,7 @@ impl<'a, 'tcx> intravisit::Visitor<'tcx> for SpecCollector<'a, 'tcx> {
         let attrs = self.tcx.get_attrs(ti.def_id.to_def_id());
 
         // Collect procedure specifications
-        if let Some(procedure_spec_ref) = get_procedure_spec_ids(def_id, attrs) {
+        if let Some(procedure_spec_ref) = get_procedure_spec_ids(self.env, def_id, attrs) {
             self.procedure_specs.insert(local_id, procedure_spec_ref);
         }
     }
@@ -285,6 +830,13 @@ impl<'a, 'tcx> intravisit::Visitor<'tcx> for SpecCollector<'a, 'tcx> {
         span: Span,
         id: rustc_hir::hir_id::HirId,
     ) {
+        // Closures are part of the item that contains them.
+        if !matches!(fn_kind, intravisit::FnKind::Closure)
+            && !self.items_with_prusti_attrs.contains(&id.owner)
+        {
+            return;
+        }
+
         intravisit::walk_fn(self, fn_kind, fn_decl, body_id, span, id);
 
         let local_id = self.tcx.hir().local_def_id(id);
@@ -296,20 +848,29 @@ impl<'a, 'tcx> intravisit::Visitor<'tcx> for SpecCollector<'a, 'tcx> {
             self.extern_resolver.add_extern_fn(fn_kind, fn_decl, body_id, span, id);
         }
 
+        // Collect refinements of specifications of instantiations
+        if has_refine_spec_attr(attrs) {
+            self.refined_resolver.add_refined_fn(fn_kind, fn_decl, body_id, span, id);
+        }
+
         // Collect procedure specifications
-        if let Some(procedure_spec_ref) = get_procedure_spec_ids(def_id, attrs) {
+        if let Some(procedure_spec_ref) = get_procedure_spec_ids(self.env, def_id, attrs) {
+            trace!("Collected procedure specification of {:?}", def_id);
             self.procedure_specs.insert(local_id, procedure_spec_ref);
         }
 
         // Collect a typed expression
         if let Some(expr_id) = read_prusti_attr("expr_id", attrs) {
-            self.typed_expressions.insert(expr_id, local_id);
+            trace!("Collected typed expression {} of {:?}", expr_id, def_id);
+            self.typed_expressions.insert(expr_id, def_id);
         }
 
         // Collect a specification id and its assertion
         if let Some(raw_spec_id) = read_prusti_attr("spec_id", attrs) {
-            let spec_id: SpecificationId = raw_spec_id.try_into()
-                .expect("failed conversion to SpecificationId");
+            let spec_id = match parse_spec_id(self.env, def_id, raw_spec_id) {
+                Some(spec_id) => spec_id,
+                None => return,
+            };
             let specification = deserialize_spec_from_attrs(attrs);
 
             // Detect the kind of specification
@@ -320,13 +881,20 @@ impl<'a, 'tcx> intravisit::Visitor<'tcx> for SpecCollector<'a, 'tcx> {
             // for postconditions and invariants.
             let spec_type = if has_prusti_attr(attrs, "loop_body_invariant_spec") {
                 SpecType::Invariant
+            } else if has_prusti_attr(attrs, "loop_body_variant_spec") {
+                SpecType::Decreases
+            } else if has_prusti_attr(attrs, "body_assertion_spec")
+                || has_prusti_attr(attrs, "body_assumption_spec")
+            {
+                SpecType::BodyAssertion
             } else {
                 let fn_name = match fn_kind {
                     intravisit::FnKind::ItemFn(ref ident, ..) |
                     intravisit::FnKind::Method(ref ident, ..) => ident.name.to_ident_string(),
                     intravisit::FnKind::Closure => unreachable!(
                         "a closure is annotated with prusti::spec_id but not with \
-                        prusti::loop_body_invariant_spec"
+                        prusti::loop_body_invariant_spec, prusti::loop_body_variant_spec, \
+                        prusti::body_assertion_spec or prusti::body_assumption_spec"
                     ),
                 };
                 if fn_name.starts_with("prusti_pre_item_")
@@ -337,6 +905,10 @@ impl<'a, 'tcx> intravisit::Visitor<'tcx> for SpecCollector<'a, 'tcx> {
                     SpecType::Postcondition
                 } else if fn_name.starts_with("prusti_pred_item_") {
                     SpecType::Predicate
+                } else if fn_name.starts_with("prusti_inv_item_") {
+                    SpecType::Invariant
+                } else if fn_name.starts_with("prusti_decreases_item_") {
+                    SpecType::Decreases
                 } else {
                     unreachable!()
                 }
@@ -346,12 +918,26 @@ impl<'a, 'tcx> intravisit::Visitor<'tcx> for SpecCollector<'a, 'tcx> {
             self.spec_items.push(spec_item);
 
             // Collect loop invariant
-            if spec_type == SpecType::Invariant {
+            if spec_type == SpecType::Invariant && has_prusti_attr(attrs, "loop_body_invariant_spec") {
                 self.loop_specs
                     .entry(local_id)
                     .or_insert(vec![])
                     .push(spec_id);
             }
+
+            // Collect loop variant
+            if spec_type == SpecType::Decreases && has_prusti_attr(attrs, "loop_body_variant_spec") {
+                self.loop_variants
+                    .entry(local_id)
+                    .or_insert(vec![])
+                    .push(spec_id);
+            }
+
+            // Collect `prusti_assert!` and `prusti_assume!`
+            if spec_type == SpecType::BodyAssertion {
+                let is_assumption = has_prusti_attr(attrs, "body_assumption_spec");
+                self.body_assertions.insert(local_id, (spec_id, is_assumption));
+            }
         }
     }
 
@@ -370,7 +956,7 @@ impl<'a, 'tcx> intravisit::Visitor<'tcx> for SpecCollector<'a, 'tcx> {
                 let local_id = self.tcx.hir().local_def_id(init_expr.hir_id);
                 let def_id = local_id.to_def_id();
                 // Collect procedure specifications
-                if let Some(procedure_spec_ref) = get_procedure_spec_ids(def_id, attrs) {
+                if let Some(procedure_spec_ref) = get_procedure_spec_ids(self.env, def_id, attrs) {
                     self.procedure_specs.insert(local_id, procedure_spec_ref);
                 }
             }
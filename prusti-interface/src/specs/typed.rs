@@ -3,6 +3,7 @@ use prusti_specs::specifications::json;
 use rustc_hir::BodyId;
 use rustc_hir::def_id::{DefId, LocalDefId};
 use rustc_middle::{mir, ty::{self, TyCtxt}};
+use rustc_middle::ty::subst::{GenericArg, GenericArgKind, SubstsRef};
 use rustc_span::Span;
 use std::collections::HashMap;
 
@@ -12,36 +13,47 @@ use crate::environment::Environment;
 
 // FIXME: these comments are not terribly useful and are a copy of the untyped ones...
 /// A specification that has no types associated with it.
-pub type Specification<'tcx> = common::Specification<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type Specification<'tcx> = common::Specification<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// A set of untyped specifications associated with a single element.
-pub type SpecificationSet<'tcx> = common::SpecificationSet<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type SpecificationSet<'tcx> = common::SpecificationSet<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// A set of untyped specifications associated with a loop.
-pub type LoopSpecification<'tcx> = common::LoopSpecification<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type LoopSpecification<'tcx> = common::LoopSpecification<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
+/// A set of untyped specifications associated with a static or a constant.
+pub type StaticSpecification<'tcx> = common::StaticSpecification<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
+/// An untyped `prusti_assert!` or `prusti_assume!` in the body of a procedure.
+pub type BodyAssertionSpecification<'tcx> = common::BodyAssertionSpecification<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// A set of untyped specifications associated with a procedure.
-pub type ProcedureSpecification<'tcx> = common::ProcedureSpecification<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type ProcedureSpecification<'tcx> = common::ProcedureSpecification<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// A map of untyped specifications for a specific crate.
 pub type SpecificationMap<'tcx> = HashMap<common::SpecificationId, Assertion<'tcx>>;
 /// An assertion that has no types associated with it.
-pub type Assertion<'tcx> = common::Assertion<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type Assertion<'tcx> = common::Assertion<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// An assertion kind that has no types associated with it.
-pub type AssertionKind<'tcx> = common::AssertionKind<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type AssertionKind<'tcx> = common::AssertionKind<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 /// An expression that has no types associated with it.
-pub type Expression = common::Expression<ExpressionId, LocalDefId>;
+pub type Expression = common::Expression<ExpressionId, DefId>;
 /// A trigger set that has no types associated with it.
-pub type TriggerSet = common::TriggerSet<ExpressionId, LocalDefId>;
+pub type TriggerSet = common::TriggerSet<ExpressionId, DefId>;
 /// Quantifier variables that have no types associated with it.
 pub type QuantifierVars<'tcx> = common::QuantifierVars<ExpressionId, (mir::Local, ty::Ty<'tcx>)>;
 /// Specification entailment variables that have no types associated.
 pub type SpecEntailmentVars<'tcx> = common::SpecEntailmentVars<ExpressionId, (mir::Local, ty::Ty<'tcx>)>;
 /// A trigger that has no types associated with it.
-pub type Trigger = common::Trigger<ExpressionId, LocalDefId>;
+pub type Trigger = common::Trigger<ExpressionId, DefId>;
 /// A pledge in the postcondition.
-pub type Pledge<'tcx> = common::Pledge<ExpressionId, LocalDefId, (mir::Local, ty::Ty<'tcx>)>;
+pub type Pledge<'tcx> = common::Pledge<ExpressionId, DefId, (mir::Local, ty::Ty<'tcx>)>;
 
 /// A map of specifications keyed by crate-local DefIds.
 pub struct DefSpecificationMap<'tcx> {
     pub specs: HashMap<LocalDefId, SpecificationSet<'tcx>>,
     pub extern_specs: HashMap<DefId, LocalDefId>,
+    /// Specifications of items of other crates, exported by the crates that
+    /// define them. External specifications take precedence over these.
+    pub imported_specs: HashMap<DefId, SpecificationSet<'tcx>>,
+    /// Refinements of the specifications of instantiations of generic
+    /// functions (see `#[refine_spec]`), keyed by the function and the
+    /// generic arguments the refinement applies to.
+    pub refined_specs: HashMap<(DefId, SubstsRef<'tcx>), LocalDefId>,
 }
 
 impl<'tcx> DefSpecificationMap<'tcx> {
@@ -49,16 +61,111 @@ impl<'tcx> DefSpecificationMap<'tcx> {
         Self {
             specs: HashMap::new(),
             extern_specs: HashMap::new(),
+            imported_specs: HashMap::new(),
+            refined_specs: HashMap::new(),
         }
     }
     pub fn get(&self, def_id: &DefId) -> Option<&SpecificationSet<'tcx>> {
         let id = if let Some(spec_id) = self.extern_specs.get(def_id) {
             *spec_id
+        } else if let Some(local_id) = def_id.as_local() {
+            local_id
         } else {
-            def_id.as_local()?
+            return self.imported_specs.get(def_id);
         };
         self.specs.get(&id)
     }
+
+    /// Returns the refinements of the specification of `def_id` that apply to
+    /// its instantiation with `substs` and that are not less specific than
+    /// another applicable refinement. More than one result means that the
+    /// applicable refinements are ambiguous.
+    pub fn get_refined_specs(&self, def_id: DefId, substs: SubstsRef<'tcx>) -> Vec<LocalDefId> {
+        let applicable: Vec<_> = self.refined_specs
+            .iter()
+            .filter(|((refined_id, pattern), _)| {
+                *refined_id == def_id && substs_match(*pattern, substs)
+            })
+            .map(|((_, pattern), local_id)| (*pattern, *local_id))
+            .collect();
+        applicable
+            .iter()
+            .filter(|(pattern, _)| {
+                !applicable.iter().any(|(other, _)| {
+                    substs_match(*pattern, *other) && !substs_match(*other, *pattern)
+                })
+            })
+            .map(|(_, local_id)| *local_id)
+            .collect()
+    }
+}
+
+/// Does `pattern` match `substs`, i.e. can the type and const parameters in
+/// `pattern` be replaced consistently so that it becomes `substs`? Lifetimes
+/// are ignored.
+fn substs_match<'tcx>(pattern: SubstsRef<'tcx>, substs: SubstsRef<'tcx>) -> bool {
+    let mut bindings = HashMap::new();
+    pattern.len() == substs.len()
+        && pattern.iter()
+            .zip(substs.iter())
+            .all(|(pattern_arg, arg)| generic_arg_matches(pattern_arg, arg, &mut bindings))
+}
+
+fn generic_arg_matches<'tcx>(
+    pattern: GenericArg<'tcx>,
+    arg: GenericArg<'tcx>,
+    bindings: &mut HashMap<u32, GenericArg<'tcx>>,
+) -> bool {
+    match (pattern.unpack(), arg.unpack()) {
+        (GenericArgKind::Lifetime(_), GenericArgKind::Lifetime(_)) => true,
+        (GenericArgKind::Type(pattern_ty), GenericArgKind::Type(ty)) => {
+            ty_matches(pattern_ty, ty, bindings)
+        }
+        (GenericArgKind::Const(pattern_const), GenericArgKind::Const(_)) => {
+            if let ty::ConstKind::Param(param) = pattern_const.val {
+                *bindings.entry(param.index).or_insert(arg) == arg
+            } else {
+                pattern == arg
+            }
+        }
+        _ => false,
+    }
+}
+
+fn ty_matches<'tcx>(
+    pattern: ty::Ty<'tcx>,
+    ty: ty::Ty<'tcx>,
+    bindings: &mut HashMap<u32, GenericArg<'tcx>>,
+) -> bool {
+    match (pattern.kind(), ty.kind()) {
+        (ty::TyKind::Param(param), _) => {
+            *bindings.entry(param.index).or_insert_with(|| ty.into()) == ty.into()
+        }
+        (ty::TyKind::Adt(pattern_def, pattern_substs), ty::TyKind::Adt(def, substs)) => {
+            pattern_def.did == def.did
+                && pattern_substs.iter()
+                    .zip(substs.iter())
+                    .all(|(pattern_arg, arg)| generic_arg_matches(pattern_arg, arg, bindings))
+        }
+        (ty::TyKind::Tuple(pattern_elems), ty::TyKind::Tuple(elems)) => {
+            pattern_elems.len() == elems.len()
+                && pattern_elems.iter()
+                    .zip(elems.iter())
+                    .all(|(pattern_arg, arg)| generic_arg_matches(pattern_arg, arg, bindings))
+        }
+        (
+            ty::TyKind::Ref(_, pattern_target, pattern_mutbl),
+            ty::TyKind::Ref(_, target, mutbl),
+        ) => pattern_mutbl == mutbl && ty_matches(*pattern_target, *target, bindings),
+        (ty::TyKind::Array(pattern_elem, pattern_len), ty::TyKind::Array(elem, len)) => {
+            ty_matches(*pattern_elem, *elem, bindings)
+                && generic_arg_matches((*pattern_len).into(), (*len).into(), bindings)
+        }
+        (ty::TyKind::Slice(pattern_elem), ty::TyKind::Slice(elem)) => {
+            ty_matches(*pattern_elem, *elem, bindings)
+        }
+        _ => pattern == ty,
+    }
 }
 
 /// This trait is implemented for specification-related types that have one or
@@ -143,24 +250,24 @@ impl<'tcx> Spanned<'tcx> for Assertion<'tcx> {
 pub trait StructuralToTyped<'tcx, Target> {
     fn to_typed(
         self,
-        typed_expressions: &HashMap<String, LocalDefId>,
+        typed_expressions: &HashMap<String, DefId>,
         env: &Environment<'tcx>,
     ) -> Target;
 }
 
 impl<'tcx> StructuralToTyped<'tcx, Expression> for json::Expression {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, _env: &Environment<'tcx>) -> Expression {
-        let local_id = typed_expressions[&format!("{}_{}", self.spec_id, self.expr_id)];
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, _env: &Environment<'tcx>) -> Expression {
+        let def_id = typed_expressions[&format!("{}_{}", self.spec_id, self.expr_id)];
         Expression {
             spec_id: self.spec_id,
             id: self.expr_id,
-            expr: local_id,
+            expr: def_id,
         }
     }
 }
 
 impl<'tcx> StructuralToTyped<'tcx, TriggerSet> for json::TriggerSet {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, env: &Environment<'tcx>) -> TriggerSet {
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, env: &Environment<'tcx>) -> TriggerSet {
         common::TriggerSet(
             self.0
                 .into_iter()
@@ -171,7 +278,7 @@ impl<'tcx> StructuralToTyped<'tcx, TriggerSet> for json::TriggerSet {
 }
 
 impl<'tcx> StructuralToTyped<'tcx, Trigger> for json::Trigger {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, env: &Environment<'tcx>) -> Trigger {
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, env: &Environment<'tcx>) -> Trigger {
         common::Trigger(
             self.0
                 .into_iter()
@@ -184,11 +291,11 @@ impl<'tcx> StructuralToTyped<'tcx, Trigger> for json::Trigger {
 impl<'tcx> StructuralToTyped<'tcx, QuantifierVars<'tcx>> for json::QuantifierVars {
     fn to_typed(
         self,
-        typed_expressions: &HashMap<String, LocalDefId>,
+        typed_expressions: &HashMap<String, DefId>,
         env: &Environment<'tcx>,
     ) -> QuantifierVars<'tcx> {
-        let local_id = typed_expressions[&format!("{}_{}", self.spec_id, self.expr_id)];
-        let body = env.local_mir(local_id);
+        let def_id = typed_expressions[&format!("{}_{}", self.spec_id, self.expr_id)];
+        let body = env.mir(def_id);
 
         // the first argument to the node is the closure itself and the
         // following ones are the variables; therefore, we need to skip
@@ -215,13 +322,13 @@ impl<'tcx> StructuralToTyped<'tcx, QuantifierVars<'tcx>> for json::QuantifierVar
 impl<'tcx> StructuralToTyped<'tcx, SpecEntailmentVars<'tcx>> for json::SpecEntailmentVars {
     fn to_typed(
         self,
-        typed_expressions: &HashMap<String, LocalDefId>,
+        typed_expressions: &HashMap<String, DefId>,
         env: &Environment<'tcx>
     ) -> SpecEntailmentVars<'tcx> {
-        let local_pre_id = typed_expressions[&format!("{}_{}", self.spec_id, self.pre_expr_id)];
-        let local_post_id = typed_expressions[&format!("{}_{}", self.spec_id, self.post_expr_id)];
-        let pre_body = env.local_mir(local_pre_id);
-        let post_body = env.local_mir(local_post_id);
+        let pre_id = typed_expressions[&format!("{}_{}", self.spec_id, self.pre_expr_id)];
+        let post_id = typed_expressions[&format!("{}_{}", self.spec_id, self.post_expr_id)];
+        let pre_body = env.mir(pre_id);
+        let post_body = env.mir(post_id);
 
         let pre_args: Vec<(mir::Local, ty::Ty)> = pre_body
             .args_iter()
@@ -255,7 +362,7 @@ impl<'tcx> StructuralToTyped<'tcx, SpecEntailmentVars<'tcx>> for json::SpecEntai
 }
 
 impl<'tcx> StructuralToTyped<'tcx, AssertionKind<'tcx>> for json::AssertionKind {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, env: &Environment<'tcx>) -> AssertionKind<'tcx> {
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, env: &Environment<'tcx>) -> AssertionKind<'tcx> {
         use json::AssertionKind::*;
         match self {
             Expr(expr) => AssertionKind::Expr(expr.to_typed(typed_expressions, env)),
@@ -293,7 +400,7 @@ impl<'tcx> StructuralToTyped<'tcx, AssertionKind<'tcx>> for json::AssertionKind
 }
 
 impl<'tcx> StructuralToTyped<'tcx, Assertion<'tcx>> for json::Assertion {
-    fn to_typed(self, typed_expressions: &HashMap<String, LocalDefId>, env: &Environment<'tcx>) -> Assertion<'tcx> {
+    fn to_typed(self, typed_expressions: &HashMap<String, DefId>, env: &Environment<'tcx>) -> Assertion<'tcx> {
         Assertion {
             kind: box self.kind.to_typed(typed_expressions, env),
         }
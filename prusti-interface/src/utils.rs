@@ -11,6 +11,7 @@ use rustc_middle::ty::{self, TyCtxt};
 use rustc_index::vec::Idx;
 use std::collections::HashSet;
 use rustc_ast::ast;
+use rustc_span::Span;
 use log::trace;
 
 use prusti_utils::force_matches;
@@ -284,6 +285,21 @@ pub fn has_prusti_attr(attrs: &[ast::Attribute], name: &str) -> bool {
     })
 }
 
+/// Check if any attribute of the form `prusti::<name>` is among the attributes.
+pub fn has_any_prusti_attr(attrs: &[ast::Attribute]) -> bool {
+    attrs.iter().any(|attr| match &attr.kind {
+        ast::AttrKind::Normal(ast::AttrItem {
+                                  path: ast::Path { span: _, segments, tokens: _ },
+                                  args: _,
+                                  tokens: _,
+                              }, _) => {
+            segments.len() == 2
+                && segments[0].ident.as_str() == "prusti"
+        }
+        _ => false,
+    })
+}
+
 /// Check if `prusti::spec_only` is among the attributes.
 pub fn has_spec_only_attr(attrs: &[ast::Attribute]) -> bool {
     has_prusti_attr(attrs, "spec_only")
@@ -294,6 +310,33 @@ pub fn has_extern_spec_attr(attrs: &[ast::Attribute]) -> bool {
     has_prusti_attr(attrs, "extern_spec")
 }
 
+/// Check if `prusti::refine_spec` is among the attributes.
+pub fn has_refine_spec_attr(attrs: &[ast::Attribute]) -> bool {
+    has_prusti_attr(attrs, "refine_spec")
+}
+
+/// Check if `prusti::skip_verification` is among the attributes.
+pub fn has_skip_verification_attr(attrs: &[ast::Attribute]) -> bool {
+    has_prusti_attr(attrs, "skip_verification")
+}
+
+/// Check whether a path such as `parser::Lexer::next` matches a pattern in
+/// which `*` matches any part of a path segment and `**` matches any sequence
+/// of path segments.
+pub fn path_matches_pattern(path: &str, pattern: &str) -> bool {
+    fn matches(path: &[u8], pattern: &[u8]) -> bool {
+        match pattern {
+            [] => path.is_empty(),
+            [b'*', b'*', rest @ ..] => (0..=path.len()).any(|index| matches(&path[index..], rest)),
+            [b'*', rest @ ..] => (0..=path.len())
+                .take_while(|&index| index == 0 || path[index - 1] != b':')
+                .any(|index| matches(&path[index..], rest)),
+            [first, rest @ ..] => path.first() == Some(first) && matches(&path[1..], rest),
+        }
+    }
+    matches(path.as_bytes(), pattern.as_bytes())
+}
+
 /// Read the value stored in a Prusti attribute (e.g. `prusti::<attr_name>="...")`.
 pub fn read_prusti_attrs(attr_name: &str, attrs: &[ast::Attribute]) -> Vec<String> {
     let mut strings = vec![];
@@ -332,3 +375,38 @@ pub fn read_prusti_attrs(attr_name: &str, attrs: &[ast::Attribute]) -> Vec<Strin
 pub fn read_prusti_attr(attr_name: &str, attrs: &[ast::Attribute]) -> Option<String> {
     read_prusti_attrs(attr_name, attrs).pop()
 }
+
+/// Read the integer argument of a Prusti attribute of the form
+/// `prusti::<attr_name>(N)`. If the attribute has another form, the span of the
+/// attribute is returned as the error.
+pub fn read_prusti_attr_int_arg(attr_name: &str, attrs: &[ast::Attribute]) -> Option<Result<u64, Span>> {
+    use rustc_ast::token::{Lit, LitKind, TokenKind};
+    use rustc_ast::tokenstream::TokenTree;
+    attrs.iter().filter_map(|attr| match &attr.kind {
+        ast::AttrKind::Normal(ast::AttrItem {
+                                  path: ast::Path { span: _, segments, tokens: _ },
+                                  args,
+                                  tokens: _,
+                              }, _) if segments.len() == 2
+            && segments[0].ident.as_str() == "prusti"
+            && segments[1].ident.as_str() == attr_name => {
+            let value = match args {
+                ast::MacArgs::Delimited(_, _, tokens) => {
+                    let trees: Vec<_> = tokens.trees().collect();
+                    match trees.as_slice() {
+                        [TokenTree::Token(token)] => match &token.kind {
+                            TokenKind::Literal(Lit { kind: LitKind::Integer, symbol, suffix: None }) => {
+                                symbol.as_str().replace('_', "").parse().ok()
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            Some(value.ok_or(attr.span))
+        }
+        _ => None,
+    }).last()
+}
@@ -5,7 +5,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::process::Command;
-use prusti_launch::get_rust_toolchain_channel;
+use prusti_launch::{get_rust_toolchain_channel, workspace::Workspace};
 
 fn main(){
     if let Err(code) = process(std::env::args().skip(1)) {
@@ -26,23 +26,124 @@ fn process<I>(args: I) -> Result<(), i32>
 
     // Remove the leading "prusti" argument when `cargo-prusti` is invocated
     // as `cargo prusti` (note the space)
-    let clean_args = args.skip_while(|x| x == "prusti");
+    let mut force_reverify = false;
+    let mut changed_since = None;
+    let mut clean_args = vec![];
+    let mut args = args.skip_while(|x| x == "prusti");
+    while let Some(arg) = args.next() {
+        if arg == "--force-reverify" {
+            force_reverify = true;
+        } else if arg == "--changed-since" {
+            match args.next() {
+                Some(rev) => changed_since = Some(rev),
+                None => {
+                    eprintln!("error: `--changed-since` requires a git revision");
+                    return Err(1);
+                }
+            }
+        } else if let Some(rev) = arg.strip_prefix("--changed-since=") {
+            changed_since = Some(rev.to_string());
+        } else {
+            clean_args.push(arg);
+        }
+    }
 
     let cargo_path = std::env::var("CARGO_PATH").unwrap_or("cargo".to_string());
 
-    let exit_status = Command::new(cargo_path)
+    // Verify only the workspace members that changed since the given
+    // revision, and the members that depend on them.
+    let mut skipped_packages = vec![];
+    let mut verified_packages = vec![];
+    if let Some(rev) = &changed_since {
+        let workspace = Workspace::load(&cargo_path).and_then(|workspace| {
+            let changed_files = workspace.changed_files(rev)?;
+            Ok((workspace, changed_files))
+        });
+        let (workspace, changed_files) = match workspace {
+            Ok(result) => result,
+            Err(message) => {
+                eprintln!("error: {}", message);
+                return Err(1);
+            }
+        };
+        let affected = workspace.affected_members(&changed_files);
+        for member in workspace.members {
+            if affected.contains(&member.name) {
+                verified_packages.push(member.name);
+            } else {
+                skipped_packages.push(member.name);
+            }
+        }
+        skipped_packages.sort();
+        if verified_packages.is_empty() {
+            report_skipped_packages(&skipped_packages, rev);
+            return Ok(());
+        }
+        for package in &verified_packages {
+            clean_args.push("--package".to_string());
+            clean_args.push(package.clone());
+        }
+    }
+
+    // Store the verification results next to the compilation artifacts
+    let cache_path = std::env::var("PRUSTI_CACHE_PATH").unwrap_or_else(|_| {
+        let target_dir = std::env::var("CARGO_TARGET_DIR").unwrap_or("target".to_string());
+        std::env::current_dir()
+            .expect("current working directory invalid")
+            .join(target_dir)
+            .join("prusti-cache")
+            .to_string_lossy()
+            .into_owned()
+    });
+
+    // The remaining arguments, such as `--features`, `--no-default-features`,
+    // `--lib`, `--bins` and `--tests`, select the compilation units like for
+    // `cargo check`. Prusti verifies each of them with the same configuration,
+    // including the `#[cfg(test)]` code of test units.
+    let mut command = Command::new(cargo_path);
+    command
         .arg("check")
         .args(clean_args)
+        .env("PRUSTI_CACHE_PATH", cache_path);
+    if force_reverify {
+        command.env("PRUSTI_FORCE_REVERIFY", "true");
+    }
+    if !verified_packages.is_empty() {
+        // The dependencies of the selected packages are compiled as well, but
+        // only to collect their specifications.
+        command.env("PRUSTI_VERIFY_PACKAGES", verified_packages.join(","));
+    }
+    if std::env::var_os("PRUSTI_QUIET").is_none() {
+        // Cargo captures the stderr of Prusti, which therefore cannot tell
+        // whether the progress of the verification is shown on a terminal.
+        let quiet = !atty::is(atty::Stream::Stderr);
+        command.env("PRUSTI_QUIET", quiet.to_string());
+    }
+    let exit_status = command
         .env("RUST_TOOLCHAIN", get_rust_toolchain_channel())
-        .env("PRUSTI_QUIET", "true")
         .env("PRUSTI_FULL_COMPILATION", "true")
         .env("RUSTC_WRAPPER", prusti_rustc_path)
         .status()
         .expect("could not run cargo");
 
+    if let Some(rev) = &changed_since {
+        report_skipped_packages(&skipped_packages, rev);
+    }
+
     if exit_status.success() {
         Ok(())
     } else {
         Err(exit_status.code().unwrap_or(-1))
     }
 }
+
+fn report_skipped_packages(skipped_packages: &[String], rev: &str) {
+    if !skipped_packages.is_empty() {
+        eprintln!(
+            "Skipped the verification of {} workspace member(s) unaffected by the changes since {}: {}",
+            skipped_packages.len(),
+            rev,
+            skipped_packages.join(", "),
+        );
+    }
+}
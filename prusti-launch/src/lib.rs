@@ -6,6 +6,8 @@
 
 #![deny(unused_must_use)]
 
+pub mod workspace;
+
 use std::{
     env,
     path::{Path, PathBuf},
@@ -0,0 +1,195 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Selection of the members of a cargo workspace that are affected by the
+//! changes since a git revision.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    process::Command,
+};
+use serde::Deserialize;
+
+/// The files that affect every member of a workspace when they change
+/// outside of the members.
+const WORKSPACE_FILES: [&str; 5] = [
+    "Cargo.toml",
+    "Cargo.lock",
+    "Prusti.toml",
+    "rust-toolchain",
+    "rust-toolchain.toml",
+];
+
+/// The subset of the output of `cargo metadata` that we need.
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    workspace_members: Vec<String>,
+    workspace_root: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    manifest_path: PathBuf,
+    dependencies: Vec<Dependency>,
+}
+
+#[derive(Deserialize)]
+struct Dependency {
+    name: String,
+    /// `None` for the dependencies on other members of the workspace.
+    source: Option<String>,
+    /// `None` for normal dependencies, `"dev"` or `"build"` otherwise.
+    kind: Option<String>,
+}
+
+/// A member of a cargo workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub name: String,
+    /// The directory that contains the manifest of the member.
+    pub dir: PathBuf,
+    /// The names of the members that this member depends on. Development and
+    /// build dependencies are not included, because they cannot change the
+    /// contracts that the member relies on.
+    pub dependencies: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub members: Vec<Member>,
+}
+
+impl Workspace {
+    /// Load the workspace of the current directory with `cargo metadata`.
+    pub fn load(cargo_path: &str) -> Result<Self, String> {
+        let output = Command::new(cargo_path)
+            .args(&["metadata", "--format-version", "1", "--no-deps"])
+            .output()
+            .map_err(|err| format!("could not run `cargo metadata`: {}", err))?;
+        if !output.status.success() {
+            return Err(format!(
+                "`cargo metadata` failed:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        let metadata: Metadata = serde_json::from_slice(&output.stdout)
+            .map_err(|err| format!("could not parse the output of `cargo metadata`: {}", err))?;
+        Ok(Self::from_metadata(metadata))
+    }
+
+    fn from_metadata(metadata: Metadata) -> Self {
+        let member_ids: BTreeSet<_> = metadata.workspace_members.into_iter().collect();
+        let packages: Vec<_> = metadata.packages
+            .into_iter()
+            .filter(|package| member_ids.contains(&package.id))
+            .collect();
+        let member_names: BTreeSet<_> = packages.iter().map(|package| package.name.clone()).collect();
+        let members = packages
+            .into_iter()
+            .map(|package| Member {
+                dir: package.manifest_path
+                    .parent()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default(),
+                dependencies: package.dependencies
+                    .into_iter()
+                    .filter(|dependency| {
+                        dependency.source.is_none()
+                            && dependency.kind.is_none()
+                            && member_names.contains(&dependency.name)
+                    })
+                    .map(|dependency| dependency.name)
+                    .collect(),
+                name: package.name,
+            })
+            .collect();
+        Workspace { root: metadata.workspace_root, members }
+    }
+
+    /// The files of the workspace that changed since the git revision `rev`,
+    /// relative to the root of the workspace. This includes the uncommitted
+    /// changes and the untracked files that are not ignored.
+    pub fn changed_files(&self, rev: &str) -> Result<Vec<PathBuf>, String> {
+        let mut files = self.git(&["diff", "--name-only", "--relative", rev, "--"])?;
+        files.extend(self.git(&["ls-files", "--others", "--exclude-standard"])?);
+        Ok(files)
+    }
+
+    fn git(&self, args: &[&str]) -> Result<Vec<PathBuf>, String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.root)
+            .args(args)
+            .output()
+            .map_err(|err| format!("could not run git: {}", err))?;
+        if !output.status.success() {
+            return Err(format!(
+                "`git {}` failed:\n{}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    /// The member that contains `file`, given relative to the root of the
+    /// workspace. Members can be nested, so the innermost one is chosen.
+    fn member_of(&self, file: &Path) -> Option<&Member> {
+        let file = self.root.join(file);
+        self.members
+            .iter()
+            .filter(|member| file.starts_with(&member.dir))
+            .max_by_key(|member| member.dir.components().count())
+    }
+
+    /// The names of the members that need to be verified again after
+    /// `changed_files` changed: the members that contain them, and the
+    /// members that depend on those, directly or transitively.
+    pub fn affected_members(&self, changed_files: &[PathBuf]) -> BTreeSet<String> {
+        let mut changed = BTreeSet::new();
+        for file in changed_files {
+            match self.member_of(file) {
+                Some(member) => {
+                    changed.insert(member.name.clone());
+                }
+                None => {
+                    let is_workspace_file = file.file_name()
+                        .and_then(|name| name.to_str())
+                        .map_or(false, |name| WORKSPACE_FILES.contains(&name));
+                    if is_workspace_file {
+                        return self.members.iter().map(|member| member.name.clone()).collect();
+                    }
+                }
+            }
+        }
+
+        let mut dependents: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for member in &self.members {
+            for dependency in &member.dependencies {
+                dependents.entry(dependency.as_str()).or_default().push(member.name.as_str());
+            }
+        }
+        let mut affected = BTreeSet::new();
+        let mut worklist: Vec<String> = changed.into_iter().collect();
+        while let Some(name) = worklist.pop() {
+            if affected.insert(name.clone()) {
+                for &dependent in dependents.get(name.as_str()).into_iter().flatten() {
+                    worklist.push(dependent.to_string());
+                }
+            }
+        }
+        affected
+    }
+}
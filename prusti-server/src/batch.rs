@@ -0,0 +1,269 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::{RemoteVerificationResult, VerifierPanicked};
+use prusti_common::verification_service::{BatchVerificationRequest, VerificationRequest};
+use serde_json;
+use std::{
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use viper::ProgramVerificationResult;
+
+/// The result of one request of a batch, as streamed back by the server.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchItem {
+    /// The position of the request in the batch.
+    pub index: usize,
+    pub program_name: String,
+    pub result: BatchItemResult,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum BatchItemResult {
+    Verified(ProgramVerificationResult),
+    Panicked,
+    /// The verification took longer than the timeout of the batch. Its result
+    /// is discarded, but the verifier is not interrupted.
+    TimedOut,
+}
+
+impl BatchItemResult {
+    /// How the verification ended, as reported in the progress of the
+    /// verification.
+    pub fn outcome(&self) -> &'static str {
+        match self {
+            BatchItemResult::Verified(result)
+                if result.verification_errors.is_empty()
+                    && result.consistency_errors.is_empty()
+                    && result.java_exceptions.is_empty() => "verified",
+            BatchItemResult::Verified(_) => "failed to verify",
+            BatchItemResult::Panicked => "panicked while verifying",
+            BatchItemResult::TimedOut => "timed out while verifying",
+        }
+    }
+}
+
+impl From<RemoteVerificationResult> for BatchItemResult {
+    fn from(result: RemoteVerificationResult) -> Self {
+        match result {
+            Ok(result) => BatchItemResult::Verified(result),
+            Err(VerifierPanicked) => BatchItemResult::Panicked,
+        }
+    }
+}
+
+/// Verifies the requests of `batch` with `verify`, on at most
+/// `max_concurrency` threads, and sends the result of each request through the
+/// returned receiver as soon as it is available. The receiver is closed once
+/// all requests have a result.
+///
+/// A panic of `verify` is reported as the result of the request that caused
+/// it; the other requests are not affected.
+pub fn verify_batch<F>(
+    batch: BatchVerificationRequest,
+    max_concurrency: usize,
+    verify: F,
+) -> mpsc::Receiver<BatchItem>
+where
+    F: Fn(VerificationRequest) -> RemoteVerificationResult + Send + Sync + 'static,
+{
+    let timeout = batch.timeout_ms.map(Duration::from_millis);
+    let thread_count = batch
+        .max_concurrency
+        .unwrap_or(max_concurrency)
+        .min(max_concurrency)
+        .max(1)
+        .min(batch.requests.len());
+    // The backend stops a verification that we no longer wait for, so that
+    // its thread does not keep running.
+    let mut requests = batch.requests;
+    if let Some(timeout) = timeout {
+        for request in &mut requests {
+            request.backend_config.set_timeout(timeout);
+        }
+    }
+    let queue: Arc<Mutex<VecDeque<(usize, VerificationRequest)>>> =
+        Arc::new(Mutex::new(requests.into_iter().enumerate().collect()));
+    let verify = Arc::new(verify);
+    let (item_sender, item_receiver) = mpsc::channel();
+
+    for thread_index in 0..thread_count {
+        let queue = queue.clone();
+        let verify = verify.clone();
+        let item_sender = item_sender.clone();
+        let spawned = thread::Builder::new()
+            .name(format!("Batch worker {}", thread_index))
+            .spawn(move || loop {
+                let next_request = queue.lock().unwrap().pop_front();
+                let (index, request) = match next_request {
+                    Some(request) => request,
+                    None => break,
+                };
+                let program_name = request.program_name.clone();
+                let result = verify_with_timeout(verify.clone(), request, timeout);
+                item_sender
+                    .send(BatchItem { index, program_name, result })
+                    .unwrap_or_else(|err| {
+                        error!("batch worker attempting to send result to dropped receiver: {:?}", err);
+                    });
+            });
+        if let Err(err) = spawned {
+            error!("Failed to spawn a batch worker: {:?}", err);
+        }
+    }
+
+    item_receiver
+}
+
+/// Runs `verify` on a thread of its own, so that we can stop waiting for it
+/// after `timeout`.
+fn verify_with_timeout<F>(
+    verify: Arc<F>,
+    request: VerificationRequest,
+    timeout: Option<Duration>,
+) -> BatchItemResult
+where
+    F: Fn(VerificationRequest) -> RemoteVerificationResult + Send + Sync + 'static,
+{
+    let (result_sender, result_receiver) = mpsc::channel();
+    let program_name = request.program_name.clone();
+    let spawned = thread::Builder::new()
+        .name(format!("Verification of {}", program_name))
+        .spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| verify(request)))
+                .unwrap_or(Err(VerifierPanicked));
+            // The receiver is gone if the verification timed out.
+            let _ = result_sender.send(result);
+        });
+    if let Err(err) = spawned {
+        error!("Failed to spawn the verification of {}: {:?}", program_name, err);
+        return BatchItemResult::Panicked;
+    }
+    let result = match timeout {
+        Some(timeout) => result_receiver.recv_timeout(timeout).map_err(|err| match err {
+            mpsc::RecvTimeoutError::Timeout => BatchItemResult::TimedOut,
+            mpsc::RecvTimeoutError::Disconnected => BatchItemResult::Panicked,
+        }),
+        None => result_receiver.recv().map_err(|_| BatchItemResult::Panicked),
+    };
+    match result {
+        Ok(result) => result.into(),
+        Err(failure) => {
+            error!("Verification of {} failed: {:?}", program_name, failure);
+            failure
+        }
+    }
+}
+
+/// Encodes a result as one line of newline-delimited JSON.
+pub fn encode_batch_item(item: &BatchItem) -> Vec<u8> {
+    let mut line = serde_json::to_vec(item).expect("could not encode batch item");
+    line.push(b'\n');
+    line
+}
+
+/// Decodes one line of newline-delimited JSON, without its newline.
+pub fn decode_batch_item(line: &str) -> serde_json::Result<BatchItem> {
+    serde_json::from_str(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prusti_common::verification_service::ViperBackendConfig;
+    use viper::VerificationBackend;
+
+    fn request(program_name: &str) -> VerificationRequest {
+        VerificationRequest {
+            programs: vec![],
+            program_name: program_name.to_string(),
+            backend_config: ViperBackendConfig {
+                backend: VerificationBackend::Silicon,
+                verifier_args: vec![],
+            },
+        }
+    }
+
+    fn batch(program_names: &[&str], timeout_ms: Option<u64>) -> BatchVerificationRequest {
+        BatchVerificationRequest {
+            requests: program_names.iter().map(|name| request(name)).collect(),
+            max_concurrency: Some(2),
+            timeout_ms,
+        }
+    }
+
+    /// A backend that verifies every program successfully, except the ones
+    /// whose name says otherwise.
+    fn mock_verify(request: VerificationRequest) -> RemoteVerificationResult {
+        match request.program_name.as_str() {
+            "panic" => panic!("mock verifier panicked"),
+            "slow" => {
+                thread::sleep(Duration::from_secs(3));
+                Ok(ProgramVerificationResult::default())
+            }
+            _ => Ok(ProgramVerificationResult::default()),
+        }
+    }
+
+    fn collect(receiver: mpsc::Receiver<BatchItem>) -> Vec<BatchItem> {
+        let mut items: Vec<_> = receiver.into_iter().collect();
+        items.sort_by_key(|item| item.index);
+        items
+    }
+
+    #[test]
+    fn every_request_gets_one_result() {
+        let names = ["a", "b", "c", "d", "e"];
+        let items = collect(verify_batch(batch(&names, None), 4, mock_verify));
+        assert_eq!(items.len(), names.len());
+        for (index, item) in items.iter().enumerate() {
+            assert_eq!(item.index, index);
+            assert_eq!(item.program_name, names[index]);
+            assert!(matches!(item.result, BatchItemResult::Verified(_)));
+        }
+    }
+
+    #[test]
+    fn partial_failure() {
+        let items = collect(verify_batch(batch(&["a", "panic", "slow", "b"], Some(1000)), 4, mock_verify));
+        assert_eq!(items.len(), 4);
+        assert!(matches!(items[0].result, BatchItemResult::Verified(_)));
+        assert!(matches!(items[1].result, BatchItemResult::Panicked));
+        assert!(matches!(items[2].result, BatchItemResult::TimedOut));
+        assert!(matches!(items[3].result, BatchItemResult::Verified(_)));
+    }
+
+    #[test]
+    fn results_are_streamed() {
+        let receiver = verify_batch(batch(&["slow", "a"], None), 2, mock_verify);
+        let first = receiver.recv().unwrap();
+        assert_eq!(first.program_name, "a");
+        let second = receiver.recv().unwrap();
+        assert_eq!(second.program_name, "slow");
+        assert!(receiver.recv().is_err());
+    }
+
+    #[test]
+    fn ndjson_framing() {
+        let item = BatchItem {
+            index: 3,
+            program_name: "a".to_string(),
+            result: BatchItemResult::TimedOut,
+        };
+        let line = encode_batch_item(&item);
+        assert_eq!(line.last(), Some(&b'\n'));
+        assert_eq!(line.iter().filter(|&&byte| byte == b'\n').count(), 1);
+        let line = String::from_utf8(line).unwrap();
+        let decoded = decode_batch_item(line.trim_end()).unwrap();
+        assert_eq!(decoded.index, 3);
+        assert_eq!(decoded.program_name, "a");
+        assert!(matches!(decoded.result, BatchItemResult::TimedOut));
+    }
+}
@@ -37,6 +37,9 @@ fn main() {
         .parse()
         .expect("Invalid port provided");
 
-    let service = ServerSideService::new();
+    let service = ServerSideService::new().unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        std::process::exit(2);
+    });
     service.listen_on_port(port);
 }
@@ -18,11 +18,16 @@ extern crate prusti_common;
 extern crate tokio;
 #[macro_use]
 extern crate serde;
+extern crate hyper;
+extern crate serde_json;
 
+mod batch;
 mod service;
+mod verifier_pool;
 mod verifier_runner;
 mod verifier_thread;
 
+pub use batch::*;
 use futures::Future;
 use prusti_common::{verification_context::VerifierBuilder, verification_service::*, Stopwatch};
 pub use service::*;
@@ -30,6 +35,7 @@ use std::{
     collections::VecDeque,
     sync::{Arc, RwLock},
 };
+pub use verifier_pool::*;
 pub use verifier_runner::*;
 use verifier_thread::*;
 use viper::ProgramVerificationResult;
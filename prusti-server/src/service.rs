@@ -4,33 +4,90 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use super::{PrustiServer, RemoteVerificationResult};
+use super::{
+    decode_batch_item, encode_batch_item, verify_batch, BatchItem, PrustiServer,
+    RemoteVerificationResult,
+};
 use prusti_common::{config, verification_service::*};
 
 use bincode;
-use futures::{self};
+use futures::{self, Future, Stream};
+use hyper;
 use num_cpus;
-use reqwest::{self, Client, Url, UrlError};
+use reqwest::{self, header, Certificate, Client, StatusCode, Url, UrlError};
 use std::{
-    net::{Ipv4Addr, SocketAddr},
+    error::Error,
+    fmt, fs,
+    io::{self, BufRead, BufReader},
+    net::{AddrParseError, IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
     sync::{mpsc, Arc},
     thread,
 };
 use tokio;
 use viper::ProgramVerificationResult;
-use warp::{self, Buf, Filter};
+use warp::{self, Buf, Filter, Rejection};
 
 #[derive(Clone)]
 pub struct ServerSideService {
     server: Arc<PrustiServer>,
     max_concurrency: usize,
+    max_batch_concurrency: usize,
+    /// The address on which the server listens.
+    bind_address: IpAddr,
+    /// The bearer token that the requests must carry, if any.
+    token: Option<String>,
+    /// The paths of the certificate chain and of the private key with which
+    /// the requests are served over TLS, if any.
+    tls: Option<(PathBuf, PathBuf)>,
+}
+
+/// Extracted by the filter of the endpoints once the request is authorized.
+struct Authorized;
+
+/// The rejection of a request without the token of the server.
+#[derive(Debug)]
+struct Unauthorized;
+
+impl fmt::Display for Unauthorized {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid or missing authentication token")
+    }
+}
+
+impl Error for Unauthorized {}
+
+/// An invalid server configuration. Its message says which settings to fix.
+#[derive(Debug)]
+pub enum ServiceConfigError {
+    InvalidBindAddress(String, AddrParseError),
+    /// Only one of the TLS certificate and private key is set.
+    IncompleteTls,
 }
 
+impl fmt::Display for ServiceConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ServiceConfigError::InvalidBindAddress(address, err) => write!(
+                f,
+                "invalid bind address `{}` ({}); PRUSTI_SERVER_BIND_ADDRESS must be an IP address",
+                address, err,
+            ),
+            ServiceConfigError::IncompleteTls => write!(
+                f,
+                "PRUSTI_SERVER_TLS_CERT and PRUSTI_SERVER_TLS_KEY must be set together",
+            ),
+        }
+    }
+}
+
+impl Error for ServiceConfigError {}
+
 impl ServerSideService {
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self, ServiceConfigError> {
         // FIXME: since viper seems to dislike using verifiers in parallel, this is what we're doing to ensure correctness for now.
         // Eventually, we should lock only specific parts, instantiate multiple JVMs, or even address the root cause.
-        let _max_concurrency = config::server_max_concurrency().unwrap_or_else(num_cpus::get);
+        let max_batch_concurrency = config::server_max_concurrency().unwrap_or_else(num_cpus::get);
         let max_concurrency = 1;
 
         let cache_size = config::server_max_stored_verifiers().unwrap_or(max_concurrency);
@@ -38,16 +95,64 @@ impl ServerSideService {
             warn!("PRUSTI_SERVER_MAX_STORED_VERIFIERS is lower than PRUSTI_SERVER_MAX_CONCURRENCY—you probably don't want to do this, since it means the server will likely have to keep creating new verifiers, reducing the performance gained from reuse.");
         }
 
-        Self {
+        let bind_address = match config::server_bind_address() {
+            Some(address) => address
+                .parse()
+                .map_err(|err| ServiceConfigError::InvalidBindAddress(address, err))?,
+            None => Ipv4Addr::LOCALHOST.into(),
+        };
+
+        let tls = match (config::server_tls_cert(), config::server_tls_key()) {
+            (Some(cert), Some(key)) => Some((cert, key)),
+            (None, None) => None,
+            _ => return Err(ServiceConfigError::IncompleteTls),
+        };
+
+        Ok(Self {
             max_concurrency,
+            max_batch_concurrency,
             server: Arc::new(PrustiServer::new(cache_size)),
-        }
+            bind_address,
+            token: config::server_token(),
+            tls,
+        })
+    }
+
+    /// Listens on `address` instead of the configured bind address.
+    pub fn with_bind_address(mut self, address: IpAddr) -> Self {
+        self.bind_address = address;
+        self
+    }
+
+    /// Requires the requests to carry the bearer `token`.
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Serves the requests over TLS, with the PEM certificate chain `cert` and
+    /// its PEM private key `key`.
+    pub fn with_tls(mut self, cert: PathBuf, key: PathBuf) -> Self {
+        self.tls = Some((cert, key));
+        self
     }
 
+    /// Starts a server that only accepts connections from the local machine.
+    ///
+    /// Panics if the configuration of the server is invalid.
     pub fn spawn_off_thread() -> SocketAddr {
+        ServerSideService::new()
+            .unwrap_or_else(|err| panic!("Invalid Prusti server configuration: {}", err))
+            .with_bind_address(Ipv4Addr::LOCALHOST.into())
+            .spawn_self_off_thread()
+    }
+
+    /// Like `spawn_off_thread`, but for a service that has already been
+    /// configured.
+    pub fn spawn_self_off_thread(self) -> SocketAddr {
         let (sender, receiver) = mpsc::channel();
         thread::spawn(move || {
-            ServerSideService::new().listen_on_ephemeral_port(
+            self.listen_on_ephemeral_port(
                 0, // ask system for port
                 |address| sender.send(address).unwrap(),
             );
@@ -73,20 +178,33 @@ impl ServerSideService {
     where
         F: FnOnce(SocketAddr),
     {
+        let token = self.token.clone();
+        let authorized = warp::header::optional::<String>("authorization").and_then(
+            move |authorization: Option<String>| match &token {
+                Some(token) if !is_bearer_token(authorization.as_deref(), token) => {
+                    info!("Rejecting a request without the authentication token");
+                    Err(warp::reject::custom(Unauthorized))
+                }
+                _ => Ok(Authorized),
+            },
+        );
+
         let clone = self.clone();
         let json_verify = warp::path("json")
             .and(warp::path("verify"))
             .and(warp::path::end())
+            .and(authorized.clone())
             .and(warp::body::json())
-            .map(move |request: VerificationRequest| clone.verify(request))
+            .map(move |_: Authorized, request: VerificationRequest| clone.verify(request))
             .map(|response| warp::reply::json(&response));
 
         let clone = self.clone();
         let bincode_verify = warp::path("bincode")
             .and(warp::path("verify"))
             .and(warp::path::end())
+            .and(authorized.clone())
             .and(warp::body::concat())
-            .and_then(|buf: warp::body::FullBody| {
+            .and_then(|_: Authorized, buf: warp::body::FullBody| {
                 bincode::deserialize(&buf.bytes()).map_err(|err| {
                     info!("request bincode body error: {}", err);
                     warp::reject::custom(err)
@@ -99,11 +217,50 @@ impl ServerSideService {
                 )
             });
 
-        let endpoints = json_verify.or(bincode_verify);
+        let clone = self.clone();
+        let json_verify_batch = warp::path("json")
+            .and(warp::path("verify_batch"))
+            .and(warp::path::end())
+            .and(authorized)
+            .and(warp::body::json())
+            .map(move |_: Authorized, batch: BatchVerificationRequest| {
+                warp::http::Response::builder()
+                    .header("content-type", "application/x-ndjson")
+                    .body(hyper::Body::wrap_stream(clone.verify_batch(batch)))
+                    .expect("could not build the batch response")
+            });
 
-        info!("Prusti Server binding to port {}", port);
-        let (address, server_handle) =
-            warp::serve(endpoints).bind_ephemeral((Ipv4Addr::LOCALHOST, port));
+        let endpoints = json_verify
+            .or(bincode_verify)
+            .or(json_verify_batch)
+            .recover(|rejection: Rejection| {
+                if rejection.find_cause::<Unauthorized>().is_some() {
+                    Ok(warp::reply::with_status(
+                        Unauthorized.to_string(),
+                        warp::http::StatusCode::UNAUTHORIZED,
+                    ))
+                } else {
+                    Err(rejection)
+                }
+            });
+
+        let bind_address = self.bind_address;
+        info!("Prusti Server binding to {}:{}", bind_address, port);
+        let (address, server_handle): (SocketAddr, Box<dyn Future<Item = (), Error = ()> + Send>) =
+            match self.tls.clone() {
+                Some((cert, key)) => {
+                    info!("Prusti Server using TLS with the certificate {}", cert.display());
+                    let (address, handle) = warp::serve(endpoints)
+                        .tls(cert, key)
+                        .bind_ephemeral((bind_address, port));
+                    (address, Box::new(handle))
+                }
+                None => {
+                    let (address, handle) =
+                        warp::serve(endpoints).bind_ephemeral((bind_address, port));
+                    (address, Box::new(handle))
+                }
+            };
         address_callback(address);
 
         info!("Prusti Server starting on port {}", address.port());
@@ -127,55 +284,231 @@ impl ServerSideService {
         info!("Handling verification request for {}", request.program_name);
         self.server.run_verifier(request)
     }
+
+    /// Returns the stream of the results of the requests of `batch`, encoded
+    /// as newline-delimited JSON, in the order in which they complete.
+    fn verify_batch(
+        &self,
+        batch: BatchVerificationRequest,
+    ) -> impl Stream<Item = Vec<u8>, Error = io::Error> {
+        info!("Handling a batch of {} verification requests", batch.requests.len());
+        let server = self.server.clone();
+        let items = verify_batch(batch, self.max_batch_concurrency, move |request| {
+            server.run_verifier(request)
+        });
+        let (line_sender, line_receiver) = futures::sync::mpsc::unbounded();
+        thread::spawn(move || {
+            for item in items {
+                if line_sender.unbounded_send(encode_batch_item(&item)).is_err() {
+                    info!("The client of a batch request disconnected");
+                    break;
+                }
+            }
+        });
+        line_receiver.map_err(|()| io::Error::new(io::ErrorKind::Other, "batch results channel failed"))
+    }
 }
 
+/// Compares the `Authorization` header of a request with the bearer `token`,
+/// in a time that does not depend on how much of the token matches.
+fn is_bearer_token(authorization: Option<&str>, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    match authorization {
+        Some(authorization) if authorization.len() == expected.len() => authorization
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0,
+        _ => false,
+    }
+}
+
+/// An error while communicating with the server. Its message says how the
+/// user can address it.
+#[derive(Debug)]
+pub enum ConnectionError {
+    InvalidAddress(String, UrlError),
+    InvalidCertificate(PathBuf, String),
+    /// The client would send its authentication token over plain http.
+    InsecureToken(Url),
+    /// The server rejected the authentication token of the client.
+    Unauthorized,
+    Request(Url, reqwest::Error),
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConnectionError::InvalidAddress(address, err) => write!(
+                f,
+                "invalid Prusti server address `{}` ({}); check PRUSTI_SERVER_ADDRESS",
+                address, err,
+            ),
+            ConnectionError::InvalidCertificate(path, err) => write!(
+                f,
+                "could not load the certificate `{}` ({}); PRUSTI_SERVER_CA_CERT must be the path \
+                of a PEM certificate",
+                path.display(), err,
+            ),
+            ConnectionError::InsecureToken(url) => write!(
+                f,
+                "refusing to send the authentication token in plain text to the Prusti server at \
+                {}; use an `https://` PRUSTI_SERVER_ADDRESS, or set \
+                PRUSTI_SERVER_ALLOW_INSECURE_TOKEN=true to send it anyway",
+                url,
+            ),
+            ConnectionError::Unauthorized => write!(
+                f,
+                "the Prusti server rejected the authentication token; check that \
+                PRUSTI_SERVER_TOKEN is set to the token of the server",
+            ),
+            ConnectionError::Request(url, err) => {
+                let message = err.to_string();
+                if let Some(status) = err.status() {
+                    write!(f, "the Prusti server at {} answered with status {}", url, status)
+                } else if message.contains("certificate") || message.contains("ssl") || message.contains("tls") {
+                    write!(
+                        f,
+                        "could not establish a secure connection to the Prusti server at {} ({}); \
+                        if the server uses a self-signed certificate, set PRUSTI_SERVER_CA_CERT \
+                        to its path",
+                        url, message,
+                    )
+                } else if err.is_timeout() {
+                    write!(f, "the connection to the Prusti server at {} timed out", url)
+                } else {
+                    write!(
+                        f,
+                        "could not communicate with the Prusti server at {} ({}); check that it is \
+                        running, and that PRUSTI_SERVER_ADDRESS starts with `https://` if it uses TLS",
+                        url, message,
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl Error for ConnectionError {}
+
 pub struct PrustiServerConnection {
     client: Client,
     server_url: Url,
+    token: Option<String>,
 }
 
 impl PrustiServerConnection {
-    pub fn new<S: ToString>(server_address: S) -> Result<Self, UrlError> {
+    /// Connects to the server with the token and the root certificate of the
+    /// configuration.
+    pub fn new<S: ToString>(server_address: S) -> Result<Self, ConnectionError> {
+        let ca_cert = config::server_ca_cert();
+        Self::with_credentials(server_address, config::server_token(), ca_cert.as_deref())
+    }
+
+    /// Connects to the server, authenticating with the bearer `token` and
+    /// trusting the PEM certificate at `ca_cert` in addition to the system's
+    /// root certificates. Unless `server_allow_insecure_token` is set, a token
+    /// is only sent over `https://`.
+    pub fn with_credentials<S: ToString>(
+        server_address: S,
+        token: Option<String>,
+        ca_cert: Option<&Path>,
+    ) -> Result<Self, ConnectionError> {
         let mut address = server_address.to_string();
         if !address.starts_with("http") {
             address = format!("http://{}", address);
         }
+        let server_url = Url::parse(address.as_str())
+            .map_err(|err| ConnectionError::InvalidAddress(address.clone(), err))?;
+        if token.is_some() && server_url.scheme() != "https" && !config::server_allow_insecure_token() {
+            return Err(ConnectionError::InsecureToken(server_url));
+        }
+        let mut client = Client::builder().timeout(None);
+        if let Some(path) = ca_cert {
+            let certificate = fs::read(path)
+                .map_err(|err| err.to_string())
+                .and_then(|pem| Certificate::from_pem(&pem).map_err(|err| err.to_string()))
+                .map_err(|err| ConnectionError::InvalidCertificate(path.to_path_buf(), err))?;
+            client = client.add_root_certificate(certificate);
+        }
         Ok(Self {
-            client: Client::builder().timeout(None).build().unwrap(),
-            server_url: Url::parse(address.as_str())?,
+            client: client
+                .build()
+                .map_err(|err| ConnectionError::Request(server_url.clone(), err))?,
+            server_url,
+            token,
         })
     }
 
+    /// Sends the request to `path` of the server, with the token if any.
+    fn post(&self, path: &str, build: impl FnOnce(reqwest::RequestBuilder) -> reqwest::RequestBuilder)
+        -> Result<reqwest::Response, ConnectionError>
+    {
+        let url = self.server_url.join(path).unwrap();
+        let mut request = self.client.post(url.clone());
+        if let Some(token) = &self.token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let response = build(request)
+            .send()
+            .map_err(|err| ConnectionError::Request(url.clone(), err))?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Err(ConnectionError::Unauthorized);
+        }
+        response
+            .error_for_status()
+            .map_err(|err| ConnectionError::Request(url, err))
+    }
+
     pub fn verify_checked(
         &self,
         request: VerificationRequest,
-    ) -> reqwest::Result<RemoteVerificationResult> {
+    ) -> Result<RemoteVerificationResult, ConnectionError> {
         let use_json = config::json_communication();
-        let base = self.client.post(
-            self.server_url
-                .join(if use_json { "json/" } else { "bincode/" })
-                .unwrap()
-                .join("verify/")
-                .unwrap(),
-        );
+        let path = if use_json { "json/verify/" } else { "bincode/verify/" };
         let response = if use_json {
-            base.json(&request).send()?.error_for_status()?.json()?
+            let url = self.server_url.join(path).unwrap();
+            self.post(path, |base| base.json(&request))?
+                .json()
+                .map_err(|err| ConnectionError::Request(url, err))?
         } else {
-            let raw = base
-                .body(bincode::serialize(&request).expect("error encoding verification request"))
-                .send()?
-                .error_for_status()?;
+            let raw = self.post(path, |base| {
+                base.body(bincode::serialize(&request).expect("error encoding verification request"))
+            })?;
             bincode::deserialize_from(raw).expect("error decoding verification result")
         };
         Ok(response)
     }
+
+    /// Sends all requests of `batch` at once, and returns the results as they
+    /// are streamed back by the server. A result that cannot be read ends the
+    /// iteration, after logging the error.
+    pub fn verify_batch(
+        &self,
+        batch: BatchVerificationRequest,
+    ) -> Result<impl Iterator<Item = BatchItem>, ConnectionError> {
+        let response = self.post("json/verify_batch/", |base| base.json(&batch))?;
+        let lines = BufReader::new(response).lines();
+        Ok(lines
+            .map(|line| {
+                line.map_err(|err| err.to_string())
+                    .and_then(|line| decode_batch_item(&line).map_err(|err| err.to_string()))
+            })
+            .take_while(|item| {
+                if let Err(err) = item {
+                    error!("Could not read a batch verification result: {}", err);
+                }
+                item.is_ok()
+            })
+            .map(Result::unwrap))
+    }
 }
 
 impl VerificationService for PrustiServerConnection {
     /// panics if the verification request fails
     fn verify(&self, request: VerificationRequest) -> ProgramVerificationResult {
         self.verify_checked(request)
-            .expect("Verification request to server failed!")
+            .unwrap_or_else(|err| panic!("Verification request to server failed: {}", err))
             .expect("Server panicked while processing request!")
     }
 }
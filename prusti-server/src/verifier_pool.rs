@@ -0,0 +1,235 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::{BatchItemResult, RemoteVerificationResult, VerifierPanicked, VerifierRunner};
+use prusti_common::{
+    config,
+    report::progress::{self, Phase},
+    verification_context::VerifierBuilder,
+    verification_service::VerificationRequest,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+type BatchQueue = Arc<Mutex<VecDeque<(usize, VerificationRequest)>>>;
+
+/// What a verifier thread tells `verify_in_parallel`.
+enum WorkerMessage {
+    /// The thread started verifying the batch with this index.
+    Started(usize, usize),
+    /// The thread finished verifying the batch with this index.
+    Finished(usize, usize, RemoteVerificationResult),
+    /// The thread stopped taking batches.
+    Exited(usize),
+}
+
+/// Verifies independent requests concurrently, on a pool of verifier threads
+/// that share the JVM of `verifier_builder`. Each thread attaches to the JVM
+/// and creates its own verifier, which it recreates whenever a request needs a
+/// different backend configuration.
+///
+/// The results are returned in the same order as the requests. A panic while
+/// verifying a batch is reported as the result of that batch only; the
+/// thread that panicked stops taking new batches, which are verified by the
+/// remaining threads.
+///
+/// A batch whose verification takes longer than `timeout` is reported as
+/// timed out, and a new thread takes over the remaining batches. The backend
+/// cannot be interrupted from the outside, so it is told to stop on its own
+/// shortly after `timeout`; the thread verifying the batch then exits.
+pub fn verify_in_parallel(
+    verifier_builder: Arc<VerifierBuilder>,
+    mut requests: Vec<VerificationRequest>,
+    timeout: Option<Duration>,
+) -> Vec<BatchItemResult> {
+    if let Some(timeout) = timeout {
+        for request in &mut requests {
+            request.backend_config.set_timeout(timeout);
+        }
+    }
+    let batch_count = requests.len();
+    // The name of the program of each batch, to report the progress.
+    let batch_names: Vec<String> = requests
+        .iter()
+        .map(|request| {
+            let names: Vec<_> = request.programs.iter().map(|program| program.name.as_str()).collect();
+            names.join(", ")
+        })
+        .collect();
+    let thread_count = config::num_parallel_verifiers()
+        .unwrap_or_else(num_cpus::get)
+        .max(1)
+        .min(batch_count);
+    let queue: BatchQueue = Arc::new(Mutex::new(requests.into_iter().enumerate().collect()));
+    let (message_sender, message_receiver) = mpsc::channel();
+
+    // The threads that are still taking batches, with the flag that tells
+    // them to stop once they have been abandoned.
+    let mut workers: HashMap<usize, Arc<AtomicBool>> = HashMap::new();
+    let mut next_thread_index = 0;
+    let mut spawn_worker = |workers: &mut HashMap<usize, Arc<AtomicBool>>| {
+        let thread_index = next_thread_index;
+        next_thread_index += 1;
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let spawned = spawn_verifier_thread(
+            thread_index,
+            verifier_builder.clone(),
+            queue.clone(),
+            message_sender.clone(),
+            abandoned.clone(),
+        );
+        match spawned {
+            Ok(()) => {
+                workers.insert(thread_index, abandoned);
+            }
+            Err(err) => error!("Failed to spawn a verifier thread: {:?}", err),
+        }
+    };
+    for _ in 0..thread_count {
+        spawn_worker(&mut workers);
+    }
+
+    let mut results: Vec<Option<BatchItemResult>> = (0..batch_count).map(|_| None).collect();
+    let mut remaining = batch_count;
+    // The time at which each batch that is being verified times out, with
+    // the thread that verifies it.
+    let mut deadlines: HashMap<usize, (Instant, usize)> = HashMap::new();
+    while remaining > 0 && !workers.is_empty() {
+        let next_deadline = deadlines.values().map(|&(deadline, _)| deadline).min();
+        let message = match next_deadline {
+            Some(deadline) => {
+                let wait = deadline.saturating_duration_since(Instant::now());
+                match message_receiver.recv_timeout(wait) {
+                    Ok(message) => Some(message),
+                    Err(mpsc::RecvTimeoutError::Timeout) => None,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            None => match message_receiver.recv() {
+                Ok(message) => Some(message),
+                Err(_) => break,
+            },
+        };
+        match message {
+            Some(WorkerMessage::Started(thread_index, batch_index)) => {
+                progress::start(Phase::Verification, &batch_names[batch_index]);
+                if let Some(timeout) = timeout {
+                    deadlines.insert(batch_index, (Instant::now() + timeout, thread_index));
+                }
+            }
+            Some(WorkerMessage::Finished(_, batch_index, result)) => {
+                deadlines.remove(&batch_index);
+                // The result of a batch that timed out is discarded.
+                if results[batch_index].is_none() {
+                    let result = BatchItemResult::from(result);
+                    progress::finish(Phase::Verification, &batch_names[batch_index], result.outcome());
+                    results[batch_index] = Some(result);
+                    remaining -= 1;
+                }
+            }
+            Some(WorkerMessage::Exited(thread_index)) => {
+                workers.remove(&thread_index);
+            }
+            None => {
+                let now = Instant::now();
+                let expired: Vec<_> = deadlines
+                    .iter()
+                    .filter(|(_, &(deadline, _))| deadline <= now)
+                    .map(|(&batch_index, &(_, thread_index))| (batch_index, thread_index))
+                    .collect();
+                for (batch_index, thread_index) in expired {
+                    info!("The verification of batch {} timed out", batch_index);
+                    deadlines.remove(&batch_index);
+                    let result = BatchItemResult::TimedOut;
+                    progress::finish(Phase::Verification, &batch_names[batch_index], result.outcome());
+                    results[batch_index] = Some(result);
+                    remaining -= 1;
+                    if let Some(abandoned) = workers.remove(&thread_index) {
+                        abandoned.store(true, Ordering::SeqCst);
+                    }
+                    if !queue.lock().unwrap().is_empty() {
+                        spawn_worker(&mut workers);
+                    }
+                }
+            }
+        }
+    }
+
+    // A batch without a result was left in the queue by threads that panicked.
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or(BatchItemResult::Panicked))
+        .collect()
+}
+
+fn spawn_verifier_thread(
+    thread_index: usize,
+    verifier_builder: Arc<VerifierBuilder>,
+    queue: BatchQueue,
+    message_sender: mpsc::Sender<WorkerMessage>,
+    abandoned: Arc<AtomicBool>,
+) -> std::io::Result<()> {
+    thread::Builder::new()
+        .name(format!("Verifier thread {}", thread_index))
+        .spawn(move || {
+            let send = |message| {
+                message_sender.send(message).unwrap_or_else(|err| {
+                    error!("verifier thread attempting to send result to dropped receiver: {:?}", err);
+                });
+            };
+            let next_batch = || queue.lock().unwrap().pop_front();
+            // A batch that needs a different backend configuration than the
+            // one of the current verifier.
+            let mut pending = next_batch();
+            while let Some((first_index, first_request)) = pending.take() {
+                let backend_config = first_request.backend_config.clone();
+                let panicked = VerifierRunner::with_runner(&verifier_builder, &backend_config, |runner| {
+                    let mut batch = Some((first_index, first_request));
+                    while let Some((batch_index, request)) = batch.take() {
+                        send(WorkerMessage::Started(thread_index, batch_index));
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                            runner.verify(request.programs, request.program_name.as_str())
+                        }));
+                        let panicked = result.is_err();
+                        send(WorkerMessage::Finished(
+                            thread_index,
+                            batch_index,
+                            result.map_err(|_| VerifierPanicked),
+                        ));
+                        if panicked {
+                            return true;
+                        }
+                        if abandoned.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        match next_batch() {
+                            Some(next) if next.1.backend_config == backend_config => batch = Some(next),
+                            next => pending = next,
+                        }
+                    }
+                    false
+                });
+                if panicked || abandoned.load(Ordering::SeqCst) {
+                    // A verifier that panicked might be in an inconsistent
+                    // state; the remaining threads take over its batches.
+                    if let Some(batch) = pending.take() {
+                        queue.lock().unwrap().push_front(batch);
+                    }
+                    break;
+                }
+            }
+            send(WorkerMessage::Exited(thread_index));
+        })
+        .map(|_| ())
+}
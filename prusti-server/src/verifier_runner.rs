@@ -6,13 +6,15 @@
 
 use prusti_common::{
     config,
-    report::log,
     verification_context::*,
     verification_service::ViperBackendConfig,
     vir::{Program, ToViper},
     Stopwatch,
 };
 use viper::{self, ConsistencyError, JavaExceptionWithOrigin, ProgramVerificationResult, VerificationResult};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
 
 pub struct VerifierRunner<'v> {
     verifier: viper::Verifier<'v, viper::state::Started>,
@@ -58,14 +60,25 @@ impl<'v> VerifierRunner<'v> {
     pub fn verify(&self, programs: Vec<Program>, program_name: &str) -> ProgramVerificationResult {
         let mut results = ProgramVerificationResult::default();
         for program in programs {
+            let start_time = Instant::now();
             let mut stopwatch = Stopwatch::start("prusti-server", "construction of JVM objects");
             let viper_program = program.to_viper(&self.ast_factory);
-            if config::dump_viper_program() {
+            if let Some(dump_dir) = config::dump_viper_program() {
                 stopwatch.start_next("dumping viper program");
-                self.dump(viper_program, program_name, &program.name);
+                self.dump(viper_program, &dump_dir, program_name, &program.name);
             }
             stopwatch.start_next("verification");
-            match self.verifier.verify(viper_program) {
+            let rlimit_count_before = self.verifier.smt_rlimit_count();
+            let result = self.verifier.verify(viper_program);
+            if let Some(count) = self.verifier.smt_rlimit_count() {
+                // The count is cumulative, unless the solver was restarted.
+                let consumed = match rlimit_count_before {
+                    Some(before) if before <= count => count - before,
+                    _ => count,
+                };
+                results.smt_rlimit_count = Some(results.smt_rlimit_count.unwrap_or(0) + consumed);
+            }
+            match result {
                 VerificationResult::Success => {},
                 VerificationResult::Failure(errors) => {
                     results.verification_errors.extend(errors);
@@ -85,14 +98,29 @@ impl<'v> VerifierRunner<'v> {
                     });
                 }
             }
+            results.verification_time += start_time.elapsed();
         }
         results
     }
 
-    fn dump(&self, program: viper::Program, program_name: &str, method_name: &str) {
-        let namespace = "viper_program";
-        let filename = format!("{}-{}.vpr", program_name, method_name);
-        info!("Dumping Viper program to '{}/{}'", namespace, filename);
-        log::report(namespace, filename, self.ast_utils.pretty_print(program));
+    /// Write the program to a file of `dump_dir` named after the source file
+    /// and the procedure from which it was encoded.
+    fn dump(&self, program: viper::Program, dump_dir: &Path, program_name: &str, method_name: &str) {
+        let filename = format!("{}-{}.vpr", program_name, sanitize_file_name(method_name));
+        let path = dump_dir.join(filename);
+        info!("Dumping Viper program to '{}'", path.display());
+        let result = fs::create_dir_all(dump_dir)
+            .and_then(|()| fs::write(&path, self.ast_utils.pretty_print(program)));
+        if let Err(error) = result {
+            error!("Failed to dump the Viper program to '{}': {}", path.display(), error);
+        }
     }
 }
+
+/// Replace the characters of a def path such as `module::Type::<T>::method`
+/// that are not allowed, or not convenient, in file names.
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect()
+}
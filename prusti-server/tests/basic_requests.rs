@@ -5,10 +5,10 @@ extern crate viper;
 extern crate lazy_static;
 
 use prusti_common::{
-    verification_service::{VerificationRequest, VerificationService},
+    verification_service::{BatchVerificationRequest, VerificationRequest, VerificationService},
     vir::*,
 };
-use prusti_server::{PrustiServerConnection, ServerSideService};
+use prusti_server::{BatchItemResult, PrustiServerConnection, ServerSideService};
 use viper::ProgramVerificationResult;
 
 lazy_static! {
@@ -28,7 +28,8 @@ fn consistency_error() {
     let ProgramVerificationResult {
         verification_errors,
         mut consistency_errors,
-        java_exceptions
+        java_exceptions,
+        ..
     } = result;
 
     assert!(verification_errors.is_empty());
@@ -45,7 +46,8 @@ fn empty_program() {
     let ProgramVerificationResult {
         verification_errors,
         consistency_errors,
-        java_exceptions
+        java_exceptions,
+        ..
     } = result;
 
     assert!(verification_errors.is_empty());
@@ -53,14 +55,54 @@ fn empty_program() {
     assert!(consistency_errors.is_empty());
 }
 
-fn process_program<F>(configure: F) -> ProgramVerificationResult
-where
-    F: FnOnce(&mut Program),
-{
+#[test]
+fn batch_of_programs() {
     let service =
         PrustiServerConnection::new(SERVER_ADDRESS.clone()).expect("Could not connect to server!");
 
-    let mut program = Program {
+    let mut invalid_program = dummy_program();
+    invalid_program.fields.push(Field {
+        name: "field".to_string(), // reserved identifier
+        typ: Type::Int,
+    });
+    let programs = vec![dummy_program(), invalid_program, dummy_program()];
+    let batch = BatchVerificationRequest {
+        requests: programs
+            .into_iter()
+            .enumerate()
+            .map(|(index, program)| VerificationRequest {
+                programs: vec![program],
+                program_name: format!("dummy_{}", index),
+                backend_config: Default::default(),
+            })
+            .collect(),
+        max_concurrency: Some(2),
+        timeout_ms: None,
+    };
+
+    let mut items: Vec<_> = service
+        .verify_batch(batch)
+        .expect("Batch verification request failed!")
+        .collect();
+    items.sort_by_key(|item| item.index);
+
+    assert_eq!(items.len(), 3);
+    for (index, item) in items.into_iter().enumerate() {
+        assert_eq!(item.index, index);
+        assert_eq!(item.program_name, format!("dummy_{}", index));
+        let result = match item.result {
+            BatchItemResult::Verified(result) => result,
+            failure => panic!("unexpected result: {:?}", failure),
+        };
+        assert!(result.verification_errors.is_empty());
+        assert!(result.java_exceptions.is_empty());
+        // Only the invalid program fails; the others are not affected.
+        assert_eq!(result.consistency_errors.is_empty(), index != 1);
+    }
+}
+
+fn dummy_program() -> Program {
+    Program {
         name: "very_dummy".to_string(),
         domains: vec![],
         fields: vec![],
@@ -68,7 +110,17 @@ where
         methods: vec![],
         functions: vec![],
         viper_predicates: vec![],
-    };
+    }
+}
+
+fn process_program<F>(configure: F) -> ProgramVerificationResult
+where
+    F: FnOnce(&mut Program),
+{
+    let service =
+        PrustiServerConnection::new(SERVER_ADDRESS.clone()).expect("Could not connect to server!");
+
+    let mut program = dummy_program();
     configure(&mut program);
 
     let request = VerificationRequest {
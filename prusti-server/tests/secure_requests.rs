@@ -0,0 +1,120 @@
+extern crate prusti_common;
+extern crate prusti_server;
+#[macro_use]
+extern crate lazy_static;
+
+use prusti_common::{
+    verification_service::{VerificationRequest, ViperBackendConfig},
+    vir::*,
+};
+use prusti_server::{ConnectionError, PrustiServerConnection, ServerSideService};
+use std::path::PathBuf;
+
+const TOKEN: &str = "correct horse battery staple";
+
+lazy_static! {
+    // only start the jvm & server once
+    static ref SERVER_ADDRESS: String = {
+        let address = ServerSideService::new()
+            .expect("invalid server configuration")
+            .with_token(Some(TOKEN.to_string()))
+            .with_tls(cert_path("localhost.crt"), cert_path("localhost.key"))
+            .spawn_self_off_thread();
+        format!("https://127.0.0.1:{}", address.port())
+    };
+}
+
+/// The self-signed certificate of the tests, generated with
+/// `openssl req -x509 -newkey rsa:2048 -nodes -keyout localhost.key -out localhost.crt
+/// -days 36500 -subj "/CN=localhost" -addext "subjectAltName=DNS:localhost,IP:127.0.0.1"`
+fn cert_path(file_name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("certs")
+        .join(file_name)
+}
+
+fn connect(token: Option<&str>, trust_cert: bool) -> PrustiServerConnection {
+    let ca_cert = if trust_cert { Some(cert_path("localhost.crt")) } else { None };
+    PrustiServerConnection::with_credentials(
+        SERVER_ADDRESS.clone(),
+        token.map(|token| token.to_string()),
+        ca_cert.as_deref(),
+    )
+    .expect("Could not connect to server!")
+}
+
+fn dummy_request() -> VerificationRequest {
+    VerificationRequest {
+        programs: vec![Program {
+            name: "very_dummy".to_string(),
+            domains: vec![],
+            fields: vec![],
+            builtin_methods: vec![],
+            methods: vec![],
+            functions: vec![],
+            viper_predicates: vec![],
+        }],
+        program_name: "dummy".to_string(),
+        backend_config: ViperBackendConfig::default(),
+    }
+}
+
+#[test]
+fn correct_token() {
+    let result = connect(Some(TOKEN), true)
+        .verify_checked(dummy_request())
+        .expect("the request should be accepted")
+        .expect("the server should not panic");
+    assert!(result.verification_errors.is_empty());
+    assert!(result.consistency_errors.is_empty());
+    assert!(result.java_exceptions.is_empty());
+}
+
+#[test]
+fn wrong_token() {
+    let error = connect(Some("wrong token"), true)
+        .verify_checked(dummy_request())
+        .expect_err("the request should be rejected");
+    assert!(matches!(error, ConnectionError::Unauthorized), "{:?}", error);
+    assert!(error.to_string().contains("PRUSTI_SERVER_TOKEN"));
+}
+
+#[test]
+fn missing_token() {
+    let error = connect(None, true)
+        .verify_checked(dummy_request())
+        .expect_err("the request should be rejected");
+    assert!(matches!(error, ConnectionError::Unauthorized), "{:?}", error);
+}
+
+#[test]
+fn untrusted_certificate() {
+    let error = connect(Some(TOKEN), false)
+        .verify_checked(dummy_request())
+        .expect_err("the certificate should not be trusted");
+    assert!(matches!(error, ConnectionError::Request(..)), "{:?}", error);
+    assert!(error.to_string().contains("PRUSTI_SERVER_CA_CERT"), "{}", error);
+}
+
+#[test]
+fn invalid_certificate_path() {
+    let result = PrustiServerConnection::with_credentials(
+        SERVER_ADDRESS.clone(),
+        None,
+        Some(&cert_path("missing.crt")),
+    );
+    assert!(matches!(result, Err(ConnectionError::InvalidCertificate(..))));
+}
+
+#[test]
+fn token_over_plain_http() {
+    let address = format!("http://127.0.0.1:{}", SERVER_ADDRESS.rsplit(':').next().unwrap());
+    let result = PrustiServerConnection::with_credentials(address, Some(TOKEN.to_string()), None);
+    match result {
+        Err(error @ ConnectionError::InsecureToken(..)) => {
+            assert!(error.to_string().contains("PRUSTI_SERVER_ALLOW_INSECURE_TOKEN"), "{}", error)
+        }
+        _ => panic!("the token should not be sent over plain http"),
+    }
+}
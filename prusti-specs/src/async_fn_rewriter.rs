@@ -0,0 +1,140 @@
+//! Prusti does not encode the state machine that an `async fn` is compiled
+//! to. Instead, it verifies a synchronous copy of the function: the copy has
+//! the signature of the `async fn` without the `async`, the same
+//! specification, and the same body, except that every `.await` on a call of
+//! another `async fn` is replaced by a call of the synchronous copy of the
+//! callee. The callee thus needs a Prusti attribute too, like `#[trusted]`,
+//! so that its copy exists. The `async fn` itself is trusted.
+//!
+//! This ignores the scheduling of the futures and their cancellation at
+//! `.await` points.
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, quote_spanned};
+use syn::{spanned::Spanned, visit_mut::VisitMut};
+
+use crate::specifications::untyped;
+
+/// The name of the synchronous copy of the `async fn` named `ident`.
+fn sync_copy_ident(ident: &syn::Ident) -> syn::Ident {
+    format_ident!("prusti_async_body_{}", ident, span = ident.span())
+}
+
+/// Rewrite an `async fn`, given the items and the attributes generated for
+/// its specification.
+pub fn rewrite_async_fn(
+    item: &untyped::AnyFnItem,
+    spec_items: Vec<syn::Item>,
+    spec_attributes: Vec<syn::Attribute>,
+) -> syn::Result<TokenStream> {
+    let span = item.span();
+    let is_pure = spec_attributes.iter().any(|attr| {
+        attr.path.segments.len() == 2 && attr.path.segments[1].ident == "pure"
+    });
+    if is_pure {
+        return Err(syn::Error::new(span, "an `async fn` cannot be `#[pure]`"));
+    }
+
+    let mut sync_fn: syn::ItemFn = match item {
+        untyped::AnyFnItem::Fn(item) => item.clone(),
+        untyped::AnyFnItem::ImplMethod(method) => syn::ItemFn {
+            attrs: method.attrs.clone(),
+            vis: method.vis.clone(),
+            sig: method.sig.clone(),
+            block: Box::new(method.block.clone()),
+        },
+        untyped::AnyFnItem::TraitMethod(_) => {
+            return Err(syn::Error::new(span, "Prusti does not support `async fn`s in traits"));
+        }
+    };
+    sync_fn.sig.asyncness = None;
+    sync_fn.sig.ident = sync_copy_ident(&sync_fn.sig.ident);
+    // Other attributes, like the ones of async runtimes, might not apply to
+    // a synchronous function.
+    sync_fn.attrs.retain(|attr| attr.path.is_ident("cfg"));
+    let mut rewriter = AwaitRewriter { error: None };
+    rewriter.visit_block_mut(&mut sync_fn.block);
+    if let Some(error) = rewriter.error {
+        return Err(error);
+    }
+
+    Ok(quote_spanned! {span=>
+        #(#spec_items)*
+        #[prusti::async_body]
+        #[allow(dead_code, non_snake_case)]
+        #(#spec_attributes)*
+        #sync_fn
+        #[prusti::trusted]
+        #item
+    })
+}
+
+/// Replaces `callee(args).await` by `prusti_async_body_callee(args)`, and
+/// `receiver.callee(args).await` by `receiver.prusti_async_body_callee(args)`.
+struct AwaitRewriter {
+    error: Option<syn::Error>,
+}
+
+impl AwaitRewriter {
+    fn unsupported(&mut self, span: proc_macro2::Span, message: &str) {
+        if self.error.is_none() {
+            self.error = Some(syn::Error::new(span, message));
+        }
+    }
+}
+
+impl VisitMut for AwaitRewriter {
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        match expr {
+            // The `.await`s of a nested async block belong to that block.
+            syn::Expr::Async(_) => return,
+            syn::Expr::Await(await_expr) => {
+                let await_span = await_expr.span();
+                let mut base = (*await_expr.base).clone();
+                match &mut base {
+                    syn::Expr::Call(syn::ExprCall { func, .. }) => match func.as_mut() {
+                        syn::Expr::Path(syn::ExprPath { path, .. }) => {
+                            let segment = path.segments.last_mut().unwrap();
+                            segment.ident = sync_copy_ident(&segment.ident);
+                        }
+                        _ => self.unsupported(
+                            await_span,
+                            "Prusti only supports `.await` on calls of `async fn`s given by their path",
+                        ),
+                    },
+                    syn::Expr::MethodCall(call) => {
+                        call.method = sync_copy_ident(&call.method);
+                    }
+                    _ => self.unsupported(
+                        await_span,
+                        "Prusti only supports `.await` directly on calls of `async fn`s",
+                    ),
+                }
+                *expr = base;
+            }
+            _ => {}
+        }
+        syn::visit_mut::visit_expr_mut(self, expr);
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut syn::Macro) {
+        if contains_await(mac.tokens.clone()) {
+            self.unsupported(
+                mac.span(),
+                "Prusti does not support `.await` in the arguments of macros",
+            );
+        }
+    }
+
+    fn visit_item_mut(&mut self, _item: &mut syn::Item) {
+        // Nested items cannot contain the `.await`s of the function.
+    }
+}
+
+fn contains_await(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == "await",
+        TokenTree::Group(group) => contains_await(group.stream()),
+        _ => false,
+    })
+}
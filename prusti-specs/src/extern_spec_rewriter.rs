@@ -23,6 +23,7 @@ pub fn rewrite_mod(item_mod: &mut syn::ItemMod, path: &mut syn::Path) -> syn::Re
                                     item_mod.span());
 
     for item in item_mod.content.as_mut().unwrap().1.iter_mut() {
+        let item_span = item.span();
         match item {
             syn::Item::Fn(item_fn) => {
                 rewrite_fn(item_fn, path);
@@ -31,28 +32,7 @@ pub fn rewrite_mod(item_mod: &mut syn::ItemMod, path: &mut syn::Path) -> syn::Re
                 rewrite_mod(inner_mod, path)?;
             },
             syn::Item::Verbatim(tokens) => {
-                // Transforms function stubs (functions with a `;` after the
-                // signature instead of the body) into functions, then
-                // processes them.
-                let mut new_tokens = TokenStream::new();
-                for mut token in tokens.clone().into_iter() {
-                    if let TokenTree::Punct(punct) = &mut token {
-                        if punct.as_char() == ';' {
-                            new_tokens.extend(Group::new(proc_macro2::Delimiter::Brace, TokenStream::new()).to_token_stream());
-                            continue;
-                        }
-                    }
-                    new_tokens.extend(token.to_token_stream());
-                }
-                let res: Result<syn::Item, _> = syn::parse2(new_tokens);
-                if res.is_err() {
-                    return Err(syn::Error::new(
-                        item.span(),
-                        "invalid function signature",
-                    ))
-                }
-
-                let mut item = res.unwrap();
+                let mut item = parse_fn_stub(tokens.clone(), item_span)?;
                 if let syn::Item::Fn(item_fn) = &mut item {
                     rewrite_fn(item_fn, path);
                 }
@@ -68,15 +48,39 @@ pub fn rewrite_mod(item_mod: &mut syn::ItemMod, path: &mut syn::Path) -> syn::Re
     Ok(())
 }
 
+/// Transforms a function stub (a function with a `;` after the signature
+/// instead of the body) into a function with an empty body.
+pub fn parse_fn_stub(tokens: TokenStream, span: proc_macro2::Span) -> syn::Result<syn::Item> {
+    let mut new_tokens = TokenStream::new();
+    for mut token in tokens.into_iter() {
+        if let TokenTree::Punct(punct) = &mut token {
+            if punct.as_char() == ';' {
+                new_tokens.extend(Group::new(proc_macro2::Delimiter::Brace, TokenStream::new()).to_token_stream());
+                continue;
+            }
+        }
+        new_tokens.extend(token.to_token_stream());
+    }
+    syn::parse2(new_tokens).map_err(|_| syn::Error::new(
+        span,
+        "invalid function signature",
+    ))
+}
+
 /// Rewrite a specification function to a call to the specified function.
 /// The result of this rewriting is then parsed in `ExternSpecResolver`.
+///
+/// The type and const parameters of the specification function are passed
+/// explicitly to the specified function, so that the compiler checks that
+/// they match the generics of the specified function.
 fn rewrite_fn(item_fn: &mut syn::ItemFn, path: &mut syn::Path) {
     let ident = &item_fn.sig.ident;
-    let args = &item_fn.sig.inputs;
+    let args = rewrite_fn_inputs(&item_fn.sig);
+    let generic_args = rewrite_fn_generics(&item_fn.sig.generics);
     let item_fn_span = item_fn.span();
     item_fn.block = parse_quote_spanned! {item_fn_span=>
         {
-            #path :: #ident (#args);
+            #path :: #ident #generic_args (#args);
             unimplemented!()
         }
     };
@@ -87,10 +91,17 @@ fn rewrite_fn(item_fn: &mut syn::ItemFn, path: &mut syn::Path) {
 
 /// Rewrite all methods in an impl block to calls to the specified methods.
 /// The result of this rewriting is then parsed in `ExternSpecResolver`.
+///
+/// If the impl block implements a trait, the methods are rewritten to calls
+/// of the form `<Type as Trait>::method` and the rewritten impl block becomes
+/// an inherent impl of `new_ty`. The methods are marked with the `marker`
+/// attribute, e.g. `prusti::extern_spec`.
 pub fn rewrite_impl(
     impl_item: &mut syn::ItemImpl,
     new_ty: Box<syn::Type>,
+    marker: &syn::Path,
 ) -> syn::Result<TokenStream> {
+    let trait_path = impl_item.trait_.take().map(|(_, path, _)| path);
     let item_ty = &mut impl_item.self_ty;
     if let syn::Type::Path(type_path) = item_ty.as_mut() {
         for seg in type_path.path.segments.iter_mut() {
@@ -111,11 +122,17 @@ pub fn rewrite_impl(
                 let args = rewrite_method_inputs(item_ty, method);
                 let ident = &method.sig.ident;
 
-                method.attrs.push(parse_quote_spanned!(item_span=> #[prusti::extern_spec]));
+                method.attrs.push(parse_quote_spanned!(item_span=> #[#marker]));
                 method.attrs.push(parse_quote_spanned!(item_span=> #[trusted]));
 
-                let mut method_path: syn::ExprPath = parse_quote_spanned! {ident.span()=>
-                    #item_ty :: #ident
+                let mut method_path: syn::ExprPath = if let Some(trait_path) = &trait_path {
+                    parse_quote_spanned! {ident.span()=>
+                        < #item_ty as #trait_path > :: #ident
+                    }
+                } else {
+                    parse_quote_spanned! {ident.span()=>
+                        #item_ty :: #ident
+                    }
                 };
 
                 // Fix the span
@@ -169,6 +186,42 @@ fn rewrite_self(tokens: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
     new_tokens
 }
 
+/// Convert the inputs of a function signature to the arguments of a call.
+pub fn rewrite_fn_inputs(sig: &syn::Signature) ->
+    syn::punctuated::Punctuated<syn::Expr, syn::token::Comma> {
+    let mut args: syn::punctuated::Punctuated<syn::Expr, syn::token::Comma> =
+        syn::punctuated::Punctuated::new();
+
+    for input in sig.inputs.iter() {
+        if let syn::FnArg::Typed(typed) = input {
+            if let syn::Pat::Ident(ident) = &*typed.pat {
+                let arg = &ident.ident;
+                let expr: syn::Expr = syn::parse_quote!(#arg);
+                args.push(expr);
+            }
+        }
+    }
+    args
+}
+
+/// Convert the type and const parameters of a function to a turbofish. Lifetime
+/// parameters are skipped because they cannot always be given explicitly.
+fn rewrite_fn_generics(generics: &syn::Generics) -> TokenStream {
+    let params: Vec<&syn::Ident> = generics.params
+        .iter()
+        .filter_map(|param| match param {
+            syn::GenericParam::Type(type_param) => Some(&type_param.ident),
+            syn::GenericParam::Const(const_param) => Some(&const_param.ident),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    if params.is_empty() {
+        TokenStream::new()
+    } else {
+        quote! { :: < #(#params),* > }
+    }
+}
+
 fn rewrite_method_inputs(item_ty: &Box<syn::Type>, method: &mut ImplItemMethod) ->
     syn::punctuated::Punctuated<syn::Expr, syn::token::Comma> {
     let mut args: syn::punctuated::Punctuated<syn::Expr, syn::token::Comma> =
@@ -228,10 +281,20 @@ pub fn generate_new_struct(item: &syn::ItemImpl) -> syn::Result<syn::ItemStruct>
 
     let mut fields_str: String = String::new();
 
-    // Add `PhantomData` markers for each type parameter to silence errors
-    // about unused type parameters.
+    // Add `PhantomData` markers for each type and lifetime parameter to
+    // silence errors about unused parameters.
     for param in generics.params.iter() {
-        let field = format!("std::marker::PhantomData<{}>,", param.to_token_stream().to_string());
+        let field = match param {
+            syn::GenericParam::Lifetime(lifetime_def) => format!(
+                "std::marker::PhantomData<&{} ()>,",
+                lifetime_def.lifetime.to_token_stream().to_string()
+            ),
+            syn::GenericParam::Type(type_param) => format!(
+                "std::marker::PhantomData<{}>,",
+                type_param.ident.to_token_stream().to_string()
+            ),
+            syn::GenericParam::Const(_) => continue,
+        };
         fields_str.push_str(&field);
     }
 
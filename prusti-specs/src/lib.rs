@@ -3,7 +3,9 @@
 #[macro_use]
 mod parse_quote_spanned;
 mod span_overrider;
+mod async_fn_rewriter;
 mod extern_spec_rewriter;
+mod refine_spec_rewriter;
 mod rewriter;
 mod parse_closure_macro;
 mod spec_attribute_kind;
@@ -29,18 +31,20 @@ macro_rules! handle_result {
 }
 
 fn extract_prusti_attributes(
-    item: &mut untyped::AnyFnItem
+    attrs: &mut Vec<syn::Attribute>
 ) -> Vec<(SpecAttributeKind, TokenStream)> {
     let mut prusti_attributes = Vec::new();
     let mut regular_attributes = Vec::new();
-    for attr in item.attrs_mut().drain(0..) {
+    for attr in attrs.drain(0..) {
         if attr.path.segments.len() == 1 {
             if let Ok(attr_kind) = attr.path.segments[0].ident.to_string().try_into() {
                 let tokens = match attr_kind {
                     SpecAttributeKind::Requires
                     | SpecAttributeKind::Ensures
                     | SpecAttributeKind::AfterExpiry
-                    | SpecAttributeKind::AfterExpiryIf => {
+                    | SpecAttributeKind::AfterExpiryIf
+                    | SpecAttributeKind::Decreases
+                    | SpecAttributeKind::SmtRlimit => {
                         // We need to drop the surrounding parenthesis to make the
                         // tokens identical to the ones passed by the native procedural
                         // macro call.
@@ -51,7 +55,9 @@ fn extract_prusti_attributes(
                     }
                     // Nothing to do for attributes without arguments.
                     SpecAttributeKind::Pure
+                    | SpecAttributeKind::PureMemoized
                     | SpecAttributeKind::Trusted
+                    | SpecAttributeKind::Terminates
                     | SpecAttributeKind::Predicate => {
                         assert!(attr.tokens.is_empty(), "Unexpected shape of an attribute.");
                         attr.tokens
@@ -65,7 +71,7 @@ fn extract_prusti_attributes(
             regular_attributes.push(attr);
         }
     }
-    *item.attrs_mut() = regular_attributes;
+    *attrs = regular_attributes;
     prusti_attributes
 }
 
@@ -78,6 +84,10 @@ pub fn rewrite_prusti_attributes(
     outer_attr_tokens: TokenStream,
     item_tokens: TokenStream,
 ) -> TokenStream {
+    if let Ok(cl) = syn::parse2::<syn::ExprClosure>(item_tokens.clone()) {
+        return rewrite_closure_attributes(outer_attr_kind, outer_attr_tokens, cl);
+    }
+
     let mut item: untyped::AnyFnItem = handle_result!(syn::parse2(item_tokens));
 
     // Start with the outer attribute
@@ -86,7 +96,7 @@ pub fn rewrite_prusti_attributes(
     ];
 
     // Collect the remaining Prusti attributes, removing them from `item`.
-    prusti_attributes.extend(extract_prusti_attributes(&mut item));
+    prusti_attributes.extend(extract_prusti_attributes(item.attrs_mut()));
 
     // make sure to also update the check in the predicate! handling method
     if prusti_attributes
@@ -103,6 +113,14 @@ pub fn rewrite_prusti_attributes(
         generate_spec_and_assertions(prusti_attributes, &item)
     );
 
+    if item.sig().asyncness.is_some() {
+        return handle_result!(async_fn_rewriter::rewrite_async_fn(
+            &item,
+            generated_spec_items,
+            generated_attributes,
+        ));
+    }
+
     quote_spanned! {item.span()=>
         #(#generated_spec_items)*
         #(#generated_attributes)*
@@ -110,6 +128,36 @@ pub fn rewrite_prusti_attributes(
     }
 }
 
+/// Rewrite a closure annotated with `#[requires(..)]` and `#[ensures(..)]`
+/// attributes in the same way as the `closure!` macro.
+fn rewrite_closure_attributes(
+    outer_attr_kind: SpecAttributeKind,
+    outer_attr_tokens: TokenStream,
+    mut cl: syn::ExprClosure,
+) -> TokenStream {
+    let mut prusti_attributes = vec![
+        (outer_attr_kind, outer_attr_tokens)
+    ];
+    prusti_attributes.extend(extract_prusti_attributes(&mut cl.attrs));
+
+    let mut preconds = vec![];
+    let mut postconds = vec![];
+    for (attr_kind, attr_tokens) in prusti_attributes {
+        match attr_kind {
+            SpecAttributeKind::Requires => preconds.push(attr_tokens),
+            SpecAttributeKind::Ensures => postconds.push(attr_tokens),
+            _ => {
+                return syn::Error::new(
+                    cl.span(),
+                    "only `requires` and `ensures` can be attached to closures",
+                ).to_compile_error();
+            }
+        }
+    }
+
+    rewrite_closure(preconds, postconds, cl)
+}
+
 type GeneratedResult = syn::Result<(Vec<syn::Item>, Vec<syn::Attribute>)>;
 
 /// Generate spec items and attributes for `item` from the Prusti attributes
@@ -127,7 +175,11 @@ fn generate_spec_and_assertions(
             SpecAttributeKind::AfterExpiry => generate_for_after_expiry(attr_tokens, item),
             SpecAttributeKind::AfterExpiryIf => generate_for_after_expiry_if(attr_tokens, item),
             SpecAttributeKind::Pure => generate_for_pure(attr_tokens, item),
+            SpecAttributeKind::PureMemoized => generate_for_pure_memoized(attr_tokens, item),
             SpecAttributeKind::Trusted => generate_for_trusted(attr_tokens, item),
+            SpecAttributeKind::Terminates => generate_for_terminates(attr_tokens, item),
+            SpecAttributeKind::Decreases => generate_for_decreases(attr_tokens, item),
+            SpecAttributeKind::SmtRlimit => generate_for_smt_rlimit(attr_tokens, item),
             // Predicates are handled separately below; the entry in the SpecAttributeKind enum
             // only exists so we successfully parse it and emit an error in
             // `check_incompatible_attrs`; so we'll never reach here.
@@ -270,6 +322,30 @@ fn generate_for_pure(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedR
     ))
 }
 
+/// Generate spec items and attributes to typecheck and later retrieve "pure_memoized"
+/// annotations. The function is pure as far as its callers can tell, but its body is
+/// not checked for purity, e.g. because it fills a cache behind a `RefCell`.
+fn generate_for_pure_memoized(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    if !attr.is_empty() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "the `#[pure_memoized]` attribute does not take parameters"
+        ));
+    }
+
+    Ok((
+        vec![],
+        vec![
+            parse_quote_spanned! {item.span()=>
+                #[prusti::pure]
+            },
+            parse_quote_spanned! {item.span()=>
+                #[prusti::pure_memoized]
+            },
+        ],
+    ))
+}
+
 /// Generate spec items and attributes to typecheck and later retrieve "trusted" annotations.
 fn generate_for_trusted(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
     if !attr.is_empty() {
@@ -287,6 +363,59 @@ fn generate_for_trusted(attr: TokenStream, item: &untyped::AnyFnItem) -> Generat
     ))
 }
 
+/// Generate spec items and attributes to typecheck and later retrieve "terminates" annotations.
+fn generate_for_terminates(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    if !attr.is_empty() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "the `#[terminates]` attribute does not take parameters"
+        ));
+    }
+
+    Ok((
+        vec![],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::terminates]
+        }],
+    ))
+}
+
+/// Generate spec items and attributes to typecheck and later retrieve "decreases" annotations.
+fn generate_for_decreases(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    let mut rewriter = rewriter::AstRewriter::new();
+    let spec_id = rewriter.generate_spec_id();
+    let spec_id_str = spec_id.to_string();
+    let measure = rewriter.parse_measure(spec_id, attr)?;
+    let spec_item = rewriter.generate_spec_item_fn(
+        rewriter::SpecItemType::Decreases,
+        spec_id,
+        measure,
+        &item
+    )?;
+    Ok((
+        vec![spec_item],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::decreases_spec_id_ref = #spec_id_str]
+        }],
+    ))
+}
+
+/// Generate the attribute that sets the resource limit of the SMT solver.
+fn generate_for_smt_rlimit(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    let rlimit: syn::LitInt = syn::parse2(attr.clone()).map_err(|_| syn::Error::new(
+        attr.span(),
+        "the `#[smt_rlimit]` attribute takes an integer literal"
+    ))?;
+    rlimit.base10_parse::<u64>()?;
+
+    Ok((
+        vec![],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::smt_rlimit(#rlimit)]
+        }],
+    ))
+}
+
 pub fn body_invariant(tokens: TokenStream) -> TokenStream {
     let mut rewriter = rewriter::AstRewriter::new();
     let spec_id = rewriter.generate_spec_id();
@@ -301,6 +430,77 @@ pub fn body_invariant(tokens: TokenStream) -> TokenStream {
     }
 }
 
+pub fn body_variant(tokens: TokenStream) -> TokenStream {
+    let mut rewriter = rewriter::AstRewriter::new();
+    let spec_id = rewriter.generate_spec_id();
+    let variant = handle_result!(rewriter.parse_measure(spec_id, tokens));
+    let check = rewriter.generate_spec_loop_variant(spec_id, variant);
+    let callsite_span = Span::call_site();
+    quote_spanned! {callsite_span=>
+        #[allow(unused_must_use, unused_variables)]
+        if false {
+            #check
+        }
+    }
+}
+
+pub fn prusti_assert(tokens: TokenStream) -> TokenStream {
+    generate_body_assertion(tokens, false)
+}
+
+pub fn prusti_assume(tokens: TokenStream) -> TokenStream {
+    generate_body_assertion(tokens, true)
+}
+
+fn generate_body_assertion(tokens: TokenStream, is_assumption: bool) -> TokenStream {
+    let mut rewriter = rewriter::AstRewriter::new();
+    let spec_id = rewriter.generate_spec_id();
+    let assertion = handle_result!(rewriter.parse_assertion(spec_id, tokens));
+    let check = rewriter.generate_spec_body_assertion(spec_id, assertion, is_assumption);
+    let callsite_span = Span::call_site();
+    quote_spanned! {callsite_span=>
+        #[allow(unused_must_use, unused_variables)]
+        if false {
+            #check
+        }
+    }
+}
+
+/// Mark the statements of a `ghost!` block with `prusti::ghost`. Variable
+/// declarations are kept at the level of the macro invocation, so that ghost
+/// variables remain visible to later ghost code and specifications; the
+/// other statements are grouped in blocks.
+pub fn ghost(tokens: TokenStream) -> TokenStream {
+    let stmts = handle_result!(syn::parse::Parser::parse2(syn::Block::parse_within, tokens));
+    let callsite_span = Span::call_site();
+    let mut ghost_stmts = TokenStream::new();
+    let mut group = vec![];
+    for stmt in stmts {
+        if let syn::Stmt::Local(_) = stmt {
+            if !group.is_empty() {
+                ghost_stmts.extend(quote_spanned! {callsite_span=>
+                    #[prusti::ghost]
+                    { #(#group)* }
+                });
+                group.clear();
+            }
+            ghost_stmts.extend(quote_spanned! {callsite_span=>
+                #[prusti::ghost]
+                #stmt
+            });
+        } else {
+            group.push(stmt);
+        }
+    }
+    if !group.is_empty() {
+        ghost_stmts.extend(quote_spanned! {callsite_span=>
+            #[prusti::ghost]
+            { #(#group)* }
+        });
+    }
+    ghost_stmts
+}
+
 /// Unlike the functions above, which are only called from
 /// prusti-contracts-internal, this function also needs to be called
 /// from prusti-contracts-impl, because we still need to parse the
@@ -310,81 +510,95 @@ pub fn body_invariant(tokens: TokenStream) -> TokenStream {
 /// drop it (for -impl).
 pub fn closure(tokens: TokenStream, drop_spec: bool) -> TokenStream {
     let cl_spec: ClosureWithSpec = handle_result!(syn::parse(tokens.into()));
-    let callsite_span = Span::call_site();
 
     if drop_spec {
         cl_spec.cl.into_token_stream()
     } else {
-        let mut rewriter = rewriter::AstRewriter::new();
+        rewrite_closure(
+            cl_spec.pres.iter().map(|r| r.to_token_stream()).collect(),
+            cl_spec.posts.iter().map(|e| e.to_token_stream()).collect(),
+            cl_spec.cl,
+        )
+    }
+}
 
-        let mut preconds: Vec<(untyped::SpecificationId, untyped::Assertion)> = Vec::new();
-        let mut postconds: Vec<(untyped::SpecificationId, untyped::Assertion)> = Vec::new();
+/// Attach the given preconditions and postconditions to a closure.
+fn rewrite_closure(
+    pres: Vec<TokenStream>,
+    posts: Vec<TokenStream>,
+    cl: syn::ExprClosure,
+) -> TokenStream {
+    let callsite_span = Span::call_site();
 
-        let mut cl_annotations = TokenStream::new();
+    let mut rewriter = rewriter::AstRewriter::new();
 
-        for r in cl_spec.pres {
-            let spec_id = rewriter.generate_spec_id();
-            let precond = handle_result!(rewriter.parse_assertion(spec_id, r.to_token_stream()));
-            preconds.push((spec_id, precond));
-            let spec_id_str = spec_id.to_string();
-            cl_annotations.extend(quote_spanned! { callsite_span =>
-                #[prusti::pre_spec_id_ref = #spec_id_str]
-            });
-        }
+    let mut preconds: Vec<(untyped::SpecificationId, untyped::Assertion)> = Vec::new();
+    let mut postconds: Vec<(untyped::SpecificationId, untyped::Assertion)> = Vec::new();
 
-        for e in cl_spec.posts {
-            let spec_id = rewriter.generate_spec_id();
-            let postcond = handle_result!(rewriter.parse_assertion(spec_id, e.to_token_stream()));
-            postconds.push((spec_id, postcond));
-            let spec_id_str = spec_id.to_string();
-            cl_annotations.extend(quote_spanned! { callsite_span =>
-                #[prusti::post_spec_id_ref = #spec_id_str]
-            });
-        }
+    let mut cl_annotations = TokenStream::new();
 
-        let syn::ExprClosure {
-            attrs, asyncness, movability, capture, or1_token,
-            inputs, or2_token, output, body
-        } = cl_spec.cl;
+    for r in pres {
+        let spec_id = rewriter.generate_spec_id();
+        let precond = handle_result!(rewriter.parse_assertion(spec_id, r));
+        preconds.push((spec_id, precond));
+        let spec_id_str = spec_id.to_string();
+        cl_annotations.extend(quote_spanned! { callsite_span =>
+            #[prusti::pre_spec_id_ref = #spec_id_str]
+        });
+    }
 
-        let output_type: syn::Type = match output {
-            syn::ReturnType::Default => {
-                return syn::Error::new(output.span(), "closure must specify return type")
-                    .to_compile_error();
-            }
-            syn::ReturnType::Type(_, ref ty) => (**ty).clone()
-        };
+    for e in posts {
+        let spec_id = rewriter.generate_spec_id();
+        let postcond = handle_result!(rewriter.parse_assertion(spec_id, e));
+        postconds.push((spec_id, postcond));
+        let spec_id_str = spec_id.to_string();
+        cl_annotations.extend(quote_spanned! { callsite_span =>
+            #[prusti::post_spec_id_ref = #spec_id_str]
+        });
+    }
 
-        let (spec_toks_pre, spec_toks_post) = rewriter.generate_cl_spec(
-            inputs.clone(), output_type, preconds, postconds);
+    let syn::ExprClosure {
+        attrs, asyncness, movability, capture, or1_token,
+        inputs, or2_token, output, body
+    } = cl;
 
-        let mut attrs_ts = TokenStream::new();
-        for a in attrs {
-            attrs_ts.extend(a.into_token_stream());
+    let output_type: syn::Type = match output {
+        syn::ReturnType::Default => {
+            return syn::Error::new(output.span(), "closure must specify return type")
+                .to_compile_error();
         }
+        syn::ReturnType::Type(_, ref ty) => (**ty).clone()
+    };
 
-        quote_spanned! {callsite_span=>
-            {
-                #[allow(unused_variables)]
-                #[prusti::closure]
-                #cl_annotations #attrs_ts
-                let _prusti_closure =
-                    #asyncness #movability #capture
-                    #or1_token #inputs #or2_token #output
-                    {
-                        #[allow(unused_must_use)]
-                        if false {
-                            #spec_toks_pre
-                        }
-                        let result = #body ;
-                        #[allow(unused_must_use)]
-                        if false {
-                            #spec_toks_post
-                        }
-                        result
-                    };
-                _prusti_closure
-            }
+    let (spec_toks_pre, spec_toks_post) = rewriter.generate_cl_spec(
+        inputs.clone(), output_type, preconds, postconds);
+
+    let mut attrs_ts = TokenStream::new();
+    for a in attrs {
+        attrs_ts.extend(a.into_token_stream());
+    }
+
+    quote_spanned! {callsite_span=>
+        {
+            #[allow(unused_variables)]
+            #[prusti::closure]
+            #cl_annotations #attrs_ts
+            let _prusti_closure =
+                #asyncness #movability #capture
+                #or1_token #inputs #or2_token #output
+                {
+                    #[allow(unused_must_use)]
+                    if false {
+                        #spec_toks_pre
+                    }
+                    let result = #body ;
+                    #[allow(unused_must_use)]
+                    if false {
+                        #spec_toks_post
+                    }
+                    result
+                };
+            _prusti_closure
         }
     }
 }
@@ -397,7 +611,7 @@ pub fn refine_trait_spec(_attr: TokenStream, tokens: TokenStream) -> TokenStream
         match item {
             syn::ImplItem::Method(method) => {
                 let mut method_item = untyped::AnyFnItem::ImplMethod(method);
-                let prusti_attributes: Vec<_> = extract_prusti_attributes(&mut method_item);
+                let prusti_attributes: Vec<_> = extract_prusti_attributes(method_item.attrs_mut());
                 let (spec_items, generated_attributes) = handle_result!(
                     generate_spec_and_assertions(prusti_attributes, &method_item)
                 );
@@ -459,7 +673,11 @@ pub fn extern_spec(_attr: TokenStream, tokens:TokenStream) -> TokenStream {
             };
 
             let rewritten_item = handle_result!(
-                extern_spec_rewriter::rewrite_impl(&mut item_impl, Box::from(struct_ty))
+                extern_spec_rewriter::rewrite_impl(
+                    &mut item_impl,
+                    Box::from(struct_ty),
+                    &parse_quote_spanned!(item_span=> prusti::extern_spec),
+                )
             );
 
             quote_spanned! {item_span=>
@@ -479,6 +697,104 @@ pub fn extern_spec(_attr: TokenStream, tokens:TokenStream) -> TokenStream {
     }
 }
 
+/// Refine the specification of an instantiation of a generic function, given
+/// as a function stub or as an impl block of method stubs, e.g.
+/// `#[refine_spec(where T = u8)]`. The call sites of the instantiation use
+/// the most specific refinement that applies to them.
+pub fn refine_spec(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    let instantiation: refine_spec_rewriter::Instantiation = handle_result!(syn::parse2(attr));
+    let item: syn::Item = handle_result!(syn::parse2(tokens));
+    handle_result!(refine_spec_rewriter::rewrite_item(item, instantiation))
+}
+
+/// Mark an item as not to be verified. If the item is a module, an impl block
+/// or a trait, none of the procedures that it contains are verified. The
+/// specifications of the item are still used at its call sites.
+pub fn skip_verification(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return syn::Error::new(
+            attr.span(),
+            "the `#[skip_verification]` attribute does not take parameters"
+        ).to_compile_error();
+    }
+    let item: syn::Item = handle_result!(syn::parse2(tokens));
+    let item_span = item.span();
+    quote_spanned! {item_span=>
+        #[prusti::skip_verification]
+        #item
+    }
+}
+
+/// Generate a spec item to typecheck and later retrieve the invariant of a
+/// `static` item, of a (possibly associated) constant, or of a type.
+///
+/// The invariant of a static or a constant refers to the item by its path,
+/// e.g. `#[invariant(CONFIG.len <= CONFIG.cap)]`. The invariant of a struct or
+/// an enum refers to the value by `self`, e.g. `#[invariant(self.len <= self.cap)]`.
+/// The invariant of an associated constant refers to it through `Self`.
+///
+/// The invariants of statics and constants are collected and type-checked,
+/// but not yet checked by the verifier nor assumed where the item is read.
+pub fn invariant(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    let item: syn::Item = handle_result!(syn::parse2(tokens));
+    let item_span = item.span();
+    let (ident, type_generics) = match &item {
+        syn::Item::Static(item_static) => (item_static.ident.clone(), None),
+        syn::Item::Const(item_const) => (item_const.ident.clone(), None),
+        syn::Item::Struct(item_struct) => (item_struct.ident.clone(), Some(&item_struct.generics)),
+        syn::Item::Enum(item_enum) => (item_enum.ident.clone(), Some(&item_enum.generics)),
+        _ => {
+            return syn::Error::new(
+                item_span,
+                "`#[invariant(...)]` can only be attached to statics, constants, structs and enums",
+            ).to_compile_error();
+        }
+    };
+
+    let mut rewriter = rewriter::AstRewriter::new();
+    let spec_id = rewriter.generate_spec_id();
+    let assertion = handle_result!(rewriter.parse_assertion(spec_id, attr));
+
+    // The spec item of a static takes no arguments, so we type-check the
+    // assertion in the context of an empty function named after the item.
+    // The spec item of a type is a method that takes `self` by value.
+    let spec_fn_base: untyped::AnyFnItem = if type_generics.is_some() {
+        parse_quote_spanned! {item_span=>
+            fn #ident(self) {}
+        }
+    } else {
+        parse_quote_spanned! {item_span=>
+            fn #ident() {}
+        }
+    };
+    let spec_fn = handle_result!(rewriter.generate_spec_item_fn(
+        rewriter::SpecItemType::Invariant,
+        spec_id,
+        assertion,
+        &spec_fn_base,
+    ));
+
+    let spec_id_str = spec_id.to_string();
+    if let Some(generics) = type_generics {
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        quote_spanned! {item_span=>
+            #[prusti::inv_spec_id_ref = #spec_id_str]
+            #item
+            impl #impl_generics #ident #ty_generics #where_clause {
+                #[allow(non_snake_case)]
+                #spec_fn
+            }
+        }
+    } else {
+        quote_spanned! {item_span=>
+            #[allow(non_snake_case)]
+            #spec_fn
+            #[prusti::inv_spec_id_ref = #spec_id_str]
+            #item
+        }
+    }
+}
+
 #[derive(Debug)]
 struct PredicateFn {
     fn_sig: syn::Signature,
@@ -0,0 +1,223 @@
+use crate::extern_spec_rewriter::{parse_fn_stub, rewrite_fn_inputs};
+use crate::specifications::common::NameGenerator;
+use super::parse_quote_spanned;
+use proc_macro2::{TokenStream, TokenTree, Group};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+
+/// The instantiation of the type parameters given in an attribute such as
+/// `#[refine_spec(where T = u8, U = bool)]`.
+pub struct Instantiation {
+    bindings: Vec<syn::Binding>,
+}
+
+impl Parse for Instantiation {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<syn::Token![where]>()?;
+        let bindings: syn::punctuated::Punctuated<syn::Binding, syn::Token![,]> =
+            syn::punctuated::Punctuated::parse_terminated(input)?;
+        if bindings.is_empty() {
+            return Err(input.error("expected at least one instantiation, e.g. `where T = u8`"));
+        }
+        Ok(Self { bindings: bindings.into_iter().collect() })
+    }
+}
+
+impl Instantiation {
+    fn get(&self, ident: &syn::Ident) -> Option<&syn::Type> {
+        self.bindings
+            .iter()
+            .find(|binding| &binding.ident == ident)
+            .map(|binding| &binding.ty)
+    }
+
+    /// Check that every instantiated parameter is a type parameter of one of
+    /// the given generics.
+    fn check_params(&self, generics: &[&syn::Generics]) -> syn::Result<()> {
+        for binding in &self.bindings {
+            let is_type_param = generics.iter()
+                .flat_map(|generics| generics.type_params())
+                .any(|type_param| type_param.ident == binding.ident);
+            if !is_type_param {
+                return Err(syn::Error::new(
+                    binding.ident.span(),
+                    format!("`{}` is not a type parameter of the refined item", binding.ident),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert the type and const parameters of a function to a turbofish in
+    /// which the instantiated parameters are replaced by their types.
+    fn generic_args(&self, generics: &syn::Generics) -> TokenStream {
+        let args: Vec<TokenStream> = generics.params
+            .iter()
+            .filter_map(|param| match param {
+                syn::GenericParam::Type(type_param) => Some(match self.get(&type_param.ident) {
+                    Some(ty) => ty.to_token_stream(),
+                    None => type_param.ident.to_token_stream(),
+                }),
+                syn::GenericParam::Const(const_param) => Some(const_param.ident.to_token_stream()),
+                syn::GenericParam::Lifetime(_) => None,
+            })
+            .collect();
+        if args.is_empty() {
+            TokenStream::new()
+        } else {
+            quote! { :: < #(#args),* > }
+        }
+    }
+
+    /// Remove the instantiated parameters from the generics. Their bounds in
+    /// the where clause are kept and later checked on the given types.
+    fn remove_params(&self, generics: &mut syn::Generics) {
+        generics.params = std::mem::take(&mut generics.params)
+            .into_iter()
+            .filter(|param| match param {
+                syn::GenericParam::Type(type_param) => self.get(&type_param.ident).is_none(),
+                _ => true,
+            })
+            .collect();
+        if generics.params.is_empty() {
+            generics.lt_token = None;
+            generics.gt_token = None;
+        }
+    }
+
+    /// Replace the instantiated parameters in the tokens of an attribute,
+    /// e.g. in `#[ensures(result == std::mem::size_of::<T>())]`.
+    fn rewrite_tokens(&self, tokens: TokenStream) -> TokenStream {
+        let mut new_tokens = TokenStream::new();
+        for token in tokens.into_iter() {
+            match token {
+                TokenTree::Group(group) => {
+                    let mut new_group = Group::new(group.delimiter(), self.rewrite_tokens(group.stream()));
+                    new_group.set_span(group.span());
+                    new_tokens.extend(new_group.to_token_stream());
+                }
+                TokenTree::Ident(ident) => match self.get(&ident) {
+                    Some(ty) => new_tokens.extend(ty.to_token_stream()),
+                    None => new_tokens.extend(ident.into_token_stream()),
+                },
+                _ => new_tokens.extend(token.into_token_stream()),
+            }
+        }
+        new_tokens
+    }
+
+    fn rewrite_attrs(&self, attrs: &mut Vec<syn::Attribute>) {
+        for attr in attrs.iter_mut() {
+            attr.tokens = self.rewrite_tokens(attr.tokens.clone());
+        }
+    }
+}
+
+impl VisitMut for Instantiation {
+    fn visit_type_mut(&mut self, ty: &mut syn::Type) {
+        if let syn::Type::Path(type_path) = ty {
+            if type_path.qself.is_none() {
+                if let Some(replacement) = type_path.path.get_ident().and_then(|ident| self.get(ident)) {
+                    *ty = replacement.clone();
+                    return;
+                }
+            }
+        }
+        syn::visit_mut::visit_type_mut(self, ty);
+    }
+}
+
+/// Rewrite a refinement, given as a function stub or as an impl block of
+/// method stubs, to functions that call the refined function with the
+/// instantiated type parameters. The result of this rewriting is then parsed
+/// in `RefinedSpecResolver`.
+pub fn rewrite_item(item: syn::Item, mut instantiation: Instantiation) -> syn::Result<TokenStream> {
+    let item_span = item.span();
+    match item {
+        syn::Item::Verbatim(tokens) => match parse_fn_stub(tokens, item_span)? {
+            syn::Item::Fn(mut item_fn) => {
+                rewrite_fn(&mut item_fn, &mut instantiation)?;
+                Ok(quote!(#item_fn))
+            }
+            _ => Err(syn::Error::new(item_span, "expected a function stub or an impl block")),
+        },
+        syn::Item::Fn(mut item_fn) => {
+            rewrite_fn(&mut item_fn, &mut instantiation)?;
+            Ok(quote!(#item_fn))
+        }
+        syn::Item::Impl(mut item_impl) => {
+            rewrite_impl(&mut item_impl, &mut instantiation)
+        }
+        _ => Err(syn::Error::new(item_span, "expected a function stub or an impl block")),
+    }
+}
+
+/// Rewrite a function stub to a function with a generated name, whose
+/// signature is the instantiated signature of the refined function.
+fn rewrite_fn(item_fn: &mut syn::ItemFn, instantiation: &mut Instantiation) -> syn::Result<()> {
+    instantiation.check_params(&[&item_fn.sig.generics])?;
+    let ident = item_fn.sig.ident.clone();
+    let generic_args = instantiation.generic_args(&item_fn.sig.generics);
+    instantiation.remove_params(&mut item_fn.sig.generics);
+    instantiation.visit_signature_mut(&mut item_fn.sig);
+    instantiation.rewrite_attrs(&mut item_fn.attrs);
+
+    let args = rewrite_fn_inputs(&item_fn.sig);
+    let item_fn_span = item_fn.span();
+    let name_generator = NameGenerator::new();
+    item_fn.sig.ident = syn::Ident::new(
+        &format!("prusti_refined_{}", name_generator.generate_mod_name(&ident)),
+        ident.span(),
+    );
+    item_fn.block = parse_quote_spanned! {item_fn_span=>
+        {
+            #ident #generic_args (#args);
+            unimplemented!()
+        }
+    };
+
+    item_fn.attrs.push(parse_quote_spanned!(item_fn_span=> #[prusti::refine_spec]));
+    item_fn.attrs.push(parse_quote_spanned!(item_fn_span=> #[trusted]));
+    Ok(())
+}
+
+/// Rewrite an impl block of method stubs like an external specification
+/// (see `extern_spec_rewriter::rewrite_impl`), after instantiating the type
+/// parameters of the impl block and of its methods.
+fn rewrite_impl(item_impl: &mut syn::ItemImpl, instantiation: &mut Instantiation) -> syn::Result<TokenStream> {
+    let item_span = item_impl.span();
+    let mut generics = vec![&item_impl.generics];
+    for item in item_impl.items.iter() {
+        if let syn::ImplItem::Method(method) = item {
+            generics.push(&method.sig.generics);
+        }
+    }
+    instantiation.check_params(&generics)?;
+
+    instantiation.remove_params(&mut item_impl.generics);
+    for item in item_impl.items.iter_mut() {
+        if let syn::ImplItem::Method(method) = item {
+            instantiation.remove_params(&mut method.sig.generics);
+            instantiation.rewrite_attrs(&mut method.attrs);
+        }
+    }
+    instantiation.visit_item_impl_mut(item_impl);
+
+    let new_struct = crate::extern_spec_rewriter::generate_new_struct(item_impl)?;
+    let struct_ident = &new_struct.ident;
+    let struct_generics = &new_struct.generics;
+    let struct_ty: syn::Type = parse_quote_spanned! {item_span=>
+        #struct_ident #struct_generics
+    };
+    let rewritten_item = crate::extern_spec_rewriter::rewrite_impl(
+        item_impl,
+        Box::from(struct_ty),
+        &parse_quote_spanned!(item_span=> prusti::refine_spec),
+    )?;
+    Ok(quote! {
+        #new_struct
+        #rewritten_item
+    })
+}
@@ -15,6 +15,8 @@ pub enum SpecItemType {
     Precondition,
     Postcondition,
     Predicate,
+    Invariant,
+    Decreases,
 }
 
 impl std::fmt::Display for SpecItemType {
@@ -23,6 +25,8 @@ impl std::fmt::Display for SpecItemType {
             SpecItemType::Precondition => write!(f, "pre"),
             SpecItemType::Postcondition => write!(f, "post"),
             SpecItemType::Predicate => write!(f, "pred"),
+            SpecItemType::Invariant => write!(f, "inv"),
+            SpecItemType::Decreases => write!(f, "decreases"),
         }
     }
 }
@@ -48,6 +52,15 @@ impl AstRewriter {
         untyped::Assertion::parse(tokens, spec_id, &mut self.expr_id_generator)
     }
 
+    /// Parse a termination measure.
+    pub fn parse_measure(
+        &mut self,
+        spec_id: untyped::SpecificationId,
+        tokens: TokenStream,
+    ) -> syn::Result<untyped::Assertion> {
+        untyped::Assertion::parse_measure(tokens, spec_id, &mut self.expr_id_generator)
+    }
+
     /// Parse a pledge.
     pub fn parse_pledge(
         &mut self,
@@ -95,9 +108,10 @@ impl AstRewriter {
         fn_arg
     }
 
-    /// Generate a dummy function for checking the given precondition, postcondition or predicate.
+    /// Generate a dummy function for checking the given precondition, postcondition, predicate,
+    /// invariant or termination measure.
     ///
-    /// `spec_type` should be either `"pre"`, `"post"` or `"pred"`.
+    /// `spec_type` should be either `"pre"`, `"post"`, `"pred"`, `"inv"` or `"decreases"`.
     pub fn generate_spec_item_fn(
         &mut self,
         spec_type: SpecItemType,
@@ -117,7 +131,11 @@ impl AstRewriter {
             item_span,
         );
         let mut statements = TokenStream::new();
-        assertion.encode_type_check(&mut statements);
+        if spec_type == SpecItemType::Decreases {
+            assertion.encode_measure_type_check(&mut statements);
+        } else {
+            assertion.encode_type_check(&mut statements);
+        }
         let spec_id_str = spec_id.to_string();
         let assertion_json = crate::specifications::json::to_json_string(&assertion);
 
@@ -164,6 +182,63 @@ impl AstRewriter {
         }
     }
 
+    /// Generate statements for checking the given loop variant.
+    pub fn generate_spec_loop_variant(
+        &mut self,
+        spec_id: untyped::SpecificationId,
+        measure: untyped::Assertion,
+    ) -> TokenStream {
+        let mut statements = TokenStream::new();
+        measure.encode_measure_type_check(&mut statements);
+        let spec_id_str = spec_id.to_string();
+        let measure_json = crate::specifications::json::to_json_string(&measure);
+        let callsite_span = Span::call_site();
+        quote_spanned! {callsite_span=>
+            #[allow(unused_must_use, unused_variables)]
+            {
+                #[prusti::spec_only]
+                #[prusti::loop_body_variant_spec]
+                #[prusti::spec_id = #spec_id_str]
+                #[prusti::assertion = #measure_json]
+                || {
+                    #statements
+                };
+            }
+        }
+    }
+
+    /// Generate statements for checking the assertion of a `prusti_assert!`
+    /// or of a `prusti_assume!`.
+    pub fn generate_spec_body_assertion(
+        &mut self,
+        spec_id: untyped::SpecificationId,
+        assertion: untyped::Assertion,
+        is_assumption: bool,
+    ) -> TokenStream {
+        let mut statements = TokenStream::new();
+        assertion.encode_type_check(&mut statements);
+        let spec_id_str = spec_id.to_string();
+        let assertion_json = crate::specifications::json::to_json_string(&assertion);
+        let callsite_span = Span::call_site();
+        let kind_attr = if is_assumption {
+            quote_spanned!(callsite_span=> #[prusti::body_assumption_spec])
+        } else {
+            quote_spanned!(callsite_span=> #[prusti::body_assertion_spec])
+        };
+        quote_spanned! {callsite_span=>
+            #[allow(unused_must_use, unused_variables)]
+            {
+                #[prusti::spec_only]
+                #kind_attr
+                #[prusti::spec_id = #spec_id_str]
+                #[prusti::assertion = #assertion_json]
+                || {
+                    #statements
+                };
+            }
+        }
+    }
+
     /// Generate statements for checking a closure specification.
     /// TODO: arguments, result (types are typically not known yet after parsing...)
     pub fn generate_cl_spec(
@@ -8,8 +8,12 @@ pub enum SpecAttributeKind {
     AfterExpiry,
     AfterExpiryIf,
     Pure,
+    PureMemoized,
     Trusted,
+    Terminates,
     Predicate,
+    Decreases,
+    SmtRlimit,
 }
 
 impl TryFrom<String> for SpecAttributeKind {
@@ -22,8 +26,12 @@ impl TryFrom<String> for SpecAttributeKind {
             "after_expiry" => Ok(SpecAttributeKind::AfterExpiry),
             "after_expiry_if" => Ok(SpecAttributeKind::AfterExpiryIf),
             "pure" => Ok(SpecAttributeKind::Pure),
+            "pure_memoized" => Ok(SpecAttributeKind::PureMemoized),
             "trusted" => Ok(SpecAttributeKind::Trusted),
+            "terminates" => Ok(SpecAttributeKind::Terminates),
             "predicate" => Ok(SpecAttributeKind::Predicate),
+            "decreases" => Ok(SpecAttributeKind::Decreases),
+            "smt_rlimit" => Ok(SpecAttributeKind::SmtRlimit),
             _ => Err(name),
         }
     }
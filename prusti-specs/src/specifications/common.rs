@@ -19,6 +19,10 @@ pub enum SpecType {
     Invariant,
     /// Predicate
     Predicate,
+    /// Termination measure of a pure function
+    Decreases,
+    /// Assertion or assumption in the body of a procedure
+    BodyAssertion,
 }
 
 #[derive(Debug)]
@@ -38,6 +42,7 @@ impl<'a> TryFrom<&'a str> for SpecType {
             "ensures" => Ok(SpecType::Postcondition),
             "invariant" => Ok(SpecType::Invariant),
             "predicate" => Ok(SpecType::Predicate),
+            "decreases" => Ok(SpecType::Decreases),
             _ => Err(TryFromStringError::UnknownSpecificationType),
         }
     }
@@ -60,6 +65,7 @@ pub enum SpecIdRef {
         rhs: SpecificationId,
     },
     Predicate(SpecificationId),
+    Decreases(SpecificationId),
 }
 
 impl Display for SpecificationId {
@@ -301,9 +307,32 @@ pub struct Specification<EID, ET, AT> {
 pub struct LoopSpecification<EID, ET, AT> {
     /// Loop invariant.
     pub invariant: Vec<Assertion<EID, ET, AT>>,
+    /// Termination measures of the loop, which are compared
+    /// lexicographically in the order in which they are written.
+    pub variant: Vec<Assertion<EID, ET, AT>>,
 }
 
 impl<EID, ET, AT> LoopSpecification<EID, ET, AT> {
+    pub fn new(invariant: Vec<Assertion<EID, ET, AT>>) -> Self {
+        Self { invariant, variant: Vec::new() }
+    }
+    pub fn empty() -> Self {
+        Self::new(Vec::new())
+    }
+    pub fn is_empty(&self) -> bool {
+        self.invariant.is_empty() && self.variant.is_empty()
+    }
+}
+
+/// Specification of a static or a constant.
+#[derive(Debug, Clone)]
+pub struct StaticSpecification<EID, ET, AT> {
+    /// Invariant that holds whenever the item is read. It is only collected
+    /// and type-checked; the encoder does not yet check or assume it.
+    pub invariant: Vec<Assertion<EID, ET, AT>>,
+}
+
+impl<EID, ET, AT> StaticSpecification<EID, ET, AT> {
     pub fn new(invariant: Vec<Assertion<EID, ET, AT>>) -> Self {
         Self { invariant }
     }
@@ -315,6 +344,14 @@ impl<EID, ET, AT> LoopSpecification<EID, ET, AT> {
     }
 }
 
+/// Specification of a `prusti_assert!` or of a `prusti_assume!`.
+#[derive(Debug, Clone)]
+pub struct BodyAssertionSpecification<EID, ET, AT> {
+    pub assertion: Assertion<EID, ET, AT>,
+    /// Whether the assertion is assumed without being checked.
+    pub is_assumption: bool,
+}
+
 /// Specification of a procedure.
 #[derive(Debug, Clone)]
 pub struct ProcedureSpecification<EID, ET, AT> {
@@ -330,8 +367,22 @@ pub struct ProcedureSpecification<EID, ET, AT> {
     /// otherwise.
     pub predicate_body: Option<Assertion<EID, ET, AT>>,
 
+    /// Termination measure given by a `#[decreases(...)]` attribute. It
+    /// must decrease on every recursive call of a pure function.
+    pub decreases: Option<Assertion<EID, ET, AT>>,
+
     pub pure: bool,
     pub trusted: bool,
+    /// Whether the procedure is marked with `#[pure_memoized]`: it is pure,
+    /// but its body is neither checked nor encoded.
+    pub pure_memoized: bool,
+    /// Whether the procedure is marked with `#[terminates]`, and must be
+    /// proven to terminate.
+    pub terminates: bool,
+    /// The resource limit of the SMT solver for the verification of the
+    /// procedure, given by a `#[prusti::smt_rlimit(N)]` attribute. It
+    /// overrides the `smt_rlimit` setting.
+    pub smt_rlimit: Option<u64>,
 }
 
 impl<EID, ET, AT> ProcedureSpecification<EID, ET, AT> {
@@ -346,8 +397,12 @@ impl<EID, ET, AT> ProcedureSpecification<EID, ET, AT> {
             posts,
             pledges,
             predicate_body,
+            decreases: None,
             pure: false,
             trusted: false,
+            pure_memoized: false,
+            terminates: false,
+            smt_rlimit: None,
         }
     }
     pub fn empty() -> Self {
@@ -388,13 +443,22 @@ impl<EID: Clone + Debug, ET: Clone + Debug, AT: Clone + Debug> ProcedureSpecific
         } else {
             other.predicate_body.clone()
         };
+        let decreases = if other.decreases.is_none() {
+            self.decreases.clone()
+        } else {
+            other.decreases.clone()
+        };
         Self {
             pres,
             posts,
             pledges,
             predicate_body,
+            decreases,
             pure: other.pure,
             trusted: other.trusted,
+            pure_memoized: other.pure_memoized,
+            terminates: other.terminates,
+            smt_rlimit: other.smt_rlimit.or(self.smt_rlimit),
         }
     }
 }
@@ -408,6 +472,10 @@ pub enum SpecificationSet<EID, ET, AT> {
     Loop(LoopSpecification<EID, ET, AT>),
     /// Struct invariant.
     Struct(Vec<Specification<EID, ET, AT>>),
+    /// Invariant of a static or a constant.
+    Static(StaticSpecification<EID, ET, AT>),
+    /// Assertion or assumption in the body of a procedure.
+    BodyAssertion(BodyAssertionSpecification<EID, ET, AT>),
 }
 
 impl<EID, ET, AT> SpecificationSet<EID, ET, AT> {
@@ -416,6 +484,8 @@ impl<EID, ET, AT> SpecificationSet<EID, ET, AT> {
             SpecificationSet::Procedure(spec) => spec.is_empty(),
             SpecificationSet::Loop(ref invs) => invs.is_empty(),
             SpecificationSet::Struct(ref invs) => invs.is_empty(),
+            SpecificationSet::Static(ref invs) => invs.is_empty(),
+            SpecificationSet::BodyAssertion(_) => false,
         }
     }
 }
@@ -452,4 +522,20 @@ impl<EID: Clone + Debug, ET: Clone + Debug, AT: Clone + Debug> SpecificationSet<
         }
         unreachable!("expected Struct: {:?}", self);
     }
+
+    #[track_caller]
+    pub fn expect_static(&self) -> &StaticSpecification<EID, ET, AT> {
+        if let SpecificationSet::Static(spec) = self {
+            return spec;
+        }
+        unreachable!("expected Static: {:?}", self);
+    }
+
+    #[track_caller]
+    pub fn expect_body_assertion(&self) -> &BodyAssertionSpecification<EID, ET, AT> {
+        if let SpecificationSet::BodyAssertion(spec) = self {
+            return spec;
+        }
+        unreachable!("expected BodyAssertion: {:?}", self);
+    }
 }
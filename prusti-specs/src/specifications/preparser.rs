@@ -1,11 +1,12 @@
 /// The preparser parses Prusti into an AST
 
-use proc_macro2::{Span, TokenStream, TokenTree, Delimiter};
+use proc_macro2::{Span, TokenStream, TokenTree, Delimiter, Group};
 use std::collections::VecDeque;
 use syn::parse::{ParseStream, Parse};
 use syn::Token;
 use syn::spanned::Spanned;
-use quote::quote;
+use syn::visit_mut::VisitMut;
+use quote::{quote, quote_spanned};
 
 use super::common;
 use crate::specifications::common::{QuantifierVars, SpecEntailmentVars, TriggerSet, Trigger};
@@ -156,11 +157,28 @@ impl Parser {
         Ok((reference, assertion))
     }
 
-    /// Parse a prusti expression
+    /// Parse a prusti expression. `<==>` has the lowest precedence and cannot
+    /// be chained.
     fn parse_prusti(&mut self) -> syn::Result<AssertionWithoutId> {
+        let lhs = self.parse_implication()?;
+        if self.consume_operator("<==>") {
+            let span = self.get_error_span();
+            let rhs = self.parse_implication()?;
+            if self.peek_operator("<==>") {
+                self.consume_operator("<==>");
+                return Err(self.error_chained_iff());
+            }
+            Ok(self.desugar_iff(lhs, rhs, span))
+        } else {
+            Ok(lhs)
+        }
+    }
+    /// Parse an implication. `==>` has a lower precedence than `&&` and is
+    /// right-associative: `a ==> b ==> c` is `a ==> (b ==> c)`.
+    fn parse_implication(&mut self) -> syn::Result<AssertionWithoutId> {
         let lhs = self.parse_conjunction()?;
         if self.consume_operator("==>") {
-            let rhs = self.parse_prusti()?;
+            let rhs = self.parse_implication()?;
             Ok(AssertionWithoutId {
                 kind: Box::new(common::AssertionKind::Implies(lhs, rhs)),
             })
@@ -168,6 +186,34 @@ impl Parser {
             Ok(lhs)
         }
     }
+    /// Desugar `lhs <==> rhs` to the equality of two Rust expressions or, if
+    /// one side is not a Rust expression, to `(lhs ==> rhs) && (rhs ==> lhs)`.
+    fn desugar_iff(&self, lhs: AssertionWithoutId, rhs: AssertionWithoutId, span: Span) -> AssertionWithoutId {
+        if let (common::AssertionKind::Expr(lhs_expr), common::AssertionKind::Expr(rhs_expr)) =
+            (&*lhs.kind, &*rhs.kind)
+        {
+            let lhs_expr = &lhs_expr.expr;
+            let rhs_expr = &rhs_expr.expr;
+            let expr = syn::parse2(quote_spanned! {span=> (#lhs_expr) == (#rhs_expr) }).unwrap();
+            return AssertionWithoutId {
+                kind: Box::new(common::AssertionKind::Expr(ExpressionWithoutId {
+                    spec_id: common::SpecificationId::dummy(),
+                    id: (),
+                    expr,
+                })),
+            };
+        }
+        AssertionWithoutId {
+            kind: Box::new(common::AssertionKind::And(vec![
+                AssertionWithoutId {
+                    kind: Box::new(common::AssertionKind::Implies(lhs.clone(), rhs.clone())),
+                },
+                AssertionWithoutId {
+                    kind: Box::new(common::AssertionKind::Implies(rhs, lhs)),
+                },
+            ])),
+        }
+    }
     fn parse_conjunction(&mut self) -> syn::Result<AssertionWithoutId> {
         let mut conjuncts = vec![self.parse_entailment()?];
         while self.consume_operator("&&") {
@@ -312,21 +358,24 @@ impl Parser {
 
                 let mut vec_of_triggers = vec![];
             for item in arr.elems {
-                if let syn::Expr::Tuple(tuple) = item {
-                    vec_of_triggers.push(
-                        Trigger(tuple.elems
-                            .into_iter()
-                            .map(|x| ExpressionWithoutId {
-                                id: (),
-                                spec_id: common::SpecificationId::dummy(),
-                                expr: x,
-                            })
-                            .collect()
-                        )
-                    );
+                // A trigger is either a tuple of terms or, as a shorthand, a
+                // single term.
+                let terms: Vec<syn::Expr> = if let syn::Expr::Tuple(tuple) = item {
+                    tuple.elems.into_iter().collect()
                 } else {
-                    return Err(self.error_expected_tuple(item.span()));
-                }
+                    vec![item]
+                };
+                vec_of_triggers.push(
+                    Trigger(terms
+                        .into_iter()
+                        .map(|x| ExpressionWithoutId {
+                            id: (),
+                            spec_id: common::SpecificationId::dummy(),
+                            expr: x,
+                        })
+                        .collect()
+                    )
+                );
             }
             trigger_set = TriggerSet(vec_of_triggers);
         }
@@ -350,6 +399,7 @@ impl Parser {
 
         while !self.peek_operator("|=") &&
               !self.peek_operator("&&") &&
+              !self.peek_operator("<==>") &&
               !self.peek_operator("==>") &&
               !self.peek_operator(terminator) &&
               !self.tokens.is_empty() {
@@ -359,24 +409,112 @@ impl Parser {
         stream.extend(t.into_iter());
 
         let cloned: VecDeque<TokenTree> = stream.clone().into_iter().collect();
-        if let Some(span) = self.contains_operator_recursive(&cloned, "==>") {
-            Err(self.error_no_implies(span))
+        if let Some(span) = self.contains_operator_recursive(&cloned, "<==>") {
+            Err(self.error_no_operator(span, "<==>"))
+        } else if let Some(span) = self.contains_operator_recursive(&cloned, "==>") {
+            Err(self.error_no_operator(span, "==>"))
         } else if cloned.is_empty() {
             Err(self.error_expected("expression"))
         } else {
+            let mut expr: syn::Expr = syn::parse2(self.rewrite_snapshot_equality(stream)?)?;
+            OldPlaceRewriter.visit_expr_mut(&mut expr);
             Ok(ExpressionWithoutId {
                 spec_id: common::SpecificationId::dummy(),
                 id: (),
-                expr: syn::parse2(stream)?,
+                expr,
             })
         }
     }
 
+    /// Translate the specification-only operators `===` and `!==`, also in
+    /// nested groups, to calls of `prusti_contracts::snapshot_equality`.
+    fn rewrite_snapshot_equality(&self, stream: TokenStream) -> syn::Result<TokenStream> {
+        let mut tokens = VecDeque::new();
+        for token in stream {
+            tokens.push_back(match token {
+                TokenTree::Group(group) => {
+                    let mut rewritten = Group::new(
+                        group.delimiter(),
+                        self.rewrite_snapshot_equality(group.stream())?,
+                    );
+                    rewritten.set_span(group.span());
+                    TokenTree::Group(rewritten)
+                }
+                token => token,
+            });
+        }
+
+        // The operands of a comparison extend up to the closest operator with
+        // a lower precedence or separator.
+        let mut result = TokenStream::new();
+        let mut operand = VecDeque::new();
+        while !tokens.is_empty() {
+            if let Some(length) = self.peek_comparison_boundary(&tokens, operand.back()) {
+                result.extend(self.rewrite_comparison(operand)?);
+                result.extend(tokens.drain(..length));
+                operand = VecDeque::new();
+            } else {
+                operand.push_back(tokens.pop_front().unwrap());
+            }
+        }
+        result.extend(self.rewrite_comparison(operand)?);
+        Ok(result)
+    }
+
+    /// does the stream start with a token that ends the operands of a
+    /// comparison? If yes, return the number of its characters
+    fn peek_comparison_boundary(&self, stream: &VecDeque<TokenTree>, previous: Option<&TokenTree>) -> Option<usize> {
+        // `&&` directly after an operator is a double reference.
+        let follows_operand = !matches!(previous, None | Some(TokenTree::Punct(_)));
+        if self.peek_operator_stream_offset(stream, "=>", 0) ||
+           (follows_operand && self.peek_operator_stream_offset(stream, "&&", 0)) {
+            Some(2)
+        } else if self.peek_operator_stream_offset(stream, "|", 0) ||
+                  self.peek_operator_stream_offset(stream, ",", 0) ||
+                  self.peek_operator_stream_offset(stream, ";", 0) {
+            Some(1)
+        } else {
+            None
+        }
+    }
+    /// rewrite `lhs === rhs` and `lhs !== rhs`, if the tokens are of this form
+    fn rewrite_comparison(&self, tokens: VecDeque<TokenTree>) -> syn::Result<TokenStream> {
+        let operators: Vec<(usize, &str)> = (0..tokens.len())
+            .flat_map(|offset| vec![(offset, "==="), (offset, "!==")])
+            .filter(|(offset, operator)| self.peek_operator_stream_offset(&tokens, operator, *offset))
+            .collect();
+        let (offset, operator) = match operators.as_slice() {
+            [] => return Ok(tokens.into_iter().collect()),
+            [operator] => *operator,
+            [_, (offset, operator), ..] => {
+                return Err(syn::Error::new(
+                    self.operator_span_offset(&tokens, operator, *offset),
+                    "comparison operators cannot be chained",
+                ));
+            }
+        };
+        let span = self.operator_span_offset(&tokens, operator, offset);
+        let lhs: TokenStream = tokens.iter().take(offset).cloned().collect();
+        let rhs: TokenStream = tokens.iter().skip(offset + operator.len()).cloned().collect();
+        if lhs.is_empty() || rhs.is_empty() {
+            return Err(syn::Error::new(span, format!("expected expression on both sides of `{}`", operator)));
+        }
+        let equality = quote_spanned! {span=>
+            prusti_contracts::snapshot_equality(&(#lhs), &(#rhs))
+        };
+        Ok(if operator == "!==" {
+            quote_spanned! {span=> !#equality }
+        } else {
+            equality
+        })
+    }
+
     /// is there any non-prusti operator following the first thing?
     fn is_part_of_rust_expr(&mut self) -> bool {
         if let Some(token) = self.tokens.pop_front() {
             if self.peek_operator("|=") ||
                self.peek_operator("&&") ||
+               self.peek_operator("<==>") ||
                self.peek_operator("==>") ||
                self.tokens.front().is_none() {
                 self.tokens.push_front(token);
@@ -412,7 +550,7 @@ impl Parser {
     fn operator_span_offset(&self, stream: &VecDeque<TokenTree>, operator: &str, offset: usize) -> Span {
         stream.get(offset).unwrap().span().join(stream.get(offset + operator.len() - 1).unwrap().span()).unwrap()
     }
-    /// Check if there is a subexpression (parenthesized or separated by `==>`)
+    /// Check if there is a subexpression (parenthesized or separated by `==>` or `<==>`)
     /// that contains both `&&` and `||`. If yes, set the span to include both
     /// of those operators and everything in between them. This detects
     /// potentially ambiguous subexpressions.
@@ -563,13 +701,18 @@ impl Parser {
         syn::Error::new(self.get_error_span(), "a quantifier must have at least one argument")
     }
     fn error_expected_tuple(&self, span: Span) -> syn::Error {
-        syn::Error::new(span, "`triggers` must be an array of tuples containing Rust expressions")
+        syn::Error::new(span, "`triggers` must be an array of Rust expressions or of tuples containing Rust expressions")
     }
     fn error_unexpected(&self) -> syn::Error {
         syn::Error::new(self.get_error_span(), "unexpected token")
     }
-    fn error_no_implies(&self, span: Span) -> syn::Error {
-        syn::Error::new(span, "`==>` cannot be part of Rust expression")
+    fn error_no_operator(&self, span: Span, operator: &str) -> syn::Error {
+        syn::Error::new(span, format!("`{}` cannot be part of Rust expression", operator))
+    }
+    fn error_chained_iff(&self) -> syn::Error {
+        syn::Error::new(
+            self.get_error_span(),
+            "`<==>` cannot be chained. Hint: add parentheses to clarify the evaluation order.")
     }
     fn error_ambiguous_expression(&self, span: Span) -> syn::Error {
         syn::Error::new(
@@ -578,3 +721,38 @@ impl Parser {
             Hint: add parentheses to clarify the evaluation order.")
     }
 }
+
+/// Rewrites `old(*place)` to `(*old(&*place))`. The dereferenced place might be
+/// unsized or not `Copy` (for example `old(*self.buf)[i]` with a slice `buf`),
+/// so it cannot be passed to `old` by value. The encoder evaluates the borrowed
+/// place, including all its dereferences, in the old state.
+struct OldPlaceRewriter;
+
+impl VisitMut for OldPlaceRewriter {
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        syn::visit_mut::visit_expr_mut(self, expr);
+        let rewritten = match expr {
+            syn::Expr::Call(call) if call.args.len() == 1 => {
+                let is_old = match &*call.func {
+                    syn::Expr::Path(path) => path.path.segments.last()
+                        .map_or(false, |segment| segment.ident == "old"),
+                    _ => false,
+                };
+                match call.args.first() {
+                    Some(place @ syn::Expr::Unary(syn::ExprUnary { op: syn::UnOp::Deref(_), .. }))
+                        if is_old =>
+                    {
+                        let func = &call.func;
+                        let span = call.span();
+                        Some(quote_spanned! {span=> (*#func(&#place)) })
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(tokens) = rewritten {
+            *expr = syn::parse2(tokens).unwrap();
+        }
+    }
+}
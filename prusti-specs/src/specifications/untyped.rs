@@ -103,6 +103,38 @@ impl Assertion {
         let assertion = parser.extract_assertion()?;
         Ok(assertion.assign_id(spec_id, id_generator))
     }
+
+    /// Parse a termination measure. Unlike other assertions, a measure is a
+    /// single expression of an integer type.
+    pub(crate) fn parse_measure(
+        tokens: TokenStream,
+        spec_id: SpecificationId,
+        id_generator: &mut ExpressionIdGenerator,
+    ) -> syn::Result<Self> {
+        let assertion: common::Assertion<(), syn::Expr, Arg> = syn::parse2(tokens)?;
+        Ok(assertion.assign_id(spec_id, id_generator))
+    }
+
+    /// Generate the type check of a termination measure, which, unlike the
+    /// other assertions, is not a boolean expression.
+    pub(crate) fn encode_measure_type_check(&self, tokens: &mut TokenStream) {
+        match &*self.kind {
+            AssertionKind::Expr(expression) => {
+                let span = expression.expr.span();
+                let expr = &expression.expr;
+                let identifier = format!("{}_{}", expression.spec_id, expression.id);
+                let typeck_call = quote_spanned! { span =>
+                    #[prusti::spec_only]
+                    #[prusti::expr_id = #identifier]
+                    || {
+                        #expr
+                    };
+                };
+                tokens.extend(typeck_call);
+            }
+            _ => unreachable!("a termination measure is always a single expression"),
+        }
+    }
 }
 
 impl Parse for common::Expression<(), syn::Expr> {
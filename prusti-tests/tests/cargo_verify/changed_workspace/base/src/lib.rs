@@ -0,0 +1,8 @@
+pub fn base() -> u32 {
+    1
+}
+
+/// Fails to verify, so that the test notices when this crate is verified.
+pub fn failing_base() {
+    assert!(false);
+}
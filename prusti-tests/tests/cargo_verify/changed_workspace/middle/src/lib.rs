@@ -0,0 +1,3 @@
+pub fn middle() -> u32 {
+    base::base()
+}
@@ -0,0 +1,4 @@
+/// Fails to verify, so that the test notices when this crate is verified.
+pub fn failing_other() {
+    assert!(false);
+}
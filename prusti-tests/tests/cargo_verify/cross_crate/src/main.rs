@@ -0,0 +1,12 @@
+use library::{max, Counter};
+
+// The contracts used here are only defined in `library`.
+fn main() {
+    let m = max(3, 7);
+    assert!(m >= 7);
+
+    let mut counter = Counter::new();
+    counter.increment();
+    counter.increment();
+    assert!(counter.value() == 2);
+}
@@ -0,0 +1,33 @@
+use prusti_contracts::*;
+
+#[ensures(result == 1)]
+pub fn verified() -> u32 {
+    1
+}
+
+#[cfg(feature = "extra")]
+#[ensures(result == 10)]
+pub fn feature_gated() -> u32 {
+    2
+}
+
+#[cfg(not(feature = "basic"))]
+#[ensures(result == 20)]
+pub fn without_default_features() -> u32 {
+    3
+}
+
+#[cfg(test)]
+mod tests {
+    use prusti_contracts::*;
+
+    #[ensures(result == 30)]
+    fn test_helper() -> u32 {
+        4
+    }
+
+    #[test]
+    fn uses_helper() {
+        let _ = test_helper();
+    }
+}
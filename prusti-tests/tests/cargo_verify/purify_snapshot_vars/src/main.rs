@@ -0,0 +1,13 @@
+use prusti_contracts::*;
+
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[ensures(result.x == p.y && result.y == p.x)]
+fn shuffle(p: Point) -> Point {
+    Point { x: p.y, y: p.x }
+}
+
+fn main() {}
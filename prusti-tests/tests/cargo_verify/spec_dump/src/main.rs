@@ -0,0 +1,16 @@
+use prusti_contracts::*;
+
+#[pure]
+#[requires(n > 0)]
+#[ensures(forall(|i: u32| i < n ==> result > i))]
+fn max_below(n: u32) -> u32 {
+    n
+}
+
+#[trusted]
+#[ensures(result == a + b)]
+fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+fn main() {}
@@ -4,9 +4,11 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use cargo_test_support::{cargo_test, project, symlink_supported};
+use cargo_test_support::{cargo_test, project, symlink_supported, Project};
+use prusti_interface::report::{ItemResult, VerificationReport};
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::process::Command;
 
 fn cargo_prusti_path() -> PathBuf {
     let target_directory = if cfg!(debug_assertions) {
@@ -38,6 +40,15 @@ fn cargo_prusti_path() -> PathBuf {
     );
 }
 
+fn prusti_rustc_path() -> PathBuf {
+    let executable_name = if cfg!(windows) {
+        "prusti-rustc.exe"
+    } else {
+        "prusti-rustc"
+    };
+    cargo_prusti_path().with_file_name(executable_name)
+}
+
 #[cargo_test]
 fn simple_assert_true() {
     let p = project()
@@ -57,7 +68,7 @@ fn simple_assert_false() {
         .with_stderr(
             "\
 [CHECKING] foo v0.0.1 ([..])
-[ERROR] [Prusti: verification error] the asserted expression might not hold
+error[P0001]: [Prusti: verification error] the asserted expression might not hold
  --> src/main.rs:1:13
   |
 1 | fn main() { assert!(false); }
@@ -71,19 +82,324 @@ error: could not compile `foo` due to previous error
         .run();
 }
 
-/// Test `cargo-prusti` on one of the crates in `test/cargo_verify`.
-///
-/// Special files and folders in the root of the test crate:
-/// * `output.stdout` and `output.stderr`: if present, they are used to check the output of
-///   `cargo-prusti`.
-/// * `prusti-contracts` and related Prusti crates: during the test they will link to the
-///   corresponding Prusti crate.
-///
-/// This function requires symlinks to be supported.
+#[cargo_test]
+fn verification_cache() {
+    let p = project()
+        .file("src/main.rs", "fn main() { assert!(false); }")
+        .build();
+    p.process(cargo_prusti_path())
+        .with_status(101)
+        .with_stderr_contains("error[P0001]: [Prusti: verification error] the asserted expression might not hold")
+        .run();
+
+    // Move the code without changing its encoding. The second run should
+    // replay the cached error at the new location, without contacting a
+    // verifier (the server address is unreachable).
+    fs::write(p.root().join("src/main.rs"), "\nfn main() { assert!(false); }").unwrap();
+    p.process(cargo_prusti_path())
+        .env("PRUSTI_SERVER_ADDRESS", "127.0.0.1:1")
+        .with_status(101)
+        .with_stderr(
+            "\
+[CHECKING] foo v0.0.1 ([..])
+error[P0001]: [Prusti: verification error] the asserted expression might not hold
+ --> src/main.rs:2:13
+  |
+2 | fn main() { assert!(false); }
+  |             ^^^^^^^^^^^^^^^
+  |
+  = note: this error originates in the macro `assert` (in Nightly builds, run with -Z macro-backtrace for more info)
+
+error: could not compile `foo` due to previous error
+",
+        )
+        .run();
+
+    // With `--force-reverify` the cache is ignored, so the program is sent to
+    // the unreachable server instead of being replayed.
+    p.process(cargo_prusti_path())
+        .arg("--force-reverify")
+        .env("PRUSTI_SERVER_ADDRESS", "127.0.0.1:1")
+        .with_status(101)
+        .with_stderr_contains("error: [Prusti internal error] could not verify on the Prusti server: [..]")
+        .with_stderr_does_not_contain("[..]the asserted expression might not hold[..]")
+        .run();
+
+    // Without the unreachable server, the program is verified again.
+    p.process(cargo_prusti_path())
+        .arg("--force-reverify")
+        .with_status(101)
+        .with_stderr_contains("error[P0001]: [Prusti: verification error] the asserted expression might not hold")
+        .run();
+}
+
+#[cargo_test]
+fn verification_report() {
+    let p = project()
+        .file("src/main.rs", "\
+fn verified() { assert!(true); }
+fn failing() { assert!(false); }
+fn main() {}
+")
+        .build();
+    let report_path = p.root().join("report.json");
+    p.process(cargo_prusti_path())
+        .env("PRUSTI_REPORT", &report_path)
+        .env("PRUSTI_VERIFY_ONLY", "verified,failing")
+        .with_status(101)
+        .with_stderr_contains("error[P0001]: [Prusti: verification error] the asserted expression might not hold")
+        .run();
+
+    let report = VerificationReport::read(report_path.to_str().unwrap()).unwrap();
+    let results: Vec<_> = report.items
+        .iter()
+        .map(|item| (item.def_path.as_str(), item.result))
+        .collect();
+    assert_eq!(results, vec![
+        ("verified", ItemResult::Verified),
+        ("failing", ItemResult::Failed),
+        ("main", ItemResult::Skipped),
+    ]);
+    assert!(report.items[0].errors.is_empty());
+    let failing = &report.items[1];
+    assert_eq!(failing.span.file, "src/main.rs");
+    assert_eq!(failing.span.line_start, 2);
+    assert_eq!(failing.errors.len(), 1);
+    assert!(failing.errors[0].is_error);
+    assert!(failing.errors[0].message.contains("the asserted expression might not hold"));
+    let error_span = failing.errors[0].span.as_ref().unwrap();
+    assert_eq!((error_span.line_start, error_span.column_start), (2, 16));
+}
+
+#[cargo_test]
+fn smt_rlimit() {
+    let p = project()
+        .file("src/main.rs", "\
+fn limited() { assert!(true); }
+#[prusti::smt_rlimit(1)]
+fn starved(x: u32, y: u32) { if x < y { assert!(x + 1 <= y); } }
+fn main() {}
+")
+        .build();
+    let report_path = p.root().join("report.json");
+    p.process(cargo_prusti_path())
+        .env("PRUSTI_REPORT", &report_path)
+        .env("PRUSTI_SMT_RLIMIT", "1000000")
+        .env("PRUSTI_VERIFY_ONLY", "limited,starved")
+        .with_status(101)
+        .with_stderr_contains("error[P0013]: [Prusti: verification ran out of resources] [..]")
+        .with_stderr_contains("[..]the SMT solver reached its resource limit of 1 (`smt_rlimit`)[..]")
+        .run();
+
+    let report = VerificationReport::read(report_path.to_str().unwrap()).unwrap();
+    let results: Vec<_> = report.items
+        .iter()
+        .map(|item| (item.def_path.as_str(), item.result))
+        .collect();
+    assert_eq!(results, vec![
+        ("limited", ItemResult::Verified),
+        ("starved", ItemResult::ResourceOut),
+        ("main", ItemResult::Skipped),
+    ]);
+    // The resources actually consumed by the SMT solver are reported, not the
+    // limit.
+    let consumed = report.items[0].smt_rlimit_count.unwrap();
+    assert!(consumed > 0 && consumed != 1000000, "{}", consumed);
+    assert!(report.items[1].errors.iter().all(|error| error.code.as_deref() == Some("P0013")));
+    assert_eq!(report.items[2].smt_rlimit_count, None);
+}
+
+#[cargo_test]
+fn json_diagnostics() {
+    let p = project()
+        .file("src/main.rs", "\
+fn main() {
+    assert!(false);
+}
+")
+        .build();
+    let mut process = cargo_test_support::process(cargo_prusti_path());
+    process.cwd(p.root()).arg("--message-format=json");
+    let output = process.build_command().output().unwrap();
+    assert!(!output.status.success());
+
+    let messages: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .filter(|message: &serde_json::Value| message["reason"] == "compiler-message")
+        .map(|message| message["message"].clone())
+        .filter(|diagnostic| diagnostic["code"]["code"] == "P0001")
+        .collect();
+    assert_eq!(messages.len(), 1, "{:#?}", messages);
+    let diagnostic = &messages[0];
+    assert_eq!(diagnostic["level"], "error");
+    assert_eq!(
+        diagnostic["message"],
+        "[Prusti: verification error] the asserted expression might not hold"
+    );
+    let spans = diagnostic["spans"].as_array().unwrap();
+    let primary_span = spans.iter().find(|span| span["is_primary"] == true).unwrap();
+    assert_eq!(primary_span["file_name"], "src/main.rs");
+    assert_eq!(primary_span["line_start"], 2);
+    let notes: Vec<_> = diagnostic["children"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|child| child["level"] == "note")
+        .map(|child| child["message"].as_str().unwrap())
+        .collect();
+    assert!(
+        notes.iter().any(|note| note.starts_with("the Viper verification error is: [assert.failed:")),
+        "{:?}", notes
+    );
+}
+
+#[cargo_test]
+fn explain_error_code() {
+    let p = project().file("src/main.rs", "fn main() {}").build();
+    p.process(prusti_rustc_path())
+        .arg("--explain")
+        .arg("P0002")
+        .with_stdout_contains("The postcondition of a function might not hold when the function returns.")
+        .run();
+    p.process(prusti_rustc_path())
+        .arg("--explain=P9999")
+        .with_status(1)
+        .with_stderr_contains("error: P9999 is not a valid Prusti error code")
+        .run();
+}
+
+#[cargo_test]
+fn progress_report() {
+    let p = project()
+        .file("src/main.rs", "\
+fn first() { assert!(true); }
+fn second() { assert!(true); }
+fn third() { assert!(false); }
+fn main() {}
+")
+        .build();
+    let mut process = cargo_test_support::process(cargo_prusti_path());
+    process
+        .cwd(p.root())
+        .env("PRUSTI_QUIET", "false")
+        .env("PRUSTI_VERIFY_ONLY", "first,second,third")
+        .env("PRUSTI_NUM_PARALLEL_VERIFIERS", "2");
+    let output = process.build_command().output().unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    // The lines look like `[2/3] verifying second` when an item starts, and
+    // like `[2/3] verified second (1.25s)` when it finishes.
+    let mut started: Vec<(&str, &str)> = vec![];
+    let mut finished: Vec<(usize, &str, &str)> = vec![];
+    for line in stderr.lines() {
+        let rest = match line.strip_prefix('[') {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let (count, rest) = match rest.split_once("/3] ") {
+            Some((count, rest)) => match count.parse::<usize>() {
+                Ok(count) => (count, rest),
+                Err(_) => continue,
+            },
+            None => continue,
+        };
+        if let Some(rest) = rest.strip_suffix("s)") {
+            let (event, _elapsed) = rest.rsplit_once(" (").unwrap();
+            let (outcome, item) = event.rsplit_once(' ').unwrap();
+            finished.push((count, outcome, item));
+        } else {
+            let (phase, item) = rest.split_once(' ').unwrap();
+            started.push((phase, item));
+        }
+    }
+
+    let items = vec!["first", "second", "third"];
+    for (phase, outcomes) in [
+        ("encoding", &["encoded"][..]),
+        ("verifying", &["verified", "failed to verify"][..]),
+    ] {
+        let mut started_items: Vec<_> = started
+            .iter()
+            .filter(|&&(started_phase, _)| started_phase == phase)
+            .map(|&(_, item)| item)
+            .collect();
+        started_items.sort();
+        assert_eq!(started_items, items, "{}", stderr);
+
+        let phase_finished: Vec<_> = finished
+            .iter()
+            .filter(|(_, outcome, _)| outcomes.contains(outcome))
+            .collect();
+        let counts: Vec<_> = phase_finished.iter().map(|(count, _, _)| *count).collect();
+        assert_eq!(counts, vec![1, 2, 3], "{}", stderr);
+        let mut finished_items: Vec<_> = phase_finished.iter().map(|(_, _, item)| *item).collect();
+        finished_items.sort();
+        assert_eq!(finished_items, items, "{}", stderr);
+    }
+    assert!(finished.iter().any(|&(_, outcome, item)| (outcome, item) == ("failed to verify", "third")));
+}
+
+#[cargo_test]
+fn dump_viper_program() {
+    let p = project()
+        .file("src/main.rs", "\
+fn count() -> u32 {
+    let mut total = 0;
+    total += 1;
+    total
+}
+fn main() {}
+")
+        .build();
+    let dump_dir = p.root().join("viper");
+    p.process(cargo_prusti_path())
+        .env("PRUSTI_DUMP_VIPER_PROGRAM", &dump_dir)
+        .env("PRUSTI_VERIFY_ONLY", "count")
+        .run();
+
+    let program = fs::read_to_string(dump_dir.join("main.rs-count.vpr")).unwrap();
+    assert!(program.contains("// src/main.rs:3"), "{}", program);
+}
+
+#[cargo_test]
+fn deterministic_viper_program() {
+    let p = project()
+        .file("src/main.rs", "\
+fn pick<'a>(a: &'a mut u32, b: &'a mut u32, left: bool) -> &'a mut u32 {
+    if left { a } else { b }
+}
+fn update(a: &mut u32, b: &mut u32) {
+    let x = pick(a, b, *a < 100);
+    if *x < 100 {
+        *x += 1;
+    }
+}
+fn main() {}
+")
+        .build();
+    let mut programs = vec![];
+    for run in 0..2 {
+        let dump_dir = p.root().join(format!("viper{}", run));
+        p.process(cargo_prusti_path())
+            .env("PRUSTI_DUMP_VIPER_PROGRAM", &dump_dir)
+            .env("PRUSTI_VERIFY_ONLY", "update")
+            .arg("--force-reverify")
+            .run();
+        programs.push(fs::read_to_string(dump_dir.join("main.rs-update.vpr")).unwrap());
+    }
+    assert_eq!(programs[0], programs[1]);
+}
+
+/// Prepare one of the crates in `test/cargo_verify` as a test project, and
+/// fetch its dependencies.
 ///
-/// For more details on the special syntax allowed in the `output.*` files, check the documentation
-/// of `cargo_test_support`: <https://doc.crates.io/contrib/tests/writing.html>.
-fn test_local_project<T: Into<PathBuf>>(project_name: T) {
+/// The test project links to the files of the local project. In addition,
+/// `prusti-contracts` and related Prusti crates link to the corresponding
+/// Prusti crate. This function requires symlinks to be supported.
+fn local_project<T: Into<PathBuf>>(project_name: T) -> (Project, PathBuf) {
     let mut project_builder = project().no_manifest();
     let relative_project_path = Path::new("tests/cargo_verify").join(project_name.into());
     let project_path = fs::canonicalize(&relative_project_path).expect(
@@ -129,6 +445,25 @@ fn test_local_project<T: Into<PathBuf>>(project_name: T) {
     // Fetch dependencies
     let project = project_builder.build();
     project.process("cargo").arg("build").run();
+    (project, project_path)
+}
+
+/// Test `cargo-prusti` on one of the crates in `test/cargo_verify`.
+///
+/// Special files and folders in the root of the test crate:
+/// * `output.stdout` and `output.stderr`: if present, they are used to check the output of
+///   `cargo-prusti`.
+/// * `prusti-contracts` and related Prusti crates: during the test they will link to the
+///   corresponding Prusti crate.
+///
+/// This function requires symlinks to be supported.
+///
+/// For more details on the special syntax allowed in the `output.*` files, check the documentation
+/// of `cargo_test_support`: <https://doc.crates.io/contrib/tests/writing.html>.
+///
+/// Returns the test project, to check the files that `cargo-prusti` created in it.
+fn test_local_project<T: Into<PathBuf>>(project_name: T) -> Project {
+    let (project, project_path) = local_project(project_name);
 
     // Set the expected exit status, stdout and stderr
     let mut test_builder = project.process(cargo_prusti_path());
@@ -143,6 +478,7 @@ fn test_local_project<T: Into<PathBuf>>(project_name: T) {
 
     // Run the test
     test_builder.run();
+    project
 }
 
 #[cargo_test]
@@ -161,6 +497,86 @@ fn test_prusti_toml() {
     test_local_project("prusti_toml");
 }
 
+#[cargo_test]
+fn test_cross_crate() {
+    test_local_project("cross_crate");
+}
+
+#[cargo_test]
+fn test_features_and_targets() {
+    let (p, _) = local_project("features_and_targets");
+    // The postconditions that fail with each selection of features and
+    // targets: `result == 10` is gated by the `extra` feature, `result == 20`
+    // by the absence of the default features, and `result == 30` is in a test
+    // module.
+    let cases: [(&[&str], &[&str]); 6] = [
+        (&[], &[]),
+        (&["--features", "extra"], &["result == 10"]),
+        (&["--lib", "--features", "extra"], &["result == 10"]),
+        (&["--no-default-features"], &["result == 20"]),
+        (&["--tests"], &["result == 30"]),
+        (&["--tests", "--features=extra"], &["result == 10", "result == 30"]),
+    ];
+    for (args, failures) in cases.iter() {
+        let mut execs = p.process(cargo_prusti_path());
+        execs.args(args);
+        if failures.is_empty() {
+            execs.with_stderr_does_not_contain("[..]verification error[..]");
+        } else {
+            execs.with_status(101);
+            for failure in failures.iter() {
+                execs.with_stderr_contains(format!("[..]{}[..]", failure));
+            }
+        }
+        execs.run();
+    }
+}
+
+#[cargo_test]
+fn test_purify_snapshot_vars() {
+    let project = test_local_project("purify_snapshot_vars");
+    let program = fs::read_to_string(project.root().join("viper/main.rs-shuffle.vpr")).unwrap();
+    // The argument `p` is purified into a snapshot, so its predicate is
+    // never folded or unfolded. The result stays on the heap.
+    let folds_argument = program.lines().any(|line| {
+        let line = line.trim_start();
+        let is_fold = line.starts_with("fold ") || line.starts_with("unfold ");
+        is_fold && line.contains("Point") && line.contains("(_1)")
+    });
+    assert!(!folds_argument, "{}", program);
+}
+
+#[cargo_test]
+fn test_spec_dump() {
+    let project = test_local_project("spec_dump");
+    let dump = fs::read_to_string(project.root().join("specs.json")).unwrap();
+    let items: Vec<serde_json::Value> = serde_json::from_str(&dump).unwrap();
+    let summary: Vec<_> = items
+        .iter()
+        .map(|item| (
+            item["def_path"].as_str().unwrap(),
+            item["kind"].as_str().unwrap(),
+            item["pure"].as_bool().unwrap(),
+            item["trusted"].as_bool().unwrap(),
+        ))
+        .collect();
+    assert_eq!(summary, vec![
+        ("add", "procedure", false, true),
+        ("max_below", "procedure", true, false),
+    ], "{}", dump);
+
+    let max_below = &items[1];
+    assert_eq!(max_below["span"]["file"], "src/main.rs");
+    assert_eq!(max_below["span"]["line_start"], 6);
+    let precondition = &max_below["preconditions"][0];
+    assert_eq!(precondition["source"], "n > 0");
+    assert_eq!(precondition["structure"]["kind"], "Expr");
+    let postcondition = &max_below["postconditions"][0];
+    assert_eq!(postcondition["structure"]["kind"], "ForAll");
+    assert_eq!(postcondition["structure"]["vars"][0]["ty"], "u32");
+    assert_eq!(items[0]["postconditions"][0]["source"], "result == a + b");
+}
+
 #[cargo_test]
 fn test_prusti_toml_fail() {
     let old_value = if let Ok(value) = std::env::var("RUST_BACKTRACE") {
@@ -178,3 +594,83 @@ fn test_prusti_toml_fail() {
 }
 
 // TODO: automatically create a test for each folder in `test/cargo_verify`.
+
+/// Copy the workspace in `tests/cargo_verify/changed_workspace` into a new
+/// project, and commit it to a new git repository. In this workspace, `top`
+/// depends on `middle`, which depends on `base`; `other` is only a development
+/// dependency of `top`. `base` and `other` fail to verify.
+fn changed_workspace_project() -> Project {
+    let fixture_path = fs::canonicalize("tests/cargo_verify/changed_workspace")
+        .expect("Failed to canonicalize the path of the changed_workspace fixture");
+    let mut project_builder = project().no_manifest();
+    for file in ["Cargo.toml", ".gitignore"].iter() {
+        project_builder = project_builder.file(file, &fs::read_to_string(fixture_path.join(file)).unwrap());
+    }
+    for member in ["base", "middle", "top", "other"].iter() {
+        for file in ["Cargo.toml", "src/lib.rs"].iter() {
+            let path = Path::new(member).join(file);
+            let content = fs::read_to_string(fixture_path.join(&path)).unwrap();
+            project_builder = project_builder.file(path, &content);
+        }
+    }
+    let project = project_builder.build();
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(&["-c", "user.name=Prusti", "-c", "user.email=prusti@example.com"])
+            .args(args)
+            .current_dir(project.root())
+            .status()
+            .expect("could not run git");
+        assert!(status.success(), "`git {}` failed", args.join(" "));
+    };
+    git(&["init", "--quiet"]);
+    git(&["add", "--all"]);
+    git(&["commit", "--quiet", "--message", "Initial commit"]);
+    project
+}
+
+#[cargo_test]
+fn changed_since_dependency() {
+    let p = changed_workspace_project();
+    fs::write(p.root().join("middle/src/lib.rs"), "\
+pub fn middle() -> u32 {
+    base::base()
+}
+
+pub fn changed() {}
+").unwrap();
+    p.process(cargo_prusti_path())
+        .arg("--changed-since")
+        .arg("HEAD")
+        .with_stderr_contains(
+            "Skipped the verification of 2 workspace member(s) unaffected by the changes since HEAD: base, other"
+        )
+        .run();
+}
+
+#[cargo_test]
+fn changed_since_reverse_dependencies() {
+    let p = changed_workspace_project();
+    fs::write(p.root().join("base/src/extra.rs"), "pub fn extra() {}\n").unwrap();
+    p.process(cargo_prusti_path())
+        .arg("--changed-since=HEAD")
+        .with_status(101)
+        .with_stderr_contains("error[P0001]: [Prusti: verification error] the asserted expression might not hold")
+        .with_stderr_contains(
+            "Skipped the verification of 1 workspace member(s) unaffected by the changes since HEAD: other"
+        )
+        .run();
+}
+
+#[cargo_test]
+fn changed_since_nothing() {
+    let p = changed_workspace_project();
+    p.process(cargo_prusti_path())
+        .arg("--changed-since")
+        .arg("HEAD")
+        .with_stderr(
+            "Skipped the verification of 4 workspace member(s) unaffected by the changes since HEAD: \
+            base, middle, other, top"
+        )
+        .run();
+}
@@ -87,6 +87,14 @@ fn run_prusti_tests(group_name: &str, filter: &Option<String>, rustc_flags: Opti
 
     let path: PathBuf = ["tests", group_name, "ui"].iter().collect();
     if path.exists() {
+        let _temporary_env_vars = (
+            // The built-in specifications would show up in the printed output.
+            TemporaryEnvVar::set("PRUSTI_STD_SPECS", "false"),
+            // The errors of a procedure are reported as soon as it is
+            // verified, so the procedures are verified one at a time to
+            // report them in a deterministic order.
+            TemporaryEnvVar::set("PRUSTI_NUM_PARALLEL_VERIFIERS", "1"),
+        );
         config.target_rustcflags = Some(format!(
             "--color=never {}",
             config.target_rustcflags.unwrap_or("".to_string())
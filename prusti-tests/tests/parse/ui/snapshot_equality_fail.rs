@@ -0,0 +1,12 @@
+use prusti_contracts::*;
+
+#[requires(a === b === c)]
+fn test1(a: i32, b: i32, c: i32) {}
+
+#[requires(=== a)]
+fn test2(a: i32) {}
+
+#[ensures(a !==)]
+fn test3(a: i32) {}
+
+fn main() {}
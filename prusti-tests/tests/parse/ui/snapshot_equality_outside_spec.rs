@@ -0,0 +1,11 @@
+use prusti_contracts::*;
+
+#[ensures(result === a)]
+fn id(a: i32) -> i32 {
+    a
+}
+
+fn main() {
+    let a = id(1);
+    let _same = a === 1;
+}
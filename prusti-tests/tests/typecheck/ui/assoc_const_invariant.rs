@@ -0,0 +1,19 @@
+// compile-flags: -Pprint_typeckd_specs=true -Pno_verify=true -Phide_uuids=true
+// normalize-stdout-test: "[a-z0-9]{32}" -> "$(NUM_UUID)"
+// normalize-stdout-test: "[a-z0-9]{8}-[a-z0-9]{4}-[a-z0-9]{4}-[a-z0-9]{4}-[a-z0-9]{12}" -> "$(UUID)"
+// normalize-stdout-test: "\[[a-z0-9]{4}\]::" -> "[$(CRATE_ID)]::"
+
+#![allow(dead_code)]
+
+use prusti_contracts::*;
+
+struct Buffer {
+    len: usize,
+}
+
+impl Buffer {
+    #[invariant(Self::CAPACITY > 0)]
+    const CAPACITY: usize = 16;
+}
+
+fn main() {}
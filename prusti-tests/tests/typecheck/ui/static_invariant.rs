@@ -0,0 +1,21 @@
+// compile-flags: -Pprint_typeckd_specs=true -Pno_verify=true -Phide_uuids=true
+// normalize-stdout-test: "[a-z0-9]{32}" -> "$(NUM_UUID)"
+// normalize-stdout-test: "[a-z0-9]{8}-[a-z0-9]{4}-[a-z0-9]{4}-[a-z0-9]{4}-[a-z0-9]{12}" -> "$(UUID)"
+// normalize-stdout-test: "\[[a-z0-9]{4}\]::" -> "[$(CRATE_ID)]::"
+
+#![allow(dead_code)]
+
+use prusti_contracts::*;
+
+struct Config {
+    len: usize,
+    cap: usize,
+}
+
+#[invariant(CONFIG.len <= CONFIG.cap)]
+static CONFIG: Config = Config { len: 1, cap: 2 };
+
+#[invariant(LIMIT > 0)]
+const LIMIT: u32 = 10;
+
+fn main() {}
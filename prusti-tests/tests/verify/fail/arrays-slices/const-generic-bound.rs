@@ -0,0 +1,21 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[requires(N < s.len())]
+#[ensures(result == s[N])]
+fn nth<const N: usize>(s: &[u8]) -> u8 {
+    s[N]
+}
+
+#[ensures(result < N)] //~ ERROR postcondition might not hold
+fn below<const N: usize>() -> usize {
+    0
+}
+
+#[requires(s.len() > 2)]
+fn client(s: &[u8]) {
+    let x = nth::<2>(s);
+    assert!(x == s[1]);  //~ ERROR the asserted expression might not hold
+    nth::<3>(s);  //~ ERROR precondition might not hold
+}
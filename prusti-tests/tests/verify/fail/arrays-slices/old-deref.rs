@@ -0,0 +1,16 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+// `old(*s)` refers to the slice before the call, so its `i`-th element is not
+// necessarily `v`.
+#[requires(i < s.len())]
+#[ensures(old(*s)[i] == v)] //~ ERROR postcondition might not hold
+fn set(s: &mut [i32], i: usize, v: i32) {
+    s[i] = v;
+}
+
+#[ensures(old(*a)[0] == a[1])] //~ ERROR postcondition might not hold
+fn copy_second(a: &mut [i32; 2]) {
+    a[0] = a[1];
+}
@@ -0,0 +1,8 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+fn read_promoted_array() {
+    let a: &[u8; 2] = &[4, 5];
+    assert!(a[0] == 5); //~ ERROR the asserted expression might not hold
+}
@@ -0,0 +1,27 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+fn out_of_bounds(s: &[i32]) -> &[i32] {
+    &s[1..3] //~ ERROR the array or slice index may be out of bounds
+}
+
+#[requires(s.len() == 4)]
+fn wrong_element(s: &[i32]) {
+    let t = &s[1..3];
+    assert!(t[0] == s[0]); //~ ERROR the asserted expression might not hold
+}
+
+#[requires(i < s.len())]
+#[ensures(forall(|j: usize| (0 <= j && j < s.len()) ==> s[j] == old(s[j])))] //~ ERROR postcondition might not hold
+fn set(s: &mut [i32], i: usize, v: i32) {
+    s[i] = v;
+}
+
+fn from_out_of_bounds(s: &[i32]) -> &[i32] {
+    &s[1..] //~ ERROR the array or slice index may be out of bounds
+}
+
+fn inclusive_out_of_bounds(s: &[i32]) -> &[i32] {
+    &s[..=0] //~ ERROR the array or slice index may be out of bounds
+}
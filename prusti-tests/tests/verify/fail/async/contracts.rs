@@ -0,0 +1,23 @@
+use prusti_contracts::*;
+
+#[requires(x < 100)]
+#[ensures(result > x)]
+async fn increase(x: u32) -> u32 {
+    x + 1
+}
+
+// The callee only promises a larger result.
+#[requires(x < 100)]
+#[ensures(result == x + 1)] //~ ERROR postcondition might not hold
+async fn increment(x: u32) -> u32 {
+    increase(x).await
+}
+
+#[ensures(true)]
+async fn unchecked(x: u32) -> u32 {
+    increase(x).await //~ ERROR precondition might not hold
+}
+
+// Calling an `async fn` from a synchronous function creates a future, which
+// Prusti cannot encode.
+fn main() {}
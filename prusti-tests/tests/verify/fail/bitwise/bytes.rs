@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+
+#[ensures(result <= 127)] //~ ERROR postcondition might not hold
+fn low_byte(x: u32) -> u32 {
+    x & 0xFF
+}
+
+#[ensures(result == x)] //~ ERROR postcondition might not hold
+fn round_trip(x: u32) -> u32 {
+    (x << 8) >> 8
+}
+
+#[ensures(result == x)] //~ ERROR postcondition might not hold
+fn set_bits(x: u8) -> u8 {
+    x | 0x0F
+}
+
+fn main() {}
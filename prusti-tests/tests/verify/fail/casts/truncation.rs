@@ -0,0 +1,16 @@
+use prusti_contracts::*;
+
+#[ensures(result as u32 == x)] //~ ERROR postcondition might not hold.
+fn low_byte_without_bound(x: u32) -> u8 {
+    x as u8
+}
+
+#[requires(0 <= x && x < 65536)]
+#[ensures(result as i32 == x)] //~ ERROR postcondition might not hold.
+fn reinterpret(x: i32) -> i16 {
+    x as i16
+}
+
+fn main() {
+    assert!(-1i8 as u8 == 127); //~ ERROR the asserted expression might not hold
+}
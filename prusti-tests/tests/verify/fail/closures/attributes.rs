@@ -0,0 +1,12 @@
+#![feature(stmt_expr_attributes)]
+#![feature(proc_macro_hygiene)]
+
+use prusti_contracts::*;
+
+fn main() {
+    let f = #[requires(x > 0)] #[ensures(result == x - 1)] |x: i32| -> i32 { x - 1 };
+    f(0); //~ ERROR precondition might not hold
+
+    let g = #[ensures(result >= a)] |a: i32, b: i32| -> i32 { b }; //~ ERROR postcondition might not hold
+    g(1, 2);
+}
@@ -0,0 +1,14 @@
+#![feature(stmt_expr_attributes)]
+#![feature(proc_macro_hygiene)]
+
+use prusti_contracts::*;
+
+#[trusted]
+fn apply<F: Fn(i32) -> i32>(f: F) -> i32 {
+    f(1)
+}
+
+fn main() {
+    let f = #[requires(x > 0)] |x: i32| -> i32 { x - 1 };
+    apply(f); //~ ERROR closure contracts through trait objects are not yet supported
+}
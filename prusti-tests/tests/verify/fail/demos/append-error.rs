@@ -11,7 +11,7 @@ impl List {
     fn len(&self) -> usize {
         match self.next {
             None => 1,
-            Some(box ref tail) => tail.len() + 1
+            Some(box ref tail) => tail.len() + 1 //~ WARNING has no termination measure
         }
     }
 }
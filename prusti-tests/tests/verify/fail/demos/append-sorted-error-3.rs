@@ -12,7 +12,7 @@ impl List {
     fn len(&self) -> usize {
         match self.next {
             None => 1,
-            Some(box ref tail) => tail.len() + 1
+            Some(box ref tail) => tail.len() + 1 //~ WARNING has no termination measure
         }
     }
 
@@ -23,7 +23,7 @@ impl List {
             self.val
         } else {
             if let Some(box ref tail) = self.next {
-                tail.get(index - 1)
+                tail.get(index - 1) //~ WARNING has no termination measure
             } else {
                 unreachable!()
             }
@@ -33,7 +33,7 @@ impl List {
     #[pure]
     fn sorted(&self) -> bool {
         if let Some(box ref tail) = self.next {
-            self.val <= tail.val && tail.sorted()
+            self.val <= tail.val && tail.sorted() //~ WARNING has no termination measure
         } else {
             true
         }
@@ -0,0 +1,34 @@
+use prusti_contracts::*;
+
+#[derive(Clone, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Manual {
+    value: i32,
+}
+
+impl Clone for Manual {
+    fn clone(&self) -> Self {
+        Manual { value: 0 }
+    }
+}
+
+#[ensures(result.x == point.y)] //~ ERROR postcondition might not hold.
+fn cloned(point: &Point) -> Point {
+    point.clone()
+}
+
+// Only derived implementations get a built-in contract.
+#[ensures(result.value == manual.value)] //~ ERROR postcondition might not hold.
+fn manual_clone(manual: &Manual) -> Manual {
+    manual.clone()
+}
+
+fn main() {
+    let a = Point { x: 1, y: 2 };
+    let b = Point { x: 2, y: 1 };
+    assert!(a == b); //~ ERROR the asserted expression might not hold
+}
@@ -0,0 +1,34 @@
+use prusti_contracts::*;
+
+fn average(total: u32, count: u32) -> u32 {
+    total / count //~ ERROR the divisor `count` might be zero
+}
+
+fn remainder(a: u32, b: u32) -> u32 {
+    a % b //~ ERROR the divisor `b` might be zero
+}
+
+fn reduce(a: &mut u64, b: u64) {
+    *a %= b; //~ ERROR the divisor `b` might be zero
+}
+
+#[requires(b != 0)]
+fn signed_division(a: i32, b: i32) -> i32 {
+    a / b //~ ERROR `a` might be the minimum value of its type while the divisor `b` is -1
+}
+
+#[requires(b != 0)]
+fn signed_remainder(a: i32, b: i32) -> i32 {
+    a % b //~ ERROR `a` might be the minimum value of its type while the divisor `b` is -1
+}
+
+#[requires(b != 0 && (a != i32::MIN || b != -1))]
+fn checked_remainder(a: i32, b: i32) -> i32 {
+    a % b
+}
+
+fn min_remainder_minus_one() -> i32 {
+    checked_remainder(i32::MIN, -1) //~ ERROR precondition might not hold
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use prusti_contracts::*;
+
+// Functions of other crates can only be used in specifications through an
+// `#[extern_spec]` marked as `#[pure]`.
+#[requires(a.saturating_add(b) > 0)]
+//~^ ERROR saturating_add" in pure code is not allowed
+fn client(a: u32, b: u32) {}
+
+fn main() {}
@@ -0,0 +1,25 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[ensures(result.x == p.y && result.y == p.x)]
+fn swap(p: Point) -> Point {
+    Point { x: p.y, y: p.x }
+}
+
+fn test_assert_eq(a: i32, b: i32) {
+    let p = Point { x: a, y: b };
+    assert_eq!(swap(p), p); //~ ERROR the asserted expression might not hold
+}
+
+fn test_panic_message(a: i32) {
+    if a == 0 {
+        panic!("a is zero"); //~ ERROR panic!(..) statement might be reachable
+    }
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+pub enum List {
+    Nil,
+    Cons(i32, Box<List>),
+}
+
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[ensures(result === *p)] //~ ERROR postcondition might not hold
+fn swap_point(p: &Point) -> Point {
+    Point { x: p.y, y: p.x }
+}
+
+#[ensures(old(list) !== result)] //~ ERROR postcondition might not hold
+fn move_list(list: List) -> List {
+    list
+}
+
+#[requires(a === b)]
+fn same<T>(a: &T, b: &T) {}
+
+fn main() {
+    let a = List::Nil;
+    let b = List::Cons(1, Box::new(List::Nil));
+    same(&a, &b); //~ ERROR precondition might not hold
+}
@@ -0,0 +1,28 @@
+extern crate prusti_contracts;
+use prusti_contracts::*;
+
+pub trait Trait {
+    fn foo(&self) -> i32 { 3 }
+}
+
+pub struct A {}
+
+impl Trait for A {}
+
+pub struct B {}
+
+impl Trait for B {}
+
+#[extern_spec]
+impl A {
+    #[ensures(result == 3)]
+    fn foo(&self) -> i32;
+}
+
+#[extern_spec]
+impl B {
+    #[ensures(result == 3)] //~ ERROR conflicting specifications
+    fn foo(&self) -> i32;
+}
+
+fn main() {}
@@ -0,0 +1,40 @@
+extern crate prusti_contracts;
+use prusti_contracts::*;
+
+struct TestStruct {}
+
+impl TestStruct {
+    #[ensures(*a == old(*b) && *b == old(*a))]
+    fn swap(a: &mut i32, b: &mut i32) {
+        std::mem::swap(a, b);
+    }
+}
+
+#[extern_spec]
+impl TestStruct {
+    #[ensures(*a == old(*b) && *b == old(*a))]
+    fn swap(a: &mut i32, b: &mut i32); //~ ERROR already has a specification
+}
+
+#[extern_spec]
+impl<T> std::vec::Vec<T> {
+    #[pure]
+    fn len(&self) -> usize;
+
+    #[ensures(self.len() == 0)]
+    fn clear(&mut self);
+}
+
+// The collection continues after the first error, so this one is reported too.
+#[extern_spec]
+impl<T> std::vec::Vec<T> {
+    #[ensures(self.len() == 0)] //~ ERROR duplicate specification
+    fn clear(&mut self);
+}
+
+fn main() {
+    let mut x = 5;
+    let mut y = 42;
+    TestStruct::swap(&mut x, &mut y);
+    assert!(x == 42);
+}
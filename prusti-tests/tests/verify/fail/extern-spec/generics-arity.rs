@@ -0,0 +1,14 @@
+extern crate prusti_contracts;
+use prusti_contracts::*;
+
+#[extern_spec]
+mod std {
+    mod mem {
+        use prusti_contracts::*;
+
+        #[ensures(result == old(*dest))]
+        pub fn replace<T: PartialEq, U>(dest: &mut T, src: T) -> T; //~ ERROR this function takes 1 generic argument but 2 generic arguments were supplied
+    }
+}
+
+fn main() {}
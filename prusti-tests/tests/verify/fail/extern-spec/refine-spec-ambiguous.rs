@@ -0,0 +1,32 @@
+use prusti_contracts::*;
+
+pub struct Pair<T, U> {
+    first: T,
+    second: U,
+}
+
+impl<T, U> Pair<T, U> {
+    #[trusted]
+    pub fn has_byte(&self) -> bool {
+        std::mem::size_of::<T>() == 1 || std::mem::size_of::<U>() == 1
+    }
+}
+
+#[refine_spec(where T = u8)]
+impl<T, U> Pair<T, U> {
+    #[ensures(result)]
+    fn has_byte(&self) -> bool;
+}
+
+#[refine_spec(where U = u8)]
+impl<T, U> Pair<T, U> {
+    #[ensures(result)]
+    fn has_byte(&self) -> bool;
+}
+
+fn main() {
+    let first = Pair { first: 1u8, second: 2u32 };
+    assert!(first.has_byte());
+    let bytes = Pair { first: 1u8, second: 2u8 };
+    bytes.has_byte(); //~ ERROR ambiguous refinements
+}
@@ -0,0 +1,37 @@
+extern crate prusti_contracts;
+use prusti_contracts::*;
+
+pub trait Max {
+    fn max(&mut self) -> i32;
+}
+
+pub struct Point(pub i32, pub i32);
+
+impl Max for Point {
+    fn max(&mut self) -> i32 {
+        if self.0 > self.1 { self.0 } else { self.1 }
+    }
+}
+
+pub struct OtherPoint(pub i32, pub i32);
+
+impl Max for OtherPoint {
+    fn max(&mut self) -> i32 {
+        if self.0 > self.1 { self.0 } else { self.1 }
+    }
+}
+
+#[extern_spec]
+impl Max for Point {
+    #[ensures(result >= self.0 && result >= self.1)]
+    fn max(&mut self) -> i32;
+}
+
+fn main() {
+    let mut p = Point(3, 2);
+    assert!(p.max() >= 3);
+
+    // The specification only applies to the implementation for `Point`.
+    let mut o = OtherPoint(3, 2);
+    assert!(o.max() >= 3); //~ ERROR the asserted expression might not hold
+}
@@ -0,0 +1,35 @@
+extern crate prusti_contracts;
+use prusti_contracts::*;
+
+#[extern_spec]
+impl<T> std::option::Option<T> {
+    #[pure]
+    pub fn is_some(&self) -> bool;
+}
+
+#[extern_spec]
+impl<T, E> std::result::Result<T, E> {
+    #[pure]
+    pub fn is_err(&self) -> bool;
+}
+
+fn if_let_unchecked(x: Option<u32>) -> u32 {
+    if let Some(v) = x {
+        v
+    } else {
+        unreachable!() //~ ERROR unreachable!(..) statement might be reachable
+    }
+}
+
+#[requires(r.is_err())]
+fn match_wrong_branch(r: Result<u32, bool>) -> u32 {
+    match r {
+        Ok(v) => v,
+        Err(_) => unreachable!(), //~ ERROR unreachable!(..) statement might be reachable
+    }
+}
+
+fn main() {
+    let x: Option<u32> = None;
+    assert!(x.is_some()); //~ ERROR the asserted expression might not hold
+}
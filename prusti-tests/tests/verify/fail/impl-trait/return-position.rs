@@ -0,0 +1,17 @@
+use prusti_contracts::*;
+
+#[derive(Clone, PartialEq)]
+struct Counter {
+    value: u32,
+}
+
+fn make_counter(value: u32) -> impl Clone + PartialEq {
+    Counter { value }
+}
+
+fn main() {
+    let first = make_counter(3);
+    let second = make_counter(3);
+    // Without a postcondition, the callers know nothing about the value.
+    assert!(first == second); //~ ERROR the asserted expression might not hold
+}
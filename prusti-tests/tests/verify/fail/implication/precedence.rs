@@ -0,0 +1,25 @@
+use prusti_contracts::*;
+
+/// `a ==> b && c` is `a ==> (b && c)`: the second conjunct is not required
+/// when `a` does not hold.
+#[requires(positive ==> x > 0 && x < 100)]
+fn conditional_bound(positive: bool, x: i32) {
+    assert!(x < 100); //~ ERROR the asserted expression might not hold
+}
+
+/// `a ==> b ==> c` is not `(a ==> b) ==> c`.
+#[requires(a ==> b ==> x > 0)]
+#[ensures((a ==> b) ==> x > 0)] //~ ERROR postcondition might not hold.
+fn not_left_associative(a: bool, b: bool, x: i32) {}
+
+#[requires(a ==> b ==> x > 0)]
+fn right_associative(a: bool, b: bool, x: i32) {
+    assert!(x > 0); //~ ERROR the asserted expression might not hold
+}
+
+#[ensures(result <==> x > 0)] //~ ERROR postcondition might not hold.
+fn not_strictly_positive(x: i32) -> bool {
+    x >= 0
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(result < s.len())] //~ ERROR postcondition might not hold
+fn find(s: &[i32], x: i32) -> usize {
+    let mut k = 0;
+    let index = loop {
+        body_invariant!(k <= s.len());
+        if k == s.len() {
+            break k;
+        }
+        if s[k] == x {
+            break k;
+        }
+        k += 1;
+    };
+    index
+}
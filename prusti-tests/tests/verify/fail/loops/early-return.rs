@@ -0,0 +1,32 @@
+use prusti_contracts::*;
+
+#[requires(0 <= n && n <= 100)]
+#[ensures(result >= 0)] //~ ERROR postcondition might not hold
+fn find_pair(n: i32, target: i32) -> i32 {
+    let mut i = 0;
+    while i < n {
+        body_invariant!(0 <= i && i < n);
+        let mut j = 0;
+        while j < n {
+            body_invariant!(0 <= i && i < n && 0 <= j && j < n);
+            if i + j == target {
+                return i;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    -1
+}
+
+#[ensures(result)] //~ ERROR postcondition might not hold
+fn check(value: Option<u32>) -> bool {
+    match value {
+        Some(0) => return false,
+        Some(_) => {}
+        None => return true,
+    }
+    true
+}
+
+fn main() {}
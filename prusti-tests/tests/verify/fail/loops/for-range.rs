@@ -0,0 +1,32 @@
+use prusti_contracts::*;
+
+fn wrong_bound(n: usize) {
+    for i in 0..n {
+        body_invariant!(i + 1 < n); //~ ERROR loop invariant might not hold
+    }
+}
+
+#[ensures(result == n)] //~ ERROR postcondition might not hold
+fn wrong_count(n: usize) -> usize {
+    let mut count = 0;
+    for i in 0..n {
+        body_invariant!(count <= i);
+        count += 1;
+    }
+    count
+}
+
+fn wrong_last(n: u32) {
+    let mut last = 0;
+    for i in 0..=n {
+        last = i;
+    }
+    assert!(last > n); //~ ERROR the asserted expression might not hold
+}
+
+fn zero_step(n: u32) {
+    for _ in (0..n).step_by(0) { //~ ERROR the asserted expression might not hold
+    }
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+use prusti_contracts::*;
+
+#[requires(n < 1000)]
+fn count(n: u32) -> u32 {
+    let mut i = 0;
+    ghost! {
+        let mut steps: u32 = 0;
+    }
+    while i < n {
+        body_invariant!(steps == i + 1); //~ ERROR loop invariant might not hold
+        i += 1;
+        ghost! {
+            steps += 1;
+        }
+    }
+    i
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+use prusti_contracts::*;
+
+fn spin(mut i: u32) {
+    while i < 10 {
+        body_variant!(i); //~ ERROR loop variant might be negative or might not decrease
+        i += 1;
+    }
+}
+
+fn reset(mut i: u32, mut j: u32) {
+    while i > 0 {
+        body_variant!(i); //~ ERROR loop variant might be negative or might not decrease
+        body_variant!(j);
+        if j > 0 {
+            j -= 1;
+        } else {
+            j = 10;
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,34 @@
+use prusti_contracts::*;
+
+struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[pure]
+    fn is_even(&self) -> bool {
+        self.value % 2 == 0
+    }
+
+    #[requires(self.is_even())]
+    #[after_expiry_if(before_expiry(*result) % 2 == 0, self.is_even())]
+    fn value_mut(&mut self) -> &mut u32 {
+        &mut self.value
+    }
+}
+
+#[requires(c.is_even())]
+#[ensures(c.is_even())]
+fn keep_even(c: &mut Counter) {
+    let v = c.value_mut();
+    *v = 4;
+}
+
+#[requires(c.is_even())]
+fn make_odd(c: &mut Counter) {
+    let v = c.value_mut();
+    *v = 3;
+    let _x = c.value; //~ ERROR obligation might not hold on borrow expiry
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[after_expiry(**x == before_expiry(*result))]
+fn get<'a>(x: &'a mut &mut i32) -> &'a mut i32 {
+    *x
+}
+
+fn write_through_get() {
+    let mut a = 1;
+    let mut b = &mut a;
+    let r = get(&mut b);
+    *r = 5;
+    assert!(a == 1); //~ ERROR the asserted expression might not hold
+}
@@ -0,0 +1,7 @@
+use std::collections::HashMap;
+
+fn main() {}
+
+fn iterate(m: &HashMap<u32, u32>) {
+    for _ in m.iter() {} //~ ERROR iterating over maps is not supported
+}
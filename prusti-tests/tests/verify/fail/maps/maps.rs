@@ -0,0 +1,16 @@
+use prusti_contracts::*;
+use std::collections::HashMap;
+
+fn main() {}
+
+fn overwrite() {
+    let mut m = HashMap::new();
+    m.insert(1, 10);
+    m.insert(1, 20);
+    assert!(m.len() == 2); //~ ERROR the asserted expression might not hold
+}
+
+#[ensures(m.contains_key(&k))] //~ ERROR postcondition might not hold
+fn other_key(m: &mut HashMap<u32, u32>, k: u32, v: u32) {
+    m.insert(k + 1, v);
+}
@@ -0,0 +1,21 @@
+use prusti_contracts::*;
+
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Point {
+    #[ensures(result.x == old(self.x))] //~ ERROR postcondition might not hold
+    pub fn reset(mut self) -> Point {
+        self = Point { x: 0, y: 0 };
+        self
+    }
+
+    #[ensures(result == old(self.x))] //~ ERROR postcondition might not hold
+    pub fn into_y(self) -> i32 {
+        self.y
+    }
+}
+
+fn main() {}
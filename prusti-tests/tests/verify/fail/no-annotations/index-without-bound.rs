@@ -0,0 +1,23 @@
+extern crate prusti_contracts;
+
+fn get(v: &Vec<i32>, i: usize) -> i32 {
+    v[i] //~ ERROR the array or slice index may be out of bounds
+}
+
+fn get_from_slice(s: &[u8], i: usize) -> u8 {
+    s[i] //~ ERROR the array or slice index may be out of bounds
+}
+
+fn get_checked_wrongly(s: &[u8], i: usize) -> u8 {
+    if i <= s.len() {
+        s[i] //~ ERROR the array or slice index may be out of bounds
+    } else {
+        0
+    }
+}
+
+fn unwrap_anything(x: Option<u8>) -> u8 {
+    x.unwrap() //~ ERROR precondition might not hold
+}
+
+fn main() {}
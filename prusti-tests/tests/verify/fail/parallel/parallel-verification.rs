@@ -0,0 +1,13 @@
+// compile-flags: -Pnum_parallel_verifiers=4
+
+// More procedures than verifier threads are verified concurrently. The errors
+// of each procedure are reported as soon as its verification finishes.
+
+fn test_0(x: u32) { assert!(x != 0); } //~ ERROR the asserted expression might not hold
+fn test_1(x: u32) { assert!(x != 1); } //~ ERROR the asserted expression might not hold
+fn test_2(x: u32) { assert!(x != 2); } //~ ERROR the asserted expression might not hold
+fn test_3(x: u32) { assert!(x != 3); } //~ ERROR the asserted expression might not hold
+fn test_4(x: u32) { assert!(x != 4); } //~ ERROR the asserted expression might not hold
+fn test_5(x: u32) { assert!(x != 5); } //~ ERROR the asserted expression might not hold
+
+fn main() {}
@@ -0,0 +1,44 @@
+use prusti_contracts::*;
+
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[ensures(p.x == old(p.x) + 1 && p.y == old(p.y))] //~ ERROR postcondition might not hold.
+fn shift_diagonally(p: &mut Point) {
+    p.x += 1;
+    p.y += 1;
+}
+
+/// `prusti_assert!` pinpoints the conjunct of the postcondition that does
+/// not hold.
+#[ensures(p.x == old(p.x) + 1 && p.y == old(p.y))]
+fn shift_diagonally_pinpointed(p: &mut Point) {
+    p.x += 1;
+    p.y += 1;
+    prusti_assert!(p.x == old(p.x) + 1);
+    prusti_assert!(p.y == old(p.y)); //~ ERROR the asserted expression of prusti_assert!(..) might not hold.
+}
+
+fn quantified(a: i32) {
+    prusti_assert!(forall(|x: i32| x + 1 > x));
+    prusti_assert!(forall(|x: i32| x + a > x)); //~ ERROR the asserted expression of prusti_assert!(..) might not hold.
+}
+
+fn in_loop(n: u32) {
+    let mut i = 0;
+    while i < n {
+        body_invariant!(i < n);
+        i += 1;
+        prusti_assert!(i < n); //~ ERROR the asserted expression of prusti_assert!(..) might not hold.
+    }
+}
+
+fn after_assumption(x: u32) {
+    prusti_assume!(x > 10); //~ WARNING is assumed without being checked
+    prusti_assert!(x > 5);
+    prusti_assert!(x > 20); //~ ERROR the asserted expression of prusti_assert!(..) might not hold.
+}
+
+fn main() {}
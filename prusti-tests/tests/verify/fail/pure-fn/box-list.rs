@@ -0,0 +1,34 @@
+use prusti_contracts::*;
+
+struct Node {
+    value: i32,
+    next: Option<Box<Node>>,
+}
+
+impl Node {
+    #[pure]
+    #[ensures(result > 0)]
+    fn len(&self) -> usize {
+        match &self.next {
+            None => 1,
+            Some(next) => 1 + next.len(), //~ WARNING has no termination measure
+        }
+    }
+
+    #[ensures(self.len() == old(self.len()))] //~ ERROR postcondition might not hold
+    fn append(&mut self, value: i32) {
+        if let Some(next) = &mut self.next {
+            next.append(value);
+        } else {
+            self.next = Some(Box::new(Node { value, next: None }));
+        }
+    }
+}
+
+fn unbox(boxed: Box<Node>) -> Node {
+    let node = *boxed;
+    assert!(node.len() == 1); //~ ERROR the asserted expression might not hold
+    node
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+use prusti_contracts::*;
+
+#[pure]
+#[decreases(n)]
+fn fib(n: u32) -> u32 {
+    if n <= 1 {
+        n
+    } else {
+        fib(n - 1) + fib(n) //~ ERROR termination measure might be negative or might not decrease
+    }
+}
+
+#[pure]
+#[decreases(n)]
+fn count_down(n: i32) -> i32 {
+    if n == 0 { 0 } else { count_down(n - 1) } //~ ERROR termination measure might be negative or might not decrease
+}
+
+#[pure]
+#[decreases(n)]
+fn is_even(n: u32) -> bool {
+    if n == 0 { true } else { is_odd(n) } //~ ERROR termination measure might be negative or might not decrease
+}
+
+#[pure]
+#[decreases(n)]
+fn is_odd(n: u32) -> bool {
+    if n == 0 { false } else { is_even(n - 1) }
+}
+
+fn main() {}
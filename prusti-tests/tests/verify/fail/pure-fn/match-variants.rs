@@ -0,0 +1,41 @@
+use prusti_contracts::*;
+
+enum State {
+    Idle,
+    Running(u32),
+}
+
+struct Machine {
+    state: State,
+}
+
+impl Machine {
+    #[pure]
+    fn is_running(&self) -> bool {
+        matches!(self.state, State::Running(_))
+    }
+
+    fn check_running(&self) {
+        match &self.state {
+            State::Running(_) => assert!(!self.is_running()), //~ ERROR the asserted expression might not hold
+            State::Idle => {}
+        }
+    }
+}
+
+#[pure]
+fn is_some_ok(x: &Option<Result<u32, u32>>) -> bool {
+    matches!(x, Some(Ok(_)))
+}
+
+fn nested(x: Option<Result<u32, u32>>) {
+    match x {
+        Some(ref inner) => match inner {
+            Ok(_) => {}
+            Err(_) => assert!(is_some_ok(&x)), //~ ERROR the asserted expression might not hold
+        },
+        None => {}
+    }
+}
+
+fn main() {}
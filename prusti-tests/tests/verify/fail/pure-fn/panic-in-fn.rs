@@ -22,7 +22,7 @@ fn lookup(head: &List, index: isize) -> u32 {
         head.value
     } else {
         match head.next {
-            Some(box ref tail) => lookup(tail, index - 1),
+            Some(box ref tail) => lookup(tail, index - 1), //~ WARNING has no termination measure
             None => diverging() //~ ERROR diverging
         }
     }
@@ -0,0 +1,20 @@
+use prusti_contracts::*;
+
+struct Pair(i32, i32);
+
+impl Pair {
+    #[pure]
+    fn fst(&self) -> &i32 {
+        &self.0
+    }
+
+    #[pure]
+    fn fst_mut(&mut self) -> &mut i32 { //~ ERROR pure functions cannot return mutable references
+        &mut self.0
+    }
+}
+
+#[ensures(*pair.fst() == 1)] //~ ERROR postcondition might not hold
+fn first(pair: &Pair) {}
+
+fn main() {}
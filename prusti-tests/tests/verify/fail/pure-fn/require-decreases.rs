@@ -0,0 +1,21 @@
+// compile-flags: -Prequire_decreases=true
+
+use prusti_contracts::*;
+
+#[pure]
+fn len(n: u32) -> u32 {
+    if n == 0 { 0 } else { 1 + len(n - 1) } //~ ERROR has no termination measure
+}
+
+#[pure]
+#[decreases(n)]
+fn ping(n: u32) -> u32 {
+    if n == 0 { 0 } else { pong(n - 1) } //~ ERROR has no termination measure
+}
+
+#[pure]
+fn pong(n: u32) -> u32 {
+    if n == 0 { 0 } else { ping(n - 1) } //~ ERROR has no termination measure
+}
+
+fn main() {}
@@ -17,7 +17,7 @@ fn lookup(head: &List, index: usize) -> u32 {
         head.value
     } else {
         match head.next {
-            Some(box ref tail) => lookup(tail, index - 1),
+            Some(box ref tail) => lookup(tail, index - 1), //~ WARNING has no termination measure
             None => unreachable!() //~ ERROR unreachable!(..) statement might be reachable
         }
     }
@@ -27,7 +27,7 @@ fn lookup(head: &List, index: usize) -> u32 {
 fn len(head: &List) -> usize {
     match head.next {
         None => 1,
-        Some(box ref tail) => 1 + len(tail)
+        Some(box ref tail) => 1 + len(tail) //~ WARNING has no termination measure
     }
 }
 
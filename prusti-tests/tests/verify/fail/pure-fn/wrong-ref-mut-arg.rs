@@ -19,7 +19,7 @@ fn lookup(head: &mut List, index: usize) -> u32 {
         head.value
     } else {
         match head.next {
-            Some(box ref mut tail) => lookup(tail, index - 1),
+            Some(box ref mut tail) => lookup(tail, index - 1), //~ WARNING has no termination measure
             None => unreachable!() //~ ERROR might be reachable
         }
     }
@@ -29,7 +29,7 @@ fn lookup(head: &mut List, index: usize) -> u32 {
 fn len(head: &mut List) -> usize {
     match head.next {
         None => 1,
-        Some(box ref mut tail) => 1 + len(tail)
+        Some(box ref mut tail) => 1 + len(tail) //~ WARNING has no termination measure
     }
 }
 
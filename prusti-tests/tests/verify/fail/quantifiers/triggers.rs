@@ -5,8 +5,8 @@ fn fib(n: isize) -> isize {
     if n <= 1 {
         1
     } else {
-        fib(n-1) + fib(n-2)
-    }
+        fib(n-1) + fib(n-2) //~ WARNING has no termination measure
+    } //~^ WARNING has no termination measure
 }
 
 pub fn test1() {
@@ -0,0 +1,29 @@
+use prusti_contracts::*;
+
+struct Pair {
+    first: u32,
+    second: u32,
+}
+
+impl Pair {
+    fn first(&self) -> &u32 {
+        &self.first
+    }
+
+    fn either(&self, left: bool) -> &u32 {
+        if left { &self.first } else { &self.second }
+    }
+}
+
+fn wrong_field(pair: &Pair) {
+    let first = pair.first();
+    assert!(*first == pair.second); //~ ERROR the asserted expression might not hold
+}
+
+fn branching_getter(pair: &Pair) {
+    // Only getters that always return the same place are framed.
+    let value = pair.either(true);
+    assert!(*value == pair.first); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+
+#[ensures(matches!(result, Some(x) if x > 0))] //~ ERROR postcondition might not hold
+fn not_positive() -> Option<i32> {
+    Some(0)
+}
+
+#[ensures(matches!(result, Ok(Some(_))))] //~ ERROR postcondition might not hold
+fn nested_none() -> Result<Option<u32>, ()> {
+    Ok(None)
+}
+
+#[requires(matches!(r, Ok(Some(x)) if x > 10))]
+fn take(r: Result<Option<u32>, ()>) {}
+
+fn main() {
+    take(Ok(Some(10))); //~ ERROR precondition might not hold
+}
@@ -0,0 +1,16 @@
+use prusti_contracts::*;
+
+#[trusted]
+#[pure]
+fn related(i: u32, j: i64) -> bool {
+    (i as i64) < j
+}
+
+#[requires(forall(|i: u32| exists(|j: i64| related(i, j))))]
+#[ensures(forall(|i: u32, j: i64| related(i, j)))] //~ ERROR postcondition might not hold
+fn forall_exists() {}
+
+#[ensures(forall(|x: u32| related(x, 1) ==> exists(|x: i64| related(1, x))))] //~ ERROR postcondition might not hold
+fn shadowing() {}
+
+fn main() {}
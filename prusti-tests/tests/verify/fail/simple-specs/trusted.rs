@@ -0,0 +1,51 @@
+use prusti_contracts::*;
+
+/// The body does not satisfy the postcondition, but trusted procedures are
+/// not verified.
+#[trusted]
+#[requires(x >= 0)]
+#[ensures(result == x + 1)]
+fn increment(x: i32) -> i32 {
+    x + 2
+}
+
+/// A trusted procedure without a postcondition returns an arbitrary value.
+#[trusted]
+fn unknown() -> i32 {
+    1
+}
+
+#[trusted]
+#[pure]
+fn unknown_pure() -> i32 {
+    1
+}
+
+fn test_contract() {
+    let x = increment(1);
+    assert!(x == 2);
+    increment(-1); //~ ERROR precondition might not hold
+}
+
+fn test_unknown_result() {
+    let x = unknown();
+    assert!(x == 1); //~ ERROR the asserted expression might not hold
+}
+
+fn test_unknown_pure_result() {
+    assert!(unknown_pure() == 1); //~ ERROR the asserted expression might not hold
+}
+
+fn test_different_results() {
+    let x = unknown();
+    let y = unknown();
+    assert!(x == y); //~ ERROR the asserted expression might not hold
+}
+
+fn test_no_false() {
+    let _x = unknown();
+    let _y = unknown_pure();
+    assert!(1 == 2); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
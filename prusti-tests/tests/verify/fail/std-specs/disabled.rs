@@ -0,0 +1,11 @@
+// compile-flags: -Pstd_specs=false
+
+use prusti_contracts::*;
+
+fn main() {}
+
+fn unwrap_some() {
+    let x = Some(5);
+    let y = x.unwrap();
+    assert!(y == 5); //~ ERROR the asserted expression might not hold
+}
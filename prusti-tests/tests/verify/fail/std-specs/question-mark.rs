@@ -0,0 +1,23 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    TooLarge,
+}
+
+#[ensures(result.is_ok() == (n <= 100))]
+fn check(n: u32) -> Result<u32, Error> {
+    if n > 100 {
+        Err(Error::TooLarge)
+    } else {
+        Ok(n)
+    }
+}
+
+#[ensures(result.is_ok())] //~ ERROR postcondition might not hold
+fn double(n: u32) -> Result<u32, Error> {
+    let m = check(n)?;
+    Ok(2 * m)
+}
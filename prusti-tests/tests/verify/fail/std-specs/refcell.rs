@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+use std::cell::RefCell;
+
+fn conflicting_borrows(cell: &mut RefCell<u32>) {
+    let first = cell.borrow_mut();
+    let second = cell.borrow(); //~ ERROR the `RefCell` might already be borrowed
+}
+
+fn borrow_behind_shared_reference(cell: &RefCell<u32>) {
+    let guard = cell.borrow(); //~ ERROR the `RefCell` might already be borrowed
+}
+
+fn wrong_content(cell: &mut RefCell<u32>) {
+    cell.replace(7);
+    assert!(*cell.borrow() == 8); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
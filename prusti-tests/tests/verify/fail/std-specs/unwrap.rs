@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[derive(Debug)]
+pub enum Error {
+    TooLarge,
+}
+
+#[ensures(result.is_ok() == (n <= 100))]
+fn check(n: u32) -> Result<u32, Error> {
+    if n > 100 {
+        Err(Error::TooLarge)
+    } else {
+        Ok(n)
+    }
+}
+
+fn unwrap_unknown(x: Option<i32>) -> i32 {
+    x.unwrap() //~ ERROR precondition might not hold.
+}
+
+fn unwrap_unchecked(n: u32) -> u32 {
+    check(n).unwrap() //~ ERROR precondition might not hold.
+}
+
+fn wrong_default() {
+    let x: Option<i32> = None;
+    assert!(x.unwrap_or(3) == 4); //~ ERROR the asserted expression might not hold
+}
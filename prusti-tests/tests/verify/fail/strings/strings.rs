@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(result.len() == a.len() + b.len() + 1)] //~ ERROR postcondition might not hold.
+fn concat(a: &str, b: &str) -> String {
+    let mut result = String::new();
+    result.push_str(a);
+    result.push_str(b);
+    result
+}
+
+// Indexing is byte-based, and `é` takes two bytes.
+#[ensures(result.len() == a.len() + 1)] //~ ERROR postcondition might not hold.
+fn accent(a: &str) -> String {
+    let mut result = String::new();
+    result.push_str(a);
+    result.push('é');
+    result
+}
+
+fn first_byte(s: &str) -> u8 {
+    s.as_bytes()[0] //~ ERROR the array or slice index may be out of bounds
+}
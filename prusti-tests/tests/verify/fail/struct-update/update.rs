@@ -0,0 +1,35 @@
+use prusti_contracts::*;
+
+#[derive(Default)]
+struct Config {
+    timeout: u32,
+    retries: u32,
+}
+
+struct Manual {
+    value: u32,
+}
+
+impl Default for Manual {
+    fn default() -> Self {
+        Manual { value: 7 }
+    }
+}
+
+#[ensures(result.retries == 1)] //~ ERROR postcondition might not hold.
+fn default_retries() -> Config {
+    Config { timeout: 5, ..Default::default() }
+}
+
+#[ensures(result.retries == old_config.timeout)] //~ ERROR postcondition might not hold.
+fn wrong_field(old_config: Config) -> Config {
+    Config { timeout: 5, ..old_config }
+}
+
+// Only derived implementations are modeled precisely.
+#[ensures(result.value == 7)] //~ ERROR postcondition might not hold.
+fn manual_default() -> Manual {
+    Manual::default()
+}
+
+fn main() {}
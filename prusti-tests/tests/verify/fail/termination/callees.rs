@@ -0,0 +1,45 @@
+#![allow(unused_variables)]
+
+use prusti_contracts::*;
+
+trait Step {
+    fn step(&self, n: u32) -> u32;
+}
+
+struct Spinning;
+
+impl Step for Spinning {
+    fn step(&self, n: u32) -> u32 {
+        loop {}
+    }
+}
+
+// The call of the trait method is checked against the implementation.
+#[terminates]
+fn step_once(s: &Spinning, n: u32) -> u32 {
+    s.step(n) //~ ERROR which is not marked as terminating
+}
+
+struct Counting;
+
+impl Step for Counting {
+    #[terminates]
+    fn step(&self, n: u32) -> u32 {
+        count(self, n) //~ ERROR has no termination measure
+    }
+}
+
+// The recursion through the implementation of the trait method is part of a
+// cycle of the call graph.
+#[terminates]
+fn count(c: &Counting, n: u32) -> u32 {
+    if n == 0 { 0 } else { c.step(n - 1) } //~ ERROR has no termination measure
+}
+
+// A procedure of another crate is not known to terminate.
+#[terminates]
+fn exchange(a: &mut i32, b: &mut i32) {
+    std::mem::swap(a, b); //~ ERROR calls 'std::mem::swap', which is not marked as terminating
+}
+
+fn main() {}
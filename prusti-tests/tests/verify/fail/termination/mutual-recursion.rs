@@ -0,0 +1,36 @@
+use prusti_contracts::*;
+
+#[terminates]
+#[ensures(result == (n % 2 == 0))]
+fn is_even(n: u32) -> bool {
+    if n == 0 { true } else { is_odd(n - 1) } //~ ERROR has no termination measure
+}
+
+#[terminates]
+#[ensures(result == (n % 2 == 1))]
+fn is_odd(n: u32) -> bool {
+    if n == 0 { false } else { is_even(n - 1) } //~ ERROR has no termination measure
+}
+
+#[terminates]
+#[decreases(n)]
+fn ping(n: u32) -> u32 {
+    if n == 0 { 0 } else { pong(n) } //~ ERROR termination measure might be negative or might not decrease
+}
+
+#[terminates]
+#[decreases(n)]
+fn pong(n: u32) -> u32 {
+    if n == 0 { 0 } else { ping(n - 1) }
+}
+
+fn spin() {
+    loop {}
+}
+
+#[terminates]
+fn caller() {
+    spin(); //~ ERROR calls 'spin', which is not marked as terminating
+}
+
+fn main() {}
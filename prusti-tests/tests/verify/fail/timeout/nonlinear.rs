@@ -0,0 +1,16 @@
+// compile-flags: -Pprocedure_timeout_ms=1
+
+// With a limit of 1 ms, the verification of every procedure is abandoned
+// before the backend answers; the nonlinear assertion would take much longer.
+
+use prusti_contracts::*;
+
+#[requires(1 < a && a < 1000 && 1 < b && b < 1000 && 1 < c && c < 1000)]
+fn hard(a: i64, b: i64, c: i64) -> i64 { //~ ERROR did not finish within 1 ms
+    let x = a * a * b * c + b * b * c * a + c * c * a * b;
+    assert!(x != 3 * a * b * c * 997);
+    x
+}
+
+fn main() { //~ ERROR did not finish within 1 ms
+}
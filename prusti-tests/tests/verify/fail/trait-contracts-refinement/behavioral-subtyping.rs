@@ -0,0 +1,28 @@
+use prusti_contracts::*;
+
+trait Foo {
+    #[requires(x > 0)]
+    #[ensures(result > 0)]
+    fn foo(&self, x: i32) -> i32;
+
+    #[requires(x > 0)]
+    #[ensures(result > x)]
+    fn bar(&self, x: i32) -> i32;
+}
+
+struct Dummy;
+
+#[refine_trait_spec]
+impl Foo for Dummy {
+    #[requires(x > 10)] //~ ERROR the method's precondition may not be a valid weakening of the trait's precondition
+    fn foo(&self, x: i32) -> i32 {
+        x
+    }
+
+    #[ensures(result == x)] //~ ERROR the method's postcondition may not be a valid strengthening of the trait's postcondition
+    fn bar(&self, x: i32) -> i32 {
+        x
+    }
+}
+
+fn main() {}
@@ -0,0 +1,47 @@
+use prusti_contracts::*;
+
+trait Container {
+    #[pure]
+    fn value(&self) -> i32;
+
+    #[after_expiry(self.value() == before_expiry(*result))] //~ ERROR pledge
+    fn get_mut(&mut self) -> &mut i32;
+}
+
+struct Correct {
+    value: i32,
+    other: i32,
+}
+
+#[refine_trait_spec]
+impl Container for Correct {
+    #[pure]
+    fn value(&self) -> i32 {
+        self.value
+    }
+
+    #[after_expiry(self.value() == before_expiry(*result) && self.other == old(self.other))]
+    fn get_mut(&mut self) -> &mut i32 {
+        &mut self.value
+    }
+}
+
+struct Violating {
+    value: i32,
+    other: i32,
+}
+
+#[refine_trait_spec]
+impl Container for Violating {
+    #[pure]
+    fn value(&self) -> i32 {
+        self.value
+    }
+
+    #[after_expiry(self.other == before_expiry(*result))]
+    fn get_mut(&mut self) -> &mut i32 {
+        &mut self.other
+    }
+}
+
+fn main() {}
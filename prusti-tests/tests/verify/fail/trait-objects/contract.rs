@@ -0,0 +1,19 @@
+use prusti_contracts::*;
+
+trait Shape {
+    #[pure]
+    fn area(&self) -> u32;
+}
+
+#[requires(shape.area() <= 1000)]
+#[ensures(result == shape.area() * 3)] //~ ERROR postcondition might not hold
+fn double_area(shape: &dyn Shape) -> u32 {
+    shape.area() * 2
+}
+
+#[requires(shape.area() <= 1000)]
+fn area_is_unknown(shape: &dyn Shape) {
+    assert!(shape.area() > 0); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+use prusti_contracts::*;
+
+#[invariant(self.len <= self.capacity)]
+struct Buffer {
+    len: usize,
+    capacity: usize,
+}
+
+impl Buffer {
+    fn push(&mut self) { //~ ERROR implicit type invariants might not hold at the end of the method
+        self.len += 1;
+    }
+
+    fn check(&self) {
+        assert!(self.len < self.capacity); //~ ERROR the asserted expression might not hold
+    }
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+union Payload {
+    int: u32,
+    point: Point,
+}
+
+// The bytes of the point are not known to the verifier.
+fn read_after_overwrite() -> u32 {
+    let mut payload = Payload { int: 5 };
+    payload.point = Point { x: 3, y: 4 };
+    let value = unsafe { payload.int };
+    assert!(value == 5); //~ ERROR the asserted expression might not hold
+    value
+}
+
+#[ensures(result == 5)] //~ ERROR postcondition might not hold
+fn read_unknown(payload: Payload) -> u32 {
+    unsafe { payload.int }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+// compile-flags: -Pstrict_union_reads=true
+
+union Number {
+    unsigned: u32,
+    signed: i32,
+}
+
+fn main() {
+    let mut number = Number { unsigned: 5 };
+    number.signed = -1;
+    let _unsigned = unsafe { number.unsigned }; //~ ERROR the field `unsigned` of the union is read, but the field `signed` was written last
+}
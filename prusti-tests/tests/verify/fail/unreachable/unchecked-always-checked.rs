@@ -0,0 +1,13 @@
+// compile-flags: -Pcheck_unreachable=false
+
+// Reaching `unreachable_unchecked()` is undefined behavior, so it is checked
+// even when `unreachable!()` is assumed.
+
+fn half(n: u32) -> u32 {
+    if n % 2 == 1 {
+        unsafe { std::hint::unreachable_unchecked() } //~ ERROR unreachable_unchecked() might be reachable
+    }
+    n / 2
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use prusti_contracts::*;
+
+fn name(n: u32) -> u32 {
+    match n {
+        0 => 10,
+        1 => 20,
+        2 => 30,
+        _ => unreachable!(), //~ ERROR unreachable!(..) statement might be reachable
+    }
+}
+
+#[requires(n > 0)]
+fn half(n: u32) -> u32 {
+    if n % 2 == 1 {
+        unsafe { std::hint::unreachable_unchecked() } //~ ERROR unreachable_unchecked() might be reachable
+    }
+    n / 2
+}
+
+fn main() {}
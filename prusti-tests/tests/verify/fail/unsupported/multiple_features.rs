@@ -0,0 +1,9 @@
+use std::fmt::Debug;
+
+fn inspect(x: &i32) -> bool {
+    let p = x as *const i32; //~ ERROR the function `inspect` uses features that are not supported
+    let _d: &dyn Debug = x;
+    p.is_null()
+}
+
+fn main() {}
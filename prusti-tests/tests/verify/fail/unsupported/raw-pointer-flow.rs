@@ -0,0 +1,24 @@
+fn arithmetic(x: &i32) -> i32 {
+    unsafe { *(x as *const i32).add(1) } //~ ERROR pointer arithmetic is not supported
+}
+
+fn to_integer(x: &i32) -> usize {
+    (x as *const i32) as usize //~ ERROR casting raw pointers to integers is not supported
+}
+
+fn read(p: *const i32) -> i32 {
+    unsafe { *p } //~ ERROR raw pointers received from the caller are not supported
+}
+
+fn escape(x: &i32) -> *const i32 {
+    let p = x as *const i32;
+    p //~ ERROR returning raw pointers is not supported
+}
+
+fn reassigned(x: &i32, y: &i32) -> i32 {
+    let mut p = x as *const i32; //~ ERROR raw pointers are not supported unless they are assigned once
+    p = y as *const i32;
+    unsafe { *p }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use std::rc::Rc;
+
+struct Config {
+    level: u32,
+}
+
+fn reset(config: &mut Rc<Config>) {
+    if let Some(inner) = Rc::get_mut(config) { //~ ERROR mutable access to the content of an `Rc` or `Arc` is not supported
+        inner.level = 0;
+    }
+}
+
+fn main() {}
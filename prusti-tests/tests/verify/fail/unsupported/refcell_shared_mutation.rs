@@ -0,0 +1,7 @@
+use std::cell::RefCell;
+
+fn reset(cell: &RefCell<u32>) {
+    *cell.borrow_mut() = 0; //~ ERROR modifying the content of a `RefCell` behind a shared reference is not supported
+}
+
+fn main() {}
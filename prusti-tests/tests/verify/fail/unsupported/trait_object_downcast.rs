@@ -0,0 +1,10 @@
+use std::any::Any;
+
+fn as_number(value: &dyn Any) -> u32 {
+    match value.downcast_ref::<u32>() { //~ ERROR downcasting trait objects is not supported
+        Some(number) => *number,
+        None => 0,
+    }
+}
+
+fn main() {}
@@ -4,5 +4,7 @@ union Foo {
 }
 
 fn main() {
-    let _ = Foo { a: [0] }; //~ ERROR unions are not supported
-}
\ No newline at end of file
+    let mut foo = Foo { a: [0] };
+    let a = unsafe { &mut foo.a }; //~ ERROR mutable references to fields of unions are not supported
+    a[0] = 1;
+}
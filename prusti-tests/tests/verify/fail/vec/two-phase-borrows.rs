@@ -0,0 +1,9 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[requires(v.len() < usize::MAX)]
+#[ensures(v[old(v.len())] == 0)] //~ ERROR postcondition might not hold
+fn push_len(v: &mut Vec<usize>) {
+    v.push(v.len());
+}
@@ -0,0 +1,19 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+fn first(v: &Vec<i32>) -> i32 {
+    v[0] //~ ERROR the array or slice index may be out of bounds
+}
+
+fn pop_shrinks() {
+    let mut v = Vec::new();
+    v.push(1);
+    v.pop();
+    assert!(v.len() == 1); //~ ERROR the asserted expression might not hold
+}
+
+#[ensures(v[0] == x)] //~ ERROR postcondition might not hold
+fn push_appends(v: &mut Vec<i32>, x: i32) {
+    v.push(x);
+}
@@ -0,0 +1,56 @@
+// compile-flags: -Pverify_only=parser::*::parse_*,lexer::**::advance,main
+use prusti_contracts::*;
+
+mod parser {
+    pub mod digits {
+        use prusti_contracts::*;
+
+        #[ensures(result >= 0)] //~ ERROR postcondition might not hold
+        pub fn parse_digit(value: i32) -> i32 {
+            value
+        }
+
+        // Not verified, because the name does not match `parse_*`.
+        #[requires(value > 0)]
+        #[ensures(result >= 0)]
+        pub fn negate(value: i32) -> i32 {
+            -value
+        }
+    }
+}
+
+mod lexer {
+    use prusti_contracts::*;
+
+    pub struct Lexer {
+        pub position: usize,
+    }
+
+    impl Lexer {
+        #[requires(self.position < 100)]
+        #[ensures(self.position == old(self.position) + 2)] //~ ERROR postcondition might not hold
+        pub fn advance(&mut self) {
+            self.position += 1;
+        }
+
+        // Not verified, because the pattern only selects `advance`.
+        #[ensures(self.position == 0)]
+        pub fn rewind(&mut self) {}
+    }
+}
+
+// Not verified, because it is outside of the selected modules.
+#[ensures(result)]
+fn outside() -> bool {
+    false
+}
+
+fn main() {
+    // The contracts of the functions that are not verified are still used.
+    let value = parser::digits::negate(3);
+    assert!(value >= 0);
+    let mut cursor = lexer::Lexer { position: 7 };
+    cursor.rewind();
+    assert!(cursor.position == 0);
+    assert!(outside());
+}
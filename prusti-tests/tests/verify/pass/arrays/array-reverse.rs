@@ -0,0 +1,26 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(forall(|i: usize| (0 <= i && i < 16) ==> a[i] == old(a[15 - i])))]
+fn reverse(a: &mut [u8; 16]) {
+    let mut i = 0;
+    while i < 8 {
+        body_invariant!(0 <= i && i < 8);
+        body_invariant!(forall(|j: usize| (0 <= j && j < i) ==> a[j] == old(a[15 - j])));
+        body_invariant!(forall(|j: usize| (0 <= j && j < i) ==> a[15 - j] == old(a[j])));
+        body_invariant!(forall(|j: usize| (i <= j && j < 16 - i) ==> a[j] == old(a[j])));
+        let tmp = a[i];
+        a[i] = a[15 - i];
+        a[15 - i] = tmp;
+        i += 1;
+    }
+}
+
+fn client() {
+    let mut a = [0u8; 16];
+    a[0] = 1;
+    a[15] = 2;
+    reverse(&mut a);
+    assert!(a[0] == 2 && a[15] == 1);
+}
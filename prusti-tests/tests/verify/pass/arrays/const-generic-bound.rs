@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[requires(N < s.len())]
+#[ensures(result == s[N])]
+fn nth<const N: usize>(s: &[u8]) -> u8 {
+    s[N]
+}
+
+#[requires(N <= s.len())]
+#[ensures(s.len() == old(s.len()))]
+#[ensures(forall(|i: usize| (0 <= i && i < N) ==> s[i] == 0))]
+fn clear_prefix<const N: usize>(s: &mut [u8]) {
+    let mut k = 0;
+    while k < N {
+        body_invariant!(k < N && s.len() == old(s.len()));
+        body_invariant!(forall(|i: usize| (0 <= i && i < k) ==> s[i] == 0));
+        s[k] = 0;
+        k += 1;
+    }
+}
+
+#[requires(s.len() > 3)]
+fn client(s: &mut [u8]) {
+    let x = nth::<3>(s);
+    assert!(x == s[3]);
+    clear_prefix::<2>(s);
+    assert!(s[1] == 0);
+}
@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[requires(i < s.len())]
+#[ensures(s[i] == v)]
+#[ensures(forall(|j: usize| (0 <= j && j < s.len() && j != i) ==> s[j] == old(*s)[j]))]
+fn set(s: &mut [i32], i: usize, v: i32) {
+    s[i] = v;
+}
+
+#[ensures(a[0] == old(*a)[1] && a[1] == old(*a)[0])]
+#[ensures(a[2] == old(*a)[2])]
+fn swap_first(a: &mut [i32; 3]) {
+    let tmp = a[0];
+    a[0] = a[1];
+    a[1] = tmp;
+}
+
+#[requires(*x < 100)]
+#[ensures(*x == old(*x) + 1)]
+fn increment(x: &mut i32) {
+    *x += 1;
+}
@@ -0,0 +1,19 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(result.len() == 3)]
+#[ensures(result[0] == 1 && result[1] == 2 && result[2] == 3)]
+fn digits() -> &'static [i32] {
+    &[1, 2, 3]
+}
+
+fn read_promoted_array() {
+    let a: &[u8; 2] = &[4, 5];
+    assert!(a[0] + a[1] == 9);
+}
+
+fn read_promoted_scalar() {
+    let x: &i32 = &42;
+    assert!(*x == 42);
+}
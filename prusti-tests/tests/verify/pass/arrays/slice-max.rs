@@ -0,0 +1,21 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[requires(s.len() > 0)]
+#[ensures(forall(|i: usize| (0 <= i && i < s.len()) ==> s[i] <= result))]
+#[ensures(exists(|i: usize| (0 <= i && i < s.len()) && s[i] == result))]
+fn max(s: &[i32]) -> i32 {
+    let mut result = s[0];
+    let mut k = 1;
+    while k < s.len() {
+        body_invariant!(1 <= k && k < s.len());
+        body_invariant!(forall(|i: usize| (0 <= i && i < k) ==> s[i] <= result));
+        body_invariant!(exists(|i: usize| (0 <= i && i < k) && s[i] == result));
+        if s[k] > result {
+            result = s[k];
+        }
+        k += 1;
+    }
+    result
+}
@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[requires(i < s.len())]
+#[ensures(s.len() == old(s.len()))]
+#[ensures(s[i] == v)]
+#[ensures(forall(|j: usize| (0 <= j && j < s.len() && j != i) ==> s[j] == old(s[j])))]
+fn set(s: &mut [i32], i: usize, v: i32) {
+    s[i] = v;
+}
+
+#[requires(s.len() > 2)]
+#[ensures(s[0] == 1 && s[2] == 3)]
+#[ensures(s[1] == old(s[1]))]
+fn set_twice(s: &mut [i32]) {
+    s[0] = 1;
+    s[2] = 3;
+}
+
+#[ensures(forall(|j: usize| (0 <= j && j < s.len()) ==> s[j] == 0))]
+fn zero(s: &mut [i32]) {
+    let mut k = 0;
+    while k < s.len() {
+        body_invariant!(k < s.len());
+        body_invariant!(forall(|j: usize| (0 <= j && j < k) ==> s[j] == 0));
+        s[k] = 0;
+        k += 1;
+    }
+}
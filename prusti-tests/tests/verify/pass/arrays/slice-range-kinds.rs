@@ -0,0 +1,37 @@
+use prusti_contracts::*;
+
+fn main() {
+    let a = [1, 2, 3, 4, 5];
+    let s = &a[2..];
+    assert!(s.len() == 3 && s[0] == 3);
+    let t = &a[..=1];
+    assert!(t.len() == 2 && t[1] == 2);
+}
+
+#[requires(s.len() == 4)]
+fn range_kinds(s: &[i32]) {
+    let from = &s[1..];
+    assert!(from.len() == 3 && from[0] == s[1]);
+    let to = &s[..3];
+    assert!(to.len() == 3 && to[2] == s[2]);
+    let full = &s[..];
+    assert!(full.len() == 4 && full[3] == s[3]);
+    let inclusive = &s[1..=2];
+    assert!(inclusive.len() == 2 && inclusive[1] == s[2]);
+    let to_inclusive = &s[..=0];
+    assert!(to_inclusive.len() == 1 && to_inclusive[0] == s[0]);
+}
+
+#[pure]
+#[requires(s.len() > 0)]
+fn tail_len(s: &[i32]) -> usize {
+    s[1..].len()
+}
+
+#[requires(s.len() > 0)]
+#[ensures(s[1..].len() == s.len() - 1)]
+#[ensures(s[..].len() == s.len())]
+#[ensures(result == s.len() - 1)]
+fn tail_len_in_spec(s: &[i32]) -> usize {
+    tail_len(s)
+}
@@ -0,0 +1,15 @@
+use prusti_contracts::*;
+
+fn main() {
+    let a = [1, 2, 3, 4, 5];
+    let s = &a[1..4];
+    assert!(s.len() == 3);
+    assert!(s[0] == 2 && s[2] == 4);
+}
+
+#[requires(s.len() == 4)]
+fn inner_elements(s: &[i32]) {
+    let t = &s[1..3];
+    assert!(t.len() == 2);
+    assert!(t[0] == s[1] && t[1] == s[2]);
+}
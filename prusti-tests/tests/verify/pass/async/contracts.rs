@@ -0,0 +1,37 @@
+use prusti_contracts::*;
+
+#[requires(x < 100)]
+#[ensures(result == x + 1)]
+async fn increment(x: u32) -> u32 {
+    x + 1
+}
+
+#[requires(x < 50)]
+#[ensures(result == x + 2)]
+async fn increment_twice(x: u32) -> u32 {
+    let once = increment(x).await;
+    increment(once).await
+}
+
+struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[requires(self.value < 100)]
+    #[ensures(result == self.value + 1)]
+    async fn next(&self) -> u32 {
+        increment(self.value).await
+    }
+}
+
+#[requires(counter.value < 10)]
+#[ensures(result == counter.value + 3)]
+async fn next_of_next(counter: &Counter) -> u32 {
+    let next = counter.next().await;
+    increment_twice(next).await
+}
+
+// Calling an `async fn` from a synchronous function creates a future, which
+// Prusti cannot encode.
+fn main() {}
@@ -0,0 +1,53 @@
+use prusti_contracts::*;
+
+#[ensures(result == x & 0xFF)]
+#[ensures(result <= 255)]
+fn low_byte(x: u32) -> u32 {
+    x % 256
+}
+
+#[requires(i < 4)]
+fn byte(x: u32, i: u32) -> u8 {
+    ((x >> (8 * i)) & 0xFF) as u8
+}
+
+#[ensures(result == x / 256 % 256)]
+fn second_byte(x: u32) -> u32 {
+    (x >> 8) & 0xFF
+}
+
+#[requires(x <= 0xFF_FFFF)]
+#[ensures(result == x)]
+fn round_trip(x: u32) -> u32 {
+    (x << 8) >> 8
+}
+
+#[ensures(result == x)]
+fn identities(x: u64) -> u64 {
+    let y = x | 0;
+    let z = y & u64::MAX;
+    z ^ 0
+}
+
+#[ensures(result == 0)]
+fn xor_self(x: u16) -> u16 {
+    x ^ x
+}
+
+#[ensures(result == x | 0x0F)]
+#[ensures(result >= x)]
+fn set_low_bits(x: u8) -> u8 {
+    x | 0x0F
+}
+
+#[ensures(result == u8::MAX - x)]
+fn invert(x: u8) -> u8 {
+    !x
+}
+
+#[ensures(result + x == -1)]
+fn invert_signed(x: i32) -> i32 {
+    !x
+}
+
+fn main() {}
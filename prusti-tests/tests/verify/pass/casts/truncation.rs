@@ -0,0 +1,57 @@
+use prusti_contracts::*;
+
+#[requires(x < 256)]
+#[ensures(result as u32 == x)]
+fn low_byte_in_range(x: u32) -> u8 {
+    x as u8
+}
+
+#[requires(x < 65536)]
+#[ensures(result == x % 256)]
+fn low_byte(x: u32) -> u8 {
+    x as u8
+}
+
+#[requires(0 <= x && x < 65536)]
+#[ensures(x < 32768 ==> result as i32 == x)]
+#[ensures(x >= 32768 ==> result as i32 == x - 65536)]
+fn reinterpret(x: i32) -> i16 {
+    x as i16
+}
+
+#[requires(-128 <= x && x < 0)]
+#[ensures(result as i16 == x + 256)]
+fn negative_to_unsigned(x: i8) -> u8 {
+    x as u8
+}
+
+#[requires(-128 <= x && x < 128)]
+#[ensures(result as i8 == x)]
+fn sign_extension(x: i8) -> i64 {
+    x as i64
+}
+
+#[requires(x <= 255)]
+#[ensures(result == x)]
+fn widening(x: u8) -> u64 {
+    x as u64
+}
+
+#[ensures(result as u32 == c as u32 % 256)]
+fn char_low_byte(c: char) -> u8 {
+    c as u8
+}
+
+#[ensures(b ==> result == 1)]
+#[ensures(!b ==> result == 0)]
+fn from_bool(b: bool) -> i32 {
+    b as i32
+}
+
+fn main() {
+    assert!(low_byte(300) == 44);
+    assert!(reinterpret(40000) == -25536);
+    assert!(negative_to_unsigned(-1) == 255);
+    assert!(from_bool(true) + from_bool(false) == 1);
+    assert!('A' as u32 == 65);
+}
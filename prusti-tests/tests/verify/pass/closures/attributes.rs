@@ -0,0 +1,15 @@
+#![feature(stmt_expr_attributes)]
+#![feature(proc_macro_hygiene)]
+
+use prusti_contracts::*;
+
+fn main() {
+    let f = #[requires(x > 0)] #[ensures(result == x - 1)] |x: i32| -> i32 { x - 1 };
+    let y = f(1);
+    assert!(y == 0);
+
+    let g = #[ensures(result >= a && result >= b)] |a: i32, b: i32| -> i32 {
+        if a > b { a } else { b }
+    };
+    assert!(g(3, 4) >= 4);
+}
@@ -0,0 +1,58 @@
+use prusti_contracts::*;
+
+#[derive(Clone, PartialEq, Default)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Clone, PartialEq)]
+struct Segment {
+    from: Point,
+    to: Point,
+    visible: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Color {
+    Red,
+    Green,
+}
+
+#[ensures(result)]
+fn clones_are_equal(segment: &Segment) -> bool {
+    let copy = segment.clone();
+    copy == *segment
+}
+
+#[ensures(result.0 == result.1)]
+fn two_clones(point: &Point) -> (Point, Point) {
+    (point.clone(), point.clone())
+}
+
+#[ensures(result.from.x == segment.from.x && result.visible == segment.visible)]
+fn cloned(segment: &Segment) -> Segment {
+    segment.clone()
+}
+
+#[pure]
+fn same_color(a: Color, b: Color) -> bool {
+    a == b
+}
+
+#[pure]
+fn origin() -> Point {
+    Point::default()
+}
+
+fn main() {
+    let a = Point { x: 1, y: 2 };
+    let b = a.clone();
+    assert!(a == b);
+    assert!(!(a != b));
+    let c = Point { x: 1, y: 3 };
+    assert!(a != c);
+    assert!(same_color(Color::Red, Color::Red));
+    assert!(!same_color(Color::Red, Color::Green));
+    assert!(origin().x == 0);
+}
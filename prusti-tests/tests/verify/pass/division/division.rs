@@ -0,0 +1,34 @@
+use prusti_contracts::*;
+
+#[requires(count > 0)]
+#[ensures(result <= total)]
+fn average(total: u32, count: u32) -> u32 {
+    total / count
+}
+
+fn guarded_remainder(a: u32, b: u32) -> u32 {
+    if b != 0 {
+        a % b
+    } else {
+        0
+    }
+}
+
+#[requires(b != 0 && (a != i32::MIN || b != -1))]
+fn checked_remainder(a: i32, b: i32) -> i32 {
+    a % b
+}
+
+fn guarded_signed_division(a: i32, b: i32) -> i32 {
+    if b == 0 || (a == i32::MIN && b == -1) {
+        0
+    } else {
+        a / b
+    }
+}
+
+fn min_remainder() -> i32 {
+    checked_remainder(i32::MIN, 3)
+}
+
+fn main() {}
@@ -0,0 +1,26 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[ensures(result.x == p.y && result.y == p.x)]
+fn swap(p: Point) -> Point {
+    Point { x: p.y, y: p.x }
+}
+
+fn test_assert_eq(a: i32, b: i32) {
+    let p = Point { x: a, y: b };
+    assert_eq!(swap(swap(p)), p);
+    debug_assert_eq!(swap(swap(p)), p);
+}
+
+#[requires(a != b)]
+fn test_assert_ne(a: i32, b: i32) {
+    let p = Point { x: a, y: b };
+    assert_ne!(swap(p), p);
+}
+
+fn main() {}
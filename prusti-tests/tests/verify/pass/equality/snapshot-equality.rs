@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+pub enum List {
+    Nil,
+    Cons(i32, Box<List>),
+}
+
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[ensures(result === *p)]
+fn copy_point(p: &Point) -> Point {
+    Point { x: p.x, y: p.y }
+}
+
+#[requires(p.x != q.x)]
+#[ensures(*p !== *q)]
+fn different_points(p: &Point, q: &Point) {}
+
+#[ensures(old(list) === result)]
+fn move_list(list: List) -> List {
+    list
+}
+
+/// References are compared by their pointees.
+#[requires(a === b)]
+#[ensures(b === a && *a === *b)]
+fn symmetric(a: &List, b: &List) {}
+
+#[requires(a === b && b === c)]
+#[ensures(a === c)]
+fn transitive<T>(a: &T, b: &T, c: &T) {}
+
+fn main() {
+    let list = List::Cons(1, Box::new(List::Nil));
+    symmetric(&list, &list);
+    transitive(&list, &list, &list);
+}
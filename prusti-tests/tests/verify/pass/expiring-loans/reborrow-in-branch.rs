@@ -0,0 +1,42 @@
+/// Borrows that expire in the middle of a branch, before the end of their
+/// lexical scope. Their expiry is computed from the Polonius facts, as in
+/// `borrow-die-with-if.rs`.
+
+use prusti_contracts::*;
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[after_expiry(p.x == before_expiry(*result) && p.y == old(p.y))]
+fn x_mut(p: &mut Point) -> &mut i32 {
+    &mut p.x
+}
+
+fn apply_pledge_in_branch(p: &mut Point, b: bool) {
+    let old_y = p.y;
+    if b {
+        let x = x_mut(p);
+        *x = 5;
+        // The borrow expires here, before the end of the branch.
+        assert!(p.x == 5);
+        p.x = 6;
+        assert!(p.x == 6);
+    }
+    assert!(p.y == old_y);
+}
+
+fn reborrow_in_branch(p: &mut Point, b: bool) {
+    let r = &mut *p;
+    if b {
+        let s = &mut r.x;
+        *s = 1;
+        r.y = 2;
+        assert!(r.x == 1);
+    }
+    r.x = 3;
+    assert!(p.x == 3);
+}
+
+fn main() {}
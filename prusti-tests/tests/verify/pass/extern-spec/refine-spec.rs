@@ -0,0 +1,50 @@
+use prusti_contracts::*;
+
+#[trusted]
+#[ensures(result >= 1)]
+fn size_of<T>() -> usize {
+    std::mem::size_of::<T>().max(1)
+}
+
+#[refine_spec(where T = u8)]
+#[ensures(result == 1)]
+fn size_of<T>() -> usize;
+
+#[refine_spec(where T = (u8, u8))]
+#[ensures(result == 2)]
+fn size_of<T>() -> usize;
+
+pub struct Pair<T, U> {
+    first: T,
+    second: U,
+}
+
+impl<T, U> Pair<T, U> {
+    #[trusted]
+    pub fn size(&self) -> usize {
+        std::mem::size_of::<T>() + std::mem::size_of::<U>()
+    }
+}
+
+#[refine_spec(where T = u8)]
+impl<T, U> Pair<T, U> {
+    #[ensures(result >= 1)]
+    fn size(&self) -> usize;
+}
+
+#[refine_spec(where T = u8, U = u8)]
+impl<T, U> Pair<T, U> {
+    #[ensures(result == 2)]
+    fn size(&self) -> usize;
+}
+
+fn main() {
+    assert!(size_of::<u8>() == 1);
+    assert!(size_of::<(u8, u8)>() == 2);
+    assert!(size_of::<u32>() >= 1);
+
+    let bytes = Pair { first: 1u8, second: 2u8 };
+    assert!(bytes.size() == 2);
+    let mixed = Pair { first: 1u8, second: true };
+    assert!(mixed.size() >= 1);
+}
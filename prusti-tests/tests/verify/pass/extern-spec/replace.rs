@@ -0,0 +1,31 @@
+extern crate prusti_contracts;
+use prusti_contracts::*;
+
+#[extern_spec]
+mod std {
+    mod mem {
+        use prusti_contracts::*;
+
+        #[ensures(*dest == src)]
+        #[ensures(result == old(*dest))]
+        pub fn replace<T: PartialEq>(dest: &mut T, src: T) -> T;
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[pure]
+fn get_x(p: &Point) -> i32 {
+    p.x
+}
+
+fn main() {
+    let mut p = Point { x: 1, y: 2 };
+    let old_p = std::mem::replace(&mut p, Point { x: 3, y: 4 });
+    assert!(get_x(&old_p) == 1);
+    assert!(get_x(&p) == 3);
+}
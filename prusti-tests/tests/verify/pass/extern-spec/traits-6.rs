@@ -0,0 +1,50 @@
+extern crate prusti_contracts;
+use prusti_contracts::*;
+
+#[extern_spec]
+impl<T> ExactSizeIterator for std::vec::IntoIter<T> {
+    #[pure]
+    fn len(&self) -> usize;
+}
+
+#[extern_spec]
+impl<T> Iterator for std::vec::IntoIter<T> {
+    #[ensures(old(self.len()) > 0 ==> self.len() == old(self.len()) - 1)]
+    #[ensures(old(self.len()) == 0 ==> self.len() == 0)]
+    fn next(&mut self) -> Option<T>;
+}
+
+fn drain(mut iter: std::vec::IntoIter<i32>) {
+    let mut remaining = iter.len();
+    while remaining > 0 {
+        body_invariant!(iter.len() == remaining);
+        iter.next();
+        remaining -= 1;
+    }
+    assert!(iter.len() == 0);
+}
+
+#[extern_spec]
+impl<'a, T> ExactSizeIterator for std::slice::Iter<'a, T> {
+    #[pure]
+    fn len(&self) -> usize;
+}
+
+#[extern_spec]
+impl<'a, T> Iterator for std::slice::Iter<'a, T> {
+    #[ensures(old(self.len()) > 0 ==> self.len() == old(self.len()) - 1)]
+    #[ensures(old(self.len()) == 0 ==> self.len() == 0)]
+    fn next(&mut self) -> Option<&'a T>;
+}
+
+fn skip_all(mut iter: std::slice::Iter<i32>) {
+    let mut remaining = iter.len();
+    while remaining > 0 {
+        body_invariant!(iter.len() == remaining);
+        iter.next();
+        remaining -= 1;
+    }
+    assert!(iter.len() == 0);
+}
+
+fn main() {}
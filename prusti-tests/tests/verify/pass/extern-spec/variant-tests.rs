@@ -0,0 +1,75 @@
+extern crate prusti_contracts;
+use prusti_contracts::*;
+
+// The variant tests are declared pure without a postcondition; their relation
+// to the discriminant of the receiver is built into the verifier.
+
+#[extern_spec]
+impl<T> std::option::Option<T> {
+    #[pure]
+    pub fn is_some(&self) -> bool;
+
+    #[pure]
+    pub fn is_none(&self) -> bool;
+}
+
+#[extern_spec]
+impl<T, E> std::result::Result<T, E> {
+    #[pure]
+    pub fn is_ok(&self) -> bool;
+
+    #[pure]
+    pub fn is_err(&self) -> bool;
+}
+
+#[requires(x.is_some())]
+fn if_let_some(x: Option<u32>) -> u32 {
+    if let Some(v) = x {
+        v
+    } else {
+        unreachable!()
+    }
+}
+
+#[requires(x.is_none())]
+fn if_let_none(x: Option<u32>) -> u32 {
+    if let Some(_) = x {
+        unreachable!()
+    } else {
+        0
+    }
+}
+
+#[requires(r.is_ok())]
+fn match_ok(r: &Result<u32, bool>) -> u32 {
+    match r {
+        Ok(v) => *v,
+        Err(_) => unreachable!(),
+    }
+}
+
+#[requires(r.is_err())]
+fn match_err(r: Result<u32, bool>) -> bool {
+    match r {
+        Ok(_) => unreachable!(),
+        Err(e) => e,
+    }
+}
+
+#[ensures(result.is_some())]
+fn make_some() -> Option<u32> {
+    Some(4)
+}
+
+fn main() {
+    let x = Some(3);
+    assert!(x.is_some());
+    assert!(!x.is_none());
+    if_let_some(x);
+    if_let_none(None);
+    if_let_some(make_some());
+    let r: Result<u32, bool> = Ok(5);
+    assert!(r.is_ok());
+    match_ok(&r);
+    match_err(Err(true));
+}
@@ -0,0 +1,62 @@
+use prusti_contracts::*;
+
+trait Scored {
+    #[pure]
+    fn score(&self) -> i32;
+
+    #[pure]
+    #[ensures(result == (self.score() <= other.score()))]
+    fn le(&self, other: &Self) -> bool;
+}
+
+#[requires(lo.le(&hi))]
+#[ensures(lo.score() <= result.score() && result.score() <= hi.score())]
+#[ensures(lo.le(&x) && x.le(&hi) ==> result.score() == x.score())]
+fn clamp<T: Scored>(x: T, lo: T, hi: T) -> T {
+    if !lo.le(&x) {
+        lo
+    } else if !x.le(&hi) {
+        hi
+    } else {
+        x
+    }
+}
+
+struct Celsius(i32);
+
+impl Scored for Celsius {
+    #[pure]
+    fn score(&self) -> i32 {
+        self.0
+    }
+
+    #[pure]
+    fn le(&self, other: &Self) -> bool {
+        self.0 <= other.0
+    }
+}
+
+struct Priority {
+    level: u8,
+}
+
+impl Scored for Priority {
+    #[pure]
+    fn score(&self) -> i32 {
+        self.level as i32
+    }
+
+    #[pure]
+    fn le(&self, other: &Self) -> bool {
+        self.level <= other.level
+    }
+}
+
+fn main() {
+    let temperature = clamp(Celsius(42), Celsius(0), Celsius(30));
+    assert!(temperature.score() <= 30);
+    assert!(0 <= temperature.0);
+
+    let priority = clamp(Priority { level: 3 }, Priority { level: 1 }, Priority { level: 5 });
+    assert!(priority.level == 3);
+}
@@ -0,0 +1,41 @@
+use prusti_contracts::*;
+
+#[derive(Clone, PartialEq)]
+struct Counter {
+    value: u32,
+}
+
+fn make_counter(value: u32) -> impl Clone + PartialEq {
+    Counter { value }
+}
+
+fn counter_pair(value: u32) -> (impl Clone + PartialEq, u32) {
+    (Counter { value }, value)
+}
+
+fn forward(value: u32) -> impl Clone + PartialEq {
+    make_counter(value)
+}
+
+#[requires(value < 100)]
+#[ensures(result == value + 1)]
+fn next(value: u32) -> u32 {
+    let counter = make_counter(value);
+    let _copy = counter.clone();
+    value + 1
+}
+
+fn main() {
+    let counter = make_counter(3);
+    let copy = counter.clone();
+    assert!(copy == counter);
+
+    let (counter, value) = counter_pair(5);
+    assert!(counter.clone() == counter);
+    assert!(value == 5);
+
+    let forwarded = forward(7);
+    assert!(forwarded.clone() == forwarded);
+
+    assert!(next(1) == 2);
+}
@@ -0,0 +1,47 @@
+use prusti_contracts::*;
+
+/// `a ==> b && c` is `a ==> (b && c)`, so both conjuncts can be assumed.
+#[requires(positive ==> x > 0 && x < 100)]
+#[ensures(positive ==> result > 1)]
+fn successor(positive: bool, x: i32) -> i32 {
+    if positive {
+        assert!(x > 0);
+        assert!(x < 100);
+    }
+    x + 1
+}
+
+/// `a ==> b ==> c` is `a ==> (b ==> c)`.
+#[requires(a ==> b ==> x > 0)]
+#[ensures(result)]
+fn right_associative(a: bool, b: bool, x: i32) -> bool {
+    !(a && b) || x > 0
+}
+
+/// `a && b ==> c` is `(a && b) ==> c`.
+#[requires(a && b ==> x > 0)]
+#[ensures(result)]
+fn conjunction_premise(a: bool, b: bool, x: i32) -> bool {
+    !a || !b || x > 0
+}
+
+#[ensures(result <==> x > 0)]
+fn is_positive(x: i32) -> bool {
+    x > 0
+}
+
+/// `<==>` has a lower precedence than `==>` and `&&`.
+#[ensures(result && a ==> b <==> a ==> b)]
+fn iff_lowest(a: bool, b: bool) -> bool {
+    true
+}
+
+#[ensures(result <==> (a ==> b))]
+fn implies(a: bool, b: bool) -> bool {
+    !a || b
+}
+
+fn main() {
+    assert!(is_positive(3));
+    assert!(!is_positive(-3));
+}
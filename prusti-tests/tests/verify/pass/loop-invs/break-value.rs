@@ -0,0 +1,66 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(result <= s.len())]
+#[ensures(result < s.len() ==> s[result] == x)]
+#[ensures(result == s.len() ==> forall(|i: usize| (0 <= i && i < s.len()) ==> s[i] != x))]
+fn find(s: &[i32], x: i32) -> usize {
+    let mut k = 0;
+    let index = loop {
+        body_invariant!(k <= s.len());
+        body_invariant!(forall(|i: usize| (0 <= i && i < k) ==> s[i] != x));
+        if k == s.len() {
+            break k;
+        }
+        if s[k] == x {
+            break k;
+        }
+        k += 1;
+    };
+    index
+}
+
+#[requires(n >= 0 && m >= 0)]
+#[ensures(result == n)]
+fn skip_inner(n: i32, m: i32) -> i32 {
+    let mut i = 0;
+    'outer: while i < n {
+        body_invariant!(0 <= i && i < n);
+        i += 1;
+        let mut j = 0;
+        while j < m {
+            body_invariant!(0 <= j && j < m);
+            body_invariant!(0 < i && i <= n);
+            if j == 3 {
+                continue 'outer;
+            }
+            j += 1;
+        }
+    }
+    i
+}
+
+#[requires(n > 0 && m > 0)]
+#[ensures(result == n)]
+fn break_outer(n: i32, m: i32) -> i32 {
+    let mut i = 0;
+    'outer: loop {
+        body_invariant!(0 <= i && i < n);
+        let mut j = 0;
+        while j < m {
+            body_invariant!(0 <= j && j < m);
+            body_invariant!(0 <= i && i < n);
+            if i + 1 == n {
+                i += 1;
+                break 'outer;
+            }
+            j += 1;
+        }
+        i += 1;
+        if i == n {
+            break;
+        }
+    }
+    i
+}
@@ -0,0 +1,58 @@
+use prusti_contracts::*;
+
+#[requires(0 <= n && n <= 100)]
+#[ensures(result >= -1 && result < n)]
+fn find_pair(n: i32, target: i32) -> i32 {
+    let mut i = 0;
+    while i < n {
+        body_invariant!(0 <= i && i < n);
+        let mut j = 0;
+        while j < n {
+            body_invariant!(0 <= i && i < n && 0 <= j && j < n);
+            if i + j == target {
+                return i;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    -1
+}
+
+enum Command {
+    Skip,
+    Stop,
+    Add(u32),
+}
+
+#[pure]
+fn value(command: &Command) -> u32 {
+    match command {
+        Command::Add(value) => *value,
+        _ => 0,
+    }
+}
+
+#[requires(value(&first) <= 100 && value(&second) <= 100)]
+#[ensures(result <= 200)]
+fn run(first: Command, second: Command) -> u32 {
+    let mut total = 0;
+    match first {
+        Command::Skip => {}
+        Command::Stop => return total,
+        Command::Add(value) => total += value,
+    }
+    match second {
+        Command::Stop => return total,
+        Command::Skip => {}
+        Command::Add(value) => {
+            if value == 0 {
+                return total;
+            }
+            total += value;
+        }
+    }
+    total
+}
+
+fn main() {}
@@ -0,0 +1,89 @@
+use prusti_contracts::*;
+
+#[ensures(result == n)]
+fn count(n: usize) -> usize {
+    let mut count = 0;
+    for i in 0..n {
+        body_invariant!(i < n);
+        body_invariant!(count == i);
+        count += 1;
+    }
+    count
+}
+
+#[requires(n < 1000)]
+#[ensures(result == n + 1)]
+fn count_inclusive(n: u32) -> u32 {
+    let mut count = 0;
+    for i in 0..=n {
+        body_invariant!(i <= n);
+        body_invariant!(count == i);
+        count += 1;
+    }
+    count
+}
+
+#[requires(a <= b)]
+#[ensures(result == b - a)]
+fn count_between(a: i32, b: i32) -> i32 {
+    let mut count = 0;
+    for i in a..b {
+        body_invariant!(a <= i && i < b);
+        body_invariant!(count == i - a);
+        count += 1;
+    }
+    count
+}
+
+fn up_to_max() {
+    // The iteration stops after yielding the maximum value
+    let mut last = 0;
+    for i in 250..=u8::MAX {
+        body_invariant!(250 <= i);
+        last = i;
+    }
+    assert!(last == 255);
+}
+
+#[ensures(result == n)]
+fn count_down(n: usize) -> usize {
+    let mut count = 0;
+    for i in (0..n).rev() {
+        body_invariant!(i < n);
+        body_invariant!(count == n - i - 1);
+        count += 1;
+    }
+    count
+}
+
+#[requires(n < 1000)]
+fn count_down_inclusive(n: u32) {
+    let mut previous = n + 1;
+    for i in (0..=n).rev() {
+        body_invariant!(i <= n);
+        body_invariant!(previous == i + 1);
+        previous = i;
+    }
+    assert!(previous == 0);
+}
+
+#[requires(n < 1000)]
+fn even_steps(n: u32) {
+    let mut sum = 0;
+    for i in (0..n).step_by(2) {
+        body_invariant!(i < n);
+        body_invariant!(i % 2 == 0);
+        body_invariant!(sum <= i);
+        sum += 1;
+    }
+}
+
+fn while_let_next(n: usize) {
+    let mut range = 0..n;
+    while let Some(i) = range.next() {
+        body_invariant!(i < n);
+        assert!(i + 1 <= n);
+    }
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use prusti_contracts::*;
+
+/// The ghost variable `steps` counts the iterations of the loop.
+#[requires(n < 1000)]
+fn count(n: u32) -> u32 {
+    let mut i = 0;
+    ghost! {
+        let mut steps: u32 = 0;
+    }
+    while i < n {
+        body_invariant!(i < n);
+        body_invariant!(steps == i);
+        i += 1;
+        ghost! {
+            steps += 1;
+        }
+    }
+    i
+}
+
+fn main() {}
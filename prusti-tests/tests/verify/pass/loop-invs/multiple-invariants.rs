@@ -0,0 +1,17 @@
+use prusti_contracts::*;
+
+#[requires(n >= 0)]
+#[ensures(result == n * (n - 1) / 2)]
+fn sum_below(n: i32) -> i32 {
+    let mut sum = 0;
+    let mut i = 0;
+    while i < n {
+        body_invariant!(i <= n);
+        body_invariant!(sum == i * (i - 1) / 2);
+        sum += i;
+        i += 1;
+    }
+    sum
+}
+
+fn main() {}
@@ -0,0 +1,38 @@
+use prusti_contracts::*;
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        body_variant!(b);
+        let t = b;
+        b = a % b;
+        a = t;
+    }
+    a
+}
+
+/// The variants form a lexicographic tuple: `j` may grow when `i` decreases.
+fn countdown(mut i: u32, mut j: u32) {
+    while i > 0 {
+        body_variant!(i);
+        body_variant!(j);
+        if j > 0 {
+            j -= 1;
+        } else {
+            i -= 1;
+            j = 10;
+        }
+    }
+}
+
+#[ensures(result == n)]
+fn count_up(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        body_invariant!(i < n);
+        body_variant!(n - i);
+        i += 1;
+    }
+    i
+}
+
+fn main() {}
@@ -0,0 +1,47 @@
+use prusti_contracts::*;
+
+pub struct VecWrapperI32 {
+    v: Vec<i32>
+}
+
+impl VecWrapperI32 {
+    #[trusted]
+    #[pure]
+    pub fn len(&self) -> usize {
+        self.v.len()
+    }
+
+    #[trusted]
+    #[pure]
+    #[requires(index < self.len())]
+    pub fn lookup(&self, index: usize) -> i32 {
+        self.v[index]
+    }
+
+    /// `old(..)` in the pledge refers to the state at the call of `index_mut`,
+    /// while `before_expiry(..)` refers to the state just before the returned
+    /// borrow expires.
+    #[trusted]
+    #[requires(index < self.len())]
+    #[after_expiry(
+        self.len() == old(self.len()) &&
+        self.lookup(index) == before_expiry(*result) &&
+        forall(|i: usize| (i < self.len() && i != index) ==>
+            self.lookup(i) == old(self.lookup(i)))
+    )]
+    pub fn index_mut(&mut self, index: usize) -> &mut i32 {
+        &mut self.v[index]
+    }
+}
+
+#[requires(v.len() == 3)]
+#[requires(v.lookup(0) == 5)]
+#[ensures(v.len() == old(v.len()))]
+#[ensures(v.lookup(0) == 5)]
+#[ensures(v.lookup(1) == 7)]
+fn update(v: &mut VecWrapperI32) {
+    let x = v.index_mut(1);
+    *x = 7;
+}
+
+fn main() {}
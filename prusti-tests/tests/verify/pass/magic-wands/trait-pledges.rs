@@ -0,0 +1,59 @@
+use prusti_contracts::*;
+
+trait Container {
+    #[pure]
+    fn value(&self) -> i32;
+
+    #[after_expiry(self.value() == before_expiry(*result))]
+    fn get_mut(&mut self) -> &mut i32;
+}
+
+struct Single {
+    value: i32,
+}
+
+impl Container for Single {
+    #[pure]
+    fn value(&self) -> i32 {
+        self.value
+    }
+
+    fn get_mut(&mut self) -> &mut i32 {
+        &mut self.value
+    }
+}
+
+struct Pair {
+    first: i32,
+    second: i32,
+}
+
+#[refine_trait_spec]
+impl Container for Pair {
+    #[pure]
+    fn value(&self) -> i32 {
+        self.first
+    }
+
+    #[ensures(*result == old(self.first))]
+    #[after_expiry(self.value() == before_expiry(*result) && self.second == old(self.second))]
+    fn get_mut(&mut self) -> &mut i32 {
+        &mut self.first
+    }
+}
+
+fn update_single(single: &mut Single) {
+    let value = single.get_mut();
+    *value = 5;
+    assert!(single.value() == 5);
+}
+
+#[requires(pair.second == 3)]
+fn update_pair(pair: &mut Pair) {
+    let first = pair.get_mut();
+    *first = 5;
+    assert!(pair.value() == 5);
+    assert!(pair.second == 3);
+}
+
+fn main() {}
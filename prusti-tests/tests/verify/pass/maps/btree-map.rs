@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+use std::collections::BTreeMap;
+
+fn main() {}
+
+fn insert_then_get() {
+    let mut m = BTreeMap::new();
+    let (a, b) = (1, 2);
+    m.insert(a, true);
+    m.insert(b, false);
+    assert!(m.len() == 2);
+    match m.get(&b) {
+        Some(v) => assert!(!*v),
+        None => unreachable!(),
+    }
+    let old_value = m.insert(b, true);
+    assert!(m.len() == 2);
+    match old_value {
+        Some(v) => assert!(!v),
+        None => unreachable!(),
+    }
+    m.remove(&a);
+    assert!(m.len() == 1 && !m.contains_key(&a) && m.contains_key(&b));
+}
@@ -0,0 +1,61 @@
+use prusti_contracts::*;
+use std::collections::HashMap;
+
+fn main() {}
+
+#[pure]
+#[requires(m.contains_key(&k))]
+fn value(m: &HashMap<u32, u32>, k: u32) -> u32 {
+    match m.get(&k) {
+        Some(v) => *v,
+        None => unreachable!(),
+    }
+}
+
+fn insert_then_get() {
+    let mut m = HashMap::new();
+    assert!(m.is_empty());
+    let (a, b, c) = (1, 2, 3);
+    m.insert(a, 10);
+    m.insert(b, 20);
+    assert!(m.len() == 2);
+    match m.get(&a) {
+        Some(v) => assert!(*v == 10),
+        None => unreachable!(),
+    }
+    assert!(!m.contains_key(&c));
+}
+
+#[requires(k != other)]
+#[ensures(m.contains_key(&k) && value(m, k) == v)]
+#[ensures(m.contains_key(&other) == old(m.contains_key(&other)))]
+#[ensures(old(m.contains_key(&other)) ==> value(m, other) == old(value(m, other)))]
+fn insert(m: &mut HashMap<u32, u32>, k: u32, v: u32, other: u32) {
+    m.insert(k, v);
+}
+
+#[requires(!m.contains_key(&k))]
+#[ensures(m.len() == old(m.len()) + 1)]
+fn insert_fresh(m: &mut HashMap<u32, u32>, k: u32, v: u32) {
+    m.insert(k, v);
+}
+
+/// A cache that computes missing values.
+#[ensures(m.contains_key(&k) && value(m, k) == result)]
+#[ensures(old(m.contains_key(&k)) ==> m.len() == old(m.len()))]
+fn get_or_compute(m: &mut HashMap<u32, u32>, k: u32) -> u32 {
+    match m.get(&k) {
+        Some(v) => *v,
+        None => {
+            let v = k * 2;
+            m.insert(k, v);
+            v
+        }
+    }
+}
+
+#[ensures(!m.contains_key(&k))]
+#[ensures(old(m.contains_key(&k)) ==> m.len() == old(m.len()) - 1)]
+fn remove(m: &mut HashMap<u32, u32>, k: u32) {
+    m.remove(&k);
+}
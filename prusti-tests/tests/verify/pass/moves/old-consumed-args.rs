@@ -0,0 +1,76 @@
+use prusti_contracts::*;
+
+pub struct Wrapper(u32);
+
+impl Wrapper {
+    #[ensures(result == old(self.0))]
+    pub fn into_inner(self) -> u32 {
+        self.0
+    }
+}
+
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Point {
+    #[ensures(result.x == old(self.y))]
+    #[ensures(result.y == old(self.x))]
+    pub fn swap(self) -> Point {
+        Point {
+            x: self.y,
+            y: self.x,
+        }
+    }
+
+    #[ensures(result.x == old(self.x))]
+    #[ensures(result.y == y)]
+    pub fn with_y(mut self, y: i32) -> Point {
+        self.y = y;
+        self
+    }
+
+    #[requires(self.x < 1000)]
+    #[ensures(result.x == old(self.x) + 1)]
+    #[ensures(result.y == old(self.y))]
+    pub fn shift(mut self) -> Point {
+        self = Point {
+            x: self.x + 1,
+            y: self.y,
+        };
+        self
+    }
+}
+
+pub struct Person {
+    name: String,
+    age: u32,
+}
+
+impl Person {
+    #[ensures(result.age == old(self.age))]
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = name;
+        self
+    }
+}
+
+#[requires(a.x < 1000)]
+#[ensures(result.x == old(a.x) + 1)]
+#[ensures(result.y == old(a.y))]
+pub fn consume(a: Point) -> Point {
+    let b = a;
+    b.shift()
+}
+
+fn main() {
+    let w = Wrapper(3);
+    assert!(w.into_inner() == 3);
+
+    let p = Point { x: 1, y: 2 }.swap().with_y(5);
+    assert!(p.x == 2 && p.y == 5);
+
+    let person = Person { name: String::new(), age: 42 }.with_name(String::from("Ada"));
+    assert!(person.age == 42);
+}
@@ -0,0 +1,39 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[after_expiry(**x == before_expiry(*result))]
+fn get<'a>(x: &'a mut &mut i32) -> &'a mut i32 {
+    *x
+}
+
+#[after_expiry(***x == before_expiry(*result))]
+fn get2<'a>(x: &'a mut &mut &mut i32) -> &'a mut i32 {
+    **x
+}
+
+fn write_through_get() {
+    let mut a = 1;
+    let mut b = &mut a;
+    let r = get(&mut b);
+    *r = 5;
+    assert!(*b == 5);
+    *b = 6;
+    assert!(a == 6);
+}
+
+fn write_through_get2() {
+    let mut a = 1;
+    let mut b = &mut a;
+    let mut c = &mut b;
+    let r = get2(&mut c);
+    *r = 5;
+    assert!(**c == 5);
+    assert!(a == 5);
+}
+
+#[ensures(**x == 3)]
+fn write_argument(x: &mut &mut i32) {
+    let r = get(x);
+    *r = 3;
+}
@@ -0,0 +1,27 @@
+extern crate prusti_contracts;
+
+fn get(v: &Vec<i32>, i: usize) -> i32 {
+    if i < v.len() {
+        v[i]
+    } else {
+        0
+    }
+}
+
+fn get_from_slice(s: &[u8], i: usize) -> u8 {
+    if i < s.len() {
+        s[i]
+    } else {
+        0
+    }
+}
+
+fn unwrap_checked(x: Option<u8>) -> u8 {
+    if x.is_some() {
+        x.unwrap()
+    } else {
+        0
+    }
+}
+
+fn main() {}
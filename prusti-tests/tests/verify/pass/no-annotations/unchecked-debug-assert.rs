@@ -0,0 +1,10 @@
+// compile-flags: -Pcheck_debug_assertions=false
+
+extern crate prusti_contracts;
+
+fn unchecked(x: u32) {
+    debug_assert!(x > 0);
+    debug_assert_eq!(x, 1);
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+// compile-flags: -Pvir_optimizations=fix_quantifiers,remove_empty_if,fold_constants,remove_unused_vars,remove_trivial_assertions,clean_cfg
+use prusti_contracts::*;
+
+#[requires(0 < step && step <= 1000)]
+#[requires(bound <= 1000)]
+#[ensures(result >= bound)]
+#[ensures(result < bound + step)]
+fn next_multiple(bound: usize, step: usize) -> usize {
+    let mut value = 0;
+    while value < bound {
+        body_invariant!(value < bound);
+        value += step;
+    }
+    value
+}
+
+fn main() {
+    let value = next_multiple(10, 3);
+    assert!(value >= 10);
+    assert!(value < 13);
+}
@@ -0,0 +1,31 @@
+use prusti_contracts::*;
+
+predicate! {
+    fn in_bounds(x: i32, lo: i32, hi: i32) -> bool {
+        lo <= x && x <= hi
+    }
+}
+
+#[requires(in_bounds(n, 0, 100))]
+#[ensures(result == n)]
+fn count_up(n: i32) -> i32 {
+    let mut i = 0;
+    while i < n {
+        body_invariant!(in_bounds(i, 0, n));
+        i += 1;
+    }
+    i
+}
+
+#[requires(in_bounds(n, 0, 100))]
+#[ensures(in_bounds(result, 0, 200))]
+fn double(n: i32) -> i32 {
+    n * 2
+}
+
+fn main() {
+    let n = count_up(10);
+    assert!(n == 10);
+    let m = double(n);
+    assert!(m >= 0);
+}
@@ -0,0 +1,57 @@
+use prusti_contracts::*;
+
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[pure]
+fn double(x: i32) -> i32 {
+    2 * x
+}
+
+#[ensures(p.x == old(p.x) + 1)]
+#[ensures(p.y == old(p.y))]
+fn shift_right(p: &mut Point) {
+    p.x += 1;
+    prusti_assert!(p.x == old(p.x) + 1);
+    prusti_assert!(p.y == old(p.y));
+}
+
+#[ensures(result === *p)]
+fn copy_point(p: &Point) -> Point {
+    let q = Point { x: p.x, y: p.y };
+    prusti_assert!(q === *p);
+    q
+}
+
+fn quantified(a: i32) {
+    prusti_assert!(forall(|x: i32| double(x) == x + x));
+    let b = double(a);
+    prusti_assert!(b == a + a);
+}
+
+fn in_loop(n: u32) {
+    let mut i = 0;
+    let mut sum = 0;
+    while i < n {
+        body_invariant!(sum == 2 * i);
+        prusti_assert!(i < n);
+        sum += 2;
+        i += 1;
+        prusti_assert!(sum == 2 * i);
+    }
+    prusti_assert!(i >= n);
+}
+
+fn in_branch(x: i32) -> i32 {
+    if x > 0 {
+        prusti_assert!(x >= 1);
+        x
+    } else {
+        prusti_assert!(-x >= 0);
+        -x
+    }
+}
+
+fn main() {}
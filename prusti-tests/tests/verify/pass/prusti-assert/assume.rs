@@ -0,0 +1,15 @@
+use prusti_contracts::*;
+
+#[trusted]
+fn unknown() -> u32 {
+    42
+}
+
+#[ensures(result > 0)]
+fn assumed_positive() -> u32 {
+    let x = unknown();
+    prusti_assume!(x > 0); //~ WARNING is assumed without being checked
+    x
+}
+
+fn main() {}
@@ -0,0 +1,61 @@
+use prusti_contracts::*;
+
+struct Node {
+    value: i32,
+    next: Option<Box<Node>>,
+}
+
+impl Node {
+    #[pure]
+    #[ensures(result > 0)]
+    fn len(&self) -> usize {
+        match &self.next {
+            None => 1,
+            Some(next) => 1 + next.len(),
+        }
+    }
+
+    #[pure]
+    fn next(&self) -> &Option<Box<Node>> {
+        &self.next
+    }
+
+    #[ensures(self.len() == old(self.len()) + 1)]
+    fn append(&mut self, value: i32) {
+        if let Some(next) = &mut self.next {
+            next.append(value);
+        } else {
+            self.next = Some(Box::new(Node { value, next: None }));
+        }
+    }
+}
+
+#[ensures(result.len() == 1)]
+#[ensures(result.next().is_none())]
+fn singleton(value: i32) -> Node {
+    Node { value, next: None }
+}
+
+#[ensures((*boxed).value == value)]
+#[ensures(boxed.len() == old(boxed.len()))]
+fn set_value(boxed: &mut Box<Node>, value: i32) {
+    (**boxed).value = value;
+}
+
+#[ensures(result.value == old(boxed.value))]
+#[ensures(result.len() == old(boxed.len()))]
+fn unbox(boxed: Box<Node>) -> Node {
+    *boxed
+}
+
+fn main() {
+    let mut list = singleton(1);
+    list.append(2);
+    list.append(3);
+    assert!(list.len() == 3);
+    let mut boxed = Box::new(list);
+    set_value(&mut boxed, 4);
+    let list = unbox(boxed);
+    assert!(list.value == 4);
+    assert!(list.len() == 3);
+}
@@ -0,0 +1,37 @@
+use prusti_contracts::*;
+
+#[pure]
+#[decreases(n)]
+fn fib(n: u32) -> u32 {
+    if n <= 1 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
+
+#[pure]
+#[requires(n >= 0)]
+#[decreases(n)]
+#[ensures(result == n * (n + 1) / 2)]
+fn sum(n: i32) -> i32 {
+    if n == 0 { 0 } else { sum(n - 1) + n }
+}
+
+#[pure]
+#[decreases(n)]
+fn is_even(n: u32) -> bool {
+    if n == 0 { true } else { is_odd(n - 1) }
+}
+
+#[pure]
+#[decreases(n)]
+fn is_odd(n: u32) -> bool {
+    if n == 0 { false } else { is_even(n - 1) }
+}
+
+fn test() {
+    assert!(sum(3) == 6);
+}
+
+fn main() {}
@@ -0,0 +1,73 @@
+use prusti_contracts::*;
+
+enum State {
+    Idle,
+    Running(u32),
+    Done(Result<u32, u32>),
+}
+
+struct Machine {
+    state: State,
+}
+
+impl Machine {
+    #[pure]
+    fn is_running(&self) -> bool {
+        matches!(self.state, State::Running(_))
+    }
+
+    #[pure]
+    fn is_done_ok(&self) -> bool {
+        matches!(self.state, State::Done(Ok(_)))
+    }
+
+    #[ensures(result == self.is_running())]
+    fn check_running(&self) -> bool {
+        match &self.state {
+            State::Running(_) => {
+                assert!(self.is_running());
+                true
+            }
+            State::Idle => {
+                assert!(!self.is_running());
+                false
+            }
+            State::Done(_) => false,
+        }
+    }
+
+    #[ensures(result == self.is_done_ok())]
+    fn check_done_ok(&self) -> bool {
+        match &self.state {
+            State::Done(result) => match result {
+                Ok(_) => {
+                    assert!(self.is_done_ok());
+                    true
+                }
+                Err(_) => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+#[pure]
+fn is_some_ok(x: &Option<Result<u32, u32>>) -> bool {
+    matches!(x, Some(Ok(_)))
+}
+
+#[ensures(result == is_some_ok(&x))]
+fn nested(x: Option<Result<u32, u32>>) -> bool {
+    match x {
+        Some(ref inner) => match inner {
+            Ok(_) => {
+                assert!(is_some_ok(&x));
+                true
+            }
+            Err(_) => false,
+        },
+        None => false,
+    }
+}
+
+fn main() {}
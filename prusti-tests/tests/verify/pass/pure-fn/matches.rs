@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+#[ensures(matches!(result, Some(x) if x > 0))]
+fn positive() -> Option<i32> {
+    Some(5)
+}
+
+#[ensures(matches!(result, Ok(Some(_))))]
+fn nested() -> Result<Option<u32>, ()> {
+    Ok(Some(1))
+}
+
+#[ensures(matches!(result, Ok(Some(x)) if x == n))]
+fn nested_binding(n: u32) -> Result<Option<u32>, ()> {
+    Ok(Some(n))
+}
+
+#[requires(matches!(r, Ok(Some(x)) if x > 10))]
+#[ensures(result > 10)]
+fn unwrap_nested(r: Result<Option<u32>, ()>) -> u32 {
+    match r {
+        Ok(Some(x)) => x,
+        _ => unreachable!(),
+    }
+}
+
+fn main() {
+    let r = nested_binding(42);
+    assert!(unwrap_nested(r) > 10);
+}
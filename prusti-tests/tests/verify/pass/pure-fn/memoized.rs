@@ -0,0 +1,50 @@
+use prusti_contracts::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Computes the Fibonacci numbers, remembering the ones that were already
+/// computed.
+struct Fib {
+    cache: RefCell<HashMap<u64, u64>>,
+}
+
+impl Fib {
+    #[pure_memoized]
+    #[requires(n <= 90)]
+    #[ensures(n <= 1 ==> result == n)]
+    fn get(&self, n: u64) -> u64 {
+        if n <= 1 {
+            return n;
+        }
+        if let Some(&value) = self.cache.borrow().get(&n) {
+            return value;
+        }
+        let value = self.get(n - 1) + self.get(n - 2);
+        self.cache.borrow_mut().insert(n, value);
+        value
+    }
+}
+
+#[requires(n <= 90)]
+fn walk(fib: &Fib, n: u64) {
+    let mut i = 0;
+    let mut last = fib.get(0);
+    while i < n {
+        body_invariant!(i < n && n <= 90);
+        body_invariant!(last == fib.get(i));
+        i += 1;
+        last = fib.get(i);
+    }
+}
+
+fn base_cases(fib: &Fib) {
+    assert!(fib.get(0) == 0);
+    assert!(fib.get(1) == 1);
+    assert!(fib.get(7) == fib.get(7));
+}
+
+fn main() {
+    let fib = Fib { cache: RefCell::new(HashMap::new()) };
+    walk(&fib, 90);
+    base_cases(&fib);
+}
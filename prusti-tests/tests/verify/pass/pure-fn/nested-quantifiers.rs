@@ -0,0 +1,25 @@
+use prusti_contracts::*;
+
+#[trusted]
+#[pure]
+fn related(i: u32, j: i64) -> bool {
+    (i as i64) < j
+}
+
+#[requires(forall(|i: u32| exists(|j: i64| related(i, j))))]
+#[ensures(forall(|i: u32| exists(|j: i64| related(i, j))))]
+fn forall_exists() {}
+
+#[requires(exists(|j: i64| forall(|i: u32| related(i, j))))]
+#[ensures(exists(|j: i64| forall(|i: u32| related(i, j))))]
+fn exists_forall() {}
+
+#[ensures(forall(|i: u32, k: i64| related(i, k) ==> exists(|j: i64| related(i, j))))]
+fn witness() {}
+
+/// The same name bound at two nesting levels refers to two different
+/// variables, of two different types.
+#[ensures(forall(|x: u32| (x == 1 && related(x, 1)) ==> exists(|x: i64| related(1, x))))]
+fn shadowing() {}
+
+fn main() {}
@@ -0,0 +1,35 @@
+use prusti_contracts::*;
+
+struct Pair(i32, i32);
+
+impl Pair {
+    #[pure]
+    fn fst(&self) -> &i32 {
+        &self.0
+    }
+
+    #[pure]
+    fn snd(&self) -> &i32 {
+        &self.1
+    }
+
+    #[ensures(*self.fst() == old(*self.fst()))]
+    #[ensures(*self.snd() == value)]
+    fn set_snd(&mut self, value: i32) {
+        self.1 = value;
+    }
+}
+
+#[requires(*pair.fst() < *pair.snd())]
+#[ensures(result == *pair.snd())]
+fn larger(pair: &Pair) -> i32 {
+    *pair.snd()
+}
+
+fn main() {
+    let mut pair = Pair(1, 2);
+    assert!(*pair.fst() == 1);
+    pair.set_snd(3);
+    assert!(*pair.fst() == 1);
+    assert!(larger(&pair) == 3);
+}
@@ -0,0 +1,46 @@
+use prusti_contracts::*;
+
+pub struct VecWrapperI32 {
+    v: Vec<i32>
+}
+
+impl VecWrapperI32 {
+    #[trusted]
+    #[pure]
+    pub fn len(&self) -> usize {
+        self.v.len()
+    }
+
+    #[trusted]
+    #[pure]
+    #[requires(index < self.len())]
+    pub fn lookup(&self, index: usize) -> i32 {
+        self.v[index]
+    }
+}
+
+#[pure]
+fn is_pair(a: i32, b: i32) -> bool {
+    a == b
+}
+
+/// A trigger consisting of a single term can be written without a tuple.
+#[requires(forall(|i: usize| (0 <= i && i < v.len()) ==> v.lookup(i) > 0, triggers = [v.lookup(i)]))]
+#[requires(1 < v.len())]
+#[ensures(result > 0)]
+fn first_positive(v: &VecWrapperI32) -> i32 {
+    v.lookup(1)
+}
+
+/// Single-term triggers and tuples of terms can be mixed.
+#[requires(forall(
+    |i: usize, j: usize| (i < v.len() && j < v.len()) ==> is_pair(v.lookup(i), v.lookup(j)),
+    triggers = [(v.lookup(i), v.lookup(j)), is_pair(v.lookup(i), v.lookup(j))]
+))]
+#[requires(2 < v.len())]
+#[ensures(result)]
+fn all_pairs(v: &VecWrapperI32) -> bool {
+    is_pair(v.lookup(0), v.lookup(2))
+}
+
+fn main() {}
@@ -0,0 +1,52 @@
+use prusti_contracts::*;
+
+#[ensures(*x == old(*y) && *y == old(*x))]
+fn swap(x: &mut i32, y: &mut i32) {
+    let px = x as *mut i32;
+    let py = y as *mut i32;
+    unsafe {
+        let tmp = *px;
+        *px = *py;
+        *py = tmp;
+    }
+}
+
+struct Pair {
+    first: i32,
+    second: i32,
+}
+
+#[ensures(pair.first == old(pair.second) && pair.second == old(pair.first))]
+fn swap_fields(pair: &mut Pair) {
+    let first = &mut pair.first as *mut i32;
+    let second = &mut pair.second as *mut i32;
+    unsafe { swap(&mut *first, &mut *second) }
+}
+
+#[ensures(result == x + 1)]
+fn increment_through_pointer(x: i32) -> i32 {
+    let mut value = x;
+    let pointer = &mut value as *mut i32;
+    let reference = unsafe { &mut *pointer };
+    *reference += 1;
+    value
+}
+
+#[ensures(result == *x)]
+fn read_through_const_pointer(x: &i32) -> i32 {
+    let pointer = x as *const i32;
+    let copy = pointer;
+    unsafe { *copy }
+}
+
+fn main() {
+    let mut a = 1;
+    let mut b = 2;
+    swap(&mut a, &mut b);
+    assert!(a == 2 && b == 1);
+    let mut pair = Pair { first: 3, second: 4 };
+    swap_fields(&mut pair);
+    assert!(pair.first == 4 && pair.second == 3);
+    assert!(increment_through_pointer(5) == 6);
+    assert!(read_through_const_pointer(&7) == 7);
+}
@@ -0,0 +1,79 @@
+use prusti_contracts::*;
+
+struct Name {
+    len: usize,
+}
+
+impl Name {
+    #[pure]
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+struct Person {
+    name: Name,
+    age: u32,
+}
+
+impl Person {
+    #[pure]
+    fn name(&self) -> &Name {
+        &self.name
+    }
+
+    // No pledge needed: the returned reference is a shared reborrow of
+    // `self.name`.
+    fn name_ref(&self) -> &Name {
+        &self.name
+    }
+}
+
+struct Team {
+    leader: Person,
+    size: u32,
+}
+
+impl Team {
+    fn leader(&self) -> &Person {
+        &self.leader
+    }
+
+    fn leader_name(&self) -> &Name {
+        let leader = &self.leader;
+        &leader.name
+    }
+}
+
+#[ensures(result.name().len() > 0)]
+fn make_person() -> Person {
+    Person { name: Name { len: 3 }, age: 30 }
+}
+
+fn use_getters(team: &Team) {
+    let leader = team.leader();
+    assert!(leader.name().len() == team.leader.name.len);
+    assert!(leader.age == team.leader.age);
+    let name = team.leader_name();
+    assert!(name.len() == team.leader.name.len());
+    let name = team.leader.name_ref();
+    assert!(name.len == team.leader.name.len);
+}
+
+#[requires(team.leader.name().len() > 0)]
+#[ensures(result > 0)]
+fn leader_name_len(team: &Team) -> usize {
+    let leader = team.leader();
+    let len = leader.name_ref().len();
+    assert!(team.leader.name().len() == len);
+    len
+}
+
+fn main() {
+    let person = make_person();
+    let team = Team { leader: person, size: 1 };
+    use_getters(&team);
+    let name = team.leader.name_ref();
+    assert!(name.len() > 0);
+    assert!(team.size == 1);
+}
@@ -0,0 +1,32 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+fn unwrap_some() {
+    let x = Some(5);
+    assert!(x.is_some());
+    let y = x.unwrap();
+    assert!(y == 5);
+}
+
+fn unwrap_or_none() {
+    let x: Option<i32> = None;
+    assert!(x.is_none());
+    assert!(x.unwrap_or(3) == 3);
+}
+
+#[requires(x.is_some())]
+fn expect_some(x: Option<i32>) -> i32 {
+    x.expect("checked by the precondition")
+}
+
+#[ensures(result.is_ok() == old(x.is_some()))]
+fn to_result(x: Option<u8>) -> Result<u8, u8> {
+    x.ok_or(0)
+}
+
+#[requires(x.is_some())]
+fn map_some(x: Option<u32>) {
+    let y = x.map(|v| v / 2);
+    assert!(y.is_some());
+}
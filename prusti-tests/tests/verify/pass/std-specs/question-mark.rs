@@ -0,0 +1,52 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    TooLarge,
+}
+
+#[ensures(result.is_ok() == (n <= 100))]
+#[ensures(match result { Ok(m) => m == n, Err(e) => e == Error::TooLarge })]
+fn check(n: u32) -> Result<u32, Error> {
+    if n > 100 {
+        Err(Error::TooLarge)
+    } else {
+        Ok(n)
+    }
+}
+
+#[ensures(result.is_ok() == (a <= 100 && b <= 100 && c <= 100))]
+#[ensures(match result { Ok(sum) => sum == a + b + c, Err(_) => true })]
+fn sum(a: u32, b: u32, c: u32) -> Result<u32, Error> {
+    let x = check(a)?;
+    let y = check(b)?;
+    let z = check(c)?;
+    Ok(x + y + z)
+}
+
+#[ensures(result.is_err() == (n > 100))]
+fn propagate(n: u32) -> Result<(), Error> {
+    check(n)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppError {
+    Input(Error),
+    Other,
+}
+
+impl From<Error> for AppError {
+    #[pure]
+    fn from(error: Error) -> Self {
+        AppError::Input(error)
+    }
+}
+
+#[ensures(match result { Ok(m) => m == n, Err(e) => n > 100 && e == AppError::Input(Error::TooLarge) })]
+fn convert(n: u32) -> Result<u32, AppError> {
+    let m = check(n)?;
+    Ok(m)
+}
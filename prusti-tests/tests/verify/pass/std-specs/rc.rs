@@ -0,0 +1,28 @@
+use prusti_contracts::*;
+use std::rc::Rc;
+use std::sync::Arc;
+
+struct Config {
+    level: u32,
+}
+
+#[requires(*a === *b)]
+#[ensures(result)]
+fn same_level(a: Rc<Config>, b: Rc<Config>) -> bool {
+    a.level == b.level
+}
+
+#[requires(config.level == 3)]
+fn read_through_arc(config: Arc<Config>) {
+    let other = config.clone();
+    assert!(other.level == 3);
+}
+
+fn main() {
+    let config = Rc::new(Config { level: 3 });
+    let copy = config.clone();
+    assert!(copy.level == 3);
+    assert!(Rc::strong_count(&config) >= 1);
+    assert!(same_level(config, copy));
+    read_through_arc(Arc::new(Config { level: 3 }));
+}
@@ -0,0 +1,10 @@
+// compile-flags: -Pcheck_refcell_borrows=false
+
+use prusti_contracts::*;
+use std::cell::RefCell;
+
+fn borrow_behind_shared_reference(cell: &RefCell<u32>) -> u32 {
+    *cell.borrow()
+}
+
+fn main() {}
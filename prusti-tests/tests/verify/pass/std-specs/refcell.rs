@@ -0,0 +1,52 @@
+use prusti_contracts::*;
+use std::cell::RefCell;
+
+struct Log {
+    items: RefCell<Vec<i32>>,
+}
+
+impl Log {
+    #[pure]
+    fn len(&self) -> usize {
+        self.items.borrow().len()
+    }
+
+    #[ensures(self.len() == old(self.len()) + 1)]
+    fn push(&mut self, value: i32) {
+        self.items.borrow_mut().push(value);
+    }
+}
+
+fn shared_borrows(cell: &mut RefCell<u32>) {
+    let first = cell.borrow();
+    let second = cell.borrow();
+    assert!(*first == *second);
+}
+
+fn sequential_borrows(cell: &mut RefCell<u32>) {
+    {
+        let mut guard = cell.borrow_mut();
+        *guard = 5;
+    }
+    let guard = cell.borrow();
+    assert!(*guard == 5);
+}
+
+fn replace_and_take(cell: &mut RefCell<u32>) {
+    let old_value = cell.replace(7);
+    assert!(*cell.borrow() == 7);
+    let value = cell.take();
+    assert!(value == 7);
+    cell.replace(old_value);
+}
+
+fn main() {
+    let cell = RefCell::new(3);
+    assert!(*cell.borrow() == 3);
+    assert!(cell.into_inner() == 3);
+
+    let mut log = Log { items: RefCell::new(Vec::new()) };
+    let len = log.len();
+    log.push(1);
+    assert!(log.len() == len + 1);
+}
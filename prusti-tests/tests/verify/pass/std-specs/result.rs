@@ -0,0 +1,37 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Error {
+    TooLarge,
+}
+
+#[ensures(result.is_ok() == (n <= 100))]
+fn check(n: u32) -> Result<u32, Error> {
+    if n > 100 {
+        Err(Error::TooLarge)
+    } else {
+        Ok(n)
+    }
+}
+
+#[requires(n <= 100)]
+fn checked(n: u32) -> u32 {
+    check(n).unwrap()
+}
+
+#[ensures(result.is_ok() == (n <= 100))]
+fn with_code(n: u32) -> Result<u32, u8> {
+    check(n).map_err(|_| 1)
+}
+
+#[ensures(result.is_some() == (n <= 100))]
+fn ok(n: u32) -> Option<u32> {
+    check(n).ok()
+}
+
+#[ensures(result === old(e))]
+fn convert(e: Error) -> Error {
+    From::from(e)
+}
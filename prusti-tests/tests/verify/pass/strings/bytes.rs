@@ -0,0 +1,26 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[requires(s.len() > 0)]
+#[ensures(result == s.as_bytes()[0])]
+fn first_byte(s: &str) -> u8 {
+    s.as_bytes()[0]
+}
+
+#[ensures(result == (s.len() == 0))]
+fn is_empty(s: &str) -> bool {
+    s.is_empty()
+}
+
+#[pure]
+#[requires(i < s.len())]
+fn byte_at(s: &str, i: usize) -> u8 {
+    s.as_bytes()[i]
+}
+
+#[requires(i < s.len())]
+#[ensures(result == byte_at(s, i))]
+fn get(s: &str, i: usize) -> u8 {
+    s.as_bytes()[i]
+}
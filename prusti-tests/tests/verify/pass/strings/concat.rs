@@ -0,0 +1,37 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(result.len() == a.len() + b.len())]
+fn concat(a: &str, b: &str) -> String {
+    let mut result = String::new();
+    result.push_str(a);
+    result.push_str(b);
+    result
+}
+
+#[ensures(result.len() == a.len() + 1)]
+#[ensures(result.as_bytes()[a.len()] == b'!')]
+fn exclaim(a: &str) -> String {
+    let mut result = String::new();
+    result.push_str(a);
+    result.push('!');
+    result
+}
+
+#[requires(!a.is_empty())]
+#[ensures(result.len() == 2 * a.len())]
+#[ensures(result.as_bytes()[0] == a.as_bytes()[0])]
+fn twice(a: &str) -> String {
+    let mut result = String::new();
+    result.push_str(a);
+    result.push_str(a);
+    result
+}
+
+#[requires(a.len() > 0)]
+fn client(a: &str) {
+    let s = concat(a, a);
+    assert!(s.len() == 2 * a.len());
+    assert!(!s.is_empty());
+}
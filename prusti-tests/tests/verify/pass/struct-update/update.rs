@@ -0,0 +1,45 @@
+use prusti_contracts::*;
+
+#[derive(Clone, Copy, Default)]
+struct Limits {
+    min: u32,
+    max: u32,
+}
+
+#[derive(Default)]
+struct Config {
+    timeout: u32,
+    retries: u32,
+    verbose: bool,
+    limits: Limits,
+}
+
+#[ensures(result.timeout == 5)]
+#[ensures(result.retries == old_config.retries)]
+#[ensures(result.verbose == old_config.verbose)]
+#[ensures(result.limits.max == old_config.limits.max)]
+fn with_timeout(old_config: Config) -> Config {
+    Config { timeout: 5, ..old_config }
+}
+
+#[ensures(result.timeout == 5)]
+#[ensures(result.retries == 0)]
+#[ensures(!result.verbose)]
+#[ensures(result.limits.min == 0 && result.limits.max == 0)]
+fn default_with_timeout() -> Config {
+    Config { timeout: 5, ..Default::default() }
+}
+
+#[ensures(result.max == 10 && result.min == 0)]
+fn default_limits() -> Limits {
+    Limits { max: 10, ..Limits::default() }
+}
+
+fn main() {
+    let config = default_with_timeout();
+    let config = Config { retries: 3, ..config };
+    assert!(config.timeout == 5);
+    assert!(config.retries == 3);
+    let config = with_timeout(config);
+    assert!(config.retries == 3);
+}
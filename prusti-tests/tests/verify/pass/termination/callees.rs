@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+#[extern_spec]
+mod std {
+    mod mem {
+        use prusti_contracts::*;
+
+        #[terminates]
+        #[ensures(*a == old(*b) && *b == old(*a))]
+        pub fn swap(a: &mut i32, b: &mut i32);
+    }
+}
+
+trait Step {
+    fn step(&self, n: u32) -> u32;
+}
+
+struct Counting;
+
+impl Step for Counting {
+    #[terminates]
+    #[decreases(n)]
+    fn step(&self, n: u32) -> u32 {
+        if n == 0 { 0 } else { count(self, n - 1) }
+    }
+}
+
+#[terminates]
+#[decreases(n)]
+fn count(c: &Counting, n: u32) -> u32 {
+    if n == 0 { 0 } else { c.step(n - 1) }
+}
+
+// The external specification marks the procedure as terminating.
+#[terminates]
+fn exchange(a: &mut i32, b: &mut i32) {
+    std::mem::swap(a, b);
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+#[terminates]
+#[decreases(n)]
+#[ensures(result == (n % 2 == 0))]
+fn is_even(n: u32) -> bool {
+    if n == 0 { true } else { is_odd(n - 1) }
+}
+
+#[terminates]
+#[decreases(n)]
+#[ensures(result == (n % 2 == 1))]
+fn is_odd(n: u32) -> bool {
+    if n == 0 { false } else { is_even(n - 1) }
+}
+
+// Not part of a cycle of the call graph, so no measure is needed.
+#[terminates]
+#[ensures(result == n % 2)]
+fn parity(n: u32) -> u32 {
+    if is_even(n) { 0 } else { 1 }
+}
+
+#[terminates]
+#[decreases(n)]
+fn count_down(n: u32) -> u32 {
+    if n == 0 { 0 } else { count_down(n - 1) }
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+use prusti_contracts::*;
+
+trait Positive {
+    #[requires(x > 0)]
+    #[ensures(result > 0)]
+    fn keep(&self, x: i32) -> i32;
+}
+
+struct Identity;
+
+#[refine_trait_spec]
+impl Positive for Identity {
+    // Weaker precondition and a postcondition that implies the trait's one
+    // under the trait's precondition.
+    #[requires(true)]
+    #[ensures(result == x)]
+    fn keep(&self, x: i32) -> i32 {
+        x
+    }
+}
+
+fn main() {
+    let id = Identity;
+    assert!(id.keep(-3) == -3);
+}
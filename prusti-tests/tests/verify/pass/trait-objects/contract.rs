@@ -0,0 +1,28 @@
+use prusti_contracts::*;
+
+trait Shape {
+    #[pure]
+    fn area(&self) -> u32;
+
+    #[ensures(result >= self.area())]
+    fn bounding_area(&self) -> u32;
+}
+
+#[requires(shape.area() <= 1000)]
+#[ensures(result == shape.area() * 2)]
+fn double_area(shape: &dyn Shape) -> u32 {
+    shape.area() * 2
+}
+
+#[requires(shape.area() <= 1000)]
+#[ensures(result == 3 * shape.area())]
+fn triple_area(shape: &dyn Shape) -> u32 {
+    shape.area() + double_area(shape)
+}
+
+#[ensures(result >= shape.area())]
+fn wasted_area(shape: &dyn Shape) -> u32 {
+    shape.bounding_area()
+}
+
+fn main() {}
@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+#[invariant(self.len <= self.capacity)]
+struct Buffer {
+    len: usize,
+    capacity: usize,
+}
+
+impl Buffer {
+    #[ensures(result.len == 0)]
+    #[ensures(result.capacity == capacity)]
+    fn new(capacity: usize) -> Self {
+        Buffer { len: 0, capacity }
+    }
+
+    #[requires(self.len < self.capacity)]
+    fn push(&mut self) {
+        self.len += 1;
+    }
+
+    /// The invariant is violated after the first assignment, and restored
+    /// before the end of the method.
+    #[ensures(self.len == 0)]
+    fn clear_and_shrink(&mut self) {
+        self.capacity = 0;
+        self.len = 0;
+    }
+}
+
+fn check(buffer: &Buffer) {
+    assert!(buffer.len <= buffer.capacity);
+}
+
+fn main() {
+    let mut buffer = Buffer::new(2);
+    buffer.push();
+    check(&buffer);
+    buffer.clear_and_shrink();
+    check(&buffer);
+}
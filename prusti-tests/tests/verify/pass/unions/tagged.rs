@@ -0,0 +1,71 @@
+use prusti_contracts::*;
+
+const INT_TAG: u32 = 0;
+const POINT_TAG: u32 = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+union Payload {
+    int: u32,
+    point: Point,
+}
+
+#[repr(C)]
+struct Tagged {
+    tag: u32,
+    payload: Payload,
+}
+
+#[ensures(result.x == x && result.y == y)]
+fn make_point(x: i32, y: i32) -> Point {
+    Point { x, y }
+}
+
+#[ensures(result.x == 1)]
+fn move_right(point: Point) -> Point {
+    let mut point = point;
+    point.x = 1;
+    point
+}
+
+#[ensures(result == 5)]
+fn read_int() -> u32 {
+    let tagged = Tagged { tag: INT_TAG, payload: Payload { int: 5 } };
+    if tagged.tag == INT_TAG {
+        unsafe { tagged.payload.int }
+    } else {
+        0
+    }
+}
+
+#[ensures(result == 3)]
+fn read_point() -> i32 {
+    let mut tagged = Tagged { tag: INT_TAG, payload: Payload { int: 5 } };
+    tagged.tag = POINT_TAG;
+    tagged.payload.point = make_point(3, 4);
+    if tagged.tag == POINT_TAG {
+        unsafe { tagged.payload.point.x }
+    } else {
+        0
+    }
+}
+
+#[ensures(result == 7)]
+fn overwrite() -> u32 {
+    let mut payload = Payload { point: make_point(3, 4) };
+    payload.int = 7;
+    unsafe { payload.int }
+}
+
+fn main() {
+    assert!(make_point(3, 4).y == 4);
+    assert!(move_right(make_point(3, 4)).x == 1);
+    assert!(read_int() == 5);
+    assert!(read_point() == 3);
+    assert!(overwrite() == 7);
+}
@@ -0,0 +1,21 @@
+// compile-flags: -Pcheck_unreachable=false
+
+use prusti_contracts::*;
+
+fn name(n: u32) -> u32 {
+    match n {
+        0 => 10,
+        1 => 20,
+        _ => unreachable!(),
+    }
+}
+
+#[ensures(result < 2)]
+fn bit(n: u32) -> u32 {
+    if n > 1 {
+        unreachable!()
+    }
+    n
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use prusti_contracts::*;
+
+#[requires(n < 3)]
+fn name(n: u32) -> u32 {
+    match n {
+        0 => 10,
+        1 => 20,
+        2 => 30,
+        _ => unreachable!(),
+    }
+}
+
+#[requires(n % 2 == 0)]
+fn half(n: u32) -> u32 {
+    if n % 2 == 1 {
+        unsafe { std::hint::unreachable_unchecked() }
+    }
+    n / 2
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+// compile-flags: -Pskip_unsupported_features=true
+use prusti_contracts::*;
+
+#[pure]
+#[ensures(result == *x)]
+fn read(x: &i32) -> i32 {
+    let p = x as *const i32;
+    unsafe { *p.add(0) }
+}
+
+#[ensures(result >= 0)]
+fn abs_via_pointer(x: i32) -> i32 {
+    let p = &x as *const i32;
+    let value = unsafe { *p.offset(0) };
+    if value < 0 { -value } else { value }
+}
+
+fn main() {
+    let value = 5;
+    assert!(read(&value) == 5);
+    assert!(abs_via_pointer(-3) >= 0);
+}
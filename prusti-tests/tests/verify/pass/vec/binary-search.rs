@@ -0,0 +1,46 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+predicate! {
+    fn sorted(v: &Vec<i32>) -> bool {
+        forall(|i: usize, j: usize| (0 <= i && i < j && j < v.len()) ==> v[i] <= v[j])
+    }
+}
+
+#[requires(sorted(v))]
+#[ensures(result ==> exists(|k: usize| (0 <= k && k < v.len()) && v[k] == x))]
+#[ensures(!result ==> forall(|k: usize| (0 <= k && k < v.len()) ==> v[k] != x))]
+fn contains(v: &Vec<i32>, x: i32) -> bool {
+    let mut lo = 0;
+    let mut hi = v.len();
+    let mut found = false;
+    let mut found_at = 0;
+    let mut continue_loop = lo < hi;
+
+    while continue_loop {
+        body_invariant!(lo < hi && hi <= v.len());
+        body_invariant!(!found);
+        body_invariant!(sorted(v));
+        body_invariant!(forall(|k: usize| (0 <= k && k < lo) ==> v[k] < x));
+        body_invariant!(forall(|k: usize| (hi <= k && k < v.len()) ==> x < v[k]));
+        body_invariant!(found ==> (found_at < v.len() && v[found_at] == x));
+
+        let mid = lo + (hi - lo) / 2;
+        let mid_element = v[mid];
+        if mid_element == x {
+            found = true;
+            found_at = mid;
+        } else if mid_element < x {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+        continue_loop = lo < hi && !found;
+    }
+
+    if found {
+        assert!(v[found_at] == x);
+    }
+    found
+}
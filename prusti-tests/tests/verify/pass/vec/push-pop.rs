@@ -0,0 +1,52 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+fn push_and_pop() {
+    let mut v = Vec::new();
+    v.push(1);
+    v.push(2);
+    assert!(v.len() == 2);
+    assert!(v[0] == 1 && v[1] == 2);
+
+    let last = v.pop();
+    assert!(v.len() == 1);
+    assert!(v[0] == 1);
+    match last {
+        Some(x) => assert!(x == 2),
+        None => unreachable!(),
+    }
+
+    v.clear();
+    assert!(v.is_empty());
+    match v.pop() {
+        Some(_) => unreachable!(),
+        None => {}
+    }
+}
+
+#[ensures(v.len() == old(v.len()) + 1)]
+#[ensures(v[old(v.len())] == x)]
+#[ensures(forall(|i: usize| (0 <= i && i < old(v.len())) ==> v[i] == old(v[i])))]
+fn append(v: &mut Vec<i32>, x: i32) {
+    v.push(x);
+}
+
+#[requires(v.len() >= 2)]
+#[ensures(v.len() == 2)]
+#[ensures(v[0] == old(v[0]) && v[1] == old(v[1]))]
+fn keep_two(v: &mut Vec<i32>) {
+    v.truncate(2);
+}
+
+#[pure]
+#[requires(i < v.len())]
+fn get(v: &Vec<i32>, i: usize) -> i32 {
+    v[i]
+}
+
+#[requires(v.len() > 0)]
+#[ensures(result == get(v, 0))]
+fn first(v: &Vec<i32>) -> i32 {
+    v[0]
+}
@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[requires(v.len() < usize::MAX)]
+#[ensures(v.len() == old(v.len()) + 1)]
+#[ensures(v[old(v.len())] == old(v.len()))]
+fn push_len(v: &mut Vec<usize>) {
+    v.push(v.len());
+}
+
+fn push_len_owned() {
+    let mut v = Vec::new();
+    v.push(v.len());
+    v.push(v.len());
+    assert!(v.len() == 2);
+    assert!(v[1] == 1);
+}
+
+fn foo(_a: &mut [i32], _l: usize) {}
+
+fn reborrow_argument(a: &mut [i32]) {
+    foo(a, a.len());
+}
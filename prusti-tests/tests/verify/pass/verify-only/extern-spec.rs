@@ -0,0 +1,30 @@
+// compile-flags: -Pverify_only=client
+use prusti_contracts::*;
+
+#[extern_spec]
+mod std {
+    mod mem {
+        use prusti_contracts::*;
+
+        #[ensures(*a == old(*b) && *b == old(*a))]
+        pub fn swap(a: &mut i32, b: &mut i32);
+    }
+}
+
+// The external specification is still used in the selected function.
+fn client() {
+    let mut x = 5;
+    let mut y = 42;
+    std::mem::swap(&mut x, &mut y);
+    assert!(x == 42 && y == 5);
+}
+
+// Not verified, so the possible overflow is not reported.
+fn unselected(x: u32) -> u32 {
+    x + 1
+}
+
+fn main() {
+    client();
+    unselected(1);
+}
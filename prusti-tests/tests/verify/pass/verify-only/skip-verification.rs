@@ -0,0 +1,39 @@
+use prusti_contracts::*;
+
+#[skip_verification]
+#[ensures(result == a + b)]
+fn add(a: u32, b: u32) -> u32 {
+    a.wrapping_add(b)
+}
+
+#[skip_verification]
+mod unverified {
+    use prusti_contracts::*;
+
+    #[ensures(result > 0)]
+    pub fn positive(x: i32) -> i32 {
+        x.abs().max(1)
+    }
+}
+
+struct Counter {
+    value: u32,
+}
+
+#[skip_verification]
+impl Counter {
+    #[requires(self.value < 10)]
+    #[ensures(self.value == old(self.value) + 1)]
+    fn increment(&mut self) {
+        self.value = self.value.wrapping_add(1);
+    }
+}
+
+fn main() {
+    let sum = add(1, 2);
+    assert!(sum == 3);
+    assert!(unverified::positive(5) > 0);
+    let mut counter = Counter { value: 0 };
+    counter.increment();
+    assert!(counter.value == 1);
+}
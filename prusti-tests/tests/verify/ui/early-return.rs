@@ -0,0 +1,12 @@
+use prusti_contracts::*;
+
+#[ensures(result > 0)]
+fn test(x: Option<u32>) -> u32 {
+    match x {
+        Some(0) => return 0,
+        _ => {}
+    }
+    100
+}
+
+fn main() {}
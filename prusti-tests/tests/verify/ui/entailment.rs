@@ -0,0 +1,19 @@
+#![feature(stmt_expr_attributes)]
+#![feature(proc_macro_hygiene)]
+
+use prusti_contracts::*;
+
+#[trusted]
+#[requires(x >= 0)]
+#[requires(f |= |a: i32| [
+    requires(a >= 0),
+    ensures(result >= a)
+])]
+fn twice<F: Fn(i32) -> i32>(f: F, x: i32) -> i32 {
+    f(f(x))
+}
+
+fn main() {
+    let dec = #[requires(a >= 0)] #[ensures(result == a - 1)] |a: i32| -> i32 { a - 1 };
+    twice(dec, 1);
+}
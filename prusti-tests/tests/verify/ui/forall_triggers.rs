@@ -18,4 +18,10 @@ pub fn test2() {}
 #[requires(forall(|n: usize, res: usize| count(n) == res ==> true, triggers=[(res == count(n),)]))]
 pub fn test3() {}
 
+#[requires(forall(|n: usize| count(n) == n, triggers=[n + 1]))]
+pub fn test4() {}
+
+#[requires(forall(|n: usize| count(n) == n, triggers=[count(n + 1)]))]
+pub fn test5() {}
+
 fn main() {}
@@ -0,0 +1,29 @@
+#![allow(dead_code)]
+
+use prusti_contracts::*;
+
+fn leak() -> u32 {
+    ghost! {
+        let secret = 42;
+    }
+    let x = secret;
+    x
+}
+
+fn modify(mut y: u32) -> u32 {
+    ghost! {
+        y += 1;
+    }
+    y
+}
+
+fn update(mut z: u32) -> u32 {
+    ghost! {
+        let mut step = 1;
+        step += 1;
+    }
+    z += step;
+    z
+}
+
+fn main() {}
@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+fn invariants_first() {
+    let mut i = 0;
+    while i < 10 {
+        body_invariant!(i < 10);
+        body_invariant!(i >= 0);
+        i += 1;
+    }
+}
+
+fn invariant_after_statement() {
+    let mut i = 0;
+    while i < 10 {
+        i += 1;
+        body_invariant!(i <= 10);
+    }
+}
+
+fn invariant_in_loop() {
+    let mut i = 0;
+    loop {
+        body_invariant!(i >= 0);
+        if i >= 10 {
+            break;
+        }
+        i += 1;
+        body_invariant!(i <= 10);
+    }
+}
+
+fn invariant_in_for_loop() {
+    let mut sum = 0;
+    for i in 0..10 {
+        sum += i;
+        body_invariant!(sum >= 0);
+    }
+}
+
+fn main() {}
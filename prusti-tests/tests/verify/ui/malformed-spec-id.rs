@@ -0,0 +1,9 @@
+#![allow(dead_code)]
+
+#[prusti::spec_id = "not-a-uuid"]
+fn spec_item() {}
+
+#[prusti::pre_spec_id_ref = "not-a-uuid"]
+fn test() {}
+
+fn main() {}
@@ -0,0 +1,7 @@
+extern crate prusti_contracts;
+
+fn get(s: &[u8], i: usize) -> u8 {
+    s[i]
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use prusti_contracts::*;
+
+#[ensures(result > 0 && result < 10 && result % 2 == 0)]
+fn small_even() -> u32 {
+    12
+}
+
+#[requires(x > 0 && x < 10)]
+fn bounded(x: u32) {}
+
+fn client() {
+    bounded(10);
+}
+
+fn main() {}
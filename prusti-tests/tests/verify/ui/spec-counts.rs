@@ -0,0 +1,40 @@
+// rustc-env:PRUSTI_LOG=prusti_interface::specs=info
+// rustc-env:PRUSTI_LOG_STYLE=never
+// normalize-stderr-test: "\[\S+ INFO  prusti_interface::specs\] " -> ""
+
+use prusti_contracts::*;
+
+#[extern_spec]
+mod std {
+    mod mem {
+        use prusti_contracts::*;
+
+        #[ensures(*a == old(*b) && *b == old(*a))]
+        pub fn swap(a: &mut i32, b: &mut i32);
+    }
+}
+
+#[requires(x > 0)]
+fn test1(x: i32) {}
+
+#[pure]
+#[ensures(result == x)]
+fn test2(x: i32) -> i32 {
+    x
+}
+
+fn test3() {
+    let mut curr = 0;
+    while curr < 2 {
+        body_invariant!(curr < 2);
+        curr += 1;
+    }
+}
+
+fn main() {
+    let mut x = 5;
+    let mut y = 42;
+    std::mem::swap(&mut x, &mut y);
+    test1(x);
+    assert!(test2(y) == 5);
+}
@@ -0,0 +1,12 @@
+use prusti_contracts::*;
+
+fn shift(x: u32, n: u32) -> u32 {
+    x << n //~ ERROR the shift amount might not be smaller than 32, the bit width of u32
+}
+
+#[requires(i < 5)]
+fn byte(x: u32, i: u32) -> u32 {
+    (x >> (8 * i)) & 0xFF //~ ERROR the shift amount might not be smaller than 32, the bit width of u32
+}
+
+fn main() {}
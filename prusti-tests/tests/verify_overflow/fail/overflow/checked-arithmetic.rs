@@ -0,0 +1,11 @@
+use prusti_contracts::*;
+
+#[ensures(result == a + b)] //~ ERROR postcondition might not hold
+fn add_or_zero(a: u32, b: u32) -> u32 {
+    match a.checked_add(b) {
+        Some(sum) => sum,
+        None => 0,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,35 @@
+use prusti_contracts::*;
+
+fn add(a: u8, b: u8) -> u8 {
+    a + b //~ ERROR assertion might fail with "attempt to add with overflow": the result might be greater than u8::MAX
+}
+
+#[requires(a <= 200)]
+fn add_guarded(a: u8, b: u8) -> u8 {
+    a + b //~ ERROR the result might be greater than u8::MAX
+}
+
+#[requires(a <= 100 && b <= 100)]
+fn sub(a: u8, b: u8) -> u8 {
+    a - b //~ ERROR the result might be smaller than u8::MIN
+}
+
+#[allow(arithmetic_overflow)]
+fn shift(x: u8) -> u8 {
+    x << 8 //~ ERROR attempt to shift left with overflow
+}
+
+fn wrapping() {
+    let x: u8 = 200;
+    assert!(x.wrapping_add(100) > 200); //~ ERROR the asserted expression might not hold
+}
+
+fn checked() {
+    let x: u8 = 200;
+    match x.checked_add(55) {
+        Some(sum) => assert!(sum == 254), //~ ERROR the asserted expression might not hold
+        None => {}
+    }
+}
+
+fn main() {}
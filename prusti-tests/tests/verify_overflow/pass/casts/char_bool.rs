@@ -0,0 +1,28 @@
+use prusti_contracts::*;
+
+fn roundtrip(a: u8) {
+    assert!(a as char as u8 as u16 as u32 as u64 as u128 == a as u128);
+}
+
+#[ensures(result <= 0x10FFFF)]
+fn code_point(c: char) -> u32 {
+    c as u32
+}
+
+#[ensures(result == 0 || result == 1)]
+fn from_bool(b: bool) -> u8 {
+    b as u8
+}
+
+#[ensures(result == x)]
+fn u64_usize(x: u64) -> usize {
+    x as usize
+}
+
+#[requires(x >= 0)]
+#[ensures(result as isize == x)]
+fn isize_usize(x: isize) -> usize {
+    x as usize
+}
+
+fn main() {}
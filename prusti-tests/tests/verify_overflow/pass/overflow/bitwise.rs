@@ -0,0 +1,14 @@
+use prusti_contracts::*;
+
+#[requires(n < 32)]
+fn shift(x: u32, n: u32) -> u32 {
+    x << n
+}
+
+#[requires(i < 4)]
+#[ensures(result <= 255)]
+fn byte(x: u32, i: u32) -> u32 {
+    (x >> (8 * i)) & 0xFF
+}
+
+fn main() {}
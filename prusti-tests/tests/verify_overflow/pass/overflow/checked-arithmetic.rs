@@ -0,0 +1,37 @@
+use prusti_contracts::*;
+
+#[ensures(result as u64 <= u32::MAX as u64)]
+#[ensures(a as u64 + b as u64 <= u32::MAX as u64 ==> result == a + b)]
+fn add_or_zero(a: u32, b: u32) -> u32 {
+    match a.checked_add(b) {
+        Some(sum) => sum,
+        None => 0,
+    }
+}
+
+#[ensures(a >= b ==> result == a - b)]
+#[ensures(a < b ==> result == 0)]
+fn sub_or_zero(a: u8, b: u8) -> u8 {
+    if a < b {
+        return 0;
+    }
+    match a.checked_sub(b) {
+        Some(difference) => difference,
+        None => 0,
+    }
+}
+
+#[pure]
+fn mul_or_max(a: u16, b: u16) -> u16 {
+    match a.checked_mul(b) {
+        Some(product) => product,
+        None => u16::MAX,
+    }
+}
+
+fn test_mul_or_max() {
+    assert!(mul_or_max(300, 200) == u16::MAX);
+    assert!(mul_or_max(300, 100) == 30000);
+}
+
+fn main() {}
@@ -0,0 +1,51 @@
+use prusti_contracts::*;
+
+#[requires(a as u32 + b as u32 <= 255)]
+#[ensures(result as u32 == a as u32 + b as u32)]
+fn add(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+#[requires(a <= 100 && b <= 100)]
+fn add_small(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+#[ensures(result == 255)]
+fn shift() -> u8 {
+    let x: u8 = 0b1111_1111;
+    (x << 4 >> 4) + 0b1111_0000
+}
+
+#[ensures(a as u32 + b as u32 <= 255 ==> result as u32 == a as u32 + b as u32)]
+#[ensures(a as u32 + b as u32 > 255 ==> result as u32 == a as u32 + b as u32 - 256)]
+fn wrapping(a: u8, b: u8) -> u8 {
+    a.wrapping_add(b)
+}
+
+fn wrapping_sub() {
+    let x: u8 = 3;
+    assert!(x.wrapping_sub(5) == 254);
+    assert!(x.wrapping_mul(100) == 44);
+}
+
+#[ensures(result == (a as u32 + b as u32 <= 255))]
+fn checked(a: u8, b: u8) -> bool {
+    match a.checked_add(b) {
+        Some(sum) => {
+            assert!(sum as u32 == a as u32 + b as u32);
+            true
+        }
+        None => false,
+    }
+}
+
+fn overflowing() {
+    let x: u8 = 200;
+    let (sum, overflow) = x.overflowing_add(100);
+    assert!(sum == 44 && overflow);
+    let (difference, overflow) = x.overflowing_sub(100);
+    assert!(difference == 100 && !overflow);
+}
+
+fn main() {}
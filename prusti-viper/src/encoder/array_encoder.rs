@@ -13,6 +13,7 @@ use crate::encoder::{
     Encoder,
     errors::EncodingResult,
     builtin_encoder::BuiltinFunctionKind,
+    type_encoder::slice_model_ty,
 };
 use prusti_common::{
     vir,
@@ -148,6 +149,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ArrayTypesEncoder<'tcx> {
         encoder: &'p Encoder<'v, 'tcx>,
         slice_ty_rs: ty::Ty<'tcx>,
     ) -> EncodingResult<EncodedSliceTypes<'tcx>> {
+        // `str` is encoded like `[u8]`
+        let slice_ty_rs = slice_model_ty(encoder.env().tcx(), slice_ty_rs);
         if let Some(cached) = self.slice_types_cache.get(&slice_ty_rs) {
             return Ok(cached.clone());
         }
@@ -96,6 +96,11 @@ where
     /// Magic wands passed out of the procedure.
     /// TODO: Implement support for `blocked_lifetimes` via nested magic wands.
     pub borrow_infos: Vec<BorrowInfo<P>>,
+    /// The place behind a shared reference argument that the returned shared
+    /// reference is a reborrow of. For example, if it is `(*_1).0`, the
+    /// postcondition states that `*_0` and `(*_1).0` have the same value, and
+    /// the magic wand that `(*_1).0` is unchanged when `*_0` expires.
+    pub returned_reborrow: Option<P>,
     /// The functional specification: precondition and postcondition
     pub specification: typed::SpecificationSet<'tcx>,
 }
@@ -190,6 +195,7 @@ impl<'tcx> ProcedureContractMirDef<'tcx> {
                 .collect(),
             returned_value: self.returned_value.into(),
             borrow_infos,
+            returned_reborrow: self.returned_reborrow.as_ref().map(|place| place.into()),
             specification: self.specification.clone(),
         }
     }
@@ -224,12 +230,15 @@ impl<'tcx> ProcedureContractMirDef<'tcx> {
             })
             .collect();
         let returned_refs = self.returned_refs.iter().map(&substitute).collect();
+        let returned_reborrow = self.returned_reborrow
+            .map(|place| substitute(&(place, Mutability::Not)).0);
         let result = ProcedureContract {
             def_id: self.def_id,
             args: args.clone(),
             returned_refs: returned_refs,
             returned_value: target,
             borrow_infos,
+            returned_reborrow,
             specification: self.specification.clone(),
         };
         result
@@ -446,7 +455,8 @@ where
         args_ty = (0usize .. fn_sig.inputs().len())
             .map(|i| (mir::Local::from_usize(i + 1), fn_sig.inputs()[i]))
             .collect();
-        return_ty = fn_sig.output(); // FIXME: Shouldn't this also go through maybe_tymap?
+        // FIXME: Shouldn't this also go through maybe_tymap?
+        return_ty = env.reveal_opaque_types(fn_sig.output());
     } else {
         let mir = env.local_mir(proc_def_id.expect_local());
         // local_decls:
@@ -494,15 +504,123 @@ where
         .into_iter()
         .filter(|(place, _)| is_not_blocked(place))
         .collect();
+    let only_shared_borrows = borrow_infos.len() == 1 && borrow_infos[0]
+        .blocked_paths
+        .iter()
+        .chain(borrow_infos[0].blocking_paths.iter())
+        .all(|(_, mutability)| *mutability == Mutability::Not);
+    let returned_reborrow = if only_shared_borrows {
+        find_returned_shared_reborrow(proc_def_id, env)
+    } else {
+        None
+    };
     let contract = ProcedureContractGeneric {
         def_id: proc_def_id,
         args: fake_mir_args,
         returned_refs,
         returned_value: mir::RETURN_PLACE,
         borrow_infos,
+        returned_reborrow,
         specification,
     };
 
     trace!("[compute_borrow_infos] exit result={}", contract);
     Ok(contract)
 }
+
+/// If the body of the procedure returns a shared reborrow of a place behind a
+/// shared reference argument, such as `&self.name`, returns that place.
+/// Methods of traits are ignored, because their implementations can differ.
+fn find_returned_shared_reborrow<'tcx>(
+    proc_def_id: ProcedureDefId,
+    env: &Environment<'tcx>,
+) -> Option<mir::Place<'tcx>> {
+    let local_def_id = proc_def_id.as_local()?;
+    if !env.has_local_mir(local_def_id) || env.tcx().trait_of_item(proc_def_id).is_some() {
+        return None;
+    }
+    let mir = env.local_mir(local_def_id);
+    let returned_place = find_unique_assignment(&mir, mir::RETURN_PLACE).and_then(|rvalue| {
+        match rvalue {
+            mir::Rvalue::Ref(_, mir::BorrowKind::Shared, place) => Some(*place),
+            _ => None,
+        }
+    })?;
+    let place = resolve_shared_reborrow(env.tcx(), &mir, returned_place, 0)?;
+    trace!("[find_returned_shared_reborrow] {:?} returns a reborrow of {:?}", proc_def_id, place);
+    Some(place)
+}
+
+/// The right-hand side of the only assignment to `local`, if there is
+/// exactly one.
+fn find_unique_assignment<'a, 'tcx>(
+    mir: &'a mir::Body<'tcx>,
+    local: mir::Local,
+) -> Option<&'a mir::Rvalue<'tcx>> {
+    let mut assignments = mir.basic_blocks().iter()
+        .flat_map(|block| block.statements.iter())
+        .filter_map(|statement| match statement.kind {
+            mir::StatementKind::Assign(box (place, ref rvalue)) if place.local == local => {
+                Some((place, rvalue))
+            }
+            _ => None,
+        });
+    match (assignments.next(), assignments.next()) {
+        (Some((place, rvalue)), None) if place.projection.is_empty() => Some(rvalue),
+        _ => None,
+    }
+}
+
+/// Follows the temporaries of the body back to a place behind a shared
+/// reference argument, through field projections and dereferences of shared
+/// references only.
+fn resolve_shared_reborrow<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    mir: &mir::Body<'tcx>,
+    place: mir::Place<'tcx>,
+    depth: usize,
+) -> Option<mir::Place<'tcx>> {
+    let mut base: mir::Place<'tcx> = place.local.into();
+    for elem in place.projection.iter() {
+        match elem {
+            mir::ProjectionElem::Deref => match base.ty(mir, tcx).ty.kind() {
+                ty::TyKind::Ref(_, _, Mutability::Not) => {}
+                _ => return None,
+            },
+            mir::ProjectionElem::Field(..) => {}
+            _ => return None,
+        }
+        base = tcx.mk_place_elem(base, elem);
+    }
+    let is_argument = place.local.index() >= 1 && place.local.index() <= mir.arg_count;
+    if is_argument {
+        return if place.projection.first() == Some(&mir::ProjectionElem::Deref) {
+            Some(place)
+        } else {
+            None
+        };
+    }
+    // A reference to a temporary of the body, which must be a copy of an
+    // argument or a reborrow itself.
+    if depth > 8 || place.projection.first() != Some(&mir::ProjectionElem::Deref) {
+        return None;
+    }
+    let origin = match find_unique_assignment(mir, place.local)? {
+        mir::Rvalue::Ref(_, mir::BorrowKind::Shared, origin) => {
+            let origin = resolve_shared_reborrow(tcx, mir, *origin, depth + 1)?;
+            let mut resolved = origin;
+            for elem in place.projection.iter().skip(1) {
+                resolved = tcx.mk_place_elem(resolved, elem);
+            }
+            return Some(resolved);
+        }
+        mir::Rvalue::Use(mir::Operand::Copy(origin))
+        | mir::Rvalue::Use(mir::Operand::Move(origin)) => *origin,
+        _ => return None,
+    };
+    let mut resolved = origin;
+    for elem in place.projection.iter() {
+        resolved = tcx.mk_place_elem(resolved, elem);
+    }
+    resolve_shared_reborrow(tcx, mir, resolved, depth + 1)
+}
@@ -16,6 +16,7 @@ use crate::encoder::places;
 use crate::encoder::procedure_encoder::ProcedureEncoder;
 use crate::encoder::pure_function_encoder::PureFunctionEncoder;
 use crate::encoder::stub_function_encoder::StubFunctionEncoder;
+use crate::encoder::trait_function_encoder::TraitFunctionEncoder;
 use crate::encoder::spec_encoder::encode_spec_assertion;
 use crate::encoder::type_encoder::{
     compute_discriminant_values, compute_discriminant_bounds, TypeEncoder,
@@ -26,8 +27,9 @@ use prusti_common::{vir, vir_local};
 use prusti_common::vir::{WithIdentifier, ExprIterator};
 use prusti_common::config;
 use prusti_common::report::log;
+use prusti_common::report::progress::{self, Phase};
 use prusti_interface::data::ProcedureDefId;
-use prusti_interface::environment::Environment;
+use prusti_interface::environment::{CallGraph, Environment};
 use prusti_interface::specs::typed;
 use prusti_interface::specs::typed::SpecificationId;
 use prusti_interface::utils::{has_spec_only_attr, read_prusti_attrs};
@@ -38,11 +40,12 @@ use rustc_hir::def_id::DefId;
 // use rustc::middle::const_val::ConstVal;
 use rustc_middle::mir;
 // use rustc::mir::interpret::GlobalId;
-use rustc_middle::ty;
+use rustc_middle::ty::{self, DefIdTree};
 use std::cell::{RefCell, RefMut, Ref};
 use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::mem;
+use std::time::{Duration, Instant};
 // use viper;
 use crate::encoder::stub_procedure_encoder::StubProcedureEncoder;
 use std::ops::AddAssign;
@@ -100,6 +103,18 @@ pub struct Encoder<'v, 'tcx: 'v> {
     pure_function_bodies: RefCell<HashMap<(ProcedureDefId, String), vir::Expr>>,
     pure_functions: RefCell<HashMap<(ProcedureDefId, String), vir::FunctionIdentifier>>,
     failed_pure_functions: RefCell<HashSet<(ProcedureDefId, String)>>,
+    /// Procedures that use constructs that cannot be encoded (see
+    /// `check_supported_constructs`), and that are therefore not encoded.
+    unsupported_procedures: RefCell<HashSet<ProcedureDefId>>,
+    /// The procedure to which the encoding errors are currently attributed
+    /// (see `encode_for_procedure`).
+    current_procedure: RefCell<Option<ProcedureDefId>>,
+    /// The encoding errors and warnings reported for each procedure.
+    procedure_encoding_errors: RefCell<HashMap<ProcedureDefId, Vec<PrustiError>>>,
+    /// The `prusti_assume!` statements encoded in each procedure.
+    procedure_assumptions: RefCell<HashMap<ProcedureDefId, Vec<rustc_span::Span>>>,
+    /// The time spent encoding each procedure.
+    procedure_encoding_times: RefCell<HashMap<ProcedureDefId, Duration>>,
     /// Stub pure functions. Generated when an impure Rust function is invoked
     /// where a pure function is required.
     stub_pure_functions: RefCell<HashMap<(ProcedureDefId, String), vir::FunctionIdentifier>>,
@@ -113,6 +128,8 @@ pub struct Encoder<'v, 'tcx: 'v> {
     type_tags: RefCell<HashMap<String, vir::FunctionIdentifier>>,
     type_discriminant_funcs: RefCell<HashMap<String, vir::FunctionIdentifier>>,
     type_cast_functions: RefCell<HashMap<(ty::Ty<'tcx>, ty::Ty<'tcx>), vir::FunctionIdentifier>>,
+    bitwise_functions: RefCell<HashMap<(mir::BinOp, ty::Ty<'tcx>), vir::FunctionIdentifier>>,
+    const_param_functions: RefCell<HashMap<String, vir::FunctionIdentifier>>,
     fields: RefCell<HashMap<String, vir::Field>>,
     snapshot_encoder: RefCell<SnapshotEncoder>,
     mirror_encoder: RefCell<MirrorEncoder>,
@@ -126,6 +143,8 @@ pub struct Encoder<'v, 'tcx: 'v> {
     name_interner: RefCell<NameInterner>,
     /// Maps locals to the local of their discriminant.
     discriminants_info: RefCell<HashMap<(ProcedureDefId, String), Vec<String>>>,
+    /// The call graph of the crate, built when a termination check first needs it.
+    call_graph: RefCell<Option<CallGraph>>,
 }
 
 impl<'v, 'tcx> Encoder<'v, 'tcx> {
@@ -165,6 +184,11 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             pure_function_bodies: RefCell::new(HashMap::new()),
             pure_functions: RefCell::new(HashMap::new()),
             failed_pure_functions: RefCell::new(HashSet::new()),
+            unsupported_procedures: RefCell::new(HashSet::new()),
+            current_procedure: RefCell::new(None),
+            procedure_encoding_errors: RefCell::new(HashMap::new()),
+            procedure_assumptions: RefCell::new(HashMap::new()),
+            procedure_encoding_times: RefCell::new(HashMap::new()),
             stub_pure_functions: RefCell::new(HashMap::new()),
             spec_functions: RefCell::new(HashMap::new()),
             type_predicate_names: RefCell::new(HashMap::new()),
@@ -176,6 +200,8 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             type_tags: RefCell::new(HashMap::new()),
             type_discriminant_funcs: RefCell::new(HashMap::new()),
             type_cast_functions: RefCell::new(HashMap::new()),
+            bitwise_functions: RefCell::new(HashMap::new()),
+            const_param_functions: RefCell::new(HashMap::new()),
             fields: RefCell::new(HashMap::new()),
             closures_collector: RefCell::new(SpecsClosuresCollector::new()),
             encoding_queue: RefCell::new(vec![]),
@@ -188,6 +214,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             encoding_errors_counter: RefCell::new(0),
             name_interner: RefCell::new(NameInterner::new()),
             discriminants_info: RefCell::new(HashMap::new()),
+            call_graph: RefCell::new(None),
         }
     }
 
@@ -268,13 +295,84 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         if prusti_error.is_error() {
             self.encoding_errors_counter.borrow_mut().add_assign(1);
         }
+        if let Some(proc_def_id) = *self.current_procedure.borrow() {
+            self.procedure_encoding_errors
+                .borrow_mut()
+                .entry(proc_def_id)
+                .or_default()
+                .push(prusti_error.clone());
+        }
         prusti_error.emit(self.env);
     }
 
+    /// Report that the current procedure assumes the `prusti_assume!` at
+    /// `span` without checking it, which might make the verification unsound.
+    pub(in crate::encoder) fn register_assumption(&self, span: rustc_span::Span) {
+        let mut warning = PrustiError::incorrect(
+            "the asserted expression of `prusti_assume!` is assumed without being checked",
+            span.into(),
+        ).set_help("the verification is unsound if the expression does not hold");
+        warning.set_warning();
+        if let Some(proc_def_id) = *self.current_procedure.borrow() {
+            self.procedure_encoding_errors
+                .borrow_mut()
+                .entry(proc_def_id)
+                .or_default()
+                .push(warning.clone());
+            self.procedure_assumptions
+                .borrow_mut()
+                .entry(proc_def_id)
+                .or_default()
+                .push(span);
+        }
+        warning.emit(self.env);
+    }
+
     pub fn count_encoding_errors(&self) -> usize {
         *self.encoding_errors_counter.borrow()
     }
 
+    /// Run `encode`, attributing the time that it takes and the encoding
+    /// errors that it registers to the procedure.
+    fn encode_for_procedure<T>(&self, proc_def_id: ProcedureDefId, encode: impl FnOnce() -> T) -> T {
+        let previous_procedure = self.current_procedure.replace(Some(proc_def_id));
+        let start_time = Instant::now();
+        let result = encode();
+        *self.procedure_encoding_times
+            .borrow_mut()
+            .entry(proc_def_id)
+            .or_default() += start_time.elapsed();
+        self.current_procedure.replace(previous_procedure);
+        result
+    }
+
+    /// The encoding errors and warnings reported for the procedure.
+    pub fn get_procedure_encoding_errors(&self, proc_def_id: ProcedureDefId) -> Vec<PrustiError> {
+        self.procedure_encoding_errors
+            .borrow()
+            .get(&proc_def_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The spans of the `prusti_assume!` statements of the procedure.
+    pub fn get_procedure_assumptions(&self, proc_def_id: ProcedureDefId) -> Vec<rustc_span::Span> {
+        self.procedure_assumptions
+            .borrow()
+            .get(&proc_def_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The time spent encoding the procedure.
+    pub fn get_procedure_encoding_time(&self, proc_def_id: ProcedureDefId) -> Duration {
+        self.procedure_encoding_times
+            .borrow()
+            .get(&proc_def_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
 
     pub(super) fn get_domain(&self, name: &str) -> vir::Domain {
         if let Some(domain) = self.snapshot_encoder.borrow().get_domain(name) {
@@ -335,6 +433,14 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         Vec<mir::Operand<'tcx>>,
         Vec<ty::Ty<'tcx>>,
     )> {
+        let tcx = self.env.tcx();
+        if !closure_def_id.is_local() && tcx.is_closure(closure_def_id) {
+            // The closures of specifications imported from other crates are
+            // only collected when they are encoded.
+            if let Some(parent_def_id) = tcx.parent(closure_def_id) {
+                self.closures_collector.borrow_mut().collect(self.env, parent_def_id);
+            }
+        }
         self.closures_collector.borrow().get_single_instantiation(closure_def_id)
     }
 
@@ -350,12 +456,81 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         Some(spec.expect_loop().clone())
     }
 
+    /// Get the `prusti_assert!` or `prusti_assume!` of the closure with a
+    /// `prusti::body_assertion_spec` or `prusti::body_assumption_spec` attribute.
+    pub fn get_body_assertion_specs(&self, def_id: DefId) -> Option<typed::BodyAssertionSpecification<'tcx>> {
+        match self.def_spec.get(&def_id)? {
+            typed::SpecificationSet::BodyAssertion(spec) => Some(spec.clone()),
+            _ => None,
+        }
+    }
+
     /// Get the specifications attached to the `def_id` function.
     pub fn get_procedure_specs(&self, def_id: DefId) -> Option<typed::ProcedureSpecification<'tcx>> {
         let spec = self.def_spec.get(&def_id)?;
         Some(spec.expect_procedure().clone())
     }
 
+    /// Get the refinement (see `#[refine_spec]`) of the specification of the
+    /// instantiation of `proc_def_id` that is called under the current type
+    /// substitution. Of several applicable refinements, the most specific one
+    /// is used; an error is reported if there is no single most specific one.
+    pub fn get_refined_procedure_specs(
+        &self,
+        proc_def_id: ProcedureDefId,
+        call_site_span: rustc_span::Span,
+    ) -> SpannedEncodingResult<Option<typed::ProcedureSpecification<'tcx>>> {
+        if !self.def_spec.refined_specs.keys().any(|(def_id, _)| *def_id == proc_def_id) {
+            return Ok(None);
+        }
+        let tcx = self.env().tcx();
+        let identity_substs = ty::List::identity_for_item(tcx, proc_def_id);
+        let substs = tcx.erase_regions(tcx.mk_substs(identity_substs.iter().map(|arg| {
+            match arg.unpack() {
+                ty::subst::GenericArgKind::Type(ty) => self.resolve_typaram(ty).into(),
+                ty::subst::GenericArgKind::Const(value) => {
+                    match self.resolve_typaram(self.const_param_key(value)).kind() {
+                        ty::TyKind::Array(_, rep) => (*rep).into(),
+                        _ => arg,
+                    }
+                }
+                ty::subst::GenericArgKind::Lifetime(_) => arg,
+            }
+        })));
+        let refinements = self.def_spec.get_refined_specs(proc_def_id, substs);
+        match refinements.as_slice() {
+            [] => Ok(None),
+            [local_id] => {
+                debug!("Call of {:?} with {:?} uses the refinement {:?}", proc_def_id, substs, local_id);
+                Ok(self.def_spec.specs.get(local_id).map(|spec| spec.expect_procedure().clone()))
+            }
+            _ => {
+                let mut span = MultiSpan::from_span(call_site_span);
+                for local_id in &refinements {
+                    span.push_span_label(
+                        self.env().get_item_span(local_id.to_def_id()),
+                        "this refinement applies to the call".to_string(),
+                    );
+                }
+                Err(SpannedEncodingError::incorrect(
+                    format!(
+                        "ambiguous refinements of the specification of {} for this call",
+                        self.env().get_item_name(proc_def_id),
+                    ),
+                    span,
+                ).set_help(
+                    "add a refinement for an instantiation that is more specific than all of them"
+                ))
+            }
+        }
+    }
+
+    /// Get the invariants attached to the `def_id` struct or enum.
+    pub fn get_struct_specs(&self, def_id: DefId) -> Option<Vec<typed::Specification<'tcx>>> {
+        let spec = self.def_spec.get(&def_id)?;
+        Some(spec.expect_struct().clone())
+    }
+
     /// Get a local wrapper `DefId` for functions that have external specs.
     /// Return the original `DefId` for everything else.
     fn get_wrapper_def_id(&self, def_id: DefId) -> DefId {
@@ -390,6 +565,11 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
                     .ok()
                     .and_then(|const_value| const_value.try_to_scalar())
             }
+            ty::ConstKind::Param(param) => {
+                return Err(EncodingError::unsupported(
+                    format!("the value of the const generic parameter {} is unknown", param)
+                ));
+            }
             _ => unimplemented!("{:?}", value),
         };
 
@@ -418,16 +598,23 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         &self,
         self_ty: Option<&'tcx ty::TyS<'tcx>>,
         proc_def_id: ProcedureDefId,
+        refined_spec: Option<typed::ProcedureSpecification<'tcx>>,
         args: &Vec<places::Local>,
         target: places::Local,
     ) -> EncodingResult<ProcedureContract<'tcx>> {
         // get specification on trait declaration method or inherent impl
-        let trait_spec = self.get_procedure_specs(proc_def_id)
+        let mut trait_spec = self.get_procedure_specs(proc_def_id)
             .unwrap_or_else(|| {
                 debug!("Procedure {:?} has no specification", proc_def_id);
                 typed::ProcedureSpecification::empty()
             });
 
+        // the refinement for the called instantiation replaces the general
+        // specification
+        if let Some(refined_spec) = refined_spec {
+            trait_spec = trait_spec.refine(&refined_spec);
+        }
+
         let tymap = self.typaram_repl.borrow();
 
         if tymap.len() != 1 {
@@ -682,6 +869,136 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         Ok(function_name)
     }
 
+    /// Encode the use of a function that models a bitwise operation (`&`, `|`,
+    /// `^`, or a shift by an amount that is not a constant) on an unsigned
+    /// integer type. The function is uninterpreted, but its postconditions
+    /// describe the common cases: masking, identities and shifts by each
+    /// possible amount.
+    pub fn encode_bitwise_function_use(&self, op: mir::BinOp, ty: ty::Ty<'tcx>)
+        -> EncodingResult<String>
+    {
+        trace!("encode_bitwise_function_use(op={:?}, ty={:?})", op, ty);
+        let op_name = match op {
+            mir::BinOp::BitAnd => "bitand",
+            mir::BinOp::BitOr => "bitor",
+            mir::BinOp::BitXor => "bitxor",
+            mir::BinOp::Shl => "shl",
+            mir::BinOp::Shr => "shr",
+            _ => unreachable!("{:?}", op),
+        };
+        let pointer_width = self.env.tcx().data_layout.pointer_size.bits();
+        let bits = match ty.kind() {
+            ty::TyKind::Uint(uint_ty) => uint_ty.bit_width().unwrap_or(pointer_width),
+            _ => 0,
+        };
+        if bits == 0 || bits > 64 {
+            return Err(EncodingError::unsupported(format!(
+                "bitwise operations are supported only on unsigned integers of at most 64 \
+                bits, not on '{:?}'",
+                ty,
+            )));
+        }
+        let function_name = format!("builtin${}${}", op_name, ty);
+        if !self.bitwise_functions.borrow().contains_key(&(op, ty)) {
+            let left = vir_local!{ left: Int };
+            let right = vir_local!{ right: Int };
+            let result = vir_local!{ __result: Int };
+            let (a, b, r): (vir::Expr, vir::Expr, vir::Expr) =
+                (left.clone().into(), right.clone().into(), result.into());
+            let power = |k: u64| vir::Expr::from(1u128 << k);
+            let max = vir::Expr::from((1u128 << bits) - 1);
+            let in_range = |x: &vir::Expr| vir::Expr::and(
+                vir::Expr::le_cmp(0.into(), x.clone()),
+                vir::Expr::le_cmp(x.clone(), max.clone()),
+            );
+            let valid = if op == mir::BinOp::Shl || op == mir::BinOp::Shr {
+                in_range(&a)
+            } else {
+                vir::Expr::and(in_range(&a), in_range(&b))
+            };
+            let mut facts = vec![in_range(&r)];
+            match op {
+                mir::BinOp::BitAnd | mir::BinOp::BitOr => {
+                    facts.push(vir::Expr::implies(
+                        vir::Expr::eq_cmp(a.clone(), b.clone()),
+                        vir::Expr::eq_cmp(r.clone(), a.clone()),
+                    ));
+                    for (x, y) in &[(&a, &b), (&b, &a)] {
+                        let (x, y) = (*x, *y);
+                        if op == mir::BinOp::BitAnd {
+                            facts.push(vir::Expr::le_cmp(r.clone(), x.clone()));
+                        } else {
+                            facts.push(vir::Expr::le_cmp(x.clone(), r.clone()));
+                        }
+                        // Masks of the lowest `k` bits
+                        for k in 0..=bits {
+                            let mask = vir::Expr::sub(power(k), 1.into());
+                            let low_bits = vir::Expr::modulo(x.clone(), power(k));
+                            let masked = if op == mir::BinOp::BitAnd {
+                                low_bits
+                            } else {
+                                vir::Expr::add(vir::Expr::sub(x.clone(), low_bits), mask.clone())
+                            };
+                            facts.push(vir::Expr::implies(
+                                vir::Expr::eq_cmp(y.clone(), mask),
+                                vir::Expr::eq_cmp(r.clone(), masked),
+                            ));
+                        }
+                    }
+                }
+                mir::BinOp::BitXor => {
+                    facts.push(vir::Expr::le_cmp(r.clone(), vir::Expr::add(a.clone(), b.clone())));
+                    facts.push(vir::Expr::implies(
+                        vir::Expr::eq_cmp(a.clone(), b.clone()),
+                        vir::Expr::eq_cmp(r.clone(), 0.into()),
+                    ));
+                    for (x, y) in &[(&a, &b), (&b, &a)] {
+                        let (x, y) = (*x, *y);
+                        facts.push(vir::Expr::implies(
+                            vir::Expr::eq_cmp(y.clone(), 0.into()),
+                            vir::Expr::eq_cmp(r.clone(), x.clone()),
+                        ));
+                        facts.push(vir::Expr::implies(
+                            vir::Expr::eq_cmp(y.clone(), max.clone()),
+                            vir::Expr::eq_cmp(r.clone(), vir::Expr::sub(max.clone(), x.clone())),
+                        ));
+                    }
+                }
+                mir::BinOp::Shl | mir::BinOp::Shr => {
+                    if op == mir::BinOp::Shr {
+                        facts.push(vir::Expr::le_cmp(r.clone(), a.clone()));
+                    }
+                    for k in 0..bits {
+                        let shifted = if op == mir::BinOp::Shl {
+                            vir::Expr::modulo(vir::Expr::mul(a.clone(), power(k)), power(bits))
+                        } else {
+                            vir::Expr::div(a.clone(), power(k))
+                        };
+                        facts.push(vir::Expr::implies(
+                            vir::Expr::eq_cmp(b.clone(), k.into()),
+                            vir::Expr::eq_cmp(r.clone(), shifted),
+                        ));
+                    }
+                }
+                _ => unreachable!(),
+            }
+            let function = vir::Function {
+                name: function_name.clone(),
+                formal_args: vec![left, right],
+                return_type: vir::Type::Int,
+                pres: vec![],
+                posts: facts
+                    .into_iter()
+                    .map(|fact| vir::Expr::implies(valid.clone(), fact))
+                    .collect(),
+                body: None,
+            };
+            let identifier = self.insert_function(function);
+            self.bitwise_functions.borrow_mut().insert((op, ty), identifier);
+        }
+        Ok(function_name)
+    }
+
     pub fn patch_snapshots_method(&self, method: vir::CfgMethod)
         -> EncodingResult<vir::CfgMethod>
     {
@@ -714,7 +1031,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             def_id
         );
         if !self.procedures.borrow().contains_key(&def_id) {
-            self.closures_collector.borrow_mut().collect(self.env, def_id.expect_local());
+            self.closures_collector.borrow_mut().collect(self.env, def_id);
             let procedure = self.env.get_procedure(def_id);
             let proc_encoder = ProcedureEncoder::new(self, &procedure)?;
             let mut method = match proc_encoder.encode() {
@@ -779,6 +1096,17 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         type_encoder.encode_bounds(var)
     }
 
+    /// The smallest and the largest value of an integer type, or `None` if
+    /// `ty` is not an integer type.
+    pub fn encode_integer_bounds(&self, ty: ty::Ty<'tcx>) -> Option<(vir::Expr, vir::Expr)> {
+        match ty.kind() {
+            ty::TyKind::Int(_) | ty::TyKind::Uint(_) => {
+                TypeEncoder::new(self, ty).get_integer_bounds()
+            }
+            _ => None,
+        }
+    }
+
     /// See `spec_encoder::encode_spec_assertion` for a description of the arguments.
     pub fn encode_assertion(
         &self,
@@ -793,16 +1121,36 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         parent_def_id: ProcedureDefId,
     ) -> SpannedEncodingResult<vir::Expr> {
         trace!("encode_assertion {:?}", assertion);
-        let encoded_assertion = encode_spec_assertion(
-            self,
-            assertion,
-            pre_label,
-            target_args,
-            target_return,
-            targets_are_values,
-            assertion_location,
-            parent_def_id,
-        )?;
+        let encoded_assertion = if let box typed::AssertionKind::And(ref conjuncts) = assertion.kind {
+            // Give each conjunct its own position, so that a failure points
+            // to the conjunct that does not hold.
+            conjuncts.iter()
+                .map(|conjunct| self.encode_assertion(
+                    conjunct,
+                    mir,
+                    pre_label,
+                    target_args,
+                    target_return,
+                    targets_are_values,
+                    assertion_location,
+                    error.clone(),
+                    parent_def_id,
+                ))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .conjoin()
+        } else {
+            encode_spec_assertion(
+                self,
+                assertion,
+                pre_label,
+                target_args,
+                target_return,
+                targets_are_values,
+                assertion_location,
+                parent_def_id,
+            )?
+        };
         Ok(encoded_assertion.set_default_pos(
             self.error_manager()
                 .register(typed::Spanned::get_spans(assertion, mir, self.env().tcx()), error, parent_def_id)
@@ -859,7 +1207,8 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     }
 
     /// Checks whether the given type implements structural equality
-    /// by either being a primitive type or by deriving the Eq trait.
+    /// by either being a primitive type or by deriving the Eq or the
+    /// PartialEq trait.
     pub fn has_structural_eq_impl(&self, ty: ty::Ty<'tcx>) -> bool {
         let ty = ty.peel_refs();
         let ty = self.env().tcx().erase_regions_ty(ty);
@@ -874,11 +1223,93 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             | ty::TyKind::Param(_) => true,
             ty::TyKind::Adt(_, _) => {
                 self.env().tcx().has_structural_eq_impls(ty)
+                    || self.has_derived_structural_eq_impl(ty)
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks whether `==` on the given type is snapshot equality because the
+    /// `PartialEq` implementations of the type and of its fields are derived.
+    fn has_derived_structural_eq_impl(&self, ty: ty::Ty<'tcx>) -> bool {
+        let tcx = self.env().tcx();
+        let eq_trait = if let Some(eq_trait) = tcx.lang_items().eq_trait() {
+            eq_trait
+        } else {
+            return false;
+        };
+        match ty.kind() {
+            ty::TyKind::Adt(adt_def, substs) if !adt_def.is_union() => {
+                self.env().has_derived_trait_impl(ty, eq_trait)
+                    && adt_def.all_fields().all(|field| {
+                        self.has_structural_eq_impl(field.ty(tcx, substs))
+                    })
             }
             _ => false,
         }
     }
 
+    /// Checks whether `clone()` on the given type returns a value with the same
+    /// snapshot, by being a primitive type, a shared reference, or by deriving
+    /// the `Clone` trait with fields that have the same property.
+    pub fn has_derived_clone_impl(&self, ty: ty::Ty<'tcx>) -> bool {
+        let tcx = self.env().tcx();
+        match ty.kind() {
+            ty::TyKind::Bool
+            | ty::TyKind::Int(_)
+            | ty::TyKind::Uint(_)
+            | ty::TyKind::Char
+            | ty::TyKind::Ref(_, _, hir::Mutability::Not) => true,
+            ty::TyKind::Tuple(_) => ty.tuple_fields().all(|field_ty| self.has_derived_clone_impl(field_ty)),
+            ty::TyKind::Adt(adt_def, substs) if !adt_def.is_union() => {
+                let clone_trait = if let Some(clone_trait) = tcx.lang_items().clone_trait() {
+                    clone_trait
+                } else {
+                    return false;
+                };
+                self.env().has_derived_trait_impl(ty, clone_trait)
+                    && adt_def.all_fields().all(|field| {
+                        self.has_derived_clone_impl(field.ty(tcx, substs))
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    /// Encode the snapshot of `<ty as Default>::default()` if it is known:
+    /// the default values of primitive types, and structs whose `Default`
+    /// implementation is derived and whose fields all have known defaults.
+    /// `default_def_id` is the `Default::default` trait method.
+    pub fn encode_derived_default_snapshot(
+        &self,
+        default_def_id: ProcedureDefId,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<Option<vir::Expr>> {
+        let tcx = self.env().tcx();
+        Ok(match ty.kind() {
+            ty::TyKind::Bool => Some(false.into()),
+            ty::TyKind::Int(_) | ty::TyKind::Uint(_) | ty::TyKind::Char => Some(0.into()),
+            ty::TyKind::Adt(adt_def, substs) if adt_def.is_struct() => {
+                let is_derived = tcx.trait_of_item(default_def_id).map_or(false, |default_trait|
+                    self.env().has_derived_trait_impl(ty, default_trait)
+                );
+                if !is_derived {
+                    return Ok(None);
+                }
+                let mut fields = vec![];
+                for field in adt_def.non_enum_variant().fields.iter() {
+                    let field_ty = field.ty(tcx, substs);
+                    match self.encode_derived_default_snapshot(default_def_id, field_ty)? {
+                        Some(field_default) => fields.push(field_default),
+                        None => return Ok(None),
+                    }
+                }
+                Some(self.encode_snapshot_constructor(ty, fields)?)
+            }
+            _ => None,
+        })
+    }
+
     pub fn encode_snapshot_type(&self, ty: ty::Ty<'tcx>)
         -> EncodingResult<vir::Type>
     {
@@ -895,6 +1326,17 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         self.snapshot_encoder.borrow_mut().encode_constructor(self, ty, args)
     }
 
+    pub fn encode_snapshot_variant_constructor(
+        &self,
+        ty: ty::Ty<'tcx>,
+        variant_idx: usize,
+        args: Vec<vir::Expr>,
+    )
+        -> EncodingResult<vir::Expr>
+    {
+        self.snapshot_encoder.borrow_mut().encode_variant_constructor(self, ty, variant_idx, args)
+    }
+
     pub fn encode_snapshot_array_idx(
         &self,
         ty: ty::Ty<'tcx>,
@@ -921,6 +1363,32 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         self.snapshot_encoder.borrow_mut().encode_slice_len(self, ty, slice)
     }
 
+    pub fn encode_snapshot_map_contains(
+        &self,
+        ty: ty::Ty<'tcx>,
+        map: vir::Expr,
+        key: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        self.snapshot_encoder.borrow_mut().encode_map_contains(self, ty, map, key)
+    }
+
+    pub fn encode_snapshot_map_lookup(
+        &self,
+        ty: ty::Ty<'tcx>,
+        map: vir::Expr,
+        key: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        self.snapshot_encoder.borrow_mut().encode_map_lookup(self, ty, map, key)
+    }
+
+    pub fn encode_snapshot_map_len(
+        &self,
+        ty: ty::Ty<'tcx>,
+        map: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        self.snapshot_encoder.borrow_mut().encode_map_len(self, ty, map)
+    }
+
     pub fn encode_snapshot_slicing(
         &self,
         base_ty: ty::Ty<'tcx>,
@@ -1000,10 +1468,13 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
 
     pub fn encode_const_expr(
         &self,
-        ty: &ty::TyS<'tcx>,
+        ty: ty::Ty<'tcx>,
         value: &ty::ConstKind<'tcx>
     ) -> EncodingResult<vir::Expr> {
         trace!("encode_const_expr {:?}", value);
+        if let ty::ConstKind::Param(param) = value {
+            return self.encode_const_param(ty, *param);
+        }
         let scalar_value = self.const_eval_intlike(value)?;
 
         let expr = match ty.kind() {
@@ -1035,6 +1506,62 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         Ok(expr)
     }
 
+    /// The key under which the value of a const generic parameter is stored in
+    /// the maps of type substitutions, which only map types to types.
+    /// FIXME: this is a hack to support const generics. See issue #187.
+    pub fn const_param_key(&self, value: &'tcx ty::Const<'tcx>) -> ty::Ty<'tcx> {
+        let tcx = self.env().tcx();
+        tcx.mk_ty(ty::TyKind::Array(tcx.types.unit, value))
+    }
+
+    /// Encode the value of a const generic parameter. If the current type
+    /// substitution does not give a value to the parameter, it is encoded as
+    /// an unknown constant within the bounds of its type.
+    fn encode_const_param(
+        &self,
+        ty: ty::Ty<'tcx>,
+        param: ty::ParamConst,
+    ) -> EncodingResult<vir::Expr> {
+        let tcx = self.env().tcx();
+        let key = self.const_param_key(tcx.mk_const_param(param.index, param.name, ty));
+        let param = match self.resolve_typaram(key).kind() {
+            ty::TyKind::Array(_, value) => match value.val {
+                ty::ConstKind::Param(resolved) => resolved,
+                ref resolved => return self.encode_const_expr(ty, resolved),
+            },
+            _ => param,
+        };
+        let function_name = format!("builtin$const_param${}${}", param.name, param.index);
+        let return_type = self.encode_snapshot_type(ty)?;
+        if !self.const_param_functions.borrow().contains_key(&function_name) {
+            let result: vir::Expr = vir_local!{ __result: {return_type.clone()} }.into();
+            let posts = match self.encode_integer_bounds(ty) {
+                Some((lower, upper)) => vec![
+                    vir!([lower] <= [result]),
+                    vir!([result] <= [upper]),
+                ],
+                None => vec![],
+            };
+            let function = vir::Function {
+                name: function_name.clone(),
+                formal_args: vec![],
+                return_type: return_type.clone(),
+                pres: vec![],
+                posts,
+                body: None,
+            };
+            let identifier = self.insert_function(function);
+            self.const_param_functions.borrow_mut().insert(function_name.clone(), identifier);
+        }
+        Ok(vir::Expr::func_app(
+            function_name,
+            vec![],
+            vec![],
+            return_type,
+            vir::Position::default(),
+        ))
+    }
+
     pub fn encode_int_cast(&self, value: u128, ty: ty::Ty<'tcx>) -> vir::Expr {
         trace!("encode_int_cast {:?} as {:?}", value, ty);
 
@@ -1161,27 +1688,34 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             self.failed_pure_functions.borrow_mut().insert(key.clone());
 
             let wrapper_def_id = self.get_wrapper_def_id(proc_def_id);
-            let procedure = self.env.get_procedure(wrapper_def_id);
-            let pure_function_encoder =
-                PureFunctionEncoder::new(self, proc_def_id, procedure.get_mir(), false, proc_def_id);
-            let (mut function, needs_patching) = if let Some(predicate_body) = self.get_predicate_body(proc_def_id) {
-                (pure_function_encoder.encode_predicate_function(predicate_body)?, false)
-            } else if self.is_trusted(proc_def_id) {
-                (pure_function_encoder.encode_bodyless_function()?, false)
+            let function = if self.is_bodyless_trait_method(wrapper_def_id) {
+                TraitFunctionEncoder::new(self, proc_def_id).encode_function()?
             } else {
-                (pure_function_encoder.encode_function()?, true)
-            };
+                let procedure = self.env.get_procedure(wrapper_def_id);
+                let pure_function_encoder =
+                    PureFunctionEncoder::new(self, proc_def_id, procedure.get_mir(), false, proc_def_id);
+                let (mut function, needs_patching) = if let Some(predicate_body) = self.get_predicate_body(proc_def_id) {
+                    (pure_function_encoder.encode_predicate_function(predicate_body)?, false)
+                } else if self.is_trusted(proc_def_id)
+                    || self.is_pure_memoized(proc_def_id)
+                    || self.is_skipped_as_unsupported(proc_def_id)
+                {
+                    (pure_function_encoder.encode_bodyless_function()?, false)
+                } else {
+                    (pure_function_encoder.encode_function()?, true)
+                };
 
-            if needs_patching {
-                self.mirror_encoder
-                    .borrow_mut()
-                    .encode_mirrors(proc_def_id, &mut function);
-            }
+                if needs_patching {
+                    self.mirror_encoder
+                        .borrow_mut()
+                        .encode_mirrors(proc_def_id, &mut function);
+                }
 
-            function = self.snapshot_encoder
-                .borrow_mut()
-                .patch_snapshots_function(self, function)
-                .with_span(procedure.get_span())?;
+                self.snapshot_encoder
+                    .borrow_mut()
+                    .patch_snapshots_function(self, function)
+                    .with_span(procedure.get_span())?
+            };
 
             self.log_vir_program_before_viper(function.to_string());
             self.failed_pure_functions.borrow_mut().remove(&key);
@@ -1208,7 +1742,6 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         parent_def_id: ProcedureDefId,
     ) -> SpannedEncodingResult<(String, vir::Type)> {
         let wrapper_def_id = self.get_wrapper_def_id(proc_def_id);
-        let procedure = self.env.get_procedure(wrapper_def_id);
 
         assert!(
             self.is_pure(proc_def_id),
@@ -1216,15 +1749,24 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             proc_def_id
         );
 
-        let pure_function_encoder =
-            PureFunctionEncoder::new(self, proc_def_id, procedure.get_mir(), false, parent_def_id);
-
         let substs = self.current_tymap().into_iter().collect();
         if let Err(error) = self.encode_pure_function_def(proc_def_id, substs) {
             self.register_encoding_error(error);
             debug!("Error encoding pure function: {:?}", proc_def_id);
         }
 
+        if self.is_bodyless_trait_method(wrapper_def_id) {
+            let trait_function_encoder = TraitFunctionEncoder::new(self, proc_def_id);
+            return Ok((
+                trait_function_encoder.encode_function_name(),
+                trait_function_encoder.encode_function_return_type()?,
+            ));
+        }
+
+        let procedure = self.env.get_procedure(wrapper_def_id);
+        let pure_function_encoder =
+            PureFunctionEncoder::new(self, proc_def_id, procedure.get_mir(), false, parent_def_id);
+
         Ok((
             pure_function_encoder.encode_function_name(),
             pure_function_encoder.encode_function_return_type()?,
@@ -1269,48 +1811,148 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
 
     pub fn process_encoding_queue(&mut self) {
         self.initialize();
+        progress::begin(Phase::Encoding, self.encoding_queue.borrow().len());
         while !self.encoding_queue.borrow().is_empty() {
             let (proc_def_id, substs) = self.encoding_queue.borrow_mut().pop().unwrap();
-
+            assert!(substs.is_empty());
             let proc_name = self.env.get_absolute_item_name(proc_def_id);
-            let proc_def_path = self.env.get_item_def_path(proc_def_id);
-            let wrapper_def_id = self.get_wrapper_def_id(proc_def_id);
-            let proc_span = self.env.get_item_span(wrapper_def_id);
-            info!(
-                "Encoding: {} from {:?} ({})",
-                proc_name, proc_span, proc_def_path
+            progress::start(Phase::Encoding, &proc_name);
+            let program = self.encode_for_procedure(
+                proc_def_id,
+                || self.encode_queued_procedure(proc_def_id),
             );
-            assert!(substs.is_empty());
-            if self.is_pure(proc_def_id) {
-                // Check that the pure Rust function satisfies the basic
-                // requirements by trying to encode it as a Viper function,
-                // which will automatically run the validity checks.
-
-                // TODO: Make sure that this encoded function does not end up in
-                // the Viper file because that would be unsound.
-                if let Err(error) = self.encode_pure_function_def(proc_def_id, Vec::new()) {
-                    self.register_encoding_error(error);
-                    debug!("Error encoding function: {:?}", proc_def_id);
-                    // Skip encoding the function as a method.
-                    continue;
-                }
-            }
-            if self.is_trusted(proc_def_id) {
-                debug!(
-                    "Trusted procedure will not be encoded or verified: {:?}",
-                    proc_def_id
-                );
+            let outcome = if program.is_some() {
+                "encoded"
+            } else if self.is_trusted(proc_def_id) {
+                "skipped trusted"
+            } else if self.is_pure_memoized(proc_def_id) {
+                "skipped assumed pure"
             } else {
-                if let Err(error) = self.encode_procedure(proc_def_id) {
-                    self.register_encoding_error(error);
-                    debug!("Error encoding function: {:?}", proc_def_id);
-                } else {
-                    let program = self.finalize_viper_program(proc_name);
-                    self.programs.push(program);
-                }
+                "failed to encode"
+            };
+            progress::finish(Phase::Encoding, &proc_name, outcome);
+            if let Some(program) = program {
+                self.programs.push(program);
+            }
+        }
+        progress::end(Phase::Encoding);
+    }
+
+    /// Encode a procedure of the encoding queue to the Viper program that
+    /// verifies it, if any.
+    fn encode_queued_procedure(&self, proc_def_id: ProcedureDefId) -> Option<vir::Program> {
+        let proc_name = self.env.get_absolute_item_name(proc_def_id);
+        let proc_def_path = self.env.get_item_def_path(proc_def_id);
+        let wrapper_def_id = self.get_wrapper_def_id(proc_def_id);
+        let proc_span = self.env.get_item_span(wrapper_def_id);
+        info!(
+            "Encoding: {} from {:?} ({})",
+            proc_name, proc_span, proc_def_path
+        );
+        if self.is_pure(proc_def_id) {
+            // Check that the pure Rust function satisfies the basic
+            // requirements by trying to encode it as a Viper function,
+            // which will automatically run the validity checks.
+
+            // TODO: Make sure that this encoded function does not end up in
+            // the Viper file because that would be unsound.
+            if let Err(error) = self.encode_pure_function_def(proc_def_id, Vec::new()) {
+                self.register_encoding_error(error);
+                debug!("Error encoding function: {:?}", proc_def_id);
+                // Skip encoding the function as a method.
+                return None;
             }
+        }
+        if self.is_trusted(proc_def_id) {
+            debug!(
+                "Trusted procedure will not be encoded or verified: {:?}",
+                proc_def_id
+            );
+            None
+        } else if self.is_pure_memoized(proc_def_id) {
+            debug!(
+                "Procedure assumed to be pure will not be encoded or verified: {:?}",
+                proc_def_id
+            );
+            None
+        } else if let Err(error) = self.encode_procedure(proc_def_id) {
+            self.register_encoding_error(error);
+            debug!("Error encoding function: {:?}", proc_def_id);
+            None
+        } else {
+            Some(self.finalize_viper_program(proc_name))
+        }
+    }
+
+    /// Report all the constructs of the procedure that cannot be encoded in a
+    /// single error, before its encoding starts. Returns whether the procedure
+    /// can be encoded; the other procedures are not encoded at all.
+    pub fn check_supported_constructs(&self, proc_def_id: ProcedureDefId) -> bool {
+        self.encode_for_procedure(
+            proc_def_id,
+            || self.report_unsupported_constructs(proc_def_id),
+        )
+    }
+
+    fn report_unsupported_constructs(&self, proc_def_id: ProcedureDefId) -> bool {
+        // Unsupported code is then encoded as `assert false`.
+        if config::allow_unreachable_unsupported_code()
+            || self.is_trusted(proc_def_id)
+            || self.is_pure_memoized(proc_def_id)
+        {
+            return true;
+        }
+        let wrapper_def_id = self.get_wrapper_def_id(proc_def_id);
+        let procedure = self.env.get_procedure(wrapper_def_id);
+        let constructs = procedure.get_unsupported_constructs();
+        if constructs.is_empty() {
+            return true;
+        }
 
+        let mut reasons: Vec<&str> = vec![];
+        for construct in &constructs {
+            if !reasons.contains(&construct.reason) {
+                reasons.push(construct.reason);
+            }
         }
+        let mut span = MultiSpan::from_spans(
+            constructs.iter().map(|construct| construct.span).collect()
+        );
+        let message = if let [reason] = reasons.as_slice() {
+            reason.to_string()
+        } else {
+            for construct in &constructs {
+                span.push_span_label(construct.span, construct.reason.to_string());
+            }
+            format!(
+                "the function `{}` uses features that are not supported: {}",
+                self.env.get_item_name(proc_def_id),
+                reasons.join("; "),
+            )
+        };
+        let help = if config::skip_unsupported_features() {
+            "the function is not verified, and its callers only rely on its specification"
+        } else {
+            "the function is not verified. Set the configuration parameter \
+            SKIP_UNSUPPORTED_FEATURES to report this as a warning and verify the rest of the crate"
+        };
+        self.register_encoding_error(
+            SpannedEncodingError::unsupported(message, span).set_help(help)
+        );
+        self.unsupported_procedures.borrow_mut().insert(proc_def_id);
+        false
+    }
+
+    /// Whether the procedure uses unsupported constructs, and is therefore not
+    /// encoded (see `check_supported_constructs`).
+    pub fn is_unsupported(&self, def_id: ProcedureDefId) -> bool {
+        self.unsupported_procedures.borrow().contains(&def_id)
+    }
+
+    /// Whether calls of the procedure rely only on its specification because
+    /// it uses unsupported constructs (see `check_supported_constructs`).
+    fn is_skipped_as_unsupported(&self, def_id: ProcedureDefId) -> bool {
+        config::skip_unsupported_features() && self.is_unsupported(def_id)
     }
 
     pub fn is_trusted(&self, def_id: ProcedureDefId) -> bool {
@@ -1319,18 +1961,61 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         result
     }
 
+    /// The resource limit of the SMT solver for the verification of the
+    /// procedure: the one of its `#[prusti::smt_rlimit(N)]` attribute, if any,
+    /// otherwise the one of the `smt_rlimit` setting.
+    pub fn get_smt_rlimit(&self, def_id: ProcedureDefId) -> Option<u64> {
+        self.def_spec.get(&def_id)
+            .and_then(|spec| spec.expect_procedure().smt_rlimit)
+            .or_else(config::smt_rlimit)
+    }
+
     pub fn is_pure(&self, def_id: ProcedureDefId) -> bool {
         let result = self.def_spec.get(&def_id).map_or(false, |spec| spec.expect_procedure().pure);
         trace!("is_pure {:?} = {}", def_id, result);
         result
     }
 
+    /// Whether the procedure is marked with `#[pure_memoized]`. Such a pure
+    /// function is encoded from its signature and contract only, as a Viper
+    /// function without a body: calls with equal arguments are thus assumed to
+    /// return equal results, while its body, which might fill a cache behind a
+    /// `RefCell`, is not checked to be pure.
+    pub fn is_pure_memoized(&self, def_id: ProcedureDefId) -> bool {
+        let result = self.def_spec.get(&def_id).map_or(false, |spec| spec.expect_procedure().pure_memoized);
+        trace!("is_pure_memoized {:?} = {}", def_id, result);
+        result
+    }
+
+    pub fn is_terminating(&self, def_id: ProcedureDefId) -> bool {
+        let result = self.def_spec.get(&def_id).map_or(false, |spec| spec.expect_procedure().terminates);
+        trace!("is_terminating {:?} = {}", def_id, result);
+        result
+    }
+
+    /// Whether the call from `caller` to `callee` belongs to a cycle of the call graph.
+    pub fn is_recursive_call(&self, caller: ProcedureDefId, callee: ProcedureDefId) -> bool {
+        let mut call_graph = self.call_graph.borrow_mut();
+        let call_graph = call_graph.get_or_insert_with(|| {
+            self.env.build_call_graph(&self.env.get_annotated_procedures())
+        });
+        let result = call_graph.is_recursive_call(caller, callee);
+        trace!("is_recursive_call {:?} -> {:?} = {}", caller, callee, result);
+        result
+    }
+
     pub fn get_predicate_body(&self, def_id: ProcedureDefId) -> Option<&typed::Assertion<'tcx>> {
         let result = self.def_spec.get(&def_id).map_or(None, |spec| spec.expect_procedure().predicate_body.as_ref());
         trace!("get_predicate_body {:?} = {:?}", def_id, result);
         result
     }
 
+    pub fn get_decreases_measure(&self, def_id: ProcedureDefId) -> Option<&typed::Assertion<'tcx>> {
+        let result = self.def_spec.get(&def_id).map_or(None, |spec| spec.expect_procedure().decreases.as_ref());
+        trace!("get_decreases_measure {:?} = {:?}", def_id, result);
+        result
+    }
+
     pub fn has_extern_spec(&self, def_id: ProcedureDefId) -> bool {
         // FIXME: eventually, procedure specs (the entries in def_spec) should
         // have an `is_extern_spec` field. For now, due to the way we handle
@@ -1342,6 +2027,74 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         result
     }
 
+    /// If the called trait method `def_id` resolves (with `substs`) to a method
+    /// of an implementation that has an external specification, returns that
+    /// method together with its substitutions. Otherwise, returns `def_id` and
+    /// `substs` unchanged.
+    pub fn resolve_extern_spec_call(
+        &self,
+        caller_def_id: ProcedureDefId,
+        def_id: ProcedureDefId,
+        substs: ty::subst::SubstsRef<'tcx>,
+    ) -> (ProcedureDefId, ty::subst::SubstsRef<'tcx>) {
+        let tcx = self.env().tcx();
+        if tcx.trait_of_item(def_id).is_none() {
+            return (def_id, substs);
+        }
+        let param_env = tcx.param_env(caller_def_id);
+        if let Ok(Some(instance)) = ty::Instance::resolve(tcx, param_env, def_id, substs) {
+            let impl_def_id = instance.def_id();
+            if impl_def_id != def_id && self.has_extern_spec(impl_def_id) {
+                trace!("resolve_extern_spec_call {:?} = {:?}", def_id, impl_def_id);
+                return (impl_def_id, instance.substs);
+            }
+        }
+        (def_id, substs)
+    }
+
+    /// If the called pure trait method `def_id` resolves (with `substs`, after
+    /// replacing the type parameters of the current substitution) to a pure
+    /// method of an implementation, returns that method together with its
+    /// substitutions. Otherwise, for example when the receiver is a type
+    /// parameter, the call uses the contract of the trait declaration and
+    /// `def_id` and `substs` are returned unchanged.
+    pub fn resolve_pure_trait_call(
+        &self,
+        caller_def_id: ProcedureDefId,
+        def_id: ProcedureDefId,
+        substs: ty::subst::SubstsRef<'tcx>,
+    ) -> (ProcedureDefId, ty::subst::SubstsRef<'tcx>) {
+        use rustc_middle::ty::fold::TypeFoldable;
+        let tcx = self.env().tcx();
+        if tcx.trait_of_item(def_id).is_none() || !self.is_pure(def_id) {
+            return (def_id, substs);
+        }
+        let resolved_substs = tcx.mk_substs(substs.iter().map(|arg| match arg.unpack() {
+            ty::subst::GenericArgKind::Type(ty) => self.resolve_typaram(ty).into(),
+            _ => arg,
+        }));
+        // A call on a type parameter cannot be resolved to an implementation.
+        if resolved_substs.needs_subst() {
+            return (def_id, substs);
+        }
+        let param_env = tcx.param_env(caller_def_id);
+        if let Ok(Some(instance)) = ty::Instance::resolve(tcx, param_env, def_id, resolved_substs) {
+            let impl_def_id = instance.def_id();
+            if impl_def_id != def_id && self.is_pure(impl_def_id) {
+                trace!("resolve_pure_trait_call {:?} = {:?}", def_id, impl_def_id);
+                return (impl_def_id, instance.substs);
+            }
+        }
+        (def_id, substs)
+    }
+
+    /// Whether `def_id` is a method that a trait declares without a default
+    /// body, which can only be encoded from its signature and contract.
+    fn is_bodyless_trait_method(&self, def_id: ProcedureDefId) -> bool {
+        let tcx = self.env().tcx();
+        tcx.trait_of_item(def_id).is_some() && !tcx.is_mir_available(def_id)
+    }
+
     /// Convert a potential type parameter to a concrete type.
     pub fn resolve_typaram(&self, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
         // TODO: better generics ...
@@ -1358,6 +2111,16 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
                 let rep = self.tymap.get(&ty).unwrap_or(&ty);
                 rep.super_fold_with(self)
             }
+            fn fold_const(&mut self, value: &'tcx ty::Const<'tcx>) -> &'tcx ty::Const<'tcx> {
+                // See `Encoder::const_param_key`.
+                if let ty::ConstKind::Param(_) = value.val {
+                    let key = self.tcx.mk_ty(ty::TyKind::Array(self.tcx.types.unit, value));
+                    if let Some(ty::TyKind::Array(_, rep)) = self.tymap.get(&key).map(|ty| ty.kind()) {
+                        return *rep;
+                    }
+                }
+                value.super_fold_with(self)
+            }
         }
         ty.fold_with(&mut Resolver {
             tcx: self.env().tcx(),
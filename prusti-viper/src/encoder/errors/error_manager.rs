@@ -9,7 +9,7 @@ use std::collections::HashMap;
 use rustc_span::source_map::SourceMap;
 use rustc_span::MultiSpan;
 use viper::VerificationError;
-use prusti_interface::PrustiError;
+use prusti_interface::{error_codes, PrustiError};
 use log::debug;
 use prusti_interface::data::ProcedureDefId;
 
@@ -34,14 +34,18 @@ pub enum PanicCause {
 /// required to describe the error.
 #[derive(Clone, Debug)]
 pub enum ErrorCtxt {
-    /// A Viper `assert false` that encodes a Rust panic
-    Panic(PanicCause),
+    /// A Viper `assert false` that encodes a Rust panic, with the panic message
+    /// if it is a string literal
+    Panic(PanicCause, Option<String>),
     /// A Viper `exhale expr` that encodes the call of a Rust procedure with precondition `expr`
     ExhaleMethodPrecondition,
     /// A Viper `assert expr` that encodes the call of a Rust procedure with precondition `expr`
     AssertMethodPostcondition,
     /// A Viper `assert expr` that encodes the call of a Rust procedure with precondition `expr`
     AssertMethodPostconditionTypeInvariants,
+    /// A Viper `assert site ==> expr` that encodes the end of a Rust procedure with
+    /// postcondition `expr`, reached from the `return` expression with the given span
+    AssertMethodPostconditionOnReturn(MultiSpan),
     /// A Viper `exhale expr` that encodes the end of a Rust procedure with postcondition `expr`
     ExhaleMethodPostcondition,
     /// A Viper `exhale expr` that exhales the permissions of a loop invariant `expr`
@@ -50,13 +54,30 @@ pub enum ErrorCtxt {
     /// A Viper `assert expr` that asserts the functional specification of a loop invariant `expr`
     AssertLoopInvariantOnEntry,
     AssertLoopInvariantAfterIteration,
+    /// A Viper `assert expr` that checks that the termination measure of a loop decreases
+    AssertLoopVariant,
+    /// A Viper `assert expr` that checks that the termination measure decreases on a call
+    /// that belongs to a cycle of the call graph
+    AssertTerminationMeasure,
+    /// A Viper `assert expr` that encodes a `prusti_assert!(expr)` in the body of a procedure
+    AssertPrustiAssertion,
     /// A Viper `assert false` that encodes the failure (panic) of an `assert` Rust terminator
     /// Arguments: the message of the Rust assertion
     AssertTerminator(String),
+    /// A Viper `assert expr` that checks that the result of a Rust arithmetic operation fits
+    /// into the range of its type.
+    /// Arguments: the message of the Rust overflow assertion, the bound that might be exceeded
+    OverflowCheck(String, String),
+    /// A Viper `assert expr` that checks that a Rust division or remainder does not divide
+    /// by zero or overflow.
+    /// Arguments: the message of the Rust assertion, the cause of the failure
+    DivisionCheck(String, String),
     /// A Viper `assert false` in the context of a bounds check
     BoundsCheckAssert,
     /// A Viper `assert false` that encodes an `abort` Rust terminator
     AbortTerminator,
+    /// A Viper `assert false` that encodes a call of `unreachable_unchecked()`
+    UnreachableUnchecked,
     /// A Viper `assert false` that encodes an `unreachable` Rust terminator
     #[allow(dead_code)]
     UnreachableTerminator,
@@ -86,13 +107,17 @@ pub enum ErrorCtxt {
     /// A Viper pure function call with `false` precondition that encodes a Rust panic in a pure function
     PanicInPureFunction(PanicCause),
     /// A Viper `assert e1 ==> e2` that encodes a weakening of the precondition
-    /// of a method implementation of a trait
-    AssertMethodPreconditionWeakening(MultiSpan),
+    /// of a method implementation of a trait. The spans are the ones of the
+    /// precondition of the method and of the precondition of the trait.
+    AssertMethodPreconditionWeakening(MultiSpan, MultiSpan),
     /// A Viper `assert e1 ==> e2` that encodes a strengthening of the precondition
     /// of a method implementation of a trait.
     AssertMethodPostconditionStrengthening(MultiSpan),
     /// A cast like `usize as u32`.
     TypeCast,
+    /// A Viper `assert expr` that checks that the borrow flag of a `RefCell`
+    /// allows a new borrow of it.
+    RefCellBorrowConflict,
     /// A Viper `assert false` that encodes an unsupported feature
     Unsupported(String),
 }
@@ -152,6 +177,37 @@ impl<'tcx> ErrorManager<'tcx>
         pos
     }
 
+    /// The file and line of the source code at which a position was
+    /// registered, e.g. `src/main.rs:5`.
+    pub fn get_source_location(&self, pos: &Position) -> Option<String> {
+        let span = self.source_span.get(&pos.id())?.primary_span()?;
+        let location = self.codemap.lookup_char_pos(span.source_callsite().lo());
+        Some(format!("{}:{}", location.file.name.prefer_local(), location.line))
+    }
+
+    /// The fact that would rule out the failure of a bounds check, derived
+    /// from the source code of an index expression like `v[i]`.
+    fn bounds_check_missing_fact(&self, span: &MultiSpan) -> Option<String> {
+        let snippet = self.codemap.span_to_snippet(span.primary_span()?).ok()?;
+        let snippet = snippet.trim().strip_suffix(']')?;
+        // Find the bracket that opens the index, skipping nested indexing.
+        let mut depth = 0;
+        let open = snippet.char_indices().rev().find(|&(_, c)| {
+            match c {
+                ']' => depth += 1,
+                '[' if depth == 0 => return true,
+                '[' => depth -= 1,
+                _ => {}
+            }
+            false
+        })?.0;
+        let (base, index) = (snippet[..open].trim(), snippet[open + 1..].trim());
+        if base.is_empty() || index.is_empty() || index.contains("..") {
+            return None;
+        }
+        Some(format!("{} < {}.len()", index, base))
+    }
+
     pub fn get_def_id(&self, ver_error: &VerificationError) -> Option<&ProcedureDefId> {
         ver_error.pos_id.as_ref()
             .and_then(|id| id.parse().ok())
@@ -250,40 +306,59 @@ impl<'tcx> ErrorManager<'tcx>
             }
         };
 
-        match (ver_error.full_id.as_str(), error_ctxt) {
-            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Generic)) => {
-                PrustiError::verification("statement might panic", error_span)
-                    .set_failing_assertion(opt_cause_span)
-            }
-
-            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Panic)) => {
-                PrustiError::verification("panic!(..) statement might be reachable", error_span)
-                    .set_failing_assertion(opt_cause_span)
+        let prusti_error = match (ver_error.full_id.as_str(), error_ctxt) {
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(ref cause, ref opt_message)) => {
+                let description = match cause {
+                    PanicCause::Generic => "statement might panic",
+                    PanicCause::Panic => "panic!(..) statement might be reachable",
+                    PanicCause::Assert | PanicCause::DebugAssert => {
+                        "the asserted expression might not hold"
+                    }
+                    PanicCause::Unreachable => "unreachable!(..) statement might be reachable",
+                    PanicCause::Unimplemented => "unimplemented!(..) statement might be reachable",
+                };
+                let error = PrustiError::verification(description, error_span)
+                    .set_code(error_codes::PANIC)
+                    .set_failing_assertion(opt_cause_span);
+                if let Some(message) = opt_message {
+                    error.add_note(format!("the panic message is \"{}\"", message), None)
+                } else {
+                    error
+                }
             }
 
-            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Assert)) |
-            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::DebugAssert)) => {
-                    PrustiError::verification("the asserted expression might not hold", error_span)
+            ("assert.failed:assertion.false", ErrorCtxt::AssertTerminator(ref message)) => {
+                PrustiError::verification(format!("assertion might fail with \"{}\"", message), error_span)
+                    .set_code(error_codes::PANIC)
                     .set_failing_assertion(opt_cause_span)
             }
 
-            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Unreachable)) => {
-                PrustiError::verification("unreachable!(..) statement might be reachable", error_span)
+            ("assert.failed:assertion.false", ErrorCtxt::OverflowCheck(ref message, ref bound)) |
+            ("assert.failed:assertion.false", ErrorCtxt::DivisionCheck(ref message, ref bound)) => {
+                PrustiError::verification(
+                    format!("assertion might fail with \"{}\": {}", message, bound),
+                    error_span
+                ).set_code(error_codes::PANIC)
                     .set_failing_assertion(opt_cause_span)
             }
 
-            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Unimplemented)) => {
-                PrustiError::verification("unimplemented!(..) statement might be reachable", error_span)
+            ("assert.failed:assertion.false", ErrorCtxt::RefCellBorrowConflict) => {
+                PrustiError::verification("the `RefCell` might already be borrowed", error_span)
+                    .set_code(error_codes::PANIC)
                     .set_failing_assertion(opt_cause_span)
             }
 
-            ("assert.failed:assertion.false", ErrorCtxt::AssertTerminator(ref message)) => {
-                PrustiError::verification(format!("assertion might fail with \"{}\"", message), error_span)
+            ("assert.failed:assertion.false", ErrorCtxt::AbortTerminator) => {
+                PrustiError::verification("statement might abort", error_span)
+                    .set_code(error_codes::PANIC)
                     .set_failing_assertion(opt_cause_span)
             }
 
-            ("assert.failed:assertion.false", ErrorCtxt::AbortTerminator) => {
-                PrustiError::verification("statement might abort", error_span)
+            ("assert.failed:assertion.false", ErrorCtxt::UnreachableUnchecked) => {
+                PrustiError::verification(
+                    "unreachable_unchecked() might be reachable",
+                    error_span
+                ).set_code(error_codes::PANIC)
                     .set_failing_assertion(opt_cause_span)
             }
 
@@ -297,6 +372,7 @@ impl<'tcx> ErrorManager<'tcx>
 
             ("assert.failed:assertion.false", ErrorCtxt::ExhaleMethodPrecondition) => {
                 PrustiError::verification("precondition might not hold.", error_span)
+                    .set_code(error_codes::PRECONDITION)
                     .set_failing_assertion(opt_cause_span)
             }
 
@@ -304,16 +380,19 @@ impl<'tcx> ErrorManager<'tcx>
                 PrustiError::verification(
                     "implicit type invariant expected by the function call might not hold.",
                     error_span
-                ).set_failing_assertion(opt_cause_span)
+                ).set_code(error_codes::TYPE_INVARIANT)
+                    .set_failing_assertion(opt_cause_span)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::ExhaleMethodPostcondition) => {
                 PrustiError::verification("postcondition might not hold.", error_span)
+                    .set_code(error_codes::POSTCONDITION)
                     .push_primary_span(opt_cause_span)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::ExhaleLoopInvariantOnEntry) => {
                 PrustiError::verification("loop invariant might not hold in the first loop iteration.", error_span)
+                    .set_code(error_codes::LOOP_INVARIANT)
                     .push_primary_span(opt_cause_span)
             }
 
@@ -321,11 +400,13 @@ impl<'tcx> ErrorManager<'tcx>
                 PrustiError::verification(
                     "implicit type invariant of a variable might not hold on loop entry.",
                     error_span
-                ).push_primary_span(opt_cause_span)
+                ).set_code(error_codes::TYPE_INVARIANT)
+                    .push_primary_span(opt_cause_span)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertLoopInvariantOnEntry) => {
                 PrustiError::verification("loop invariant might not hold in the first loop iteration.", error_span)
+                    .set_code(error_codes::LOOP_INVARIANT)
                     .push_primary_span(opt_cause_span)
             }
 
@@ -333,28 +414,56 @@ impl<'tcx> ErrorManager<'tcx>
                 PrustiError::verification(
                     "loop invariant might not hold after a loop iteration that preserves the loop condition.",
                     error_span
-                ).push_primary_span(opt_cause_span)
+                ).set_code(error_codes::LOOP_INVARIANT)
+                    .push_primary_span(opt_cause_span)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertLoopInvariantAfterIteration) => {
                 PrustiError::verification(
                     "loop invariant might not hold after a loop iteration that preserves the loop condition.",
                     error_span
-                ).push_primary_span(opt_cause_span)
+                ).set_code(error_codes::LOOP_INVARIANT)
+                    .push_primary_span(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertLoopVariant) => {
+                PrustiError::verification(
+                    "loop variant might be negative or might not decrease.",
+                    error_span
+                ).set_code(error_codes::TERMINATION)
+                    .push_primary_span(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertTerminationMeasure) => {
+                PrustiError::verification(
+                    "termination measure might be negative or might not decrease.",
+                    error_span
+                ).set_code(error_codes::TERMINATION)
+                    .push_primary_span(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertPrustiAssertion) => {
+                PrustiError::verification(
+                    "the asserted expression of prusti_assert!(..) might not hold.",
+                    error_span
+                ).set_code(error_codes::PRUSTI_ASSERTION)
+                    .push_primary_span(opt_cause_span)
             }
 
             ("application.precondition:assertion.false", ErrorCtxt::PureFunctionCall) => {
                 PrustiError::verification(
                     "precondition of pure function call might not hold.",
                     error_span
-                ).set_failing_assertion(opt_cause_span)
+                ).set_code(error_codes::PRECONDITION)
+                    .set_failing_assertion(opt_cause_span)
             }
 
             ("package.failed:assertion.false", ErrorCtxt::PackageMagicWandForPostcondition) => {
                 PrustiError::verification(
                     "pledge in the postcondition might not hold.",
                     error_span
-                ).push_primary_span(opt_cause_span)
+                ).set_code(error_codes::PLEDGE)
+                    .push_primary_span(opt_cause_span)
             }
 
             (
@@ -364,7 +473,8 @@ impl<'tcx> ErrorManager<'tcx>
                 PrustiError::verification(
                     "diverging function call in pure function might be reachable.",
                     error_span
-                ).push_primary_span(opt_cause_span)
+                ).set_code(error_codes::PANIC)
+                    .push_primary_span(opt_cause_span)
             }
 
             (
@@ -372,6 +482,7 @@ impl<'tcx> ErrorManager<'tcx>
                 ErrorCtxt::PanicInPureFunction(PanicCause::Generic),
             ) => {
                 PrustiError::disabled_verification("statement in pure function might panic", error_span)
+                    .set_code(error_codes::PANIC)
                     .push_primary_span(opt_cause_span)
             }
 
@@ -382,7 +493,8 @@ impl<'tcx> ErrorManager<'tcx>
                 PrustiError::disabled_verification(
                     "panic!(..) statement in pure function might panic",
                     error_span
-                ).push_primary_span(opt_cause_span)
+                ).set_code(error_codes::PANIC)
+                    .push_primary_span(opt_cause_span)
             }
 
             (
@@ -390,6 +502,7 @@ impl<'tcx> ErrorManager<'tcx>
                 ErrorCtxt::PanicInPureFunction(PanicCause::Assert),
             ) => {
                 PrustiError::disabled_verification("asserted expression might not hold", error_span)
+                    .set_code(error_codes::PANIC)
                     .set_failing_assertion(opt_cause_span)
             }
 
@@ -400,7 +513,8 @@ impl<'tcx> ErrorManager<'tcx>
                 PrustiError::disabled_verification(
                     "unreachable!(..) statement in pure function might be reachable",
                     error_span
-                ).push_primary_span(opt_cause_span)
+                ).set_code(error_codes::PANIC)
+                    .push_primary_span(opt_cause_span)
             }
 
             (
@@ -410,7 +524,8 @@ impl<'tcx> ErrorManager<'tcx>
                 PrustiError::disabled_verification(
                     "unimplemented!(..) statement in pure function might be reachable",
                     error_span
-                ).push_primary_span(opt_cause_span)
+                ).set_code(error_codes::PANIC)
+                    .push_primary_span(opt_cause_span)
             }
 
             ("postcondition.violated:assertion.false", ErrorCtxt::PureFunctionDefinition) |
@@ -419,7 +534,8 @@ impl<'tcx> ErrorManager<'tcx>
                 PrustiError::disabled_verification(
                     "postcondition of pure function definition might not hold",
                     error_span
-                ).push_primary_span(opt_cause_span)
+                ).set_code(error_codes::POSTCONDITION)
+                    .push_primary_span(opt_cause_span)
             }
 
             (
@@ -429,26 +545,37 @@ impl<'tcx> ErrorManager<'tcx>
                 PrustiError::disabled_verification(
                     format!("assertion might fail with \"{}\"", message),
                     error_span
-                ).set_failing_assertion(opt_cause_span)
+                ).set_code(error_codes::PANIC)
+                    .set_failing_assertion(opt_cause_span)
             },
 
             ("application.precondition:assertion.false", ErrorCtxt::TypeCast) => {
                 PrustiError::verification(
                     "value might not fit into the target type.",
                     error_span
-                ).set_failing_assertion(opt_cause_span)
+                ).set_code(error_codes::CAST_OVERFLOW)
+                    .set_failing_assertion(opt_cause_span)
             }
 
             ("apply.failed:assertion.false", ErrorCtxt::ApplyMagicWandOnExpiry) => {
                 PrustiError::verification("obligation might not hold on borrow expiry", error_span)
+                    .set_code(error_codes::PLEDGE)
                     .set_failing_assertion(opt_cause_span)
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPostcondition) => {
                 PrustiError::verification(format!("postcondition might not hold."), error_span)
+                    .set_code(error_codes::POSTCONDITION)
                     .push_primary_span(opt_cause_span)
             }
 
+            ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPostconditionOnReturn(return_span)) => {
+                PrustiError::verification(format!("postcondition might not hold."), error_span)
+                    .set_code(error_codes::POSTCONDITION)
+                    .push_primary_span(opt_cause_span)
+                    .add_note("the method might return here", return_span.primary_span())
+            }
+
             (
                 "assert.failed:assertion.false",
                 ErrorCtxt::AssertMethodPostconditionTypeInvariants,
@@ -456,7 +583,8 @@ impl<'tcx> ErrorManager<'tcx>
                 PrustiError::verification(
                     format!("type invariants might not hold at the end of the method."),
                     error_span
-                ).set_failing_assertion(opt_cause_span)
+                ).set_code(error_codes::TYPE_INVARIANT)
+                    .set_failing_assertion(opt_cause_span)
             },
 
             ("fold.failed:assertion.false", ErrorCtxt::PackageMagicWandForPostcondition) |
@@ -464,29 +592,40 @@ impl<'tcx> ErrorManager<'tcx>
                 PrustiError::verification(
                     format!("implicit type invariants might not hold at the end of the method."),
                     error_span
-                ).set_failing_assertion(opt_cause_span)
+                ).set_code(error_codes::TYPE_INVARIANT)
+                    .set_failing_assertion(opt_cause_span)
             }
 
-            ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPreconditionWeakening(impl_span)) => {
-                PrustiError::verification(format!("the method's precondition may not be a valid weakening of the trait's precondition."), error_span)
-                    //.push_primary_span(opt_cause_span)
-                    .push_primary_span(Some(&impl_span))
+            ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPreconditionWeakening(impl_span, trait_span)) => {
+                // The failing part is the offending clause of the method
+                let impl_span = opt_cause_span.unwrap_or(impl_span);
+                PrustiError::verification(format!("the method's precondition may not be a valid weakening of the trait's precondition."), impl_span.clone())
+                    .set_code(error_codes::TRAIT_REFINEMENT)
+                    .add_note("the trait's precondition", trait_span.primary_span())
                     .set_help("The trait's precondition should imply the implemented method's precondition.")
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPostconditionStrengthening(impl_span)) => {
-                PrustiError::verification(format!("the method's postcondition may not be a valid strengthening of the trait's postcondition."), error_span)
-                    //.push_primary_span(opt_cause_span)
-                    .push_primary_span(Some(&impl_span))
+                // The failing part is the clause of the trait
+                PrustiError::verification(format!("the method's postcondition may not be a valid strengthening of the trait's postcondition."), impl_span.clone())
+                    .set_code(error_codes::TRAIT_REFINEMENT)
+                    .add_note("the trait's postcondition", opt_cause_span.and_then(|span| span.primary_span()))
                     .set_help("The implemented method's postcondition should imply the trait's postcondition.")
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::BoundsCheckAssert) |
             ("application.precondition:assertion.false", ErrorCtxt::BoundsCheckAssert) => {
-                PrustiError::verification(
+                let missing_fact = self.bounds_check_missing_fact(&error_span);
+                let error = PrustiError::verification(
                     "the array or slice index may be out of bounds".to_string(),
                     error_span,
-                ).set_failing_assertion(opt_cause_span)
+                ).set_code(error_codes::INDEX_OUT_OF_BOUNDS)
+                    .set_failing_assertion(opt_cause_span);
+                if let Some(fact) = missing_fact {
+                    error.set_help(format!("the index access requires `{}`", fact))
+                } else {
+                    error
+                }
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::Unsupported(ref reason)) => {
@@ -531,6 +670,7 @@ impl<'tcx> ErrorManager<'tcx>
                     ASSERT_TIMEOUT to a larger value."
                 )
             }
-        }
+        };
+        prusti_error.set_backend_reason(format!("[{}] {}", ver_error.full_id, ver_error.message))
     }
 }
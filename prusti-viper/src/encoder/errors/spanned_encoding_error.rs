@@ -14,13 +14,14 @@ use crate::encoder::errors::EncodingErrorKind;
 pub struct SpannedEncodingError {
     pub(super) error: EncodingErrorKind,
     span: MultiSpan,
+    help: Option<String>,
 }
 
 pub type SpannedEncodingResult<T> = Result<T, SpannedEncodingError>;
 
 impl From<SpannedEncodingError> for PrustiError {
     fn from(other: SpannedEncodingError) -> Self {
-        match other.error {
+        let error = match other.error {
             EncodingErrorKind::Unsupported(msg) => {
                 PrustiError::unsupported(msg, other.span)
             }
@@ -30,6 +31,10 @@ impl From<SpannedEncodingError> for PrustiError {
             EncodingErrorKind::Internal(msg) => {
                 PrustiError::internal(msg, other.span)
             }
+        };
+        match other.help {
+            Some(help) => error.set_help(help),
+            None => error,
         }
     }
 }
@@ -39,6 +44,7 @@ impl SpannedEncodingError {
         SpannedEncodingError {
             error,
             span: span.into(),
+            help: None,
         }
     }
 
@@ -66,6 +72,12 @@ impl SpannedEncodingError {
         )
     }
 
+    /// Add a hint on how to fix the error
+    pub fn set_help<M: ToString>(mut self, message: M) -> Self {
+        self.help = Some(message.to_string());
+        self
+    }
+
     pub fn kind(&self) -> &EncodingErrorKind {
         &self.error
     }
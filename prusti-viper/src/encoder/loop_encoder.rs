@@ -146,14 +146,21 @@ impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
             .cloned()
             .collect();
 
-        let loop_exit_blocks = loop_info.get_loop_exit_blocks(loop_head);
+        // The exits of nested loops, such as labeled breaks, are not exits
+        // of this loop's guard
+        let loop_exit_blocks: Vec<_> = loop_info
+            .get_loop_exit_blocks(loop_head)
+            .iter()
+            .filter(|&&bb| loop_info.get_loop_depth(bb) == loop_depth)
+            .cloned()
+            .collect();
         let before_invariant_block: BasicBlockIndex = loop_body
             .iter()
             .find(|&&bb| {
                 loop_info.get_loop_depth(bb) == loop_depth
                     && self.mir()[bb].terminator().successors().any(|&succ_bb| {
                         self.procedure.is_reachable_block(succ_bb)
-                            && self.procedure.is_spec_block(succ_bb)
+                            && self.procedure.is_loop_spec_block(succ_bb)
                     })
             })
             .cloned()
@@ -0,0 +1,41 @@
+use rustc_middle::mir;
+use rustc_middle::mir::visit::{MutatingUseContext, NonMutatingUseContext, PlaceContext, Visitor};
+use std::collections::HashSet;
+
+/// Returns the arguments of the procedure that are (partially) moved out or
+/// overwritten in its body. After that, the arguments no longer hold the
+/// values with which the procedure has been called.
+pub fn detect_consumed_args<'tcx>(body: &mir::Body<'tcx>) -> HashSet<mir::Local> {
+    let mut collector = ConsumedArgsCollector {
+        args: body.args_iter().collect(),
+        consumed: HashSet::new(),
+    };
+    collector.visit_body(body);
+    collector.consumed
+}
+
+struct ConsumedArgsCollector {
+    args: HashSet<mir::Local>,
+    consumed: HashSet<mir::Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for ConsumedArgsCollector {
+    fn visit_place(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        context: PlaceContext,
+        location: mir::Location,
+    ) {
+        self.super_place(place, context, location);
+
+        let is_consumed = match context {
+            PlaceContext::NonMutatingUse(NonMutatingUseContext::Move) => true,
+            PlaceContext::MutatingUse(MutatingUseContext::Store)
+            | PlaceContext::MutatingUse(MutatingUseContext::Call) => place.projection.is_empty(),
+            _ => false,
+        };
+        if is_consumed && self.args.contains(&place.local) {
+            self.consumed.insert(place.local);
+        }
+    }
+}
@@ -4,8 +4,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+mod consumed_args_detector;
 mod downcast_detector;
 mod place_encoding;
+mod two_phase_borrows_detector;
 
 use crate::encoder::builtin_encoder::BuiltinFunctionKind;
 use crate::encoder::errors::{
@@ -14,7 +16,7 @@ use crate::encoder::errors::{
 };
 use crate::encoder::Encoder;
 use crate::utils;
-use prusti_common::vir;
+use prusti_common::{vir, vir_local};
 use prusti_common::config;
 use rustc_target::abi;
 use rustc_hir::def_id::DefId;
@@ -29,11 +31,52 @@ use std::{
 use prusti_interface::environment::mir_utils::MirPlace;
 
 use downcast_detector::detect_downcasts;
+pub use consumed_args_detector::detect_consumed_args;
 pub use place_encoding::{PlaceEncoding, ExprOrArrayBase};
+pub use two_phase_borrows_detector::detect_two_phase_activations;
 
 pub static PRECONDITION_LABEL: &'static str = "pre";
 pub static WAND_LHS_LABEL: &'static str = "lhs";
 
+/// The arithmetic methods of integers whose result is modeled precisely.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegerArithmeticKind {
+    /// `wrapping_*`, which returns the wrapped result.
+    Wrapping,
+    /// `checked_*`, which returns `None` if the result overflows.
+    Checked,
+    /// `overflowing_*`, which returns the wrapped result and whether it overflowed.
+    Overflowing,
+}
+
+/// Recognizes the `wrapping_*`, `checked_*` and `overflowing_*` variants of
+/// the addition, subtraction and multiplication of integers, such as
+/// `core::num::<impl u8>::wrapping_add`.
+pub fn get_integer_arithmetic_method(
+    full_func_proc_name: &str,
+) -> Option<(IntegerArithmeticKind, mir::BinOp)> {
+    let method_name = full_func_proc_name
+        .strip_prefix("core::num::<impl ")?
+        .split(">::")
+        .nth(1)?;
+    let (kind, op_name) = if let Some(op_name) = method_name.strip_prefix("wrapping_") {
+        (IntegerArithmeticKind::Wrapping, op_name)
+    } else if let Some(op_name) = method_name.strip_prefix("checked_") {
+        (IntegerArithmeticKind::Checked, op_name)
+    } else if let Some(op_name) = method_name.strip_prefix("overflowing_") {
+        (IntegerArithmeticKind::Overflowing, op_name)
+    } else {
+        return None;
+    };
+    let op = match op_name {
+        "add" => mir::BinOp::Add,
+        "sub" => mir::BinOp::Sub,
+        "mul" => mir::BinOp::Mul,
+        _ => return None,
+    };
+    Some((kind, op))
+}
+
 pub trait PlaceEncoder<'v, 'tcx: 'v> {
 
     fn encoder(&self) -> &Encoder<'v, 'tcx>;
@@ -543,11 +586,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
             mir::BinOp::BitXor if is_bool => vir::Expr::xor(left, right),
             mir::BinOp::BitAnd |
             mir::BinOp::BitOr |
-            mir::BinOp::BitXor => {
-                return Err(EncodingError::unsupported(
-                    "bitwise operations on non-boolean types are not supported"
-                ))
-            }
+            mir::BinOp::BitXor => self.encode_bitwise_function_app(op, left, right, ty)?,
+            mir::BinOp::Shl | mir::BinOp::Shr => self.encode_shift_expr(op, left, right, ty)?,
             unsupported_op => {
                 return Err(EncodingError::unsupported(format!(
                     "operation '{:?}' is not supported",
@@ -557,13 +597,149 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
         })
     }
 
-    pub fn encode_unary_op_expr(&self, op: mir::UnOp, expr: vir::Expr) -> vir::Expr {
-        match op {
-            mir::UnOp::Not => vir::Expr::not(expr),
-            mir::UnOp::Neg => vir::Expr::minus(expr),
+    pub fn encode_unary_op_expr(
+        &self,
+        op: mir::UnOp,
+        expr: vir::Expr,
+        ty: ty::Ty<'tcx>,
+    ) -> vir::Expr {
+        match (op, ty.kind()) {
+            // The bitwise negation of an integer `x` is `MAX - x` if the
+            // type is unsigned, and `-x - 1` otherwise.
+            (mir::UnOp::Not, ty::TyKind::Uint(_)) => {
+                let (_, upper) = self.encoder.encode_integer_bounds(ty).unwrap();
+                vir::Expr::sub(upper, expr)
+            }
+            (mir::UnOp::Not, ty::TyKind::Int(_)) => {
+                vir::Expr::sub(vir::Expr::minus(expr), 1.into())
+            }
+            (mir::UnOp::Not, _) => vir::Expr::not(expr),
+            (mir::UnOp::Neg, _) => vir::Expr::minus(expr),
+        }
+    }
+
+    /// Encode a bitwise operation on integers as the application of an
+    /// uninterpreted function (see `Encoder::encode_bitwise_function_use`).
+    fn encode_bitwise_function_app(
+        &self,
+        op: mir::BinOp,
+        left: vir::Expr,
+        right: vir::Expr,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<vir::Expr> {
+        let function_name = self.encoder.encode_bitwise_function_use(op, ty)?;
+        Ok(vir::Expr::func_app(
+            function_name,
+            vec![left, right],
+            vec![vir_local!{ left: Int }, vir_local!{ right: Int }],
+            vir::Type::Int,
+            vir::Position::default(),
+        ))
+    }
+
+    /// Encode a shift of a value of an unsigned integer type. Bits shifted
+    /// out of the value are discarded.
+    fn encode_shift_expr(
+        &self,
+        op: mir::BinOp,
+        left: vir::Expr,
+        right: vir::Expr,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<vir::Expr> {
+        let amount: Option<u32> = match right {
+            vir::Expr::Const(vir::Const::Int(amount), _) => amount.try_into().ok(),
+            vir::Expr::Const(vir::Const::BigInt(ref amount), _) => amount.parse().ok(),
+            _ => None,
+        };
+        let bounds = if ty.is_signed() {
+            None
+        } else {
+            self.encoder.encode_integer_bounds(ty)
+        };
+        match (amount, bounds, self.get_integer_bit_width(ty)) {
+            (Some(amount), Some((_, upper)), Some(bits)) => {
+                // Without overflow checks, the shift amount is masked to the bit width
+                let factor: vir::Expr = (1u128 << (u64::from(amount) % bits)).into();
+                Ok(if op == mir::BinOp::Shl {
+                    vir::Expr::modulo(
+                        vir::Expr::mul(left, factor),
+                        vir::Expr::add(upper, 1.into()),
+                    )
+                } else {
+                    vir::Expr::div(left, factor)
+                })
+            }
+            (None, Some(_), Some(_)) => self.encode_bitwise_function_app(op, left, right, ty),
+            _ => Err(EncodingError::unsupported(format!(
+                "operation '{:?}' is supported only on unsigned integers",
+                op,
+            ))),
         }
     }
 
+    /// The number of bits of an integer type.
+    fn get_integer_bit_width(&self, ty: ty::Ty<'tcx>) -> Option<u64> {
+        let pointer_width = config::pointer_width();
+        match ty.kind() {
+            ty::TyKind::Int(int_ty) => Some(int_ty.bit_width().unwrap_or(pointer_width)),
+            ty::TyKind::Uint(uint_ty) => Some(uint_ty.bit_width().unwrap_or(pointer_width)),
+            _ => None,
+        }
+    }
+
+    /// Returns the conditions under which the operation does not overflow,
+    /// each together with a description of the bound that is exceeded when
+    /// the condition does not hold.
+    pub fn encode_bin_op_overflow_conditions(
+        &self,
+        op: mir::BinOp,
+        left: vir::Expr,
+        right: vir::Expr,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<Vec<(vir::Expr, String)>> {
+        Ok(match op {
+            mir::BinOp::Add | mir::BinOp::Mul | mir::BinOp::Sub => {
+                let (lower, upper) = self.encoder.encode_integer_bounds(ty).ok_or_else(||
+                    EncodingError::unsupported(format!(
+                        "overflow checks are unsupported for operation '{:?}' on type '{:?}'",
+                        op,
+                        ty,
+                    ))
+                )?;
+                let result = self.encode_bin_op_expr(op, left, right, ty)?;
+                vec![
+                    (
+                        vir::Expr::le_cmp(lower, result.clone()),
+                        format!("the result might be smaller than {}::MIN", ty),
+                    ),
+                    (
+                        vir::Expr::le_cmp(result, upper),
+                        format!("the result might be greater than {}::MAX", ty),
+                    ),
+                ]
+            }
+
+            mir::BinOp::Shl | mir::BinOp::Shr => {
+                let bits = self.get_integer_bit_width(ty).ok_or_else(||
+                    EncodingError::unsupported(format!(
+                        "overflow checks are unsupported for operation '{:?}' on type '{:?}'",
+                        op,
+                        ty,
+                    ))
+                )?;
+                vec![(
+                    vir::Expr::and(
+                        vir::Expr::le_cmp(0.into(), right.clone()),
+                        vir::Expr::lt_cmp(right, bits.into()),
+                    ),
+                    format!("the shift amount might not be smaller than {}, the bit width of {}", bits, ty),
+                )]
+            }
+
+            _ => unreachable!("{:?}", op),
+        })
+    }
+
     /// Returns `true` is an overflow happened
     pub fn encode_bin_op_check(
         &self,
@@ -575,78 +751,71 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
         if !op.is_checkable() || !config::check_overflows() {
             Ok(false.into())
         } else {
-            let result = self.encode_bin_op_expr(op, left, right, ty)?;
-
-            Ok(match op {
-                mir::BinOp::Add | mir::BinOp::Mul | mir::BinOp::Sub => match ty.kind() {
-                    // Unsigned
-                    ty::TyKind::Uint(ty::UintTy::U8) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::u8::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::u8::MAX.into()),
-                    ),
-                    ty::TyKind::Uint(ty::UintTy::U16) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::u16::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::u16::MAX.into()),
-                    ),
-                    ty::TyKind::Uint(ty::UintTy::U32) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::u32::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::u32::MAX.into()),
-                    ),
-                    ty::TyKind::Uint(ty::UintTy::U64) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::u64::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::u64::MAX.into()),
-                    ),
-                    ty::TyKind::Uint(ty::UintTy::U128) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::u128::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::u128::MAX.into()),
-                    ),
-                    ty::TyKind::Uint(ty::UintTy::Usize) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::usize::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::usize::MAX.into()),
-                    ),
-                    // Signed
-                    ty::TyKind::Int(ty::IntTy::I8) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::i8::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::i8::MAX.into()),
-                    ),
-                    ty::TyKind::Int(ty::IntTy::I16) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::i16::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::i16::MIN.into()),
-                    ),
-                    ty::TyKind::Int(ty::IntTy::I32) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::i32::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::i32::MAX.into()),
-                    ),
-                    ty::TyKind::Int(ty::IntTy::I64) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::i64::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::i64::MAX.into()),
-                    ),
-                    ty::TyKind::Int(ty::IntTy::I128) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::i128::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::i128::MAX.into()),
-                    ),
-                    ty::TyKind::Int(ty::IntTy::Isize) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::isize::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::isize::MAX.into()),
-                    ),
+            let conditions = self.encode_bin_op_overflow_conditions(op, left, right, ty)?;
+            Ok(conditions
+                .into_iter()
+                .map(|(condition, _)| vir::Expr::not(condition))
+                .reduce(vir::Expr::or)
+                .unwrap_or_else(|| false.into()))
+        }
+    }
 
-                    _ => {
-                        return Err(EncodingError::unsupported(format!(
-                            "overflow checks are unsupported for operation '{:?}' on type '{:?}'",
-                            op,
-                            ty,
-                        )));
-                    }
-                },
+    /// Encode the result of one of the `wrapping_*`, `checked_*` or
+    /// `overflowing_*` arithmetic methods of integers, as a value of type
+    /// `result_ty` (a snapshot, for `Option` and tuple results).
+    pub fn encode_integer_arithmetic(
+        &self,
+        kind: IntegerArithmeticKind,
+        op: mir::BinOp,
+        left: vir::Expr,
+        right: vir::Expr,
+        ty: ty::Ty<'tcx>,
+        result_ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<vir::Expr> {
+        let (lower, upper) = self.encoder.encode_integer_bounds(ty).ok_or_else(||
+            EncodingError::internal(format!("'{:?}' is not an integer type", ty))
+        )?;
+        let result = self.encode_bin_op_expr(op, left, right, ty)?;
+        let modulus = vir::Expr::add(vir::Expr::sub(upper.clone(), lower.clone()), 1.into());
+        let wrapped = vir::Expr::add(
+            vir::Expr::modulo(vir::Expr::sub(result.clone(), lower.clone()), modulus),
+            lower.clone(),
+        );
+        let overflow = vir::Expr::or(
+            vir::Expr::lt_cmp(result.clone(), lower),
+            vir::Expr::gt_cmp(result, upper),
+        );
+        Ok(match kind {
+            IntegerArithmeticKind::Wrapping => wrapped,
+            IntegerArithmeticKind::Checked => {
+                // The variants of `Option` are `None` and `Some`, in this order.
+                let none = self.encoder.encode_snapshot_variant_constructor(result_ty, 0, vec![])?;
+                let some = self.encoder.encode_snapshot_variant_constructor(result_ty, 1, vec![wrapped])?;
+                vir::Expr::ite(overflow, none, some)
+            }
+            IntegerArithmeticKind::Overflowing => {
+                self.encoder.encode_snapshot_constructor(result_ty, vec![wrapped, overflow])?
+            }
+        })
+    }
 
-                mir::BinOp::Shl | mir::BinOp::Shr => {
-                    return Err(EncodingError::unsupported(
-                        "overflow checks on a shift operation are unsupported",
-                    ));
+    /// Whether all the values of `src_ty` are values of `dst_ty`. The values
+    /// of `char` are at most 21 bits wide.
+    fn is_lossless_cast(&self, src_ty: ty::Ty<'tcx>, dst_ty: ty::Ty<'tcx>) -> bool {
+        let src_bits = match src_ty.kind() {
+            ty::TyKind::Char => 21,
+            _ => self.get_integer_bit_width(src_ty).unwrap(),
+        };
+        match dst_ty.kind() {
+            ty::TyKind::Char => src_bits <= 8 && !src_ty.is_signed(),
+            _ => {
+                let dst_bits = self.get_integer_bit_width(dst_ty).unwrap();
+                match (src_ty.is_signed(), dst_ty.is_signed()) {
+                    (false, false) | (true, true) => src_bits <= dst_bits,
+                    (false, true) => src_bits < dst_bits,
+                    (true, false) => false,
                 }
-
-                _ => unreachable!("{:?}", op),
-            })
+            }
         }
     }
 
@@ -659,55 +828,29 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
         let src_ty = self.get_operand_ty(operand);
 
         let encoded_val = match (src_ty.kind(), dst_ty.kind()) {
+            (ty::TyKind::Bool, ty::TyKind::Int(_))
+            | (ty::TyKind::Bool, ty::TyKind::Uint(_))
+            => {
+                let encoded_operand = self.encode_operand_expr(operand).with_span(span)?;
+                vir::Expr::ite(encoded_operand, 1.into(), 0.into())
+            }
+
             // Numeric casts that cannot fail
-            | (ty::TyKind::Char, ty::TyKind::Char)
-            | (ty::TyKind::Char, ty::TyKind::Uint(ty::UintTy::U8))
-            | (ty::TyKind::Char, ty::TyKind::Uint(ty::UintTy::U16))
-            | (ty::TyKind::Char, ty::TyKind::Uint(ty::UintTy::U32))
-            | (ty::TyKind::Char, ty::TyKind::Uint(ty::UintTy::U64))
-            | (ty::TyKind::Char, ty::TyKind::Uint(ty::UintTy::U128))
-            | (ty::TyKind::Int(ty::IntTy::I8), ty::TyKind::Int(ty::IntTy::I8))
-            | (ty::TyKind::Int(ty::IntTy::I8), ty::TyKind::Int(ty::IntTy::I16))
-            | (ty::TyKind::Int(ty::IntTy::I8), ty::TyKind::Int(ty::IntTy::I32))
-            | (ty::TyKind::Int(ty::IntTy::I8), ty::TyKind::Int(ty::IntTy::I64))
-            | (ty::TyKind::Int(ty::IntTy::I8), ty::TyKind::Int(ty::IntTy::I128))
-            | (ty::TyKind::Int(ty::IntTy::I16), ty::TyKind::Int(ty::IntTy::I16))
-            | (ty::TyKind::Int(ty::IntTy::I16), ty::TyKind::Int(ty::IntTy::I32))
-            | (ty::TyKind::Int(ty::IntTy::I16), ty::TyKind::Int(ty::IntTy::I64))
-            | (ty::TyKind::Int(ty::IntTy::I16), ty::TyKind::Int(ty::IntTy::I128))
-            | (ty::TyKind::Int(ty::IntTy::I32), ty::TyKind::Int(ty::IntTy::I32))
-            | (ty::TyKind::Int(ty::IntTy::I32), ty::TyKind::Int(ty::IntTy::I64))
-            | (ty::TyKind::Int(ty::IntTy::I32), ty::TyKind::Int(ty::IntTy::I128))
-            | (ty::TyKind::Int(ty::IntTy::I64), ty::TyKind::Int(ty::IntTy::I64))
-            | (ty::TyKind::Int(ty::IntTy::I64), ty::TyKind::Int(ty::IntTy::I128))
-            | (ty::TyKind::Int(ty::IntTy::I128), ty::TyKind::Int(ty::IntTy::I128))
-            | (ty::TyKind::Int(ty::IntTy::Isize), ty::TyKind::Int(ty::IntTy::Isize))
-            | (ty::TyKind::Uint(ty::UintTy::U8), ty::TyKind::Char)
-            | (ty::TyKind::Uint(ty::UintTy::U8), ty::TyKind::Uint(ty::UintTy::U8))
-            | (ty::TyKind::Uint(ty::UintTy::U8), ty::TyKind::Uint(ty::UintTy::U16))
-            | (ty::TyKind::Uint(ty::UintTy::U8), ty::TyKind::Uint(ty::UintTy::U32))
-            | (ty::TyKind::Uint(ty::UintTy::U8), ty::TyKind::Uint(ty::UintTy::U64))
-            | (ty::TyKind::Uint(ty::UintTy::U8), ty::TyKind::Uint(ty::UintTy::U128))
-            | (ty::TyKind::Uint(ty::UintTy::U16), ty::TyKind::Uint(ty::UintTy::U16))
-            | (ty::TyKind::Uint(ty::UintTy::U16), ty::TyKind::Uint(ty::UintTy::U32))
-            | (ty::TyKind::Uint(ty::UintTy::U16), ty::TyKind::Uint(ty::UintTy::U64))
-            | (ty::TyKind::Uint(ty::UintTy::U16), ty::TyKind::Uint(ty::UintTy::U128))
-            | (ty::TyKind::Uint(ty::UintTy::U32), ty::TyKind::Uint(ty::UintTy::U32))
-            | (ty::TyKind::Uint(ty::UintTy::U32), ty::TyKind::Uint(ty::UintTy::U64))
-            | (ty::TyKind::Uint(ty::UintTy::U32), ty::TyKind::Uint(ty::UintTy::U128))
-            | (ty::TyKind::Uint(ty::UintTy::U64), ty::TyKind::Uint(ty::UintTy::U64))
-            | (ty::TyKind::Uint(ty::UintTy::U64), ty::TyKind::Uint(ty::UintTy::U128))
-            | (ty::TyKind::Uint(ty::UintTy::U128), ty::TyKind::Uint(ty::UintTy::U128))
-            | (ty::TyKind::Uint(ty::UintTy::Usize), ty::TyKind::Uint(ty::UintTy::Usize))
+            (ty::TyKind::Char, ty::TyKind::Int(_))
+            | (ty::TyKind::Char, ty::TyKind::Uint(_))
+            | (ty::TyKind::Int(_), ty::TyKind::Int(_))
+            | (ty::TyKind::Int(_), ty::TyKind::Uint(_))
+            | (ty::TyKind::Uint(_), ty::TyKind::Char)
+            | (ty::TyKind::Uint(_), ty::TyKind::Int(_))
+            | (ty::TyKind::Uint(_), ty::TyKind::Uint(_))
+            if self.is_lossless_cast(src_ty, dst_ty)
             => self.encode_operand_expr(operand).with_span(span)?,
 
             // Numeric casts where the source value might not fit into the target type
             (ty::TyKind::Char, ty::TyKind::Int(_))
             | (ty::TyKind::Char, ty::TyKind::Uint(_))
-            | (ty::TyKind::Int(_), ty::TyKind::Char)
             | (ty::TyKind::Int(_), ty::TyKind::Int(_))
             | (ty::TyKind::Int(_), ty::TyKind::Uint(_))
-            | (ty::TyKind::Uint(_), ty::TyKind::Char)
             | (ty::TyKind::Uint(_), ty::TyKind::Int(_))
             | (ty::TyKind::Uint(_), ty::TyKind::Uint(_))
             => {
@@ -734,8 +877,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
                         pos,
                     ));
                 } else {
-                    // Don't check the cast
-                    encoded_operand
+                    // Truncate the value, reinterpreting it in two's complement
+                    // if the target type is signed
+                    let (lower, upper) = self.encoder.encode_integer_bounds(dst_ty).unwrap();
+                    let modulus = vir::Expr::add(vir::Expr::sub(upper, lower.clone()), 1.into());
+                    vir::Expr::add(
+                        vir::Expr::modulo(vir::Expr::sub(encoded_operand, lower.clone()), modulus),
+                        lower,
+                    )
                 }
             }
 
@@ -754,6 +903,50 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
         Ok(encoded_val)
     }
 
+    /// Encode the bounds `(start, end)` of the subslice `base[range]` of a
+    /// sequence of length `len`, where `range` has type `range_ty`. The
+    /// subslice consists of the elements from `start` (inclusive) to `end`
+    /// (exclusive).
+    pub fn encode_slicing_bounds(
+        &self,
+        range: vir::Expr,
+        range_ty: ty::Ty<'tcx>,
+        len: vir::Expr,
+    ) -> EncodingResult<(vir::Expr, vir::Expr)> {
+        let tcx = self.encoder.env().tcx();
+        let usize_ty = tcx.types.usize;
+        let encode_field = |name: &str, field_ty: ty::Ty<'tcx>| {
+            let field = self.encoder.encode_struct_field(name, field_ty)?;
+            self.encoder.encode_value_expr(range.clone().field(field), field_ty)
+        };
+        let range_name = match range_ty.ty_adt_def() {
+            Some(adt_def) => tcx.def_path_str(adt_def.did),
+            None => format!("{:?}", range_ty),
+        };
+        Ok(match range_name.as_str() {
+            "std::ops::Range" => (encode_field("start", usize_ty)?, encode_field("end", usize_ty)?),
+            "std::ops::RangeFrom" => (encode_field("start", usize_ty)?, len),
+            "std::ops::RangeTo" => (0.into(), encode_field("end", usize_ty)?),
+            "std::ops::RangeFull" => (0.into(), len),
+            "std::ops::RangeInclusive" => {
+                // An exhausted range is empty, see `RangeInclusive::into_slice_range`
+                let end = vir::Expr::add(encode_field("end", usize_ty)?, 1.into());
+                let start = vir::Expr::ite(
+                    encode_field("exhausted", tcx.types.bool)?,
+                    end.clone(),
+                    encode_field("start", usize_ty)?,
+                );
+                (start, end)
+            }
+            "std::ops::RangeToInclusive" => {
+                (0.into(), vir::Expr::add(encode_field("end", usize_ty)?, 1.into()))
+            }
+            _ => return Err(EncodingError::unsupported(
+                format!("slicing with {} as index/range type is not supported yet", range_name),
+            )),
+        })
+    }
+
     pub fn encode_operand_place(
         &self,
         operand: &mir::Operand<'tcx>,
@@ -824,6 +1017,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
             ["core::panic::panic_2015", "core::macros::panic", "std::unreachable"] => PanicCause::Unreachable,
             ["std::assert", "std::debug_assert", ..] => PanicCause::DebugAssert,
             ["std::assert", ..] => PanicCause::Assert,
+            ["std::assert_eq", "std::debug_assert_eq", ..]
+            | ["std::assert_ne", "std::debug_assert_ne", ..] => PanicCause::DebugAssert,
+            ["std::assert_eq", ..] | ["std::assert_ne", ..] => PanicCause::Assert,
             ["std::panic::panic_2015", "std::panic", "std::debug_assert"] => PanicCause::DebugAssert,
             // TODO: assert!(_, "") currently has the same backtrace as panic!()
             // see https://github.com/rust-lang/rust/issues/82157
@@ -832,4 +1028,79 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
             _ => PanicCause::Generic,
         }
     }
+
+    /// Describe why the check of a Rust division or remainder might fail,
+    /// naming the operands as they are written in the source code.
+    pub fn encode_division_check_cause<O>(
+        &self,
+        msg: &mir::AssertKind<O>,
+        span: Span,
+    ) -> Option<String> {
+        let snippet = self.encoder.env().codemap().span_to_snippet(span).ok();
+        let operands = snippet.as_deref().and_then(split_division);
+        match msg {
+            mir::AssertKind::DivisionByZero(_) | mir::AssertKind::RemainderByZero(_) => {
+                Some(match operands {
+                    Some((_, divisor)) => format!("the divisor `{}` might be zero", divisor),
+                    None => "the divisor might be zero".to_string(),
+                })
+            }
+            mir::AssertKind::Overflow(mir::BinOp::Div | mir::BinOp::Rem, _, _) => {
+                Some(match operands {
+                    Some((dividend, divisor)) => format!(
+                        "`{}` might be the minimum value of its type while the divisor `{}` is -1",
+                        dividend,
+                        divisor,
+                    ),
+                    None => "the dividend might be the minimum value of its type while \
+                        the divisor is -1".to_string(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the message passed to a panic function, if it is a string literal
+    pub fn encode_panic_message(&self, operand: &mir::Operand<'tcx>) -> Option<String> {
+        let const_value = match operand {
+            &mir::Operand::Constant(box mir::Constant {
+                literal: mir::ConstantKind::Ty(&ty::Const { val: ty::ConstKind::Value(value), .. }),
+                ..
+            }) => value,
+            &mir::Operand::Constant(box mir::Constant {
+                literal: mir::ConstantKind::Val(value, _),
+                ..
+            }) => value,
+            _ => return None,
+        };
+        match const_value {
+            mir::interpret::ConstValue::Slice { data, start, end } => {
+                let bytes = data.inspect_with_uninit_and_ptr_outside_interpreter(start..end);
+                std::str::from_utf8(bytes).ok().map(|message| message.to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Split the source code of a division or remainder like `a / b` or `a %= b`
+/// into the dividend and the divisor.
+fn split_division(snippet: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    let mut operator = None;
+    for (index, c) in snippet.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '/' | '%' if depth == 0 => operator = Some(index),
+            _ => {}
+        }
+    }
+    let index = operator?;
+    let dividend = snippet[..index].trim();
+    let divisor = snippet[index + 1..].trim_start_matches('=').trim();
+    if dividend.is_empty() || divisor.is_empty() {
+        return None;
+    }
+    Some((dividend, divisor))
 }
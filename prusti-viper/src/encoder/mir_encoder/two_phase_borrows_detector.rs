@@ -0,0 +1,67 @@
+use rustc_middle::mir;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
+use std::collections::HashMap;
+
+/// Returns the two-phase borrows of the body, such as the mutable borrow of
+/// `v` in `v.push(v.len())`, mapping the location that reserves the borrow to
+/// the location that activates it.
+///
+/// A two-phase borrow is stored in a temporary variable that is used exactly
+/// once after the reservation. Before that use, the borrowed place can still
+/// be read, so the borrow behaves like a shared borrow until the activation.
+pub fn detect_two_phase_activations<'tcx>(
+    body: &mir::Body<'tcx>,
+) -> HashMap<mir::Location, mir::Location> {
+    let mut collector = TwoPhaseBorrowsCollector {
+        reservations: HashMap::new(),
+        uses: HashMap::new(),
+    };
+    collector.visit_body(body);
+    collector
+        .reservations
+        .into_iter()
+        .filter_map(|(local, reservation)| {
+            match collector.uses.get(&local).map(|uses| uses.as_slice()) {
+                Some(&[activation]) => Some((reservation, activation)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+struct TwoPhaseBorrowsCollector {
+    /// The temporary variable of each two-phase borrow, with the location of
+    /// its reservation.
+    reservations: HashMap<mir::Local, mir::Location>,
+    /// The locations that use each variable, except for the assignments that
+    /// reserve two-phase borrows.
+    uses: HashMap<mir::Local, Vec<mir::Location>>,
+}
+
+impl<'tcx> Visitor<'tcx> for TwoPhaseBorrowsCollector {
+    fn visit_assign(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        rvalue: &mir::Rvalue<'tcx>,
+        location: mir::Location,
+    ) {
+        if let mir::Rvalue::Ref(_, mir::BorrowKind::Mut { allow_two_phase_borrow: true }, _) = rvalue {
+            if place.projection.is_empty() {
+                self.reservations.insert(place.local, location);
+            }
+        }
+        self.super_assign(place, rvalue, location);
+    }
+
+    fn visit_local(
+        &mut self,
+        local: &mir::Local,
+        context: PlaceContext,
+        location: mir::Location,
+    ) {
+        let is_reservation = self.reservations.get(local) == Some(&location);
+        if !is_reservation && !matches!(context, PlaceContext::NonUse(_)) {
+            self.uses.entry(*local).or_insert_with(Vec::new).push(location);
+        }
+    }
+}
@@ -26,6 +26,7 @@ mod spec_function_encoder;
 pub use spec_function_encoder::SpecFunctionKind;
 mod stub_function_encoder;
 mod stub_procedure_encoder;
+mod trait_function_encoder;
 mod type_encoder;
 mod utils;
 mod snapshot;
@@ -14,9 +14,17 @@ use crate::encoder::foldunfold;
 use crate::encoder::initialisation::InitInfo;
 use crate::encoder::loop_encoder::{LoopEncoder, LoopEncoderError};
 use crate::encoder::mir_encoder::{MirEncoder, FakeMirEncoder, PlaceEncoder, PlaceEncoding, ExprOrArrayBase};
+use crate::encoder::mir_encoder::{detect_consumed_args, detect_two_phase_activations};
 use crate::encoder::mir_encoder::PRECONDITION_LABEL;
+use crate::encoder::mir_encoder::{IntegerArithmeticKind, get_integer_arithmetic_method};
 use crate::encoder::mir_successor::MirSuccessor;
 use crate::encoder::places::{Local, LocalVariableManager, Place};
+use crate::encoder::array_encoder::EncodedSliceTypes;
+use crate::encoder::type_encoder::{
+    box_model_ty, compute_discriminant_values, is_box_model_ty, is_map_ty, is_ref_cell_guard_ty,
+    is_result_ty, is_shared_ptr_ty, is_string_ty, is_vec_ty, range_iterator_kind, slice_model_ty,
+    RangeIteratorKind,
+};
 use crate::encoder::Encoder;
 use prusti_common::{
     config,
@@ -44,6 +52,7 @@ use prusti_interface::{
     },
 };
 use prusti_interface::utils;
+use prusti_interface::PrustiError;
 // use prusti_common::report::log;
 // use prusti_interface::specifications::*;
 use rustc_middle::mir::Mutability;
@@ -104,6 +113,12 @@ pub struct ProcedureEncoder<'p, 'v: 'p, 'tcx: 'v> {
     // /// A map that stores local variables used to preserve the value of a place accross the loop
     // /// when we cannot do that by using permissions.
     pure_var_for_preserving_value_map: HashMap<BasicBlockIndex, HashMap<vir::Expr, vir::LocalVar>>,
+    /// For each loop head, the ghost variables that store the value of the
+    /// loop variants at the beginning of the iteration.
+    loop_variant_vars: HashMap<BasicBlockIndex, Vec<vir::LocalVar>>,
+    /// The ghost variable that stores the value of the termination measure of
+    /// the procedure in the pre state, if a recursive call needs it.
+    termination_measure_var: Option<vir::LocalVar>,
     /// Information about which places are definitely initialised.
     init_info: InitInfo,
     // /// Mapping from old expressions to ghost variables with which they were replaced.
@@ -112,6 +127,35 @@ pub struct ProcedureEncoder<'p, 'v: 'p, 'tcx: 'v> {
     old_ghost_vars: HashMap<String, vir::Type>,
     /// For each loop head, the block at whose end the loop invariant holds
     cached_loop_invariant_block: HashMap<BasicBlockIndex, BasicBlockIndex>,
+    /// For each MIR edge leaving a switch on the discriminant of an enum, the
+    /// variant of the enum that the edge implies.
+    switch_discriminant_assumptions: HashMap<(BasicBlockIndex, BasicBlockIndex), vir::Expr>,
+    /// The blocks that leave the procedure through a `return` expression, with
+    /// the span of the expression.
+    return_sites: Vec<(BasicBlockIndex, Span)>,
+    /// Ghost variable that stores the position in `return_sites`, plus one, of
+    /// the last executed `return` expression. It is zero at the end of the body.
+    return_site_var: Option<vir::LocalVar>,
+    /// The first CFG block, which initializes the ghost variables
+    start_cfg_block: Option<CfgBlockIndex>,
+    /// Ghost variables that model the borrow flag of each `RefCell`, identified
+    /// by the encoded place of the cell: the number of live `Ref` guards, or
+    /// -1 while a `RefMut` guard is live.
+    refcell_borrow_flags: HashMap<vir::Expr, vir::LocalVar>,
+    /// The guards returned by `RefCell::borrow` and `RefCell::borrow_mut`,
+    /// with the borrow flag of their cell and, for a `RefMut`, the encoded
+    /// places of the cell and of the guard.
+    refcell_guards: HashMap<mir::Local, (vir::LocalVar, Option<(vir::Expr, vir::Expr)>)>,
+    /// For each MIR edge, the updates of `RefCell`s to encode after the
+    /// borrows that expire on the edge, which include the borrow of the cell.
+    refcell_updates_on_edge: HashMap<(BasicBlockIndex, BasicBlockIndex), Vec<vir::Stmt>>,
+    /// For each `for` loop over a range of integers, the ghost variables that
+    /// store the `start`, `end` and `step` of the iterator on entry.
+    range_loop_entry_vars: HashMap<BasicBlockIndex, Vec<vir::LocalVar>>,
+    /// For each two-phase borrow, the location of its activation, keyed by
+    /// the location of its reservation. The borrow is encoded at the
+    /// activation, so that the borrowed place stays readable before it.
+    two_phase_activations: HashMap<mir::Location, mir::Location>,
 }
 
 impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
@@ -163,10 +207,21 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             array_loop_old_label: HashMap::new(),
             procedure_contracts: HashMap::new(),
             pure_var_for_preserving_value_map: HashMap::new(),
+            loop_variant_vars: HashMap::new(),
+            termination_measure_var: None,
             init_info,
             old_to_ghost_var: HashMap::new(),
             old_ghost_vars: HashMap::new(),
             cached_loop_invariant_block: HashMap::new(),
+            switch_discriminant_assumptions: HashMap::new(),
+            return_sites: procedure.get_return_site_blocks(),
+            return_site_var: None,
+            start_cfg_block: None,
+            refcell_borrow_flags: HashMap::new(),
+            refcell_guards: HashMap::new(),
+            refcell_updates_on_edge: HashMap::new(),
+            range_loop_entry_vars: HashMap::new(),
+            two_phase_activations: detect_two_phase_activations(mir),
         })
     }
 
@@ -249,9 +304,13 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         assert_one_magic_wand(procedure_contract.borrow_infos.len()).with_span(mir_span)?;
         self.procedure_contract = Some(procedure_contract);
 
+        self.report_inactive_union_reads()?;
+
         // Prepare assertions to check specification refinement
         let mut precondition_weakening: Option<typed::Assertion> = None;
         let mut postcondition_strengthening: Option<typed::Assertion> = None;
+        // The strengthening only has to hold if the precondition of the trait held
+        let mut trait_precondition: Option<typed::Assertion> = None;
         debug!("procedure_contract: {:?}", self.procedure_contract());
         //trace!("def_id of proc: {:?}", &self.proc_def_id);
         let impl_def_id = self.encoder.env().tcx().impl_of_method(self.proc_def_id);
@@ -306,15 +365,38 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         });
                     }
 
-                    if proc_post_specs.is_empty() && proc_pledge_specs.is_empty() {
-                        proc_post_specs
-                            .extend_from_slice(procedure_trait_contract.functional_postcondition());
+                    if proc_pledge_specs.is_empty() {
                         proc_pledge_specs
                             .extend_from_slice(procedure_trait_contract.pledges());
                     } else {
-                        if !proc_pledge_specs.is_empty() {
-                            unimplemented!("Refining specifications with pledges is not supported");
+                        // The pledge of the method has to refine the pledge of the trait: after the
+                        // expiry, its rhs has to entail the trait's rhs under the trait's lhs.
+                        // This is checked together with the method's pledge, when packaging the
+                        // magic wand of the postcondition.
+                        for trait_pledge in procedure_trait_contract.pledges() {
+                            let trait_rhs = match trait_pledge.lhs {
+                                Some(ref trait_lhs) => typed::Assertion {
+                                    kind: box typed::AssertionKind::Implies(
+                                        trait_lhs.clone(),
+                                        trait_pledge.rhs.clone(),
+                                    ),
+                                },
+                                None => trait_pledge.rhs.clone(),
+                            };
+                            for pledge in proc_pledge_specs.iter_mut() {
+                                pledge.rhs = typed::Assertion {
+                                    kind: box typed::AssertionKind::And(
+                                        vec![pledge.rhs.clone(), trait_rhs.clone()]
+                                    ),
+                                };
+                            }
                         }
+                    }
+
+                    if proc_post_specs.is_empty() {
+                        proc_post_specs
+                            .extend_from_slice(procedure_trait_contract.functional_postcondition());
+                    } else {
                         let proc_post = typed::Assertion {
                             kind: box typed::AssertionKind::And(
                                 proc_post_specs.clone()
@@ -332,6 +414,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         postcondition_strengthening = Some(typed::Assertion {
                             kind: box typed::AssertionKind::Implies(proc_post, proc_trait_post),
                         });
+                        trait_precondition = Some(typed::Assertion {
+                            kind: box typed::AssertionKind::And(
+                                procedure_trait_contract
+                                    .functional_precondition()
+                                    .iter()
+                                    .cloned()
+                                    .collect(),
+                            ),
+                        });
                     }
                 }
             }
@@ -381,6 +472,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             ],
         );
 
+        self.start_cfg_block = Some(start_cfg_block);
+
         let return_cfg_block = self.cfg_method.add_block(
             "return",
             vec![
@@ -391,6 +484,21 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         self.cfg_method
             .set_successor(return_cfg_block, Successor::Return);
 
+        // Record which `return` expression leads to the return block, to
+        // report it when the postcondition does not hold
+        if !self.return_sites.is_empty() {
+            let return_site_var = self.cfg_method.add_fresh_local_var(vir::Type::Int);
+            self.cfg_method.add_stmt(
+                start_cfg_block,
+                vir::Stmt::Assign(
+                    vir::Expr::local(return_site_var.clone()),
+                    0.into(),
+                    vir::AssignKind::Copy,
+                ),
+            );
+            self.return_site_var = Some(return_site_var);
+        }
+
         // Encode a flag that becomes true the first time a block is executed
         for bbi in self.procedure.get_reachable_nonspec_cfg_blocks() {
             let executed_flag_var = self.cfg_method.add_fresh_local_var(vir::Type::Bool);
@@ -434,9 +542,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
         // Encode preconditions
         self.encode_preconditions(start_cfg_block, precondition_weakening)?;
+        self.encode_termination_measure_init(start_cfg_block)?;
 
         // Encode postcondition
-        self.encode_postconditions(return_cfg_block, postcondition_strengthening)?;
+        self.encode_postconditions(
+            start_cfg_block,
+            return_cfg_block,
+            postcondition_strengthening,
+            trait_precondition,
+        )?;
 
         let local_vars: Vec<_> = self
             .locals
@@ -652,9 +766,13 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             .position(|&bb| bb == before_invariant_block)
             .unwrap();
         let after_inv_block_pos = 1 + before_inv_block_pos;
+        // The exit blocks of nested loops, e.g. of a labeled break to the end
+        // of this loop, cannot be the guard of this loop.
         let exit_blocks_before_inv: Vec<_> = loop_body[0..after_inv_block_pos]
             .iter()
-            .filter(|&bb| loop_exit_blocks_set.contains(bb))
+            .filter(|&&bb| {
+                loop_exit_blocks_set.contains(&bb) && loop_info.get_loop_depth(bb) == loop_depth
+            })
             .cloned()
             .collect();
         // HEURISTIC: pick the last exit block before the invariant.
@@ -941,6 +1059,17 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
         self.encode_execution_flag(bbi, curr_block)?;
         self.encode_block_statements(bbi, curr_block)?;
+        if let Some(index) = self.return_sites.iter().position(|&(site, _)| site == bbi) {
+            let return_site_var = self.return_site_var.clone().unwrap();
+            self.cfg_method.add_stmt(
+                curr_block,
+                vir::Stmt::Assign(
+                    vir::Expr::local(return_site_var),
+                    (index + 1).into(),
+                    vir::AssignKind::Copy,
+                ),
+            );
+        }
         let mir_successor: MirSuccessor = self.encode_block_terminator(bbi, curr_block)?;
 
         // Make sure that the
@@ -1077,14 +1206,27 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
         let bb_data = &self.mir[location.block];
         let index = location.statement_index;
-        let stmts_succ_res = if index < bb_data.statements.len() {
+        let stmts_succ_res = if self.two_phase_activations.contains_key(&location) {
+            // The reservation of a two-phase borrow behaves like a shared
+            // borrow, which does not need to be encoded.
             let mir_stmt = &bb_data.statements[index];
-            self.encode_statement(mir_stmt, location)
-                .map(|stmts| (stmts, None))
+            Ok((vec![
+                vir::Stmt::comment(format!("[mir] {:?}", mir_stmt)),
+                vir::Stmt::comment("Reservation of a two-phase borrow"),
+            ], None))
         } else {
-            let mir_term = bb_data.terminator();
-            self.encode_terminator(mir_term, location)
-                .map(|(stmts, succ)| (stmts, Some(succ)))
+            self.encode_two_phase_activations_at(location).and_then(|mut stmts| {
+                if index < bb_data.statements.len() {
+                    let mir_stmt = &bb_data.statements[index];
+                    stmts.extend(self.encode_statement(mir_stmt, location)?);
+                    Ok((stmts, None))
+                } else {
+                    let mir_term = bb_data.terminator();
+                    let (term_stmts, succ) = self.encode_terminator(mir_term, location)?;
+                    stmts.extend(term_stmts);
+                    Ok((stmts, Some(succ)))
+                }
+            })
         };
 
         // Intercept encoding error caused by an unsupported feature
@@ -1126,6 +1268,28 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// Encode the mutable borrows of the two-phase borrows activated at the
+    /// given location. The borrows keep the location of their reservation, so
+    /// that the label after it refers to the state after the activation.
+    fn encode_two_phase_activations_at(
+        &mut self,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let mut reservations: Vec<_> = self.two_phase_activations
+            .iter()
+            .filter(|(_, &activation)| activation == location)
+            .map(|(&reservation, _)| reservation)
+            .collect();
+        reservations.sort();
+        let mut stmts = vec![];
+        for reservation in reservations {
+            let mir_stmt = &self.mir[reservation.block].statements[reservation.statement_index];
+            stmts.push(vir::Stmt::comment("Activation of a two-phase borrow"));
+            stmts.extend(self.encode_statement(mir_stmt, reservation)?);
+        }
+        Ok(stmts)
+    }
+
     /// Note: it's better to call `encode_statement_at` instead of this method.
     fn encode_statement(
         &mut self,
@@ -1162,16 +1326,29 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         rhs,
                         location,
                     )?
+                } else if let PlaceEncoding::SliceAccess { box base, index, rust_slice_ty, .. } = lhs_place_encoding {
+                    // Same as above, for `slice[idx] = val`
+                    self.encode_slice_direct_assign(
+                        base,
+                        index,
+                        rust_slice_ty,
+                        rhs,
+                        location,
+                    )?
                 } else {
                     let (encoded_lhs, pre_stmts) = self.postprocess_place_encoding(lhs_place_encoding, ArrayAccessKind::Mutable(None, location))
                         .with_span(span)?;
                     stmts.extend(pre_stmts);
-                    self.encode_assign(
-                        encoded_lhs,
+                    let mut assign_stmts = self.encode_assign(
+                        encoded_lhs.clone(),
                         rhs,
                         ty,
                         location,
-                    )?
+                    )?;
+                    assign_stmts.extend(
+                        self.encode_havoc_other_union_fields(lhs, &encoded_lhs, location)?
+                    );
+                    assign_stmts
                 }
             }
             ref x => unimplemented!("{:?}", x),
@@ -1319,6 +1496,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         location,
                     )?
                 } else {
+                    self.check_closure_contract_is_used(
+                        self.mir_encoder.get_operand_ty(operand),
+                        span,
+                    )?;
                     return Err(EncodingError::unsupported(
                         "unsizing a pointer or reference value is not supported"
                     )).with_span(span);
@@ -1598,6 +1779,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
     }
 
     fn construct_location_guard(&self, location: mir::Location) -> vir::Expr {
+        // A two-phase borrow is created when it is activated.
+        let location = self.two_phase_activations.get(&location).copied().unwrap_or(location);
         let bbi = &location.block;
         let executed_flag_var = self.cfg_block_has_been_executed[bbi].clone();
         vir::Expr::local(executed_flag_var).into()
@@ -1771,10 +1954,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             }
         }
 
+        // Applying the magic wand requires the left-hand side of the pledge to hold, which is an
+        // obligation of the caller at the point where the borrow expires.
         let pos = self.encoder.error_manager().register(
-            //self.mir.span,
-            // TODO change to where the loan expires?
-            self.mir.source_info(loan_location).span, // the source of the ref
+            span,
             ErrorCtxt::ApplyMagicWandOnExpiry,
             self.proc_def_id,
         );
@@ -1915,6 +2098,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     vir::AssignKind::Copy,
                 ));
 
+                self.encode_switch_discriminant_assumptions(discr, targets, switch_ty, location)?;
+
                 let guard_is_bool = match switch_ty.kind() {
                     ty::TyKind::Bool => true,
                     _ => false
@@ -1958,7 +2143,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 };
 
                 // Is the target a specification block?
-                if self.procedure.is_spec_block(default_target) {
+                if self.procedure.is_body_assertion_block(default_target) {
+                    stmts.push(vir::Stmt::comment(format!(
+                        "Ignore default target {:?}, as it is only used by Prusti to type-check \
+                        a prusti_assert!(..) or a prusti_assume!(..).",
+                        default_target
+                    )));
+                    stmts.extend(self.encode_body_assertions(default_target)?);
+                    kill_default_target = true;
+                } else if self.procedure.is_spec_block(default_target) {
                     stmts.push(vir::Stmt::comment(format!(
                         "Ignore default target {:?}, as it is only used by Prusti to type-check \
                         a loop invariant.",
@@ -2018,7 +2211,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 (stmts, MirSuccessor::Kill)
             }
 
-            TerminatorKind::Drop { target, .. } => (stmts, MirSuccessor::Goto(target)),
+            TerminatorKind::Drop { target, place, .. } => {
+                if let Some(local) = place.as_local() {
+                    self.encode_refcell_guard_drop(local, location, target);
+                }
+                (stmts, MirSuccessor::Goto(target))
+            }
 
             TerminatorKind::FalseEdge { real_target, .. } => {
                 (stmts, MirSuccessor::Goto(real_target))
@@ -2058,11 +2256,21 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     }),
                 ..
             } => {
-                if let ty::TyKind::FnDef(def_id, substs) = ty.kind() {
+                if let ty::TyKind::FnDef(called_def_id, call_substs) = ty.kind() {
+                    let (def_id, substs) = self.encoder.resolve_extern_spec_call(
+                        self.proc_def_id,
+                        *called_def_id,
+                        *call_substs,
+                    );
+                    let (def_id, substs) = self.encoder.resolve_pure_trait_call(
+                        self.proc_def_id,
+                        def_id,
+                        substs,
+                    );
                     let self_ty = {
                         // If we are calling a trait method on a struct, self_ty
                         // is the struct.
-                        let generics = self.encoder.env().tcx().generics_of(*def_id);
+                        let generics = self.encoder.env().tcx().generics_of(def_id);
                         if generics.has_self {
                             Some(substs.type_at(0))
                         } else {
@@ -2070,7 +2278,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         }
                     };
 
-                    let def_id = *def_id;
                     let full_func_proc_name: &str =
                         &self.encoder.env().tcx().def_path_str(def_id);
                         // &self.encoder.env().tcx().absolute_item_path_str(def_id);
@@ -2082,22 +2289,53 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     let mut tymap = HashMap::new();
 
                     for (kind1, kind2) in own_substs.iter().zip(substs.iter()) {
-                        if let (
-                            ty::subst::GenericArgKind::Type(ty1),
-                            ty::subst::GenericArgKind::Type(ty2),
-                        ) = (kind1.unpack(), kind2.unpack())
-                        {
-                            tymap.insert(ty1, ty2);
+                        match (kind1.unpack(), kind2.unpack()) {
+                            (
+                                ty::subst::GenericArgKind::Type(ty1),
+                                ty::subst::GenericArgKind::Type(ty2),
+                            ) => {
+                                tymap.insert(ty1, ty2);
+                            }
+                            (
+                                ty::subst::GenericArgKind::Const(value1),
+                                ty::subst::GenericArgKind::Const(value2),
+                            ) => {
+                                tymap.insert(
+                                    self.encoder.const_param_key(value1),
+                                    self.encoder.const_param_key(value2),
+                                );
+                            }
+                            _ => {}
                         }
                     }
                     let _cleanup_token = self.encoder.push_temp_tymap(tymap);
 
+                    // Direct calls of closures go through the `Fn*` traits;
+                    // any other function receiving a closure can only call it
+                    // through a generic bound, unless its precondition states
+                    // the required contract with a specification entailment.
+                    if !matches!(
+                        full_func_proc_name,
+                        "std::ops::Fn::call" | "std::ops::FnMut::call_mut" | "std::ops::FnOnce::call_once"
+                    ) && !self.has_spec_entailment_precondition(def_id) {
+                        for arg in args {
+                            self.check_closure_contract_is_used(
+                                self.mir_encoder.get_operand_ty(arg),
+                                term.source_info.span,
+                            )?;
+                        }
+                    }
+
                     match full_func_proc_name {
                         "std::rt::begin_panic"
                         | "core::panicking::panic"
-                        | "core::panicking::panic_fmt" => {
+                        | "core::panicking::panic_str"
+                        | "core::panicking::panic_fmt"
+                        | "core::panicking::assert_failed" => {
                             // This is called when a Rust assertion fails
-                            // args[0]: message
+                            // args[0]: message, or the kind of comparison for
+                            //   `assert_failed`, called by `assert_eq!` and
+                            //   `assert_ne!`
                             // args[1]: position of failing assertions
 
                             // Example of args[0]: 'const "internal error: entered unreachable code"'
@@ -2106,16 +2344,30 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             let panic_cause = self.mir_encoder.encode_panic_cause(
                                 term.source_info
                             );
+                            // The messages of `assert!`, `unreachable!` and
+                            // `unimplemented!` without arguments only repeat
+                            // the macro call.
+                            let opt_reported_message = match panic_cause {
+                                PanicCause::Generic | PanicCause::Panic => {
+                                    self.mir_encoder.encode_panic_message(&args[0])
+                                }
+                                _ => None,
+                            };
+                            let is_checked = match panic_cause {
+                                PanicCause::DebugAssert => config::check_debug_assertions(),
+                                PanicCause::Unreachable => config::check_unreachable(),
+                                _ => true,
+                            };
                             let pos = self
                                 .encoder
                                 .error_manager()
                                 .register(
                                     term.source_info.span,
-                                    ErrorCtxt::Panic(panic_cause),
+                                    ErrorCtxt::Panic(panic_cause, opt_reported_message),
                                     self.proc_def_id,
                                 );
 
-                            if self.check_panics {
+                            if self.check_panics && is_checked {
                                 stmts.push(vir::Stmt::comment(format!(
                                     "Rust panic - {}",
                                     panic_message
@@ -2129,7 +2381,25 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             }
                         }
 
-                        "std::boxed::Box::<T>::new" => {
+                        "std::hint::unreachable_unchecked"
+                        | "core::hint::unreachable_unchecked"
+                        | "std::intrinsics::unreachable"
+                        | "core::intrinsics::unreachable" => {
+                            // Reaching this call is undefined behavior, so it
+                            // is checked even if panics are not.
+                            let pos = self.encoder.error_manager().register(
+                                term.source_info.span,
+                                ErrorCtxt::UnreachableUnchecked,
+                                self.proc_def_id,
+                            );
+                            stmts.push(vir::Stmt::comment("Call of unreachable_unchecked()"));
+                            stmts.push(vir::Stmt::Assert(false.into(), pos));
+                        }
+
+                        "std::boxed::Box::<T>::new" |
+                        "std::rc::Rc::<T>::new" |
+                        "std::sync::Arc::<T>::new" |
+                        "std::cell::RefCell::<T>::new" => {
                             // This is the initialization of a box
                             // args[0]: value to put in the box
                             assert_eq!(args.len(), 1);
@@ -2138,7 +2408,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             let (dst, pre_stmts, dest_ty, _) = self.encode_place(target_place, ArrayAccessKind::Shared).unwrap();
                             stmts.extend(pre_stmts);
 
-                            let boxed_ty = dest_ty.boxed_ty();
+                            // Shared pointers and cells are encoded like boxes
+                            let boxed_ty = box_model_ty(self.encoder.env().tcx(), dest_ty).boxed_ty();
                             let ref_field = self.encoder.encode_dereference_field(boxed_ty)
                                 .with_span(span)?;
 
@@ -2166,6 +2437,49 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             );
                         }
 
+                        "std::ops::Try::branch" |
+                        "core::ops::Try::branch"
+                            if args.len() == 1 &&
+                                is_result_ty(
+                                    self.encoder.env().tcx(),
+                                    self.mir_encoder.get_operand_ty(&args[0]),
+                                )
+                        => {
+                            debug!("Encoding call of Try::branch");
+                            stmts.extend(
+                                self.encode_try_branch_call(
+                                    def_id,
+                                    self_ty,
+                                    location,
+                                    term.source_info.span,
+                                    args,
+                                    destination,
+                                )?
+                            );
+                        }
+
+                        "std::ops::FromResidual::from_residual" |
+                        "core::ops::FromResidual::from_residual"
+                            if args.len() == 1 &&
+                                is_result_ty(
+                                    self.encoder.env().tcx(),
+                                    self.mir_encoder.get_operand_ty(&args[0]),
+                                ) &&
+                                self_ty.map_or(false, |ty| is_result_ty(self.encoder.env().tcx(), ty))
+                        => {
+                            debug!("Encoding call of FromResidual::from_residual");
+                            stmts.extend(
+                                self.encode_from_residual_call(
+                                    def_id,
+                                    self_ty,
+                                    location,
+                                    term.source_info.span,
+                                    args,
+                                    destination,
+                                )?
+                            );
+                        }
+
                         "std::cmp::PartialEq::eq" |
                         "core::cmp::PartialEq::eq"
                             if args.len() == 2 &&
@@ -2206,6 +2520,40 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             );
                         }
 
+                        "std::default::Default::default" |
+                        "core::default::Default::default"
+                            if args.is_empty() &&
+                                self_ty.map_or(false, |ty| ty.is_adt())
+                        => {
+                            debug!("Encoding call of Default::default");
+                            stmts.extend(
+                                self.encode_default_call(
+                                    def_id,
+                                    self_ty.unwrap(),
+                                    location,
+                                    term.source_info.span,
+                                    destination,
+                                )?
+                            );
+                        }
+
+                        _ if args.len() == 2 &&
+                            get_integer_arithmetic_method(full_func_proc_name).is_some()
+                        => {
+                            debug!("Encoding call of integer arithmetic method {}", full_func_proc_name);
+                            let (kind, op) = get_integer_arithmetic_method(full_func_proc_name).unwrap();
+                            stmts.extend(
+                                self.encode_integer_arithmetic_call(
+                                    kind,
+                                    op,
+                                    location,
+                                    term.source_info.span,
+                                    args,
+                                    destination,
+                                )?
+                            );
+                        }
+
                         "std::ops::Fn::call" => {
                             let cl_type: ty::Ty = substs[0].expect_ty();
                             match cl_type.kind() {
@@ -2230,197 +2578,2236 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             }
                         }
 
-                        "core::slice::<impl [T]>::len" => {
-                            debug!("Encoding call of slice::len");
+                        "core::slice::<impl [T]>::len"
+                        | "core::str::<impl str>::len"
+                        | "std::string::String::len"
+                        | "std::vec::Vec::<T, A>::len" => {
+                            debug!("Encoding call of {}", full_func_proc_name);
                             stmts.extend(
                                 self.encode_slice_len_call(
                                     destination,
                                     args,
                                     location,
                                     span,
+                                    false,
                                 )?
                             );
                         }
 
-                        "std::iter::Iterator::next" |
-                        "core::iter::Iterator::next" => {
-                            return Err(SpannedEncodingError::unsupported(
-                                "iterators are not fully supported yet".to_string(),
-                                term.source_info.span,
-                            ));
+                        "core::slice::<impl [T]>::is_empty"
+                        | "core::str::<impl str>::is_empty"
+                        | "std::string::String::is_empty"
+                        | "std::vec::Vec::<T, A>::is_empty" => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_slice_len_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                    true,
+                                )?
+                            );
                         }
 
-                        _ => {
-                            let is_pure_function = self.encoder.is_pure(def_id) &&
-                                // We are verifying this pure function and,
-                                // therefore, need to always encode it as a
-                                // method.
-                                self.proc_def_id != def_id;
-                            if is_pure_function {
-                                let (function_name, _) = self.encoder
-                                    .encode_pure_function_use(def_id, self.proc_def_id)
-                                    .with_span(term.source_info.span)?;
-                                debug!("Encoding pure function call '{}'", function_name);
-                                assert!(destination.is_some());
-
-                                let mut arg_exprs = vec![];
-                                for operand in args.iter() {
-                                    let arg_expr = self.mir_encoder.encode_operand_expr(operand);
-                                    arg_exprs.push(arg_expr);
-                                }
+                        "core::str::<impl str>::as_bytes"
+                        | "std::string::String::as_bytes"
+                        | "std::string::String::as_str" => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_slice_view_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
 
-                                stmts.extend(
-                                    self.encode_pure_function_call(
-                                        location,
-                                        term.source_info.span,
-                                        args,
-                                        destination,
-                                        def_id,
-                                    )?
-                                );
-                            } else {
-                                stmts.extend(
-                                    self.encode_impure_function_call(
-                                        location,
-                                        term.source_info.span,
-                                        args,
-                                        destination,
-                                        def_id,
-                                        self_ty,
-                                    )?
-                                );
-                            }
+                        "std::ops::Deref::deref" | "core::ops::Deref::deref"
+                            if self.is_slice_model_reference(&args[0])
+                        => {
+                            debug!("Encoding call of Deref::deref on a string or a vector");
+                            stmts.extend(
+                                self.encode_slice_view_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
                         }
-                    }
 
-                    if let &Some((_, target)) = destination {
-                        (stmts, MirSuccessor::Goto(target))
-                    } else {
-                        // Encode unreachability
-                        //stmts.push(
-                        //    vir::Stmt::Inhale(false.into())
-                        //);
-                        (stmts, MirSuccessor::Kill)
-                    }
-                } else {
-                    // Other kind of calls?
-                    unimplemented!();
-                }
-            }
+                        "std::ops::Deref::deref" |
+                        "core::ops::Deref::deref"
+                            if self.is_box_model_reference(&args[0])
+                        => {
+                            debug!("Encoding call of Deref::deref on a shared pointer or a guard");
+                            let (copy_stmts, _) = self.encode_box_model_copy_call(
+                                destination,
+                                args,
+                                location,
+                                span,
+                            )?;
+                            stmts.extend(copy_stmts);
+                        }
 
-            TerminatorKind::Call { .. } => {
-                // Other kind of calls?
-                unimplemented!();
-            }
+                        "std::clone::Clone::clone" |
+                        "core::clone::Clone::clone"
+                            if self.is_shared_ptr_reference(&args[0])
+                        => {
+                            debug!("Encoding call of Clone::clone on a shared pointer");
+                            let (copy_stmts, _) = self.encode_box_model_copy_call(
+                                destination,
+                                args,
+                                location,
+                                span,
+                            )?;
+                            stmts.extend(copy_stmts);
+                        }
 
-            TerminatorKind::Assert {
-                ref cond,
-                expected,
-                target,
-                ref msg,
-                ..
-            } => {
-                trace!("Assert cond '{:?}', expected '{:?}'", cond, expected);
+                        "std::clone::Clone::clone" |
+                        "core::clone::Clone::clone"
+                            if self_ty.map_or(false, |ty| {
+                                ty.is_adt() && self.encoder.has_derived_clone_impl(ty)
+                            })
+                        => {
+                            debug!("Encoding call of a derived Clone::clone");
+                            stmts.extend(
+                                self.encode_derived_clone_call(
+                                    def_id,
+                                    self_ty.unwrap(),
+                                    location,
+                                    term.source_info.span,
+                                    args,
+                                    destination,
+                                )?
+                            );
+                        }
 
-                // Use local variables in the switch/if (see GitLab issue #57)
-                let cond_var = self.cfg_method.add_fresh_local_var(vir::Type::Bool);
-                stmts.push(vir::Stmt::Assign(
-                    cond_var.clone().into(),
-                    self.mir_encoder.encode_operand_expr(cond)
-                        .with_span(
-                            self.mir_encoder.get_span_of_location(location)
-                        )?,
-                    vir::AssignKind::Copy,
-                ));
+                        "std::cell::RefCell::<T>::into_inner" => {
+                            debug!("Encoding call of RefCell::into_inner");
+                            let (copy_stmts, _) = self.encode_box_model_copy_call(
+                                destination,
+                                args,
+                                location,
+                                span,
+                            )?;
+                            stmts.extend(copy_stmts);
+                        }
 
-                let viper_guard = if expected {
-                    cond_var.into()
-                } else {
+                        "std::cell::RefCell::<T>::borrow" |
+                        "std::cell::RefCell::<T>::borrow_mut" |
+                        "std::cell::RefCell::<T>::replace" |
+                        "std::cell::RefCell::<T>::take" => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_refcell_call(
+                                    full_func_proc_name,
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::mem::drop"
+                            if self.is_refcell_guard_operand(&args[0])
+                        => {
+                            if let (Some(local), Some((_, target))) = (
+                                args[0].place().and_then(|place| place.as_local()),
+                                destination,
+                            ) {
+                                self.encode_refcell_guard_drop(local, location, *target);
+                            }
+                        }
+
+                        "std::rc::Rc::<T>::get_mut" |
+                        "std::rc::Rc::<T>::make_mut" |
+                        "std::sync::Arc::<T>::get_mut" |
+                        "std::sync::Arc::<T>::make_mut" => {
+                            return Err(SpannedEncodingError::unsupported(
+                                "mutable access to the content of an `Rc` or `Arc` is not supported",
+                                span,
+                            ));
+                        }
+
+                        "std::any::<impl dyn std::any::Any>::is"
+                        | "std::any::<impl dyn std::any::Any>::downcast_ref"
+                        | "std::any::<impl dyn std::any::Any>::downcast_mut"
+                        | "std::any::<impl dyn std::any::Any + std::marker::Send>::is"
+                        | "std::any::<impl dyn std::any::Any + std::marker::Send>::downcast_ref"
+                        | "std::any::<impl dyn std::any::Any + std::marker::Send>::downcast_mut"
+                        | "std::boxed::Box::<dyn std::any::Any, A>::downcast" => {
+                            return Err(SpannedEncodingError::unsupported(
+                                "downcasting trait objects is not supported",
+                                span,
+                            ));
+                        }
+
+                        "std::ptr::eq" if args.iter().any(|arg| {
+                            self.mir_encoder.get_operand_ty(arg)
+                                .builtin_deref(true)
+                                .map_or(false, |pointee| pointee.ty.is_trait())
+                        }) => {
+                            return Err(SpannedEncodingError::unsupported(
+                                "comparing the addresses of trait objects is not supported",
+                                span,
+                            ));
+                        }
+
+                        "std::string::String::new" => {
+                            debug!("Encoding call of String::new");
+                            stmts.extend(
+                                self.encode_slice_model_new_call(
+                                    destination,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::vec::Vec::<T>::new" if !self.encoder.has_extern_spec(def_id) => {
+                            debug!("Encoding call of Vec::new");
+                            stmts.extend(
+                                self.encode_slice_model_new_call(
+                                    destination,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::vec::Vec::<T, A>::push" if !self.encoder.has_extern_spec(def_id) => {
+                            debug!("Encoding call of Vec::push");
+                            stmts.extend(
+                                self.encode_vec_push_call(
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::vec::Vec::<T, A>::pop" if !self.encoder.has_extern_spec(def_id) => {
+                            debug!("Encoding call of Vec::pop");
+                            stmts.extend(
+                                self.encode_vec_pop_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::vec::Vec::<T, A>::clear"
+                        | "std::vec::Vec::<T, A>::truncate" if !self.encoder.has_extern_spec(def_id) => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_vec_truncate_call(
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::ops::Index::index" |
+                        "core::ops::Index::index"
+                            if args.len() == 2 && self.is_vec_indexing(args)
+                        => {
+                            debug!("Encoding call of Index::index on a vector");
+                            stmts.extend(
+                                self.encode_vec_index_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::collections::HashMap::<K, V>::new"
+                        | "std::collections::BTreeMap::<K, V>::new"
+                            if !self.encoder.has_extern_spec(def_id)
+                        => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_map_new_call(
+                                    destination,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::collections::HashMap::<K, V, S>::len"
+                        | "std::collections::BTreeMap::<K, V>::len" => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_map_query_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                    MapQuery::Len,
+                                )?
+                            );
+                        }
+
+                        "std::collections::HashMap::<K, V, S>::is_empty"
+                        | "std::collections::BTreeMap::<K, V>::is_empty" => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_map_query_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                    MapQuery::IsEmpty,
+                                )?
+                            );
+                        }
+
+                        "std::collections::HashMap::<K, V, S>::contains_key"
+                        | "std::collections::BTreeMap::<K, V>::contains_key" => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_map_query_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                    MapQuery::ContainsKey,
+                                )?
+                            );
+                        }
+
+                        "std::collections::HashMap::<K, V, S>::get"
+                        | "std::collections::BTreeMap::<K, V>::get" => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_map_get_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::collections::HashMap::<K, V, S>::insert"
+                        | "std::collections::BTreeMap::<K, V>::insert"
+                            if !self.encoder.has_extern_spec(def_id)
+                        => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_map_update_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                    true,
+                                )?
+                            );
+                        }
+
+                        "std::collections::HashMap::<K, V, S>::remove"
+                        | "std::collections::BTreeMap::<K, V>::remove"
+                            if !self.encoder.has_extern_spec(def_id)
+                        => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_map_update_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                    false,
+                                )?
+                            );
+                        }
+
+                        "std::collections::HashMap::<K, V, S>::iter"
+                        | "std::collections::HashMap::<K, V, S>::iter_mut"
+                        | "std::collections::HashMap::<K, V, S>::keys"
+                        | "std::collections::HashMap::<K, V, S>::values"
+                        | "std::collections::BTreeMap::<K, V>::iter"
+                        | "std::collections::BTreeMap::<K, V>::iter_mut"
+                        | "std::collections::BTreeMap::<K, V>::keys"
+                        | "std::collections::BTreeMap::<K, V>::values"
+                            if !self.encoder.has_extern_spec(def_id)
+                        => {
+                            return Err(SpannedEncodingError::unsupported(
+                                "iterating over maps is not supported",
+                                span,
+                            ));
+                        }
+
+                        "std::string::String::push_str"
+                        | "std::string::String::push" => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_string_push_call(
+                                    args,
+                                    location,
+                                    span,
+                                    full_func_proc_name == "std::string::String::push",
+                                )?
+                            );
+                        }
+
+                        "std::ops::Index::index" |
+                        "core::ops::Index::index"
+                            if args.len() == 2 && self.is_slicing_by_range(args)
+                        => {
+                            debug!("Encoding call of Index::index with a range");
+                            stmts.extend(
+                                self.encode_slicing_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::iter::IntoIterator::into_iter" |
+                        "core::iter::IntoIterator::into_iter"
+                            if self_ty.and_then(|ty| range_iterator_kind(self.encoder.env().tcx(), ty)).is_some()
+                        => {
+                            // A range iterator is its own iterator
+                            let (dst, pre_stmts, _, _) = self.encode_place(
+                                &destination.as_ref().unwrap().0,
+                                ArrayAccessKind::Shared,
+                            ).with_span(span)?;
+                            stmts.extend(pre_stmts);
+                            stmts.extend(self.encode_assign_operand(&dst, &args[0], location)?);
+                        }
+
+                        "std::ops::RangeInclusive::<Idx>::new" => {
+                            debug!("Encoding call of RangeInclusive::new");
+                            stmts.extend(
+                                self.encode_range_inclusive_new_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::iter::Iterator::rev" |
+                        "core::iter::Iterator::rev" |
+                        "std::iter::Iterator::step_by" |
+                        "core::iter::Iterator::step_by"
+                            if self_ty.and_then(|ty| range_iterator_kind(self.encoder.env().tcx(), ty)).is_some()
+                        => {
+                            debug!("Encoding call of {}", full_func_proc_name);
+                            stmts.extend(
+                                self.encode_range_adapter_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::iter::Iterator::next" |
+                        "core::iter::Iterator::next"
+                            if self_ty.and_then(|ty| range_iterator_kind(self.encoder.env().tcx(), ty)).is_some()
+                        => {
+                            debug!("Encoding call of Iterator::next on a range");
+                            stmts.extend(
+                                self.encode_range_iterator_next_call(
+                                    destination,
+                                    args,
+                                    location,
+                                    span,
+                                )?
+                            );
+                        }
+
+                        "std::iter::Iterator::next" |
+                        "core::iter::Iterator::next" => {
+                            return Err(SpannedEncodingError::unsupported(
+                                "iterators are not fully supported yet".to_string(),
+                                term.source_info.span,
+                            ));
+                        }
+
+                        _ => {
+                            let is_pure_function = self.encoder.is_pure(def_id) &&
+                                // We are verifying this pure function and,
+                                // therefore, need to always encode it as a
+                                // method.
+                                self.proc_def_id != def_id;
+                            if is_pure_function {
+                                let (function_name, _) = self.encoder
+                                    .encode_pure_function_use(def_id, self.proc_def_id)
+                                    .with_span(term.source_info.span)?;
+                                debug!("Encoding pure function call '{}'", function_name);
+                                assert!(destination.is_some());
+
+                                let mut arg_exprs = vec![];
+                                for operand in args.iter() {
+                                    let arg_expr = self.mir_encoder.encode_operand_expr(operand);
+                                    arg_exprs.push(arg_expr);
+                                }
+
+                                stmts.extend(
+                                    self.encode_pure_function_call(
+                                        location,
+                                        term.source_info.span,
+                                        args,
+                                        destination,
+                                        def_id,
+                                    )?
+                                );
+                            } else {
+                                stmts.extend(
+                                    self.encode_impure_function_call(
+                                        location,
+                                        term.source_info.span,
+                                        args,
+                                        destination,
+                                        def_id,
+                                        self_ty,
+                                    )?
+                                );
+                            }
+                        }
+                    }
+
+                    if let &Some((_, target)) = destination {
+                        (stmts, MirSuccessor::Goto(target))
+                    } else {
+                        // Encode unreachability
+                        //stmts.push(
+                        //    vir::Stmt::Inhale(false.into())
+                        //);
+                        (stmts, MirSuccessor::Kill)
+                    }
+                } else {
+                    // Other kind of calls?
+                    unimplemented!();
+                }
+            }
+
+            TerminatorKind::Call { .. } => {
+                // Other kind of calls?
+                unimplemented!();
+            }
+
+            TerminatorKind::Assert {
+                ref cond,
+                expected,
+                target,
+                ref msg,
+                ..
+            } => {
+                trace!("Assert cond '{:?}', expected '{:?}'", cond, expected);
+
+                // Use local variables in the switch/if (see GitLab issue #57)
+                let cond_var = self.cfg_method.add_fresh_local_var(vir::Type::Bool);
+                stmts.push(vir::Stmt::Assign(
+                    cond_var.clone().into(),
+                    self.mir_encoder.encode_operand_expr(cond)
+                        .with_span(
+                            self.mir_encoder.get_span_of_location(location)
+                        )?,
+                    vir::AssignKind::Copy,
+                ));
+
+                let viper_guard = if expected {
+                    cond_var.into()
+                } else {
                     vir::Expr::not(cond_var.into())
                 };
 
-                // Check or assume the assertion
-                let (assert_msg, error_ctxt) = if let mir::AssertKind::BoundsCheck { .. } = msg {
-                    let mut s = String::new();
-                    msg.fmt_assert_args(&mut s).unwrap();
-                    (s, ErrorCtxt::BoundsCheckAssert)
-                } else {
-                    let assert_msg = msg.description().to_string();
-                    (assert_msg.clone(), ErrorCtxt::AssertTerminator(assert_msg))
-                };
+                // Check or assume the assertion
+                let division_check_cause = self.mir_encoder.encode_division_check_cause(
+                    msg,
+                    term.source_info.span,
+                );
+                let (assert_msg, error_ctxt) = if let mir::AssertKind::BoundsCheck { .. } = msg {
+                    let mut s = String::new();
+                    msg.fmt_assert_args(&mut s).unwrap();
+                    (s, ErrorCtxt::BoundsCheckAssert)
+                } else if let Some(cause) = division_check_cause {
+                    let assert_msg = msg.description().to_string();
+                    (assert_msg.clone(), ErrorCtxt::DivisionCheck(assert_msg, cause))
+                } else {
+                    let assert_msg = msg.description().to_string();
+                    (assert_msg.clone(), ErrorCtxt::AssertTerminator(assert_msg))
+                };
+
+                stmts.push(vir::Stmt::comment(format!("Rust assertion: {}", assert_msg)));
+                if let mir::AssertKind::Overflow(
+                    mir::BinOp::Add | mir::BinOp::Sub | mir::BinOp::Mul
+                    | mir::BinOp::Shl | mir::BinOp::Shr,
+                    ..
+                ) = msg {
+                    // The bounds of the operation are already checked where
+                    // its result is computed, see `encode_assign_checked_binary_op`.
+                    // Signed `Div` and `Rem` overflows are only checked here.
+                    stmts.push(vir::Stmt::comment("This assertion is checked at the operation"));
+                    stmts.push(vir::Stmt::Inhale(viper_guard));
+                } else if self.check_panics {
+                    stmts.push(vir::Stmt::Assert(
+                        viper_guard,
+                        self.encoder.error_manager().register(
+                            term.source_info.span,
+                            error_ctxt,
+                            self.proc_def_id,
+                        ),
+                    ));
+                } else {
+                    stmts.push(vir::Stmt::comment("This assertion will not be checked"));
+                    stmts.push(vir::Stmt::Inhale(viper_guard));
+                };
+
+                (stmts, MirSuccessor::Goto(target))
+            }
+
+            TerminatorKind::Resume
+            | TerminatorKind::Yield { .. }
+            | TerminatorKind::GeneratorDrop
+            | TerminatorKind::InlineAsm { .. } => unimplemented!("{:?}", term.kind),
+        };
+        Ok(result)
+    }
+
+    /// Encode `len()` of a slice, a string or a vector. If `is_empty` is set,
+    /// encode `is_empty()` instead.
+    fn encode_slice_len_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+        is_empty: bool,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        assert!(args.len() == 1, "unexpected args to slice::len(): {:?}", args);
+        let slice_operand = self.mir_encoder.encode_operand_expr(&args[0])
+            .with_span(span)?;
+
+        let mut stmts = vec![];
+
+        // we need to put a label before, it seems..
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::Label(label.clone()));
+
+        let slice_ty_ref = self.mir_encoder.get_operand_ty(&args[0]);
+        let slice_ty = if let ty::TyKind::Ref(_, slice_ty, _) = slice_ty_ref.kind() { slice_ty } else { unreachable!() };
+        let (slice_operand, slice_ty) = self.encode_slice_model(slice_operand, slice_ty)
+            .with_span(span)?;
+        let slice_types = self.encoder.encode_slice_types(slice_ty).with_span(span)?;
+
+        let len = slice_types.encode_slice_len_call(self.encoder, slice_operand);
+        let rhs = if is_empty {
+            vir::Expr::eq_cmp(len, 0.into())
+        } else {
+            len
+        };
+
+        let (encoded_lhs, encode_stmts, ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Mutable(None, location),
+        )
+            .with_span(span)?;
+        stmts.extend(encode_stmts);
+
+        stmts.extend(
+            self.encode_copy_value_assign(
+                encoded_lhs,
+                rhs,
+                ty,
+                location,
+            )?
+        );
+
+        self.encode_transfer_args_permissions(location, args,  &mut stmts, label.clone(), false)?;
+
+        // Store a label for permissions got back from the call
+        debug!(
+            "Pure function call location {:?} has label {}",
+            location, label
+            );
+        self.label_after_location.insert(location, label);
+
+        Ok(stmts)
+    }
+
+    /// The elements of a `String` or of a `Vec`, which are encoded like a
+    /// `Box<[T]>`. Other types, including `str`, are returned unchanged.
+    fn encode_slice_model(
+        &self,
+        expr: vir::Expr,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<(vir::Expr, ty::Ty<'tcx>)> {
+        let tcx = self.encoder.env().tcx();
+        if is_string_ty(tcx, ty) || is_vec_ty(tcx, ty) {
+            let elems_ty = slice_model_ty(tcx, ty).boxed_ty();
+            let elems_field = self.encoder.encode_dereference_field(elems_ty)?;
+            Ok((expr.field(elems_field), elems_ty))
+        } else {
+            Ok((expr, ty))
+        }
+    }
+
+    fn is_slice_model_reference(&self, operand: &mir::Operand<'tcx>) -> bool {
+        let tcx = self.encoder.env().tcx();
+        match self.mir_encoder.get_operand_ty(operand).kind() {
+            ty::TyKind::Ref(_, inner_ty, _) => is_string_ty(tcx, inner_ty) || is_vec_ty(tcx, inner_ty),
+            _ => false,
+        }
+    }
+
+    fn is_shared_ptr_reference(&self, operand: &mir::Operand<'tcx>) -> bool {
+        match self.mir_encoder.get_operand_ty(operand).kind() {
+            ty::TyKind::Ref(_, inner_ty, _) => is_shared_ptr_ty(self.encoder.env().tcx(), inner_ty),
+            _ => false,
+        }
+    }
+
+    fn is_box_model_reference(&self, operand: &mir::Operand<'tcx>) -> bool {
+        match self.mir_encoder.get_operand_ty(operand).kind() {
+            ty::TyKind::Ref(_, inner_ty, _) => is_box_model_ty(self.encoder.env().tcx(), inner_ty),
+            _ => false,
+        }
+    }
+
+    fn is_refcell_guard_operand(&self, operand: &mir::Operand<'tcx>) -> bool {
+        is_ref_cell_guard_ty(self.encoder.env().tcx(), self.mir_encoder.get_operand_ty(operand))
+    }
+
+    /// Encode a call whose result has the same snapshot as the value behind
+    /// its first argument, such as `Deref::deref()` or `Clone::clone()` on a
+    /// reference to an `Rc`, or `RefCell::borrow()`. Shared pointers, cells
+    /// and their guards are encoded like boxes, see [box_model_ty].
+    ///
+    /// Returns the statements and the label of the state before the call.
+    fn encode_box_model_copy_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<(Vec<vir::Stmt>, String)> {
+        let mut stmts = vec![];
+
+        let source = self.mir_encoder.encode_operand_expr(&args[0])
+            .with_span(span)?;
+        let (target_place, pre_stmts) = self.encode_pure_function_call_lhs_place(destination);
+        stmts.extend(pre_stmts);
+        let inhaled_expr = vir::Expr::eq_cmp(
+            vir::Expr::snap_app(target_place),
+            vir::Expr::snap_app(source),
+        );
+
+        let (call_stmts, label) = self.encode_pure_function_call_site(
+            location,
+            destination,
+            inhaled_expr,
+        );
+        stmts.extend(call_stmts);
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+        Ok((stmts, label))
+    }
+
+    /// Encode `RefCell::borrow()`, `RefCell::borrow_mut()`,
+    /// `RefCell::replace()` or `RefCell::take()`.
+    ///
+    /// The borrow flag of the cell is modelled by a ghost variable, which is
+    /// checked before the call: a conflicting borrow panics at runtime. The
+    /// content of the cell can only be modified if the cell is reached
+    /// through a place that is owned or behind mutable references, because
+    /// the content of a shared reference is assumed to never change. The
+    /// modifications are encoded when the borrow of the cell expires: at the
+    /// end of the call for `replace` and `take`, and when the `RefMut` guard
+    /// is dropped for `borrow_mut`.
+    fn encode_refcell_call(
+        &mut self,
+        func_proc_name: &str,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let is_borrow = func_proc_name == "std::cell::RefCell::<T>::borrow";
+        let (cell_place, is_writable) = self.find_borrowed_refcell_place(&args[0], location);
+        if !is_borrow && !is_writable {
+            return Err(SpannedEncodingError::unsupported(
+                "modifying the content of a `RefCell` behind a shared reference is not supported",
+                span,
+            ));
+        }
+        let (cell, pre_stmts, _, _) = self.encode_place(&cell_place, ArrayAccessKind::Shared)
+            .with_span(span)?;
+        if !pre_stmts.is_empty() {
+            return Err(SpannedEncodingError::unsupported(
+                "borrowing a `RefCell` in an array or slice is not supported",
+                span,
+            ));
+        }
+        let flag_var = self.get_refcell_borrow_flag(&cell, is_writable);
+        let flag: vir::Expr = flag_var.clone().into();
+
+        // A conflicting borrow panics
+        let mut stmts = vec![];
+        let no_conflict = if is_borrow {
+            vir::Expr::ge_cmp(flag.clone(), 0.into())
+        } else {
+            vir::Expr::eq_cmp(flag.clone(), 0.into())
+        };
+        if config::check_refcell_borrows() {
+            let pos = self.encoder.error_manager().register(
+                span,
+                ErrorCtxt::RefCellBorrowConflict,
+                self.proc_def_id,
+            );
+            stmts.push(vir::Stmt::Assert(no_conflict, pos));
+        } else {
+            stmts.push(vir::Stmt::Inhale(no_conflict));
+        }
+
+        // The result is a copy of the content of the cell
+        let (copy_stmts, label) = self.encode_box_model_copy_call(
+            destination,
+            args,
+            location,
+            span,
+        )?;
+        stmts.extend(copy_stmts);
+
+        let (guard_place, target) = destination.as_ref().unwrap();
+        match func_proc_name {
+            "std::cell::RefCell::<T>::borrow" | "std::cell::RefCell::<T>::borrow_mut" => {
+                let new_flag = if is_borrow {
+                    vir::Expr::add(flag, 1.into())
+                } else {
+                    vir::Expr::from(-1)
+                };
+                stmts.push(vir::Stmt::Assign(flag.clone(), new_flag, vir::AssignKind::Copy));
+                let opt_write_back = if is_borrow {
+                    None
+                } else {
+                    let (guard, _, _, _) = self.encode_place(guard_place, ArrayAccessKind::Shared)
+                        .with_span(span)?;
+                    Some((cell, guard))
+                };
+                if let Some(guard_local) = guard_place.as_local() {
+                    self.refcell_guards.insert(guard_local, (flag_var, opt_write_back));
+                }
+            }
+            "std::cell::RefCell::<T>::replace" => {
+                let new_value = self.mir_encoder.encode_operand_expr(&args[1])
+                    .with_span(span)?;
+                let new_snapshot = vir::Expr::labelled_old(&label, vir::Expr::snap_app(new_value));
+                let update_stmts = self.encode_refcell_update(&cell, Some(new_snapshot));
+                self.refcell_updates_on_edge.insert((location.block, *target), update_stmts);
+            }
+            "std::cell::RefCell::<T>::take" => {
+                // The default value of the content is unknown
+                let update_stmts = self.encode_refcell_update(&cell, None);
+                self.refcell_updates_on_edge.insert((location.block, *target), update_stmts);
+            }
+            _ => unreachable!(),
+        }
+        Ok(stmts)
+    }
+
+    /// Release the borrow of a `RefCell` held by a guard that is dropped at
+    /// `location`, writing back the content of a `RefMut` guard to the cell.
+    /// The update is encoded on the edge to `target`, after the borrow of the
+    /// cell expires.
+    fn encode_refcell_guard_drop(
+        &mut self,
+        guard_local: mir::Local,
+        location: mir::Location,
+        target: BasicBlockIndex,
+    ) {
+        let (flag_var, opt_write_back) = match self.refcell_guards.get(&guard_local) {
+            Some(guard) => guard.clone(),
+            None => return,
+        };
+        let flag: vir::Expr = flag_var.into();
+        let mut stmts = vec![];
+        if let Some((cell, guard)) = opt_write_back {
+            stmts.extend(self.encode_refcell_update(&cell, Some(vir::Expr::snap_app(guard))));
+            stmts.push(vir::Stmt::Assign(flag, 0.into(), vir::AssignKind::Copy));
+        } else {
+            stmts.push(vir::Stmt::Assign(
+                flag.clone(),
+                vir::Expr::sub(flag, 1.into()),
+                vir::AssignKind::Copy,
+            ));
+        }
+        self.refcell_updates_on_edge
+            .entry((location.block, target))
+            .or_default()
+            .extend(stmts);
+    }
+
+    /// Replace the content of the cell with a value of the given snapshot, or
+    /// with an unknown value.
+    fn encode_refcell_update(
+        &mut self,
+        cell: &vir::Expr,
+        opt_new_snapshot: Option<vir::Expr>,
+    ) -> Vec<vir::Stmt> {
+        let mut stmts = self.encode_havoc(cell);
+        let type_predicate = self
+            .mir_encoder
+            .encode_place_predicate_permission(cell.clone(), vir::PermAmount::Write)
+            .unwrap();
+        stmts.push(vir::Stmt::Inhale(type_predicate));
+        if let Some(new_snapshot) = opt_new_snapshot {
+            stmts.push(vir::Stmt::Inhale(
+                vir::Expr::eq_cmp(vir::Expr::snap_app(cell.clone()), new_snapshot)
+            ));
+        }
+        stmts
+    }
+
+    /// The ghost variable that models the borrow flag of the cell. The flag
+    /// of a cell reached through a place that is owned or behind mutable
+    /// references starts at zero, because no guard can borrow it when the
+    /// procedure starts. The flag of any other cell is unknown.
+    fn get_refcell_borrow_flag(&mut self, cell: &vir::Expr, is_writable: bool) -> vir::LocalVar {
+        if let Some(flag_var) = self.refcell_borrow_flags.get(cell) {
+            return flag_var.clone();
+        }
+        let flag_var = self.cfg_method.add_fresh_local_var(vir::Type::Int);
+        if is_writable {
+            self.cfg_method.add_stmt(
+                self.start_cfg_block.unwrap(),
+                vir::Stmt::Assign(flag_var.clone().into(), 0.into(), vir::AssignKind::Copy),
+            );
+        }
+        self.refcell_borrow_flags.insert(cell.clone(), flag_var.clone());
+        flag_var
+    }
+
+    /// Find the place of the `RefCell` borrowed by the reference `operand`,
+    /// created by a statement at the end of the block of `location`, and
+    /// whether the place is owned or behind mutable references. Without such
+    /// a statement, the cell is the target of the reference.
+    fn find_borrowed_refcell_place(
+        &self,
+        operand: &mir::Operand<'tcx>,
+        location: mir::Location,
+    ) -> (mir::Place<'tcx>, bool) {
+        let tcx = self.encoder.env().tcx();
+        let reference = operand.place().unwrap();
+        let mut cell_place = tcx.mk_place_deref(reference);
+        if let Some(reference_local) = reference.as_local() {
+            let statements = &self.mir[location.block].statements[..location.statement_index];
+            for statement in statements.iter().rev() {
+                match statement.kind {
+                    mir::StatementKind::Assign(box (ref lhs, mir::Rvalue::Ref(_, _, ref src)))
+                        if lhs.as_local() == Some(reference_local) =>
+                    {
+                        cell_place = *src;
+                        break;
+                    }
+                    mir::StatementKind::StorageLive(_)
+                    | mir::StatementKind::StorageDead(_)
+                    | mir::StatementKind::FakeRead(..)
+                    | mir::StatementKind::Nop => {}
+                    _ => break,
+                }
+            }
+        }
+        let is_writable = (0..cell_place.projection.len()).all(|index| {
+            if cell_place.projection[index] != mir::ProjectionElem::Deref {
+                return true;
+            }
+            let base_ty = mir::Place::ty_from(
+                cell_place.local,
+                &cell_place.projection[..index],
+                self.mir,
+                tcx,
+            ).ty;
+            matches!(base_ty.kind(), ty::TyKind::Ref(_, _, Mutability::Mut))
+                || base_ty.is_box()
+        });
+        (cell_place, is_writable)
+    }
+
+    /// Encode a conversion of a string or a vector to a slice of its
+    /// elements, such as `str::as_bytes()`, `String::as_str()` or
+    /// `Vec::deref()`. The resulting reference has the same elements.
+    fn encode_slice_view_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        assert!(args.len() == 1, "unexpected args to as_bytes(): {:?}", args);
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::Label(label.clone()));
+
+        let string = self.mir_encoder.encode_operand_expr(&args[0])
+            .with_span(span)?;
+        let string_ty = if let ty::TyKind::Ref(_, string_ty, _) = self.mir_encoder.get_operand_ty(&args[0]).kind() {
+            *string_ty
+        } else {
+            unreachable!()
+        };
+        let (bytes, bytes_ty) = self.encode_slice_model(string, string_ty).with_span(span)?;
+        let slice_types = self.encoder.encode_slice_types(bytes_ty).with_span(span)?;
+        let elem_snap_ty = self.encoder.encode_snapshot_type(slice_types.elem_ty_rs).with_span(span)?;
+
+        let (encoded_lhs, encode_stmts, lhs_ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+        stmts.extend(encode_stmts);
+
+        stmts.extend(self.encode_havoc(&encoded_lhs));
+        let val_ref_field = self.encoder.encode_value_field(lhs_ty).with_span(span)?;
+        let result_bytes = encoded_lhs.field(val_ref_field);
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::acc_permission(result_bytes.clone(), vir::PermAmount::Write)
+        ));
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::predicate_access_predicate(
+                slice_types.slice_pred.clone(),
+                result_bytes.clone(),
+                vir::PermAmount::Read,
+            )
+        ));
+
+        let len = slice_types.encode_slice_len_call(self.encoder, bytes.clone());
+        let result_len = slice_types.encode_slice_len_call(self.encoder, result_bytes.clone());
+        stmts.push(vir!{ inhale ([result_len] == [len]) });
+
+        let i_var: vir::Expr = vir_local!{ i: Int }.into();
+        let lookup_i = slice_types.encode_lookup_pure_call(
+            self.encoder,
+            bytes,
+            i_var.clone(),
+            elem_snap_ty.clone(),
+        );
+        let lookup_result_i = slice_types.encode_lookup_pure_call(
+            self.encoder,
+            result_bytes,
+            i_var.clone(),
+            elem_snap_ty,
+        );
+        let i_in_bounds = vir!{ ([vir::Expr::from(0)] <= [i_var]) && ([i_var] < [len]) };
+        let lookup_same = vir!{ [lookup_result_i] == [lookup_i] };
+        let forall_body = vir!{ [i_in_bounds] ==> [lookup_same] };
+        let same_bytes = vir!{ forall i: Int :: { [lookup_result_i] } [ forall_body ] };
+        stmts.push(vir!{ inhale [same_bytes] });
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+
+        // Store a label for permissions got back from the call
+        debug!(
+            "Slice conversion call location {:?} has label {}",
+            location, label
+        );
+        self.label_after_location.insert(location, label);
+
+        Ok(stmts)
+    }
+
+    /// Encode `String::new()` or `Vec::new()`, which return a string or a
+    /// vector without elements.
+    fn encode_slice_model_new_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let mut stmts = vec![];
+        let (dst, pre_stmts, dst_ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+        stmts.extend(pre_stmts);
+
+        let elems_ty = slice_model_ty(self.encoder.env().tcx(), dst_ty).boxed_ty();
+        let elems_field = self.encoder.encode_dereference_field(elems_ty).with_span(span)?;
+        let elems = dst.clone().field(elems_field.clone());
+        stmts.extend(
+            self.prepare_assign_target(
+                dst,
+                elems_field,
+                location,
+                vir::AssignKind::Move,
+            )?
+        );
+        stmts.extend(self.encode_havoc_and_allocation(&elems));
+
+        let slice_types = self.encoder.encode_slice_types(elems_ty).with_span(span)?;
+        let len = slice_types.encode_slice_len_call(self.encoder, elems);
+        stmts.push(vir!{ inhale ([len] == [vir::Expr::from(0)]) });
+        Ok(stmts)
+    }
+
+    /// Encode `String::push_str(&mut self, string: &str)` or, if `is_char` is
+    /// set, `String::push(&mut self, ch: char)`. The bytes of the string are
+    /// extended with the bytes of `string` or with the UTF-8 encoding of
+    /// `ch`, of which only the length and, for ASCII characters, the value
+    /// are known.
+    fn encode_string_push_call(
+        &mut self,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+        is_char: bool,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        assert!(args.len() == 2, "unexpected args to String::push(): {:?}", args);
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::label(&label));
+
+        let string = self.mir_encoder.encode_operand_expr(&args[0])
+            .with_span(span)?;
+        let string_ty = if let ty::TyKind::Ref(_, string_ty, _) = self.mir_encoder.get_operand_ty(&args[0]).kind() {
+            *string_ty
+        } else {
+            unreachable!()
+        };
+        let (bytes, bytes_ty) = self.encode_slice_model(string, string_ty).with_span(span)?;
+        let slice_types = self.encoder.encode_slice_types(bytes_ty).with_span(span)?;
+        let elem_snap_ty = self.encoder.encode_snapshot_type(slice_types.elem_ty_rs).with_span(span)?;
+
+        let len = slice_types.encode_slice_len_call(self.encoder, bytes.clone());
+        let old_len = vir::Expr::labelled_old(&label, len.clone());
+
+        // The old bytes are kept
+        stmts.extend(
+            self.encode_slice_model_havoc(&label, &slice_types, bytes.clone(), old_len.clone())
+                .with_span(span)?
+        );
+        let i_var: vir::Expr = vir_local!{ i: Int }.into();
+
+        let appended = self.mir_encoder.encode_operand_expr(&args[1])
+            .with_span(span)?;
+        if is_char {
+            // A character is encoded by one to four bytes
+            let len_grows = vir!{ ([old_len] < [len]) && ([len] <= ([old_len] + [vir::Expr::from(4)])) };
+            stmts.push(vir!{ inhale [len_grows] });
+            let lookup_last = slice_types.encode_lookup_pure_call(
+                self.encoder,
+                bytes,
+                old_len.clone(),
+                elem_snap_ty,
+            );
+            let is_ascii = vir!{ [appended] < [vir::Expr::from(128)] };
+            let ascii_byte = vir!{ ([len] == ([old_len] + [vir::Expr::from(1)])) && ([lookup_last] == [appended]) };
+            stmts.push(vir!{ inhale ([is_ascii] ==> [ascii_byte]) });
+        } else {
+            let (appended, appended_ty) = if let ty::TyKind::Ref(_, appended_ty, _) = self.mir_encoder.get_operand_ty(&args[1]).kind() {
+                (appended, *appended_ty)
+            } else {
+                unreachable!()
+            };
+            let appended_types = self.encoder.encode_slice_types(appended_ty).with_span(span)?;
+            let appended_len = appended_types.encode_slice_len_call(self.encoder, appended.clone());
+            let len_is_sum = vir!{ [len] == ([old_len] + [appended_len]) };
+            stmts.push(vir!{ inhale [len_is_sum] });
+
+            let lookup_appended_i = appended_types.encode_lookup_pure_call(
+                self.encoder,
+                appended,
+                i_var.clone(),
+                elem_snap_ty.clone(),
+            );
+            let lookup_new_i = slice_types.encode_lookup_pure_call(
+                self.encoder,
+                bytes,
+                vir!{ [old_len] + [i_var] },
+                elem_snap_ty,
+            );
+            let i_in_appended_bounds = vir!{ ([vir::Expr::from(0)] <= [i_var]) && ([i_var] < [appended_len]) };
+            let lookup_same_as_appended = vir!{ [lookup_new_i] == [lookup_appended_i] };
+            let forall_body = vir!{ [i_in_appended_bounds] ==> [lookup_same_as_appended] };
+            let appended_bytes = vir!{ forall i: Int :: { [lookup_appended_i] } [ forall_body ] };
+            stmts.push(vir!{ inhale [appended_bytes] });
+        }
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+
+        // Store a label for permissions got back from the call
+        debug!(
+            "String push call location {:?} has label {}",
+            location, label
+        );
+        self.label_after_location.insert(location, label);
+
+        Ok(stmts)
+    }
+
+    /// Havoc the elements of a string or of a vector, except for the first
+    /// `kept_len` ones, which keep their values at `label`.
+    fn encode_slice_model_havoc(
+        &self,
+        label: &str,
+        slice_types: &EncodedSliceTypes<'tcx>,
+        elems: vir::Expr,
+        kept_len: vir::Expr,
+    ) -> EncodingResult<Vec<vir::Stmt>> {
+        let mut stmts = vec![];
+        let elem_snap_ty = self.encoder.encode_snapshot_type(slice_types.elem_ty_rs)?;
+
+        let elems_acc = vir::Expr::predicate_access_predicate(
+            slice_types.slice_pred.clone(),
+            elems.clone(),
+            vir::PermAmount::Write,
+        );
+
+        // exhale and re-inhale to havoc
+        stmts.push(vir!{ exhale [elems_acc] });
+        stmts.push(vir!{ inhale [elems_acc] });
+
+        let i_var: vir::Expr = vir_local!{ i: Int }.into();
+        let lookup_i = slice_types.encode_lookup_pure_call(
+            self.encoder,
+            elems,
+            i_var.clone(),
+            elem_snap_ty,
+        );
+        let i_in_kept_bounds = vir!{ ([vir::Expr::from(0)] <= [i_var]) && ([i_var] < [kept_len]) };
+        let lookup_same_as_old = vir!{ [lookup_i] == [vir::Expr::labelled_old(label, lookup_i.clone())] };
+        let forall_body = vir!{ [i_in_kept_bounds] ==> [lookup_same_as_old] };
+        let kept_elems = vir!{ forall i: Int :: { [lookup_i] } [ forall_body ] };
+        stmts.push(vir!{ inhale [kept_elems] });
+        Ok(stmts)
+    }
+
+    /// The vector and its type from the first argument of a `Vec` method,
+    /// which takes `self` by reference.
+    fn encode_vec_self_arg(
+        &self,
+        args: &[mir::Operand<'tcx>],
+        span: Span,
+    ) -> SpannedEncodingResult<(vir::Expr, EncodedSliceTypes<'tcx>)> {
+        let vec = self.mir_encoder.encode_operand_expr(&args[0])
+            .with_span(span)?;
+        let vec_ty = if let ty::TyKind::Ref(_, vec_ty, _) = self.mir_encoder.get_operand_ty(&args[0]).kind() {
+            *vec_ty
+        } else {
+            unreachable!()
+        };
+        let (elems, elems_ty) = self.encode_slice_model(vec, vec_ty).with_span(span)?;
+        let slice_types = self.encoder.encode_slice_types(elems_ty).with_span(span)?;
+        Ok((elems, slice_types))
+    }
+
+    /// Encode `Vec::push(&mut self, value: T)`, which appends `value` to the
+    /// elements of the vector.
+    fn encode_vec_push_call(
+        &mut self,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        assert!(args.len() == 2, "unexpected args to Vec::push(): {:?}", args);
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::label(&label));
+
+        let (elems, slice_types) = self.encode_vec_self_arg(args, span)?;
+        let elem_snap_ty = self.encoder.encode_snapshot_type(slice_types.elem_ty_rs).with_span(span)?;
+        let len = slice_types.encode_slice_len_call(self.encoder, elems.clone());
+        let old_len = vir::Expr::labelled_old(&label, len.clone());
+
+        stmts.extend(
+            self.encode_slice_model_havoc(&label, &slice_types, elems.clone(), old_len.clone())
+                .with_span(span)?
+        );
+        stmts.push(vir!{ inhale ([len] == ([old_len] + [vir::Expr::from(1)])) });
+
+        let value = self.mir_encoder.encode_operand_expr(&args[1])
+            .with_span(span)?;
+        let lookup_last = slice_types.encode_lookup_pure_call(
+            self.encoder,
+            elems,
+            old_len,
+            elem_snap_ty,
+        );
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::eq_cmp(lookup_last, vir::Expr::snap_app(value))
+        ));
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+
+        // Store a label for permissions got back from the call
+        debug!(
+            "Vec::push call location {:?} has label {}",
+            location, label
+        );
+        self.label_after_location.insert(location, label);
+
+        Ok(stmts)
+    }
+
+    /// Encode `Vec::pop(&mut self) -> Option<T>`, which removes the last
+    /// element of the vector and returns it, or returns `None` if the vector
+    /// is empty.
+    fn encode_vec_pop_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        assert!(args.len() == 1, "unexpected args to Vec::pop(): {:?}", args);
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::label(&label));
+
+        let (elems, slice_types) = self.encode_vec_self_arg(args, span)?;
+        let elem_snap_ty = self.encoder.encode_snapshot_type(slice_types.elem_ty_rs).with_span(span)?;
+        let len = slice_types.encode_slice_len_call(self.encoder, elems.clone());
+        let old_len = vir::Expr::labelled_old(&label, len.clone());
+        let old_last = vir::Expr::labelled_old(
+            &label,
+            slice_types.encode_lookup_pure_call(
+                self.encoder,
+                elems.clone(),
+                vir!{ [len] - [vir::Expr::from(1)] },
+                elem_snap_ty,
+            ),
+        );
+
+        let (dst, pre_stmts, dst_ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+        stmts.extend(pre_stmts);
+        stmts.extend(self.encode_havoc(&dst));
+        stmts.push(vir::Stmt::Inhale(
+            self.mir_encoder
+                .encode_place_predicate_permission(dst.clone(), vir::PermAmount::Write)
+                .unwrap()
+        ));
+
+        let was_empty = vir!{ [old_len] == [vir::Expr::from(0)] };
+        let shrinks = vir!{ [len] == ([old_len] - [vir::Expr::from(1)]) };
+        stmts.push(vir!{ inhale ([was_empty] ==> ([len] == [vir::Expr::from(0)])) });
+        let was_not_empty = vir::Expr::not(was_empty.clone());
+        stmts.push(vir!{ inhale ([was_not_empty] ==> [shrinks]) });
+        stmts.extend(
+            self.encode_slice_model_havoc(&label, &slice_types, elems, len)
+                .with_span(span)?
+        );
+
+        // `None` and `Some` are the variants 0 and 1 of `Option`
+        let none = self.encoder.encode_snapshot_variant_constructor(dst_ty, 0, vec![])
+            .with_span(span)?;
+        let some = self.encoder.encode_snapshot_variant_constructor(dst_ty, 1, vec![old_last])
+            .with_span(span)?;
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::eq_cmp(vir::Expr::snap_app(dst), vir::Expr::ite(was_empty, none, some))
+        ));
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+
+        // Store a label for permissions got back from the call
+        debug!(
+            "Vec::pop call location {:?} has label {}",
+            location, label
+        );
+        self.label_after_location.insert(location, label);
+
+        Ok(stmts)
+    }
+
+    /// Encode `Vec::truncate(&mut self, len: usize)`, which keeps the first
+    /// `len` elements of the vector, or `Vec::clear(&mut self)`, which keeps
+    /// none.
+    fn encode_vec_truncate_call(
+        &mut self,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        assert!(args.len() <= 2, "unexpected args to Vec::truncate(): {:?}", args);
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::label(&label));
+
+        let (elems, slice_types) = self.encode_vec_self_arg(args, span)?;
+        let len = slice_types.encode_slice_len_call(self.encoder, elems.clone());
+        let old_len = vir::Expr::labelled_old(&label, len.clone());
+        let new_len = if args.len() == 2 {
+            let kept_len = self.mir_encoder.encode_operand_expr(&args[1])
+                .with_span(span)?;
+            vir::Expr::ite(
+                vir!{ [kept_len] < [old_len] },
+                kept_len,
+                old_len,
+            )
+        } else {
+            vir::Expr::from(0)
+        };
+
+        stmts.extend(
+            self.encode_slice_model_havoc(&label, &slice_types, elems, new_len.clone())
+                .with_span(span)?
+        );
+        stmts.push(vir!{ inhale ([len] == [new_len]) });
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+
+        // Store a label for permissions got back from the call
+        debug!(
+            "Vec::truncate call location {:?} has label {}",
+            location, label
+        );
+        self.label_after_location.insert(location, label);
+
+        Ok(stmts)
+    }
+
+    /// Whether `args` are the arguments of an `Index::index` call that reads
+    /// an element of a vector.
+    fn is_vec_indexing(&self, args: &[mir::Operand<'tcx>]) -> bool {
+        let tcx = self.encoder.env().tcx();
+        let is_vec = match self.mir_encoder.get_operand_ty(&args[0]).kind() {
+            ty::TyKind::Ref(_, base_ty, _) => is_vec_ty(tcx, base_ty),
+            _ => false,
+        };
+        let idx_ty = self.mir_encoder.get_operand_ty(&args[1]);
+        is_vec && matches!(idx_ty.kind(), ty::TyKind::Uint(ty::UintTy::Usize))
+    }
+
+    /// Encode `&v[i]`, where `v` is a vector. The resulting reference points
+    /// to a value equal to the `i`-th element of `v`, which must exist.
+    fn encode_vec_index_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        assert!(args.len() == 2, "unexpected args to Index::index(): {:?}", args);
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::Label(label.clone()));
+
+        let (elems, slice_types) = self.encode_vec_self_arg(args, span)?;
+        let elem_snap_ty = self.encoder.encode_snapshot_type(slice_types.elem_ty_rs).with_span(span)?;
+        let idx = self.mir_encoder.encode_operand_expr(&args[1])
+            .with_span(span)?;
+        let len = slice_types.encode_slice_len_call(self.encoder, elems.clone());
+
+        if self.check_panics {
+            let pos = self.encoder.error_manager().register(
+                span,
+                ErrorCtxt::BoundsCheckAssert,
+                self.proc_def_id,
+            );
+            stmts.push(vir::Stmt::Assert(vir!{ [idx] < [len] }, pos));
+        }
+
+        let (encoded_lhs, encode_stmts, lhs_ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+        stmts.extend(encode_stmts);
+
+        stmts.extend(self.encode_havoc(&encoded_lhs));
+        let val_ref_field = self.encoder.encode_value_field(lhs_ty).with_span(span)?;
+        let elem = encoded_lhs.field(val_ref_field);
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::acc_permission(elem.clone(), vir::PermAmount::Write)
+        ));
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::predicate_access_predicate(
+                slice_types.elem_pred.clone(),
+                elem.clone(),
+                vir::PermAmount::Read,
+            )
+        ));
+
+        let lookup = slice_types.encode_lookup_pure_call(
+            self.encoder,
+            elems,
+            idx,
+            elem_snap_ty,
+        );
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::eq_cmp(vir::Expr::snap_app(elem), lookup)
+        ));
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+
+        // Store a label for permissions got back from the call
+        debug!(
+            "Vec indexing call location {:?} has label {}",
+            location, label
+        );
+        self.label_after_location.insert(location, label);
+
+        Ok(stmts)
+    }
+
+    /// The map and its type from the first argument of a map method, which
+    /// takes `self` by reference.
+    fn encode_map_self_arg(
+        &self,
+        args: &[mir::Operand<'tcx>],
+        span: Span,
+    ) -> SpannedEncodingResult<(vir::Expr, ty::Ty<'tcx>)> {
+        let map = self.mir_encoder.encode_operand_expr(&args[0])
+            .with_span(span)?;
+        match self.mir_encoder.get_operand_ty(&args[0]).kind() {
+            ty::TyKind::Ref(_, map_ty, _) if is_map_ty(self.encoder.env().tcx(), map_ty) => {
+                Ok((map, *map_ty))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// The snapshot of the key passed as `key_arg` to a method of a map of
+    /// type `map_ty`, at `label`. The key can be passed by value or by
+    /// reference, but it must have the key type of the map: looking up a
+    /// `String` key with a `&str`, for example, is not supported.
+    fn encode_map_key_arg(
+        &self,
+        map_ty: ty::Ty<'tcx>,
+        key_arg: &mir::Operand<'tcx>,
+        label: &str,
+        span: Span,
+    ) -> SpannedEncodingResult<vir::Expr> {
+        let key_ty = match map_ty.kind() {
+            ty::TyKind::Adt(_, substs) => substs.type_at(0),
+            _ => unreachable!(),
+        };
+        let arg_ty = self.mir_encoder.get_operand_ty(key_arg);
+        let arg_key_ty = match arg_ty.kind() {
+            ty::TyKind::Ref(_, inner_ty, _) => *inner_ty,
+            _ => arg_ty,
+        };
+        if arg_key_ty != key_ty {
+            return Err(SpannedEncodingError::unsupported(
+                format!("maps can only be looked up with keys of their key type, not with a {}", arg_ty),
+                span,
+            ));
+        }
+        let key = self.mir_encoder.encode_operand_expr(key_arg)
+            .with_span(span)?;
+        Ok(vir::Expr::labelled_old(label, vir::Expr::snap_app(key)))
+    }
+
+    /// Encode `HashMap::new()` or `BTreeMap::new()`, which return a map
+    /// without keys.
+    fn encode_map_new_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let mut stmts = vec![];
+        let (dst, pre_stmts, dst_ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+        stmts.extend(pre_stmts);
+        stmts.extend(self.encode_havoc(&dst));
+        stmts.push(vir::Stmt::Inhale(
+            self.mir_encoder
+                .encode_place_predicate_permission(dst.clone(), vir::PermAmount::Write)
+                .unwrap()
+        ));
+
+        let len = self.encoder.encode_snapshot_map_len(dst_ty, dst.clone())
+            .with_span(span)?;
+        stmts.push(vir!{ inhale ([len] == [vir::Expr::from(0)]) });
+
+        let key_ty = match dst_ty.kind() {
+            ty::TyKind::Adt(_, substs) => substs.type_at(0),
+            _ => unreachable!(),
+        };
+        let key_snap_ty = self.encoder.encode_snapshot_type(key_ty).with_span(span)?;
+        let key_var = vir::LocalVar::new("key", key_snap_ty);
+        let contains_key = self.encoder.encode_snapshot_map_contains(
+            dst_ty,
+            dst,
+            key_var.clone().into(),
+        ).with_span(span)?;
+        stmts.push(vir::Stmt::Inhale(vir::Expr::forall(
+            vec![key_var],
+            vec![vir::Trigger::new(vec![contains_key.clone()])],
+            vir::Expr::not(contains_key),
+        )));
+        Ok(stmts)
+    }
+
+    /// Encode `len`, `is_empty` or `contains_key` of a map.
+    fn encode_map_query_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+        query: MapQuery,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::Label(label.clone()));
+
+        let (map, map_ty) = self.encode_map_self_arg(args, span)?;
+        let len = self.encoder.encode_snapshot_map_len(map_ty, map.clone())
+            .with_span(span)?;
+        let rhs = match query {
+            MapQuery::Len => len,
+            MapQuery::IsEmpty => vir::Expr::eq_cmp(len, 0.into()),
+            MapQuery::ContainsKey => {
+                let key = self.encode_map_key_arg(map_ty, &args[1], &label, span)?;
+                self.encoder.encode_snapshot_map_contains(map_ty, map, key)
+                    .with_span(span)?
+            }
+        };
+
+        let (encoded_lhs, encode_stmts, ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Mutable(None, location),
+        ).with_span(span)?;
+        stmts.extend(encode_stmts);
+        stmts.extend(
+            self.encode_copy_value_assign(
+                encoded_lhs,
+                rhs,
+                ty,
+                location,
+            )?
+        );
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+
+        // Store a label for permissions got back from the call
+        debug!(
+            "Map query call location {:?} has label {}",
+            location, label
+        );
+        self.label_after_location.insert(location, label);
+
+        Ok(stmts)
+    }
+
+    /// Encode `get(&self, key: &K) -> Option<&V>` of a map, which returns the
+    /// value of the key if the map contains it.
+    fn encode_map_get_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::Label(label.clone()));
+
+        let (map, map_ty) = self.encode_map_self_arg(args, span)?;
+        let key = self.encode_map_key_arg(map_ty, &args[1], &label, span)?;
+        let contains_key = self.encoder.encode_snapshot_map_contains(map_ty, map.clone(), key.clone())
+            .with_span(span)?;
+        let value = self.encoder.encode_snapshot_map_lookup(map_ty, map, key)
+            .with_span(span)?;
+
+        let (dst, pre_stmts, dst_ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+        stmts.extend(pre_stmts);
+        stmts.extend(self.encode_havoc(&dst));
+        stmts.push(vir::Stmt::Inhale(
+            self.mir_encoder
+                .encode_place_predicate_permission(dst.clone(), vir::PermAmount::Write)
+                .unwrap()
+        ));
+        let result = self.encode_option_snapshot(dst_ty, contains_key, value)
+            .with_span(span)?;
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::eq_cmp(vir::Expr::snap_app(dst), result)
+        ));
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+
+        // Store a label for permissions got back from the call
+        debug!(
+            "Map::get call location {:?} has label {}",
+            location, label
+        );
+        self.label_after_location.insert(location, label);
 
-                stmts.push(vir::Stmt::comment(format!("Rust assertion: {}", assert_msg)));
-                if self.check_panics {
-                    stmts.push(vir::Stmt::Assert(
-                        viper_guard,
-                        self.encoder.error_manager().register(
-                            term.source_info.span,
-                            error_ctxt,
-                            self.proc_def_id,
-                        ),
-                    ));
-                } else {
-                    stmts.push(vir::Stmt::comment("This assertion will not be checked"));
-                    stmts.push(vir::Stmt::Inhale(viper_guard));
-                };
+        Ok(stmts)
+    }
 
-                (stmts, MirSuccessor::Goto(target))
+    /// The snapshot of an `Option` of type `option_ty` that is `Some(value)`
+    /// if `is_some` holds and `None` otherwise.
+    fn encode_option_snapshot(
+        &self,
+        option_ty: ty::Ty<'tcx>,
+        is_some: vir::Expr,
+        value: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        // `None` and `Some` are the variants 0 and 1 of `Option`
+        let none = self.encoder.encode_snapshot_variant_constructor(option_ty, 0, vec![])?;
+        let some = self.encoder.encode_snapshot_variant_constructor(option_ty, 1, vec![value])?;
+        Ok(vir::Expr::ite(is_some, some, none))
+    }
+
+    /// Encode `insert(&mut self, key: K, value: V) -> Option<V>` or, if
+    /// `is_insert` is not set, `remove(&mut self, key: &K) -> Option<V>` of
+    /// a map. The value of the key is updated and the previous value, if any,
+    /// is returned. The other keys of the map keep their values.
+    fn encode_map_update_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+        is_insert: bool,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::Label(label.clone()));
+
+        let (map, map_ty) = self.encode_map_self_arg(args, span)?;
+        let key = self.encode_map_key_arg(map_ty, &args[1], &label, span)?;
+        let encoder = self.encoder;
+        let contains = |key| encoder.encode_snapshot_map_contains(map_ty, map.clone(), key);
+        let lookup = |key| encoder.encode_snapshot_map_lookup(map_ty, map.clone(), key);
+        let old = |expr| vir::Expr::labelled_old(&label, expr);
+
+        let contains_key = contains(key.clone()).with_span(span)?;
+        let lookup_key = lookup(key.clone()).with_span(span)?;
+        let len = self.encoder.encode_snapshot_map_len(map_ty, map.clone())
+            .with_span(span)?;
+
+        // The result is the previous value of the key
+        let (dst, pre_stmts, dst_ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+        stmts.extend(pre_stmts);
+        stmts.extend(self.encode_havoc(&dst));
+        stmts.push(vir::Stmt::Inhale(
+            self.mir_encoder
+                .encode_place_predicate_permission(dst.clone(), vir::PermAmount::Write)
+                .unwrap()
+        ));
+        let result = self.encode_option_snapshot(
+            dst_ty,
+            old(contains_key.clone()),
+            old(lookup_key.clone()),
+        ).with_span(span)?;
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::eq_cmp(vir::Expr::snap_app(dst), result)
+        ));
+
+        // exhale and re-inhale to havoc
+        let map_pred = self.encoder.encode_type_predicate_use(map_ty).with_span(span)?;
+        let map_acc = vir::Expr::predicate_access_predicate(
+            map_pred,
+            map.clone(),
+            vir::PermAmount::Write,
+        );
+        stmts.push(vir!{ exhale [map_acc] });
+        stmts.push(vir!{ inhale [map_acc] });
+
+        let old_len = old(len.clone());
+        let one = vir::Expr::from(1);
+        if is_insert {
+            let value = self.mir_encoder.encode_operand_expr(&args[2])
+                .with_span(span)?;
+            let value = old(vir::Expr::snap_app(value));
+            stmts.push(vir!{ inhale ([contains_key] && ([lookup_key] == [value])) });
+            let old_contains_key = old(contains_key.clone());
+            let len_same = vir!{ [len] == [old_len] };
+            let len_grows = vir!{ [len] == ([old_len] + [one]) };
+            stmts.push(vir!{ inhale ([old_contains_key] ==> [len_same]) });
+            let fresh_key = vir::Expr::not(old_contains_key);
+            stmts.push(vir!{ inhale ([fresh_key] ==> [len_grows]) });
+        } else {
+            stmts.push(vir::Stmt::Inhale(vir::Expr::not(contains_key.clone())));
+            let old_contains_key = old(contains_key);
+            let len_same = vir!{ [len] == [old_len] };
+            let len_shrinks = vir!{ [len] == ([old_len] - [one]) };
+            stmts.push(vir!{ inhale ([old_contains_key] ==> [len_shrinks]) });
+            let missing_key = vir::Expr::not(old_contains_key);
+            stmts.push(vir!{ inhale ([missing_key] ==> [len_same]) });
+        }
+
+        // The other keys are unchanged
+        let key_ty = match map_ty.kind() {
+            ty::TyKind::Adt(_, substs) => substs.type_at(0),
+            _ => unreachable!(),
+        };
+        let key_snap_ty = self.encoder.encode_snapshot_type(key_ty).with_span(span)?;
+        let other_key_var = vir::LocalVar::new("other_key", key_snap_ty);
+        let other_key: vir::Expr = other_key_var.clone().into();
+        let contains_other_key = contains(other_key.clone()).with_span(span)?;
+        let lookup_other_key = lookup(other_key.clone()).with_span(span)?;
+        let old_contains_other_key = old(contains_other_key.clone());
+        let old_lookup_other_key = old(lookup_other_key.clone());
+        let is_other_key = vir!{ [other_key] != [key] };
+        stmts.push(vir::Stmt::Inhale(vir::Expr::forall(
+            vec![other_key_var.clone()],
+            vec![vir::Trigger::new(vec![contains_other_key.clone()])],
+            vir!{ [is_other_key] ==> ([contains_other_key] == [old_contains_other_key]) },
+        )));
+        stmts.push(vir::Stmt::Inhale(vir::Expr::forall(
+            vec![other_key_var],
+            vec![vir::Trigger::new(vec![lookup_other_key.clone()])],
+            vir!{ ([is_other_key] && [old_contains_other_key]) ==> ([lookup_other_key] == [old_lookup_other_key]) },
+        )));
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+
+        // Store a label for permissions got back from the call
+        debug!(
+            "Map update call location {:?} has label {}",
+            location, label
+        );
+        self.label_after_location.insert(location, label);
+
+        Ok(stmts)
+    }
+
+    /// Encode the fields of an iterator over a range of integers, see
+    /// [RangeIteratorKind]. The fields of `Rev` and `StepBy` are the ones of
+    /// the range that they wrap, together with the own fields of `StepBy`.
+    fn encode_range_iterator_fields(
+        &self,
+        iter: vir::Expr,
+        iter_ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<RangeIteratorFields> {
+        let tcx = self.encoder.env().tcx();
+        let inner_ty = match iter_ty.kind() {
+            ty::TyKind::Adt(_, substs) => substs.type_at(0),
+            ref x => unreachable!("{:?}", x),
+        };
+        let encode_field = |base: vir::Expr, name: &str, field_ty: ty::Ty<'tcx>| {
+            let field = self.encoder.encode_struct_field(name, field_ty)?;
+            self.encoder.encode_value_expr(base.field(field), field_ty)
+        };
+        match range_iterator_kind(tcx, iter_ty).unwrap() {
+            kind @ (RangeIteratorKind::Range | RangeIteratorKind::RangeInclusive) => {
+                Ok(RangeIteratorFields {
+                    start: encode_field(iter.clone(), "start", inner_ty)?,
+                    end: encode_field(iter.clone(), "end", inner_ty)?,
+                    exhausted: if kind == RangeIteratorKind::RangeInclusive {
+                        Some(encode_field(iter, "exhausted", tcx.types.bool)?)
+                    } else {
+                        None
+                    },
+                    step: None,
+                    first_take: None,
+                })
             }
+            RangeIteratorKind::Rev { .. } => {
+                let field = self.encoder.encode_struct_field("iter", inner_ty)?;
+                self.encode_range_iterator_fields(iter.field(field), inner_ty)
+            }
+            RangeIteratorKind::StepBy => {
+                let field = self.encoder.encode_struct_field("iter", inner_ty)?;
+                let range_fields = self.encode_range_iterator_fields(iter.clone().field(field), inner_ty)?;
+                Ok(RangeIteratorFields {
+                    step: Some(encode_field(iter.clone(), "step", tcx.types.usize)?),
+                    first_take: Some(encode_field(iter, "first_take", tcx.types.bool)?),
+                    ..range_fields
+                })
+            }
+        }
+    }
 
-            TerminatorKind::Resume
-            | TerminatorKind::Yield { .. }
-            | TerminatorKind::GeneratorDrop
-            | TerminatorKind::InlineAsm { .. } => unimplemented!("{:?}", term.kind),
+    /// Encode `RangeInclusive::new(start, end)`, which is the desugaring of
+    /// `start..=end`.
+    fn encode_range_inclusive_new_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        assert!(args.len() == 2, "unexpected args to RangeInclusive::new(): {:?}", args);
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::label(&label));
+
+        let (dst, pre_stmts, dst_ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+        stmts.extend(pre_stmts);
+        stmts.extend(self.encode_havoc_and_allocation(&dst));
+
+        let fields = self.encode_range_iterator_fields(dst, dst_ty).with_span(span)?;
+        let start = self.mir_encoder.encode_operand_expr(&args[0]).with_span(span)?;
+        let end = self.mir_encoder.encode_operand_expr(&args[1]).with_span(span)?;
+        stmts.push(vir::Stmt::Inhale(vir::Expr::eq_cmp(
+            fields.start,
+            vir::Expr::labelled_old(&label, start),
+        )));
+        stmts.push(vir::Stmt::Inhale(vir::Expr::eq_cmp(
+            fields.end,
+            vir::Expr::labelled_old(&label, end),
+        )));
+        stmts.push(vir::Stmt::Inhale(vir::Expr::not(fields.exhausted.unwrap())));
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+        self.label_after_location.insert(location, label);
+
+        Ok(stmts)
+    }
+
+    /// Encode `Iterator::rev(self)` or `Iterator::step_by(self, step)` on a
+    /// range of integers, which wrap the range in a new iterator. A step of
+    /// zero panics.
+    fn encode_range_adapter_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let tcx = self.encoder.env().tcx();
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::label(&label));
+
+        let (dst, pre_stmts, dst_ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+        stmts.extend(pre_stmts);
+        let kind = match range_iterator_kind(tcx, dst_ty) {
+            Some(kind) => kind,
+            None => {
+                return Err(SpannedEncodingError::unsupported(
+                    format!("iterating over `{}` is not supported", dst_ty),
+                    span,
+                ));
+            }
         };
-        Ok(result)
+
+        if kind == RangeIteratorKind::StepBy && self.check_panics {
+            let step = self.mir_encoder.encode_operand_expr(&args[1]).with_span(span)?;
+            let pos = self.encoder.error_manager().register(
+                span,
+                ErrorCtxt::Panic(PanicCause::Assert, Some("assertion failed: step != 0".to_string())),
+                self.proc_def_id,
+            );
+            stmts.push(vir::Stmt::Assert(vir::Expr::ne_cmp(step, 0.into()), pos));
+        }
+
+        stmts.extend(self.encode_havoc_and_allocation(&dst));
+        let inner_ty = self.mir_encoder.get_operand_ty(&args[0]);
+        let iter_field = self.encoder.encode_struct_field("iter", inner_ty).with_span(span)?;
+        stmts.extend(self.encode_assign_operand(&dst.clone().field(iter_field), &args[0], location)?);
+
+        if kind == RangeIteratorKind::StepBy {
+            // `StepBy` stores the step minus one, and yields first the
+            // start of the range
+            let step = self.mir_encoder.encode_operand_expr(&args[1]).with_span(span)?;
+            let fields = self.encode_range_iterator_fields(dst, dst_ty).with_span(span)?;
+            stmts.push(vir::Stmt::Inhale(vir::Expr::eq_cmp(
+                fields.step.unwrap(),
+                vir::Expr::sub(vir::Expr::labelled_old(&label, step), 1.into()),
+            )));
+            stmts.push(vir::Stmt::Inhale(fields.first_take.unwrap()));
+        }
+
+        Ok(stmts)
     }
 
-    fn encode_slice_len_call(
+    /// Encode `Iterator::next(&mut self)` on an iterator over a range of
+    /// integers, see [RangeIteratorKind]. The fields of the iterator are
+    /// updated like in the implementation of the standard library, on which
+    /// the automatic invariant of `for` loops over ranges relies.
+    fn encode_range_iterator_next_call(
         &mut self,
         destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
         args: &[mir::Operand<'tcx>],
         location: mir::Location,
         span: Span,
     ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
-        assert!(args.len() == 1, "unexpected args to slice::len(): {:?}", args);
-        let slice_operand = self.mir_encoder.encode_operand_expr(&args[0])
+        assert!(args.len() == 1, "unexpected args to Iterator::next(): {:?}", args);
+        let mut stmts = vec![];
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::label(&label));
+
+        let iter = self.mir_encoder.encode_operand_expr(&args[0]).with_span(span)?;
+        let iter_ty = if let ty::TyKind::Ref(_, iter_ty, _) = self.mir_encoder.get_operand_ty(&args[0]).kind() {
+            *iter_ty
+        } else {
+            unreachable!()
+        };
+        let fields = self.encode_range_iterator_fields(iter, iter_ty).with_span(span)?;
+        let old = |expr: &vir::Expr| vir::Expr::labelled_old(&label, expr.clone());
+        let start = old(&fields.start);
+        let end = old(&fields.end);
+
+        // Whether there is a next value, the next value, and the updates of
+        // the fields
+        let one = vir::Expr::from(1);
+        let kind = range_iterator_kind(self.encoder.env().tcx(), iter_ty).unwrap();
+        let (has_next, value, updates) = match kind {
+            RangeIteratorKind::Range => {
+                let has_next = vir::Expr::lt_cmp(start.clone(), end);
+                let new_start = vir::Expr::ite(
+                    has_next.clone(),
+                    vir::Expr::add(start.clone(), one),
+                    start.clone(),
+                );
+                (has_next, start, vec![(fields.start, new_start)])
+            }
+            RangeIteratorKind::Rev { inclusive: false } => {
+                let has_next = vir::Expr::lt_cmp(start, end.clone());
+                let value = vir::Expr::sub(end.clone(), one);
+                let new_end = vir::Expr::ite(has_next.clone(), value.clone(), end);
+                (has_next, value, vec![(fields.end, new_end)])
+            }
+            RangeIteratorKind::RangeInclusive | RangeIteratorKind::Rev { inclusive: true } => {
+                // The range is exhausted after yielding its last value, which
+                // could be the maximum value of the type
+                let exhausted = old(fields.exhausted.as_ref().unwrap());
+                let has_next = vir::Expr::and(
+                    vir::Expr::not(exhausted.clone()),
+                    vir::Expr::le_cmp(start.clone(), end.clone()),
+                );
+                let is_last = vir::Expr::and(
+                    has_next.clone(),
+                    vir::Expr::eq_cmp(start.clone(), end.clone()),
+                );
+                let moves = vir::Expr::and(
+                    has_next.clone(),
+                    vir::Expr::lt_cmp(start.clone(), end.clone()),
+                );
+                let (value, moved_field, moved_value) = if kind == RangeIteratorKind::RangeInclusive {
+                    (start.clone(), fields.start, vir::Expr::add(start, one))
+                } else {
+                    (end.clone(), fields.end, vir::Expr::sub(end, one))
+                };
+                let updates = vec![
+                    (fields.exhausted.unwrap(), vir::Expr::or(exhausted, is_last)),
+                    (moved_field, vir::Expr::ite(moves, moved_value, value.clone())),
+                ];
+                (has_next, value, updates)
+            }
+            RangeIteratorKind::StepBy => {
+                // The first call yields the start of the range, the next ones
+                // skip `step` values
+                let first_take = old(fields.first_take.as_ref().unwrap());
+                let skipped = vir::Expr::ite(first_take, 0.into(), old(fields.step.as_ref().unwrap()));
+                let value = vir::Expr::add(start, skipped);
+                let has_next = vir::Expr::lt_cmp(value.clone(), end.clone());
+                let new_start = vir::Expr::ite(
+                    has_next.clone(),
+                    vir::Expr::add(value.clone(), one),
+                    end,
+                );
+                let updates = vec![
+                    (fields.start, new_start),
+                    (fields.first_take.unwrap(), false.into()),
+                ];
+                (has_next, value, updates)
+            }
+        };
+        for (field, new_value) in updates {
+            stmts.push(vir::Stmt::Assign(field, new_value, vir::AssignKind::Copy));
+        }
+
+        let (dst, pre_stmts, dst_ty, _) = self.encode_place(
+            &destination.as_ref().unwrap().0,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+        stmts.extend(pre_stmts);
+        stmts.extend(self.encode_havoc_and_allocation(&dst));
+
+        // `None` and `Some` are the variants 0 and 1 of `Option`
+        let none = self.encoder.encode_snapshot_variant_constructor(dst_ty, 0, vec![])
             .with_span(span)?;
+        let some = self.encoder.encode_snapshot_variant_constructor(dst_ty, 1, vec![value])
+            .with_span(span)?;
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::eq_cmp(vir::Expr::snap_app(dst), vir::Expr::ite(has_next, some, none))
+        ));
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
+
+        // Store a label for permissions got back from the call
+        debug!(
+            "Iterator::next call location {:?} has label {}",
+            location, label
+        );
+        self.label_after_location.insert(location, label);
+
+        Ok(stmts)
+    }
+
+    /// Whether `args` are the arguments of an `Index::index` call that takes
+    /// a subslice of a slice or of an array.
+    fn is_slicing_by_range(&self, args: &[mir::Operand<'tcx>]) -> bool {
+        let base_ty = self.mir_encoder.get_operand_ty(&args[0]);
+        let is_sequence = match base_ty.kind() {
+            ty::TyKind::Ref(_, base_ty, _) => {
+                matches!(base_ty.kind(), ty::TyKind::Array(..) | ty::TyKind::Slice(..))
+            }
+            _ => false,
+        };
+        let idx_ty = self.mir_encoder.get_operand_ty(&args[1]);
+        let is_range = match idx_ty.ty_adt_def() {
+            Some(adt_def) => matches!(
+                self.encoder.env().tcx().def_path_str(adt_def.did).as_str(),
+                "std::ops::Range"
+                    | "std::ops::RangeFrom"
+                    | "std::ops::RangeTo"
+                    | "std::ops::RangeFull"
+                    | "std::ops::RangeInclusive"
+                    | "std::ops::RangeToInclusive"
+            ),
+            None => false,
+        };
+        is_sequence && is_range
+    }
 
+    /// Encode `&base[range]`, where `base` is a slice or an array and `range`
+    /// is any kind of range, e.g. `start..end` or `start..`. The elements of
+    /// the resulting slice are the ones of `base` from the start of the range
+    /// on.
+    fn encode_slicing_call(
+        &mut self,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        args: &[mir::Operand<'tcx>],
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        assert!(args.len() == 2, "unexpected args to Index::index(): {:?}", args);
         let mut stmts = vec![];
 
-        // we need to put a label before, it seems..
         let label = self.cfg_method.get_fresh_label_name();
         stmts.push(vir::Stmt::Label(label.clone()));
 
-        let slice_ty_ref = self.mir_encoder.get_operand_ty(&args[0]);
-        let slice_ty = if let ty::TyKind::Ref(_, slice_ty, _) = slice_ty_ref.kind() { slice_ty } else { unreachable!() };
-        let slice_types = self.encoder.encode_slice_types(slice_ty).with_span(span)?;
+        let base = self.mir_encoder.encode_operand_expr(&args[0])
+            .with_span(span)?;
+        let base_ty = if let ty::TyKind::Ref(_, base_ty, _) = self.mir_encoder.get_operand_ty(&args[0]).kind() {
+            *base_ty
+        } else {
+            unreachable!()
+        };
 
-        let rhs = slice_types.encode_slice_len_call(self.encoder, slice_operand);
+        let range = self.mir_encoder.encode_operand_expr(&args[1])
+            .with_span(span)?;
+        let range_ty = self.mir_encoder.get_operand_ty(&args[1]);
 
-        let (encoded_lhs, encode_stmts, ty, _) = self.encode_place(
+        let (encoded_lhs, encode_stmts, lhs_ty, _) = self.encode_place(
             &destination.as_ref().unwrap().0,
-            ArrayAccessKind::Mutable(None, location),
-        )
-            .with_span(span)?;
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
         stmts.extend(encode_stmts);
+        let slice_ty = if let ty::TyKind::Ref(_, slice_ty, _) = lhs_ty.kind() {
+            *slice_ty
+        } else {
+            unreachable!()
+        };
+        let slice_types = self.encoder.encode_slice_types(slice_ty).with_span(span)?;
+        let elem_snap_ty = self.encoder.encode_snapshot_type(slice_types.elem_ty_rs).with_span(span)?;
 
-        stmts.extend(
-            self.encode_copy_value_assign(
-                encoded_lhs,
-                rhs,
-                ty,
-                location,
-            )?
+        // The length of `base`, the bounds of the range, and the element of
+        // `base` at `start + i`
+        let base_len = match base_ty.kind() {
+            ty::TyKind::Array(..) => {
+                let array_types = self.encoder.encode_array_types(base_ty).with_span(span)?;
+                vir::Expr::from(array_types.array_len)
+            }
+            ty::TyKind::Slice(..) => {
+                let base_types = self.encoder.encode_slice_types(base_ty).with_span(span)?;
+                base_types.encode_slice_len_call(self.encoder, base.clone())
+            }
+            ref x => unreachable!("{:?}", x),
+        };
+        let (start, end) = self.mir_encoder.encode_slicing_bounds(range, range_ty, base_len.clone())
+            .with_span(span)?;
+        let i_var: vir::Expr = vir_local!{ i: Int }.into();
+        let base_idx = vir!{ [i_var] + [start] };
+        let base_lookup = match base_ty.kind() {
+            ty::TyKind::Array(..) => {
+                let array_types = self.encoder.encode_array_types(base_ty).with_span(span)?;
+                array_types.encode_lookup_pure_call(self.encoder, base, base_idx, elem_snap_ty.clone())
+            }
+            ty::TyKind::Slice(..) => {
+                let base_types = self.encoder.encode_slice_types(base_ty).with_span(span)?;
+                base_types.encode_lookup_pure_call(self.encoder, base, base_idx, elem_snap_ty.clone())
+            }
+            ref x => unreachable!("{:?}", x),
+        };
+
+        if self.check_panics {
+            let pos = self.encoder.error_manager().register(
+                span,
+                ErrorCtxt::BoundsCheckAssert,
+                self.proc_def_id,
+            );
+            let start_le_end = vir!{ [start] <= [end] };
+            let end_le_len = vir!{ [end] <= [base_len] };
+            stmts.push(vir::Stmt::Assert(vir!{ [start_le_end] && [end_le_len] }, pos));
+        }
+
+        stmts.extend(self.encode_havoc(&encoded_lhs));
+        let val_ref_field = self.encoder.encode_value_field(lhs_ty).with_span(span)?;
+        let slice_expr = encoded_lhs.field(val_ref_field);
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::acc_permission(slice_expr.clone(), vir::PermAmount::Write)
+        ));
+        stmts.push(vir::Stmt::Inhale(
+            vir::Expr::predicate_access_predicate(
+                slice_types.slice_pred.clone(),
+                slice_expr.clone(),
+                vir::PermAmount::Read,
+            )
+        ));
+
+        let slice_len = slice_types.encode_slice_len_call(self.encoder, slice_expr.clone());
+        let len_is_range_len = vir!{ [slice_len] == ([end] - [start]) };
+        stmts.push(vir!{ inhale [len_is_range_len] });
+
+        let lookup_slice_i = slice_types.encode_lookup_pure_call(
+            self.encoder,
+            slice_expr,
+            i_var.clone(),
+            elem_snap_ty,
         );
+        let i_in_bounds = vir!{ ([vir::Expr::from(0)] <= [i_var]) && ([i_var] < [slice_len]) };
+        let lookup_same_as_base = vir!{ [lookup_slice_i] == [base_lookup] };
+        let forall_body = vir!{ [i_in_bounds] ==> [lookup_same_as_base] };
+        let all_from_base = vir!{ forall i: Int :: { [lookup_slice_i] } [ forall_body ] };
+        stmts.push(vir!{ inhale [all_from_base] });
 
-        self.encode_transfer_args_permissions(location, args,  &mut stmts, label.clone(), false)?;
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label.clone(), false)?;
 
         // Store a label for permissions got back from the call
         debug!(
-            "Pure function call location {:?} has label {}",
+            "Slicing call location {:?} has label {}",
             location, label
-            );
+        );
         self.label_after_location.insert(location, label);
 
         Ok(stmts)
@@ -2483,6 +4870,419 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// Encode a call of `Try::branch` on a `Result`, made by the `?` operator.
+    /// The call returns `ControlFlow::Continue` with the `Ok` value, or
+    /// `ControlFlow::Break` with the `Err` value wrapped in a
+    /// `Result<Infallible, E>`.
+    fn encode_try_branch_call(
+        &mut self,
+        called_def_id: ProcedureDefId,
+        self_ty: Option<ty::Ty<'tcx>>,
+        location: mir::Location,
+        call_site_span: Span,
+        args: &[mir::Operand<'tcx>],
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let (result_place, target_place) = match (args[0].place(), destination) {
+            (Some(place), Some((target, _))) => (place, *target),
+            _ => {
+                return self.encode_impure_function_call(
+                    location, call_site_span, args, destination, called_def_id, self_ty,
+                );
+            }
+        };
+        let result_ty = self.mir_encoder.get_operand_ty(&args[0]);
+        let target_ty = target_place.ty(self.mir, self.encoder.env().tcx()).ty;
+        let (ok_place, ok_ty) = self.enum_variant_field_place(result_place, result_ty, "Ok");
+        let (err_place, err_ty) = self.enum_variant_field_place(result_place, result_ty, "Err");
+        if !self.encoder.supports_snapshot_equality(ok_ty).with_span(call_site_span)?
+            || !self.encoder.supports_snapshot_equality(err_ty).with_span(call_site_span)?
+        {
+            return self.encode_impure_function_call(
+                location, call_site_span, args, destination, called_def_id, self_ty,
+            );
+        }
+        let (continue_place, _) = self.enum_variant_field_place(target_place, target_ty, "Continue");
+        let (break_place, residual_ty) = self.enum_variant_field_place(target_place, target_ty, "Break");
+        let (residual_err_place, _) = self.enum_variant_field_place(break_place, residual_ty, "Err");
+
+        let mut stmts = vec![];
+        let is_ok = self.encode_variant_check(result_place, result_ty, "Ok", &mut stmts)
+            .with_span(call_site_span)?;
+        let continues = self.encode_variant_check(target_place, target_ty, "Continue", &mut stmts)
+            .with_span(call_site_span)?;
+        let breaks = self.encode_variant_check(target_place, target_ty, "Break", &mut stmts)
+            .with_span(call_site_span)?;
+        let residual_is_err = self.encode_variant_check(break_place, residual_ty, "Err", &mut stmts)
+            .with_span(call_site_span)?;
+        let same_ok = self.encode_snapshot_equality(continue_place, ok_place, &mut stmts)
+            .with_span(call_site_span)?;
+        let same_err = self.encode_snapshot_equality(residual_err_place, err_place, &mut stmts)
+            .with_span(call_site_span)?;
+
+        // The target is built from the argument, which is still available
+        // because it is only moved by the call.
+        let call_result = vir::Expr::and(
+            vir::Expr::implies(is_ok.clone(), vir::Expr::and(continues, same_ok)),
+            vir::Expr::implies(
+                vir::Expr::not(is_ok),
+                vir::Expr::and(breaks, vir::Expr::and(residual_is_err, same_err)),
+            ),
+        );
+        let (call_stmts, _) = self.encode_pure_function_call_site(location, destination, call_result);
+        stmts.extend(call_stmts);
+        Ok(stmts)
+    }
+
+    /// Encode a call of `FromResidual::from_residual` that builds the `Err`
+    /// returned by the `?` operator from the residual `Result<Infallible, E>`
+    /// of `Try::branch`.
+    ///
+    /// When the error types are equal the conversion `From::from` is the
+    /// identity, so the error is returned unchanged. Otherwise the error is
+    /// the result of the `From::from` of the implementation that converts
+    /// between them, whose postcondition is then known, if the method is
+    /// pure. For an impure conversion only the variant of the result is known.
+    fn encode_from_residual_call(
+        &mut self,
+        called_def_id: ProcedureDefId,
+        self_ty: Option<ty::Ty<'tcx>>,
+        location: mir::Location,
+        call_site_span: Span,
+        args: &[mir::Operand<'tcx>],
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let (residual_place, target_place) = match (args[0].place(), destination) {
+            (Some(place), Some((target, _))) => (place, *target),
+            _ => {
+                return self.encode_impure_function_call(
+                    location, call_site_span, args, destination, called_def_id, self_ty,
+                );
+            }
+        };
+        let residual_ty = self.mir_encoder.get_operand_ty(&args[0]);
+        let target_ty = target_place.ty(self.mir, self.encoder.env().tcx()).ty;
+        let (residual_err_place, residual_err_ty) =
+            self.enum_variant_field_place(residual_place, residual_ty, "Err");
+        let (target_err_place, target_err_ty) =
+            self.enum_variant_field_place(target_place, target_ty, "Err");
+
+        let mut stmts = vec![];
+        let mut call_result = self.encode_variant_check(target_place, target_ty, "Err", &mut stmts)
+            .with_span(call_site_span)?;
+        let tcx = self.encoder.env().tcx();
+        if tcx.erase_regions_ty(residual_err_ty) == tcx.erase_regions_ty(target_err_ty)
+            && self.encoder.supports_snapshot_equality(target_err_ty).with_span(call_site_span)?
+        {
+            let same_err = self.encode_snapshot_equality(target_err_place, residual_err_place, &mut stmts)
+                .with_span(call_site_span)?;
+            call_result = vir::Expr::and(call_result, same_err);
+        } else if let Some((from_def_id, from_substs)) =
+            self.resolve_pure_from_call(target_err_ty, residual_err_ty)
+        {
+            let converted_err = self.encode_pure_from_call(
+                from_def_id,
+                from_substs,
+                target_err_place,
+                target_err_ty,
+                residual_err_place,
+                call_site_span,
+                &mut stmts,
+            )?;
+            call_result = vir::Expr::and(call_result, converted_err);
+        }
+        let (call_stmts, _) = self.encode_pure_function_call_site(location, destination, call_result);
+        stmts.extend(call_stmts);
+        Ok(stmts)
+    }
+
+    /// The `From::from` method, with its substitutions, of the implementation
+    /// of `From<source_ty>` for `target_ty`, if the method is pure.
+    fn resolve_pure_from_call(
+        &self,
+        target_ty: ty::Ty<'tcx>,
+        source_ty: ty::Ty<'tcx>,
+    ) -> Option<(ProcedureDefId, ty::subst::SubstsRef<'tcx>)> {
+        let tcx = self.encoder.env().tcx();
+        let from_trait = tcx.get_diagnostic_item(rustc_span::sym::From)?;
+        let from_method = self.encoder.env()
+            .get_assoc_item(from_trait, rustc_span::Symbol::intern("from"))?;
+        let substs = tcx.mk_substs_trait(target_ty, &[source_ty.into()]);
+        let param_env = tcx.param_env(self.proc_def_id);
+        let instance = ty::Instance::resolve(tcx, param_env, from_method.def_id, substs).ok()??;
+        let (impl_def_id, impl_substs) = self.encoder.resolve_extern_spec_call(
+            self.proc_def_id,
+            from_method.def_id,
+            substs,
+        );
+        let (impl_def_id, impl_substs) = if impl_def_id == from_method.def_id {
+            (instance.def_id(), instance.substs)
+        } else {
+            (impl_def_id, impl_substs)
+        };
+        if self.encoder.is_pure(impl_def_id) {
+            trace!("resolve_pure_from_call {:?} = {:?}", from_method.def_id, impl_def_id);
+            Some((impl_def_id, impl_substs))
+        } else {
+            None
+        }
+    }
+
+    /// Encode that `target` is the result of the pure `From::from` method
+    /// `from_def_id` applied to `source`.
+    #[allow(clippy::too_many_arguments)]
+    fn encode_pure_from_call(
+        &mut self,
+        from_def_id: ProcedureDefId,
+        from_substs: ty::subst::SubstsRef<'tcx>,
+        target: mir::Place<'tcx>,
+        target_ty: ty::Ty<'tcx>,
+        source: mir::Place<'tcx>,
+        call_site_span: Span,
+        stmts: &mut Vec<vir::Stmt>,
+    ) -> SpannedEncodingResult<vir::Expr> {
+        let tcx = self.encoder.env().tcx();
+        // FIXME: this is a hack to support generics. See issue #187.
+        let mut tymap = HashMap::new();
+        for (kind1, kind2) in ty::List::identity_for_item(tcx, from_def_id).iter().zip(from_substs.iter()) {
+            if let (
+                ty::subst::GenericArgKind::Type(ty1),
+                ty::subst::GenericArgKind::Type(ty2),
+            ) = (kind1.unpack(), kind2.unpack()) {
+                tymap.insert(ty1, ty2);
+            }
+        }
+        let _cleanup_token = self.encoder.push_temp_tymap(tymap);
+
+        let (function_name, return_type) = self.encoder
+            .encode_pure_function_use(from_def_id, self.proc_def_id)
+            .with_span(call_site_span)?;
+        let source = mir::Operand::Move(source);
+        let arg_expr = self.mir_encoder.encode_operand_expr(&source)
+            .with_span(call_site_span)?;
+        let arg_type = self.mir_encoder.encode_operand_expr_type(&source)
+            .with_span(call_site_span)?;
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(call_site_span, ErrorCtxt::PureFunctionCall, self.proc_def_id);
+        let converted = vir::Expr::func_app(
+            function_name,
+            vec![arg_expr],
+            vec![vir::LocalVar::new("x0", arg_type)],
+            return_type.clone(),
+            pos,
+        );
+
+        let (encoded_target, pre_stmts, _, _) = self.encode_place(&target, ArrayAccessKind::Shared)
+            .with_span(call_site_span)?;
+        stmts.extend(pre_stmts);
+        let target_value = if return_type.is_domain() {
+            vir::Expr::snap_app(encoded_target)
+        } else {
+            self.encoder.encode_value_expr(encoded_target, target_ty)
+                .with_span(call_site_span)?
+        };
+        Ok(vir::Expr::eq_cmp(target_value, converted))
+    }
+
+    /// The place of the only field of a variant of an enum, e.g. `(place as
+    /// Ok).0`, with the type of the field.
+    fn enum_variant_field_place(
+        &self,
+        place: mir::Place<'tcx>,
+        enum_ty: ty::Ty<'tcx>,
+        variant_name: &str,
+    ) -> (mir::Place<'tcx>, ty::Ty<'tcx>) {
+        let tcx = self.encoder.env().tcx();
+        match enum_ty.kind() {
+            ty::TyKind::Adt(adt_def, substs) => {
+                let (variant_index, variant_def) = adt_def.variants
+                    .iter_enumerated()
+                    .find(|(_, variant_def)| &*variant_def.ident.as_str() == variant_name)
+                    .unwrap();
+                let field_ty = variant_def.fields[0].ty(tcx, substs);
+                let variant_place = tcx.mk_place_downcast(place, adt_def, variant_index);
+                let field_place = tcx.mk_place_field(variant_place, mir::Field::new(0), field_ty);
+                (field_place, field_ty)
+            }
+            _ => unreachable!("{:?} is not an enum", enum_ty),
+        }
+    }
+
+    /// Encode the check that an enum place has the given variant.
+    fn encode_variant_check(
+        &mut self,
+        place: mir::Place<'tcx>,
+        enum_ty: ty::Ty<'tcx>,
+        variant_name: &str,
+        stmts: &mut Vec<vir::Stmt>,
+    ) -> EncodingResult<vir::Expr> {
+        let tcx = self.encoder.env().tcx();
+        let adt_def = match enum_ty.kind() {
+            ty::TyKind::Adt(adt_def, _) => adt_def,
+            _ => unreachable!("{:?} is not an enum", enum_ty),
+        };
+        let variant_index = adt_def.variants
+            .iter_enumerated()
+            .position(|(_, variant_def)| &*variant_def.ident.as_str() == variant_name)
+            .unwrap();
+        let discriminant_value = compute_discriminant_values(adt_def, tcx)[variant_index];
+        let (encoded_place, pre_stmts, _, _) = self.encode_place(&place, ArrayAccessKind::Shared)?;
+        stmts.extend(pre_stmts);
+        Ok(vir::Expr::eq_cmp(
+            self.encoder.encode_discriminant_func_app(encoded_place, adt_def),
+            discriminant_value.into(),
+        ))
+    }
+
+    /// Encode the equality of the snapshots of two places.
+    fn encode_snapshot_equality(
+        &mut self,
+        left: mir::Place<'tcx>,
+        right: mir::Place<'tcx>,
+        stmts: &mut Vec<vir::Stmt>,
+    ) -> EncodingResult<vir::Expr> {
+        let (encoded_left, left_stmts, _, _) = self.encode_place(&left, ArrayAccessKind::Shared)?;
+        let (encoded_right, right_stmts, _, _) = self.encode_place(&right, ArrayAccessKind::Shared)?;
+        stmts.extend(left_stmts);
+        stmts.extend(right_stmts);
+        Ok(vir::Expr::eq_cmp(
+            vir::Expr::snap_app(encoded_left),
+            vir::Expr::snap_app(encoded_right),
+        ))
+    }
+
+    /// Encode a call of `Default::default`. For a derived implementation whose
+    /// fields have known default values the result is modeled precisely,
+    /// otherwise the call is encoded as any other call.
+    fn encode_default_call(
+        &mut self,
+        called_def_id: ProcedureDefId,
+        self_ty: ty::Ty<'tcx>,
+        location: mir::Location,
+        call_site_span: Span,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let default_snapshot = self.encoder
+            .encode_derived_default_snapshot(called_def_id, self_ty)
+            .with_span(call_site_span)?;
+        let default_snapshot = if let Some(default_snapshot) = default_snapshot {
+            default_snapshot
+        } else {
+            return self.encode_impure_function_call(
+                location,
+                call_site_span,
+                &[],
+                destination,
+                called_def_id,
+                Some(self_ty),
+            );
+        };
+
+        let (target_value, mut stmts) = self.encode_pure_function_call_lhs_value(destination)
+            .with_span(call_site_span)?;
+        let inhaled_expr = vir::Expr::eq_cmp(vir::Expr::snap_app(target_value), default_snapshot);
+
+        let (call_stmts, label) = self.encode_pure_function_call_site(
+            location,
+            destination,
+            inhaled_expr,
+        );
+        stmts.extend(call_stmts);
+
+        self.encode_transfer_args_permissions(location, &[], &mut stmts, label, false)?;
+
+        Ok(stmts)
+    }
+
+    /// Encode a call of a derived `Clone::clone`, whose result has the same
+    /// snapshot as the cloned value. If the type does not support snapshot
+    /// equality, the call is encoded as any other call.
+    fn encode_derived_clone_call(
+        &mut self,
+        called_def_id: ProcedureDefId,
+        self_ty: ty::Ty<'tcx>,
+        location: mir::Location,
+        call_site_span: Span,
+        args: &[mir::Operand<'tcx>],
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        if !self.encoder.supports_snapshot_equality(self_ty).with_span(call_site_span)? {
+            return self.encode_impure_function_call(
+                location,
+                call_site_span,
+                args,
+                destination,
+                called_def_id,
+                Some(self_ty),
+            );
+        }
+        let encoded_arg = self.mir_encoder.encode_operand_expr(&args[0])
+            .with_span(call_site_span)?;
+
+        let (target_value, mut stmts) = self.encode_pure_function_call_lhs_value(destination)
+            .with_span(call_site_span)?;
+        let inhaled_expr = vir::Expr::eq_cmp(
+            vir::Expr::snap_app(target_value),
+            vir::Expr::snap_app(encoded_arg),
+        );
+
+        let (call_stmts, label) = self.encode_pure_function_call_site(
+            location,
+            destination,
+            inhaled_expr,
+        );
+        stmts.extend(call_stmts);
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label, false)?;
+
+        Ok(stmts)
+    }
+
+    /// Encode a call of one of the `wrapping_*`, `checked_*` or `overflowing_*`
+    /// arithmetic methods of integers, whose result is modeled precisely instead
+    /// of being checked for overflows.
+    fn encode_integer_arithmetic_call(
+        &mut self,
+        kind: IntegerArithmeticKind,
+        op: mir::BinOp,
+        location: mir::Location,
+        call_site_span: Span,
+        args: &[mir::Operand<'tcx>],
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let operand_ty = self.mir_encoder.get_operand_ty(&args[0]);
+        let left = self.mir_encoder.encode_operand_expr(&args[0])
+            .with_span(call_site_span)?;
+        let right = self.mir_encoder.encode_operand_expr(&args[1])
+            .with_span(call_site_span)?;
+        let tcx = self.encoder.env().tcx();
+        let result_ty = destination.as_ref().unwrap().0.ty(self.mir, tcx).ty;
+        let result = self.mir_encoder
+            .encode_integer_arithmetic(kind, op, left, right, operand_ty, result_ty)
+            .with_span(call_site_span)?;
+
+        let (target_value, mut stmts) = self.encode_pure_function_call_lhs_value(destination)
+            .with_span(call_site_span)?;
+        let inhaled_expr = if kind == IntegerArithmeticKind::Wrapping {
+            vir::Expr::eq_cmp(target_value, result)
+        } else {
+            vir::Expr::eq_cmp(vir::Expr::snap_app(target_value), result)
+        };
+
+        let (call_stmts, label) = self.encode_pure_function_call_site(
+            location,
+            destination,
+            inhaled_expr,
+        );
+        stmts.extend(call_stmts);
+
+        self.encode_transfer_args_permissions(location, args, &mut stmts, label, false)?;
+
+        Ok(stmts)
+    }
+
     /// Encode an edge of the MIR graph
     fn encode_edge_block(
         &mut self,
@@ -2499,25 +5299,160 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             statement_index: 0,
         };
         let stmts = self.encode_expiring_borrows_between(source_loc, destination_loc)?;
+        let opt_assumption = self.switch_discriminant_assumptions.remove(&(source, destination));
+        let refcell_updates = self.refcell_updates_on_edge.remove(&(source, destination))
+            .unwrap_or_default();
+
+        if force_block || !stmts.is_empty() || opt_assumption.is_some() || !refcell_updates.is_empty() {
+            let edge_label = self.cfg_method.get_fresh_label_name();
+            let edge_block = self.cfg_method.add_block(
+                &edge_label,
+                vec![
+                    vir::Stmt::comment(format!("========== {} ==========", edge_label)),
+                    vir::Stmt::comment(format!("MIR edge {:?} --> {:?}", source, destination)),
+                ],
+            );
+            if let Some(assumption) = opt_assumption {
+                self.cfg_method.add_stmt(edge_block, vir::Stmt::Inhale(assumption));
+            }
+            if !stmts.is_empty() {
+                self.cfg_method
+                    .add_stmt(edge_block, vir::Stmt::comment("Expire borrows"));
+                self.cfg_method.add_stmts(edge_block, stmts);
+            }
+            if !refcell_updates.is_empty() {
+                self.cfg_method
+                    .add_stmt(edge_block, vir::Stmt::comment("Update RefCells"));
+                self.cfg_method.add_stmts(edge_block, refcell_updates);
+            }
+            Ok(Some(edge_block))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// If the switch at `location` is on the discriminant of an enum, record
+    /// for each outgoing edge the variant of the enum taken by the edge. The
+    /// assumptions are inhaled by `encode_edge_block`, so that the branches
+    /// of a `match` know the variant of the matched place also when the
+    /// place is nested or behind a reference.
+    fn encode_switch_discriminant_assumptions(
+        &mut self,
+        discr: &mir::Operand<'tcx>,
+        targets: &mir::SwitchTargets,
+        switch_ty: ty::Ty<'tcx>,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<()> {
+        let enum_place = match self.find_switched_enum_place(discr, location) {
+            Some(place) => place,
+            None => return Ok(()),
+        };
+        let span = self.mir_encoder.get_span_of_location(location);
+        let (encoded_place, pre_stmts, enum_ty, _) = self.encode_place(
+            &enum_place,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+        if !pre_stmts.is_empty() {
+            return Ok(());
+        }
+        let adt_def = match enum_ty.kind() {
+            ty::TyKind::Adt(adt_def, _) if adt_def.is_enum() && adt_def.variants.len() > 1 => {
+                adt_def
+            }
+            _ => return Ok(()),
+        };
+        let discriminant = self.encoder.encode_discriminant_func_app(encoded_place, adt_def);
+
+        let mut target_assumptions: Vec<(BasicBlockIndex, vir::Expr)> = vec![];
+        let mut default_assumptions = vec![];
+        for (value, target) in targets.iter() {
+            let encoded_value = self.encoder.encode_int_cast(value, switch_ty);
+            let is_variant = vir::Expr::eq_cmp(discriminant.clone(), encoded_value.clone());
+            default_assumptions.push(vir::Expr::ne_cmp(discriminant.clone(), encoded_value));
+            match target_assumptions.iter_mut().find(|(bbi, _)| *bbi == target) {
+                Some((_, assumption)) => {
+                    *assumption = vir::Expr::or(assumption.clone(), is_variant);
+                }
+                None => target_assumptions.push((target, is_variant)),
+            }
+        }
+        let default_target = targets.otherwise();
+        if target_assumptions.iter().all(|(bbi, _)| *bbi != default_target) {
+            target_assumptions.push((default_target, default_assumptions.into_iter().conjoin()));
+        }
+        for (target, assumption) in target_assumptions {
+            self.switch_discriminant_assumptions.insert((location.block, target), assumption);
+        }
+        Ok(())
+    }
+
+    /// Find the enum place whose discriminant is read into `discr` by a
+    /// statement at the end of the block of `location`.
+    fn find_switched_enum_place(
+        &self,
+        discr: &mir::Operand<'tcx>,
+        location: mir::Location,
+    ) -> Option<mir::Place<'tcx>> {
+        let discr_local = match discr {
+            mir::Operand::Copy(place) | mir::Operand::Move(place) => place.as_local()?,
+            mir::Operand::Constant(_) => return None,
+        };
+        let statements = &self.mir[location.block].statements[..location.statement_index];
+        for statement in statements.iter().rev() {
+            match statement.kind {
+                mir::StatementKind::Assign(box (ref lhs, mir::Rvalue::Discriminant(ref src)))
+                    if lhs.as_local() == Some(discr_local) =>
+                {
+                    return Some(*src);
+                }
+                mir::StatementKind::StorageLive(_)
+                | mir::StatementKind::StorageDead(_)
+                | mir::StatementKind::FakeRead(..)
+                | mir::StatementKind::Nop => {}
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// The contract of a closure is only used at direct calls of the closure.
+    /// Report an error if a value of type `ty` is (a reference to) a closure
+    /// with a contract, which would be called through a generic bound or a
+    /// trait object.
+    fn check_closure_contract_is_used(
+        &self,
+        ty: ty::Ty<'tcx>,
+        span: Span,
+    ) -> SpannedEncodingResult<()> {
+        if let ty::TyKind::Closure(cl_def_id, _) = ty.peel_refs().kind() {
+            if let Some(spec) = self.encoder.get_procedure_specs(*cl_def_id) {
+                if !spec.pres.is_empty() || !spec.posts.is_empty() {
+                    return Err(SpannedEncodingError::unsupported(
+                        "closure contracts through trait objects are not yet supported",
+                        span,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
 
-        if force_block || !stmts.is_empty() {
-            let edge_label = self.cfg_method.get_fresh_label_name();
-            let edge_block = self.cfg_method.add_block(
-                &edge_label,
-                vec![
-                    vir::Stmt::comment(format!("========== {} ==========", edge_label)),
-                    vir::Stmt::comment(format!("MIR edge {:?} --> {:?}", source, destination)),
-                ],
-            );
-            if !stmts.is_empty() {
-                self.cfg_method
-                    .add_stmt(edge_block, vir::Stmt::comment("Expire borrows"));
-                self.cfg_method.add_stmts(edge_block, stmts);
+    /// Whether a precondition of `def_id` constrains the contract of a
+    /// closure argument with a specification entailment (`f |= ...`).
+    fn has_spec_entailment_precondition(&self, def_id: ProcedureDefId) -> bool {
+        fn has_spec_entailment(assertion: &typed::Assertion) -> bool {
+            match *assertion.kind {
+                typed::AssertionKind::Expr(_) => false,
+                typed::AssertionKind::And(ref assertions) => assertions.iter().any(has_spec_entailment),
+                typed::AssertionKind::Implies(_, ref rhs) => has_spec_entailment(rhs),
+                typed::AssertionKind::TypeCond(_, ref body)
+                | typed::AssertionKind::ForAll(_, _, ref body)
+                | typed::AssertionKind::Exists(_, _, ref body) => has_spec_entailment(body),
+                typed::AssertionKind::SpecEntailment { .. } => true,
             }
-            Ok(Some(edge_block))
-        } else {
-            Ok(None)
         }
+        self.encoder.get_procedure_specs(def_id)
+            .map_or(false, |spec| spec.pres.iter().any(has_spec_entailment))
     }
 
     fn encode_impure_function_call(
@@ -2725,10 +5660,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             expr
         };
 
+        let refined_spec = self.encoder.get_refined_procedure_specs(called_def_id, call_site_span)?;
         let procedure_contract = {
             self.encoder.get_procedure_contract_for_call(
                 self_ty,
                 called_def_id,
+                refined_spec,
                 &arguments,
                 target_local,
             ).with_span(call_site_span)?
@@ -2744,6 +5681,34 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             stmts.extend(self.encode_havoc_and_allocation(constant_arg));
         }
 
+        // Check the termination of the call.
+        let termination_check = if self.encoder.is_pure(self.proc_def_id) {
+            // The body of a pure function is verified as a method, in which
+            // its recursive calls are calls of that method.
+            let encoded_args: Vec<vir::Expr> = arguments
+                .iter()
+                .map(|&arg| self.encode_prusti_local(arg).into())
+                .collect();
+            self.encode_pure_call_termination_check(
+                called_def_id,
+                &encoded_args,
+                false,
+                call_site_span,
+            )?
+        } else if self.encoder.is_terminating(self.proc_def_id) {
+            self.encode_call_termination_check(location, called_def_id, &arguments, call_site_span)?
+        } else {
+            None
+        };
+        if let Some(check) = termination_check {
+            let pos = self.encoder.error_manager().register(
+                call_site_span,
+                ErrorCtxt::AssertTerminationMeasure,
+                self.proc_def_id,
+            );
+            stmts.push(vir::Stmt::Assert(replace_fake_exprs(check), pos));
+        }
+
         // Encode precondition.
         let (
             pre_type_spec,
@@ -2894,7 +5859,26 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             arg_exprs.push(arg_expr);
         }
 
-        self.encode_specified_pure_function_call(
+        let mut stmts = vec![];
+        // A call of a pure function that might call back the pure function
+        // being verified.
+        if self.encoder.is_pure(self.proc_def_id) {
+            let termination_check = self.encode_pure_call_termination_check(
+                called_def_id,
+                &arg_exprs,
+                true,
+                call_site_span,
+            )?;
+            if let Some(check) = termination_check {
+                let pos = self.encoder.error_manager().register(
+                    call_site_span,
+                    ErrorCtxt::AssertTerminationMeasure,
+                    self.proc_def_id,
+                );
+                stmts.push(vir::Stmt::Assert(check, pos));
+            }
+        }
+        stmts.extend(self.encode_specified_pure_function_call(
             location,
             call_site_span,
             args,
@@ -2902,7 +5886,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             function_name,
             arg_exprs,
             return_type,
-        )
+        )?);
+        Ok(stmts)
     }
 
     fn encode_specified_pure_function_call(
@@ -2941,7 +5926,11 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let (target_value, mut stmts) = self.encode_pure_function_call_lhs_value(destination)
             .with_span(call_site_span)?;
 
-        let inhaled_expr = if return_type.is_domain() {
+        // A returned reference is encoded as the snapshot of its target
+        let returns_reference = destination.as_ref().map_or(false, |(dst, _)| {
+            dst.ty(self.mir, self.encoder.env().tcx()).ty.is_ref()
+        });
+        let inhaled_expr = if return_type.is_domain() || returns_reference {
             let (target_place, pre_stmts) = self.encode_pure_function_call_lhs_place(destination);
             stmts.extend(pre_stmts);
             vir::Expr::eq_cmp(
@@ -3247,6 +6236,13 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
 
         let precondition_weakening = precondition_weakening.map(|pw| {
+            // The weakening has the form `trait_pre ==> impl_pre`
+            let trait_spans = match *pw.kind {
+                typed::AssertionKind::Implies(ref trait_pre, _) => {
+                    typed::Spanned::get_spans(trait_pre, &self.mir, self.encoder.env().tcx())
+                }
+                _ => vec![],
+            };
             self.encoder.encode_assertion(
                 &pw,
                 &self.mir,
@@ -3256,7 +6252,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 false,
                 None,
                 ErrorCtxt::AssertMethodPreconditionWeakening(
-                    precondition_spans.clone()
+                    precondition_spans.clone(),
+                    MultiSpan::from_spans(trait_spans),
                 ),
                 self.proc_def_id,
             )
@@ -3270,6 +6267,224 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         ))
     }
 
+    /// Check that a call from a procedure marked with `#[terminates]`
+    /// terminates: the callee must be marked as terminating too and, if the
+    /// call belongs to a cycle of the call graph, the termination measure has
+    /// to decrease. Returns the check of the measure, if one is needed.
+    ///
+    /// A call of a trait method is checked against the method of the
+    /// implementation it resolves to, if it is known. A procedure of another
+    /// crate is only known to terminate if its specification, for example an
+    /// external one, marks it with `#[terminates]`.
+    fn encode_call_termination_check(
+        &mut self,
+        location: mir::Location,
+        called_def_id: ProcedureDefId,
+        arguments: &[Local],
+        call_site_span: Span,
+    ) -> SpannedEncodingResult<Option<vir::Expr>> {
+        let called_def_id = self.resolve_called_trait_method(location, called_def_id);
+        // Pure functions are checked on their own.
+        if self.encoder.is_pure(called_def_id) {
+            return Ok(None);
+        }
+        let tcx = self.encoder.env().tcx();
+        if !self.encoder.is_terminating(called_def_id) {
+            return Err(SpannedEncodingError::incorrect(
+                format!(
+                    "procedure marked with #[terminates] calls '{}', which is not \
+                    marked as terminating",
+                    tcx.def_path_str(called_def_id),
+                ),
+                call_site_span,
+            ));
+        }
+        // A procedure of another crate cannot call back the local ones.
+        if !called_def_id.is_local()
+            || !self.encoder.is_recursive_call(self.proc_def_id, called_def_id)
+        {
+            return Ok(None);
+        }
+        for def_id in &[self.proc_def_id, called_def_id] {
+            if self.encoder.get_decreases_measure(*def_id).is_none() {
+                return Err(SpannedEncodingError::incorrect(
+                    format!(
+                        "recursive procedure '{}' marked with #[terminates] has no \
+                        termination measure",
+                        tcx.def_path_str(*def_id),
+                    ),
+                    call_site_span,
+                ));
+            }
+        }
+        let encoded_args: Vec<vir::Expr> = arguments
+            .iter()
+            .map(|&arg| self.encode_prusti_local(arg).into())
+            .collect();
+        self.encode_measure_decrease(called_def_id, &encoded_args, false).map(Some)
+    }
+
+    /// The method of the implementation that the trait method called by the
+    /// terminator at `location` resolves to, or `called_def_id` if it is not a
+    /// trait method or the implementation is not known, e.g. because the
+    /// receiver is a type parameter.
+    fn resolve_called_trait_method(
+        &self,
+        location: mir::Location,
+        called_def_id: ProcedureDefId,
+    ) -> ProcedureDefId {
+        let tcx = self.encoder.env().tcx();
+        if tcx.trait_of_item(called_def_id).is_none() {
+            return called_def_id;
+        }
+        let substs = match self.mir[location.block].terminator().kind {
+            mir::TerminatorKind::Call { ref func, .. } => match func.ty(self.mir, tcx).kind() {
+                ty::TyKind::FnDef(def_id, substs) if *def_id == called_def_id => *substs,
+                _ => return called_def_id,
+            },
+            _ => return called_def_id,
+        };
+        let param_env = tcx.param_env(self.proc_def_id);
+        match ty::Instance::resolve(tcx, param_env, called_def_id, substs) {
+            Ok(Some(instance)) => instance.def_id(),
+            _ => called_def_id,
+        }
+    }
+
+    /// Check that a call from the pure function being verified to a pure
+    /// function that might call it back decreases the termination measure.
+    /// The check is an assertion of the method that verifies the body of the
+    /// pure function, so the Viper function that encodes it is unchanged.
+    /// Recursive calls without a measure are accepted, but reported with an
+    /// error if `require_decreases` is set, or with a warning if
+    /// `warn_missing_decreases` is set. Returns the check of the measure, if
+    /// one is needed.
+    fn encode_pure_call_termination_check(
+        &mut self,
+        called_def_id: ProcedureDefId,
+        encoded_args: &[vir::Expr],
+        targets_are_values: bool,
+        call_site_span: Span,
+    ) -> SpannedEncodingResult<Option<vir::Expr>> {
+        if !called_def_id.is_local()
+            || !self.encoder.is_pure(called_def_id)
+            || !self.encoder.is_recursive_call(self.proc_def_id, called_def_id)
+        {
+            return Ok(None);
+        }
+        for &def_id in &[self.proc_def_id, called_def_id] {
+            if self.encoder.get_decreases_measure(def_id).is_some() {
+                continue;
+            }
+            let message = format!(
+                "recursive pure function '{}' has no termination measure",
+                self.encoder.env().tcx().def_path_str(def_id),
+            );
+            if config::require_decreases() {
+                return Err(SpannedEncodingError::incorrect(message, call_site_span));
+            }
+            if config::warn_missing_decreases() {
+                let mut warning = PrustiError::incorrect(message, call_site_span.into())
+                    .set_help("add a `#[decreases(...)]` attribute to check that the function terminates");
+                warning.set_warning();
+                warning.emit(self.encoder.env());
+            }
+            return Ok(None);
+        }
+        self.encode_measure_decrease(called_def_id, encoded_args, targets_are_values).map(Some)
+    }
+
+    /// The check that the termination measure of the callee, applied to the
+    /// arguments of the call, is smaller than the non-negative measure of the
+    /// caller in its pre state.
+    fn encode_measure_decrease(
+        &mut self,
+        called_def_id: ProcedureDefId,
+        encoded_args: &[vir::Expr],
+        targets_are_values: bool,
+    ) -> SpannedEncodingResult<vir::Expr> {
+        let caller_measure: vir::Expr = self.termination_measure_var().into();
+        let callee_measure = self.encoder.get_decreases_measure(called_def_id).unwrap();
+        self.check_termination_measure_type(callee_measure)?;
+        let callee_measure = self.encoder.encode_assertion(
+            callee_measure,
+            &self.mir,
+            None,
+            encoded_args,
+            None,
+            targets_are_values,
+            None,
+            ErrorCtxt::GenericExpression,
+            self.proc_def_id,
+        )?;
+        Ok(vir::Expr::and(
+            vir::Expr::le_cmp(vir::Expr::from(0), caller_measure.clone()),
+            vir::Expr::lt_cmp(callee_measure, caller_measure),
+        ))
+    }
+
+    /// The ghost variable that stores the termination measure of the procedure.
+    fn termination_measure_var(&mut self) -> vir::LocalVar {
+        if self.termination_measure_var.is_none() {
+            let var = self.cfg_method.add_fresh_local_var(vir::Type::Int);
+            self.termination_measure_var = Some(var);
+        }
+        self.termination_measure_var.clone().unwrap()
+    }
+
+    fn check_termination_measure_type(
+        &self,
+        measure: &typed::Assertion<'tcx>,
+    ) -> SpannedEncodingResult<()> {
+        if let typed::AssertionKind::Expr(ref expr) = *measure.kind {
+            let measure_ty = self.encoder.env().mir(expr.expr).return_ty();
+            if !measure_ty.is_integral() {
+                return Err(SpannedEncodingError::incorrect(
+                    "the termination measure must be an integer",
+                    self.encoder.env().tcx().def_span(expr.expr),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Store the value of the termination measure in the pre state, if a
+    /// recursive call of the procedure needs it.
+    fn encode_termination_measure_init(
+        &mut self,
+        start_cfg_block: CfgBlockIndex,
+    ) -> SpannedEncodingResult<()> {
+        let measure_var = if let Some(ref measure_var) = self.termination_measure_var {
+            measure_var.clone()
+        } else {
+            return Ok(());
+        };
+        let measure = self.encoder.get_decreases_measure(self.proc_def_id).unwrap();
+        self.check_termination_measure_type(measure)?;
+        let encoded_args: Vec<vir::Expr> = self
+            .procedure_contract()
+            .args
+            .iter()
+            .map(|local| self.encode_prusti_local(*local).into())
+            .collect();
+        let encoded_measure = self.encoder.encode_assertion(
+            measure,
+            &self.mir,
+            None,
+            &encoded_args,
+            None,
+            false,
+            None,
+            ErrorCtxt::GenericExpression,
+            self.proc_def_id,
+        )?;
+        self.cfg_method.add_stmt(
+            start_cfg_block,
+            vir::Stmt::Assign(measure_var.into(), encoded_measure, vir::AssignKind::Ghost),
+        );
+        Ok(())
+    }
+
     /// Encode precondition inhale on the definition side.
     fn encode_preconditions(
         &mut self,
@@ -3443,6 +6658,22 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 lhs.push(assertion_lhs);
                 rhs.push(assertion_rhs);
             }
+            // The place reborrowed by the returned shared reference is unchanged
+            // when the reference expires.
+            if let Some(frame) = self.encode_returned_reborrow_equality(
+                location,
+                contract,
+                &encoded_return,
+            )? {
+                let frame = self.wrap_arguments_into_old(frame, pre_label, contract, &encoded_args)?;
+                let ty = self.locals.get_type(contract.returned_value);
+                let (encoded_deref, ..) = self
+                    .mir_encoder
+                    .encode_deref(encoded_return.clone(), ty)
+                    .with_span(self.mir.span)?;
+                let old_expr = vir::Expr::labelled_old(post_label, encoded_deref.clone());
+                rhs.push(frame.replace_place(&encoded_deref, &old_expr).remove_redundant_old());
+            }
             let lhs = lhs
                 .into_iter()
                 .conjoin();
@@ -3455,6 +6686,36 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// If the procedure returns a shared reborrow of a place of its arguments,
+    /// encode that the returned reference and that place have the same value.
+    fn encode_returned_reborrow_equality(
+        &self,
+        location: Option<mir::Location>,
+        contract: &ProcedureContract<'tcx>,
+        encoded_return: &vir::Expr,
+    ) -> SpannedEncodingResult<Option<vir::Expr>> {
+        let reborrowed_place = if let Some(place) = &contract.returned_reborrow {
+            place
+        } else {
+            return Ok(None);
+        };
+        let (encoded_place, place_ty, _) = self.encode_generic_place(
+            contract.def_id, location, reborrowed_place
+        ).with_span(self.mir.span)?;
+        if !self.encoder.supports_snapshot_equality(place_ty).with_span(self.mir.span)? {
+            return Ok(None);
+        }
+        let ty = self.locals.get_type(contract.returned_value);
+        let (encoded_deref, ..) = self
+            .mir_encoder
+            .encode_deref(encoded_return.clone(), ty)
+            .with_span(self.mir.span)?;
+        Ok(Some(vir::Expr::eq_cmp(
+            vir::Expr::snap_app(encoded_deref),
+            vir::Expr::snap_app(encoded_place),
+        )))
+    }
+
     /// Wrap function arguments used in the postcondition into ``old``:
     ///
     /// +   For references wrap the base ``_1.var_ref``.
@@ -3633,6 +6894,13 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             )?;
             func_spec.push(assertion);
         }
+        if let Some(alias) = self.encode_returned_reborrow_equality(
+            location,
+            contract,
+            &encoded_return,
+        )? {
+            func_spec.push(self.wrap_arguments_into_old(alias, pre_label, contract, &encoded_args)?);
+        }
         let postcondition_span = MultiSpan::from_spans(func_spec_spans);
         let func_spec_pos = self.encoder.error_manager()
             .register_span(postcondition_span.clone());
@@ -3882,11 +7150,53 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(stmts)
     }
 
+    /// Replace the by-value arguments that are moved out or overwritten in the
+    /// body with snapshots of them, taken in the `start_cfg_block` CFG block
+    /// after the precondition label. Otherwise, the postcondition would refer
+    /// to whatever the argument holds at the end of the method.
+    fn encode_consumed_args_snapshots(
+        &mut self,
+        start_cfg_block: CfgBlockIndex,
+        contract: &ProcedureContract<'tcx>,
+        mut spec: vir::Expr,
+    ) -> SpannedEncodingResult<vir::Expr> {
+        let consumed_args = detect_consumed_args(self.mir);
+        for (i, &arg) in contract.args.iter().enumerate() {
+            let ty = self.locals.get_type(arg);
+            if is_reference(ty) || !consumed_args.contains(&arg.into()) {
+                continue;
+            }
+            let encoded_arg: vir::Expr = self.encode_prusti_local(arg).into();
+            if !spec.find(&encoded_arg) {
+                continue;
+            }
+            let arg_span = self.mir_encoder.get_local_span(arg.into());
+            let snapshot_type = self.encoder.encode_snapshot_type(ty).with_span(arg_span)?;
+            let name = format!("_old${}$arg{}", PRECONDITION_LABEL, i);
+            if !self.cfg_method.get_all_vars().iter().any(|var| var.name == name) {
+                self.cfg_method.add_local_var(&name, snapshot_type.clone());
+                self.cfg_method.add_stmt(
+                    start_cfg_block,
+                    vir::Stmt::Assign(
+                        vir::LocalVar::new(name.clone(), snapshot_type.clone()).into(),
+                        vir::Expr::snap_app(encoded_arg.clone()),
+                        vir::AssignKind::Ghost,
+                    ),
+                );
+            }
+            let snapshot: vir::Expr = vir::LocalVar::new(name, snapshot_type).into();
+            spec = spec.replace_place(&encoded_arg, &snapshot);
+        }
+        Ok(spec)
+    }
+
     /// Encode postcondition exhale in the `return_cfg_block` CFG block.
     fn encode_postconditions(
         &mut self,
+        start_cfg_block: CfgBlockIndex,
         return_cfg_block: CfgBlockIndex,
         postcondition_strengthening: Option<typed::Assertion<'tcx>>,
+        trait_precondition: Option<typed::Assertion<'tcx>>,
     ) -> SpannedEncodingResult<()> {
         // This clone is only due to borrow checker restrictions
         let contract = self.procedure_contract().clone();
@@ -3915,6 +7225,40 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             None,
             true,
         )?;
+        let func_spec = self.encode_consumed_args_snapshots(
+            start_cfg_block,
+            &contract,
+            func_spec,
+        )?;
+        let strengthening_spec = strengthening_spec
+            .map(|spec| self.encode_consumed_args_snapshots(start_cfg_block, &contract, spec))
+            .transpose()?;
+        let strengthening_spec = match (strengthening_spec, trait_precondition) {
+            (Some(spec), Some(trait_pre)) => {
+                let encoded_args: Vec<vir::Expr> = contract
+                    .args
+                    .iter()
+                    .map(|local| self.encode_prusti_local(*local).into())
+                    .collect();
+                let encoded_trait_pre = self.encoder.encode_assertion(
+                    &trait_pre,
+                    &self.mir,
+                    None,
+                    &encoded_args,
+                    None,
+                    false,
+                    None,
+                    ErrorCtxt::GenericExpression,
+                    self.proc_def_id,
+                )?;
+                let pos = spec.pos();
+                Some(vir::Expr::implies(
+                    vir::Expr::labelled_old(PRECONDITION_LABEL, encoded_trait_pre),
+                    spec,
+                ).set_pos(pos))
+            }
+            (spec, _) => spec,
+        };
 
         let type_inv_pos = self.encoder.error_manager().register(
             self.mir.span,
@@ -4061,10 +7405,49 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             .error_manager()
             .register(self.mir.span, ErrorCtxt::AssertMethodPostcondition, self.proc_def_id);
         let patched_func_spec = self.replace_old_places_with_ghost_vars(None, func_spec);
-        self.cfg_method.add_stmt(
-            return_cfg_block,
-            vir::Stmt::Assert(patched_func_spec, func_pos),
+        let is_trivial_func_spec = matches!(
+            patched_func_spec,
+            vir::Expr::Const(vir::Const::Bool(true), _)
         );
+        let opt_return_site_var = self.return_site_var.clone()
+            .filter(|_| !is_trivial_func_spec);
+        if let Some(return_site_var) = opt_return_site_var {
+            // Check the postcondition separately for each `return` expression,
+            // so that a failure reports the responsible one. The last check
+            // covers any other value of the ghost variable.
+            let mut other_sites = vec![];
+            for (index, &(_, return_span)) in self.return_sites.clone().iter().enumerate() {
+                let is_site = vir::Expr::eq_cmp(
+                    vir::Expr::local(return_site_var.clone()),
+                    (index + 1).into(),
+                );
+                other_sites.push(vir::Expr::not(is_site.clone()));
+                let pos = self.encoder.error_manager().register(
+                    self.mir.span,
+                    ErrorCtxt::AssertMethodPostconditionOnReturn(return_span.into()),
+                    self.proc_def_id,
+                );
+                self.cfg_method.add_stmt(
+                    return_cfg_block,
+                    vir::Stmt::Assert(
+                        vir::Expr::implies(is_site, patched_func_spec.clone()),
+                        pos,
+                    ),
+                );
+            }
+            self.cfg_method.add_stmt(
+                return_cfg_block,
+                vir::Stmt::Assert(
+                    vir::Expr::implies(other_sites.into_iter().conjoin(), patched_func_spec),
+                    func_pos,
+                ),
+            );
+        } else {
+            self.cfg_method.add_stmt(
+                return_cfg_block,
+                vir::Stmt::Assert(patched_func_spec, func_pos),
+            );
+        }
 
         // Assert type invariants
         self.cfg_method.add_stmt(
@@ -4356,7 +7739,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                                                     &e,
                                                 ));
                                             }
-                                            ExprOrArrayBase::ArrayBase(b) => {
+                                            ExprOrArrayBase::ArrayBase(b)
+                                            | ExprOrArrayBase::SliceBase(b) => {
                                                 let eq = self.construct_value_preserving_array_equality(loop_head, b);
                                                 // arrays can be mentioned multiple times, so we
                                                 // need to check here
@@ -4364,7 +7748,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                                                     equalities.push(eq);
                                                 }
                                             }
-                                            ExprOrArrayBase::SliceBase(_) => unimplemented!("slices in loops not yet implemented"),
                                         }
                                     }
                                 }
@@ -4427,12 +7810,77 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok((permissions, equalities, invs_spec))
     }
 
+    /// Encode the `prusti_assert!(..)` and `prusti_assume!(..)` that are
+    /// type-checked by the spec blocks starting at `spec_block`, as a Viper
+    /// `assert` or `inhale` respectively.
+    fn encode_body_assertions(
+        &mut self,
+        spec_block: BasicBlockIndex,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let tcx = self.encoder.env().tcx();
+        let mut spec_closures = vec![];
+        let mut to_visit = vec![spec_block];
+        let mut visited = HashSet::new();
+        while let Some(bbi) = to_visit.pop() {
+            if !self.procedure.is_body_assertion_block(bbi) || !visited.insert(bbi) {
+                continue;
+            }
+            for stmt in &self.mir.basic_blocks()[bbi].statements {
+                if let mir::StatementKind::Assign(box (
+                    _,
+                    mir::Rvalue::Aggregate(box mir::AggregateKind::Closure(cl_def_id, _), _),
+                )) = stmt.kind {
+                    if let Some(spec) = self.encoder.get_body_assertion_specs(cl_def_id) {
+                        spec_closures.push((cl_def_id, spec));
+                    }
+                }
+            }
+            to_visit.extend(self.mir.basic_blocks()[bbi].terminator().successors().cloned());
+        }
+        spec_closures.sort_by_key(|(cl_def_id, _)| tcx.def_span(*cl_def_id).lo());
+
+        let encoded_args: Vec<vir::Expr> = self
+            .mir
+            .args_iter()
+            .map(|local| self.mir_encoder.encode_local(local).map(|l| l.into()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut stmts = vec![];
+        for (cl_def_id, spec) in spec_closures {
+            let encoded_assertion = self.encoder.encode_assertion(
+                &spec.assertion,
+                &self.mir,
+                Some(PRECONDITION_LABEL),
+                &encoded_args,
+                None,
+                false,
+                Some(spec_block),
+                ErrorCtxt::AssertPrustiAssertion,
+                self.proc_def_id,
+            )?;
+            if spec.is_assumption {
+                stmts.push(vir::Stmt::comment("prusti_assume!(..)"));
+                stmts.push(vir::Stmt::Inhale(encoded_assertion));
+                self.encoder.register_assumption(tcx.def_span(cl_def_id));
+            } else {
+                let spec_spans = typed::Spanned::get_spans(&spec.assertion, &self.mir, tcx);
+                let pos = self.encoder.error_manager().register(
+                    spec_spans,
+                    ErrorCtxt::AssertPrustiAssertion,
+                    self.proc_def_id,
+                );
+                stmts.push(vir::Stmt::comment("prusti_assert!(..)"));
+                stmts.push(vir::Stmt::Assert(encoded_assertion, pos));
+            }
+        }
+        Ok(stmts)
+    }
+
     /// Get the basic blocks that encode the specification of a loop invariant
     fn get_loop_spec_blocks(&self, loop_head: BasicBlockIndex) -> Vec<BasicBlockIndex> {
         let mut res = vec![];
         for bbi in self.procedure.get_reachable_cfg_blocks() {
             if Some(loop_head) == self.loop_encoder.get_loop_head(bbi)
-                && self.procedure.is_spec_block(bbi)
+                && self.procedure.is_loop_spec_block(bbi)
             {
                 res.push(bbi)
             } else {
@@ -4442,17 +7890,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     self.loop_encoder.get_loop_head(bbi),
                     self.procedure.is_spec_block(bbi)
                 );
-            }
-        }
-        res
-    }
-
-    /// Encode the functional specification of a loop
-    fn encode_loop_invariant_specs(
-        &self,
-        loop_head: BasicBlockIndex,
-        loop_inv_block: BasicBlockIndex,
-    ) -> SpannedEncodingResult<(Vec<vir::Expr>, MultiSpan)> {
+            }
+        }
+        res
+    }
+
+    /// The closures that `body_invariant!(..)` and `body_variant!(..)` of a
+    /// loop are desugared to, in the order in which they are written.
+    fn get_loop_spec_closures(&self, loop_head: BasicBlockIndex) -> Vec<rustc_hir::def_id::DefId> {
         let spec_blocks = self.get_loop_spec_blocks(loop_head);
         trace!(
             "loop head {:?} has spec blocks {:?}",
@@ -4462,17 +7907,37 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
         // `body_invariant!(..)` is desugared to a closure with special attributes,
         // which we can detect and use to retrieve the specification.
-        let mut specs = vec![];
+        let mut spec_closures = vec![];
         for bbi in spec_blocks {
             for stmt in &self.mir.basic_blocks()[bbi].statements {
                 if let mir::StatementKind::Assign(box (
                     _,
                     mir::Rvalue::Aggregate(box mir::AggregateKind::Closure(cl_def_id, _), _),
                 )) = stmt.kind {
-                    specs.extend(self.encoder.get_loop_specs(cl_def_id).unwrap().invariant);
+                    if !spec_closures.contains(&cl_def_id) {
+                        spec_closures.push(cl_def_id);
+                    }
                 }
             }
         }
+        // The spec blocks are not necessarily visited in source order, but later
+        // invariants may depend on earlier ones (e.g. for well-definedness or
+        // triggering), so we conjoin them in the order in which they are written.
+        let tcx = self.encoder.env().tcx();
+        spec_closures.sort_by_key(|cl_def_id| tcx.def_span(*cl_def_id).lo());
+        spec_closures
+    }
+
+    /// Encode the functional specification of a loop
+    fn encode_loop_invariant_specs(
+        &self,
+        loop_head: BasicBlockIndex,
+        loop_inv_block: BasicBlockIndex,
+    ) -> SpannedEncodingResult<(Vec<vir::Expr>, MultiSpan)> {
+        let mut specs = vec![];
+        for cl_def_id in self.get_loop_spec_closures(loop_head) {
+            specs.extend(self.encoder.get_loop_specs(cl_def_id).unwrap().invariant);
+        }
         trace!("specs: {:?}", specs);
 
         let mut encoded_specs = vec![];
@@ -4510,6 +7975,289 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok((encoded_specs, MultiSpan::from_spans(encoded_spec_spans)))
     }
 
+    /// Encode the termination measures of a loop, at the point of the loop
+    /// invariant.
+    fn encode_loop_variant_specs(
+        &self,
+        loop_head: BasicBlockIndex,
+        loop_inv_block: BasicBlockIndex,
+    ) -> SpannedEncodingResult<(Vec<vir::Expr>, MultiSpan)> {
+        let mut specs = vec![];
+        for cl_def_id in self.get_loop_spec_closures(loop_head) {
+            specs.extend(self.encoder.get_loop_specs(cl_def_id).unwrap().variant);
+        }
+        trace!("variants: {:?}", specs);
+
+        let mut encoded_variants = vec![];
+        let mut encoded_variant_spans = vec![];
+        if !specs.is_empty() {
+            let encoded_args: Vec<vir::Expr> = self
+                .mir
+                .args_iter()
+                .map(|local| self.mir_encoder.encode_local(local).map(|l| l.into()))
+                .collect::<Result<Vec<_>, _>>()?;
+            for measure in &specs {
+                if let typed::AssertionKind::Expr(ref expr) = *measure.kind {
+                    let measure_ty = self.encoder.env().mir(expr.expr).return_ty();
+                    if !measure_ty.is_integral() {
+                        return Err(SpannedEncodingError::incorrect(
+                            "the loop variant must be an integer",
+                            self.encoder.env().tcx().def_span(expr.expr),
+                        ));
+                    }
+                }
+                let encoded_variant = self.encoder.encode_assertion(
+                    &measure,
+                    &self.mir,
+                    Some(PRECONDITION_LABEL),
+                    &encoded_args,
+                    None,
+                    false,
+                    Some(loop_inv_block),
+                    ErrorCtxt::GenericExpression,
+                    self.proc_def_id,
+                )?;
+                encoded_variants.push(encoded_variant);
+                encoded_variant_spans.extend(
+                    typed::Spanned::get_spans(measure, &self.mir, self.encoder.env().tcx())
+                );
+            }
+        }
+
+        Ok((encoded_variants, MultiSpan::from_spans(encoded_variant_spans)))
+    }
+
+    /// Find the iterator of a `for` loop over a range of integers, from the
+    /// call of `Iterator::next` before the loop invariant, together with the
+    /// user variables that store the value yielded by the call.
+    fn find_range_loop_iterator(
+        &self,
+        loop_head: BasicBlockIndex,
+    ) -> Option<(mir::Place<'tcx>, ty::Ty<'tcx>, Vec<mir::Local>)> {
+        let tcx = self.encoder.env().tcx();
+        let loop_body = self.loop_encoder.loops().get_loop_body(loop_head);
+        let loop_inv_block = self.cached_loop_invariant_block[&loop_head];
+        let loop_inv_pos = loop_body.iter().position(|&bb| bb == loop_inv_block)?;
+        let blocks_before_inv = &loop_body[..=loop_inv_pos];
+
+        let (call_block, result, mut reference, iter_ty) = blocks_before_inv.iter().find_map(|&bb| {
+            match self.mir[bb].terminator().kind {
+                TerminatorKind::Call {
+                    ref func,
+                    ref args,
+                    destination: Some((result, _)),
+                    ..
+                } => {
+                    let (def_id, substs) = func.const_fn_def()?;
+                    let iter_ty = substs.type_at(0);
+                    match tcx.def_path_str(def_id).as_str() {
+                        "std::iter::Iterator::next" | "core::iter::Iterator::next"
+                            if range_iterator_kind(tcx, iter_ty).is_some() =>
+                        {
+                            Some((bb, result, args[0].place()?, iter_ty))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        })?;
+
+        // Follow the reborrows of the iterator, e.g. `_6 = &mut (*_7)` and
+        // `_7 = &mut _4`
+        let mut iter_place = None;
+        for statement in self.mir[call_block].statements.iter().rev() {
+            if let mir::StatementKind::Assign(
+                box (ref lhs, mir::Rvalue::Ref(_, _, ref src))
+            ) = statement.kind {
+                if *lhs != reference {
+                    continue;
+                }
+                if let [mir::ProjectionElem::Deref] = src.projection[..] {
+                    reference = mir::Place::from(src.local);
+                } else {
+                    iter_place = Some(*src);
+                    break;
+                }
+            }
+        }
+
+        // The locals that store `((result as Some).0)`, directly or through
+        // copies
+        let mut value_locals = HashSet::new();
+        for &bb in blocks_before_inv {
+            for statement in &self.mir[bb].statements {
+                if let mir::StatementKind::Assign(
+                    box (ref lhs, mir::Rvalue::Use(ref operand))
+                ) = statement.kind {
+                    let src = match operand.place() {
+                        Some(src) => src,
+                        None => continue,
+                    };
+                    let is_value = match src.projection[..] {
+                        [mir::ProjectionElem::Downcast(_, variant), mir::ProjectionElem::Field(..)] => {
+                            src.local == result.local
+                                && result.projection.is_empty()
+                                && variant.index() == 1
+                        }
+                        [] => value_locals.contains(&src.local),
+                        _ => false,
+                    };
+                    if let (true, Some(local)) = (is_value, lhs.as_local()) {
+                        value_locals.insert(local);
+                    }
+                }
+            }
+        }
+        let user_vars = self.mir.var_debug_info
+            .iter()
+            .filter_map(|info| match info.value {
+                mir::VarDebugInfoContents::Place(place) => place.as_local(),
+                _ => None,
+            })
+            .filter(|local| value_locals.contains(local))
+            .collect();
+
+        Some((iter_place?, iter_ty, user_vars))
+    }
+
+    /// Encode the automatic invariant of a `for` loop over a range of
+    /// integers: the fields of the iterator stay within the bounds that they
+    /// had when the loop was entered, and the user variables that store the
+    /// induction variable contain the last value yielded by the iterator.
+    ///
+    /// Returns the statements that store the values of the fields on entry,
+    /// to be placed before the first check of the invariant, and the
+    /// invariant. Both are empty if the loop does not iterate over a range.
+    fn encode_range_loop_invariant(
+        &mut self,
+        loop_head: BasicBlockIndex,
+    ) -> SpannedEncodingResult<(Vec<vir::Stmt>, Vec<vir::Expr>)> {
+        let (iter_place, iter_ty, user_vars) = match self.find_range_loop_iterator(loop_head) {
+            Some(found) => found,
+            None => return Ok((vec![], vec![])),
+        };
+        let span = self.mir_encoder.get_span_of_basic_block(loop_head);
+        let (iter, pre_stmts, _, _) = self.encode_place(&iter_place, ArrayAccessKind::Shared)
+            .with_span(span)?;
+        if !pre_stmts.is_empty() {
+            // The iterator is in an array or a slice
+            return Ok((vec![], vec![]));
+        }
+        let fields = self.encode_range_iterator_fields(iter, iter_ty).with_span(span)?;
+        let mut values = vec![];
+        for local in user_vars {
+            let var = self.mir_encoder.encode_local(local)?;
+            let local_ty = self.mir_encoder.get_local_ty(local);
+            values.push(
+                self.encoder.encode_value_expr(vir::Expr::local(var), local_ty).with_span(span)?
+            );
+        }
+
+        // The ghost variables that store `start`, `end` and `step` on entry
+        let entry_vars = match self.range_loop_entry_vars.get(&loop_head) {
+            Some(entry_vars) => entry_vars.clone(),
+            None => {
+                let entry_vars: Vec<_> = (0..3)
+                    .map(|_| self.cfg_method.add_fresh_local_var(vir::Type::Int))
+                    .collect();
+                self.range_loop_entry_vars.insert(loop_head, entry_vars.clone());
+                entry_vars
+            }
+        };
+        let start_on_entry: vir::Expr = entry_vars[0].clone().into();
+        let end_on_entry: vir::Expr = entry_vars[1].clone().into();
+        let step_on_entry: vir::Expr = entry_vars[2].clone().into();
+        let mut entry_stmts = vec![
+            vir::Stmt::Assign(start_on_entry.clone(), fields.start.clone(), vir::AssignKind::Copy),
+            vir::Stmt::Assign(end_on_entry.clone(), fields.end.clone(), vir::AssignKind::Copy),
+        ];
+        if let Some(step) = &fields.step {
+            entry_stmts.push(
+                vir::Stmt::Assign(step_on_entry.clone(), step.clone(), vir::AssignKind::Copy)
+            );
+        }
+
+        let start = fields.start;
+        let end = fields.end;
+        let one = vir::Expr::from(1);
+        let mut invariant = vec![vir::Expr::le_cmp(start.clone(), end.clone())];
+        match range_iterator_kind(self.encoder.env().tcx(), iter_ty).unwrap() {
+            RangeIteratorKind::Range => {
+                invariant.push(vir::Expr::le_cmp(start_on_entry, start.clone()));
+                invariant.push(vir::Expr::eq_cmp(end, end_on_entry));
+                for value in values {
+                    invariant.push(vir::Expr::eq_cmp(vir::Expr::add(value, one.clone()), start.clone()));
+                }
+            }
+            RangeIteratorKind::RangeInclusive => {
+                // After yielding `end`, which could be the maximum value of
+                // the type, the range is exhausted instead of moving `start`
+                invariant.push(vir::Expr::le_cmp(start_on_entry, start.clone()));
+                invariant.push(vir::Expr::eq_cmp(end.clone(), end_on_entry));
+                let exhausted = fields.exhausted.unwrap();
+                for value in values {
+                    invariant.push(vir::Expr::ite(
+                        exhausted.clone(),
+                        vir::Expr::and(
+                            vir::Expr::eq_cmp(value.clone(), start.clone()),
+                            vir::Expr::eq_cmp(start.clone(), end.clone()),
+                        ),
+                        vir::Expr::eq_cmp(vir::Expr::add(value, one.clone()), start.clone()),
+                    ));
+                }
+            }
+            RangeIteratorKind::Rev { inclusive: false } => {
+                invariant.push(vir::Expr::eq_cmp(start, start_on_entry));
+                invariant.push(vir::Expr::le_cmp(end.clone(), end_on_entry));
+                for value in values {
+                    invariant.push(vir::Expr::eq_cmp(value, end.clone()));
+                }
+            }
+            RangeIteratorKind::Rev { inclusive: true } => {
+                invariant.push(vir::Expr::eq_cmp(start.clone(), start_on_entry));
+                invariant.push(vir::Expr::le_cmp(end.clone(), end_on_entry));
+                let exhausted = fields.exhausted.unwrap();
+                for value in values {
+                    invariant.push(vir::Expr::ite(
+                        exhausted.clone(),
+                        vir::Expr::and(
+                            vir::Expr::eq_cmp(value.clone(), end.clone()),
+                            vir::Expr::eq_cmp(start.clone(), end.clone()),
+                        ),
+                        vir::Expr::eq_cmp(value, vir::Expr::add(end.clone(), one.clone())),
+                    ));
+                }
+            }
+            RangeIteratorKind::StepBy => {
+                // The yielded values are `start_on_entry - 1` plus multiples
+                // of the step given by the user
+                invariant.push(vir::Expr::not(fields.first_take.unwrap()));
+                invariant.push(vir::Expr::eq_cmp(fields.step.unwrap(), step_on_entry.clone()));
+                invariant.push(vir::Expr::le_cmp(start_on_entry.clone(), start.clone()));
+                invariant.push(vir::Expr::eq_cmp(end, end_on_entry));
+                for value in values {
+                    invariant.push(vir::Expr::eq_cmp(
+                        vir::Expr::add(value.clone(), one.clone()),
+                        start.clone(),
+                    ));
+                    let distance = vir::Expr::sub(
+                        value,
+                        vir::Expr::sub(start_on_entry.clone(), one.clone()),
+                    );
+                    invariant.push(vir::Expr::eq_cmp(
+                        vir::Expr::modulo(
+                            distance,
+                            vir::Expr::add(step_on_entry.clone(), one.clone()),
+                        ),
+                        0.into(),
+                    ));
+                }
+            }
+        }
+        Ok((entry_stmts, invariant))
+    }
+
     fn encode_loop_invariant_exhale_stmts(
         &mut self,
         loop_head: BasicBlockIndex,
@@ -4526,8 +8274,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             self.pure_var_for_preserving_value_map
                 .insert(loop_head, HashMap::new());
         }
-        let (func_spec, func_spec_span) =
+        let (mut func_spec, func_spec_span) =
             self.encode_loop_invariant_specs(loop_head, loop_inv_block)?;
+        let (range_entry_stmts, range_invariant) = self.encode_range_loop_invariant(loop_head)?;
+        func_spec.splice(0..0, range_invariant);
         let (permissions, equalities, invs_spec) =
             self.encode_loop_invariant_permissions(loop_head, loop_inv_block, true)
                 .with_span(func_spec_span.clone())?;
@@ -4572,6 +8322,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     vir::AssignKind::Ghost,
                 ));
             }
+            stmts.extend(range_entry_stmts);
         }
         assert!(!assert_pos.is_default());
         let obtain_predicates = permissions.iter().map(|p| {
@@ -4583,6 +8334,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             func_spec.into_iter().conjoin(),
             assert_pos,
         ));
+        if after_loop_iteration {
+            stmts.extend(self.encode_loop_variant_check(loop_head, loop_inv_block)?);
+        }
         stmts.push(vir::Stmt::Assert(
             invs_spec.into_iter().conjoin(),
             exhale_pos,
@@ -4608,8 +8362,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             loop_head,
             after_loop
         );
-        let (func_spec, func_spec_span) =
+        let (mut func_spec, func_spec_span) =
             self.encode_loop_invariant_specs(loop_head, loop_inv_block)?;
+        let (_, range_invariant) = self.encode_range_loop_invariant(loop_head)?;
+        func_spec.splice(0..0, range_invariant);
         let (permissions, equalities, invs_spec) =
             self.encode_loop_invariant_permissions(loop_head, loop_inv_block, true)
                 .with_span(func_spec_span)?;
@@ -4633,9 +8389,67 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         stmts.push(vir::Stmt::Inhale(
             func_spec.into_iter().conjoin(),
         ));
+        if !after_loop {
+            let (variants, _) = self.encode_loop_variant_specs(loop_head, loop_inv_block)?;
+            let mut variant_vars = vec![];
+            for variant in variants {
+                let variant_var = self.cfg_method.add_fresh_local_var(vir::Type::Int);
+                stmts.push(vir::Stmt::Assign(
+                    variant_var.clone().into(),
+                    variant,
+                    vir::AssignKind::Ghost,
+                ));
+                variant_vars.push(variant_var);
+            }
+            self.loop_variant_vars.insert(loop_head, variant_vars);
+        }
         Ok(stmts)
     }
 
+    /// Check that the loop variants, compared lexicographically with their
+    /// values at the beginning of the iteration, decrease and stay
+    /// non-negative.
+    fn encode_loop_variant_check(
+        &self,
+        loop_head: BasicBlockIndex,
+        loop_inv_block: BasicBlockIndex,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let (variants, variant_span) = self.encode_loop_variant_specs(loop_head, loop_inv_block)?;
+        if variants.is_empty() {
+            return Ok(vec![]);
+        }
+        let variant_vars = &self.loop_variant_vars[&loop_head];
+        debug_assert_eq!(variants.len(), variant_vars.len());
+        let decreases = variants.into_iter()
+            .zip(variant_vars.iter())
+            .rev()
+            .fold(vir::Expr::from(false), |later_decrease, (variant, variant_var)| {
+                let old_variant: vir::Expr = variant_var.clone().into();
+                vir::Expr::or(
+                    vir::Expr::and(
+                        vir::Expr::le_cmp(vir::Expr::from(0), variant.clone()),
+                        vir::Expr::lt_cmp(variant.clone(), old_variant.clone()),
+                    ),
+                    vir::Expr::and(
+                        vir::Expr::eq_cmp(variant, old_variant),
+                        later_decrease,
+                    ),
+                )
+            });
+        let pos = self.encoder.error_manager().register(
+            variant_span,
+            ErrorCtxt::AssertLoopVariant,
+            self.proc_def_id,
+        );
+        Ok(vec![
+            vir::Stmt::comment(format!(
+                "Check the termination measure of the loop (loop head: {:?})",
+                loop_head
+            )),
+            vir::Stmt::Assert(decreases, pos),
+        ])
+    }
+
     fn encode_prusti_local(&self, local: Local) -> vir::LocalVar {
         let var_name = self.locals.get_name(local);
         let type_name = self
@@ -4848,6 +8662,82 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(stmts)
     }
 
+    /// Encode an assignment into a slice without an intermediate temporary reference into the
+    /// slice. See `encode_array_direct_assign`; in addition, the length of the slice is
+    /// preserved.
+    fn encode_slice_direct_assign(
+        &mut self,
+        base: PlaceEncoding<'tcx>,
+        index: vir::Expr,
+        slice_ty: ty::Ty<'tcx>,
+        rhs: &mir::Rvalue<'tcx>,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let span = self.mir_encoder.get_span_of_location(location);
+
+        let (encoded_slice, mut stmts) = self.postprocess_place_encoding(
+            base,
+            ArrayAccessKind::Shared,
+        ).with_span(span)?;
+
+        let label = self.cfg_method.get_fresh_label_name();
+        stmts.push(vir::Stmt::label(&label));
+
+        let slice_types = self.encoder.encode_slice_types(slice_ty).with_span(span)?;
+
+        let slice_acc_expr = vir::Expr::predicate_access_predicate(
+            slice_types.slice_pred.clone(),
+            encoded_slice.clone(),
+            vir::PermAmount::Write,
+        );
+
+        // exhale and re-inhale to havoc
+        stmts.push(vir!{ exhale [slice_acc_expr] });
+        stmts.push(vir!{ inhale [slice_acc_expr] });
+
+        let old = |e| { vir::Expr::labelled_old(&label, e) };
+
+        let slice_len = slice_types.encode_slice_len_call(self.encoder, encoded_slice.clone());
+        let len_unchanged = vir!{ [slice_len.clone()] == [old(slice_len.clone())] };
+        stmts.push(vir!{ inhale [ len_unchanged ] });
+
+        let idx_val_int = self.encoder.patch_snapshots(vir::Expr::snap_app(index)).with_span(span)?;
+
+        // inhale infos about slice contents back
+        let i_var: vir::Expr = vir_local!{ i: Int }.into();
+        let zero_le_i = vir!{ [vir::Expr::from(0)] <= [ i_var ] };
+        let i_lt_len = vir!{ [ i_var ] < [ slice_len ] };
+        let i_ne_idx = vir!{ [ i_var ] != [ old(idx_val_int.clone()) ] };
+        let idx_conditions = vir!{ [zero_le_i] && ([i_lt_len] && [i_ne_idx]) };
+        let lookup_ret_ty = self.encoder.encode_snapshot_type(slice_types.elem_ty_rs).with_span(span)?;
+        let lookup_slice_i = slice_types.encode_lookup_pure_call(self.encoder, encoded_slice.clone(), i_var, lookup_ret_ty.clone());
+        let lookup_same_as_old = vir!{ [lookup_slice_i] == [old(lookup_slice_i.clone())] };
+        let forall_body = vir!{ [idx_conditions] ==> [lookup_same_as_old] };
+        let all_others_unchanged = vir!{ forall i: Int :: { [lookup_slice_i] } [ forall_body ] };
+
+        stmts.push(vir!{ inhale [ all_others_unchanged ]});
+
+        let tmp = vir::Expr::from(self.cfg_method.add_fresh_local_var(slice_types.elem_ty.clone()));
+        stmts.extend(
+            self.encode_assign(
+                tmp.clone(),
+                rhs,
+                slice_types.elem_ty_rs,
+                location,
+            ).with_span(span)?
+        );
+
+        let tmp_val_field = self.encoder.encode_value_expr(tmp, slice_types.elem_ty_rs).with_span(span)?;
+
+        let indexed_lookup_pure_call = slice_types
+            .encode_lookup_pure_call(self.encoder, encoded_slice, old(idx_val_int), lookup_ret_ty);
+        let indexed_updated = vir!{ [ indexed_lookup_pure_call ] == [ tmp_val_field ] };
+
+        stmts.push(vir!{ inhale [ indexed_updated ] });
+
+        Ok(stmts)
+    }
+
     /// Return type:
     /// - `Vec<vir::Stmt>`: the statements that encode the assignment of `operand` to `lhs`
     fn encode_assign_operand(
@@ -4943,6 +8833,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 let (ty, val) = mir_constantkind_to_ty_val(*literal);
                 match ty.kind() {
                     ty::TyKind::Tuple(elements) if elements.is_empty() => Vec::new(),
+                    ty::TyKind::Ref(_, _, mir::Mutability::Not) => {
+                        // E.g. a promoted constant like `&[1, 2, 3]`
+                        self.encode_assign_constant_reference(lhs, ty, val, location)?
+                    }
                     _ => {
                         let field = self.encoder.encode_value_field(ty).with_span(span)?;
                         let mut stmts = self.prepare_assign_target(
@@ -5056,7 +8950,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         ).with_span(span)?;
         let encoded_check =
             self.mir_encoder
-                .encode_bin_op_check(op, encoded_left, encoded_right, operand_ty.expect_ty())
+                .encode_bin_op_check(op, encoded_left.clone(), encoded_right.clone(), operand_ty.expect_ty())
                 .with_span(span)?;
         let field_types = if let ty::TyKind::Tuple(ref x) = ty.kind() {
             x
@@ -5101,6 +8995,26 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         } else {
             Vec::with_capacity(2)
         };
+        if self.check_panics && config::check_overflows() {
+            // Check each bound separately, to report which one is exceeded.
+            // The `Assert` terminator that follows the operation only assumes
+            // that it does not overflow.
+            let message = AssertKind::<()>::Overflow(op, (), ()).description();
+            let conditions = self.mir_encoder.encode_bin_op_overflow_conditions(
+                op,
+                encoded_left,
+                encoded_right,
+                operand_ty.expect_ty(),
+            ).with_span(span)?;
+            for (condition, bound) in conditions {
+                let pos = self.encoder.error_manager().register(
+                    span,
+                    ErrorCtxt::OverflowCheck(message.to_string(), bound),
+                    self.proc_def_id,
+                );
+                stmts.push(vir::Stmt::Assert(condition, pos));
+            }
+        }
         // Initialize lhs.field
         stmts.push(vir::Stmt::Assign(
             encoded_lhs
@@ -5119,7 +9033,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
     }
 
     /// Assignment with unary op as RHS.
-    /// Unary ops currently are logical and arithmetic negation
+    /// Unary ops currently are logical, bitwise and arithmetic negation
     /// [encoded_lhs] = [op] [operand]
     fn encode_assign_unary_op(
         &mut self,
@@ -5138,7 +9052,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             .with_span(
                 self.mir_encoder.get_span_of_location(location)
             )?;
-        let encoded_value = self.mir_encoder.encode_unary_op_expr(op, encoded_val);
+        let encoded_value = self.mir_encoder.encode_unary_op_expr(op, encoded_val, ty);
         // Initialize `lhs.field`
         self.encode_copy_value_assign(encoded_lhs, encoded_value, ty, location)
     }
@@ -5517,6 +9431,86 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(stmts)
     }
 
+    /// Assignment of a constant shared reference, such as a promoted `&5` or
+    /// `&[1, 2, 3]`. The target is allocated as a fresh object whose value is
+    /// the evaluated constant.
+    fn encode_assign_constant_reference(
+        &mut self,
+        encoded_lhs: &vir::Expr,
+        ty: ty::Ty<'tcx>,
+        val: ty::ConstKind<'tcx>,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let span = self.mir_encoder.get_span_of_location(location);
+        let tcx = self.encoder.env().tcx();
+        let target_ty = if let ty::TyKind::Ref(_, target_ty, _) = ty.kind() {
+            *target_ty
+        } else {
+            unreachable!()
+        };
+        let is_supported = |ty: ty::Ty<'tcx>| matches!(
+            ty.kind(),
+            ty::TyKind::Bool | ty::TyKind::Char | ty::TyKind::Int(_) | ty::TyKind::Uint(_)
+        );
+        let is_supported_target = match target_ty.kind() {
+            ty::TyKind::Array(elem_ty, _) => is_supported(*elem_ty),
+            _ => is_supported(target_ty),
+        };
+        if !is_supported_target {
+            return Err(SpannedEncodingError::unsupported(
+                format!("constant references to values of type {:?} are not supported", target_ty),
+                span,
+            ));
+        }
+        let param_env = tcx.param_env(self.proc_def_id);
+        let const_value = match val {
+            ty::ConstKind::Value(const_value) => Some(const_value),
+            ty::ConstKind::Unevaluated(ct) => tcx.const_eval_resolve(param_env, ct, None).ok(),
+            _ => None,
+        };
+        let const_value = const_value.ok_or_else(|| SpannedEncodingError::unsupported(
+            format!("unsupported constant value: {:?}", val),
+            span,
+        ))?;
+        let reference = tcx.mk_const(ty::Const { ty, val: ty::ConstKind::Value(const_value) });
+        let pointee = tcx.deref_const(param_env.and(reference));
+
+        let mut stmts = self.encode_havoc_and_allocation(encoded_lhs);
+        let field = self.encoder.encode_value_field(ty).with_span(span)?;
+        let target = encoded_lhs.clone().field(field);
+        match target_ty.kind() {
+            ty::TyKind::Array(..) => {
+                let array_types = self.encoder.encode_array_types(target_ty).with_span(span)?;
+                let lookup_ret_ty = self.encoder.encode_snapshot_type(array_types.elem_ty_rs)
+                    .with_span(span)?;
+                let elements = tcx.destructure_const(param_env.and(pointee)).fields;
+                for (i, element) in elements.iter().enumerate() {
+                    let encoded_element = self.encoder.encode_const_expr(element.ty, &element.val)
+                        .with_span(span)?;
+                    let lookup_pure_call = array_types.encode_lookup_pure_call(
+                        self.encoder,
+                        target.clone(),
+                        vir::Expr::from(i),
+                        lookup_ret_ty.clone(),
+                    );
+                    stmts.push(vir::Stmt::Inhale(
+                        vir!{ [lookup_pure_call] == [encoded_element] }
+                    ));
+                }
+            }
+            _ => {
+                let encoded_value = self.encoder.encode_const_expr(pointee.ty, &pointee.val)
+                    .with_span(span)?;
+                let encoded_target = self.encoder.encode_value_expr(target, target_ty)
+                    .with_span(span)?;
+                stmts.push(vir::Stmt::Inhale(
+                    vir!{ [encoded_target] == [encoded_value] }
+                ));
+            }
+        }
+        Ok(stmts)
+    }
+
     pub fn get_auxiliary_local_var(&mut self, suffix: &str, vir_type: vir::Type) -> vir::LocalVar {
         let name = format!("_aux_{}_{}", suffix, vir_type.name());
         if self.auxiliary_local_vars.contains_key(&name) {
@@ -5549,6 +9543,62 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// Report the reads of union fields that are not the field that was last
+    /// written, whose values are arbitrary.
+    fn report_inactive_union_reads(&self) -> SpannedEncodingResult<()> {
+        for read in self.procedure.get_inactive_union_reads() {
+            let message = format!(
+                "the field `{}` of the union is read, but the field `{}` was written last",
+                read.read_field,
+                read.written_field,
+            );
+            let help = "the verifier does not know the value of the field";
+            if config::strict_union_reads() {
+                return Err(SpannedEncodingError::incorrect(message, read.span).set_help(help));
+            }
+            let mut warning = PrustiError::incorrect(message, read.span.into()).set_help(help);
+            warning.set_warning();
+            warning.emit(self.encoder.env());
+        }
+        Ok(())
+    }
+
+    /// If `lhs` is a field of a union, havoc the other fields of the union,
+    /// which no longer have the values that were last written to them.
+    fn encode_havoc_other_union_fields(
+        &mut self,
+        lhs: &mir::Place<'tcx>,
+        encoded_lhs: &vir::Expr,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let tcx = self.encoder.env().tcx();
+        let (base, written_field) = match lhs.as_ref().last_projection() {
+            Some((base, mir::ProjectionElem::Field(field, _))) => (base, field),
+            _ => return Ok(vec![]),
+        };
+        let (adt_def, subst) = match base.ty(self.mir, tcx).ty.kind() {
+            ty::TyKind::Adt(adt_def, subst) if adt_def.is_union() => (adt_def, subst),
+            _ => return Ok(vec![]),
+        };
+        let encoded_base = if let vir::Expr::Field(box base, _, _) = encoded_lhs {
+            base.clone()
+        } else {
+            unreachable!("{:?}", encoded_lhs)
+        };
+        let span = self.mir_encoder.get_span_of_location(location);
+        let mut stmts = vec![];
+        for (index, field) in adt_def.non_enum_variant().fields.iter().enumerate() {
+            if index == written_field.index() {
+                continue;
+            }
+            let encoded_field = self.encoder
+                .encode_struct_field(&field.ident.as_str(), field.ty(tcx, subst))
+                .with_span(span)?;
+            stmts.extend(self.encode_havoc_and_allocation(&encoded_base.clone().field(encoded_field)));
+        }
+        Ok(stmts)
+    }
+
     /// Havoc and assume permission on fields
     fn encode_havoc_and_allocation(&mut self, dst: &vir::Expr) -> Vec<vir::Stmt> {
         debug!("Encode havoc and allocation {:?}", dst);
@@ -5807,12 +9857,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 }
             }
 
-            mir::AggregateKind::Adt(..) => {
-                // It is a union
-                return Err(SpannedEncodingError::unsupported(
-                    "unions are not supported",
-                    span
-                ));
+            mir::AggregateKind::Adt(adt_def, _, subst, _, active_field) => {
+                // It is a union. Only the active field gets a value, the other
+                // fields keep the arbitrary values of the havoc.
+                let field = &adt_def.non_enum_variant().fields[active_field.unwrap()];
+                let field_ty = field.ty(self.encoder.env().tcx(), subst);
+                let encoded_field = self.encoder
+                    .encode_struct_field(&field.ident.as_str(), field_ty)
+                    .with_span(span)?;
+                stmts.extend(self.encode_assign_operand(
+                    &dst.clone().field(encoded_field),
+                    &operands[0],
+                    location,
+                )?);
             }
 
             mir::AggregateKind::Closure(def_id, _substs) => {
@@ -6099,6 +10156,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
     }
 }
 
+/// A method of a map that does not modify it, see `encode_map_query_call`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum MapQuery {
+    Len,
+    IsEmpty,
+    ContainsKey,
+}
+
 /// Whether to encode a shared or mutable array access
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum ArrayAccessKind {
@@ -6106,6 +10171,18 @@ enum ArrayAccessKind {
     Mutable(Option<Borrow>, mir::Location),
 }
 
+/// The encoded fields of an iterator over a range of integers
+struct RangeIteratorFields {
+    start: vir::Expr,
+    end: vir::Expr,
+    /// The flag of an inclusive range that is set after yielding `end`
+    exhausted: Option<vir::Expr>,
+    /// The step of `StepBy`, minus one
+    step: Option<vir::Expr>,
+    /// The flag of `StepBy` that is set until the first value is yielded
+    first_take: Option<vir::Expr>,
+}
+
 fn convert_loans_to_borrows(loans: &[facts::Loan]) -> Vec<Borrow> {
     loans.iter().map(|l| l.index().into()).collect()
 }
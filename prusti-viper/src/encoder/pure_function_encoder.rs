@@ -11,10 +11,14 @@ use crate::encoder::errors::{SpannedEncodingError, EncodingError, ErrorCtxt, Wit
 use crate::encoder::foldunfold;
 use crate::encoder::mir_encoder::{MirEncoder, PlaceEncoder, PlaceEncoding};
 use crate::encoder::mir_encoder::{PRECONDITION_LABEL, WAND_LHS_LABEL};
+use crate::encoder::mir_encoder::get_integer_arithmetic_method;
 use crate::encoder::mir_interpreter::{
     run_backward_interpretation, BackwardMirInterpreter, MultiExprBackwardInterpreterState,
 };
 use crate::encoder::snapshot;
+use crate::encoder::type_encoder::{
+    box_model_ty, compute_discriminant_values, is_box_model_ty, is_map_ty, is_string_ty, is_vec_ty,
+};
 use crate::encoder::Encoder;
 use prusti_common::{vir, vir_local};
 use prusti_common::vir::ExprIterator;
@@ -108,8 +112,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
             function_name, body_expr
         );
 
-        // if the function returns a snapshot, we take a snapshot of the body
-        if self.encode_function_return_type()?.is_snapshot() {
+        // if the function returns a snapshot or a reference, we take a snapshot of the body
+        if self.encode_function_return_type()?.is_snapshot() || self.mir.return_ty().is_ref() {
             let ty = self.encoder.resolve_typaram(self.mir.return_ty());
             let return_span = self.get_local_span(mir::RETURN_PLACE);
 
@@ -206,6 +210,10 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
         };
         let return_type = self.encode_function_return_type()?;
 
+        if let Some(variant_test) = self.encode_variant_test_postcondition(&formal_args)? {
+            postcondition.push(variant_test);
+        }
+
         let res_value_range_pos = self.encoder.error_manager().register(
             self.mir.span,
             ErrorCtxt::PureFunctionPostconditionValueRangeOfResult,
@@ -386,6 +394,56 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
         Ok(post)
     }
 
+    /// The variant tests of `Option` and `Result` (e.g. `is_some`) are often
+    /// declared `#[pure]` in external specifications without a postcondition.
+    /// Relate their result to the discriminant of the argument, so that facts
+    /// like `x.is_some()` carry over to `if let Some(..) = x` and `match x`.
+    fn encode_variant_test_postcondition(&self, formal_args: &[vir::LocalVar])
+        -> SpannedEncodingResult<Option<vir::Expr>>
+    {
+        let tcx = self.encoder.env().tcx();
+        let (variant_name, positive) = match tcx.def_path_str(self.proc_def_id).as_str() {
+            "std::option::Option::<T>::is_some"
+            | "core::option::Option::<T>::is_some" => ("Some", true),
+            "std::option::Option::<T>::is_none"
+            | "core::option::Option::<T>::is_none" => ("Some", false),
+            "std::result::Result::<T, E>::is_ok"
+            | "core::result::Result::<T, E>::is_ok" => ("Ok", true),
+            "std::result::Result::<T, E>::is_err"
+            | "core::result::Result::<T, E>::is_err" => ("Ok", false),
+            _ => return Ok(None),
+        };
+        let (formal_arg, local) = match formal_args.first().zip(self.mir.args_iter().next()) {
+            Some(arg) => arg,
+            None => return Ok(None),
+        };
+        let arg_ty = self.interpreter.mir_encoder().get_local_ty(local);
+        let adt_def = match arg_ty.peel_refs().kind() {
+            ty::TyKind::Adt(adt_def, _) if adt_def.is_enum() => adt_def,
+            _ => return Ok(None),
+        };
+        let variant_idx = match adt_def.variants.iter()
+            .position(|variant_def| &*variant_def.ident.as_str() == variant_name)
+        {
+            Some(idx) => idx,
+            None => return Ok(None),
+        };
+        let discr_value = compute_discriminant_values(adt_def, tcx)[variant_idx];
+        let has_variant = vir::Expr::eq_cmp(
+            vir::Expr::local(formal_arg.clone()).field(self.encoder.encode_discriminant_field()),
+            discr_value.into(),
+        );
+        let has_variant = if positive { has_variant } else { vir::Expr::not(has_variant) };
+        let pure_fn_return_variable = vir_local!{ __result: Bool };
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(self.mir.span, ErrorCtxt::GenericExpression, self.parent_def_id);
+        Ok(Some(
+            vir::Expr::eq_cmp(pure_fn_return_variable.into(), has_variant).set_default_pos(pos)
+        ))
+    }
+
     fn encode_local(&self, local: mir::Local) -> SpannedEncodingResult<vir::LocalVar> {
         let mir_encoder = self.interpreter.mir_encoder();
         let var_name = mir_encoder.encode_local_var_name(local);
@@ -410,7 +468,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionEncoder<'p, 'v, 'tcx> {
 
         // Return an error for unsupported return types
         let tcx = self.encoder.env().tcx();
-        if !is_supported_type_of_pure_expression(tcx, ty) {
+        if let ty::TyKind::Ref(_, _, hir::Mutability::Mut) = ty.kind() {
+            return Err(SpannedEncodingError::incorrect(
+                "pure functions cannot return mutable references",
+                return_span,
+            ));
+        }
+        // A shared reference is encoded as the snapshot of its target
+        let value_ty = if let ty::TyKind::Ref(_, target_ty, _) = ty.kind() {
+            *target_ty
+        } else {
+            ty
+        };
+        if !is_supported_type_of_pure_expression(tcx, value_ty) {
             return Err(SpannedEncodingError::incorrect(
                 "invalid return type of pure function",
                 return_span,
@@ -763,8 +833,17 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                     }),
                 ..
             } => {
-                if let ty::TyKind::FnDef(def_id, substs) = ty.kind() {
-                    let def_id = *def_id;
+                if let ty::TyKind::FnDef(called_def_id, call_substs) = ty.kind() {
+                    let (def_id, substs) = self.encoder.resolve_extern_spec_call(
+                        self.parent_def_id,
+                        *called_def_id,
+                        *call_substs,
+                    );
+                    let (def_id, substs) = self.encoder.resolve_pure_trait_call(
+                        self.parent_def_id,
+                        def_id,
+                        substs,
+                    );
                     let full_func_proc_name: &str =
                         &self.encoder.env().tcx().def_path_str(def_id);
                         // &self.encoder.env().tcx().absolute_item_path_str(def_id);
@@ -776,12 +855,23 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                     // FIXME: this is a hack to support generics. See issue #187.
                     let mut tymap = HashMap::new();
                     for (kind1, kind2) in own_substs.iter().zip(substs.iter()) {
-                        if let (
-                            ty::subst::GenericArgKind::Type(ty1),
-                            ty::subst::GenericArgKind::Type(ty2),
-                        ) = (kind1.unpack(), kind2.unpack())
-                        {
-                            tymap.insert(ty1, ty2);
+                        match (kind1.unpack(), kind2.unpack()) {
+                            (
+                                ty::subst::GenericArgKind::Type(ty1),
+                                ty::subst::GenericArgKind::Type(ty2),
+                            ) => {
+                                tymap.insert(ty1, ty2);
+                            }
+                            (
+                                ty::subst::GenericArgKind::Const(value1),
+                                ty::subst::GenericArgKind::Const(value2),
+                            ) => {
+                                tymap.insert(
+                                    self.encoder.const_param_key(value1),
+                                    self.encoder.const_param_key(value2),
+                                );
+                            }
+                            _ => {}
                         }
                     }
                     let _cleanup_token = self.encoder.push_temp_tymap(tymap);
@@ -804,13 +894,17 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
 
                                 // Return an error for unsupported old(..) types
                                 let tcx = self.encoder.env().tcx();
-                                if !is_supported_type_of_pure_expression(tcx, ty) {
+                                if !is_supported_type_of_old_expression(tcx, ty) {
                                     return Err(SpannedEncodingError::incorrect(
                                         "the type of the old expression is invalid",
                                         term.source_info.span,
                                     ));
                                 }
 
+                                // The whole argument, including all its dereferences, is
+                                // evaluated in the old state. For a reference (e.g.
+                                // `old(&*self.buf)`) this is the snapshot of its target,
+                                // which replaces all later uses of the target.
                                 let encoded_rhs = self
                                     .mir_encoder
                                     .encode_old_expr(
@@ -833,6 +927,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 state
                             }
 
+                            "prusti_contracts::snapshot_equality" => {
+                                assert_eq!(args.len(), 2);
+                                // Snapshots look through references, so this
+                                // compares the two pointees.
+                                let encoded_rhs = vir::Expr::eq_cmp(
+                                    vir::Expr::snap_app(encoded_args[0].clone()),
+                                    vir::Expr::snap_app(encoded_args[1].clone()),
+                                );
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
                             "std::cmp::PartialEq::eq"
                             if self.encoder.has_structural_eq_impl(
                                 self.mir_encoder.get_operand_ty(&args[0])
@@ -847,6 +954,32 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 state
                             }
 
+                            "std::clone::Clone::clone"
+                            if ty.is_adt() && self.encoder.has_derived_clone_impl(ty) => {
+                                assert_eq!(args.len(), 1);
+                                let encoded_rhs = vir::Expr::snap_app(encoded_args[0].clone());
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
+                            "std::default::Default::default"
+                            if args.is_empty() => {
+                                let encoded_rhs = self.encoder
+                                    .encode_derived_default_snapshot(def_id, ty)
+                                    .with_span(span)?
+                                    .ok_or_else(|| SpannedEncodingError::unsupported(
+                                        format!(
+                                            "the default value of type '{:?}' is unknown in a pure context",
+                                            ty,
+                                        ),
+                                        span,
+                                    ))?;
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
                             "std::cmp::PartialEq::ne"
                             if self.encoder.has_structural_eq_impl(
                                 self.mir_encoder.get_operand_ty(&args[0])
@@ -861,17 +994,204 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 state
                             }
 
-                            "core::slice::<impl [T]>::len" => {
+                            _ if args.len() == 2 &&
+                                get_integer_arithmetic_method(full_func_proc_name).is_some()
+                            => {
+                                let (kind, op) = get_integer_arithmetic_method(full_func_proc_name).unwrap();
+                                let operand_ty = self.mir_encoder.get_operand_ty(&args[0]);
+                                let encoded_rhs = self.mir_encoder.encode_integer_arithmetic(
+                                    kind,
+                                    op,
+                                    encoded_args[0].clone(),
+                                    encoded_args[1].clone(),
+                                    operand_ty,
+                                    ty,
+                                ).with_span(span)?;
+                                let mut state = states[target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
+                            "core::slice::<impl [T]>::len"
+                            | "core::str::<impl str>::len"
+                            | "std::string::String::len"
+                            | "std::vec::Vec::<T, A>::len" => {
+                                assert_eq!(args.len(), 1);
+                                let slice_ty = self.mir_encoder.get_operand_ty(&args[0]);
+                                let len = self.encoder.encode_snapshot_slice_len(slice_ty, encoded_args[0].clone())
+                                    .with_span(span)?;
+
+                                let mut state = states[target_block].clone();
+                                state.substitute_value(&lhs_value, len);
+                                state
+                            }
+
+                            "core::slice::<impl [T]>::is_empty"
+                            | "core::str::<impl str>::is_empty"
+                            | "std::string::String::is_empty"
+                            | "std::vec::Vec::<T, A>::is_empty" => {
                                 assert_eq!(args.len(), 1);
                                 let slice_ty = self.mir_encoder.get_operand_ty(&args[0]);
                                 let len = self.encoder.encode_snapshot_slice_len(slice_ty, encoded_args[0].clone())
                                     .with_span(span)?;
 
+                                let mut state = states[target_block].clone();
+                                state.substitute_value(&lhs_value, vir::Expr::eq_cmp(len, 0.into()));
+                                state
+                            }
+
+                            "std::collections::HashMap::<K, V, S>::len"
+                            | "std::collections::BTreeMap::<K, V>::len" => {
+                                assert_eq!(args.len(), 1);
+                                let map_ty = self.mir_encoder.get_operand_ty(&args[0]);
+                                let len = self.encoder.encode_snapshot_map_len(map_ty, encoded_args[0].clone())
+                                    .with_span(span)?;
+
                                 let mut state = states[target_block].clone();
                                 state.substitute_value(&lhs_value, len);
                                 state
                             }
 
+                            "std::collections::HashMap::<K, V, S>::is_empty"
+                            | "std::collections::BTreeMap::<K, V>::is_empty" => {
+                                assert_eq!(args.len(), 1);
+                                let map_ty = self.mir_encoder.get_operand_ty(&args[0]);
+                                let len = self.encoder.encode_snapshot_map_len(map_ty, encoded_args[0].clone())
+                                    .with_span(span)?;
+
+                                let mut state = states[target_block].clone();
+                                state.substitute_value(&lhs_value, vir::Expr::eq_cmp(len, 0.into()));
+                                state
+                            }
+
+                            "std::collections::HashMap::<K, V, S>::contains_key"
+                            | "std::collections::BTreeMap::<K, V>::contains_key"
+                            | "std::collections::HashMap::<K, V, S>::get"
+                            | "std::collections::BTreeMap::<K, V>::get" => {
+                                assert_eq!(args.len(), 2);
+                                let map_ty = self.mir_encoder.get_operand_ty(&args[0]);
+                                let key_ty = self.mir_encoder.get_operand_ty(&args[1]);
+                                let is_key_ty = match (map_ty.kind(), key_ty.kind()) {
+                                    (ty::TyKind::Ref(_, map_ty, _), ty::TyKind::Ref(_, key_ty, _)) => {
+                                        is_map_ty(self.encoder.env().tcx(), map_ty)
+                                            && matches!(map_ty.kind(), ty::TyKind::Adt(_, substs) if substs.type_at(0) == *key_ty)
+                                    }
+                                    _ => false,
+                                };
+                                if !is_key_ty {
+                                    return Err(SpannedEncodingError::unsupported(
+                                        format!("maps can only be looked up with keys of their key type, not with a {}", key_ty),
+                                        span,
+                                    ));
+                                }
+                                let key = vir::Expr::snap_app(encoded_args[1].clone());
+                                let contains_key = self.encoder.encode_snapshot_map_contains(
+                                    map_ty,
+                                    encoded_args[0].clone(),
+                                    key.clone(),
+                                ).with_span(span)?;
+                                let encoded_rhs = if full_func_proc_name.ends_with("::contains_key") {
+                                    contains_key
+                                } else {
+                                    // `None` and `Some` are the variants 0 and 1 of `Option`
+                                    let value = self.encoder.encode_snapshot_map_lookup(
+                                        map_ty,
+                                        encoded_args[0].clone(),
+                                        key,
+                                    ).with_span(span)?;
+                                    let none = self.encoder.encode_snapshot_variant_constructor(ty, 0, vec![])
+                                        .with_span(span)?;
+                                    let some = self.encoder.encode_snapshot_variant_constructor(ty, 1, vec![value])
+                                        .with_span(span)?;
+                                    vir::Expr::ite(contains_key, some, none)
+                                };
+
+                                let mut state = states[target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
+                            // Strings and vectors are encoded as their
+                            // elements, so these conversions do not change
+                            // the snapshot.
+                            "core::str::<impl str>::as_bytes"
+                            | "std::string::String::as_bytes"
+                            | "std::string::String::as_str" => {
+                                assert_eq!(args.len(), 1);
+                                let encoded_rhs = vir::Expr::snap_app(encoded_args[0].clone());
+                                let mut state = states[target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
+                            // Shared pointers, cells and their guards are
+                            // encoded like boxes, so their snapshot is the
+                            // one of their content.
+                            "std::ops::Deref::deref" |
+                            "core::ops::Deref::deref" |
+                            "std::cell::RefCell::<T>::borrow"
+                                if matches!(
+                                    self.mir_encoder.get_operand_ty(&args[0]).kind(),
+                                    ty::TyKind::Ref(_, inner_ty, _)
+                                        if is_string_ty(self.encoder.env().tcx(), inner_ty)
+                                            || is_vec_ty(self.encoder.env().tcx(), inner_ty)
+                                            || is_box_model_ty(self.encoder.env().tcx(), inner_ty)
+                                )
+                            => {
+                                assert_eq!(args.len(), 1);
+                                let encoded_rhs = vir::Expr::snap_app(encoded_args[0].clone());
+                                let mut state = states[target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
+
+                            "std::ops::Index::index"
+                                if matches!(
+                                    self.mir_encoder.get_operand_ty(&args[0]).kind(),
+                                    ty::TyKind::Ref(_, base_ty, _) if is_vec_ty(self.encoder.env().tcx(), base_ty)
+                                ) && matches!(
+                                    self.mir_encoder.get_operand_ty(&args[1]).kind(),
+                                    ty::TyKind::Uint(ty::UintTy::Usize)
+                                )
+                            => {
+                                assert_eq!(args.len(), 2);
+                                let base_ty = self.mir_encoder.get_operand_ty(&args[0]);
+                                let idx = encoded_args[1].clone();
+                                let elem = self.encoder.encode_snapshot_slice_idx(
+                                    base_ty,
+                                    encoded_args[0].clone(),
+                                    idx.clone(),
+                                ).with_span(span)?;
+                                let len = self.encoder.encode_snapshot_slice_len(base_ty, encoded_args[0].clone())
+                                    .with_span(span)?;
+                                let in_bounds = vir::Expr::lt_cmp(idx, len);
+
+                                let pos = self.encoder.error_manager().register(
+                                    term.source_info.span,
+                                    ErrorCtxt::BoundsCheckAssert,
+                                    self.parent_def_id,
+                                );
+
+                                let mut state = states[target_block].clone();
+                                state.substitute_value(&lhs_value, elem);
+                                MultiExprBackwardInterpreterState::new(
+                                    state
+                                        .exprs()
+                                        .iter()
+                                        .map(|expr| {
+                                            let failure_result = if self.is_encoding_assertion {
+                                                Ok(false.into())
+                                            } else {
+                                                unreachable_expr(pos).with_span(term.source_info.span)
+                                            };
+                                            failure_result.map(
+                                                |result| vir::Expr::ite(in_bounds.clone(), expr.clone(), result)
+                                            )
+                                        })
+                                        .collect::<Result<_, _>>()?,
+                                )
+                            }
+
                             "std::ops::Index::index" => {
                                 assert_eq!(args.len(), 2);
                                 trace!("slice::index(args={:?}, encoded_args={:?}, ty={:?}, lhs_value={:?})", args, encoded_args, ty, lhs_value);
@@ -879,32 +1199,24 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 let base_ty = self.mir_encoder.get_operand_ty(&args[0]);
 
                                 let idx_ty = self.mir_encoder.get_operand_ty(&args[1]);
-                                let idx_ident = self.encoder.env().tcx().def_path_str(idx_ty.ty_adt_def().unwrap().did);
-                                let encoded_idx = &encoded_args[1];
-
-                                let (start, end) = match &*idx_ident {
-                                    "std::ops::Range" => {
-                                        // there's fields like _5.f$start.val_int on `encoded_idx`, it just feels hacky to
-                                        // manually re-do them here when we probably just encoded the type and the
-                                        // construction of the fields..
-                                        let usize_ty = self.encoder.env().tcx().mk_ty(ty::TyKind::Uint(ty::UintTy::Usize));
-                                        let start = encoded_idx.clone()
-                                            .field(self.encoder.encode_struct_field("start", usize_ty).with_span(span)?);
-                                        let start_expr = self.encoder.encode_value_expr(start, usize_ty).with_span(span)?;
-
-                                        let end = encoded_idx.clone()
-                                            .field(self.encoder.encode_struct_field("end", usize_ty).with_span(span)?);
-                                        let end_expr = self.encoder.encode_value_expr(end, usize_ty).with_span(span)?;
-
-                                        (start_expr, end_expr)
-                                    },
-                                    // other things like RangeFull, RangeFrom, RangeTo, RangeInclusive could be added here
-                                    // relatively easily
-                                    _ => return Err(SpannedEncodingError::unsupported(
-                                        format!("slicing with {} as index/range type is not supported yet", idx_ident),
-                                        span,
-                                    )),
+                                let base_inner_ty = match base_ty.kind() {
+                                    ty::TyKind::Ref(_, base_inner_ty, _) => *base_inner_ty,
+                                    _ => base_ty,
                                 };
+                                let base_len = match base_inner_ty.kind() {
+                                    ty::TyKind::Array(..) => {
+                                        let array_types = self.encoder.encode_array_types(base_inner_ty)
+                                            .with_span(span)?;
+                                        vir::Expr::from(array_types.array_len)
+                                    }
+                                    _ => self.encoder.encode_snapshot_slice_len(base_ty, encoded_args[0].clone())
+                                        .with_span(span)?,
+                                };
+                                let (start, end) = self.mir_encoder.encode_slicing_bounds(
+                                    encoded_args[1].clone(),
+                                    idx_ty,
+                                    base_len,
+                                ).with_span(span)?;
 
                                 let slice_expr = self.encoder.encode_snapshot_slicing(
                                     base_ty,
@@ -926,13 +1238,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                     self.encoder.encode_pure_function_use(def_id, self.parent_def_id)
                                         .with_span(term.source_info.span)?
                                 } else {
+                                    let help = if def_id.is_local() {
+                                        "only functions marked as `#[pure]` can be used in pure code"
+                                    } else {
+                                        "a function of another crate can be marked as pure with an \
+                                        `#[extern_spec]` that has the `#[pure]` attribute"
+                                    };
                                     return Err(SpannedEncodingError::incorrect(
                                         format!(
                                             "use of impure function {:?} in pure code is not allowed",
                                             func_proc_name
                                         ),
                                         term.source_info.span,
-                                    ));
+                                    ).set_help(help));
                                 };
                                 trace!("Encoding pure function call '{}'", function_name);
 
@@ -969,7 +1287,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                         let error_ctxt = match full_func_proc_name {
                             "std::rt::begin_panic"
                             | "core::panicking::panic"
-                            | "core::panicking::panic_fmt" => {
+                            | "core::panicking::panic_str"
+                            | "core::panicking::panic_fmt"
+                            | "core::panicking::assert_failed" => {
                                 // This is called when a Rust assertion fails
                                 // args[0]: message
                                 // args[1]: position of failing assertions
@@ -982,12 +1302,21 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
 
                             _ => ErrorCtxt::DivergingCallInPureFunction,
                         };
+                        let is_checked = !matches!(
+                            error_ctxt,
+                            ErrorCtxt::PanicInPureFunction(PanicCause::Unreachable)
+                        ) || config::check_unreachable();
                         let pos = self
                             .encoder
                             .error_manager()
                             .register(term.source_info.span, error_ctxt, self.parent_def_id);
+                        let encoded_expr = if is_checked {
+                            unreachable_expr(pos)
+                        } else {
+                            undef_expr(pos)
+                        };
                         MultiExprBackwardInterpreterState::new_single(
-                            unreachable_expr(pos).with_span(term.source_info.span)?
+                            encoded_expr.with_span(term.source_info.span)?
                         )
                     };
 
@@ -1083,6 +1412,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
             mir::StatementKind::StorageLive(..)
             | mir::StatementKind::StorageDead(..)
             | mir::StatementKind::FakeRead(..)    // FIXME
+            | mir::StatementKind::Nop
             // | mir::StatementKind::ReadForMatch(..)
             // | mir::StatementKind::EndRegion(..)
              => {
@@ -1339,7 +1669,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                     &mir::Rvalue::UnaryOp(op, ref operand) => {
                         let encoded_val = self.mir_encoder.encode_operand_expr(operand)
                             .with_span(span)?;
-                        let encoded_value = self.mir_encoder.encode_unary_op_expr(op, encoded_val);
+                        let operand_ty = self.mir_encoder.get_operand_ty(operand);
+                        let encoded_value = self.mir_encoder.encode_unary_op_expr(
+                            op,
+                            encoded_val,
+                            operand_ty,
+                        );
 
                         // Substitute a place of a value with an expression
                         state.substitute_value(&opt_lhs_value_place.unwrap(), encoded_value);
@@ -1493,8 +1828,20 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
     }
 }
 
-fn is_supported_type_of_pure_expression<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> bool {
-    // Since we don't support box, references and raw pointers this will not recurse forever.
+pub(super) fn is_supported_type_of_pure_expression<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    is_supported_type_of_pure_expression_rec(tcx, ty, &mut vec![])
+}
+
+/// Boxes are encoded transparently as their content, so a recursive type
+/// such as `struct Node { next: Option<Box<Node>> }` is reached again while
+/// its fields are being checked. `in_progress` contains the ADTs that are
+/// being checked; reaching one of them again does not make the type
+/// unsupported.
+fn is_supported_type_of_pure_expression_rec<'tcx>(
+    tcx: ty::TyCtxt<'tcx>,
+    ty: ty::Ty<'tcx>,
+    in_progress: &mut Vec<ty::Ty<'tcx>>,
+) -> bool {
     match ty.kind() {
         ty::TyKind::Bool
         | ty::TyKind::Int(_)
@@ -1502,15 +1849,41 @@ fn is_supported_type_of_pure_expression<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<
         | ty::TyKind::Char => true,
 
         ty::TyKind::Tuple(elems) => {
-            elems.types().all(|t| is_supported_type_of_pure_expression(tcx, t))
+            elems.types().all(|t| is_supported_type_of_pure_expression_rec(tcx, t, in_progress))
+        }
+
+        ty::TyKind::Adt(adt_def, _) if adt_def.is_box() => {
+            is_supported_type_of_pure_expression_rec(tcx, ty.boxed_ty(), in_progress)
         }
 
-        ty::TyKind::Adt(adt_def, subst) if !adt_def.is_box() => {
-            adt_def.all_fields()
-                    .map(|field| field.ty(tcx, subst))
-                    .all(|t| is_supported_type_of_pure_expression(tcx, t))
+        ty::TyKind::Adt(_, _) if is_box_model_ty(tcx, ty) => {
+            is_supported_type_of_pure_expression_rec(tcx, box_model_ty(tcx, ty), in_progress)
+        }
+
+        ty::TyKind::Adt(_, _) if in_progress.contains(&ty) => true,
+
+        ty::TyKind::Adt(adt_def, subst) => {
+            in_progress.push(ty);
+            let is_supported = adt_def.all_fields()
+                .map(|field| field.ty(tcx, subst))
+                .all(|t| is_supported_type_of_pure_expression_rec(tcx, t, in_progress));
+            in_progress.pop();
+            is_supported
         }
 
         _ => false,
     }
 }
+
+/// Arguments of `old(..)` can additionally be references, arrays and slices,
+/// which are encoded with the snapshot of their (pre-state) contents.
+fn is_supported_type_of_old_expression<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    match ty.kind() {
+        ty::TyKind::Ref(_, target_ty, _) => is_supported_type_of_old_expression(tcx, *target_ty),
+
+        ty::TyKind::Array(elem_ty, _)
+        | ty::TyKind::Slice(elem_ty) => is_supported_type_of_old_expression(tcx, *elem_ty),
+
+        _ => is_supported_type_of_pure_expression(tcx, ty),
+    }
+}
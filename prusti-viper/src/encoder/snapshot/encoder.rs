@@ -28,6 +28,7 @@ use crate::encoder::{
     errors::{EncodingError, EncodingResult, SpannedEncodingResult},
     snapshot::{Snapshot, patcher::SnapshotPatcher},
     builtin_encoder::BuiltinFunctionKind,
+    type_encoder::{box_model_ty, is_map_ty, slice_model_ty},
 };
 
 type PredicateName = String;
@@ -58,11 +59,14 @@ pub struct SnapshotEncoder {
 }
 
 /// Snapshot encoding flattens references and boxes. This function removes any
-/// [Box<...>] or reference (mutable or shared) wrappers.
-fn strip_refs_and_boxes<'tcx>(ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+/// [Box<...>] or reference (mutable or shared) wrappers. Strings and vectors
+/// are treated like slices, see [slice_model_ty], and shared pointers and
+/// cells like boxes, see [box_model_ty].
+fn strip_refs_and_boxes<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+    let ty = box_model_ty(tcx, slice_model_ty(tcx, ty));
     match ty.kind() {
-        _ if ty.is_box() => strip_refs_and_boxes(ty.boxed_ty()),
-        ty::TyKind::Ref(_, ref sub_ty, _) => strip_refs_and_boxes(sub_ty),
+        _ if ty.is_box() => strip_refs_and_boxes(tcx, ty.boxed_ty()),
+        ty::TyKind::Ref(_, ref sub_ty, _) => strip_refs_and_boxes(tcx, sub_ty),
         _ => ty,
     }
 }
@@ -74,6 +78,8 @@ fn strip_refs_and_boxes_expr<'p, 'v: 'p, 'tcx: 'v>(
     ty: ty::Ty<'tcx>,
     expr: Expr,
 ) -> EncodingResult<(ty::Ty<'tcx>, Expr)> {
+    let tcx = encoder.env().tcx();
+    let ty = box_model_ty(tcx, slice_model_ty(tcx, ty));
     match ty.kind() {
         _ if ty.is_box() => strip_refs_and_boxes_expr(
             encoder,
@@ -438,6 +444,25 @@ impl SnapshotEncoder {
         }
     }
 
+    /// Encode the construction of a snapshot of the given variant of an enum.
+    pub fn encode_variant_constructor<'p, 'v: 'p, 'tcx: 'v>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        ty: ty::Ty<'tcx>,
+        variant_idx: usize,
+        args: Vec<vir::Expr>,
+    ) -> EncodingResult<vir::Expr> {
+        let snapshot = self.encode_snapshot(encoder, ty)?;
+        match snapshot {
+            Snapshot::Complex { ref variants, .. } if variant_idx < variants.len() => {
+                Ok(variants[variant_idx].0.apply(args))
+            },
+            _ => Err(EncodingError::internal(
+                format!("invalid constructor of variant {} of {}", variant_idx, ty),
+            )),
+        }
+    }
+
     /// Encode the array indexing operation `a[i]`. Basically translated into `read(snap(a), i)`.
     pub fn encode_array_idx<'p, 'v: 'p, 'tcx: 'v>(
         &mut self,
@@ -503,6 +528,57 @@ impl SnapshotEncoder {
         Ok(len_func.apply(vec![slice]))
     }
 
+    /// Encode whether the map `map` contains the key `key`.
+    pub fn encode_map_contains<'p, 'v: 'p, 'tcx: 'v>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        map_ty: ty::Ty<'tcx>,
+        map: vir::Expr,
+        key: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        if let Snapshot::Map { contains, .. } = self.encode_snapshot(encoder, map_ty)? {
+            Ok(contains.apply(vec![map, key]))
+        } else {
+            Err(EncodingError::internal(
+                format!("called encode_map_contains on non-map-type {:?}", map_ty)
+            ))
+        }
+    }
+
+    /// Encode the value of the key `key` in the map `map`, which is only
+    /// meaningful if the map contains the key.
+    pub fn encode_map_lookup<'p, 'v: 'p, 'tcx: 'v>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        map_ty: ty::Ty<'tcx>,
+        map: vir::Expr,
+        key: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        if let Snapshot::Map { lookup, .. } = self.encode_snapshot(encoder, map_ty)? {
+            Ok(lookup.apply(vec![map, key]))
+        } else {
+            Err(EncodingError::internal(
+                format!("called encode_map_lookup on non-map-type {:?}", map_ty)
+            ))
+        }
+    }
+
+    /// Encode the number of keys of the map `map`.
+    pub fn encode_map_len<'p, 'v: 'p, 'tcx: 'v>(
+        &mut self,
+        encoder: &'p Encoder<'v, 'tcx>,
+        map_ty: ty::Ty<'tcx>,
+        map: vir::Expr,
+    ) -> EncodingResult<vir::Expr> {
+        if let Snapshot::Map { len, .. } = self.encode_snapshot(encoder, map_ty)? {
+            Ok(len.apply(vec![map]))
+        } else {
+            Err(EncodingError::internal(
+                format!("called encode_map_len on non-map-type {:?}", map_ty)
+            ))
+        }
+    }
+
     pub fn encode_slicing<'p, 'v: 'p, 'tcx: 'v>(
         &mut self,
         encoder: &'p Encoder<'v, 'tcx>,
@@ -554,7 +630,7 @@ impl SnapshotEncoder {
         encoder: &'p Encoder<'v, 'tcx>,
         ty: ty::Ty<'tcx>,
     ) -> EncodingResult<Snapshot> {
-        let ty = encoder.resolve_typaram(strip_refs_and_boxes(ty));
+        let ty = encoder.resolve_typaram(strip_refs_and_boxes(encoder.env().tcx(), ty));
         let predicate_name = encoder.encode_type_predicate_use(ty)?;
 
         // was the snapshot for the type already encoded?
@@ -657,6 +733,11 @@ impl SnapshotEncoder {
                     name: None,
                 }], predicate_name)
             }
+            ty::TyKind::Adt(_, subst) if is_map_ty(tcx, ty) => {
+                let key_snap_ty = self.encode_type(encoder, subst.type_at(0))?;
+                let value_snap_ty = self.encode_type(encoder, subst.type_at(1))?;
+                self.encode_map(predicate_name, key_snap_ty, value_snap_ty)
+            }
             ty::TyKind::Adt(adt_def, subst) if adt_def.is_struct() => {
                 let mut fields = vec![];
                 for field in adt_def.all_fields() { // or adt_def.variants[0].fields ?
@@ -1329,6 +1410,98 @@ impl SnapshotEncoder {
         })
     }
 
+    /// Encodes the snapshot of a map. Like [encode_abstract], the snap
+    /// function has no body; the domain functions are constrained by the
+    /// encoding of the map operations instead.
+    fn encode_map(
+        &mut self,
+        predicate_name: &str,
+        key_snap_ty: Type,
+        value_snap_ty: Type,
+    ) -> EncodingResult<Snapshot> {
+        let domain_name = format!("Snap${}", predicate_name);
+        let snapshot_type = Type::Snapshot(predicate_name.to_string());
+
+        let arg_ref_local = vir::LocalVar::new(
+            "self",
+            Type::TypedRef(predicate_name.to_string()),
+        );
+        let arg_ref_expr = Expr::local(arg_ref_local.clone());
+
+        let contains = vir::DomainFunc {
+            name: format!("contains${}$", domain_name),
+            formal_args: vec![
+                vir_local!{ self: {snapshot_type.clone()} },
+                vir_local!{ key: {key_snap_ty.clone()} },
+            ],
+            return_type: Type::Bool,
+            unique: false,
+            domain_name: domain_name.clone(),
+        };
+
+        let lookup = vir::DomainFunc {
+            name: format!("lookup${}$", domain_name),
+            formal_args: vec![
+                vir_local!{ self: {snapshot_type.clone()} },
+                vir_local!{ key: {key_snap_ty} },
+            ],
+            return_type: value_snap_ty,
+            unique: false,
+            domain_name: domain_name.clone(),
+        };
+
+        let len = vir::DomainFunc {
+            name: format!("len${}$", domain_name),
+            formal_args: vec![
+                vir_local!{ self: {snapshot_type.clone()} },
+            ],
+            return_type: Type::Int,
+            unique: false,
+            domain_name: domain_name.clone(),
+        };
+
+        let len_positive = {
+            let len_call = len.apply(vec![vir_local!{ map: {snapshot_type.clone()} }.into()]);
+
+            vir::DomainAxiom {
+                name: format!("{}$len_positive", predicate_name),
+                expr: vir!{ forall map: {snapshot_type.clone()} :: { [len_call] } ([len_call] >= [Expr::from(0)]) },
+                domain_name: domain_name.clone(),
+            }
+        };
+
+        let snap_func = vir::Function {
+            name: SNAP_FUNC_NAME.to_string(),
+            formal_args: vec![arg_ref_local],
+            return_type: snapshot_type,
+            pres: vec![Expr::predicate_access_predicate(
+                predicate_name,
+                arg_ref_expr,
+                PermAmount::Read,
+            )],
+            posts: vec![],
+            body: None,
+        };
+
+        Ok(Snapshot::Map {
+            predicate_name: predicate_name.to_string(),
+            domain: self.insert_domain(vir::Domain {
+                name: domain_name,
+                functions: vec![
+                    contains.clone(),
+                    lookup.clone(),
+                    len.clone(),
+                ],
+                axioms: vec![len_positive],
+                type_vars: vec![],
+            }),
+            snap_func: self.insert_function(snap_func),
+            contains,
+            lookup,
+            len,
+        })
+    }
+
     /// Encodes the snapshot for a complex data structure (tuple, struct,
     /// enum, or closure). There must be one or more variants, at least one
     /// with one or more fields to encode. The returned snapshot will be of the
@@ -61,6 +61,16 @@ enum Snapshot {
         read: vir::DomainFunc,
         len: vir::DomainFunc,
     },
+    /// Maps, of which only the keys they contain, the values of these keys
+    /// and the number of keys are known.
+    Map {
+        predicate_name: String,
+        domain: String,
+        snap_func: vir::FunctionIdentifier,
+        contains: vir::DomainFunc,
+        lookup: vir::DomainFunc,
+        len: vir::DomainFunc,
+    },
     /// Type cannot be encoded: type parameters, unsupported types.
     Abstract {
         predicate_name: String,
@@ -81,7 +91,8 @@ impl Snapshot {
             Self::Complex { predicate_name, .. }
             | Self::Abstract { predicate_name, .. }
             | Self::Array { predicate_name, .. }
-            | Self::Slice { predicate_name, .. } => Type::Snapshot(predicate_name.to_string()),
+            | Self::Slice { predicate_name, .. }
+            | Self::Map { predicate_name, .. } => Type::Snapshot(predicate_name.to_string()),
             Self::Lazy(ty) => ty.clone(),
         }
     }
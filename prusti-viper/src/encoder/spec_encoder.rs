@@ -128,67 +128,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
         vir::LocalVar::new(var_name, ty)
     }
 
-    fn encode_trigger(
-        &self,
-        trigger: &typed::Trigger,
-        bounded_vars: &[vir::LocalVar]
-    ) -> SpannedEncodingResult<vir::Trigger> {
+    /// Encode a trigger. The triggers have been checked when the
+    /// specifications were collected.
+    fn encode_trigger(&self, trigger: &typed::Trigger) -> SpannedEncodingResult<vir::Trigger> {
         trace!("encode_trigger {:?}", trigger);
-        struct TriggerChecker {
-            span: rustc_span::MultiSpan,
-            error: Option<SpannedEncodingError>,
-        }
-        impl vir::ExprWalker for TriggerChecker {
-            fn walk(&mut self, expr: &vir::Expr) {
-                match expr {
-                    vir::Expr::Local(..) |
-                    vir::Expr::Const(..) |
-                    vir::Expr::FuncApp(..) |
-                    vir::Expr::DomainFuncApp(..) => {
-                        // Legal triggers.
-                    }
-                    _ => {
-                        // Everything else is illegal in triggers.
-                        let msg = "Only function calls are allowed in triggers.";
-                        // TODO: We should use a more precise span.
-                        self.error = Some(SpannedEncodingError::incorrect(msg, self.span.clone()));
-                    }
-                }
-                if self.error.is_none() {
-                    vir::default_walk_expr(self, expr);
-                }
-            }
-        }
-        let bounded_vars: Vec<_> = bounded_vars.iter().map(|var| var.clone().into()).collect();
-        let mut found_bounded_vars = std::collections::HashSet::new();
-        let mut encoded_expressions = Vec::new();
-        for term in trigger.terms() {
-            let encoded_expr = self.encode_expression(term)?;
-            let mut checker = TriggerChecker {
-                error: None,
-                span: self.encoder.env().tcx().def_span(term.expr).into(),
-            };
-            vir::ExprWalker::walk(&mut checker, &encoded_expr);
-            if let Some(error) = checker.error {
-                return Err(error);
-            }
-            for var in &bounded_vars {
-                if encoded_expr.find(var) {
-                    found_bounded_vars.insert(var);
-                }
-            }
-            encoded_expressions.push(encoded_expr);
-        }
-        for var in &bounded_vars {
-            if !found_bounded_vars.contains(var) {
-                // FIXME: We should mention the missing variable in the error message.
-                let msg = "A trigger must mention all bounded variables.";
-                let span = rustc_span::MultiSpan::from_spans(
-                    trigger.terms().iter().map(|term| self.encoder.env().tcx().def_span(term.expr)).collect()
-                );
-                return Err(SpannedEncodingError::incorrect(msg, span));
-            }
-        }
+        let encoded_expressions = trigger
+            .terms()
+            .iter()
+            .map(|term| self.encode_expression(term))
+            .collect::<Result<Vec<_>, _>>()?;
         Ok(vir::Trigger::new(encoded_expressions))
     }
 
@@ -237,7 +185,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
             } => {
                 // TODO: refactor, simplify, or extract into a function
                 let tcx = self.encoder.env().tcx();
-                let mir = self.encoder.env().local_mir(closure.expr);
+                let mir = self.encoder.env().mir(closure.expr);
                 let result = &mir.local_decls[(0 as u32).into()];
                 let ty = result.ty;
                 if let Some(ty_repl) = self.encoder.current_tymap().get(ty) {
@@ -340,9 +288,22 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                                 )
                             );
 
+                            // A failure of the hypothetical calls is reported at
+                            // the entailment.
+                            let entailment_span = pres.iter()
+                                .chain(posts.iter())
+                                .flat_map(|assertion| typed::Spanned::get_spans(assertion, &mir, tcx))
+                                .fold(tcx.def_span(closure.expr), |span, other| span.to(other));
+                            let pos = self.encoder.error_manager().register(
+                                entailment_span,
+                                ErrorCtxt::GenericExpression,
+                                self.parent_def_id,
+                            );
+
                             vec![pre_conjunct, post_conjunct]
                                 .into_iter()
                                 .conjoin()
+                                .set_default_pos(pos)
                         }
                         _ => unreachable!()
                     }
@@ -388,7 +349,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
         }
         let mut encoded_triggers = Vec::new();
         for trigger in trigger_set.triggers() {
-            let encoded_trigger = self.encode_trigger(trigger, &encoded_args)?;
+            let encoded_trigger = self.encode_trigger(trigger)?;
             encoded_triggers.push(encoded_trigger);
         }
         let encoded_body = self.encode_assertion(body)?;
@@ -433,7 +394,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
         inner_def_id: DefId,
     ) -> SpannedEncodingResult<(vir::Expr, DefId, mir::Location)> {
         debug!("translate_expr_to_closure_def_site {} {:?}", expr, inner_def_id);
-        let inner_mir = self.encoder.env().local_mir(inner_def_id.expect_local());
+        let inner_mir = self.encoder.env().mir(inner_def_id);
         let inner_mir_encoder = MirEncoder::new(self.encoder, &inner_mir, inner_def_id);
         let inner_attrs = self.encoder.env().tcx().get_attrs(inner_def_id);
 
@@ -448,7 +409,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
         ) = opt_instantiation.expect(
             &format!("cannot find definition site for closure {:?}", inner_def_id)
         );
-        let outer_mir = self.encoder.env().local_mir(outer_def_id.expect_local());
+        let outer_mir = self.encoder.env().mir(outer_def_id);
         let outer_mir_encoder = MirEncoder::new(self.encoder, &outer_mir, outer_def_id);
         let outer_span = outer_mir_encoder.get_span_of_location(outer_location);
         trace!("Replacing variables of {:?} captured from {:?}", inner_def_id, outer_def_id);
@@ -577,7 +538,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
         target_location: mir::BasicBlock,
     ) -> SpannedEncodingResult<vir::Expr> {
         debug!("translate_expr_to_state {} {:?} {:?}", expr, def_id, expr_location);
-        let mir = self.encoder.env().local_mir(def_id.expect_local());
+        let mir = self.encoder.env().mir(def_id);
 
         // Translate an intermediate state to the state at the beginning of the method
         let state = MultiExprBackwardInterpreterState::new_single(
@@ -612,7 +573,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
     {
         debug!("encode_expression {:?}", assertion_expr);
 
-        let mut curr_def_id = assertion_expr.expr.to_def_id();
+        let mut curr_def_id = assertion_expr.expr;
         let mut curr_expr = self.encoder.encode_pure_expression(curr_def_id, self.parent_def_id)?;
 
         loop {
@@ -645,7 +606,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
 
         // At this point `curr_def_id` should be either a SPEC item (when encoding a contract) or
         // the method being verified (when encoding a loop invariant).
-        let mir = self.encoder.env().local_mir(curr_def_id.expect_local());
+        let mir = self.encoder.env().mir(curr_def_id);
         let mir_encoder = MirEncoder::new(self.encoder, &mir, curr_def_id);
 
         // Replacements to use the provided `target_args` and `target_return`
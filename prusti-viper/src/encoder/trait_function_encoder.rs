@@ -0,0 +1,186 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::encoder::errors::{ErrorCtxt, SpannedEncodingError, WithSpan};
+use crate::encoder::errors::SpannedEncodingResult;
+use crate::encoder::pure_function_encoder::is_supported_type_of_pure_expression;
+use crate::encoder::spec_encoder::encode_spec_assertion;
+use crate::encoder::Encoder;
+use prusti_common::{vir, vir_local};
+use prusti_common::vir::ExprIterator;
+use prusti_common::config;
+use rustc_hir as hir;
+use rustc_hir::def_id::DefId;
+use rustc_middle::{mir, ty};
+use rustc_span::Span;
+use log::{debug, trace};
+
+/// Encodes a pure method that a trait declares without a default body. Such a
+/// method has no MIR, so it is encoded from its signature as a bodyless
+/// function whose contract is the contract of the trait declaration. This is
+/// how calls on receivers whose type is a type parameter are encoded.
+pub struct TraitFunctionEncoder<'p, 'v: 'p, 'tcx: 'v> {
+    encoder: &'p Encoder<'v, 'tcx>,
+    proc_def_id: DefId,
+    fn_sig: ty::FnSig<'tcx>,
+    span: Span,
+}
+
+impl<'p, 'v: 'p, 'tcx: 'v> TraitFunctionEncoder<'p, 'v, 'tcx> {
+    pub fn new(encoder: &'p Encoder<'v, 'tcx>, proc_def_id: DefId) -> Self {
+        trace!("TraitFunctionEncoder constructor: {:?}", proc_def_id);
+        let tcx = encoder.env().tcx();
+        TraitFunctionEncoder {
+            encoder,
+            proc_def_id,
+            // FIXME: "skip_binder" is most likely wrong
+            fn_sig: tcx.fn_sig(proc_def_id).skip_binder(),
+            span: tcx.def_span(proc_def_id),
+        }
+    }
+
+    pub fn encode_function(&self) -> SpannedEncodingResult<vir::Function> {
+        let function_name = self.encode_function_name();
+        debug!("Encode trait method {} as a bodyless function", function_name);
+
+        let contract = self.encoder
+            .get_procedure_contract_for_def(self.proc_def_id)
+            .with_span(self.span)?;
+        let subst_strings = self.encoder.type_substitution_strings().with_span(self.span)?;
+
+        let formal_args = self.fn_sig.inputs()
+            .iter()
+            .enumerate()
+            .map(|(index, &arg_ty)| {
+                let var_type = self.encoder
+                    .encode_snapshot_type(arg_ty)
+                    .with_span(self.span)?;
+                Ok(vir::LocalVar::new(
+                    format!("{:?}", mir::Local::from_usize(index + 1)),
+                    var_type.patch(&subst_strings),
+                ))
+            })
+            .collect::<SpannedEncodingResult<Vec<_>>>()?;
+        let return_type = self.encode_function_return_type()?;
+
+        // The arguments of the contract are the arguments of the signature.
+        let encoded_args: Vec<vir::Expr> = formal_args
+            .iter()
+            .cloned()
+            .map(vir::Expr::local)
+            .collect();
+        let encoded_return: vir::Expr = vir::LocalVar::new(
+            format!("{:?}", mir::RETURN_PLACE),
+            return_type.clone(),
+        ).into();
+        let pure_fn_return_variable = vir_local!{ __result: {return_type.clone()} };
+
+        let pos = self.encoder.error_manager().register(
+            self.span,
+            ErrorCtxt::GenericExpression,
+            self.proc_def_id,
+        );
+        let mut precondition = vec![];
+        for item in contract.functional_precondition() {
+            debug!("Encode spec item: {:?}", item);
+            precondition.push(encode_spec_assertion(
+                self.encoder,
+                item,
+                None,
+                &encoded_args,
+                None,
+                true,
+                None,
+                self.proc_def_id,
+            )?);
+        }
+        let mut postcondition = vec![];
+        for item in contract.functional_postcondition() {
+            debug!("Encode spec item: {:?}", item);
+            let encoded_postcond = encode_spec_assertion(
+                self.encoder,
+                item,
+                None,
+                &encoded_args,
+                Some(&encoded_return),
+                true,
+                None,
+                self.proc_def_id,
+            )?;
+            postcondition.push(
+                encoded_postcond.replace_place(&encoded_return, &pure_fn_return_variable.clone().into())
+            );
+        }
+
+        // Add value range of the arguments and return value to the pre/postconditions
+        if config::check_overflows() {
+            let res_value_range_pos = self.encoder.error_manager().register(
+                self.span,
+                ErrorCtxt::PureFunctionPostconditionValueRangeOfResult,
+                self.proc_def_id,
+            );
+            postcondition.extend(
+                self.encoder
+                    .encode_type_bounds(&pure_fn_return_variable.into(), self.fn_sig.output())
+                    .into_iter()
+                    .map(|p| p.set_default_pos(res_value_range_pos))
+            );
+            for (formal_arg, &arg_ty) in formal_args.iter().zip(self.fn_sig.inputs()) {
+                precondition.extend(
+                    self.encoder.encode_type_bounds(&vir::Expr::local(formal_arg.clone()), arg_ty)
+                );
+            }
+        }
+
+        let function = vir::Function {
+            name: function_name,
+            formal_args,
+            return_type,
+            pres: vec![precondition.into_iter().conjoin().set_default_pos(pos)],
+            posts: vec![postcondition.into_iter().conjoin().set_default_pos(pos)],
+            body: None,
+        };
+
+        self.encoder
+            .log_vir_program_before_foldunfold(function.to_string());
+
+        // The contract refers to the arguments only by value, so there is
+        // nothing to fold or unfold.
+        self.encoder.patch_snapshots_function(function)
+            .with_span(self.span)
+    }
+
+    pub fn encode_function_name(&self) -> String {
+        self.encoder.encode_item_name(self.proc_def_id)
+    }
+
+    pub fn encode_function_return_type(&self) -> SpannedEncodingResult<vir::Type> {
+        let ty = self.encoder.resolve_typaram(self.fn_sig.output());
+
+        // Return an error for unsupported return types
+        let tcx = self.encoder.env().tcx();
+        if let ty::TyKind::Ref(_, _, hir::Mutability::Mut) = ty.kind() {
+            return Err(SpannedEncodingError::incorrect(
+                "pure functions cannot return mutable references",
+                self.span,
+            ));
+        }
+        // A shared reference is encoded as the snapshot of its target
+        let value_ty = if let ty::TyKind::Ref(_, target_ty, _) = ty.kind() {
+            *target_ty
+        } else {
+            ty
+        };
+        if !is_supported_type_of_pure_expression(tcx, value_ty) {
+            return Err(SpannedEncodingError::incorrect(
+                "invalid return type of pure function",
+                self.span,
+            ));
+        }
+
+        self.encoder.encode_snapshot_type(ty).with_span(self.span)
+    }
+}
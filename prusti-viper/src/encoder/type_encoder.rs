@@ -8,6 +8,7 @@ use crate::encoder::foldunfold;
 use crate::encoder::utils::range_extract;
 use crate::encoder::utils::PlusOne;
 use crate::encoder::Encoder;
+use crate::encoder::spec_encoder::encode_spec_assertion;
 use prusti_common::{
     config,
     vir,
@@ -16,6 +17,7 @@ use prusti_common::{
 };
 // use prusti_interface::specifications::*;
 // use rustc::middle::const_val::ConstVal;
+use rustc_hir::def_id::DefId;
 use rustc_middle::ty;
 use rustc_target::abi;
 use rustc_middle::ty::layout::IntegerExt;
@@ -39,6 +41,8 @@ pub struct TypeEncoder<'p, 'v: 'p, 'tcx: 'v> {
 
 impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
     pub fn new(encoder: &'p Encoder<'v, 'tcx>, ty: ty::Ty<'tcx>) -> Self {
+        let tcx = encoder.env().tcx();
+        let ty = box_model_ty(tcx, slice_model_ty(tcx, ty));
         TypeEncoder { encoder, ty }
     }
 
@@ -176,7 +180,7 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
         })
     }
 
-    fn get_integer_bounds(&self) -> Option<(vir::Expr, vir::Expr)> {
+    pub fn get_integer_bounds(&self) -> Option<(vir::Expr, vir::Expr)> {
         match self.ty.kind() {
             ty::TyKind::Int(int_ty) => {
                 let bounds = match int_ty {
@@ -185,7 +189,10 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                     ty::IntTy::I32 => (std::i32::MIN.into(), std::i32::MAX.into()),
                     ty::IntTy::I64 => (std::i64::MIN.into(), std::i64::MAX.into()),
                     ty::IntTy::I128 => (std::i128::MIN.into(), std::i128::MAX.into()),
-                    ty::IntTy::Isize => (std::isize::MIN.into(), std::isize::MAX.into()),
+                    ty::IntTy::Isize => {
+                        let bits = config::pointer_width();
+                        ((-(1i128 << (bits - 1))).into(), ((1i128 << (bits - 1)) - 1).into())
+                    }
                 };
                 Some(bounds)
             }
@@ -196,7 +203,7 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                     ty::UintTy::U32 => (0.into(), std::u32::MAX.into()),
                     ty::UintTy::U64 => (0.into(), std::u64::MAX.into()),
                     ty::UintTy::U128 => (0.into(), std::u128::MAX.into()),
-                    ty::UintTy::Usize => (0.into(), std::usize::MAX.into()),
+                    ty::UintTy::Usize => (0.into(), ((1u128 << config::pointer_width()) - 1).into()),
                 };
                 Some(bounds)
             }
@@ -270,11 +277,25 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 vec![vir::Predicate::new_struct(typ, fields)]
             }
 
+            // Maps are abstract, see [is_map_ty].
+            ty::TyKind::Adt(_, _) if is_map_ty(self.encoder.env().tcx(), self.ty) => {
+                vec![vir::Predicate::new_abstract(typ)]
+            }
+
             ty::TyKind::Adt(adt_def, subst) if !adt_def.is_box() => {
                 let num_variants = adt_def.variants.len();
                 let tcx = self.encoder.env().tcx();
                 if num_variants == 1 {
                     debug!("ADT {:?} has only one variant", adt_def);
+                    // The fields of a union that were not last written get
+                    // arbitrary values, which is only sound for `Copy` fields.
+                    if adt_def.is_union() && adt_def.non_enum_variant().fields.iter().any(|field| {
+                        !self.encoder.env().type_is_copy(field.ty(tcx, subst))
+                    }) {
+                        return Err(EncodingError::unsupported(
+                            "unions with fields that are not `Copy` are not supported"
+                        ));
+                    }
                     let mut fields = vec![];
                     for field in &adt_def.variants[0usize.into()].fields {
                         let field_name = field.ident.to_string();
@@ -286,9 +307,19 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                             )?
                         );
                     }
-                    vec![vir::Predicate::new_struct(typ, fields)]
+                    let this = vir::Predicate::construct_this(typ.clone());
+                    let mut predicate = vir::StructPredicate::new(typ, fields);
+                    if let Some(invariant) = self.encode_type_invariant(adt_def.did, subst, this.into())? {
+                        predicate.body = predicate.body.map(|body| vir::Expr::and(body, invariant));
+                    }
+                    vec![vir::Predicate::Struct(predicate)]
                 } else {
                     debug!("ADT {:?} has {} variants", adt_def, num_variants);
+                    if self.encoder.get_struct_specs(adt_def.did).is_some() {
+                        return Err(EncodingError::unsupported(
+                            "invariants of enums are not supported yet"
+                        ));
+                    }
                     let discriminant_field = self.encoder.encode_discriminant_field();
                     let this = vir::Predicate::construct_this(typ.clone());
                     let discriminant_loc =
@@ -400,6 +431,60 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
         })
     }
 
+    /// Encode the invariants attached to the struct `def_id`, which are part
+    /// of the body of its predicate. `this` is the instance of the struct.
+    fn encode_type_invariant(
+        &self,
+        def_id: DefId,
+        subst: ty::subst::SubstsRef<'tcx>,
+        this: vir::Expr,
+    ) -> EncodingResult<Option<vir::Expr>> {
+        let specs = match self.encoder.get_struct_specs(def_id) {
+            Some(specs) => specs,
+            None => return Ok(None),
+        };
+        let own_substs = ty::List::identity_for_item(self.encoder.env().tcx(), def_id);
+
+        // FIXME: this is a hack to support generics. See issue #187.
+        let mut tymap = HashMap::new();
+        for (kind1, kind2) in own_substs.iter().zip(subst.iter()) {
+            match (kind1.unpack(), kind2.unpack()) {
+                (
+                    ty::subst::GenericArgKind::Type(ty1),
+                    ty::subst::GenericArgKind::Type(ty2),
+                ) => {
+                    tymap.insert(ty1, ty2);
+                }
+                (
+                    ty::subst::GenericArgKind::Const(value1),
+                    ty::subst::GenericArgKind::Const(value2),
+                ) => {
+                    tymap.insert(
+                        self.encoder.const_param_key(value1),
+                        self.encoder.const_param_key(value2),
+                    );
+                }
+                _ => {}
+            }
+        }
+        let _cleanup_token = self.encoder.push_temp_tymap(tymap);
+
+        let mut invariants = vec![];
+        for spec in specs {
+            invariants.push(encode_spec_assertion(
+                self.encoder,
+                &spec.assertion,
+                None,
+                &[this.clone()],
+                None,
+                false,
+                None,
+                def_id,
+            )?);
+        }
+        Ok(Some(invariants.into_iter().conjoin()))
+    }
+
     /// The string to be appended to the encoding of certain types to make generics "less fragile".
     fn encode_substs(&self, substs: ty::subst::SubstsRef<'tcx>) -> EncodingResult<String> {
         let mut composed_name = vec![
@@ -480,7 +565,7 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
             ty::TyKind::Array(elem_ty, size) => {
                 let array_len =
                     self.encoder
-                        .const_eval_intlike(&size.val).unwrap()
+                        .const_eval_intlike(&size.val)?
                         .to_u64().unwrap();
                 format!(
                     "Array${}${}",
@@ -524,8 +609,16 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 composed_name.join("$")
             }
 
-            ty::TyKind::Dynamic(..) => {
-                "unsupported$dynamic".to_string()
+            ty::TyKind::Dynamic(predicates, _) => {
+                // The methods of a trait object are called through the
+                // contract of its principal trait.
+                match predicates.principal_def_id() {
+                    Some(trait_def_id) => format!(
+                        "dyn${}",
+                        self.encoder.encode_item_name(trait_def_id)
+                    ),
+                    None => "dyn$".to_string(),
+                }
             }
 
             ty::TyKind::FnPtr(..) => {
@@ -779,6 +872,145 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
     }
 }
 
+pub fn is_string_ty<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    match ty.kind() {
+        ty::TyKind::Adt(adt_def, _) => tcx.def_path_str(adt_def.did) == "std::string::String",
+        _ => false,
+    }
+}
+
+pub fn is_vec_ty<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    match ty.kind() {
+        ty::TyKind::Adt(adt_def, _) => tcx.def_path_str(adt_def.did) == "std::vec::Vec",
+        _ => false,
+    }
+}
+
+pub fn is_result_ty<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    match ty.kind() {
+        ty::TyKind::Adt(adt_def, _) => tcx.def_path_str(adt_def.did) == "std::result::Result",
+        _ => false,
+    }
+}
+
+/// The iterators over a range of integers that `for` loops can iterate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeIteratorKind {
+    /// `start..end`
+    Range,
+    /// `start..=end`
+    RangeInclusive,
+    /// `(start..end).rev()`, or `(start..=end).rev()` if inclusive
+    Rev { inclusive: bool },
+    /// `(start..end).step_by(step)`
+    StepBy,
+}
+
+/// Classify the iterators over a range of integers, which are encoded with
+/// their fields.
+pub fn range_iterator_kind<'tcx>(
+    tcx: ty::TyCtxt<'tcx>,
+    ty: ty::Ty<'tcx>,
+) -> Option<RangeIteratorKind> {
+    let (adt_def, substs) = match ty.kind() {
+        ty::TyKind::Adt(adt_def, substs) => (adt_def, substs),
+        _ => return None,
+    };
+    let inner_ty = substs.types().next()?;
+    match tcx.def_path_str(adt_def.did).as_str() {
+        "std::ops::Range" if inner_ty.is_integral() => Some(RangeIteratorKind::Range),
+        "std::ops::RangeInclusive" if inner_ty.is_integral() => {
+            Some(RangeIteratorKind::RangeInclusive)
+        }
+        "std::iter::Rev" => match range_iterator_kind(tcx, inner_ty)? {
+            RangeIteratorKind::Range => Some(RangeIteratorKind::Rev { inclusive: false }),
+            RangeIteratorKind::RangeInclusive => Some(RangeIteratorKind::Rev { inclusive: true }),
+            _ => None,
+        },
+        "std::iter::StepBy" => match range_iterator_kind(tcx, inner_ty)? {
+            RangeIteratorKind::Range => Some(RangeIteratorKind::StepBy),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Maps (`HashMap` and `BTreeMap`) are encoded without their implementation:
+/// their predicate is abstract and their snapshot is only described by the
+/// functions `contains`, `lookup` and `len` of its domain.
+pub fn is_map_ty<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    match ty.kind() {
+        ty::TyKind::Adt(adt_def, _) => matches!(
+            tcx.def_path_str(adt_def.did).as_str(),
+            "std::collections::HashMap" | "std::collections::BTreeMap"
+        ),
+        _ => false,
+    }
+}
+
+/// `Rc` and `Arc` are only supported for read-only sharing, see
+/// [box_model_ty].
+pub fn is_shared_ptr_ty<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    match ty.kind() {
+        ty::TyKind::Adt(adt_def, _) => matches!(
+            tcx.def_path_str(adt_def.did).as_str(),
+            "std::rc::Rc" | "std::sync::Arc"
+        ),
+        _ => false,
+    }
+}
+
+pub fn is_ref_cell_ty<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    match ty.kind() {
+        ty::TyKind::Adt(adt_def, _) => tcx.def_path_str(adt_def.did) == "std::cell::RefCell",
+        _ => false,
+    }
+}
+
+/// The guards `Ref` and `RefMut` returned by `RefCell::borrow` and
+/// `RefCell::borrow_mut`.
+pub fn is_ref_cell_guard_ty<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    match ty.kind() {
+        ty::TyKind::Adt(adt_def, _) => matches!(
+            tcx.def_path_str(adt_def.did).as_str(),
+            "std::cell::Ref" | "std::cell::RefMut"
+        ),
+        _ => false,
+    }
+}
+
+pub fn is_box_model_ty<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    is_shared_ptr_ty(tcx, ty) || is_ref_cell_ty(tcx, ty) || is_ref_cell_guard_ty(tcx, ty)
+}
+
+/// Shared pointers, cells and their guards are encoded like boxes: `Rc<T>`,
+/// `Arc<T>`, `RefCell<T>`, `Ref<T>` and `RefMut<T>` like `Box<T>`. Since the
+/// content of a shared pointer is never modified, each clone can own a copy
+/// of it. A guard owns a copy of the content of its cell, which a `RefMut`
+/// writes back when it is dropped. Other types are returned unchanged.
+pub fn box_model_ty<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+    match ty.kind() {
+        ty::TyKind::Adt(_, substs) if is_box_model_ty(tcx, ty) => {
+            tcx.mk_box(substs.types().next().unwrap())
+        }
+        _ => ty,
+    }
+}
+
+/// Strings and vectors are encoded like slices: `str` like `[u8]`, `String`
+/// like `Box<[u8]>` (i.e. as its UTF-8 bytes) and `Vec<T>` like `Box<[T]>`.
+/// Other types are returned unchanged.
+pub fn slice_model_ty<'tcx>(tcx: ty::TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+    match ty.kind() {
+        ty::TyKind::Str => tcx.mk_slice(tcx.types.u8),
+        _ if is_string_ty(tcx, ty) => tcx.mk_box(tcx.mk_slice(tcx.types.u8)),
+        ty::TyKind::Adt(_, substs) if is_vec_ty(tcx, ty) => {
+            tcx.mk_box(tcx.mk_slice(substs.type_at(0)))
+        }
+        _ => ty,
+    }
+}
+
 /// Compute the values that a discriminant can take.
 pub fn compute_discriminant_values<'tcx>(adt_def: &'tcx ty::AdtDef, tcx: ty::TyCtxt<'tcx>) -> Vec<i128> {
     let mut discr_values: Vec<i128> = vec![];
@@ -28,4 +28,5 @@ extern crate lazy_static;
 
 pub mod encoder;
 mod utils;
+mod verification_cache;
 pub mod verifier;
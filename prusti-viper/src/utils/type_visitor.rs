@@ -70,6 +70,9 @@ pub trait TypeVisitor<'tcx>: Sized {
             TyKind::Array(ty, len) => {
                 self.visit_array(ty, len)
             }
+            TyKind::Dynamic(..) => {
+                self.visit_dynamic()
+            }
             ref x => {
                 self.visit_unsupported_sty(x)
             }
@@ -109,6 +112,11 @@ pub trait TypeVisitor<'tcx>: Sized {
         Ok(())
     }
 
+    /// Trait objects are opaque, like type parameters.
+    fn visit_dynamic(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
     fn visit_projection(
         &mut self,
         _data: ProjectionTy<'tcx>
@@ -0,0 +1,158 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A persistent cache of verification results, stored in the directory given
+//! by the `cache_path` flag.
+//!
+//! Each Viper program (i.e. a procedure together with the contracts of
+//! everything it uses) is identified by a hash of its encoding, computed after
+//! the optimizations. Positions are not part of the hash: they are replaced by
+//! their index in the program, so that moving code around in the source file
+//! still hits the cache. The errors stored in the cache refer to positions by
+//! the same indices, which are mapped back to the positions of the current
+//! encoding when the errors are replayed.
+
+use prusti_common::{config, verification_service::ViperBackendConfig, vir};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use ::log::{debug, warn};
+
+/// Identifies the encoding of a Viper program in the cache.
+pub struct CacheKey {
+    hash: String,
+    /// The ids of the positions of the program, in the order of their indices.
+    positions: Vec<u64>,
+}
+
+impl CacheKey {
+    /// The key of `program` verified with `backend_config`, which includes
+    /// the backend, its arguments, and the resource limit of the SMT solver.
+    pub fn new(program: &vir::Program, backend_config: &ViperBackendConfig) -> Self {
+        let mut value = serde_json::to_value(program)
+            .expect("failed to serialize a Viper program");
+        let mut positions = vec![];
+        normalize(&mut value, &mut positions);
+        let mut hasher = DefaultHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        backend_config.hash(&mut hasher);
+        value.to_string().hash(&mut hasher);
+        CacheKey {
+            hash: format!("{:016x}", hasher.finish()),
+            positions,
+        }
+    }
+
+    /// Replace the id of a position with its index, or the other way around.
+    fn map_position(&self, pos_id: &Option<String>, to_index: bool) -> Option<Option<String>> {
+        match pos_id {
+            None => Some(None),
+            Some(pos_id) => {
+                let number: u64 = pos_id.parse().ok()?;
+                let mapped = if to_index {
+                    self.positions.iter().position(|&id| id == number)? as u64
+                } else {
+                    *self.positions.get(number as usize)?
+                };
+                Some(Some(mapped.to_string()))
+            }
+        }
+    }
+
+    fn map_error(&self, error: &viper::VerificationError, to_index: bool) -> Option<viper::VerificationError> {
+        let mut mapped = error.clone();
+        mapped.pos_id = self.map_position(&error.pos_id, to_index)?;
+        mapped.reason_pos_id = self.map_position(&error.reason_pos_id, to_index)?;
+        Some(mapped)
+    }
+}
+
+/// Replace all positions in the serialized program by their indices, and
+/// remove the sources of nondeterminism in the serialization.
+fn normalize(value: &mut Value, positions: &mut Vec<u64>) {
+    let position_id = match value {
+        Value::Object(map) if map.len() == 3 && map.contains_key("line")
+            && map.contains_key("column") => map.get("id").and_then(|id| id.as_u64()),
+        _ => None,
+    };
+    if let Some(id) = position_id {
+        let index = positions.iter().position(|&other| other == id).unwrap_or_else(|| {
+            positions.push(id);
+            positions.len() - 1
+        });
+        *value = Value::String(format!("pos#{}", index));
+        return;
+    }
+    match value {
+        Value::Object(map) => {
+            // The labels of a method are stored in a `HashSet`.
+            if let Some(Value::Array(labels)) = map.get_mut("labels") {
+                labels.sort_by_key(|label| label.to_string());
+            }
+            for field in map.values_mut() {
+                normalize(field, positions);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                normalize(item, positions);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub struct VerificationCache {
+    path: PathBuf,
+}
+
+impl VerificationCache {
+    /// The cache configured by the `cache_path` flag, if any.
+    pub fn from_config() -> Option<Self> {
+        config::cache_path().map(|path| VerificationCache { path: PathBuf::from(path) })
+    }
+
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        self.path.join(format!("{}.json", key.hash))
+    }
+
+    /// The errors reported the last time a program with the same encoding was
+    /// verified, or `None` if there is no such result.
+    pub fn load(&self, key: &CacheKey) -> Option<Vec<viper::VerificationError>> {
+        if config::force_reverify() {
+            return None;
+        }
+        let content = fs::read_to_string(self.entry_path(key)).ok()?;
+        let errors: Vec<viper::VerificationError> = serde_json::from_str(&content).ok()?;
+        let errors = errors.iter()
+            .map(|error| key.map_error(error, false))
+            .collect::<Option<Vec<_>>>();
+        debug!("Verification cache hit for {}: {:?}", key.hash, errors);
+        errors
+    }
+
+    /// Store the errors reported by the verification of a program.
+    pub fn store(&self, key: &CacheKey, errors: &[viper::VerificationError]) {
+        let errors = match errors.iter()
+            .map(|error| key.map_error(error, true))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(errors) => errors,
+            // Some error does not refer to a position of the program.
+            None => return,
+        };
+        let result = fs::create_dir_all(&self.path).and_then(|_| {
+            let content = serde_json::to_string(&errors)
+                .expect("failed to serialize verification errors");
+            fs::write(self.entry_path(key), content)
+        });
+        if let Err(error) = result {
+            warn!("Failed to store the verification result in the cache: {}", error);
+        }
+    }
+}
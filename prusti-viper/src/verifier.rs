@@ -6,15 +6,22 @@
 
 use prusti_common::vir::{self, optimizations::optimize_program, ToViper, ToViperDecl};
 use prusti_common::{
-    config, report::log, verification_context::VerifierBuilder, verification_service::*, Stopwatch,
+    config,
+    report::{log, progress::{self, Phase}},
+    verification_context::VerifierBuilder,
+    verification_service::*,
+    Stopwatch,
 };
 use crate::encoder::Encoder;
 use crate::encoder::counterexample_translation;
-// use prusti_filter::validators::Validator;
+use crate::verification_cache::{CacheKey, VerificationCache};
 use prusti_interface::data::VerificationResult;
-use prusti_interface::data::VerificationTask;
+use prusti_interface::data::{ProcedureDefId, VerificationTask};
 use prusti_interface::environment::Environment;
+use prusti_interface::error_codes;
 use prusti_interface::PrustiError;
+use prusti_interface::report::{ErrorReport, ItemReport, ItemResult, VerificationReport};
+use prusti_interface::specs::dump::SpanDump;
 // use prusti_interface::specifications::TypedSpecificationMap;
 use std::time::Instant;
 use viper::{self, VerificationBackend, Viper};
@@ -23,8 +30,13 @@ use std::fs::{create_dir_all, canonicalize};
 use std::ffi::OsString;
 use prusti_interface::specs::typed;
 use ::log::{info, debug, error};
-use prusti_server::{PrustiServerConnection, ServerSideService, VerifierRunner};
-use rustc_span::DUMMY_SP;
+use prusti_server::{
+    verify_in_parallel, BatchItemResult, PrustiServerConnection, ServerSideService,
+};
+use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use rustc_span::{Span, DUMMY_SP};
 
 // /// A verifier builder is an object that lives entire program's
 // /// lifetime, has no mutable state, and is responsible for constructing
@@ -140,6 +152,24 @@ where
 {
     env: &'v Environment<'tcx>,
     encoder: Encoder<'v, 'tcx>,
+    /// The report of the last verification.
+    report: VerificationReport,
+}
+
+/// What the verification of the programs of a task reported, gathered as
+/// their results arrive for the report of the verification.
+#[derive(Default)]
+struct ProgramOutcomes {
+    /// The errors reported for each program.
+    errors: HashMap<String, Vec<ErrorReport>>,
+    /// The time spent by the backend verifying each program.
+    backend_times: HashMap<String, Duration>,
+    /// The resources consumed by the SMT solver for each program.
+    smt_rlimit_counts: HashMap<String, u64>,
+    /// The programs whose verification timed out.
+    timed_out_program_names: HashSet<String>,
+    /// Whether an error has been reported for any program.
+    failed: bool,
 }
 
 impl<'v, 'tcx> Verifier<'v, 'tcx> {
@@ -150,9 +180,16 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
         Verifier {
             env,
             encoder: Encoder::new(env, def_spec),
+            report: VerificationReport::default(),
         }
     }
 
+    /// Take the report of the last verification, with an item for each
+    /// procedure of its task.
+    pub fn take_report(&mut self) -> VerificationReport {
+        std::mem::take(&mut self.report)
+    }
+
     pub fn verify(&mut self, task: &VerificationTask) -> VerificationResult {
         info!(
             "Received {} functions to be verified:",
@@ -164,6 +201,8 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
         // Dump the configuration
         log::report("config", "prusti", config::dump());
 
+        progress::init(self.env.has_json_diagnostics());
+
         for &proc_id in &task.procedures {
             let proc_name = self.env.get_absolute_item_name(proc_id);
             let proc_def_path = self.env.get_item_def_path(proc_id);
@@ -171,75 +210,21 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
             info!(" - {} from {:?} ({})", proc_name, proc_span, proc_def_path);
         }
 
-        // // Check support status, and queue encoding
-        // let validator = Validator::new(self.env.tcx());
-
-        // let report_support_status = config::report_support_status();
-        // let skip_unsupported_features = config::skip_unsupported_features();
-        // let error_on_partially_supported = config::error_on_partially_supported();
-        // let mut skipped_functions_count = 0;
-
-        // for &proc_id in &task.procedures {
-        //     let proc_name = self.env.get_absolute_item_name(proc_id);
-        //     let proc_span = self.env.get_item_span(proc_id);
-        //     let is_pure_function = self.env.has_prusti_attribute(proc_id, "pure");
-
-        //     let support_status = if is_pure_function {
-        //         validator.pure_function_support_status(proc_id)
-        //     } else {
-        //         validator.procedure_support_status(proc_id)
-        //     };
-
-        //     if report_support_status {
-        //         support_status.report_support_status(
-        //             &self.env,
-        //             is_pure_function,
-        //             // true ==> raise compiler errors for partially supported functions
-        //             error_on_partially_supported,
-        //             // true ==> raise compiler errors for unsupported functions
-        //             error_on_partially_supported || !skip_unsupported_features,
-        //         );
-        //     }
-
-        //     if !support_status.is_supported() && skip_unsupported_features {
-        //         warn!(
-        //             "Skip verification of {}, as it is not fully supported.",
-        //             proc_name
-        //         );
-        //         self.env.span_warn_with_help_and_note(
-        //             proc_span,
-        //             &format!(
-        //                 "this function will be ignored because it is not fully supported by \
-        //                 Prusti: {}",
-        //                 proc_name
-        //             ),
-        //             &Some(if report_support_status {
-        //                 "Disable the SKIP_UNSUPPORTED_FEATURES configuration flag to verify \
-        //                     this function anyway."
-        //                     .to_string()
-        //             } else {
-        //                 "Enable the REPORT_SUPPORT_STATUS configuration flag for more details \
-        //                     on why the function is not fully supported, or disable \
-        //                     SKIP_UNSUPPORTED_FEATURES to verify this function anyway."
-        //                     .to_string()
-        //             }),
-        //             &None,
-        //         );
-        //         skipped_functions_count += 1;
-        //     } else {
-        //         self.encoder.queue_procedure_encoding(proc_id);
-        //     }
-        // }
-        // info!(
-        //     "Out of {} functions, {} are not fully supported and have been skipped.",
-        //     task.procedures.len(),
-        //     skipped_functions_count,
-        // );
-
+        // Report the unsupported constructs of each procedure upfront, and
+        // skip the encoding of the procedures that use them.
+        let mut skipped_functions_count = 0;
         for &proc_id in task.procedures.iter().rev() {
-            // FIXME: Use the loop above.
-            self.encoder.queue_procedure_encoding(proc_id);
+            if self.encoder.check_supported_constructs(proc_id) {
+                self.encoder.queue_procedure_encoding(proc_id);
+            } else {
+                skipped_functions_count += 1;
+            }
         }
+        info!(
+            "Out of {} functions, {} are not fully supported and have been skipped.",
+            task.procedures.len(),
+            skipped_functions_count,
+        );
         self.encoder.process_encoding_queue();
 
         let encoding_errors_count = self.encoder.count_encoding_errors();
@@ -261,71 +246,298 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
             .to_str()
             .unwrap()
             .to_owned();
-        let verification_result: viper::ProgramVerificationResult = if let Some(server_address) =
-            config::server_address()
-        {
+
+        // Replay the results of the programs whose encoding did not change
+        // since they were last verified.
+        let cache = VerificationCache::from_config();
+        let mut cached_errors = vec![];
+        let mut cached_program_names = HashSet::new();
+        let mut cache_keys = vec![];
+        let mut program_names = vec![];
+        let mut requests = vec![];
+        // The Viper program that verifies a procedure is named after it.
+        let procedures: HashMap<String, ProcedureDefId> = task.procedures
+            .iter()
+            .map(|&proc_id| (self.env.get_absolute_item_name(proc_id), proc_id))
+            .collect();
+        // The resource limit of the SMT solver for each program, if any.
+        let mut smt_rlimits = HashMap::new();
+        // The programs are sent in the order of their procedures in the
+        // source, which is the order of their errors if they are verified one
+        // at a time.
+        programs.sort_by_key(|program| {
+            procedures.get(&program.name).map(|&proc_id| self.env.tcx().def_span(proc_id))
+        });
+        progress::begin(Phase::Verification, programs.len());
+        for mut program in programs {
+            let smt_rlimit = match procedures.get(&program.name) {
+                Some(&proc_id) => self.encoder.get_smt_rlimit(proc_id),
+                None => config::smt_rlimit(),
+            };
+            if let Some(rlimit) = smt_rlimit {
+                smt_rlimits.insert(program.name.clone(), rlimit);
+            }
+            let backend_config = ViperBackendConfig::new(smt_rlimit);
+            if let Some(ref cache) = cache {
+                let key = CacheKey::new(&program, &backend_config);
+                if let Some(errors) = cache.load(&key) {
+                    info!("Reusing the cached verification result of {}", program.name);
+                    cached_errors.push((program.name.clone(), errors));
+                    cached_program_names.insert(program.name.clone());
+                    progress::finish(Phase::Verification, &program.name, "cached");
+                    continue;
+                }
+                cache_keys.push(key);
+            }
+            if config::dump_viper_program().is_some() {
+                annotate_source_locations(&mut program, &self.encoder);
+            }
+            // Each program is verified on its own, so that the programs can
+            // be verified in parallel and their results stored in the cache.
+            program_names.push(program.name.clone());
+            requests.push(VerificationRequest {
+                programs: vec![program],
+                program_name: program_name.clone(),
+                backend_config,
+            });
+        }
+
+        // The errors of each program are emitted as soon as its result is
+        // known, starting with the cached ones.
+        let mut outcomes = ProgramOutcomes::default();
+        for (name, errors) in cached_errors {
+            let smt_rlimit = smt_rlimits.get(&name).cloned();
+            self.report_verification_errors(&name, &errors, smt_rlimit, &mut outcomes);
+        }
+        let report_result = |outcomes: &mut ProgramOutcomes, index: usize, result: BatchItemResult| {
+            let name = &program_names[index];
+            let span = procedures
+                .get(name)
+                .map(|&proc_id| self.env.tcx().def_span(proc_id))
+                .unwrap_or(DUMMY_SP);
+            let smt_rlimit = smt_rlimits.get(name).cloned();
+            self.report_program_result(name, result, span, smt_rlimit, outcomes);
+        };
+
+        // Results are stored as soon as they are available, so that they are
+        // kept even if the verification of a later program fails.
+        let store_in_cache = |index: usize, program_result: &viper::ProgramVerificationResult| {
+            if let (Some(cache), Some(key)) = (&cache, cache_keys.get(index)) {
+                if program_result.consistency_errors.is_empty()
+                    && program_result.java_exceptions.is_empty()
+                {
+                    cache.store(key, &program_result.verification_errors);
+                }
+            }
+        };
+        let procedure_timeout = config::procedure_timeout_ms().map(Duration::from_millis);
+        if requests.is_empty() {
+            // Nothing to verify.
+        } else if let Some(server_address) = config::server_address() {
             let server_address = if server_address == "MOCK" {
                 ServerSideService::spawn_off_thread().to_string()
             } else {
                 server_address
             };
             info!("Connecting to Prusti server at {}", server_address);
-            let service = PrustiServerConnection::new(&server_address).unwrap_or_else(|error| {
-                panic!(
-                    "Could not parse server address ({}) due to {:?}",
-                    server_address, error
-                )
-            });
 
-            let request = VerificationRequest {
-                programs,
-                program_name,
-                backend_config: Default::default(),
+            // All programs are sent as one batch; the server decides how many
+            // of them to verify concurrently, and streams back their results.
+            let batch = BatchVerificationRequest {
+                requests,
+                max_concurrency: config::num_parallel_verifiers(),
+                timeout_ms: procedure_timeout
+                    .into_iter()
+                    .chain(config::server_request_timeout().map(Duration::from_secs))
+                    .min()
+                    .map(|timeout| timeout.as_millis() as u64),
             };
-            service.verify(request)
+            // The server does not tell when it starts verifying a program,
+            // so all of them are reported to start when they are sent.
+            for program_name in &program_names {
+                progress::start(Phase::Verification, program_name);
+            }
+            let items = PrustiServerConnection::new(&server_address)
+                .and_then(|service| service.verify_batch(batch));
+            match items {
+                Ok(items) => {
+                    let mut received = vec![false; program_names.len()];
+                    for item in items {
+                        let program_name = &program_names[item.index];
+                        match &item.result {
+                            BatchItemResult::Verified(program_result) => {
+                                info!(
+                                    "Received the result of {}: {} verification errors",
+                                    program_name,
+                                    program_result.verification_errors.len(),
+                                );
+                                store_in_cache(item.index, program_result);
+                            }
+                            failure => {
+                                error!("Verification of {} failed: {:?}", program_name, failure)
+                            }
+                        }
+                        progress::finish(Phase::Verification, program_name, item.result.outcome());
+                        received[item.index] = true;
+                        report_result(&mut outcomes, item.index, item.result);
+                    }
+                    // A program without a result was lost with the connection.
+                    for index in (0..program_names.len()).filter(|&index| !received[index]) {
+                        report_result(&mut outcomes, index, BatchItemResult::Panicked);
+                    }
+                }
+                Err(error) => {
+                    let prusti_error = PrustiError::internal(
+                        format!("could not verify on the Prusti server: {}", error),
+                        DUMMY_SP.into(),
+                    );
+                    for program_name in &program_names {
+                        outcomes.errors.entry(program_name.clone()).or_default()
+                            .push(prusti_error.to_report(self.env));
+                    }
+                    prusti_error.emit(self.env);
+                    outcomes.failed = true;
+                }
+            }
         } else {
             let mut stopwatch = Stopwatch::start("prusti-viper", "JVM startup");
-            let verifier_builder = VerifierBuilder::new();
+            let verifier_builder = Arc::new(VerifierBuilder::new());
             stopwatch.start_next("running verifier");
-            VerifierRunner::with_default_configured_runner(&verifier_builder, |runner| {
-                runner.verify(programs, program_name.as_str())
-            })
-        };
+            let results = verify_in_parallel(verifier_builder, requests, procedure_timeout);
+            for (index, result) in results.into_iter().enumerate() {
+                if let BatchItemResult::Verified(ref program_result) = result {
+                    store_in_cache(index, program_result);
+                }
+                report_result(&mut outcomes, index, result);
+            }
+        }
 
         stopwatch.finish();
+        progress::end(Phase::Verification);
+
+        let result = if outcomes.failed || encoding_errors_count != 0 {
+            VerificationResult::Failure
+        } else {
+            VerificationResult::Success
+        };
+
+        // An `async fn` is verified through a synchronous copy of its body,
+        // generated by `prusti-specs`.
+        for &proc_id in &task.procedures {
+            if self.env.has_prusti_attribute(proc_id, "async_body") && !self.encoder.is_trusted(proc_id) {
+                self.env.span_note_with_notes(
+                    self.env.tcx().def_span(proc_id),
+                    "this `async fn` has been verified as if it were synchronous",
+                    &[
+                        "each `.await` is assumed to run the awaited `async fn` to completion, \
+                        according to its specification",
+                        "the future might be dropped at an `.await` instead, which cancels the rest \
+                        of the function; the postcondition and the changes made after that point \
+                        are then not guaranteed",
+                    ],
+                );
+            }
+        }
+
+        self.report = self.build_report(task, outcomes, cached_program_names);
 
-        let viper::ProgramVerificationResult {
-            verification_errors,
-            consistency_errors,
-            java_exceptions
-        } = verification_result;
+        result
+    }
 
-        let mut result = VerificationResult::Success;
+    /// Emit the errors of the verification of a program, as soon as its
+    /// result is known, and record them in `outcomes`. `span` is the one of
+    /// the procedure verified by the program, and `smt_rlimit` the resource
+    /// limit of the SMT solver for it, if any.
+    fn report_program_result(
+        &self,
+        program_name: &str,
+        result: BatchItemResult,
+        span: Span,
+        smt_rlimit: Option<u64>,
+        outcomes: &mut ProgramOutcomes,
+    ) {
+        let program_result = match result {
+            BatchItemResult::Verified(program_result) => program_result,
+            BatchItemResult::TimedOut => {
+                let timeout = config::procedure_timeout_ms()
+                    .map(|timeout| format!("{} ms", timeout))
+                    .unwrap_or_else(|| "the time limit of the server".to_string());
+                let prusti_error = PrustiError::verification_timeout(
+                    format!("the verification of `{}` did not finish within {}", program_name, timeout),
+                    span.into(),
+                ).set_help(
+                    "add triggers to the quantifiers of its specification, or split it into \
+                    smaller functions; the limit is set by `PRUSTI_PROCEDURE_TIMEOUT_MS`"
+                );
+                outcomes.errors.entry(program_name.to_string()).or_default()
+                    .push(prusti_error.to_report(self.env));
+                prusti_error.emit(self.env);
+                outcomes.timed_out_program_names.insert(program_name.to_string());
+                outcomes.failed = true;
+                return;
+            }
+            BatchItemResult::Panicked => {
+                let prusti_error = PrustiError::internal(
+                    format!("the verifier panicked while verifying {}", program_name),
+                    DUMMY_SP.into(),
+                );
+                outcomes.errors.entry(program_name.to_string()).or_default()
+                    .push(prusti_error.to_report(self.env));
+                prusti_error.emit(self.env);
+                outcomes.failed = true;
+                return;
+            }
+        };
+        outcomes.backend_times.insert(program_name.to_string(), program_result.verification_time);
+        if let Some(count) = program_result.smt_rlimit_count {
+            outcomes.smt_rlimit_counts.insert(program_name.to_string(), count);
+        }
 
-        for viper::ConsistencyError { method, error} in consistency_errors {
-            PrustiError::internal(
+        for viper::ConsistencyError { method, error } in program_result.consistency_errors {
+            let prusti_error = PrustiError::internal(
                 format!("consistency error in {}: {}", method, error), DUMMY_SP.into()
-            ).emit(self.env);
-            result = VerificationResult::Failure;
+            );
+            outcomes.errors.entry(method).or_default().push(prusti_error.to_report(self.env));
+            prusti_error.emit(self.env);
+            outcomes.failed = true;
         }
 
-        for viper::JavaExceptionWithOrigin { method, exception } in java_exceptions {
+        for viper::JavaExceptionWithOrigin { method, exception } in program_result.java_exceptions {
             error!("Java exception: {}", exception.get_stack_trace());
-            PrustiError::internal(
+            let prusti_error = PrustiError::internal(
                 format!("in {}: {}", method, exception), DUMMY_SP.into()
-            ).emit(self.env);
-            result = VerificationResult::Failure;
+            );
+            outcomes.errors.entry(method).or_default().push(prusti_error.to_report(self.env));
+            prusti_error.emit(self.env);
+            outcomes.failed = true;
         }
 
+        self.report_verification_errors(
+            program_name,
+            &program_result.verification_errors,
+            smt_rlimit,
+            outcomes,
+        );
+    }
+
+    /// Translate the verification errors of a program to Prusti errors, emit
+    /// them in source order, and record them in `outcomes`.
+    fn report_verification_errors(
+        &self,
+        program_name: &str,
+        verification_errors: &[viper::VerificationError],
+        smt_rlimit: Option<u64>,
+        outcomes: &mut ProgramOutcomes,
+    ) {
         let error_manager = self.encoder.error_manager();
         let mut prusti_errors: Vec<_> = verification_errors.iter().map(|verification_error| {
             debug!("Verification error: {:?}", verification_error);
-            let mut prusti_error = error_manager.translate_verification_error(&verification_error);
+            let mut prusti_error = error_manager.translate_verification_error(verification_error);
 
             // annotate with counterexample, if requested
             if config::produce_counterexample() {
                 if let Some(silicon_counterexample) = &verification_error.counterexample {
-                    if let Some(def_id) = error_manager.get_def_id(&verification_error) {
+                    if let Some(def_id) = error_manager.get_def_id(verification_error) {
                         let counterexample = counterexample_translation::backtranslate(
                             &self.encoder,
                             *def_id,
@@ -341,6 +553,12 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
                 }
             }
 
+            if verification_error.is_resource_out() {
+                if let Some(rlimit) = smt_rlimit {
+                    prusti_error = prusti_error.into_resource_out(rlimit);
+                }
+            }
+
             prusti_error
         }).collect();
         prusti_errors.sort();
@@ -349,15 +567,107 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
             if prusti_error.is_disabled() {
                 prusti_error.cancel();
             } else {
+                outcomes.errors.entry(program_name.to_string()).or_default()
+                    .push(prusti_error.to_report(self.env));
                 prusti_error.emit(self.env);
             }
-            result = VerificationResult::Failure;
+            outcomes.failed = true;
         }
+    }
+
+    /// Build the report of the verification of the procedures of the task,
+    /// given the outcomes of the verification of their programs, and the
+    /// programs whose result has been reused from the verification cache.
+    fn build_report(
+        &self,
+        task: &VerificationTask,
+        mut outcomes: ProgramOutcomes,
+        cached_program_names: HashSet<String>,
+    ) -> VerificationReport {
+        let items = task.procedures.iter().map(|&proc_id| {
+            // The Viper program that verifies a procedure is named after it.
+            let program_name = self.env.get_absolute_item_name(proc_id);
+            let mut errors: Vec<ErrorReport> = self.encoder
+                .get_procedure_encoding_errors(proc_id)
+                .iter()
+                .map(|error| error.to_report(self.env))
+                .collect();
+            errors.extend(outcomes.errors.remove(&program_name).unwrap_or_default());
+            let is_resource_out = |error: &ErrorReport| {
+                error.code.as_deref() == Some(error_codes::RESOURCE_OUT)
+            };
+            let result = if self.encoder.is_unsupported(proc_id) {
+                ItemResult::Unsupported
+            } else if outcomes.timed_out_program_names.contains(&program_name) {
+                ItemResult::TimedOut
+            } else if errors.iter().any(|error| error.is_error && !is_resource_out(error)) {
+                ItemResult::Failed
+            } else if errors.iter().any(is_resource_out) {
+                ItemResult::ResourceOut
+            } else if self.encoder.is_trusted(proc_id) || self.encoder.is_pure_memoized(proc_id) {
+                ItemResult::Skipped
+            } else if cached_program_names.contains(&program_name) {
+                ItemResult::Cached
+            } else {
+                ItemResult::Verified
+            };
+            let mut item = ItemReport::new(self.env, proc_id, result);
+            item.errors = errors;
+            item.assumptions = self.encoder
+                .get_procedure_assumptions(proc_id)
+                .into_iter()
+                .map(|span| SpanDump::new(self.env.codemap(), span))
+                .collect();
+            item.assumes_purity = self.encoder.is_pure_memoized(proc_id);
+            item.set_encoding_time(self.encoder.get_procedure_encoding_time(proc_id));
+            item.set_backend_time(outcomes.backend_times.get(&program_name).cloned().unwrap_or_default());
+            item.smt_rlimit_count = outcomes.smt_rlimit_counts.get(&program_name).cloned();
+            item
+        }).collect();
+        VerificationReport { items }
+    }
+}
 
-        if encoding_errors_count != 0 {
-            result = VerificationResult::Failure;
+/// Insert before the statements of the methods of the program comments with
+/// the Rust source location from which they were encoded, so that a dumped
+/// program can be related to the source code. The locations are those of the
+/// positions registered for the error reporting, and a comment is only
+/// inserted when the location changes within a basic block.
+fn annotate_source_locations(program: &mut vir::Program, encoder: &Encoder) {
+    let error_manager = encoder.error_manager();
+    for method in &mut program.methods {
+        for block in &mut method.basic_blocks {
+            let mut last_location = None;
+            for stmt in std::mem::take(&mut block.stmts) {
+                let location = stmt_position(&stmt)
+                    .and_then(|pos| error_manager.get_source_location(&pos))
+                    .filter(|location| Some(location) != last_location.as_ref());
+                if let Some(location) = location {
+                    block.stmts.push(vir::Stmt::comment(&location));
+                    last_location = Some(location);
+                }
+                block.stmts.push(stmt);
+            }
         }
+    }
+}
 
-        result
+/// The position of a statement, or of the expression that it inhales or
+/// assigns, if it has one.
+fn stmt_position(stmt: &vir::Stmt) -> Option<vir::Position> {
+    let pos = match stmt {
+        vir::Stmt::Exhale(_, pos)
+        | vir::Stmt::Assert(_, pos)
+        | vir::Stmt::Obtain(_, pos)
+        | vir::Stmt::Fold(.., pos)
+        | vir::Stmt::PackageMagicWand(.., pos)
+        | vir::Stmt::ApplyMagicWand(_, pos) => *pos,
+        vir::Stmt::Inhale(expr) | vir::Stmt::Assign(_, expr, _) => expr.pos(),
+        _ => return None,
+    };
+    if pos.is_default() {
+        None
+    } else {
+        Some(pos)
     }
 }
@@ -0,0 +1,125 @@
+// Built-in specifications of the standard library. They are added by Prusti
+// to every crate that uses `prusti_contracts`, unless the `std_specs` flag is
+// disabled. Specifications given in the verified crate for the same functions
+// take precedence over these.
+
+use prusti_contracts::*;
+
+#[extern_spec]
+impl<T> std::option::Option<T> {
+    #[pure]
+    #[ensures(result == matches!(*self, Some(_)))]
+    pub fn is_some(&self) -> bool;
+
+    #[pure]
+    #[ensures(result == !self.is_some())]
+    pub fn is_none(&self) -> bool;
+
+    #[requires(self.is_some())]
+    #[ensures(match old(self) { Some(x) => x === result, None => false })]
+    pub fn unwrap(self) -> T;
+
+    #[requires(self.is_some())]
+    #[ensures(match old(self) { Some(x) => x === result, None => false })]
+    pub fn expect(self, msg: &str) -> T;
+
+    #[ensures(match old(self) { Some(x) => x === result, None => old(default) === result })]
+    pub fn unwrap_or(self, default: T) -> T;
+
+    #[ensures(result.is_some() == old(self.is_some()))]
+    pub fn map<U, F>(self, f: F) -> Option<U>
+        where F: FnOnce(T) -> U;
+
+    #[ensures(old(self.is_none()) ==> result.is_none())]
+    pub fn and_then<U, F>(self, f: F) -> Option<U>
+        where F: FnOnce(T) -> Option<U>;
+
+    #[ensures(result.is_ok() == old(self.is_some()))]
+    #[ensures(match old(self) {
+        Some(x) => match result { Ok(y) => x === y, Err(_) => false },
+        None => match result { Ok(_) => false, Err(e) => old(err) === e },
+    })]
+    pub fn ok_or<E>(self, err: E) -> Result<T, E>;
+}
+
+#[extern_spec]
+impl<T, E> std::result::Result<T, E> {
+    #[pure]
+    #[ensures(result == matches!(*self, Ok(_)))]
+    pub fn is_ok(&self) -> bool;
+
+    #[pure]
+    #[ensures(result == !self.is_ok())]
+    pub fn is_err(&self) -> bool;
+
+    #[requires(self.is_ok())]
+    #[ensures(match old(self) { Ok(x) => x === result, Err(_) => false })]
+    pub fn unwrap(self) -> T
+        where E: std::fmt::Debug;
+
+    #[requires(self.is_ok())]
+    #[ensures(match old(self) { Ok(x) => x === result, Err(_) => false })]
+    pub fn expect(self, msg: &str) -> T
+        where E: std::fmt::Debug;
+
+    #[ensures(match old(self) { Ok(x) => x === result, Err(_) => old(default) === result })]
+    pub fn unwrap_or(self, default: T) -> T;
+
+    #[ensures(result.is_some() == old(self.is_ok()))]
+    #[ensures(match old(self) {
+        Ok(x) => match result { Some(y) => x === y, None => false },
+        Err(_) => true,
+    })]
+    pub fn ok(self) -> Option<T>;
+
+    #[ensures(result.is_some() == old(self.is_err()))]
+    #[ensures(match old(self) {
+        Ok(_) => true,
+        Err(e) => match result { Some(f) => e === f, None => false },
+    })]
+    pub fn err(self) -> Option<E>;
+
+    #[ensures(result.is_ok() == old(self.is_ok()))]
+    pub fn map<U, F>(self, op: F) -> Result<U, E>
+        where F: FnOnce(T) -> U;
+
+    #[ensures(result.is_ok() == old(self.is_ok()))]
+    #[ensures(match old(self) {
+        Ok(x) => match result { Ok(y) => x === y, Err(_) => false },
+        Err(_) => true,
+    })]
+    pub fn map_err<F, O>(self, op: O) -> Result<T, F>
+        where O: FnOnce(E) -> F;
+
+    #[ensures(old(self.is_err()) ==> result.is_err())]
+    pub fn and_then<U, F>(self, op: F) -> Result<U, E>
+        where F: FnOnce(T) -> Result<U, E>;
+}
+
+// The conversion that `?` applies to an error of the same type.
+#[extern_spec]
+impl<T> std::convert::From<T> for T {
+    #[ensures(old(t) === result)]
+    fn from(t: T) -> T;
+}
+
+#[extern_spec]
+impl<T> std::rc::Rc<T> {
+    #[ensures(result >= 1)]
+    pub fn strong_count(this: &Self) -> usize;
+}
+
+#[extern_spec]
+impl<T> std::sync::Arc<T> {
+    #[ensures(result >= 1)]
+    pub fn strong_count(this: &Self) -> usize;
+}
+
+// A `RefMut` guard owns a copy of the content of its `RefCell`, which is
+// written back to the cell when the guard is dropped.
+#[extern_spec]
+impl<'b, T> std::ops::DerefMut for std::cell::RefMut<'b, T> {
+    #[ensures(*result === old(**self))]
+    #[after_expiry(**self === before_expiry(*result))]
+    fn deref_mut(&mut self) -> &mut T;
+}
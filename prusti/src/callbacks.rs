@@ -1,4 +1,4 @@
-use prusti_interface::{specs, environment::{Environment, mir_storage}};
+use prusti_interface::{specs, environment::{Environment, mir_storage}, PrustiError};
 use rustc_driver::Compilation;
 use rustc_hir::intravisit;
 use rustc_interface::interface::Compiler;
@@ -6,21 +6,36 @@ use rustc_interface::{Queries, Config};
 use regex::Regex;
 use prusti_common::config;
 use crate::verifier::verify;
+use crate::std_specs::inject_std_specs;
 use rustc_middle::ty::query::query_values::mir_borrowck;
 use rustc_middle::ty::query::Providers;
 use rustc_session::Session;
+use rustc_span::MultiSpan;
 use rustc_hir::def_id::LocalDefId;
 use rustc_middle::ty::{self, TyCtxt};
 
 #[derive(Default)]
 pub struct PrustiCompilerCalls;
 
+/// Whether the package of the crate being compiled is selected by
+/// `verify_packages`. Outside of cargo, the crate is always verified.
+fn is_verified_package() -> bool {
+    let packages = config::verify_packages();
+    packages.is_empty() || std::env::var("CARGO_PKG_NAME")
+        .map_or(true, |name| packages.contains(&name))
+}
+
 fn mir_borrowck<'tcx>(tcx: TyCtxt<'tcx>, def_id: LocalDefId) -> mir_borrowck<'tcx> {
-    let body_with_facts = rustc_mir::consumers::get_body_with_borrowck_facts(
-        tcx, ty::WithOptConstParam::unknown(def_id));
-    // SAFETY: This is safe because we are feeding in the same `tcx` that is
-    // going to be used as a witness when pulling out the data.
-    unsafe { mir_storage::store_mir_body(tcx, def_id, body_with_facts); }
+    // The borrow checker facts of a body that is tainted by type errors are
+    // not reliable and computing them may crash the compiler. Such bodies are
+    // never verified, so we leave the errors to rustc.
+    if tcx.typeck(def_id).tainted_by_errors.is_none() {
+        let body_with_facts = rustc_mir::consumers::get_body_with_borrowck_facts(
+            tcx, ty::WithOptConstParam::unknown(def_id));
+        // SAFETY: This is safe because we are feeding in the same `tcx` that is
+        // going to be used as a witness when pulling out the data.
+        unsafe { mir_storage::store_mir_body(tcx, def_id, body_with_facts); }
+    }
     let mut providers = Providers::default();
     rustc_mir::provide(&mut providers);
     let original_mir_borrowck = providers.mir_borrowck;
@@ -37,6 +52,20 @@ impl rustc_driver::Callbacks for PrustiCompilerCalls {
         assert!(config.override_queries.is_none());
         config.override_queries = Some(override_queries);
     }
+    fn after_parsing<'tcx>(
+        &mut self,
+        compiler: &Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        let session = compiler.session();
+        // The built-in specifications can only be resolved in crates that
+        // depend on `prusti_contracts`.
+        if config::std_specs() && session.opts.externs.get("prusti_contracts").is_some() {
+            let krate = &mut *queries.parse().unwrap().peek_mut();
+            inject_std_specs(session, krate);
+        }
+        Compilation::Continue
+    }
     fn after_expansion<'tcx>(
         &mut self,
         compiler: &Compiler,
@@ -69,6 +98,8 @@ impl rustc_driver::Callbacks for PrustiCompilerCalls {
             let env = Environment::new(tcx);
             let mut spec_checker = specs::checker::SpecChecker::new();
             spec_checker.check_predicate_usages(tcx, krate);
+            spec_checker.check_loop_invariants(tcx, krate);
+            spec_checker.check_ghost_code(tcx, krate);
             spec_checker.report_errors(&env);
             compiler.session().abort_if_errors();
 
@@ -101,7 +132,15 @@ impl rustc_driver::Callbacks for PrustiCompilerCalls {
                     println!("{}", value);
                 }
             }
-            if !config::no_verify() {
+            if let Some(path) = config::dump_specs() {
+                if let Err(error) = specs::dump::dump_specs(&env, &def_spec, &path) {
+                    PrustiError::internal(
+                        format!("failed to dump the specifications to {:?}: {}", path, error),
+                        MultiSpan::new(),
+                    ).emit(&env);
+                }
+            }
+            if !config::no_verify() && is_verified_package() {
                 verify(env, def_spec);
             }
         });
@@ -34,9 +34,11 @@ extern crate prusti_common;
 mod callbacks;
 mod verifier;
 mod arg_value;
+mod std_specs;
 
 use std::{env, panic, borrow::Cow};
 use prusti_common::report::user;
+use prusti_interface::error_codes;
 use lazy_static::lazy_static;
 use callbacks::PrustiCompilerCalls;
 use prusti_common::config;
@@ -133,6 +135,20 @@ fn main() {
     // have been filtered out.
     let mut rustc_args = config::get_filtered_args();
 
+    // rustc does not know the error codes of Prusti, so we explain them.
+    if let Some(code) = arg_value(&rustc_args, "--explain", |code| code.starts_with(&['P', 'p'][..])) {
+        match error_codes::find(code) {
+            Some(error_code) => {
+                println!("{}", error_code.explanation);
+                std::process::exit(0);
+            }
+            None => {
+                eprintln!("error: {} is not a valid Prusti error code", code);
+                std::process::exit(1);
+            }
+        }
+    }
+
     // If the environment asks us to actually be rustc, or if lints have been disabled (which
     // indicates that an upstream dependency is being compiled), then run `rustc` instead of Prusti.
     let prusti_be_rustc = config::be_rustc();
@@ -158,6 +174,9 @@ fn main() {
         env::set_var("POLONIUS_ALGORITHM", "Naive");
         rustc_args.push("-Zpolonius".to_owned());
         rustc_args.push("-Zalways-encode-mir".to_owned());
+        // The specifications exported to other crates refer to closures
+        // that the MIR optimizations would remove as dead code.
+        rustc_args.push("-Zmir-opt-level=0".to_owned());
         rustc_args.push("-Zcrate-attr=feature(register_tool)".to_owned());
         rustc_args.push("-Zcrate-attr=register_tool(prusti)".to_owned());
 
@@ -0,0 +1,27 @@
+use prusti_interface::specs::external::STD_SPECS_MODULE;
+use rustc_ast::ast;
+use rustc_session::Session;
+use rustc_span::FileName;
+
+/// The source code of the built-in specifications.
+const STD_SPECS: &str = include_str!("../resources/std_specs.rs");
+
+/// Add the built-in specifications of the standard library to the crate, in
+/// a module named `STD_SPECS_MODULE`. They are written with the macros of
+/// `prusti_contracts`, so this must happen before the macros are expanded.
+pub fn inject_std_specs(session: &Session, krate: &mut ast::Crate) {
+    let source = format!(
+        "#[allow(dead_code, unused_imports)]\nmod {} {{\n{}}}\n",
+        STD_SPECS_MODULE,
+        STD_SPECS,
+    );
+    let parsed = rustc_parse::parse_crate_from_source_str(
+        FileName::Custom("prusti std specs".to_string()),
+        source,
+        &session.parse_sess,
+    );
+    match parsed {
+        Ok(std_specs) => krate.items.extend(std_specs.items),
+        Err(mut diagnostic) => diagnostic.emit(),
+    }
+}
@@ -5,10 +5,13 @@ use log::{debug, trace, warn};
 use prusti_interface::{
     data::{VerificationResult, VerificationTask},
     environment::Environment,
+    report::{ItemReport, ItemResult, VerificationReport},
+    PrustiError,
 };
 use prusti_viper::verifier::Verifier;
 use prusti_common::config;
 use prusti_common::report::user;
+use rustc_span::MultiSpan;
 
 pub fn verify<'tcx>(
     env: Environment<'tcx>,
@@ -20,9 +23,26 @@ pub fn verify<'tcx>(
         warn!("The compiler reported an error, so the program will not be verified.");
     } else {
         debug!("Prepare verification task...");
-        let annotated_procedures = env.get_annotated_procedures();
+        let (annotated_procedures, unavailable_procedures): (Vec<_>, Vec<_>) = env
+            .get_annotated_procedures()
+            .into_iter()
+            .partition(|procedure| {
+                procedure.as_local().map_or(true, |local_id| env.has_local_mir(local_id))
+            });
+        for procedure in &unavailable_procedures {
+            warn!(
+                "The MIR of {} is not available, so it will not be verified.",
+                env.get_item_def_path(*procedure)
+            );
+        }
+        let (selected_procedures, skipped_procedures): (Vec<_>, Vec<_>) = annotated_procedures
+            .into_iter()
+            .partition(|&procedure| env.is_selected_for_verification(procedure));
+        for procedure in &skipped_procedures {
+            debug!("Skip the verification of {}", env.get_item_def_path(*procedure));
+        }
         let verification_task = VerificationTask {
-            procedures: annotated_procedures,
+            procedures: selected_procedures,
         };
         debug!("Verification task: {:?}", &verification_task);
 
@@ -30,6 +50,12 @@ pub fn verify<'tcx>(
             "Verification of {} items...",
             verification_task.procedures.len()
         ));
+        if !skipped_procedures.is_empty() {
+            user::message(format!(
+                "{} items are not selected for verification and have been skipped",
+                skipped_procedures.len()
+            ));
+        }
 
         if config::print_collected_verification_items() {
             println!("Collected verification items {}:", verification_task.procedures.len());
@@ -38,6 +64,7 @@ pub fn verify<'tcx>(
             }
         }
 
+        let mut report = VerificationReport::default();
         let verification_result = if verification_task.procedures.is_empty() {
             VerificationResult::Success
         } else {
@@ -47,10 +74,23 @@ pub fn verify<'tcx>(
             let mut verifier = Verifier::new(&env, &def_spec);
             let verification_result = verifier.verify(&verification_task);
             debug!("Verifier returned {:?}", verification_result);
+            report = verifier.take_report();
 
             verification_result
         };
 
+        if let Some(path) = config::report_path() {
+            report.items.extend(skipped_procedures.iter().map(|&procedure| {
+                ItemReport::new(&env, procedure, ItemResult::Skipped)
+            }));
+            if let Err(error) = report.write(&path) {
+                PrustiError::internal(
+                    format!("failed to write the verification report to {:?}: {}", path, error),
+                    MultiSpan::new(),
+                ).emit(&env);
+            }
+        }
+
         match verification_result {
             VerificationResult::Success => {
                 if env.has_errors() {
@@ -58,13 +98,17 @@ pub fn verify<'tcx>(
                                        were encountered during encoding.");
                 } else {
                     user::message(format!(
-                        "Successful verification of {} items",
-                        verification_task.procedures.len()
+                        "Successful verification of {} items ({} items skipped)",
+                        verification_task.procedures.len(),
+                        skipped_procedures.len()
                     ));
                 }
             }
             VerificationResult::Failure => {
-                user::message("Verification failed");
+                user::message(format!(
+                    "Verification failed ({} items skipped)",
+                    skipped_procedures.len()
+                ));
                 assert!(env.has_errors());
             }
         };
@@ -113,6 +113,9 @@ fn main() {
                 constructor!("()V"),
                 method!("updated", "(Ljava/lang/Object;Ljava/lang/Object;)Lscala/collection/immutable/HashMap;"),
             ]),
+            java_class!("scala.collection.immutable.Map", vec![
+                method!("get"),
+            ]),
             java_class!("scala.collection.immutable.Nil$", vec![
                 object_getter!(),
             ]),
@@ -137,6 +140,13 @@ fn main() {
             // Silicon
             java_class!("viper.silicon.Silicon", vec![
                 constructor!("(Lviper/silver/reporter/Reporter;Lscala/collection/immutable/Seq;)V"),
+                method!("verifier"),
+            ]),
+            java_class!("viper.silicon.verifier.Verifier", vec![
+                method!("decider"),
+            ]),
+            java_class!("viper.silicon.decider.Decider", vec![
+                method!("statistics"),
             ]),
             // Carbon
             java_class!("viper.carbon.CarbonVerifier", vec![
@@ -613,7 +623,8 @@ fn main() {
                 method!("fullId"),
                 method!("reason"),
                 method!("readableMessage", "()Ljava/lang/String;"),
-                method!("counterexample")
+                method!("counterexample"),
+                method!("failureContexts"),
             ]),
             java_class!("viper.silver.verifier.Counterexample", vec![
                 method!("model"),
@@ -622,6 +633,9 @@ fn main() {
             java_class!("viper.silicon.interfaces.SiliconMappedCounterexample", vec![
                 method!("converter")
             ]),
+            java_class!("viper.silicon.interfaces.SiliconFailureContext", vec![
+                method!("reasonUnknown")
+            ]),
             java_class!("viper.silver.verifier.ErrorReason", vec![
                 method!("id"),
                 method!("pos"),
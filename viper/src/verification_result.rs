@@ -6,6 +6,7 @@
 
 use JavaException;
 use silicon_counterexample::SiliconCounterexample;
+use std::time::Duration;
 
 /// The result of a verification request on a Viper program.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
@@ -16,6 +17,12 @@ pub struct ProgramVerificationResult {
     pub consistency_errors: Vec<ConsistencyError>,
     /// Java exceptions raised by the verifier.
     pub java_exceptions: Vec<JavaExceptionWithOrigin>,
+    /// The time spent verifying the programs.
+    pub verification_time: Duration,
+    /// The resources consumed by the SMT solver while verifying the programs,
+    /// in the unit of its resource limit, if the backend reports it.
+    #[serde(default)]
+    pub smt_rlimit_count: Option<u64>,
 }
 
 /// The result of a verification request on a Viper method.
@@ -44,6 +51,10 @@ pub struct VerificationError {
     pub reason_pos_id: Option<String>,
     pub message: String,
     pub counterexample: Option<SiliconCounterexample>,
+    /// The reason given by the SMT solver when it could neither prove nor
+    /// disprove the failing check, if the backend reports it.
+    #[serde(default)]
+    pub reason_unknown: Option<String>,
 }
 
 impl VerificationError {
@@ -53,6 +64,7 @@ impl VerificationError {
         reason_pos_id: Option<String>,
         message: String,
         counterexample: Option<SiliconCounterexample>,
+        reason_unknown: Option<String>,
     ) -> Self {
         VerificationError {
             full_id,
@@ -60,8 +72,17 @@ impl VerificationError {
             reason_pos_id,
             message,
             counterexample,
+            reason_unknown,
         }
     }
+
+    /// Whether the check failed because the SMT solver reached its resource
+    /// limit, rather than because it found a counterexample.
+    pub fn is_resource_out(&self) -> bool {
+        self.reason_unknown
+            .as_ref()
+            .map_or(false, |reason| reason.contains("resource limit"))
+    }
 }
 
 /// The consistency error reported by the verifier.
@@ -230,6 +230,24 @@ impl<'a> Verifier<'a, state::Started> {
                     None
                 };
                 
+                let failure_contexts = self.jni.seq_to_vec(self.jni.unwrap_result(
+                    verification_error_wrapper.call_failureContexts(viper_error),
+                ));
+                let reason_unknown = failure_contexts
+                    .into_iter()
+                    .filter(|context| self.jni.is_instance_of(
+                        *context,
+                        "viper/silicon/interfaces/SiliconFailureContext",
+                    ))
+                    .map(|context| self.jni.unwrap_result(
+                        silicon::interfaces::SiliconFailureContext::with(self.env)
+                            .call_reasonUnknown(context),
+                    ))
+                    .find(|option_reason| !self.jni.is_instance_of(*option_reason, "scala/None$"))
+                    .map(|option_reason| self.jni.to_string(self.jni.unwrap_result(
+                        scala::Some::with(self.env).call_get(option_reason),
+                    )));
+
                 let reason = self
                     .jni
                     .unwrap_result(verification_error_wrapper.call_reason(viper_error));
@@ -292,6 +310,7 @@ impl<'a> Verifier<'a, state::Started> {
                     reason_pos_id,
                     message,
                     counterexample,
+                    reason_unknown,
                 ))
             }
 
@@ -300,4 +319,33 @@ impl<'a> Verifier<'a, state::Started> {
             VerificationResult::Success
         }
     }
+
+    /// The resources consumed by the SMT solver since it was started, as
+    /// counted by its resource limit (the `rlimit-count` statistic of Z3).
+    /// Only Silicon reports it.
+    pub fn smt_rlimit_count(&self) -> Option<u64> {
+        if !self.jni.is_instance_of(self.verifier_instance, "viper/silicon/Silicon") {
+            return None;
+        }
+        let main_verifier = self.jni.unwrap_result(
+            silicon::Silicon::with(self.env).call_verifier(self.verifier_instance),
+        );
+        let decider = self.jni.unwrap_result(
+            silicon::verifier::Verifier::with(self.env).call_decider(main_verifier),
+        );
+        let statistics = self.jni.unwrap_result(
+            silicon::decider::Decider::with(self.env).call_statistics(decider),
+        );
+        let option_count = self.jni.unwrap_result(
+            scala::collection::immutable::Map::with(self.env)
+                .call_get(statistics, self.jni.new_string("rlimit-count")),
+        );
+        if self.jni.is_instance_of(option_count, "scala/None$") {
+            return None;
+        }
+        let count = self.jni.to_string(self.jni.unwrap_result(
+            scala::Some::with(self.env).call_get(option_count),
+        ));
+        count.parse().ok()
+    }
 }
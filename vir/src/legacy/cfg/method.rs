@@ -29,7 +29,7 @@ pub struct CfgMethod {
     #[serde(skip)]
     pub(crate) reserved_labels: HashSet<String>,
     pub basic_blocks: Vec<CfgBlock>, // FIXME: Hack, should be pub(super).
-    pub(crate) basic_blocks_labels: Vec<String>,
+    pub basic_blocks_labels: Vec<String>, // FIXME: Hack, should be pub(super).
     #[serde(skip)]
     pub(crate) fresh_var_index: i32,
     #[serde(skip)]